@@ -6,12 +6,15 @@
 //!
 //! - **Buffer Analysis**: Point, line, and polygon buffering with variable widths
 //! - **Overlay Operations**: Union, intersection, difference, and symmetric difference
+//! - **Spatial Join**: Attribute joins by location and layer overlay with attribute carry-over
 //! - **Proximity Analysis**: Nearest neighbor, distance matrices, Voronoi diagrams
 //! - **Network Analysis**: Shortest path, service areas, route optimization
+//! - **Linear Referencing**: Calibrated routes, event location, dynamic segmentation
 //! - **Surface Analysis**: Slope, aspect, hillshade, contour generation
 //! - **Spatial Statistics**: Hot spot detection, cluster analysis, autocorrelation
 //! - **Geometry Transformation**: Simplification, smoothing, densification
 //! - **Validation & Repair**: Geometry validation and automated repair
+//! - **Topology**: Coverage rules, coverage-preserving simplification, snapping
 //!
 //! ## Examples
 //!
@@ -62,11 +65,14 @@
 
 pub mod buffer;
 pub mod error;
+pub mod linear_referencing;
 pub mod network;
 pub mod overlay;
 pub mod proximity;
+pub mod spatial_join;
 pub mod statistics;
 pub mod surface;
+pub mod topology;
 pub mod transform;
 pub mod validation;
 
@@ -77,6 +83,14 @@ pub use error::{AnalysisError, Result};
 pub use buffer::{buffer_point, buffer_line, buffer_polygon, BufferParams, CapStyle, JoinStyle};
 pub use overlay::{union, intersection, difference, symmetric_difference, OverlayOp};
 pub use proximity::{nearest_neighbor, k_nearest_neighbors, voronoi_diagram, NearestNeighbor};
+pub use spatial_join::{
+    overlay_features, spatial_join, tabulate_intersection, JoinAggregate, JoinCardinality, JoinPredicate,
+    LayerOverlayOp, SpatialJoinOptions,
+};
+pub use linear_referencing::{
+    dynamic_segmentation, overlay_route_events, EventSchema, LocateStatus, Route, RouteLocation,
+    RouteOverlayOp, RouteSystem,
+};
 pub use network::{Network, NetworkNode, NetworkEdge, ShortestPath, shortest_path_dijkstra, shortest_path_astar};
 pub use surface::{Dem, slope, aspect, hillshade, contour};
 pub use statistics::{
@@ -87,6 +101,7 @@ pub use transform::{
     simplify_line_douglas_peucker, simplify_polygon_douglas_peucker, smooth_line_moving_average,
     smooth_line_chaikin, densify_line, densify_polygon, SimplificationAlgorithm, SmoothingAlgorithm,
 };
+pub use topology::{validate_topology, Coverage, RuleType, TopologyReport, TopologyRule, TopologyViolation};
 pub use validation::{
     validate_line, validate_polygon, repair_polygon, clean_polygon, ValidationResult,
     ValidationIssue, IssueType, Severity,
@@ -0,0 +1,1015 @@
+//! Linear referencing: calibrated routes, event location and dynamic segmentation
+//!
+//! Routes are lines with a measure at every vertex. Events are rows of a
+//! [`Layer`] that reference a route ID and a measure (point events) or a
+//! measure range (line events); their geometry is ignored on input.
+//! Offsets are perpendicular distances, positive to the left of the
+//! direction of increasing measures.
+
+use crate::error::{AnalysisError, Result};
+use geo::{Coord, LineString, Point};
+use meridian_core::crs::Crs;
+use meridian_core::feature::Feature;
+use meridian_core::geometry::{
+    Geometry, GeometryCollection, LineString as RouteLine, Point as EventPoint,
+};
+use meridian_core::layer::Layer;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Property added by [`dynamic_segmentation`] with the [`LocateStatus`] of each event
+pub const LOC_ERROR_FIELD: &str = "loc_error";
+
+/// A line with a measure at every vertex
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    pub id: String,
+    pub line: LineString,
+    pub measures: Vec<f64>,
+}
+
+/// Position of a point relative to a route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteLocation {
+    pub route_id: String,
+    pub measure: f64,
+    /// Signed perpendicular distance, positive to the left of the route
+    pub offset: f64,
+    /// Closest point on the route
+    pub point: Point,
+}
+
+impl Route {
+    /// Create a route from a line and one measure per vertex
+    ///
+    /// Measures must be finite and non-decreasing along the line.
+    pub fn new(id: impl Into<String>, line: LineString, measures: Vec<f64>) -> Result<Self> {
+        if line.0.len() < 2 {
+            return Err(AnalysisError::invalid_geometry(
+                "A route needs at least two vertices",
+            ));
+        }
+        if measures.len() != line.0.len() {
+            return Err(AnalysisError::invalid_parameters(format!(
+                "Route has {} vertices but {} measures",
+                line.0.len(),
+                measures.len()
+            )));
+        }
+        if measures.iter().any(|m| !m.is_finite()) || measures.windows(2).any(|w| w[1] < w[0]) {
+            return Err(AnalysisError::invalid_parameters(
+                "Route measures must be finite and non-decreasing",
+            ));
+        }
+
+        Ok(Self {
+            id: id.into(),
+            line,
+            measures,
+        })
+    }
+
+    /// Create a route measured by length along the line from `start_measure`
+    pub fn from_length(id: impl Into<String>, line: LineString, start_measure: f64) -> Result<Self> {
+        let measures = cumulative_lengths(&line)
+            .into_iter()
+            .map(|d| start_measure + d)
+            .collect();
+        Self::new(id, line, measures)
+    }
+
+    /// Create a route with measures proportional to length between two end measures
+    pub fn calibrate(
+        id: impl Into<String>,
+        line: LineString,
+        from_measure: f64,
+        to_measure: f64,
+    ) -> Result<Self> {
+        if to_measure < from_measure {
+            return Err(AnalysisError::invalid_parameters(
+                "To-measure must not be less than from-measure",
+            ));
+        }
+        let lengths = cumulative_lengths(&line);
+        let total = lengths.last().copied().unwrap_or(0.0);
+        if total <= 0.0 {
+            return Err(AnalysisError::invalid_geometry("Route line has zero length"));
+        }
+        let measures = lengths
+            .into_iter()
+            .map(|d| from_measure + (to_measure - from_measure) * d / total)
+            .collect();
+        Self::new(id, line, measures)
+    }
+
+    /// Create a route from a line feature
+    ///
+    /// The route ID is read from `id_field`. M values of the line are used
+    /// as measures; lines without them are measured by length from zero.
+    /// The parts of a multi line string are joined when each starts where
+    /// the previous one ends.
+    pub fn from_feature(feature: &Feature, id_field: &str) -> Result<Self> {
+        let id = feature
+            .get_property(id_field)
+            .and_then(property_key)
+            .ok_or_else(|| {
+                AnalysisError::invalid_parameters(format!("Feature has no route ID in '{}'", id_field))
+            })?;
+        match &feature.geometry {
+            Geometry::LineString(line) => match &line.m {
+                Some(measures) => Self::new(id, line.geom.clone(), measures.clone()),
+                None => Self::from_length(id, line.geom.clone(), 0.0),
+            },
+            Geometry::MultiLineString(lines) => {
+                let mut coords: Vec<Coord> = Vec::new();
+                let mut measures = Vec::new();
+                let mut start = 0;
+                for part in &lines.geom.0 {
+                    let part_measures = lines.m.as_ref().and_then(|m| m.get(start..start + part.0.len()));
+                    start += part.0.len();
+                    let skip = match (coords.last(), part.0.first()) {
+                        (Some(last), Some(first)) if last == first => 1,
+                        (Some(_), Some(_)) => {
+                            return Err(AnalysisError::invalid_geometry(format!(
+                                "Route '{}' is a MultiLineString with disconnected parts",
+                                id
+                            )))
+                        }
+                        _ => 0,
+                    };
+                    coords.extend(part.0.iter().skip(skip));
+                    measures.extend(part_measures.into_iter().flatten().skip(skip).copied());
+                }
+                match lines.m {
+                    Some(_) => Self::new(id, LineString(coords), measures),
+                    None => Self::from_length(id, LineString(coords), 0.0),
+                }
+            }
+            other => Err(AnalysisError::invalid_geometry(format!(
+                "Route '{}' is a {}, not a line string",
+                id,
+                geometry_type(other)
+            ))),
+        }
+    }
+
+    /// Convert the route to a feature with measures as M values
+    pub fn to_feature(&self, id_field: &str, crs: Crs) -> Feature {
+        let line = RouteLine::new(self.line.0.clone(), crs).with_m(self.measures.clone());
+        let mut feature = Feature::new(Geometry::LineString(line));
+        feature.set_property(id_field, Value::String(self.id.clone()));
+        feature
+    }
+
+    /// Measure at the start of the route
+    pub fn start_measure(&self) -> f64 {
+        self.measures[0]
+    }
+
+    /// Measure at the end of the route
+    pub fn end_measure(&self) -> f64 {
+        self.measures[self.measures.len() - 1]
+    }
+
+    /// Check if a measure lies on the route
+    pub fn contains_measure(&self, measure: f64) -> bool {
+        measure >= self.start_measure() && measure <= self.end_measure()
+    }
+
+    /// Segment index and fraction along it for a measure on the route
+    fn position(&self, measure: f64) -> (usize, f64) {
+        let last = self.measures.len() - 2;
+        let i = self
+            .measures
+            .windows(2)
+            .position(|w| measure <= w[1])
+            .unwrap_or(last);
+        let (m0, m1) = (self.measures[i], self.measures[i + 1]);
+        let t = if m1 > m0 { ((measure - m0) / (m1 - m0)).clamp(0.0, 1.0) } else { 0.0 };
+        (i, t)
+    }
+
+    /// Locate a point at a measure, shifted by a perpendicular offset
+    pub fn point_at(&self, measure: f64, offset: f64) -> Result<Point> {
+        if !self.contains_measure(measure) {
+            return Err(measure_error(&self.id, measure));
+        }
+        let (i, t) = self.position(measure);
+        let (a, b) = (self.line.0[i], self.line.0[i + 1]);
+        let coord = Coord {
+            x: a.x + (b.x - a.x) * t,
+            y: a.y + (b.y - a.y) * t,
+        };
+        let normal = left_normal(a, b);
+        Ok(Point::new(coord.x + normal.x * offset, coord.y + normal.y * offset))
+    }
+
+    /// Extract the part of the route between two measures, shifted by an offset
+    ///
+    /// The line runs from `from_measure` to `to_measure`, so it is reversed
+    /// when `to_measure` is the smaller one.
+    pub fn segment(&self, from_measure: f64, to_measure: f64, offset: f64) -> Result<LineString> {
+        let (low, high) = (from_measure.min(to_measure), from_measure.max(to_measure));
+        for measure in [low, high] {
+            if !self.contains_measure(measure) {
+                return Err(measure_error(&self.id, measure));
+            }
+        }
+
+        let (i, t) = self.position(low);
+        let (j, u) = self.position(high);
+        let lerp = |k: usize, t: f64| {
+            let (a, b) = (self.line.0[k], self.line.0[k + 1]);
+            Coord {
+                x: a.x + (b.x - a.x) * t,
+                y: a.y + (b.y - a.y) * t,
+            }
+        };
+
+        let mut coords = vec![lerp(i, t)];
+        coords.extend_from_slice(&self.line.0[i + 1..=j]);
+        coords.push(lerp(j, u));
+        coords.dedup();
+        if coords.len() == 1 {
+            coords.push(coords[0]);
+        }
+
+        let mut coords = offset_coords(&coords, offset, left_normal(self.line.0[i], self.line.0[i + 1]));
+        if to_measure < from_measure {
+            coords.reverse();
+        }
+        Ok(LineString::from(coords))
+    }
+
+    /// Find the closest location on the route to a point
+    pub fn locate(&self, point: &Point) -> RouteLocation {
+        let p = point.0;
+        let mut best = (f64::INFINITY, 0, 0.0, 0.0);
+        for (i, w) in self.line.0.windows(2).enumerate() {
+            let (a, b) = (w[0], w[1]);
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let len2 = dx * dx + dy * dy;
+            let t = if len2 > 0.0 {
+                (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (cx, cy) = (a.x + dx * t, a.y + dy * t);
+            let distance = (p.x - cx).hypot(p.y - cy);
+            if distance < best.0 {
+                let side = dx * (p.y - a.y) - dy * (p.x - a.x);
+                best = (distance, i, t, if side < 0.0 { -distance } else { distance });
+            }
+        }
+
+        let (_, i, t, offset) = best;
+        let (a, b) = (self.line.0[i], self.line.0[i + 1]);
+        RouteLocation {
+            route_id: self.id.clone(),
+            measure: self.measures[i] + (self.measures[i + 1] - self.measures[i]) * t,
+            offset,
+            point: Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t),
+        }
+    }
+}
+
+/// Routes by ID, in one coordinate reference system
+#[derive(Debug, Clone)]
+pub struct RouteSystem {
+    routes: BTreeMap<String, Route>,
+    crs: Crs,
+}
+
+impl RouteSystem {
+    /// Create an empty route system
+    pub fn new(crs: Crs) -> Self {
+        Self {
+            routes: BTreeMap::new(),
+            crs,
+        }
+    }
+
+    /// Build routes from the line features of a layer
+    ///
+    /// See [`Route::from_feature`]. Route IDs must be unique.
+    pub fn from_layer(layer: &Layer, id_field: &str) -> Result<Self> {
+        let mut system = Self::new(layer.crs.clone());
+        for feature in layer.iter() {
+            system.add_route(Route::from_feature(feature, id_field)?)?;
+        }
+        Ok(system)
+    }
+
+    /// Add a route
+    pub fn add_route(&mut self, route: Route) -> Result<()> {
+        if self.routes.contains_key(&route.id) {
+            return Err(AnalysisError::invalid_parameters(format!(
+                "Duplicate route ID '{}'",
+                route.id
+            )));
+        }
+        self.routes.insert(route.id.clone(), route);
+        Ok(())
+    }
+
+    /// Get a route by ID
+    pub fn get(&self, id: &str) -> Option<&Route> {
+        self.routes.get(id)
+    }
+
+    /// Coordinate reference system of the routes
+    pub fn crs(&self) -> &Crs {
+        &self.crs
+    }
+
+    /// Number of routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Check if there are no routes
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Iterate over the routes in ID order
+    pub fn iter(&self) -> impl Iterator<Item = &Route> {
+        self.routes.values()
+    }
+
+    /// Convert the routes to a layer of measured line features
+    pub fn to_layer(&self, name: &str, id_field: &str) -> Layer {
+        Layer::from_features(
+            name,
+            self.crs.clone(),
+            self.iter().map(|r| r.to_feature(id_field, self.crs.clone())).collect(),
+        )
+    }
+
+    /// Locate a point on the closest route within a search tolerance
+    pub fn locate_point(&self, point: &Point, tolerance: f64) -> Option<RouteLocation> {
+        self.iter()
+            .map(|route| route.locate(point))
+            .filter(|location| location.offset.abs() <= tolerance)
+            .min_by(|a, b| a.offset.abs().total_cmp(&b.offset.abs()))
+    }
+
+    /// Locate point features along routes, producing a point event table
+    ///
+    /// Each located feature keeps its geometry and properties and gets the
+    /// route ID, measure and (if the schema has one) offset fields. Features
+    /// that are not points or not within `tolerance` of a route are skipped.
+    pub fn locate_features(&self, points: &Layer, tolerance: f64, schema: &EventSchema) -> Layer {
+        let mut events = Layer::new(format!("{}_events", points.name), points.crs.clone());
+        for feature in points.iter() {
+            let Geometry::Point(point) = &feature.geometry else {
+                continue;
+            };
+            let Some(location) = self.locate_point(&point.geom, tolerance) else {
+                continue;
+            };
+
+            let mut event = feature.clone();
+            event.set_property(schema.route_id_field.clone(), Value::String(location.route_id));
+            event.set_property(schema.measure_field.clone(), number(location.measure));
+            if let Some(field) = &schema.offset_field {
+                event.set_property(field.clone(), number(location.offset));
+            }
+            events.add_feature(event);
+        }
+        events
+    }
+}
+
+/// Property names of an event table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSchema {
+    pub route_id_field: String,
+    /// Measure of point events, from-measure of line events
+    pub measure_field: String,
+    /// To-measure of line events; `None` for point events
+    pub to_measure_field: Option<String>,
+    pub offset_field: Option<String>,
+}
+
+impl EventSchema {
+    /// Schema of a point event table
+    pub fn point(route_id_field: impl Into<String>, measure_field: impl Into<String>) -> Self {
+        Self {
+            route_id_field: route_id_field.into(),
+            measure_field: measure_field.into(),
+            to_measure_field: None,
+            offset_field: None,
+        }
+    }
+
+    /// Schema of a line event table
+    pub fn line(
+        route_id_field: impl Into<String>,
+        from_measure_field: impl Into<String>,
+        to_measure_field: impl Into<String>,
+    ) -> Self {
+        Self {
+            route_id_field: route_id_field.into(),
+            measure_field: from_measure_field.into(),
+            to_measure_field: Some(to_measure_field.into()),
+            offset_field: None,
+        }
+    }
+
+    /// Set the offset field
+    pub fn with_offset(mut self, offset_field: impl Into<String>) -> Self {
+        self.offset_field = Some(offset_field.into());
+        self
+    }
+
+    /// Check if the schema describes line events
+    pub fn is_line(&self) -> bool {
+        self.to_measure_field.is_some()
+    }
+
+    fn is_event_field(&self, key: &str) -> bool {
+        key == self.route_id_field
+            || key == self.measure_field
+            || self.to_measure_field.as_deref() == Some(key)
+            || self.offset_field.as_deref() == Some(key)
+    }
+
+    /// Read the route ID and measure range of an event
+    fn read(&self, feature: &Feature) -> Option<(String, f64, f64)> {
+        let route_id = feature.get_property(&self.route_id_field).and_then(property_key)?;
+        let from = feature.get_property(&self.measure_field).and_then(Value::as_f64)?;
+        let to = match &self.to_measure_field {
+            Some(field) => feature.get_property(field).and_then(Value::as_f64)?,
+            None => from,
+        };
+        Some((route_id, from, to))
+    }
+
+    fn offset(&self, feature: &Feature) -> f64 {
+        self.offset_field
+            .as_ref()
+            .and_then(|field| feature.get_property(field))
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Result of locating an event on its route
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LocateStatus {
+    /// The event lies entirely on the route
+    Located,
+    /// Part of a line event lies beyond the route and was clipped
+    PartialMatch,
+    /// The event's measures lie beyond the route
+    MeasureOutOfRange,
+    /// No route has the event's route ID
+    RouteNotFound,
+    /// The event has no route ID or measures
+    MissingValues,
+}
+
+impl LocateStatus {
+    /// Status name as written to [`LOC_ERROR_FIELD`]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocateStatus::Located => "NO ERROR",
+            LocateStatus::PartialMatch => "PARTIAL MATCH",
+            LocateStatus::MeasureOutOfRange => "MEASURE OUT OF RANGE",
+            LocateStatus::RouteNotFound => "ROUTE NOT FOUND",
+            LocateStatus::MissingValues => "MISSING VALUES",
+        }
+    }
+}
+
+/// Generate geometries for an event table (dynamic segmentation)
+///
+/// Point events become points and line events become lines along their
+/// routes, shifted by the event offset. Every output feature keeps the
+/// event's properties and gets a [`LOC_ERROR_FIELD`] status; events that
+/// cannot be located get an empty geometry collection.
+pub fn dynamic_segmentation(routes: &RouteSystem, events: &Layer, schema: &EventSchema) -> Result<Layer> {
+    let crs = routes.crs().clone();
+    let mut output = Layer::with_capacity(events.name.clone(), crs.clone(), events.len());
+
+    for event in events.iter() {
+        let (geometry, status) = match locate_event(routes, schema, event)? {
+            Ok((geometry, status)) => (geometry, status),
+            Err(status) => (empty_geometry(&crs), status),
+        };
+        let mut feature = event.with_geometry(geometry);
+        feature.set_property(LOC_ERROR_FIELD, Value::String(status.as_str().to_string()));
+        output.add_feature(feature);
+    }
+
+    Ok(output)
+}
+
+fn locate_event(
+    routes: &RouteSystem,
+    schema: &EventSchema,
+    event: &Feature,
+) -> Result<std::result::Result<(Geometry, LocateStatus), LocateStatus>> {
+    let Some((route_id, from, to)) = schema.read(event) else {
+        return Ok(Err(LocateStatus::MissingValues));
+    };
+    let Some(route) = routes.get(&route_id) else {
+        return Ok(Err(LocateStatus::RouteNotFound));
+    };
+    let offset = schema.offset(event);
+    let crs = routes.crs().clone();
+
+    if !schema.is_line() {
+        if !route.contains_measure(from) {
+            return Ok(Err(LocateStatus::MeasureOutOfRange));
+        }
+        let point = route.point_at(from, offset)?;
+        return Ok(Ok((
+            Geometry::Point(EventPoint::new(point.x(), point.y(), crs)),
+            LocateStatus::Located,
+        )));
+    }
+
+    let (start, end) = (route.start_measure(), route.end_measure());
+    let clipped_from = from.clamp(start, end);
+    let clipped_to = to.clamp(start, end);
+    if from.max(to) < start || from.min(to) > end {
+        return Ok(Err(LocateStatus::MeasureOutOfRange));
+    }
+    let status = if clipped_from == from && clipped_to == to {
+        LocateStatus::Located
+    } else {
+        LocateStatus::PartialMatch
+    };
+    let line = route.segment(clipped_from, clipped_to, offset)?;
+    Ok(Ok((Geometry::LineString(RouteLine::new(line.0, crs)), status)))
+}
+
+/// Route event overlay operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouteOverlayOp {
+    /// Keep only the route portions covered by events of both tables
+    Intersect,
+    /// Keep every route portion covered by an event of either table
+    Union,
+}
+
+/// Overlay two event tables on the same routes
+///
+/// Produces an event table in the first table's schema. Intersect accepts
+/// point or line events as the first input and line events as the second;
+/// union needs line events on both sides. Output events carry the
+/// properties of both inputs, with colliding names from the second table
+/// suffixed by `_1`, and have empty geometries: use [`dynamic_segmentation`]
+/// to locate them.
+pub fn overlay_route_events(
+    a: &Layer,
+    a_schema: &EventSchema,
+    b: &Layer,
+    b_schema: &EventSchema,
+    op: RouteOverlayOp,
+) -> Result<Layer> {
+    if !b_schema.is_line() || (op == RouteOverlayOp::Union && !a_schema.is_line()) {
+        return Err(AnalysisError::invalid_parameters(
+            "Route overlay needs line events, except for the first table of an intersect",
+        ));
+    }
+
+    let a_events = group_events(a, a_schema);
+    let b_events = group_events(b, b_schema);
+    let mut output = Layer::new(format!("{}_{}", a.name, b.name), a.crs.clone());
+    let mut emit = |a_feature: Option<&Feature>, b_feature: Option<&Feature>, route_id: &str, from: f64, to: f64| {
+        let mut properties = a_feature.map(|f| f.properties.clone()).unwrap_or_default();
+        if let Some(b_feature) = b_feature {
+            merge_properties(&mut properties, &b_feature.properties, b_schema);
+        }
+        properties.insert(a_schema.route_id_field.clone(), Value::String(route_id.to_string()));
+        properties.insert(a_schema.measure_field.clone(), number(from));
+        if let Some(field) = &a_schema.to_measure_field {
+            properties.insert(field.clone(), number(to));
+        }
+        output.add_feature(Feature::with_properties(empty_geometry(&a.crs), properties));
+    };
+
+    match op {
+        RouteOverlayOp::Intersect => {
+            for (route_id, a_list) in &a_events {
+                let Some(b_list) = b_events.get(route_id) else {
+                    continue;
+                };
+                for (a_feature, a_from, a_to) in a_list {
+                    for (b_feature, b_from, b_to) in b_list {
+                        let (from, to) = (a_from.max(*b_from), a_to.min(*b_to));
+                        let overlaps = if a_schema.is_line() { from < to } else { from <= to };
+                        if overlaps {
+                            emit(Some(*a_feature), Some(*b_feature), route_id, from, to);
+                        }
+                    }
+                }
+            }
+        }
+        RouteOverlayOp::Union => {
+            let mut route_ids: Vec<&String> = a_events.keys().chain(b_events.keys()).collect();
+            route_ids.sort();
+            route_ids.dedup();
+            let none = Vec::new();
+
+            for route_id in route_ids {
+                let a_list = a_events.get(route_id).unwrap_or(&none);
+                let b_list = b_events.get(route_id).unwrap_or(&none);
+                for (a_index, b_index, from, to) in union_pieces(a_list, b_list) {
+                    emit(
+                        a_index.map(|i| a_list[i].0),
+                        b_index.map(|i| b_list[i].0),
+                        route_id,
+                        from,
+                        to,
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Located events of a table, grouped by route, with ordered measure ranges
+type EventGroups<'a> = BTreeMap<String, Vec<(&'a Feature, f64, f64)>>;
+
+fn group_events<'a>(layer: &'a Layer, schema: &EventSchema) -> EventGroups<'a> {
+    let mut groups = EventGroups::new();
+    for feature in layer.iter() {
+        if let Some((route_id, from, to)) = schema.read(feature) {
+            groups
+                .entry(route_id)
+                .or_default()
+                .push((feature, from.min(to), from.max(to)));
+        }
+    }
+    groups
+}
+
+/// Split the union of two sets of measure ranges at every breakpoint
+///
+/// Returns the covering event of each table for every piece, with adjacent
+/// pieces covered by the same pair of events merged.
+fn union_pieces(
+    a: &[(&Feature, f64, f64)],
+    b: &[(&Feature, f64, f64)],
+) -> Vec<(Option<usize>, Option<usize>, f64, f64)> {
+    let mut breaks: Vec<f64> = a.iter().chain(b).flat_map(|(_, from, to)| [*from, *to]).collect();
+    breaks.sort_by(f64::total_cmp);
+    breaks.dedup();
+
+    let covering = |events: &[(&Feature, f64, f64)], from: f64, to: f64| -> Vec<Option<usize>> {
+        let hits: Vec<_> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, f, t))| *f <= from && *t >= to)
+            .map(|(i, _)| Some(i))
+            .collect();
+        if hits.is_empty() {
+            vec![None]
+        } else {
+            hits
+        }
+    };
+
+    let mut open: HashMap<(Option<usize>, Option<usize>), (f64, f64)> = HashMap::new();
+    let mut pieces = Vec::new();
+    for w in breaks.windows(2) {
+        let (from, to) = (w[0], w[1]);
+        let mut current = HashMap::new();
+        for a_index in covering(a, from, to) {
+            for b_index in covering(b, from, to) {
+                if a_index.is_some() || b_index.is_some() {
+                    let start = open.get(&(a_index, b_index)).map_or(from, |(start, _)| *start);
+                    current.insert((a_index, b_index), (start, to));
+                }
+            }
+        }
+        for (key, (start, end)) in open.drain() {
+            if !current.contains_key(&key) {
+                pieces.push((key.0, key.1, start, end));
+            }
+        }
+        open = current;
+    }
+    pieces.extend(open.into_iter().map(|(key, (start, end))| (key.0, key.1, start, end)));
+    pieces.sort_by(|x, y| x.2.total_cmp(&y.2).then(x.0.cmp(&y.0)).then(x.1.cmp(&y.1)));
+    pieces
+}
+
+fn merge_properties(target: &mut Map<String, Value>, source: &Map<String, Value>, schema: &EventSchema) {
+    for (key, value) in source {
+        if schema.is_event_field(key) {
+            continue;
+        }
+        let key = if target.contains_key(key) {
+            format!("{}_1", key)
+        } else {
+            key.clone()
+        };
+        target.insert(key, value.clone());
+    }
+}
+
+fn cumulative_lengths(line: &LineString) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut lengths = Vec::with_capacity(line.0.len());
+    for (i, c) in line.0.iter().enumerate() {
+        if i > 0 {
+            let p = line.0[i - 1];
+            total += (c.x - p.x).hypot(c.y - p.y);
+        }
+        lengths.push(total);
+    }
+    lengths
+}
+
+/// Unit normal to the left of the direction from `a` to `b`
+fn left_normal(a: Coord, b: Coord) -> Coord {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = dx.hypot(dy);
+    if len > 0.0 {
+        Coord { x: -dy / len, y: dx / len }
+    } else {
+        Coord { x: 0.0, y: 0.0 }
+    }
+}
+
+/// Offset a polyline, mitering the joins
+fn offset_coords(coords: &[Coord], offset: f64, fallback: Coord) -> Vec<Coord> {
+    if offset == 0.0 {
+        return coords.to_vec();
+    }
+    let normals: Vec<Coord> = coords
+        .windows(2)
+        .map(|w| {
+            let n = left_normal(w[0], w[1]);
+            if n.x == 0.0 && n.y == 0.0 {
+                fallback
+            } else {
+                n
+            }
+        })
+        .collect();
+
+    coords
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let before = normals[i.saturating_sub(1).min(normals.len() - 1)];
+            let after = normals[i.min(normals.len() - 1)];
+            let (mx, my) = (before.x + after.x, before.y + after.y);
+            let len = mx.hypot(my);
+            // Limit the miter length at sharp turns to twice the offset
+            let scale = if len > 0.0 {
+                let cos_half = len / 2.0;
+                offset / cos_half.max(0.5) / len
+            } else {
+                0.0
+            };
+            Coord {
+                x: c.x + mx * scale,
+                y: c.y + my * scale,
+            }
+        })
+        .collect()
+}
+
+/// Name of the type of a geometry, for error messages
+fn geometry_type(geometry: &Geometry) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "Point",
+        Geometry::MultiPoint(_) => "MultiPoint",
+        Geometry::LineString(_) => "LineString",
+        Geometry::MultiLineString(_) => "MultiLineString",
+        Geometry::Polygon(_) => "Polygon",
+        Geometry::MultiPolygon(_) => "MultiPolygon",
+        Geometry::GeometryCollection(_) => "GeometryCollection",
+        Geometry::CircularString(_) => "CircularString",
+        Geometry::CompoundCurve(_) => "CompoundCurve",
+    }
+}
+
+fn empty_geometry(crs: &Crs) -> Geometry {
+    Geometry::GeometryCollection(GeometryCollection::new(vec![], crs.clone()))
+}
+
+fn number(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+/// Route IDs may be stored as strings or numbers
+fn property_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn measure_error(route_id: &str, measure: f64) -> AnalysisError {
+    AnalysisError::invalid_parameters(format!(
+        "Measure {} is not on route '{}'",
+        measure, route_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use meridian_core::geometry::MultiLineString;
+    use serde_json::json;
+
+    fn l_shaped_route() -> Route {
+        // 10 units east, then 10 units north
+        let line = LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        Route::calibrate("R1", line, 100.0, 120.0).unwrap()
+    }
+
+    fn event(properties: Value) -> Feature {
+        let Value::Object(properties) = properties else {
+            unreachable!()
+        };
+        Feature::with_properties(empty_geometry(&Crs::wgs84()), properties)
+    }
+
+    fn routes() -> RouteSystem {
+        let mut routes = RouteSystem::new(Crs::wgs84());
+        routes.add_route(l_shaped_route()).unwrap();
+        routes
+    }
+
+    #[test]
+    fn test_route_calibration() {
+        let route = l_shaped_route();
+        assert_eq!(route.measures, vec![100.0, 110.0, 120.0]);
+
+        let point = route.point_at(105.0, 2.0).unwrap();
+        assert_relative_eq!(point.x(), 5.0);
+        assert_relative_eq!(point.y(), 2.0);
+        assert!(route.point_at(121.0, 0.0).is_err());
+
+        let unordered = LineString::from(vec![(0.0, 0.0), (1.0, 0.0)]);
+        assert!(Route::new("bad", unordered, vec![5.0, 1.0]).is_err());
+
+        // Measures are read from M values of route features
+        let feature = route.to_feature("route", Crs::wgs84());
+        assert_eq!(Route::from_feature(&feature, "route").unwrap(), route);
+
+        // Contiguous parts of a multi line string are joined
+        let parts = vec![
+            LineString::from(vec![(0.0, 0.0), (10.0, 0.0)]),
+            LineString::from(vec![(10.0, 0.0), (10.0, 10.0)]),
+        ];
+        let multi = MultiLineString::new(parts.clone(), Crs::wgs84()).with_m(vec![100.0, 110.0, 110.0, 120.0]);
+        let mut feature = Feature::new(Geometry::MultiLineString(multi));
+        feature.set_property("route", json!("R1"));
+        assert_eq!(Route::from_feature(&feature, "route").unwrap(), route);
+
+        let disconnected = vec![parts[1].clone(), parts[0].clone()];
+        feature.geometry = Geometry::MultiLineString(MultiLineString::new(disconnected, Crs::wgs84()));
+        assert!(Route::from_feature(&feature, "route").is_err());
+        feature.geometry = Geometry::Point(EventPoint::new(0.0, 0.0, Crs::wgs84()));
+        let error = Route::from_feature(&feature, "route").unwrap_err();
+        assert!(error.to_string().contains("Point"), "{}", error);
+    }
+
+    #[test]
+    fn test_locate_point_along_route() {
+        let route = l_shaped_route();
+        let location = route.locate(&Point::new(7.0, 5.0));
+        assert_relative_eq!(location.measure, 115.0);
+        // West of a north-bound segment is on its left
+        assert_relative_eq!(location.offset, 3.0);
+        assert_relative_eq!(location.point.x(), 10.0);
+
+        let right = route.locate(&Point::new(4.0, -1.5));
+        assert_relative_eq!(right.measure, 104.0);
+        assert_relative_eq!(right.offset, -1.5);
+
+        let routes = routes();
+        assert!(routes.locate_point(&Point::new(7.0, 5.0), 1.0).is_none());
+        assert_eq!(routes.locate_point(&Point::new(7.0, 5.0), 5.0).unwrap().route_id, "R1");
+    }
+
+    #[test]
+    fn test_route_segment() {
+        let route = l_shaped_route();
+        let segment = route.segment(105.0, 115.0, 0.0).unwrap();
+        assert_eq!(
+            segment,
+            LineString::from(vec![(5.0, 0.0), (10.0, 0.0), (10.0, 5.0)])
+        );
+
+        // Offset to the left mitres the corner
+        let offset = route.segment(105.0, 115.0, 1.0).unwrap();
+        assert_relative_eq!(offset.0[1].x, 9.0);
+        assert_relative_eq!(offset.0[1].y, 1.0);
+
+        let reversed = route.segment(115.0, 105.0, 0.0).unwrap();
+        assert_eq!(reversed.0[0], Coord { x: 10.0, y: 5.0 });
+    }
+
+    #[test]
+    fn test_dynamic_segmentation() {
+        let events = Layer::from_features(
+            "events",
+            Crs::wgs84(),
+            vec![
+                event(json!({"route": "R1", "from": 102.0, "to": 108.0, "kind": "pothole"})),
+                event(json!({"route": "R1", "from": 115.0, "to": 130.0})),
+                event(json!({"route": "R2", "from": 0.0, "to": 1.0})),
+                event(json!({"route": "R1", "from": 200.0, "to": 300.0})),
+            ],
+        );
+        let schema = EventSchema::line("route", "from", "to");
+        let output = dynamic_segmentation(&routes(), &events, &schema).unwrap();
+
+        let status: Vec<_> = output
+            .iter()
+            .map(|f| f.get_property(LOC_ERROR_FIELD).unwrap().as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            status,
+            vec!["NO ERROR", "PARTIAL MATCH", "ROUTE NOT FOUND", "MEASURE OUT OF RANGE"]
+        );
+
+        let first = output.get(0).unwrap();
+        assert_eq!(first.get_property("kind"), Some(&json!("pothole")));
+        let Geometry::LineString(line) = &first.geometry else {
+            panic!("expected a line");
+        };
+        assert_eq!(line.geom, LineString::from(vec![(2.0, 0.0), (8.0, 0.0)]));
+
+        let points = Layer::from_features(
+            "signs",
+            Crs::wgs84(),
+            vec![event(json!({"route": "R1", "at": 112.0, "side": -1.0}))],
+        );
+        let schema = EventSchema::point("route", "at").with_offset("side");
+        let output = dynamic_segmentation(&routes(), &points, &schema).unwrap();
+        let Geometry::Point(point) = &output.get(0).unwrap().geometry else {
+            panic!("expected a point");
+        };
+        assert_eq!((point.x(), point.y()), (11.0, 2.0));
+    }
+
+    #[test]
+    fn test_route_overlay() {
+        let schema = EventSchema::line("route", "from", "to");
+        let surface = Layer::from_features(
+            "surface",
+            Crs::wgs84(),
+            vec![
+                event(json!({"route": "R1", "from": 100.0, "to": 110.0, "surface": "asphalt"})),
+                event(json!({"route": "R1", "from": 110.0, "to": 120.0, "surface": "gravel"})),
+            ],
+        );
+        let speed = Layer::from_features(
+            "speed",
+            Crs::wgs84(),
+            vec![event(json!({"route": "R1", "from": 105.0, "to": 125.0, "limit": 50}))],
+        );
+
+        let intersect =
+            overlay_route_events(&surface, &schema, &speed, &schema, RouteOverlayOp::Intersect).unwrap();
+        let ranges: Vec<_> = intersect
+            .iter()
+            .map(|f| (f.get_property("from").unwrap().clone(), f.get_property("to").unwrap().clone()))
+            .collect();
+        assert_eq!(ranges, vec![(json!(105.0), json!(110.0)), (json!(110.0), json!(120.0))]);
+        assert_eq!(intersect.get(1).unwrap().get_property("surface"), Some(&json!("gravel")));
+        assert_eq!(intersect.get(1).unwrap().get_property("limit"), Some(&json!(50)));
+
+        let union = overlay_route_events(&surface, &schema, &speed, &schema, RouteOverlayOp::Union).unwrap();
+        let pieces: Vec<_> = union
+            .iter()
+            .map(|f| {
+                (
+                    f.get_property("from").unwrap().as_f64().unwrap(),
+                    f.get_property("to").unwrap().as_f64().unwrap(),
+                    f.has_property("surface"),
+                    f.has_property("limit"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (100.0, 105.0, true, false),
+                (105.0, 110.0, true, true),
+                (110.0, 120.0, true, true),
+                (120.0, 125.0, false, true),
+            ]
+        );
+
+        let points = EventSchema::point("route", "from");
+        assert!(overlay_route_events(&surface, &schema, &speed, &points, RouteOverlayOp::Intersect).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 //! Overlay operations for geometric intersection, union, difference
+//!
+//! These functions work on single geometries; see [`crate::spatial_join`] for
+//! overlays of whole layers that keep feature attributes.
 
 use crate::error::Result;
 use geo::{Area, BooleanOps, Contains, Intersects, MultiPolygon, Polygon};
@@ -0,0 +1,998 @@
+//! Spatial join and layer overlay
+//!
+//! Layer-level tools that carry feature attributes through spatial
+//! relationships. Candidates are prefiltered with a [`SpatialIndex`] on the
+//! bounding boxes of the join layer and target features are processed in
+//! parallel.
+
+use crate::error::{AnalysisError, Result};
+use geo::{
+    Area, BooleanOps, Coord, EuclideanDistance, EuclideanLength, Geometry, Intersects, LineString,
+    MultiLineString, MultiPoint, MultiPolygon, Point, Relate,
+};
+use meridian_core::bbox::BoundingBox;
+use meridian_core::crs::Crs;
+use meridian_core::feature::Feature;
+use meridian_core::geometry::{
+    Geometry as CoreGeometry, GeometryCollection as CoreGeometryCollection,
+    LineString as CoreLineString, MultiLineString as CoreMultiLineString,
+    MultiPoint as CoreMultiPoint, MultiPolygon as CoreMultiPolygon, Point as CorePoint,
+    Polygon as CorePolygon, DEFAULT_SEGMENTS_PER_QUADRANT,
+};
+use meridian_core::layer::Layer;
+use meridian_core::spatial_index::SpatialIndex;
+use meridian_core::traits::Bounded;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Property holding the number of matches of a one-to-one join
+pub const JOIN_COUNT_FIELD: &str = "join_count";
+
+/// Property holding the distance to the matched feature of a nearest join
+pub const JOIN_DISTANCE_FIELD: &str = "join_distance";
+
+/// Spatial relationship between a target and a join feature
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum JoinPredicate {
+    /// The features share any point
+    Intersects,
+    /// The target feature is within the join feature
+    Within,
+    /// The target feature contains the join feature
+    Contains,
+    /// The join feature closest to the target, optionally within a distance
+    Nearest { max_distance: Option<f64> },
+}
+
+/// How matches are written to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinCardinality {
+    /// One output feature per target, with join attributes aggregated
+    OneToOne,
+    /// One output feature per target and matching join feature
+    OneToMany,
+}
+
+/// Rule combining the values of a join field over all matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JoinAggregate {
+    First,
+    Last,
+    Sum,
+    Mean,
+    Min,
+    Max,
+    /// Number of matches with a non-null value
+    Count,
+    /// Values as text, joined by a separator
+    Join(String),
+}
+
+impl JoinAggregate {
+    /// Combine the values of one field
+    pub fn apply(&self, values: &[&Value]) -> Value {
+        let numbers = || values.iter().filter_map(|v| v.as_f64());
+        match self {
+            JoinAggregate::First => values.first().map_or(Value::Null, |v| (*v).clone()),
+            JoinAggregate::Last => values.last().map_or(Value::Null, |v| (*v).clone()),
+            JoinAggregate::Sum => json!(numbers().sum::<f64>()),
+            JoinAggregate::Mean => {
+                let count = numbers().count();
+                if count == 0 {
+                    Value::Null
+                } else {
+                    json!(numbers().sum::<f64>() / count as f64)
+                }
+            }
+            JoinAggregate::Min => numbers().reduce(f64::min).map_or(Value::Null, |v| json!(v)),
+            JoinAggregate::Max => numbers().reduce(f64::max).map_or(Value::Null, |v| json!(v)),
+            JoinAggregate::Count => json!(values.iter().filter(|v| !v.is_null()).count()),
+            JoinAggregate::Join(separator) => {
+                let parts: Vec<String> = values
+                    .iter()
+                    .filter(|v| !v.is_null())
+                    .map(|v| match v {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                json!(parts.join(separator))
+            }
+        }
+    }
+}
+
+/// Spatial join parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialJoinOptions {
+    pub predicate: JoinPredicate,
+    pub cardinality: JoinCardinality,
+    /// Keep target features without matches, with null join attributes
+    pub keep_all: bool,
+    /// Aggregation rules per join field for one-to-one joins; other fields use `First`
+    pub aggregates: BTreeMap<String, JoinAggregate>,
+}
+
+impl SpatialJoinOptions {
+    /// Create one-to-one join options keeping all target features
+    pub fn new(predicate: JoinPredicate) -> Self {
+        Self {
+            predicate,
+            cardinality: JoinCardinality::OneToOne,
+            keep_all: true,
+            aggregates: BTreeMap::new(),
+        }
+    }
+
+    /// Set the join cardinality
+    pub fn cardinality(mut self, cardinality: JoinCardinality) -> Self {
+        self.cardinality = cardinality;
+        self
+    }
+
+    /// Set whether target features without matches are kept
+    pub fn keep_all(mut self, keep_all: bool) -> Self {
+        self.keep_all = keep_all;
+        self
+    }
+
+    /// Set the aggregation rule of a join field
+    pub fn aggregate(mut self, field: impl Into<String>, rule: JoinAggregate) -> Self {
+        self.aggregates.insert(field.into(), rule);
+        self
+    }
+}
+
+/// Join the attributes of `join` features to `target` features by location
+///
+/// Output features have the target geometry and properties, followed by the
+/// join properties; join fields whose names are taken get a `_1` suffix.
+/// One-to-one joins add [`JOIN_COUNT_FIELD`] and nearest joins add
+/// [`JOIN_DISTANCE_FIELD`].
+pub fn spatial_join(target: &Layer, join: &Layer, options: &SpatialJoinOptions) -> Result<Layer> {
+    if let JoinPredicate::Nearest {
+        max_distance: Some(d),
+    } = options.predicate
+    {
+        if d < 0.0 {
+            return Err(AnalysisError::invalid_parameters(
+                "Maximum distance must be non-negative",
+            ));
+        }
+    }
+    let join_layer = IndexedLayer::new(join);
+    let join_fields = field_names(join);
+    let targets: Vec<&Feature> = target.iter().collect();
+    let joins: Vec<&Feature> = join.iter().collect();
+
+    let matches: Vec<Vec<(usize, f64)>> = targets
+        .par_iter()
+        .map(|feature| {
+            let geometry = to_geo_geometry(&feature.geometry);
+            let bounds = feature.geometry.bounds();
+            match options.predicate {
+                JoinPredicate::Nearest { max_distance } => join_layer
+                    .nearest(&geometry, &bounds, max_distance)
+                    .into_iter()
+                    .collect(),
+                predicate => join_layer
+                    .candidates(&bounds)
+                    .filter(|&j| {
+                        let other = &join_layer.geometries[j];
+                        match predicate {
+                            JoinPredicate::Intersects => geometry.intersects(other),
+                            JoinPredicate::Within => geometry.relate(other).is_within(),
+                            JoinPredicate::Contains => geometry.relate(other).is_contains(),
+                            JoinPredicate::Nearest { .. } => unreachable!(),
+                        }
+                    })
+                    .map(|j| (j, 0.0))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let nearest = matches!(options.predicate, JoinPredicate::Nearest { .. });
+    let mut output = Layer::new(target.name.clone(), target.crs.clone());
+    for (feature, matched) in targets.iter().zip(matches) {
+        if matched.is_empty() && !options.keep_all {
+            continue;
+        }
+        match options.cardinality {
+            JoinCardinality::OneToMany if !matched.is_empty() => {
+                for (j, distance) in matched {
+                    let mut properties = feature.properties.clone();
+                    merge_properties(&mut properties, &joins[j].properties);
+                    if nearest {
+                        properties.insert(JOIN_DISTANCE_FIELD.to_string(), json!(distance));
+                    }
+                    output.add_feature(Feature::with_properties(
+                        feature.geometry.clone(),
+                        properties,
+                    ));
+                }
+            }
+            JoinCardinality::OneToMany => {
+                let mut properties = feature.properties.clone();
+                let nulls = join_fields
+                    .iter()
+                    .map(|field| (field.clone(), Value::Null))
+                    .collect();
+                merge_properties(&mut properties, &nulls);
+                if nearest {
+                    properties.insert(JOIN_DISTANCE_FIELD.to_string(), Value::Null);
+                }
+                output.add_feature(Feature::with_properties(
+                    feature.geometry.clone(),
+                    properties,
+                ));
+            }
+            JoinCardinality::OneToOne => {
+                let aggregated = join_fields
+                    .iter()
+                    .map(|field| {
+                        let values: Vec<&Value> = matched
+                            .iter()
+                            .map(|(j, _)| joins[*j].properties.get(field).unwrap_or(&Value::Null))
+                            .collect();
+                        let rule = options
+                            .aggregates
+                            .get(field)
+                            .unwrap_or(&JoinAggregate::First);
+                        (field.clone(), rule.apply(&values))
+                    })
+                    .collect();
+                let mut properties = feature.properties.clone();
+                merge_properties(&mut properties, &aggregated);
+                properties.insert(JOIN_COUNT_FIELD.to_string(), json!(matched.len()));
+                if nearest {
+                    let distance = matched.first().map_or(Value::Null, |(_, d)| json!(d));
+                    properties.insert(JOIN_DISTANCE_FIELD.to_string(), distance);
+                }
+                output.add_feature(Feature::with_properties(
+                    feature.geometry.clone(),
+                    properties,
+                ));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Layer overlay operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerOverlayOp {
+    /// Parts of input features inside overlay features, with attributes of both
+    Intersect,
+    /// Intersections plus the parts of either layer outside the other; polygons only
+    Union,
+    /// Parts of input features outside all overlay features, with input attributes
+    Erase,
+}
+
+/// Overlay two feature layers, keeping the attributes of both sides
+///
+/// The overlay layer must contain polygons; input features may be points,
+/// lines or polygons except for [`LayerOverlayOp::Union`]. Overlay fields
+/// whose names are taken by input fields get a `_1` suffix, and fields of a
+/// side that does not contribute to an output feature are null. Overlaps
+/// within one layer are not resolved.
+pub fn overlay_features(input: &Layer, overlay: &Layer, op: LayerOverlayOp) -> Result<Layer> {
+    let overlay_layer = IndexedLayer::new(overlay);
+    let input_layer = IndexedLayer::new(input);
+    let inputs: Vec<&Feature> = input.iter().collect();
+    let overlays: Vec<&Feature> = overlay.iter().collect();
+    let input_nulls: Map<String, Value> = field_names(input)
+        .into_iter()
+        .map(|f| (f, Value::Null))
+        .collect();
+    let overlay_nulls: Map<String, Value> = field_names(overlay)
+        .into_iter()
+        .map(|f| (f, Value::Null))
+        .collect();
+    let with_properties = |input_properties: &Map<String, Value>,
+                           overlay_properties: &Map<String, Value>| {
+        let mut properties = input_properties.clone();
+        for (key, value) in &input_nulls {
+            properties
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        if op != LayerOverlayOp::Erase {
+            let mut overlay_all = overlay_nulls.clone();
+            overlay_all.extend(overlay_properties.clone());
+            merge_properties(&mut properties, &overlay_all);
+        }
+        properties
+    };
+    if op == LayerOverlayOp::Union
+        && (input_layer
+            .geometries
+            .iter()
+            .chain(&overlay_layer.geometries))
+        .any(|g| polygonal(g).is_none())
+    {
+        return Err(AnalysisError::invalid_geometry(
+            "Union overlay requires polygon layers",
+        ));
+    }
+
+    // Parts of the input features, intersected with or outside the overlay features
+    let pieces: Vec<Vec<(Geometry, Option<usize>)>> = (0..input.len())
+        .into_par_iter()
+        .map(|i| {
+            let geometry = &input_layer.geometries[i];
+            let candidates: Vec<usize> = overlay_layer
+                .candidates(&inputs[i].geometry.bounds())
+                .filter(|&j| geometry.intersects(&overlay_layer.geometries[j]))
+                .collect();
+            let mut pieces = Vec::new();
+            if op != LayerOverlayOp::Erase {
+                for &j in &candidates {
+                    if let Some(piece) = intersect_geometry(geometry, &overlay_layer.geometries[j])?
+                    {
+                        pieces.push((piece, Some(j)));
+                    }
+                }
+            }
+            if op != LayerOverlayOp::Intersect {
+                if let Some(piece) = erase_geometry(geometry, &overlay_layer.union_of(&candidates))?
+                {
+                    pieces.push((piece, None));
+                }
+            }
+            Ok(pieces)
+        })
+        .collect::<Result<_>>()?;
+
+    let mut output = Layer::new(input.name.clone(), input.crs.clone());
+    for (i, pieces) in pieces.into_iter().enumerate() {
+        for (piece, j) in pieces {
+            let overlay_properties = j.map_or(&overlay_nulls, |j| &overlays[j].properties);
+            let properties = with_properties(&inputs[i].properties, overlay_properties);
+            output.add_feature(Feature::with_properties(
+                from_geo_geometry(&piece, &input.crs),
+                properties,
+            ));
+        }
+    }
+
+    // Parts of the overlay features outside the input features
+    if op == LayerOverlayOp::Union {
+        let outside: Vec<Option<Geometry>> = (0..overlay.len())
+            .into_par_iter()
+            .map(|j| {
+                let geometry = &overlay_layer.geometries[j];
+                let candidates: Vec<usize> = input_layer
+                    .candidates(&overlays[j].geometry.bounds())
+                    .filter(|&i| geometry.intersects(&input_layer.geometries[i]))
+                    .collect();
+                erase_geometry(geometry, &input_layer.union_of(&candidates))
+            })
+            .collect::<Result<_>>()?;
+        for (j, piece) in outside.into_iter().enumerate() {
+            if let Some(piece) = piece {
+                let properties = with_properties(&input_nulls, &overlays[j].properties);
+                output.add_feature(Feature::with_properties(
+                    from_geo_geometry(&piece, &input.crs),
+                    properties,
+                ));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Cross-tabulate the overlap of zone features and class features
+///
+/// Produces one record per zone and class value with the overlapping area,
+/// length or count of the class features, depending on their dimension, and
+/// its percentage of the zone area, or of the class total in the zone for
+/// lines and points. Records have empty geometries. Without a class field
+/// all class features form one class.
+pub fn tabulate_intersection(
+    zones: &Layer,
+    zone_field: &str,
+    classes: &Layer,
+    class_field: Option<&str>,
+) -> Result<Layer> {
+    let class_layer = IndexedLayer::new(classes);
+    let zone_layer = IndexedLayer::new(zones);
+    let zone_features: Vec<&Feature> = zones.iter().collect();
+    let class_features: Vec<&Feature> = classes.iter().collect();
+    let measure_field = match class_layer.geometries.iter().map(dimension).max() {
+        Some(2) | None => "area",
+        Some(1) => "length",
+        _ => "count",
+    };
+
+    let records: Vec<Vec<(Value, f64)>> = (0..zones.len())
+        .into_par_iter()
+        .map(|z| {
+            let zone = &zone_layer.geometries[z];
+            if polygonal(zone).is_none() {
+                return Err(AnalysisError::invalid_geometry(format!(
+                    "Zone {} is not a polygon",
+                    z
+                )));
+            }
+            let mut totals: Vec<(Value, f64)> = Vec::new();
+            for c in class_layer.candidates(&zone_features[z].geometry.bounds()) {
+                let Some(piece) = intersect_geometry(&class_layer.geometries[c], zone)? else {
+                    continue;
+                };
+                let measure = match measure_field {
+                    "area" => piece.unsigned_area(),
+                    "length" => geometry_length(&piece),
+                    _ => point_count(&piece) as f64,
+                };
+                let class = class_field.map_or(Value::Null, |field| {
+                    class_features[c]
+                        .properties
+                        .get(field)
+                        .cloned()
+                        .unwrap_or(Value::Null)
+                });
+                match totals.iter_mut().find(|(value, _)| *value == class) {
+                    Some((_, total)) => *total += measure,
+                    None => totals.push((class, measure)),
+                }
+            }
+            Ok(totals)
+        })
+        .collect::<Result<_>>()?;
+
+    let mut output = Layer::new(format!("{}_tabulation", zones.name), zones.crs.clone());
+    for (z, totals) in records.into_iter().enumerate() {
+        let denominator = match measure_field {
+            "area" => zone_layer.geometries[z].unsigned_area(),
+            _ => totals.iter().map(|(_, total)| total).sum(),
+        };
+        for (class, total) in totals {
+            let mut feature = Feature::new(CoreGeometry::GeometryCollection(
+                CoreGeometryCollection::new(vec![], zones.crs.clone()),
+            ));
+            let zone_value = zone_features[z]
+                .properties
+                .get(zone_field)
+                .cloned()
+                .unwrap_or(Value::Null);
+            feature.set_property(zone_field, zone_value);
+            if let Some(field) = class_field {
+                feature.set_property(
+                    if field == zone_field {
+                        format!("{}_1", field)
+                    } else {
+                        field.to_string()
+                    },
+                    class,
+                );
+            }
+            feature.set_property(measure_field, json!(total));
+            let percentage = if denominator > 0.0 {
+                total / denominator * 100.0
+            } else {
+                0.0
+            };
+            feature.set_property("percentage", json!(percentage));
+            output.add_feature(feature);
+        }
+    }
+    Ok(output)
+}
+
+/// Layer geometries converted for exact predicates, with a bounding box index
+pub(crate) struct IndexedLayer {
+    pub(crate) geometries: Vec<Geometry>,
+    index: SpatialIndex,
+    bounds: Option<BoundingBox>,
+}
+
+impl IndexedLayer {
+    pub(crate) fn new(layer: &Layer) -> Self {
+        Self {
+            geometries: layer.iter().map(|f| to_geo_geometry(&f.geometry)).collect(),
+            index: SpatialIndex::from_geometries(layer.iter().map(|f| f.geometry.clone())),
+            bounds: layer.bounds(),
+        }
+    }
+
+    /// Indices of features whose bounding boxes intersect `bounds`, ascending
+    pub(crate) fn candidates(&self, bounds: &BoundingBox) -> impl Iterator<Item = usize> {
+        let mut ids: Vec<usize> = self
+            .index
+            .query_bbox(bounds)
+            .map(|g| g.id as usize)
+            .collect();
+        ids.sort_unstable();
+        ids.into_iter()
+    }
+
+    /// Closest feature, searching boxes of growing size around `bounds`
+    fn nearest(
+        &self,
+        geometry: &Geometry,
+        bounds: &BoundingBox,
+        max_distance: Option<f64>,
+    ) -> Option<(usize, f64)> {
+        let extent = self.bounds.as_ref()?.union(bounds);
+        let limit = max_distance.unwrap_or(f64::INFINITY);
+        let diagonal = extent.width().hypot(extent.height());
+        let mut radius = (diagonal / (self.geometries.len() as f64).sqrt())
+            .min(limit)
+            .max(f64::MIN_POSITIVE);
+        loop {
+            let best = self
+                .candidates(&bounds.buffer(radius))
+                .map(|j| (j, geometry_distance(geometry, &self.geometries[j])))
+                .filter(|(_, d)| *d <= limit)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            match best {
+                // Features outside the box are farther away than the radius
+                Some((j, d)) if d <= radius => return Some((j, d)),
+                Some((_, d)) => radius = d,
+                None if radius >= limit || radius > diagonal => return None,
+                None => radius = (radius * 2.0).min(limit),
+            }
+        }
+    }
+
+    fn union_of(&self, ids: &[usize]) -> MultiPolygon {
+        ids.iter()
+            .filter_map(|&j| polygonal(&self.geometries[j]))
+            .fold(MultiPolygon::new(vec![]), |union, polygons| {
+                union.union(&polygons)
+            })
+    }
+}
+
+fn field_names(layer: &Layer) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for feature in layer.iter() {
+        for key in feature.property_keys() {
+            if !names.contains(key) {
+                names.push(key.clone());
+            }
+        }
+    }
+    names
+}
+
+fn merge_properties(target: &mut Map<String, Value>, source: &Map<String, Value>) {
+    let own: Vec<String> = target.keys().cloned().collect();
+    for (key, value) in source {
+        let key = if own.contains(key) {
+            format!("{}_1", key)
+        } else {
+            key.clone()
+        };
+        target.insert(key, value.clone());
+    }
+}
+
+/// Convert a core geometry to a `geo` geometry, linearizing curves
+pub(crate) fn to_geo_geometry(geometry: &CoreGeometry) -> Geometry {
+    match geometry {
+        CoreGeometry::Point(p) => Geometry::Point(p.geom),
+        CoreGeometry::MultiPoint(p) => Geometry::MultiPoint(p.geom.clone()),
+        CoreGeometry::LineString(l) => Geometry::LineString(l.geom.clone()),
+        CoreGeometry::MultiLineString(l) => Geometry::MultiLineString(l.geom.clone()),
+        CoreGeometry::Polygon(p) => Geometry::Polygon(p.geom.clone()),
+        CoreGeometry::MultiPolygon(p) => Geometry::MultiPolygon(p.geom.clone()),
+        CoreGeometry::GeometryCollection(c) => {
+            Geometry::GeometryCollection(c.geometries.iter().map(to_geo_geometry).collect())
+        }
+        curve => to_geo_geometry(&curve.linearize(DEFAULT_SEGMENTS_PER_QUADRANT)),
+    }
+}
+
+/// Convert a `geo` geometry to a core geometry
+pub(crate) fn from_geo_geometry(geometry: &Geometry, crs: &Crs) -> CoreGeometry {
+    match geometry {
+        Geometry::Point(p) => CoreGeometry::Point(CorePoint::new(p.x(), p.y(), crs.clone())),
+        Geometry::MultiPoint(p) if p.0.len() == 1 => {
+            from_geo_geometry(&Geometry::Point(p.0[0]), crs)
+        }
+        Geometry::MultiPoint(p) => {
+            CoreGeometry::MultiPoint(CoreMultiPoint::new(p.0.clone(), crs.clone()))
+        }
+        Geometry::Line(l) => {
+            CoreGeometry::LineString(CoreLineString::new(vec![l.start, l.end], crs.clone()))
+        }
+        Geometry::LineString(l) => {
+            CoreGeometry::LineString(CoreLineString::new(l.0.clone(), crs.clone()))
+        }
+        Geometry::MultiLineString(l) if l.0.len() == 1 => {
+            from_geo_geometry(&Geometry::LineString(l.0[0].clone()), crs)
+        }
+        Geometry::MultiLineString(l) => {
+            CoreGeometry::MultiLineString(CoreMultiLineString::new(l.0.clone(), crs.clone()))
+        }
+        Geometry::Polygon(p) => CoreGeometry::Polygon(CorePolygon::new(
+            p.exterior().clone(),
+            p.interiors().to_vec(),
+            crs.clone(),
+        )),
+        Geometry::MultiPolygon(p) if p.0.len() == 1 => {
+            from_geo_geometry(&Geometry::Polygon(p.0[0].clone()), crs)
+        }
+        Geometry::MultiPolygon(p) => {
+            CoreGeometry::MultiPolygon(CoreMultiPolygon::new(p.0.clone(), crs.clone()))
+        }
+        Geometry::Rect(r) => from_geo_geometry(&Geometry::Polygon(r.to_polygon()), crs),
+        Geometry::Triangle(t) => from_geo_geometry(&Geometry::Polygon(t.to_polygon()), crs),
+        Geometry::GeometryCollection(c) => {
+            CoreGeometry::GeometryCollection(CoreGeometryCollection::new(
+                c.0.iter().map(|g| from_geo_geometry(g, crs)).collect(),
+                crs.clone(),
+            ))
+        }
+    }
+}
+
+fn polygonal(geometry: &Geometry) -> Option<MultiPolygon> {
+    match geometry {
+        Geometry::Polygon(p) => Some(MultiPolygon::new(vec![p.clone()])),
+        Geometry::MultiPolygon(p) => Some(p.clone()),
+        Geometry::Rect(r) => Some(MultiPolygon::new(vec![r.to_polygon()])),
+        Geometry::Triangle(t) => Some(MultiPolygon::new(vec![t.to_polygon()])),
+        _ => None,
+    }
+}
+
+fn lineal(geometry: &Geometry) -> Option<MultiLineString> {
+    match geometry {
+        Geometry::Line(l) => Some(MultiLineString::new(vec![LineString::new(vec![
+            l.start, l.end,
+        ])])),
+        Geometry::LineString(l) => Some(MultiLineString::new(vec![l.clone()])),
+        Geometry::MultiLineString(l) => Some(l.clone()),
+        _ => None,
+    }
+}
+
+fn puntal(geometry: &Geometry) -> Option<MultiPoint> {
+    match geometry {
+        Geometry::Point(p) => Some(MultiPoint::new(vec![*p])),
+        Geometry::MultiPoint(p) => Some(p.clone()),
+        _ => None,
+    }
+}
+
+fn dimension(geometry: &Geometry) -> u8 {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => 0,
+        Geometry::Line(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => 1,
+        Geometry::GeometryCollection(c) => c.0.iter().map(dimension).max().unwrap_or(0),
+        _ => 2,
+    }
+}
+
+/// Part of `geometry` inside the polygons of `other`, `None` if empty
+fn intersect_geometry(geometry: &Geometry, other: &Geometry) -> Result<Option<Geometry>> {
+    let Some(mask) = polygonal(other) else {
+        return Err(AnalysisError::invalid_geometry(
+            "Overlay features must be polygons",
+        ));
+    };
+    clip_geometry(geometry, &mask, false)
+}
+
+/// Part of `geometry` outside `mask`, `None` if empty
+fn erase_geometry(geometry: &Geometry, mask: &MultiPolygon) -> Result<Option<Geometry>> {
+    clip_geometry(geometry, mask, true)
+}
+
+fn clip_geometry(
+    geometry: &Geometry,
+    mask: &MultiPolygon,
+    invert: bool,
+) -> Result<Option<Geometry>> {
+    if let Some(polygons) = polygonal(geometry) {
+        let result = if invert {
+            polygons.difference(mask)
+        } else {
+            polygons.intersection(mask)
+        };
+        return Ok((result.unsigned_area() > 0.0).then_some(Geometry::MultiPolygon(result)));
+    }
+    if let Some(lines) = lineal(geometry) {
+        let mut result = mask.clip(&lines, invert);
+        result.0.retain(|line| line.euclidean_length() > 0.0);
+        return Ok((!result.0.is_empty()).then_some(Geometry::MultiLineString(result)));
+    }
+    if let Some(points) = puntal(geometry) {
+        let result: Vec<Point> = points
+            .0
+            .into_iter()
+            .filter(|p| mask.intersects(p) != invert)
+            .collect();
+        return Ok((!result.is_empty()).then(|| Geometry::MultiPoint(MultiPoint::new(result))));
+    }
+    Err(AnalysisError::invalid_geometry(
+        "Geometry collections are not supported in overlays",
+    ))
+}
+
+fn geometry_distance(a: &Geometry, b: &Geometry) -> f64 {
+    if a.intersects(b) {
+        return 0.0;
+    }
+    let coords = |g: &Geometry| -> Vec<Coord> {
+        match g {
+            Geometry::Point(p) => vec![p.0],
+            Geometry::MultiPoint(p) => p.0.iter().map(|p| p.0).collect(),
+            _ => vec![],
+        }
+    };
+    match (puntal(a), puntal(b)) {
+        (Some(_), _) => coords(a)
+            .into_iter()
+            .map(|c| Point::from(c).euclidean_distance(b))
+            .fold(f64::INFINITY, f64::min),
+        (None, Some(_)) => geometry_distance(b, a),
+        (None, None) => a.euclidean_distance(b),
+    }
+}
+
+fn geometry_length(geometry: &Geometry) -> f64 {
+    lineal(geometry).map_or(0.0, |lines| lines.euclidean_length())
+}
+
+fn point_count(geometry: &Geometry) -> usize {
+    puntal(geometry).map_or(0, |points| points.0.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn square(x: f64, y: f64, size: f64) -> CoreGeometry {
+        CoreGeometry::Polygon(CorePolygon::new(
+            LineString::from(vec![
+                (x, y),
+                (x + size, y),
+                (x + size, y + size),
+                (x, y + size),
+                (x, y),
+            ]),
+            vec![],
+            Crs::wgs84(),
+        ))
+    }
+
+    fn point(x: f64, y: f64) -> CoreGeometry {
+        CoreGeometry::Point(CorePoint::new(x, y, Crs::wgs84()))
+    }
+
+    fn layer(name: &str, features: Vec<(CoreGeometry, Value)>) -> Layer {
+        let features = features
+            .into_iter()
+            .map(|(geometry, properties)| {
+                let Value::Object(properties) = properties else {
+                    unreachable!()
+                };
+                Feature::with_properties(geometry, properties)
+            })
+            .collect();
+        Layer::from_features(name, Crs::wgs84(), features)
+    }
+
+    fn districts() -> Layer {
+        layer(
+            "districts",
+            vec![
+                (
+                    square(0.0, 0.0, 10.0),
+                    json!({"name": "north", "zone": "A"}),
+                ),
+                (
+                    square(10.0, 0.0, 10.0),
+                    json!({"name": "south", "zone": "B"}),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_spatial_join_one_to_one() {
+        let wells = layer(
+            "wells",
+            vec![
+                (point(1.0, 1.0), json!({"name": "w1", "yield": 10.0})),
+                (point(2.0, 5.0), json!({"name": "w2", "yield": 30.0})),
+                (point(15.0, 5.0), json!({"name": "w3", "yield": 5.0})),
+            ],
+        );
+        let options = SpatialJoinOptions::new(JoinPredicate::Intersects)
+            .aggregate("yield", JoinAggregate::Sum)
+            .aggregate("name", JoinAggregate::Join(",".to_string()));
+        let joined = spatial_join(&districts(), &wells, &options).unwrap();
+
+        assert_eq!(joined.len(), 2);
+        let north = joined.get(0).unwrap();
+        assert_eq!(north.get_property("name"), Some(&json!("north")));
+        assert_eq!(north.get_property("name_1"), Some(&json!("w1,w2")));
+        assert_eq!(north.get_property("yield"), Some(&json!(40.0)));
+        assert_eq!(north.get_property(JOIN_COUNT_FIELD), Some(&json!(2)));
+        assert_eq!(
+            joined.get(1).unwrap().get_property(JOIN_COUNT_FIELD),
+            Some(&json!(1))
+        );
+    }
+
+    #[test]
+    fn test_spatial_join_within_one_to_many() {
+        let wells = layer(
+            "wells",
+            vec![
+                (point(1.0, 1.0), json!({"id": 1})),
+                (point(50.0, 50.0), json!({"id": 2})),
+            ],
+        );
+        let options =
+            SpatialJoinOptions::new(JoinPredicate::Within).cardinality(JoinCardinality::OneToMany);
+        let joined = spatial_join(&wells, &districts(), &options).unwrap();
+        assert_eq!(joined.len(), 2);
+        assert_eq!(
+            joined.get(0).unwrap().get_property("zone"),
+            Some(&json!("A"))
+        );
+        assert_eq!(
+            joined.get(1).unwrap().get_property("zone"),
+            Some(&json!(null))
+        );
+
+        let inner = spatial_join(&wells, &districts(), &options.keep_all(false)).unwrap();
+        assert_eq!(inner.len(), 1);
+    }
+
+    #[test]
+    fn test_spatial_join_nearest() {
+        let stations = layer(
+            "stations",
+            vec![
+                (point(0.0, 20.0), json!({"station": "a"})),
+                (point(30.0, 5.0), json!({"station": "b"})),
+            ],
+        );
+        let wells = layer(
+            "wells",
+            vec![
+                (point(3.0, 12.0), json!({})),
+                (point(100.0, 100.0), json!({})),
+            ],
+        );
+
+        let options = SpatialJoinOptions::new(JoinPredicate::Nearest { max_distance: None });
+        let joined = spatial_join(&wells, &stations, &options).unwrap();
+        let first = joined.get(0).unwrap();
+        assert_eq!(first.get_property("station"), Some(&json!("a")));
+        assert_relative_eq!(
+            first
+                .get_property(JOIN_DISTANCE_FIELD)
+                .unwrap()
+                .as_f64()
+                .unwrap(),
+            73.0_f64.sqrt()
+        );
+
+        // Distance to a polygon is measured to its boundary
+        let nearest_district = spatial_join(&stations, &districts(), &options).unwrap();
+        assert_eq!(
+            nearest_district.get(1).unwrap().get_property("zone"),
+            Some(&json!("B"))
+        );
+        assert_relative_eq!(
+            nearest_district
+                .get(1)
+                .unwrap()
+                .get_property(JOIN_DISTANCE_FIELD)
+                .unwrap()
+                .as_f64()
+                .unwrap(),
+            10.0
+        );
+
+        let limited = SpatialJoinOptions::new(JoinPredicate::Nearest {
+            max_distance: Some(20.0),
+        })
+        .keep_all(false);
+        assert_eq!(spatial_join(&wells, &stations, &limited).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_overlay_features() {
+        let parcels = layer(
+            "parcels",
+            vec![(square(5.0, 0.0, 10.0), json!({"parcel": 7, "zone": "R1"}))],
+        );
+
+        let intersect =
+            overlay_features(&parcels, &districts(), LayerOverlayOp::Intersect).unwrap();
+        assert_eq!(intersect.len(), 2);
+        let first = intersect.get(0).unwrap();
+        assert_eq!(first.get_property("parcel"), Some(&json!(7)));
+        assert_eq!(first.get_property("zone"), Some(&json!("R1")));
+        assert_eq!(first.get_property("zone_1"), Some(&json!("A")));
+        assert_relative_eq!(to_geo_geometry(&first.geometry).unsigned_area(), 50.0);
+
+        let outer = layer("outer", vec![(square(0.0, 0.0, 8.0), json!({"name": "x"}))]);
+        let erased = overlay_features(&parcels, &outer, LayerOverlayOp::Erase).unwrap();
+        assert_eq!(erased.len(), 1);
+        assert!(!erased.get(0).unwrap().has_property("name"));
+        assert_relative_eq!(
+            to_geo_geometry(&erased.get(0).unwrap().geometry).unsigned_area(),
+            76.0
+        );
+
+        let union = overlay_features(&parcels, &outer, LayerOverlayOp::Union).unwrap();
+        assert_eq!(union.len(), 3);
+        let areas: Vec<f64> = union
+            .iter()
+            .map(|f| to_geo_geometry(&f.geometry).unsigned_area())
+            .collect();
+        assert_relative_eq!(areas.iter().sum::<f64>(), 140.0);
+        assert_eq!(
+            union.get(2).unwrap().get_property("parcel"),
+            Some(&json!(null))
+        );
+        assert_eq!(
+            union.get(2).unwrap().get_property("name"),
+            Some(&json!("x"))
+        );
+
+        // Lines are clipped by polygons
+        let roads = layer(
+            "roads",
+            vec![(
+                CoreGeometry::LineString(CoreLineString::new(
+                    vec![Coord { x: -5.0, y: 5.0 }, Coord { x: 25.0, y: 5.0 }],
+                    Crs::wgs84(),
+                )),
+                json!({"road": "main"}),
+            )],
+        );
+        let clipped = overlay_features(&roads, &districts(), LayerOverlayOp::Intersect).unwrap();
+        assert_eq!(clipped.len(), 2);
+        assert!(overlay_features(&roads, &districts(), LayerOverlayOp::Union).is_err());
+    }
+
+    #[test]
+    fn test_tabulate_intersection() {
+        let soils = layer(
+            "soils",
+            vec![
+                (square(0.0, 0.0, 5.0), json!({"soil": "clay"})),
+                (square(5.0, 0.0, 10.0), json!({"soil": "sand"})),
+                (square(0.0, 5.0, 5.0), json!({"soil": "clay"})),
+            ],
+        );
+        let table = tabulate_intersection(&districts(), "name", &soils, Some("soil")).unwrap();
+        assert_eq!(table.len(), 3);
+        let north_clay = table.get(0).unwrap();
+        assert_eq!(north_clay.get_property("name"), Some(&json!("north")));
+        assert_eq!(north_clay.get_property("soil"), Some(&json!("clay")));
+        assert_relative_eq!(
+            north_clay.get_property("area").unwrap().as_f64().unwrap(),
+            50.0
+        );
+        assert_relative_eq!(
+            north_clay
+                .get_property("percentage")
+                .unwrap()
+                .as_f64()
+                .unwrap(),
+            50.0
+        );
+        let south_sand = table.get(2).unwrap();
+        assert_eq!(south_sand.get_property("name"), Some(&json!("south")));
+        assert_relative_eq!(
+            south_sand.get_property("area").unwrap().as_f64().unwrap(),
+            50.0
+        );
+    }
+}
@@ -0,0 +1,1035 @@
+//! Topology rules and editing tools for polygon coverages
+//!
+//! A coverage is a set of polygons that should tile an area without
+//! overlaps or gaps, such as parcels or administrative units. Shared borders
+//! are expected to have the same vertices on both sides; [`Coverage::snap`]
+//! establishes that for data digitized separately.
+
+use crate::error::{AnalysisError, Result};
+use geo::{
+    Area, BooleanOps, BoundingRect, Coord, Geometry, LineString, MultiLineString, MultiPolygon,
+    Polygon, Rect, Simplify,
+};
+use meridian_core::crs::Crs;
+use meridian_core::feature::Feature;
+use meridian_core::geometry::{
+    Geometry as CoreGeometry, LineString as CoreLineString, MultiLineString as CoreMultiLineString,
+    MultiPolygon as CoreMultiPolygon, Polygon as CorePolygon,
+};
+use meridian_core::layer::Layer;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+/// A topology rule for a polygon layer
+#[derive(Debug, Clone, Copy)]
+pub enum TopologyRule<'a> {
+    /// Polygons must not overlap each other
+    MustNotOverlap,
+    /// The union of the polygons must not have holes
+    MustNotHaveGaps,
+    /// Polygons must lie within the polygons of another layer
+    MustBeCoveredBy(&'a Layer),
+    /// Polygon boundaries must lie on the boundaries of another layer
+    BoundaryMustAlignWith(&'a Layer),
+}
+
+impl TopologyRule<'_> {
+    /// Rule type reported for violations
+    pub fn rule_type(&self) -> RuleType {
+        match self {
+            TopologyRule::MustNotOverlap => RuleType::MustNotOverlap,
+            TopologyRule::MustNotHaveGaps => RuleType::MustNotHaveGaps,
+            TopologyRule::MustBeCoveredBy(_) => RuleType::MustBeCoveredBy,
+            TopologyRule::BoundaryMustAlignWith(_) => RuleType::BoundaryMustAlignWith,
+        }
+    }
+}
+
+/// Topology rule type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleType {
+    MustNotOverlap,
+    MustNotHaveGaps,
+    MustBeCoveredBy,
+    BoundaryMustAlignWith,
+}
+
+impl RuleType {
+    /// Rule name as written to violation features
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleType::MustNotOverlap => "must_not_overlap",
+            RuleType::MustNotHaveGaps => "must_not_have_gaps",
+            RuleType::MustBeCoveredBy => "must_be_covered_by",
+            RuleType::BoundaryMustAlignWith => "boundary_must_align_with",
+        }
+    }
+}
+
+/// A topology rule violation
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopologyViolation {
+    pub rule: RuleType,
+    /// Indices of the features involved; empty for gaps
+    pub features: Vec<usize>,
+    /// Overlapping, missing or uncovered area, or misaligned boundary
+    pub geometry: Geometry,
+}
+
+/// Violations found by [`validate_topology`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopologyReport {
+    pub violations: Vec<TopologyViolation>,
+}
+
+impl TopologyReport {
+    /// Check if no rule is violated
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Violations of one rule
+    pub fn violations_of(&self, rule: RuleType) -> impl Iterator<Item = &TopologyViolation> {
+        self.violations.iter().filter(move |v| v.rule == rule)
+    }
+
+    /// Convert the violations to error features
+    ///
+    /// Each feature has the violation geometry and `rule`, `features` and
+    /// `area` or `length` properties. Violation geometries must be areas or
+    /// lines, as found by [`validate_topology`].
+    pub fn to_layer(&self, name: &str, crs: Crs) -> Result<Layer> {
+        let features = self
+            .violations
+            .iter()
+            .map(|violation| {
+                let mut feature = Feature::new(to_core_geometry(&violation.geometry, &crs)?);
+                feature.set_property("rule", json!(violation.rule.as_str()));
+                feature.set_property("features", json!(violation.features));
+                match &violation.geometry {
+                    Geometry::MultiLineString(lines) => {
+                        let length: f64 = lines.0.iter().map(line_length).sum();
+                        feature.set_property("length", json!(length));
+                    }
+                    geometry => feature.set_property("area", json!(geometry.unsigned_area())),
+                }
+                Ok(feature)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Layer::from_features(name, crs, features))
+    }
+}
+
+/// Check topology rules on a polygon layer
+///
+/// Overlaps, gaps and uncovered areas smaller than `tolerance²` and
+/// misaligned boundary pieces shorter than `tolerance` are ignored.
+/// Boundaries within `tolerance` of each other count as aligned.
+pub fn validate_topology(
+    layer: &Layer,
+    rules: &[TopologyRule],
+    tolerance: f64,
+) -> Result<TopologyReport> {
+    Coverage::from_layer(layer)?.validate(rules, tolerance)
+}
+
+/// A set of polygons, one per feature
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    pub polygons: Vec<MultiPolygon>,
+}
+
+impl Coverage {
+    /// Create a coverage from polygons
+    pub fn new(polygons: Vec<MultiPolygon>) -> Self {
+        Self { polygons }
+    }
+
+    /// Create a coverage from the features of a polygon layer
+    ///
+    /// Z and M values are not kept.
+    pub fn from_layer(layer: &Layer) -> Result<Self> {
+        let polygons = layer
+            .iter()
+            .enumerate()
+            .map(|(i, feature)| match &feature.geometry {
+                CoreGeometry::Polygon(polygon) => Ok(MultiPolygon::new(vec![polygon.geom.clone()])),
+                CoreGeometry::MultiPolygon(polygons) => Ok(polygons.geom.clone()),
+                _ => Err(AnalysisError::invalid_geometry(format!(
+                    "Feature {} of a coverage is not a polygon",
+                    i
+                ))),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { polygons })
+    }
+
+    /// Copy a layer, replacing the feature geometries by the coverage polygons
+    pub fn to_layer(&self, template: &Layer) -> Result<Layer> {
+        if template.len() != self.polygons.len() {
+            return Err(AnalysisError::invalid_parameters(format!(
+                "Coverage has {} polygons but the layer has {} features",
+                self.polygons.len(),
+                template.len()
+            )));
+        }
+        let features = template
+            .iter()
+            .zip(&self.polygons)
+            .map(|(feature, polygons)| {
+                let geometry = to_core_geometry(&Geometry::MultiPolygon(polygons.clone()), &template.crs)?;
+                Ok(feature.with_geometry(geometry))
+            })
+            .collect::<Result<_>>()?;
+        let mut layer = Layer::from_features(template.name.clone(), template.crs.clone(), features);
+        layer.metadata = template.metadata.clone();
+        Ok(layer)
+    }
+
+    /// Number of polygons
+    pub fn len(&self) -> usize {
+        self.polygons.len()
+    }
+
+    /// Check if there are no polygons
+    pub fn is_empty(&self) -> bool {
+        self.polygons.is_empty()
+    }
+
+    /// Union of all polygons
+    pub fn union(&self) -> MultiPolygon {
+        self.polygons
+            .iter()
+            .fold(MultiPolygon::new(vec![]), |union, polygons| {
+                union.union(polygons)
+            })
+    }
+
+    /// Check topology rules, see [`validate_topology`]
+    pub fn validate(&self, rules: &[TopologyRule], tolerance: f64) -> Result<TopologyReport> {
+        if tolerance < 0.0 {
+            return Err(AnalysisError::invalid_parameters(
+                "Tolerance must be non-negative",
+            ));
+        }
+        let min_area = tolerance * tolerance;
+        let mut report = TopologyReport::default();
+        for rule in rules {
+            let violations = match rule {
+                TopologyRule::MustNotOverlap => self.overlaps(min_area),
+                TopologyRule::MustNotHaveGaps => self.gaps(min_area),
+                TopologyRule::MustBeCoveredBy(layer) => {
+                    self.uncovered_by(&Coverage::from_layer(layer)?, min_area)
+                }
+                TopologyRule::BoundaryMustAlignWith(layer) => {
+                    self.misaligned_boundaries(&Coverage::from_layer(layer)?, tolerance)
+                }
+            };
+            report.violations.extend(violations);
+        }
+        Ok(report)
+    }
+
+    /// Areas shared by two polygons
+    pub fn overlaps(&self, min_area: f64) -> Vec<TopologyViolation> {
+        let rects = self.rects();
+        let mut violations = Vec::new();
+        for i in 0..self.polygons.len() {
+            for j in i + 1..self.polygons.len() {
+                if !rects_intersect(rects[i], rects[j], 0.0) {
+                    continue;
+                }
+                let overlap = self.polygons[i].intersection(&self.polygons[j]);
+                if overlap.unsigned_area() > min_area {
+                    violations.push(TopologyViolation {
+                        rule: RuleType::MustNotOverlap,
+                        features: vec![i, j],
+                        geometry: Geometry::MultiPolygon(overlap),
+                    });
+                }
+            }
+        }
+        violations
+    }
+
+    /// Holes in the union of the polygons
+    pub fn gaps(&self, min_area: f64) -> Vec<TopologyViolation> {
+        gap_polygons(&self.union())
+            .filter(|gap| gap.unsigned_area() > min_area)
+            .map(|gap| TopologyViolation {
+                rule: RuleType::MustNotHaveGaps,
+                features: vec![],
+                geometry: Geometry::Polygon(gap),
+            })
+            .collect()
+    }
+
+    /// Parts of the polygons outside another coverage
+    pub fn uncovered_by(&self, other: &Coverage, min_area: f64) -> Vec<TopologyViolation> {
+        let cover = other.union();
+        self.polygons
+            .iter()
+            .enumerate()
+            .filter_map(|(i, polygons)| {
+                let outside = polygons.difference(&cover);
+                (outside.unsigned_area() > min_area).then(|| TopologyViolation {
+                    rule: RuleType::MustBeCoveredBy,
+                    features: vec![i],
+                    geometry: Geometry::MultiPolygon(outside),
+                })
+            })
+            .collect()
+    }
+
+    /// Parts of the polygon boundaries not on a boundary of another coverage
+    pub fn misaligned_boundaries(
+        &self,
+        other: &Coverage,
+        tolerance: f64,
+    ) -> Vec<TopologyViolation> {
+        let targets = boundary_segments(&other.polygons);
+        self.polygons
+            .iter()
+            .enumerate()
+            .filter_map(|(i, polygons)| {
+                let mut pieces: Vec<Vec<Coord>> = Vec::new();
+                for (a, b) in boundary_segments(std::slice::from_ref(polygons)) {
+                    for (t0, t1) in uncovered_intervals(a, b, &targets, tolerance) {
+                        if (t1 - t0) * distance(a, b) <= tolerance {
+                            continue;
+                        }
+                        let (start, end) = (lerp(a, b, t0), lerp(a, b, t1));
+                        match pieces.last_mut() {
+                            Some(piece) if piece.last() == Some(&start) => piece.push(end),
+                            _ => pieces.push(vec![start, end]),
+                        }
+                    }
+                }
+                (!pieces.is_empty()).then(|| TopologyViolation {
+                    rule: RuleType::BoundaryMustAlignWith,
+                    features: vec![i],
+                    geometry: Geometry::MultiLineString(MultiLineString::new(
+                        pieces.into_iter().map(LineString::from).collect(),
+                    )),
+                })
+            })
+            .collect()
+    }
+
+    /// Snap nearby vertices and edges of different polygons together
+    ///
+    /// Vertices within `tolerance` of each other are merged, and vertices
+    /// within `tolerance` of another polygon's edge are moved onto it and
+    /// inserted into that edge, so that neighbors share their border vertices.
+    pub fn snap(&self, tolerance: f64) -> Coverage {
+        if tolerance <= 0.0 {
+            return self.clone();
+        }
+        let mut rings = explode(&self.polygons);
+
+        // Merge vertices into the first vertex within tolerance
+        let cell = |c: Coord| {
+            (
+                (c.x / tolerance).floor() as i64,
+                (c.y / tolerance).floor() as i64,
+            )
+        };
+        let mut grid: HashMap<(i64, i64), Vec<Coord>> = HashMap::new();
+        for coord in rings.iter_mut().flatten().flatten().flatten() {
+            let (cx, cy) = cell(*coord);
+            let nearest = (cx - 1..=cx + 1)
+                .flat_map(|x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+                .filter_map(|key| grid.get(&key))
+                .flatten()
+                .filter(|c| distance(**c, *coord) <= tolerance)
+                .min_by(|a, b| distance(**a, *coord).total_cmp(&distance(**b, *coord)))
+                .copied();
+            match nearest {
+                Some(target) => *coord = target,
+                None => grid.entry((cx, cy)).or_default().push(*coord),
+            }
+        }
+
+        // Move vertices onto nearby edges of other polygons
+        let rects: Vec<Option<Rect>> = self.polygons.iter().map(|p| p.bounding_rect()).collect();
+        let mut moves = Vec::new();
+        let mut insertions: HashMap<RingSegment, Vec<(f64, Coord)>> = HashMap::new();
+        for (f, polygons) in rings.iter().enumerate() {
+            for (p, polygon) in polygons.iter().enumerate() {
+                for (r, ring) in polygon.iter().enumerate() {
+                    for (k, &v) in ring.iter().enumerate() {
+                        let point_rect = Some(Rect::new(v, v));
+                        let mut best: Option<(f64, RingSegment, f64, Coord)> = None;
+                        for (g, other) in rings.iter().enumerate() {
+                            if g == f || !rects_intersect(rects[g], point_rect, tolerance) {
+                                continue;
+                            }
+                            for (q, other_polygon) in other.iter().enumerate() {
+                                for (s, other_ring) in other_polygon.iter().enumerate() {
+                                    for i in 0..other_ring.len() {
+                                        let (a, b) =
+                                            (other_ring[i], other_ring[(i + 1) % other_ring.len()]);
+                                        if v == a || v == b {
+                                            continue;
+                                        }
+                                        let (t, projected) = project(v, a, b);
+                                        let d = distance(v, projected);
+                                        if t > 0.0
+                                            && t < 1.0
+                                            && d <= tolerance
+                                            && best.is_none_or(|x| d < x.0)
+                                        {
+                                            best = Some((d, (g, q, s, i), t, projected));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if let Some((_, segment, t, projected)) = best {
+                            moves.push(((f, p, r, k), projected));
+                            insertions.entry(segment).or_default().push((t, projected));
+                        }
+                    }
+                }
+            }
+        }
+        for ((f, p, r, k), coord) in moves {
+            rings[f][p][r][k] = coord;
+        }
+        for (f, polygons) in rings.iter_mut().enumerate() {
+            for (p, polygon) in polygons.iter_mut().enumerate() {
+                for (r, ring) in polygon.iter_mut().enumerate() {
+                    let mut snapped = Vec::with_capacity(ring.len());
+                    for (i, &coord) in ring.iter().enumerate() {
+                        snapped.push(coord);
+                        if let Some(points) = insertions.get_mut(&(f, p, r, i)) {
+                            points.sort_by(|a, b| a.0.total_cmp(&b.0));
+                            snapped.extend(points.iter().map(|(_, c)| *c));
+                        }
+                    }
+                    *ring = snapped;
+                }
+            }
+        }
+
+        Coverage::new(assemble(rings))
+    }
+
+    /// Remove overlaps by subtracting each polygon's predecessors from it
+    ///
+    /// Overlapping areas stay with the polygon that comes first.
+    pub fn remove_overlaps(&self) -> Coverage {
+        let mut result: Vec<MultiPolygon> = Vec::with_capacity(self.polygons.len());
+        for polygons in &self.polygons {
+            let rect = polygons.bounding_rect();
+            let mut remaining = polygons.clone();
+            for earlier in &result {
+                if rects_intersect(rect, earlier.bounding_rect(), 0.0) {
+                    remaining = remaining.difference(earlier);
+                }
+            }
+            result.push(remaining);
+        }
+        Coverage::new(result)
+    }
+
+    /// Merge gaps up to `max_area` into the neighbor sharing most of their boundary
+    pub fn fill_gaps(&self, max_area: f64) -> Coverage {
+        let union = self.union();
+        let epsilon = union.bounding_rect().map_or(0.0, |r| {
+            1e-9 * (1.0
+                + r.max()
+                    .x
+                    .abs()
+                    .max(r.max().y.abs())
+                    .max(r.min().x.abs())
+                    .max(r.min().y.abs()))
+        });
+        let segments: Vec<Vec<(Coord, Coord)>> = self
+            .polygons
+            .iter()
+            .map(|p| boundary_segments(std::slice::from_ref(p)))
+            .collect();
+
+        let mut result = self.polygons.clone();
+        for gap in gap_polygons(&union).filter(|gap| gap.unsigned_area() <= max_area) {
+            let gap_segments = boundary_segments(&[MultiPolygon::new(vec![gap.clone()])]);
+            let shared = |targets: &[(Coord, Coord)]| -> f64 {
+                gap_segments
+                    .iter()
+                    .map(|&(a, b)| {
+                        let uncovered: f64 = uncovered_intervals(a, b, targets, epsilon)
+                            .iter()
+                            .map(|(t0, t1)| t1 - t0)
+                            .sum();
+                        (1.0 - uncovered) * distance(a, b)
+                    })
+                    .sum()
+            };
+            let best = segments
+                .iter()
+                .map(|targets| shared(targets))
+                .enumerate()
+                .filter(|(_, length)| *length > epsilon)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((i, _)) = best {
+                result[i] = result[i].union(&MultiPolygon::new(vec![gap]));
+            }
+        }
+        Coverage::new(result)
+    }
+
+    /// Simplify the coverage without opening gaps or overlaps between neighbors
+    ///
+    /// Borders are split into edges at vertices where three or more polygons
+    /// meet. Each edge is simplified once with Douglas-Peucker and the result
+    /// used by every polygon sharing it. Neighbors must share their border
+    /// vertices; use [`Coverage::snap`] first if they do not.
+    pub fn simplify(&self, tolerance: f64) -> Result<Coverage> {
+        if tolerance < 0.0 {
+            return Err(AnalysisError::invalid_parameters(
+                "Tolerance must be non-negative",
+            ));
+        }
+        let mut rings = explode(&self.polygons);
+        let all: Vec<&Vec<Coord>> = rings
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|r| r.len() >= 3)
+            .collect();
+
+        // Edge owners and vertex neighbors
+        let mut owners: HashMap<(Key, Key), Vec<usize>> = HashMap::new();
+        let mut neighbors: HashMap<Key, HashSet<Key>> = HashMap::new();
+        for (id, ring) in all.iter().enumerate() {
+            for i in 0..ring.len() {
+                let (a, b) = (key(ring[i]), key(ring[(i + 1) % ring.len()]));
+                owners.entry(edge_key(a, b)).or_default().push(id);
+                neighbors.entry(a).or_default().insert(b);
+                neighbors.entry(b).or_default().insert(a);
+            }
+        }
+        for ids in owners.values_mut() {
+            ids.sort_unstable();
+        }
+
+        // Nodes, where the polygons sharing the border change
+        let mut nodes: HashSet<Key> = HashSet::new();
+        for ring in &all {
+            let n = ring.len();
+            for i in 0..n {
+                let (prev, here, next) = (
+                    key(ring[(i + n - 1) % n]),
+                    key(ring[i]),
+                    key(ring[(i + 1) % n]),
+                );
+                if neighbors[&here].len() != 2
+                    || owners[&edge_key(prev, here)] != owners[&edge_key(here, next)]
+                {
+                    nodes.insert(here);
+                }
+            }
+        }
+        // Rings need three nodes to stay polygons when their edges collapse
+        for ring in &all {
+            let mut ring_nodes: Vec<Coord> = ring
+                .iter()
+                .filter(|c| nodes.contains(&key(**c)))
+                .copied()
+                .collect();
+            while ring_nodes.len() < 3 {
+                let clearance = |c: Coord| {
+                    ring_nodes
+                        .iter()
+                        .map(|n| distance(*n, c))
+                        .fold(f64::INFINITY, f64::min)
+                };
+                let farthest = ring
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| clearance(*a).total_cmp(&clearance(*b)));
+                match farthest {
+                    Some(c) if clearance(c) > 0.0 => {
+                        nodes.insert(key(c));
+                        ring_nodes.push(c);
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        let mut edges: HashMap<Vec<Key>, Vec<Coord>> = HashMap::new();
+        for ring in rings.iter_mut().flatten().flatten() {
+            if ring.len() < 3 {
+                continue;
+            }
+            let start = ring
+                .iter()
+                .position(|c| nodes.contains(&key(*c)))
+                .unwrap_or(0);
+            ring.rotate_left(start);
+            ring.push(ring[0]);
+
+            let mut simplified = vec![ring[0]];
+            let mut from = 0;
+            for to in 1..ring.len() {
+                if !nodes.contains(&key(ring[to])) {
+                    continue;
+                }
+                let edge = &ring[from..=to];
+                let keys: Vec<Key> = edge.iter().map(|c| key(*c)).collect();
+                let reversed: Vec<Key> = keys.iter().rev().copied().collect();
+                let forward = keys <= reversed;
+                let cached = edges
+                    .entry(if forward { keys } else { reversed })
+                    .or_insert_with(|| {
+                        let mut coords = edge.to_vec();
+                        if !forward {
+                            coords.reverse();
+                        }
+                        LineString::from(coords).simplify(&tolerance).0
+                    });
+                if forward {
+                    simplified.extend(cached.iter().skip(1));
+                } else {
+                    simplified.extend(cached.iter().rev().skip(1));
+                }
+                from = to;
+            }
+            simplified.pop();
+            *ring = simplified;
+        }
+
+        Ok(Coverage::new(assemble(rings)))
+    }
+
+    fn rects(&self) -> Vec<Option<Rect>> {
+        self.polygons.iter().map(|p| p.bounding_rect()).collect()
+    }
+}
+
+/// Feature, polygon, ring and vertex index of a ring segment's start
+type RingSegment = (usize, usize, usize, usize);
+
+/// Exact coordinate identity, with -0.0 equal to 0.0
+type Key = (u64, u64);
+
+fn key(c: Coord) -> Key {
+    ((c.x + 0.0).to_bits(), (c.y + 0.0).to_bits())
+}
+
+fn edge_key(a: Key, b: Key) -> (Key, Key) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Open rings without repeated vertices, per feature and polygon
+fn explode(polygons: &[MultiPolygon]) -> Vec<Vec<Vec<Vec<Coord>>>> {
+    polygons
+        .iter()
+        .map(|multi| {
+            multi
+                .0
+                .iter()
+                .map(|polygon| {
+                    std::iter::once(polygon.exterior())
+                        .chain(polygon.interiors())
+                        .map(|ring| {
+                            let mut coords = ring.0.clone();
+                            coords.dedup();
+                            if coords.len() > 1 && coords.first() == coords.last() {
+                                coords.pop();
+                            }
+                            coords
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Close open rings into polygons, dropping collapsed rings
+fn assemble(rings: Vec<Vec<Vec<Vec<Coord>>>>) -> Vec<MultiPolygon> {
+    rings
+        .into_iter()
+        .map(|polygons| {
+            MultiPolygon::new(
+                polygons
+                    .into_iter()
+                    .filter_map(|polygon| {
+                        let mut rings = polygon.into_iter().filter_map(|mut ring| {
+                            ring.dedup();
+                            if ring.len() > 1 && ring.first() == ring.last() {
+                                ring.pop();
+                            }
+                            (ring.len() >= 3).then(|| {
+                                ring.push(ring[0]);
+                                LineString::from(ring)
+                            })
+                        });
+                        let exterior = rings.next()?;
+                        Some(Polygon::new(exterior, rings.collect()))
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+fn gap_polygons(union: &MultiPolygon) -> impl Iterator<Item = Polygon> + '_ {
+    union
+        .0
+        .iter()
+        .flat_map(|polygon| polygon.interiors())
+        .map(|ring| Polygon::new(ring.clone(), vec![]))
+}
+
+fn boundary_segments(polygons: &[MultiPolygon]) -> Vec<(Coord, Coord)> {
+    polygons
+        .iter()
+        .flat_map(|multi| &multi.0)
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+        .flat_map(|ring| ring.lines().map(|line| (line.start, line.end)))
+        .filter(|(a, b)| a != b)
+        .collect()
+}
+
+/// Parameter ranges of segment `a`-`b` not within `tolerance` of a collinear target segment
+fn uncovered_intervals(
+    a: Coord,
+    b: Coord,
+    targets: &[(Coord, Coord)],
+    tolerance: f64,
+) -> Vec<(f64, f64)> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 == 0.0 {
+        return vec![];
+    }
+    let len = len2.sqrt();
+    let offset = |p: Coord| ((p.x - a.x) * dy - (p.y - a.y) * dx).abs() / len;
+    let param = |p: Coord| ((p.x - a.x) * dx + (p.y - a.y) * dy) / len2;
+    let (min_x, max_x) = (a.x.min(b.x) - tolerance, a.x.max(b.x) + tolerance);
+    let (min_y, max_y) = (a.y.min(b.y) - tolerance, a.y.max(b.y) + tolerance);
+
+    let mut covered: Vec<(f64, f64)> = targets
+        .iter()
+        .filter(|(c, d)| {
+            c.x.max(d.x) >= min_x
+                && c.x.min(d.x) <= max_x
+                && c.y.max(d.y) >= min_y
+                && c.y.min(d.y) <= max_y
+        })
+        .filter(|(c, d)| offset(*c) <= tolerance && offset(*d) <= tolerance)
+        .map(|(c, d)| {
+            let (t0, t1) = (param(*c), param(*d));
+            (t0.min(t1).max(0.0), t0.max(t1).min(1.0))
+        })
+        .filter(|(start, end)| end > start)
+        .collect();
+    covered.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut gaps = Vec::new();
+    let mut cursor = 0.0;
+    for (start, end) in covered {
+        if start > cursor {
+            gaps.push((cursor, start));
+        }
+        cursor = f64::max(cursor, end);
+    }
+    if cursor < 1.0 {
+        gaps.push((cursor, 1.0));
+    }
+    gaps
+}
+
+fn rects_intersect(a: Option<Rect>, b: Option<Rect>, padding: f64) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.min().x - padding <= b.max().x
+                && b.min().x - padding <= a.max().x
+                && a.min().y - padding <= b.max().y
+                && b.min().y - padding <= a.max().y
+        }
+        _ => false,
+    }
+}
+
+/// Parameter and closest point of `p` on segment `a`-`b`
+fn project(p: Coord, a: Coord, b: Coord) -> (f64, Coord) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 == 0.0 {
+        return (0.0, a);
+    }
+    let t = ((p.x - a.x) * dx + (p.y - a.y) * dy) / len2;
+    (t, lerp(a, b, t.clamp(0.0, 1.0)))
+}
+
+fn lerp(a: Coord, b: Coord, t: f64) -> Coord {
+    Coord {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+    }
+}
+
+fn distance(a: Coord, b: Coord) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn line_length(line: &LineString) -> f64 {
+    line.lines().map(|l| distance(l.start, l.end)).sum()
+}
+
+fn to_core_geometry(geometry: &Geometry, crs: &Crs) -> Result<CoreGeometry> {
+    Ok(match geometry {
+        Geometry::Polygon(polygon) => CoreGeometry::Polygon(CorePolygon::new(
+            polygon.exterior().clone(),
+            polygon.interiors().to_vec(),
+            crs.clone(),
+        )),
+        Geometry::MultiPolygon(polygons) if polygons.0.len() == 1 => {
+            return to_core_geometry(&Geometry::Polygon(polygons.0[0].clone()), crs);
+        }
+        Geometry::MultiPolygon(polygons) => {
+            CoreGeometry::MultiPolygon(CoreMultiPolygon::new(polygons.0.clone(), crs.clone()))
+        }
+        Geometry::MultiLineString(lines) if lines.0.len() == 1 => {
+            CoreGeometry::LineString(CoreLineString::new(lines.0[0].0.clone(), crs.clone()))
+        }
+        Geometry::MultiLineString(lines) => {
+            CoreGeometry::MultiLineString(CoreMultiLineString::new(lines.0.clone(), crs.clone()))
+        }
+        _ => {
+            return Err(AnalysisError::invalid_geometry(
+                "Topology violations must be polygons or lines",
+            ))
+        }
+    })
+}
+
+/// Feature indices of a violation feature written by [`TopologyReport::to_layer`]
+pub fn violation_features(feature: &Feature) -> Vec<usize> {
+    feature
+        .get_property("features")
+        .and_then(Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(Value::as_u64)
+                .map(|id| id as usize)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn square(x: f64, y: f64, size: f64) -> MultiPolygon {
+        MultiPolygon::new(vec![Polygon::new(
+            LineString::from(vec![
+                (x, y),
+                (x + size, y),
+                (x + size, y + size),
+                (x, y + size),
+                (x, y),
+            ]),
+            vec![],
+        )])
+    }
+
+    fn layer(polygons: &[MultiPolygon]) -> Layer {
+        let features = polygons
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let mut feature = Feature::new(CoreGeometry::MultiPolygon(CoreMultiPolygon::new(
+                    p.0.clone(),
+                    Crs::wgs84(),
+                )));
+                feature.set_property("parcel", json!(i));
+                feature
+            })
+            .collect();
+        Layer::from_features("parcels", Crs::wgs84(), features)
+    }
+
+    #[test]
+    fn test_overlaps_and_gaps() {
+        // A 3x3 grid with the middle cell missing and one cell pushed into its neighbor
+        let mut cells: Vec<MultiPolygon> = (0..9)
+            .filter(|i| *i != 4)
+            .map(|i| square((i % 3) as f64 * 10.0, (i / 3) as f64 * 10.0, 10.0))
+            .collect();
+        cells[0] = square(0.0, 0.0, 11.0);
+        let parcels = layer(&cells);
+
+        let report = validate_topology(
+            &parcels,
+            &[TopologyRule::MustNotOverlap, TopologyRule::MustNotHaveGaps],
+            0.01,
+        )
+        .unwrap();
+        let overlaps: Vec<_> = report.violations_of(RuleType::MustNotOverlap).collect();
+        assert_eq!(overlaps.len(), 2);
+        assert_eq!(overlaps[0].features, vec![0, 1]);
+        assert_relative_eq!(overlaps[0].geometry.unsigned_area(), 10.0, epsilon = 1e-9);
+
+        let gaps: Vec<_> = report.violations_of(RuleType::MustNotHaveGaps).collect();
+        assert_eq!(gaps.len(), 1);
+        assert_relative_eq!(gaps[0].geometry.unsigned_area(), 99.0, epsilon = 1e-9);
+
+        let errors = report.to_layer("errors", Crs::wgs84()).unwrap();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(violation_features(errors.get(0).unwrap()), vec![0, 1]);
+        assert_eq!(
+            errors.get(2).unwrap().get_property("rule"),
+            Some(&json!("must_not_have_gaps"))
+        );
+
+        let mut invalid = report.clone();
+        invalid.violations[0].geometry = Geometry::Point(geo::Point::new(0.0, 0.0));
+        assert!(invalid.to_layer("errors", Crs::wgs84()).is_err());
+    }
+
+    #[test]
+    fn test_covered_by_and_boundary_alignment() {
+        let blocks = layer(&[square(0.0, 0.0, 20.0)]);
+        let parcels = layer(&[square(0.0, 0.0, 10.0), square(15.0, 15.0, 10.0)]);
+
+        let report = validate_topology(
+            &parcels,
+            &[
+                TopologyRule::MustBeCoveredBy(&blocks),
+                TopologyRule::BoundaryMustAlignWith(&blocks),
+            ],
+            0.01,
+        )
+        .unwrap();
+
+        let uncovered: Vec<_> = report.violations_of(RuleType::MustBeCoveredBy).collect();
+        assert_eq!(uncovered.len(), 1);
+        assert_eq!(uncovered[0].features, vec![1]);
+        assert_relative_eq!(uncovered[0].geometry.unsigned_area(), 75.0, epsilon = 1e-9);
+
+        // The inner sides of the first parcel are not block boundaries
+        let misaligned: Vec<_> = report
+            .violations_of(RuleType::BoundaryMustAlignWith)
+            .collect();
+        assert_eq!(misaligned[0].features, vec![0]);
+        let Geometry::MultiLineString(lines) = &misaligned[0].geometry else {
+            panic!("expected lines");
+        };
+        assert_relative_eq!(lines.0.iter().map(line_length).sum::<f64>(), 20.0);
+    }
+
+    #[test]
+    fn test_snap_closes_slivers() {
+        // The right parcel was digitized slightly apart, with an extra vertex on the border
+        let left = square(0.0, 0.0, 10.0);
+        let right = MultiPolygon::new(vec![Polygon::new(
+            LineString::from(vec![
+                (10.05, 0.0),
+                (20.0, 0.0),
+                (20.0, 10.0),
+                (10.05, 10.0),
+                (10.03, 5.0),
+                (10.05, 0.0),
+            ]),
+            vec![],
+        )]);
+        let coverage = Coverage::new(vec![left, right]);
+        assert_eq!(coverage.union().0.len(), 2);
+
+        let snapped = coverage.snap(0.1);
+        assert!(snapped.overlaps(1e-9).is_empty());
+        assert_eq!(snapped.union().0.len(), 1);
+        assert_relative_eq!(snapped.union().unsigned_area(), 200.0, epsilon = 1e-9);
+        // The T-junction vertex was inserted into the left parcel
+        assert!(snapped.polygons[0].0[0]
+            .exterior()
+            .0
+            .contains(&Coord { x: 10.0, y: 5.0 }));
+    }
+
+    #[test]
+    fn test_remove_overlaps_and_fill_gaps() {
+        let coverage = Coverage::new(vec![
+            square(0.0, 0.0, 10.0),
+            square(8.0, 0.0, 10.0),
+            square(0.0, 10.0, 20.0),
+        ]);
+        let fixed = coverage.remove_overlaps();
+        assert!(fixed.overlaps(1e-9).is_empty());
+        assert_relative_eq!(fixed.polygons[1].unsigned_area(), 80.0, epsilon = 1e-9);
+
+        let holed = Coverage::new(vec![
+            MultiPolygon::new(vec![Polygon::new(
+                LineString::from(vec![
+                    (0.0, 0.0),
+                    (10.0, 0.0),
+                    (10.0, 10.0),
+                    (0.0, 10.0),
+                    (0.0, 0.0),
+                ]),
+                vec![LineString::from(vec![
+                    (2.0, 2.0),
+                    (2.0, 4.0),
+                    (4.0, 4.0),
+                    (4.0, 2.0),
+                    (2.0, 2.0),
+                ])],
+            )]),
+            square(10.0, 0.0, 10.0),
+        ]);
+        assert_eq!(holed.gaps(0.0).len(), 1);
+        let filled = holed.fill_gaps(5.0);
+        assert!(filled.gaps(0.0).is_empty());
+        assert_relative_eq!(filled.polygons[0].unsigned_area(), 100.0, epsilon = 1e-9);
+        assert_eq!(holed.fill_gaps(1.0), holed);
+    }
+
+    #[test]
+    fn test_coverage_simplification() {
+        // Two parcels sharing a zigzag border
+        let border: Vec<(f64, f64)> = (0..=10)
+            .map(|i| (10.0 + if i % 2 == 0 { 0.0 } else { 0.1 }, i as f64))
+            .collect();
+        let mut left = vec![(0.0, 0.0)];
+        left.extend(border.iter().copied());
+        left.extend([(0.0, 10.0), (0.0, 0.0)]);
+        let mut right: Vec<(f64, f64)> = border.iter().rev().copied().collect();
+        right.extend([(20.0, 0.0), (20.0, 10.0)]);
+        right.rotate_right(2);
+        right.push(right[0]);
+        let coverage = Coverage::new(vec![
+            MultiPolygon::new(vec![Polygon::new(LineString::from(left), vec![])]),
+            MultiPolygon::new(vec![Polygon::new(LineString::from(right), vec![])]),
+        ]);
+        assert!(coverage.gaps(0.0).is_empty() && coverage.overlaps(1e-9).is_empty());
+
+        let simplified = coverage.simplify(0.5).unwrap();
+        assert_eq!(simplified.polygons[0].0[0].exterior().0.len(), 5);
+        assert!(simplified.overlaps(1e-9).is_empty());
+        assert_eq!(simplified.union().0.len(), 1);
+        assert!(simplified.gaps(0.0).is_empty());
+        assert_relative_eq!(simplified.union().unsigned_area(), 200.0, epsilon = 1e-9);
+
+        let parcels = layer(&coverage.polygons);
+        let output = simplified.to_layer(&parcels).unwrap();
+        assert_eq!(
+            output.get(1).unwrap().get_property("parcel"),
+            Some(&json!(1))
+        );
+        assert!(Coverage::from_layer(&output).is_ok());
+    }
+}
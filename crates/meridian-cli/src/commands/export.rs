@@ -1,9 +1,16 @@
 use clap::Parser;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use meridian_db::{FeatureFilter, FeatureQuery, FeatureRepository, LayerRepository, Pool};
+use meridian_io::shapefile::ShapefileWriter;
+use meridian_io::{Feature, FeatureCollection, Format, FormatRegistry, LayerOptions};
+use meridian_server::ogc::{cql2, geojson};
 use tracing::info;
 
-use super::utils::{create_progress_bar, success, info as print_info, TRUCK};
+use super::utils::{create_progress_bar, success, warning, info as print_info, TRUCK};
+
+/// Features fetched from the database per query
+const PAGE_SIZE: u64 = 1000;
 
 #[derive(Parser)]
 pub struct ExportArgs {
@@ -27,7 +34,7 @@ pub struct ExportArgs {
     #[arg(long)]
     pub target_srid: Option<u32>,
 
-    /// SQL WHERE clause to filter features
+    /// Filter expression (CQL2 text, e.g. "population > 1000 AND name LIKE 'A%'")
     #[arg(long)]
     pub filter: Option<String>,
 
@@ -39,6 +46,14 @@ pub struct ExportArgs {
     #[arg(long)]
     pub overwrite: bool,
 
+    /// Append to an existing output file; for GeoPackage this adds the layer to the file
+    #[arg(long, conflicts_with = "overwrite")]
+    pub append: bool,
+
+    /// Layer creation option (KEY=VALUE, format-dependent), e.g. --lco SPATIAL_INDEX=NO
+    #[arg(long = "lco", value_name = "KEY=VALUE")]
+    pub layer_options: Vec<String>,
+
     /// Compression level (0-9, format-dependent)
     #[arg(long)]
     pub compression: Option<u8>,
@@ -47,12 +62,29 @@ pub struct ExportArgs {
 pub async fn execute(args: ExportArgs) -> Result<()> {
     print_info(&format!("{}Exporting layer '{}' to: {}", TRUCK, args.layer, args.output.display()));
 
+    let format = parse_format(&args.format)?;
+    if !FormatRegistry::supports_writing(format) {
+        return Err(anyhow::anyhow!("Writing {} files is not supported", args.format));
+    }
+
+    let mut options = LayerOptions::parse(args.layer_options.iter().map(String::as_str))?;
+    if format == Format::GeoPackage && options.get("LAYER_NAME").is_none() {
+        options.set("LAYER_NAME", args.layer.as_str());
+    }
+
+    // Reject invalid options before querying the database
+    FormatRegistry::get_writer_with_options(format, &options)?;
+
     // Check if output file exists
-    if args.output.exists() && !args.overwrite {
+    if args.output.exists() && !args.overwrite && !args.append {
         return Err(anyhow::anyhow!(
-            "Output file already exists. Use --overwrite to replace it."
+            "Output file already exists. Use --overwrite to replace it or --append to add to it."
         ));
     }
+    if args.output.exists() && args.overwrite {
+        std::fs::remove_file(&args.output)
+            .context("Failed to remove existing output file")?;
+    }
 
     // Ensure output directory exists
     if let Some(parent) = args.output.parent() {
@@ -60,38 +92,137 @@ pub async fn execute(args: ExportArgs) -> Result<()> {
             .context("Failed to create output directory")?;
     }
 
-    info!("Export format: {}", args.format);
-    print_info(&format!("Format: {}", args.format));
-
-    // Display filter if specified
-    if let Some(ref filter) = args.filter {
-        print_info(&format!("Filter: {}", filter));
-    }
-
-    // Display target SRID if specified
-    if let Some(srid) = args.target_srid {
+    info!("Export format: {:?}", format);
+    print_info(&format!("Format: {:?}", format));
+
+    let filter = match args.filter {
+        Some(ref filter) => {
+            print_info(&format!("Filter: {}", filter));
+            cql2::parse_text(filter, cql2::FilterCrs::default())
+                .map_err(|e| anyhow::anyhow!("Invalid filter: {}", e))?
+        }
+        None => FeatureFilter::Include,
+    };
+
+    let columns: Option<Vec<String>> = args.columns.as_ref().map(|columns| {
+        columns.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+    });
+
+    let pool = Pool::from_url(&args.connection)
+        .await
+        .context("Failed to connect to database")?;
+    let layer = LayerRepository::new(&pool)
+        .find_by_name(&args.layer)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Layer not found: {}", args.layer))?;
+
+    let srid = args.target_srid.map(|srid| srid as i32).unwrap_or(layer.srid);
+    if args.target_srid.is_some() {
         print_info(&format!("Target SRID: {}", srid));
     }
 
     // Create progress bar
-    let pb = create_progress_bar(100, "Exporting features");
-
-    // TODO: Implement actual export logic
-    // This would involve:
-    // 1. Connecting to the database
-    // 2. Querying features from the layer (with optional filter)
-    // 3. Transforming coordinates if target_srid is specified
-    // 4. Writing features to output file based on format
-    // 5. Applying compression if specified
-
-    // Simulated progress
-    for i in 0..100 {
-        pb.set_position(i);
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+    let pb = create_progress_bar(0, "Exporting features");
+
+    let repository = FeatureRepository::new(&pool);
+    let mut query = FeatureQuery::new(filter);
+    query.output_srid = srid;
+
+    let mut features = Vec::new();
+    loop {
+        let page = repository
+            .query(layer.id, &query.clone().with_paging(features.len() as u64, Some(PAGE_SIZE)))
+            .await
+            .context("Failed to query features")?;
+        pb.set_length(page.number_matched);
+
+        let fetched = page.features.len() as u64;
+        for feature in page.features {
+            features.push(convert_feature(feature, columns.as_deref())?);
+        }
+        pb.set_position(features.len() as u64);
+
+        if fetched < PAGE_SIZE || features.len() as u64 >= page.number_matched {
+            break;
+        }
     }
 
+    let count = features.len();
+    let collection = FeatureCollection {
+        features,
+        crs: Some(format!("EPSG:{}", srid)),
+        bbox: None,
+    };
+    write_collection(&args.output, format, &collection, &options, args.append)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
     pb.finish_with_message("Export complete");
-    success(&format!("Successfully exported to {}", args.output.display()));
+    success(&format!("Successfully exported {} features to {}", count, args.output.display()));
 
     Ok(())
 }
+
+/// Parse a format name or file extension
+fn parse_format(name: &str) -> Result<Format> {
+    let format = match name.to_lowercase().as_str() {
+        "shapefile" | "esri shapefile" => Some(Format::Shapefile),
+        "geopackage" => Some(Format::GeoPackage),
+        other => Format::from_extension(other)
+            .or_else(|| FormatRegistry::driver_by_name(other).and_then(|driver| driver.format())),
+    };
+
+    format.ok_or_else(|| anyhow::anyhow!("Unknown format: {}", name))
+}
+
+/// Convert a database feature, keeping only the selected columns
+fn convert_feature(feature: meridian_db::Feature, columns: Option<&[String]>) -> Result<Feature> {
+    let geometry = feature
+        .geometry_json
+        .as_ref()
+        .map(geojson::to_geometry)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid geometry for feature {}: {}", feature.id, e))?;
+
+    let mut converted = Feature::new(geometry).with_id(feature.id.to_string());
+    if let serde_json::Value::Object(properties) = feature.properties {
+        converted.properties = properties
+            .into_iter()
+            .filter(|(key, _)| columns.is_none_or(|columns| columns.contains(key)))
+            .collect();
+    }
+
+    Ok(converted)
+}
+
+/// Write features in a format, appending when requested
+fn write_collection(
+    path: &Path,
+    format: Format,
+    collection: &FeatureCollection,
+    options: &LayerOptions,
+    append: bool,
+) -> meridian_io::Result<()> {
+    if format == Format::Shapefile {
+        // Written directly to report split files and renamed fields
+        let writer = ShapefileWriter::from_options(options)?;
+        let report = if append {
+            writer.append_with_report(path, collection)?
+        } else {
+            writer.write_with_report(path, collection)?
+        };
+        for (property, field) in &report.renamed_fields {
+            warning(&format!("Property '{}' written as field '{}'", property, field));
+        }
+        for file in report.files.iter().filter(|file| file.as_path() != path) {
+            print_info(&format!("Wrote {}", file.display()));
+        }
+        return Ok(());
+    }
+
+    let writer = FormatRegistry::get_writer_with_options(format, options)?;
+    if append {
+        writer.append(path, collection)
+    } else {
+        writer.write(path, collection)
+    }
+}
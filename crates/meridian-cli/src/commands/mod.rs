@@ -3,6 +3,7 @@ pub mod export;
 pub mod query;
 pub mod serve;
 pub mod analyze;
+pub mod raster;
 pub mod db;
 
 use std::path::PathBuf;
@@ -0,0 +1,87 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use anyhow::{Context, Result};
+use meridian_imagery::indices::expression::{BandMathJob, ExpressionInput, RasterExpression};
+
+use super::utils::{create_spinner, success, info as print_info, SPARKLE};
+
+#[derive(Parser)]
+pub struct RasterArgs {
+    #[command(subcommand)]
+    pub operation: RasterOperation,
+}
+
+#[derive(Subcommand)]
+pub enum RasterOperation {
+    /// Evaluate a band math expression, e.g. "(B8 - B4) / (B8 + B4)"
+    Calc(CalcArgs),
+}
+
+#[derive(Parser)]
+pub struct CalcArgs {
+    /// Expression to evaluate
+    #[arg(short, long)]
+    pub expression: String,
+
+    /// Input raster as name=path, or a path named after its file stem (repeatable)
+    #[arg(short, long = "input", required = true)]
+    pub inputs: Vec<String>,
+
+    /// Output Cloud Optimized GeoTIFF
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Name of the output band (defaults to the expression)
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Only parse and type-check the expression
+    #[arg(long)]
+    pub check: bool,
+}
+
+pub async fn execute(args: RasterArgs) -> Result<()> {
+    match args.operation {
+        RasterOperation::Calc(calc_args) => execute_calc(calc_args).await,
+    }
+}
+
+async fn execute_calc(args: CalcArgs) -> Result<()> {
+    let mut expression = RasterExpression::parse(&args.expression)?;
+    if let Some(name) = args.name {
+        expression = expression.with_name(name);
+    }
+    if args.check {
+        success(&format!("Expression is valid ({} result)", expression.value_type()));
+        return Ok(());
+    }
+
+    let inputs = args
+        .inputs
+        .iter()
+        .map(|input| input.parse::<ExpressionInput>())
+        .collect::<Result<Vec<_>, _>>()?;
+    print_info(&format!("{}Evaluating '{}' over {} inputs", SPARKLE, expression.source(), inputs.len()));
+
+    let job = BandMathJob {
+        expression,
+        inputs,
+        output: args.output,
+    };
+    let output = job.output.clone();
+
+    let spinner = create_spinner("Evaluating expression");
+    let metadata = tokio::task::spawn_blocking(move || job.run())
+        .await
+        .context("Band math task panicked")??;
+    spinner.finish_and_clear();
+
+    success(&format!(
+        "Wrote {}x{} raster to: {}",
+        metadata.width,
+        metadata.height,
+        output.display()
+    ));
+
+    Ok(())
+}
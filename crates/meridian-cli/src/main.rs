@@ -37,6 +37,9 @@ enum Commands {
     /// Perform spatial analysis operations
     Analyze(commands::analyze::AnalyzeArgs),
 
+    /// Raster processing operations
+    Raster(commands::raster::RasterArgs),
+
     /// Database management commands
     Db(commands::db::DbArgs),
 }
@@ -69,6 +72,7 @@ async fn main() -> anyhow::Result<()> {
         Commands::Query(args) => commands::query::execute(args).await,
         Commands::Serve(args) => commands::serve::execute(args).await,
         Commands::Analyze(args) => commands::analyze::execute(args).await,
+        Commands::Raster(args) => commands::raster::execute(args).await,
         Commands::Db(args) => commands::db::execute(args).await,
     }
 }
@@ -5,6 +5,8 @@
 use crate::error::{CompressionError, Result};
 use crate::stats::CompressionStats;
 use crate::{CompressionAlgorithm, Compressor};
+use crate::geometry::{detect_coordinate_dimensions, GeometryCodec, GeometryCodecConfig};
+use crate::gorilla::GorillaCodec;
 use crate::{
     lz4::Lz4Compressor,
     zstd::ZstdCompressor,
@@ -43,6 +45,11 @@ pub enum DataType {
     Compressed,
     /// Multimedia data
     Multimedia,
+    /// Raw little-endian `f64` coordinate arrays
+    Coordinates {
+        /// Values per coordinate
+        dimensions: u8,
+    },
     /// Unknown type
     Unknown,
 }
@@ -145,6 +152,12 @@ impl DataCharacteristics {
             return DataType::Multimedia;
         }
 
+        if let Some(dimensions) = detect_coordinate_dimensions(data) {
+            return DataType::Coordinates {
+                dimensions: dimensions as u8,
+            };
+        }
+
         DataType::Binary
     }
 
@@ -194,6 +207,8 @@ pub struct AdaptiveCompressor {
     benchmark_timeout_ms: u64,
     /// Minimum data size for adaptive selection
     min_adaptive_size: usize,
+    /// Geometry codec settings; coordinates are only quantized when set
+    geometry: Option<GeometryCodecConfig>,
 }
 
 impl Default for AdaptiveCompressor {
@@ -209,6 +224,7 @@ impl AdaptiveCompressor {
             enable_benchmarking: false,
             benchmark_timeout_ms: 100,
             min_adaptive_size: 1024, // 1 KB
+            geometry: None,
         }
     }
 
@@ -218,11 +234,26 @@ impl AdaptiveCompressor {
             enable_benchmarking: true,
             benchmark_timeout_ms,
             min_adaptive_size: 1024,
+            geometry: None,
         }
     }
 
+    /// Enable the lossy geometry codec for detected coordinate arrays
+    ///
+    /// The dimension count of the configuration is replaced by the detected
+    /// one; the precision of the first dimension fills any new dimensions.
+    pub fn with_geometry_codec(mut self, config: GeometryCodecConfig) -> Self {
+        self.geometry = Some(config);
+        self
+    }
+
     /// Select best algorithm based on data characteristics
     pub fn select_algorithm(&self, characteristics: &DataCharacteristics) -> CompressionAlgorithm {
+        // Coordinates gain far more from quantization than from byte codecs
+        if matches!(characteristics.data_type, DataType::Coordinates { .. }) && self.geometry.is_some() {
+            return CompressionAlgorithm::Geometry;
+        }
+
         // For very small data, use fast algorithm
         if characteristics.size < self.min_adaptive_size {
             return CompressionAlgorithm::Snappy;
@@ -276,7 +307,7 @@ impl AdaptiveCompressor {
         let characteristics = DataCharacteristics::analyze(data);
         let algorithm = self.select_algorithm(&characteristics);
 
-        self.compress_with_algorithm(data, algorithm)
+        self.compress_with_algorithm(data, algorithm, &characteristics)
     }
 
     /// Compress with specific algorithm
@@ -284,6 +315,7 @@ impl AdaptiveCompressor {
         &self,
         data: &[u8],
         algorithm: CompressionAlgorithm,
+        characteristics: &DataCharacteristics,
     ) -> Result<Vec<u8>> {
         match algorithm {
             CompressionAlgorithm::Lz4 => Lz4Compressor::default().compress(data),
@@ -291,17 +323,30 @@ impl AdaptiveCompressor {
             CompressionAlgorithm::Brotli => BrotliCompressor::default().compress(data),
             CompressionAlgorithm::Gzip => GzipCompressor::default().compress(data),
             CompressionAlgorithm::Snappy => SnappyCompressor::default().compress(data),
+            CompressionAlgorithm::Geometry => self.geometry_codec(characteristics).compress(data),
+            CompressionAlgorithm::Gorilla => GorillaCodec::default().compress(data),
             CompressionAlgorithm::Adaptive => {
                 self.compress_with_best_algorithm(data)
             }
         }
     }
 
+    /// Geometry codec matching the detected coordinate layout
+    fn geometry_codec(&self, characteristics: &DataCharacteristics) -> GeometryCodec {
+        let config = self.geometry.clone().unwrap_or_default();
+        match characteristics.data_type {
+            DataType::Coordinates { dimensions } => {
+                GeometryCodec::with_config(config.with_dimensions(dimensions as usize))
+            }
+            _ => GeometryCodec::with_config(config),
+        }
+    }
+
     /// Benchmark multiple algorithms and select best
     pub fn compress_with_benchmark(&self, data: &[u8]) -> Result<AdaptiveCompressionResult> {
         let characteristics = DataCharacteristics::analyze(data);
 
-        let algorithms = match characteristics.priority {
+        let mut algorithms = match characteristics.priority {
             CompressionPriority::Speed => {
                 vec![
                     CompressionAlgorithm::Snappy,
@@ -324,12 +369,16 @@ impl AdaptiveCompressor {
             }
         };
 
+        if matches!(characteristics.data_type, DataType::Coordinates { .. }) && self.geometry.is_some() {
+            algorithms.push(CompressionAlgorithm::Geometry);
+        }
+
         let mut best_result: Option<(CompressionAlgorithm, Vec<u8>, CompressionStats)> = None;
         let mut best_score = 0.0;
 
         for algorithm in algorithms {
             let start = std::time::Instant::now();
-            let compressed = match self.compress_with_algorithm(data, algorithm) {
+            let compressed = match self.compress_with_algorithm(data, algorithm, &characteristics) {
                 Ok(c) => c,
                 Err(_) => continue,
             };
@@ -494,6 +543,39 @@ mod tests {
         assert!(result.stats.is_effective());
     }
 
+    fn coordinate_bytes(points: usize) -> Vec<u8> {
+        (0..points)
+            .flat_map(|i| [13.4050 + i as f64 * 1e-4, 52.5200 + (i as f64 * 0.01).cos() * 1e-3])
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_coordinate_detection() {
+        let chars = DataCharacteristics::analyze(&coordinate_bytes(500));
+        assert_eq!(chars.data_type, DataType::Coordinates { dimensions: 2 });
+
+        let compressor = AdaptiveCompressor::new();
+        assert_ne!(compressor.select_algorithm(&chars), CompressionAlgorithm::Geometry);
+
+        let compressor = AdaptiveCompressor::new().with_geometry_codec(GeometryCodecConfig::default());
+        assert_eq!(compressor.select_algorithm(&chars), CompressionAlgorithm::Geometry);
+    }
+
+    #[test]
+    fn test_adaptive_geometry_compression() {
+        let data = coordinate_bytes(500);
+        let compressor = AdaptiveCompressor::new()
+            .with_geometry_codec(GeometryCodecConfig::new(2, 1e-6));
+
+        let compressed = compressor.compress_with_best_algorithm(&data).unwrap();
+        assert!(compressed.len() * 4 < data.len());
+
+        let restored = crate::CompressionFacade::decompress(&compressed, CompressionAlgorithm::Geometry)
+            .unwrap();
+        assert_eq!(restored.len(), data.len());
+    }
+
     #[test]
     fn test_entropy_calculation() {
         // Uniform data - low entropy
@@ -79,6 +79,14 @@ pub enum CompressionError {
     #[error("Delta compression error: {0}")]
     Delta(String),
 
+    /// Geometry codec error
+    #[error("Geometry codec error: {0}")]
+    Geometry(String),
+
+    /// Time series codec error
+    #[error("Time series codec error: {0}")]
+    TimeSeries(String),
+
     /// Streaming operation error
     #[error("Streaming error: {0}")]
     Streaming(String),
@@ -0,0 +1,603 @@
+//! Gorilla time-series compression
+//!
+//! Lossless compression for regularly sampled GPS tracks and sensor readings,
+//! following Facebook's Gorilla paper. Timestamps are stored as
+//! delta-of-deltas in variable-width buckets, so a steady sampling interval
+//! costs one bit per point. Each value channel stores the XOR with its
+//! previous value, reusing the previous window of meaningful bits when it
+//! fits, so slowly changing readings cost a few bits each.
+//!
+//! # Format
+//!
+//! ```text
+//! magic "MGTS" | version u8 | channels u8 | point count u64 | bit stream
+//! ```
+//!
+//! As a [`Compressor`] the input is fixed-size records of a little-endian
+//! `i64` timestamp followed by one little-endian `f64` per channel.
+
+use crate::error::{CompressionError, Result};
+use crate::stats::{CompressionStats, Timer};
+use crate::Compressor;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Format magic
+const MAGIC: &[u8; 4] = b"MGTS";
+
+/// Format version
+const VERSION: u8 = 1;
+
+/// Header size: magic, version, channels, point count
+const HEADER_SIZE: usize = 4 + 1 + 1 + 8;
+
+/// Maximum value channels per point
+pub const MAX_CHANNELS: usize = 255;
+
+/// Delta-of-delta buckets: control bits, control length, payload bits
+const TIMESTAMP_BUCKETS: [(u64, u32, u32); 3] = [(0b10, 2, 7), (0b110, 3, 9), (0b1110, 4, 12)];
+
+/// Multi-channel time series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeSeries {
+    /// Number of values per point
+    pub channels: usize,
+    /// Timestamp of each point (e.g. milliseconds since the epoch)
+    pub timestamps: Vec<i64>,
+    /// Point values, `channels` per point
+    pub values: Vec<f64>,
+}
+
+impl TimeSeries {
+    /// Create an empty series
+    pub fn new(channels: usize) -> Self {
+        Self {
+            channels,
+            timestamps: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Append a point
+    pub fn push(&mut self, timestamp: i64, values: &[f64]) -> Result<()> {
+        if values.len() != self.channels {
+            return Err(CompressionError::TimeSeries(format!(
+                "Expected {} values per point, got {}",
+                self.channels,
+                values.len()
+            )));
+        }
+
+        self.timestamps.push(timestamp);
+        self.values.extend_from_slice(values);
+        Ok(())
+    }
+
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Check if the series has no points
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Values of one point
+    pub fn point(&self, index: usize) -> Option<(i64, &[f64])> {
+        let timestamp = *self.timestamps.get(index)?;
+        let start = index * self.channels;
+        Some((timestamp, &self.values[start..start + self.channels]))
+    }
+}
+
+/// Gorilla time-series codec
+#[derive(Debug, Clone)]
+pub struct GorillaCodec {
+    /// Value channels per point when used as a [`Compressor`]
+    channels: usize,
+}
+
+impl Default for GorillaCodec {
+    fn default() -> Self {
+        Self { channels: 1 }
+    }
+}
+
+impl GorillaCodec {
+    /// Create a codec for single-channel series
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a codec for records with the given number of channels
+    pub fn with_channels(channels: usize) -> Self {
+        Self { channels }
+    }
+
+    /// Number of value channels per record
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Encode a series
+    pub fn encode(&self, series: &TimeSeries) -> Result<Vec<u8>> {
+        let channels = series.channels;
+        if channels == 0 || channels > MAX_CHANNELS {
+            return Err(CompressionError::TimeSeries(format!(
+                "Channel count must be between 1 and {}, got {}",
+                MAX_CHANNELS, channels
+            )));
+        }
+
+        if series.values.len() != series.timestamps.len() * channels {
+            return Err(CompressionError::TimeSeries(format!(
+                "{} values do not match {} points of {} channels",
+                series.values.len(),
+                series.timestamps.len(),
+                channels
+            )));
+        }
+
+        let mut out = Vec::with_capacity(HEADER_SIZE + series.len() * (1 + channels));
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(channels as u8);
+        out.extend_from_slice(&(series.len() as u64).to_le_bytes());
+
+        let mut writer = BitWriter::new(out);
+        let mut previous_timestamp = 0i64;
+        let mut previous_delta = 0i64;
+        let mut previous_values = vec![ValueState::default(); channels];
+
+        for (index, &timestamp) in series.timestamps.iter().enumerate() {
+            if index == 0 {
+                writer.write_bits(timestamp as u64, 64);
+            } else {
+                let delta = timestamp.wrapping_sub(previous_timestamp);
+                write_delta_of_delta(&mut writer, delta.wrapping_sub(previous_delta));
+                previous_delta = delta;
+            }
+            previous_timestamp = timestamp;
+
+            let values = &series.values[index * channels..(index + 1) * channels];
+            for (state, value) in previous_values.iter_mut().zip(values) {
+                if index == 0 {
+                    writer.write_bits(value.to_bits(), 64);
+                    state.bits = value.to_bits();
+                } else {
+                    state.write(&mut writer, value.to_bits());
+                }
+            }
+        }
+
+        Ok(writer.finish())
+    }
+
+    /// Decode a series produced by [`GorillaCodec::encode`]
+    pub fn decode(&self, data: &[u8]) -> Result<TimeSeries> {
+        if data.len() < HEADER_SIZE || &data[..4] != MAGIC {
+            return Err(CompressionError::corrupted("Missing time series header"));
+        }
+
+        if data[4] != VERSION {
+            return Err(CompressionError::TimeSeries(format!(
+                "Unsupported time series version {}",
+                data[4]
+            )));
+        }
+
+        let channels = data[5] as usize;
+        if channels == 0 {
+            return Err(CompressionError::corrupted("Time series has no channels"));
+        }
+
+        let mut count_bytes = [0u8; 8];
+        count_bytes.copy_from_slice(&data[6..HEADER_SIZE]);
+        let count = u64::from_le_bytes(count_bytes) as usize;
+
+        // Every point takes at least one bit per timestamp and channel
+        let body = &data[HEADER_SIZE..];
+        if count.saturating_mul(1 + channels) > body.len().saturating_mul(8) {
+            return Err(CompressionError::corrupted("Point count exceeds encoded data"));
+        }
+
+        let mut reader = BitReader::new(body);
+        let mut series = TimeSeries {
+            channels,
+            timestamps: Vec::with_capacity(count),
+            values: Vec::with_capacity(count * channels),
+        };
+
+        let mut previous_timestamp = 0i64;
+        let mut previous_delta = 0i64;
+        let mut previous_values = vec![ValueState::default(); channels];
+
+        for index in 0..count {
+            let timestamp = if index == 0 {
+                reader.read_bits(64)? as i64
+            } else {
+                let delta = previous_delta.wrapping_add(read_delta_of_delta(&mut reader)?);
+                previous_delta = delta;
+                previous_timestamp.wrapping_add(delta)
+            };
+            series.timestamps.push(timestamp);
+            previous_timestamp = timestamp;
+
+            for state in &mut previous_values {
+                if index == 0 {
+                    state.bits = reader.read_bits(64)?;
+                } else {
+                    state.read(&mut reader)?;
+                }
+                series.values.push(f64::from_bits(state.bits));
+            }
+        }
+
+        Ok(series)
+    }
+
+    /// Compress with statistics
+    pub fn compress_with_stats(&self, data: &[u8]) -> Result<(Vec<u8>, CompressionStats)> {
+        let timer = Timer::start("gorilla_compress");
+        let compressed = self.compress(data)?;
+        let elapsed = timer.stop();
+
+        let stats = CompressionStats::new(data.len(), compressed.len(), elapsed, "gorilla");
+
+        Ok((compressed, stats))
+    }
+
+    /// Size of one input record in bytes
+    fn record_size(&self) -> usize {
+        8 * (1 + self.channels)
+    }
+}
+
+#[async_trait]
+impl Compressor for GorillaCodec {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let record_size = self.record_size();
+        if !data.len().is_multiple_of(record_size) {
+            return Err(CompressionError::TimeSeries(format!(
+                "{} bytes is not a whole number of {}-byte records",
+                data.len(),
+                record_size
+            )));
+        }
+
+        let mut series = TimeSeries::new(self.channels);
+        let mut values = Vec::with_capacity(self.channels);
+        for record in data.chunks_exact(record_size) {
+            let mut fields = record.chunks_exact(8).map(|chunk| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(chunk);
+                bytes
+            });
+
+            let timestamp = fields.next().map(i64::from_le_bytes).unwrap_or_default();
+            values.clear();
+            values.extend(fields.map(f64::from_le_bytes));
+            series.push(timestamp, &values)?;
+        }
+
+        self.encode(&series)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let series = self.decode(data)?;
+        let mut out = Vec::with_capacity(series.len() * 8 * (1 + series.channels));
+
+        for index in 0..series.len() {
+            if let Some((timestamp, values)) = series.point(index) {
+                out.extend_from_slice(&timestamp.to_le_bytes());
+                for value in values {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn algorithm(&self) -> &str {
+        "gorilla"
+    }
+}
+
+/// Write a timestamp delta-of-delta in the smallest bucket that holds it
+fn write_delta_of_delta(writer: &mut BitWriter, dod: i64) {
+    if dod == 0 {
+        writer.write_bits(0, 1);
+        return;
+    }
+
+    for (control, control_len, bits) in TIMESTAMP_BUCKETS {
+        let limit = 1i64 << (bits - 1);
+        if (-limit..limit).contains(&dod) {
+            writer.write_bits(control, control_len);
+            writer.write_bits(dod as u64, bits);
+            return;
+        }
+    }
+
+    writer.write_bits(0b1111, 4);
+    writer.write_bits(dod as u64, 64);
+}
+
+/// Read a timestamp delta-of-delta
+fn read_delta_of_delta(reader: &mut BitReader) -> Result<i64> {
+    let mut control_len = 0;
+    while control_len < 4 && reader.read_bit()? {
+        control_len += 1;
+    }
+
+    let bits = match control_len {
+        0 => return Ok(0),
+        1..=3 => TIMESTAMP_BUCKETS[control_len - 1].2,
+        _ => return Ok(reader.read_bits(64)? as i64),
+    };
+
+    // Sign-extend the bucket payload
+    let raw = reader.read_bits(bits)?;
+    let shift = 64 - bits;
+    Ok(((raw << shift) as i64) >> shift)
+}
+
+/// Per-channel XOR state
+#[derive(Debug, Clone, Copy, Default)]
+struct ValueState {
+    /// Bits of the previous value
+    bits: u64,
+    /// Leading and trailing zeros of the current meaningful-bit window
+    window: Option<(u32, u32)>,
+}
+
+impl ValueState {
+    fn write(&mut self, writer: &mut BitWriter, bits: u64) {
+        let xor = bits ^ self.bits;
+        self.bits = bits;
+
+        if xor == 0 {
+            writer.write_bits(0, 1);
+            return;
+        }
+        writer.write_bits(1, 1);
+
+        // Leading zeros are stored in 5 bits
+        let leading = xor.leading_zeros().min(31);
+        let trailing = xor.trailing_zeros();
+
+        match self.window {
+            Some((window_leading, window_trailing))
+                if leading >= window_leading && trailing >= window_trailing =>
+            {
+                writer.write_bits(0, 1);
+                writer.write_bits(xor >> window_trailing, 64 - window_leading - window_trailing);
+            }
+            _ => {
+                let length = 64 - leading - trailing;
+                writer.write_bits(1, 1);
+                writer.write_bits(u64::from(leading), 5);
+                writer.write_bits(u64::from(length - 1), 6);
+                writer.write_bits(xor >> trailing, length);
+                self.window = Some((leading, trailing));
+            }
+        }
+    }
+
+    fn read(&mut self, reader: &mut BitReader) -> Result<()> {
+        if !reader.read_bit()? {
+            return Ok(());
+        }
+
+        if reader.read_bit()? {
+            let leading = reader.read_bits(5)? as u32;
+            let length = reader.read_bits(6)? as u32 + 1;
+            if leading + length > 64 {
+                return Err(CompressionError::corrupted("Invalid XOR window"));
+            }
+            self.window = Some((leading, 64 - leading - length));
+        }
+
+        let (leading, trailing) = self
+            .window
+            .ok_or_else(|| CompressionError::corrupted("XOR window reused before being set"))?;
+        let meaningful = reader.read_bits(64 - leading - trailing)?;
+        self.bits ^= meaningful << trailing;
+        Ok(())
+    }
+}
+
+/// MSB-first bit writer
+struct BitWriter {
+    out: Vec<u8>,
+    /// Pending bits, left-aligned
+    buffer: u64,
+    /// Number of pending bits
+    filled: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        Self {
+            out,
+            buffer: 0,
+            filled: 0,
+        }
+    }
+
+    /// Write the low `count` bits of `value`
+    fn write_bits(&mut self, value: u64, count: u32) {
+        if count == 0 {
+            return;
+        }
+
+        let value = if count == 64 { value } else { value & ((1u64 << count) - 1) };
+        let free = 64 - self.filled;
+
+        if count <= free {
+            self.buffer |= value << (free - count);
+            self.filled += count;
+        } else {
+            let overflow = count - free;
+            self.buffer |= value >> overflow;
+            self.filled = 64;
+            self.flush_full();
+            self.buffer = value << (64 - overflow);
+            self.filled = overflow;
+        }
+
+        if self.filled == 64 {
+            self.flush_full();
+        }
+    }
+
+    fn flush_full(&mut self) {
+        self.out.extend_from_slice(&self.buffer.to_be_bytes());
+        self.buffer = 0;
+        self.filled = 0;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let bytes = self.filled.div_ceil(8) as usize;
+        self.out.extend_from_slice(&self.buffer.to_be_bytes()[..bytes]);
+        self.out
+    }
+}
+
+/// MSB-first bit reader
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Bit position
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u64> {
+        if self.pos + count as usize > self.data.len() * 8 {
+            return Err(CompressionError::corrupted("Unexpected end of time series data"));
+        }
+
+        let mut value = 0u64;
+        let mut remaining = count;
+        while remaining > 0 {
+            let byte = self.data[self.pos / 8];
+            let offset = (self.pos % 8) as u32;
+            let take = remaining.min(8 - offset);
+            let bits = (byte >> (8 - offset - take)) & ((1u16 << take) - 1) as u8;
+
+            value = (value << take) | u64::from(bits);
+            self.pos += take as usize;
+            remaining -= take;
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gps_track(points: usize) -> TimeSeries {
+        let mut series = TimeSeries::new(3);
+        for i in 0..points {
+            let t = i as f64;
+            series
+                .push(
+                    1_700_000_000_000 + i as i64 * 1000 + if i % 10 == 0 { 3 } else { 0 },
+                    &[-122.4194 + t * 1e-5, 37.7749 + t * 2e-5, 12.5],
+                )
+                .unwrap();
+        }
+        series
+    }
+
+    #[test]
+    fn test_bit_roundtrip() {
+        let mut writer = BitWriter::new(Vec::new());
+        writer.write_bits(0b101, 3);
+        writer.write_bits(u64::MAX, 64);
+        writer.write_bits(0x1234, 13);
+        let data = writer.finish();
+
+        let mut reader = BitReader::new(&data);
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.read_bits(64).unwrap(), u64::MAX);
+        assert_eq!(reader.read_bits(13).unwrap(), 0x1234 & 0x1fff);
+    }
+
+    #[test]
+    fn test_roundtrip_is_lossless() {
+        let series = gps_track(1000);
+        let codec = GorillaCodec::new();
+
+        let encoded = codec.encode(&series).unwrap();
+        let decoded = codec.decode(&encoded).unwrap();
+
+        assert_eq!(decoded, series);
+        let raw_size = series.len() * 8 * 4;
+        assert!(encoded.len() * 2 < raw_size);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let mut series = TimeSeries::new(1);
+        let values = [0.0, -0.0, f64::INFINITY, f64::MIN_POSITIVE, f64::MAX, 1.0, 1.0, -1.5];
+        for (i, v) in values.iter().enumerate() {
+            series.push(i64::MIN / 2 + (i as i64) * (i64::MAX / 8), &[*v]).unwrap();
+        }
+        series.push(i64::MAX, &[f64::NAN]).unwrap();
+
+        let codec = GorillaCodec::new();
+        let decoded = codec.decode(&codec.encode(&series).unwrap()).unwrap();
+
+        assert_eq!(decoded.timestamps, series.timestamps);
+        for (a, b) in series.values.iter().zip(&decoded.values) {
+            assert_eq!(a.to_bits(), b.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_compressor_interface() {
+        let series = gps_track(200);
+        let mut raw = Vec::new();
+        for i in 0..series.len() {
+            let (timestamp, values) = series.point(i).unwrap();
+            raw.extend_from_slice(&timestamp.to_le_bytes());
+            for v in values {
+                raw.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+
+        let codec = GorillaCodec::with_channels(3);
+        let compressed = codec.compress(&raw).unwrap();
+        assert!(compressed.len() < raw.len() / 2);
+        assert_eq!(codec.decompress(&compressed).unwrap(), raw);
+
+        assert!(codec.compress(&raw[..20]).is_err());
+    }
+
+    #[test]
+    fn test_empty_and_corrupted() {
+        let codec = GorillaCodec::new();
+        let empty = codec.encode(&TimeSeries::new(2)).unwrap();
+        assert!(codec.decode(&empty).unwrap().is_empty());
+
+        let encoded = codec.encode(&gps_track(50)).unwrap();
+        assert!(codec.decode(&encoded[..encoded.len() / 2]).is_err());
+        assert!(codec.decode(b"MGTS").is_err());
+
+        let mut series = TimeSeries::new(2);
+        assert!(series.push(0, &[1.0]).is_err());
+    }
+}
@@ -10,6 +10,8 @@
 //! - **Adaptive Compression**: Automatic algorithm selection
 //! - **Pipeline Processing**: Chain multiple compression stages
 //! - **Delta Compression**: Efficient versioning support
+//! - **Geometry Codec**: Quantized, delta-encoded coordinates for feature data
+//! - **Time Series Codec**: Gorilla compression for GPS and sensor streams
 //! - **Comprehensive Stats**: Detailed performance metrics
 //!
 //! # Quick Start
@@ -41,6 +43,8 @@ pub mod gzip;
 pub mod snappy;
 pub mod dictionary;
 pub mod delta;
+pub mod geometry;
+pub mod gorilla;
 pub mod streaming;
 pub mod adaptive;
 pub mod pipeline;
@@ -48,6 +52,8 @@ pub mod pipeline;
 // Re-exports
 pub use error::{CompressionError, Result};
 pub use stats::{CompressionStats, StatsCollector, BenchmarkRunner};
+pub use geometry::{GeometryCodec, GeometryCodecConfig};
+pub use gorilla::{GorillaCodec, TimeSeries};
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -66,6 +72,10 @@ pub enum CompressionAlgorithm {
     Gzip,
     /// Snappy - Real-time compression
     Snappy,
+    /// Geometry - Lossy quantized coordinate encoding
+    Geometry,
+    /// Gorilla - Lossless time-series encoding
+    Gorilla,
     /// Adaptive - Automatically select best algorithm
     Adaptive,
 }
@@ -78,6 +88,8 @@ impl fmt::Display for CompressionAlgorithm {
             Self::Brotli => write!(f, "Brotli"),
             Self::Gzip => write!(f, "Gzip"),
             Self::Snappy => write!(f, "Snappy"),
+            Self::Geometry => write!(f, "Geometry"),
+            Self::Gorilla => write!(f, "Gorilla"),
             Self::Adaptive => write!(f, "Adaptive"),
         }
     }
@@ -92,6 +104,7 @@ impl CompressionAlgorithm {
             Self::Brotli => (0, 11),
             Self::Gzip => (0, 9),
             Self::Snappy => (0, 0), // Snappy has no compression level
+            Self::Geometry | Self::Gorilla => (0, 0),
             Self::Adaptive => (1, 9),
         }
     }
@@ -104,13 +117,19 @@ impl CompressionAlgorithm {
             Self::Brotli => 6,
             Self::Gzip => 6,
             Self::Snappy => 0,
+            Self::Geometry | Self::Gorilla => 0,
             Self::Adaptive => 6,
         }
     }
 
     /// Check if algorithm supports streaming
     pub fn supports_streaming(&self) -> bool {
-        !matches!(self, Self::Snappy)
+        !matches!(self, Self::Snappy | Self::Geometry | Self::Gorilla)
+    }
+
+    /// Check if algorithm may alter the data (decompression is approximate)
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, Self::Geometry)
     }
 
     /// Check if algorithm supports dictionary training
@@ -301,6 +320,8 @@ impl CompressionFacade {
             CompressionAlgorithm::Brotli => brotli::BrotliCompressor::default().compress(data),
             CompressionAlgorithm::Gzip => gzip::GzipCompressor::default().compress(data),
             CompressionAlgorithm::Snappy => snappy::SnappyCompressor::default().compress(data),
+            CompressionAlgorithm::Geometry => geometry::GeometryCodec::default().compress(data),
+            CompressionAlgorithm::Gorilla => gorilla::GorillaCodec::default().compress(data),
             CompressionAlgorithm::Adaptive => {
                 adaptive::AdaptiveCompressor::new().compress_with_best_algorithm(data)
             }
@@ -318,6 +339,9 @@ impl CompressionFacade {
             CompressionAlgorithm::Brotli => brotli::BrotliCompressor::default().decompress(data),
             CompressionAlgorithm::Gzip => gzip::GzipCompressor::default().decompress(data),
             CompressionAlgorithm::Snappy => snappy::SnappyCompressor::default().decompress(data),
+            // Both formats carry their own layout
+            CompressionAlgorithm::Geometry => geometry::GeometryCodec::default().decompress(data),
+            CompressionAlgorithm::Gorilla => gorilla::GorillaCodec::default().decompress(data),
             CompressionAlgorithm::Adaptive => {
                 Err(CompressionError::Adaptive(
                     "Cannot decompress with Adaptive - original algorithm unknown".to_string()
@@ -349,6 +373,12 @@ impl CompressionFacade {
             CompressionAlgorithm::Snappy => {
                 snappy::SnappyCompressor::default().compress(data)
             }
+            CompressionAlgorithm::Geometry => {
+                geometry::GeometryCodec::default().compress(data)
+            }
+            CompressionAlgorithm::Gorilla => {
+                gorilla::GorillaCodec::default().compress(data)
+            }
             CompressionAlgorithm::Adaptive => {
                 adaptive::AdaptiveCompressor::new().compress_with_best_algorithm(data)
             }
@@ -382,5 +412,7 @@ mod tests {
         assert!(CompressionAlgorithm::Zstd.supports_streaming());
         assert!(CompressionAlgorithm::Zstd.supports_dictionary());
         assert!(!CompressionAlgorithm::Snappy.supports_streaming());
+        assert!(CompressionAlgorithm::Geometry.is_lossy());
+        assert!(!CompressionAlgorithm::Gorilla.is_lossy());
     }
 }
@@ -3,6 +3,8 @@
 //! Chain multiple compression algorithms for specialized use cases.
 
 use crate::error::{CompressionError, Result};
+use crate::geometry::{GeometryCodec, GeometryCodecConfig};
+use crate::gorilla::GorillaCodec;
 use crate::stats::{CompressionStats, Timer};
 use crate::{CompressionPipeline, Compressor};
 use async_trait::async_trait;
@@ -321,6 +323,40 @@ impl PipelineTemplates {
         pipeline
     }
 
+    /// Geometry codec + Zstandard pipeline for coordinate arrays
+    ///
+    /// Separate per-dimension streams give Zstandard longer runs of similar
+    /// deltas to work with.
+    pub fn geometry_zstd(config: GeometryCodecConfig) -> CompressionPipelineImpl {
+        use crate::zstd::ZstdCompressor;
+
+        let mut pipeline = CompressionPipelineImpl::new();
+        pipeline
+            .add_named_stage(
+                "geometry",
+                Box::new(GeometryCodec::with_config(config.with_separate_streams(true))),
+            )
+            .unwrap();
+        pipeline
+            .add_named_stage("zstd", Box::new(ZstdCompressor::with_level(3)))
+            .unwrap();
+        pipeline
+    }
+
+    /// Gorilla + Zstandard pipeline for time-series records
+    pub fn time_series_zstd(channels: usize) -> CompressionPipelineImpl {
+        use crate::zstd::ZstdCompressor;
+
+        let mut pipeline = CompressionPipelineImpl::new();
+        pipeline
+            .add_named_stage("gorilla", Box::new(GorillaCodec::with_channels(channels)))
+            .unwrap();
+        pipeline
+            .add_named_stage("zstd", Box::new(ZstdCompressor::with_level(1)))
+            .unwrap();
+        pipeline
+    }
+
     /// Fast compression pipeline
     pub fn fast_compression() -> CompressionPipelineImpl {
         use crate::snappy::SnappyCompressor;
@@ -486,6 +522,21 @@ mod tests {
         assert!(result.len() < data.len());
     }
 
+    #[tokio::test]
+    async fn test_geometry_pipeline() {
+        let coords: Vec<f64> = (0..1000)
+            .flat_map(|i| [2.3522 + i as f64 * 1e-5, 48.8566 - i as f64 * 2e-5])
+            .collect();
+        let data: Vec<u8> = coords.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        let pipeline = PipelineTemplates::geometry_zstd(GeometryCodecConfig::new(2, 1e-7));
+        let compressed = pipeline.execute(Bytes::from(data.clone())).await.unwrap();
+        assert!(compressed.len() * 8 < data.len());
+
+        let restored = pipeline.execute_reverse(compressed).await.unwrap();
+        assert_eq!(restored.len(), data.len());
+    }
+
     #[tokio::test]
     async fn test_parallel_pipeline() {
         let mut parallel = ParallelPipeline::new();
@@ -1,17 +1,19 @@
 //! Bounding box implementations with R-tree integration.
 //!
 //! This module provides axis-aligned bounding box functionality with support
-//! for spatial indexing through R-tree integration.
+//! for spatial indexing through R-tree integration. Boxes of geometries with
+//! Z ordinates also carry their vertical extent.
 
 use geo_types::{Coord, CoordFloat, Rect};
 use rstar::{RTreeObject, AABB};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-/// An axis-aligned bounding box in 2D space.
+/// An axis-aligned bounding box in 2D space, with an optional Z range.
 ///
 /// A bounding box is defined by its minimum and maximum x and y coordinates.
-/// It represents the smallest rectangle that contains a geometry.
+/// It represents the smallest rectangle that contains a geometry. Spatial
+/// predicates work on the 2D extent unless noted otherwise.
 ///
 /// # Examples
 ///
@@ -33,6 +35,12 @@ pub struct BoundingBox {
     pub max_x: f64,
     /// Maximum y coordinate (north/top)
     pub max_y: f64,
+    /// Minimum z coordinate, for 3D extents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_z: Option<f64>,
+    /// Maximum z coordinate, for 3D extents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_z: Option<f64>,
 }
 
 impl BoundingBox {
@@ -69,9 +77,49 @@ impl BoundingBox {
             min_y,
             max_x,
             max_y,
+            min_z: None,
+            max_z: None,
         }
     }
 
+    /// Creates a bounding box with a Z range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if min values are greater than max values or if any coordinate is NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let bbox = BoundingBox::new_3d(0.0, 0.0, -5.0, 10.0, 10.0, 120.0);
+    /// assert_eq!(bbox.depth(), Some(125.0));
+    /// ```
+    pub fn new_3d(min_x: f64, min_y: f64, min_z: f64, max_x: f64, max_y: f64, max_z: f64) -> Self {
+        Self::new(min_x, min_y, max_x, max_y).with_z(min_z, max_z)
+    }
+
+    /// Sets the Z range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_z` is greater than `max_z` or either is NaN.
+    pub fn with_z(mut self, min_z: f64, max_z: f64) -> Self {
+        assert!(min_z <= max_z, "min_z must be <= max_z");
+        self.min_z = Some(min_z);
+        self.max_z = Some(max_z);
+        self
+    }
+
+    /// Checks if the box has a Z range.
+    pub fn has_z(&self) -> bool {
+        self.min_z.is_some() && self.max_z.is_some()
+    }
+
+    /// Returns the Z extent of the bounding box, if it has one.
+    pub fn depth(&self) -> Option<f64> {
+        Some(self.max_z? - self.min_z?)
+    }
+
     /// Creates a bounding box from two corner coordinates.
     ///
     /// The coordinates will be ordered correctly regardless of which corner is provided.
@@ -256,6 +304,44 @@ impl BoundingBox {
         self.min_y = self.min_y.min(other.min_y);
         self.max_x = self.max_x.max(other.max_x);
         self.max_y = self.max_y.max(other.max_y);
+        (self.min_z, self.max_z) = self.z_union(other);
+    }
+
+    /// Expands the Z range of this bounding box to include a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - The z coordinate to include; NaN values are ignored
+    pub fn expand_to_include_z(&mut self, z: f64) {
+        if z.is_nan() {
+            return;
+        }
+        self.min_z = Some(self.min_z.map_or(z, |min_z| min_z.min(z)));
+        self.max_z = Some(self.max_z.map_or(z, |max_z| max_z.max(z)));
+    }
+
+    /// Z range covering both boxes, from whichever have one
+    fn z_union(&self, other: &BoundingBox) -> (Option<f64>, Option<f64>) {
+        let min = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let max = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        (min(self.min_z, other.min_z), max(self.max_z, other.max_z))
+    }
+
+    /// Checks if this bounding box intersects another in 3D.
+    ///
+    /// The Z ranges are only compared when both boxes have one.
+    pub fn intersects_3d(&self, other: &BoundingBox) -> bool {
+        let z_overlap = match (self.min_z, self.max_z, other.min_z, other.max_z) {
+            (Some(min1), Some(max1), Some(min2), Some(max2)) => min1 <= max2 && max1 >= min2,
+            _ => true,
+        };
+        self.intersects(other) && z_overlap
     }
 
     /// Creates a new bounding box that is the union of this box and another.
@@ -268,12 +354,9 @@ impl BoundingBox {
     ///
     /// A new bounding box that contains both input boxes
     pub fn union(&self, other: &BoundingBox) -> BoundingBox {
-        BoundingBox::new(
-            self.min_x.min(other.min_x),
-            self.min_y.min(other.min_y),
-            self.max_x.max(other.max_x),
-            self.max_y.max(other.max_y),
-        )
+        let mut union = *self;
+        union.expand_to_include_bbox(other);
+        union
     }
 
     /// Creates a new bounding box that is the intersection of this box and another.
@@ -284,21 +367,26 @@ impl BoundingBox {
     ///
     /// # Returns
     ///
-    /// Some bounding box if the boxes intersect, None otherwise
+    /// Some bounding box if the boxes intersect, None otherwise. The result
+    /// has a Z range when both boxes have one.
     pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
-        if !self.intersects(other) {
+        if !self.intersects_3d(other) {
             return None;
         }
 
-        Some(BoundingBox::new(
+        let intersection = BoundingBox::new(
             self.min_x.max(other.min_x),
             self.min_y.max(other.min_y),
             self.max_x.min(other.max_x),
             self.max_y.min(other.max_y),
-        ))
+        );
+        match (self.min_z, self.max_z, other.min_z, other.max_z) {
+            (Some(min1), Some(max1), Some(min2), Some(max2)) => Some(intersection.with_z(min1.max(min2), max1.min(max2))),
+            _ => Some(intersection),
+        }
     }
 
-    /// Expands the bounding box by a fixed amount in all directions.
+    /// Expands the bounding box by a fixed amount in all horizontal directions.
     ///
     /// # Arguments
     ///
@@ -347,11 +435,18 @@ impl RTreeObject for BoundingBox {
 
 impl fmt::Display for BoundingBox {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "BBox[({}, {}) -> ({}, {})]",
-            self.min_x, self.min_y, self.max_x, self.max_y
-        )
+        match (self.min_z, self.max_z) {
+            (Some(min_z), Some(max_z)) => write!(
+                f,
+                "BBox[({}, {}, {}) -> ({}, {}, {})]",
+                self.min_x, self.min_y, min_z, self.max_x, self.max_y, max_z
+            ),
+            _ => write!(
+                f,
+                "BBox[({}, {}) -> ({}, {})]",
+                self.min_x, self.min_y, self.max_x, self.max_y
+            ),
+        }
     }
 }
 
@@ -440,4 +535,25 @@ mod tests {
         assert_eq!(buffered.max_x, 15.0);
         assert_eq!(buffered.max_y, 15.0);
     }
+
+    #[test]
+    fn test_bbox_3d() {
+        let mut bbox = BoundingBox::new_3d(0.0, 0.0, 5.0, 10.0, 10.0, 20.0);
+        assert_eq!(bbox.depth(), Some(15.0));
+        assert_eq!(bbox.to_string(), "BBox[(0, 0, 5) -> (10, 10, 20)]");
+
+        bbox.expand_to_include_z(-1.0);
+        assert_eq!(bbox.min_z, Some(-1.0));
+
+        let flat = BoundingBox::new(5.0, 5.0, 15.0, 15.0);
+        let union = bbox.union(&flat);
+        assert_eq!((union.min_z, union.max_z), (Some(-1.0), Some(20.0)));
+        assert!(!flat.union(&flat).has_z());
+
+        let above = BoundingBox::new_3d(5.0, 5.0, 30.0, 15.0, 15.0, 40.0);
+        assert!(bbox.intersects(&above));
+        assert!(!bbox.intersects_3d(&above));
+        assert!(bbox.intersection(&above).is_none());
+        assert_eq!(bbox.intersection(&flat).unwrap().min_z, None);
+    }
 }
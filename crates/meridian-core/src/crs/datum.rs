@@ -0,0 +1,284 @@
+//! Ellipsoids, geodetic datums and Helmert datum shifts.
+
+use serde::{Deserialize, Serialize};
+
+/// A reference ellipsoid.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ellipsoid {
+    /// Semi-major axis in meters
+    pub a: f64,
+
+    /// Inverse flattening, `0.0` for a sphere
+    pub inv_f: f64,
+}
+
+impl Ellipsoid {
+    /// WGS 84 ellipsoid
+    pub const WGS84: Ellipsoid = Ellipsoid::new(6_378_137.0, 298.257_223_563);
+
+    /// GRS 1980 ellipsoid, used by ETRS89, NAD83 and GDA
+    pub const GRS80: Ellipsoid = Ellipsoid::new(6_378_137.0, 298.257_222_101);
+
+    /// Clarke 1866 ellipsoid, used by NAD27
+    pub const CLARKE_1866: Ellipsoid = Ellipsoid::new(6_378_206.4, 294.978_698_213_898);
+
+    /// International 1924 (Hayford) ellipsoid, used by ED50
+    pub const INTERNATIONAL_1924: Ellipsoid = Ellipsoid::new(6_378_388.0, 297.0);
+
+    /// Bessel 1841 ellipsoid, used by DHDN
+    pub const BESSEL_1841: Ellipsoid = Ellipsoid::new(6_377_397.155, 299.152_812_8);
+
+    /// Airy 1830 ellipsoid, used by OSGB 1936
+    pub const AIRY_1830: Ellipsoid = Ellipsoid::new(6_377_563.396, 299.324_964_6);
+
+    /// Creates an ellipsoid from its semi-major axis and inverse flattening.
+    pub const fn new(a: f64, inv_f: f64) -> Self {
+        Self { a, inv_f }
+    }
+
+    /// Creates a sphere.
+    pub const fn sphere(radius: f64) -> Self {
+        Self { a: radius, inv_f: 0.0 }
+    }
+
+    /// Creates an ellipsoid from its semi-major and semi-minor axes.
+    pub fn from_axes(a: f64, b: f64) -> Self {
+        if (a - b).abs() < 1e-9 {
+            Self::sphere(a)
+        } else {
+            Self::new(a, a / (a - b))
+        }
+    }
+
+    /// Flattening.
+    pub fn f(&self) -> f64 {
+        if self.inv_f == 0.0 {
+            0.0
+        } else {
+            1.0 / self.inv_f
+        }
+    }
+
+    /// Semi-minor axis in meters.
+    pub fn b(&self) -> f64 {
+        self.a * (1.0 - self.f())
+    }
+
+    /// First eccentricity squared.
+    pub fn e2(&self) -> f64 {
+        let f = self.f();
+        f * (2.0 - f)
+    }
+
+    /// First eccentricity.
+    pub fn e(&self) -> f64 {
+        self.e2().sqrt()
+    }
+
+    /// Converts geodetic coordinates (radians, meters) to geocentric X, Y, Z.
+    pub fn to_geocentric(&self, lon: f64, lat: f64, h: f64) -> [f64; 3] {
+        let e2 = self.e2();
+        let (sin_lat, cos_lat) = lat.sin_cos();
+        let n = self.a / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+        [
+            (n + h) * cos_lat * lon.cos(),
+            (n + h) * cos_lat * lon.sin(),
+            (n * (1.0 - e2) + h) * sin_lat,
+        ]
+    }
+
+    /// Converts geocentric X, Y, Z to geodetic coordinates (radians, meters).
+    pub fn from_geocentric(&self, [x, y, z]: [f64; 3]) -> (f64, f64, f64) {
+        let e2 = self.e2();
+        let p = x.hypot(y);
+        let lon = y.atan2(x);
+
+        if p < 1e-9 {
+            let lat = std::f64::consts::FRAC_PI_2.copysign(z);
+            return (lon, lat, z.abs() - self.b());
+        }
+
+        let mut lat = z.atan2(p * (1.0 - e2));
+        let mut h = 0.0;
+        for _ in 0..10 {
+            let sin_lat = lat.sin();
+            let n = self.a / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+            h = p / lat.cos() - n;
+            let next = z.atan2(p * (1.0 - e2 * n / (n + h)));
+            if (next - lat).abs() < 1e-14 {
+                lat = next;
+                break;
+            }
+            lat = next;
+        }
+        (lon, lat, h)
+    }
+}
+
+/// Seven parameter Helmert transformation to WGS 84.
+///
+/// Uses the position vector convention (EPSG method 9606, PROJ `+towgs84`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Helmert {
+    /// X-axis translation in meters
+    pub tx: f64,
+
+    /// Y-axis translation in meters
+    pub ty: f64,
+
+    /// Z-axis translation in meters
+    pub tz: f64,
+
+    /// X-axis rotation in arc-seconds
+    pub rx: f64,
+
+    /// Y-axis rotation in arc-seconds
+    pub ry: f64,
+
+    /// Z-axis rotation in arc-seconds
+    pub rz: f64,
+
+    /// Scale difference in parts per million
+    pub ds: f64,
+}
+
+impl Helmert {
+    /// Creates a three parameter (translation only) transformation.
+    pub const fn translation(tx: f64, ty: f64, tz: f64) -> Self {
+        Self { tx, ty, tz, rx: 0.0, ry: 0.0, rz: 0.0, ds: 0.0 }
+    }
+
+    /// Creates a seven parameter transformation.
+    pub const fn new(tx: f64, ty: f64, tz: f64, rx: f64, ry: f64, rz: f64, ds: f64) -> Self {
+        Self { tx, ty, tz, rx, ry, rz, ds }
+    }
+
+    /// Creates a transformation from coordinate frame rotation parameters
+    /// (EPSG method 9607), which use the opposite rotation sign.
+    pub fn from_coordinate_frame(tx: f64, ty: f64, tz: f64, rx: f64, ry: f64, rz: f64, ds: f64) -> Self {
+        Self::new(tx, ty, tz, -rx, -ry, -rz, ds)
+    }
+
+    /// Creates a transformation from PROJ `+towgs84` values (3 or 7 of them).
+    pub fn from_towgs84(values: &[f64]) -> Option<Self> {
+        match values {
+            [tx, ty, tz] => Some(Self::translation(*tx, *ty, *tz)),
+            [tx, ty, tz, rx, ry, rz, ds] => Some(Self::new(*tx, *ty, *tz, *rx, *ry, *rz, *ds)),
+            _ => None,
+        }
+    }
+
+    /// Checks if all parameters are zero.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn rotation(&self) -> (f64, f64, f64, f64) {
+        const ARC_SECOND: f64 = std::f64::consts::PI / 648_000.0;
+        (
+            self.rx * ARC_SECOND,
+            self.ry * ARC_SECOND,
+            self.rz * ARC_SECOND,
+            1.0 + self.ds * 1e-6,
+        )
+    }
+
+    /// Applies the transformation to geocentric coordinates.
+    pub fn apply(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let (rx, ry, rz, s) = self.rotation();
+        [
+            self.tx + s * (x - rz * y + ry * z),
+            self.ty + s * (rz * x + y - rx * z),
+            self.tz + s * (-ry * x + rx * y + z),
+        ]
+    }
+
+    /// Applies the reverse transformation to geocentric coordinates.
+    pub fn apply_inverse(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let (rx, ry, rz, s) = self.rotation();
+        let (x, y, z) = ((x - self.tx) / s, (y - self.ty) / s, (z - self.tz) / s);
+        [x + rz * y - ry * z, -rz * x + y + rx * z, ry * x - rx * y + z]
+    }
+}
+
+/// A geodetic datum.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Datum {
+    /// Datum name
+    pub name: String,
+
+    /// Reference ellipsoid
+    pub ellipsoid: Ellipsoid,
+
+    /// Transformation to WGS 84, if known
+    pub to_wgs84: Option<Helmert>,
+
+    /// Longitude of the prime meridian east of Greenwich, in degrees
+    pub prime_meridian: f64,
+}
+
+impl Datum {
+    /// Creates a datum on the Greenwich meridian.
+    pub fn new(name: impl Into<String>, ellipsoid: Ellipsoid, to_wgs84: Option<Helmert>) -> Self {
+        Self {
+            name: name.into(),
+            ellipsoid,
+            to_wgs84,
+            prime_meridian: 0.0,
+        }
+    }
+
+    /// World Geodetic System 1984.
+    pub fn wgs84() -> Self {
+        Self::new("World Geodetic System 1984", Ellipsoid::WGS84, Some(Helmert::default()))
+    }
+
+    /// Checks if converting between the datums needs a datum shift.
+    ///
+    /// Like PROJ, no shift is applied when either datum has no known
+    /// transformation to WGS 84, or when both coincide with WGS 84.
+    pub(crate) fn needs_shift(&self, other: &Datum) -> bool {
+        match (self.to_wgs84, other.to_wgs84) {
+            (Some(a), Some(b)) => a != b || (!a.is_identity() && self.ellipsoid != other.ellipsoid),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_geocentric_roundtrip() {
+        // EPSG Guidance Note 7-2, example 2.2.1
+        let lon = (2.0 + 7.0 / 60.0 + 46.38 / 3600.0_f64).to_radians();
+        let lat = (53.0 + 48.0 / 60.0 + 33.82 / 3600.0_f64).to_radians();
+        let [x, y, z] = Ellipsoid::WGS84.to_geocentric(lon, lat, 73.0);
+        assert_abs_diff_eq!(x, 3_771_793.968, epsilon = 1e-3);
+        assert_abs_diff_eq!(y, 140_253.342, epsilon = 1e-3);
+        assert_abs_diff_eq!(z, 5_124_304.349, epsilon = 1e-3);
+
+        let (lon2, lat2, h) = Ellipsoid::WGS84.from_geocentric([x, y, z]);
+        assert_abs_diff_eq!(lon2, lon, epsilon = 1e-12);
+        assert_abs_diff_eq!(lat2, lat, epsilon = 1e-12);
+        assert_abs_diff_eq!(h, 73.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_helmert_position_vector() {
+        // EPSG Guidance Note 7-2, example 2.4.3.2.2 (WGS 72 to WGS 84)
+        let helmert = Helmert::new(0.0, 0.0, 4.5, 0.0, 0.0, 0.554, 0.219);
+        let source = [3_657_660.66, 255_768.55, 5_201_382.11];
+        let [x, y, z] = helmert.apply(source);
+        assert_abs_diff_eq!(x, 3_657_660.78, epsilon = 0.01);
+        assert_abs_diff_eq!(y, 255_778.43, epsilon = 0.01);
+        assert_abs_diff_eq!(z, 5_201_387.75, epsilon = 0.01);
+
+        let back = helmert.apply_inverse([x, y, z]);
+        for (a, b) in back.iter().zip(source) {
+            assert_abs_diff_eq!(*a, b, epsilon = 1e-3);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Pure Rust coordinate transformation engine.
+//!
+//! Used by [`Crs::transform_point`](super::Crs::transform_point) when the
+//! `proj-transform` feature is disabled, e.g. in WASM builds.
+
+use super::datum::{Datum, Ellipsoid, Helmert};
+use super::projection::{Method, Prepared, Projection};
+use crate::error::{MeridianError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Full definition of a coordinate reference system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrsDefinition {
+    /// CRS name
+    pub name: String,
+
+    /// EPSG code, if known
+    pub epsg: Option<u32>,
+
+    /// Geodetic datum
+    pub datum: Datum,
+
+    /// Projection, [`Projection::geographic`] for longitude/latitude
+    pub projection: Projection,
+
+    /// Meters per projected coordinate unit
+    pub to_meter: f64,
+}
+
+impl CrsDefinition {
+    /// Creates a geographic CRS.
+    pub fn geographic(name: impl Into<String>, datum: Datum) -> Self {
+        Self {
+            name: name.into(),
+            epsg: None,
+            datum,
+            projection: Projection::geographic(),
+            to_meter: 1.0,
+        }
+    }
+
+    /// Creates a projected CRS with coordinates in meters.
+    pub fn projected(name: impl Into<String>, datum: Datum, projection: Projection) -> Self {
+        Self {
+            name: name.into(),
+            epsg: None,
+            datum,
+            projection,
+            to_meter: 1.0,
+        }
+    }
+
+    /// Sets the EPSG code.
+    pub fn with_epsg(mut self, code: u32) -> Self {
+        self.epsg = Some(code);
+        self
+    }
+
+    /// Sets the projected coordinate unit.
+    pub fn with_to_meter(mut self, to_meter: f64) -> Self {
+        self.to_meter = to_meter;
+        self
+    }
+
+    /// Checks if coordinates are longitude/latitude in degrees.
+    pub fn is_geographic(&self) -> bool {
+        self.projection.is_geographic()
+    }
+
+    /// Formats the definition as OGC WKT 1, the flavor GDAL writes to
+    /// `.prj` files and GeoPackage `gpkg_spatial_ref_sys` tables.
+    pub fn to_wkt(&self) -> String {
+        let datum = &self.datum;
+        let ellipsoid = &datum.ellipsoid;
+        let mut datum_wkt = format!(
+            "DATUM[\"{}\",SPHEROID[\"{}\",{},{}]",
+            datum.name,
+            ellipsoid_name(ellipsoid),
+            ellipsoid.a,
+            ellipsoid.inv_f
+        );
+        if let Some(h) = datum.to_wgs84 {
+            datum_wkt += &format!(",TOWGS84[{},{},{},{},{},{},{}]", h.tx, h.ty, h.tz, h.rx, h.ry, h.rz, h.ds);
+        }
+        datum_wkt.push(']');
+
+        let geogcs_name = if self.is_geographic() { &self.name } else { &datum.name };
+        let geogcs = format!(
+            "GEOGCS[\"{}\",{},PRIMEM[\"{}\",{}],UNIT[\"degree\",0.0174532925199433]",
+            geogcs_name,
+            datum_wkt,
+            if datum.prime_meridian == 0.0 { "Greenwich" } else { "unnamed" },
+            datum.prime_meridian
+        );
+        let authority = self
+            .epsg
+            .map(|code| format!(",AUTHORITY[\"EPSG\",\"{}\"]", code))
+            .unwrap_or_default();
+        if self.is_geographic() {
+            return format!("{}{}]", geogcs, authority);
+        }
+
+        let p = &self.projection;
+        let (x0, y0) = (p.false_easting / self.to_meter, p.false_northing / self.to_meter);
+        let (method, params): (&str, Vec<(&str, f64)>) = match p.method {
+            Method::Geographic => unreachable!("geographic CRSs return above"),
+            Method::TransverseMercator => (
+                "Transverse_Mercator",
+                vec![("latitude_of_origin", p.lat_0), ("central_meridian", p.lon_0), ("scale_factor", p.k_0)],
+            ),
+            Method::Mercator => match p.lat_ts {
+                Some(lat_ts) => ("Mercator_2SP", vec![("standard_parallel_1", lat_ts), ("central_meridian", p.lon_0)]),
+                None => ("Mercator_1SP", vec![("central_meridian", p.lon_0), ("scale_factor", p.k_0)]),
+            },
+            // WKT 1 has no Pseudo-Mercator, so GDAL adds the PROJ string
+            Method::WebMercator => ("Mercator_1SP", vec![("central_meridian", 0.0), ("scale_factor", 1.0)]),
+            Method::LambertConformalConic if p.lat_1 == p.lat_2 && p.lat_1 == p.lat_0 => (
+                "Lambert_Conformal_Conic_1SP",
+                vec![("latitude_of_origin", p.lat_0), ("central_meridian", p.lon_0), ("scale_factor", p.k_0)],
+            ),
+            Method::LambertConformalConic => (
+                "Lambert_Conformal_Conic_2SP",
+                vec![
+                    ("standard_parallel_1", p.lat_1),
+                    ("standard_parallel_2", p.lat_2),
+                    ("latitude_of_origin", p.lat_0),
+                    ("central_meridian", p.lon_0),
+                ],
+            ),
+            Method::AlbersEqualArea => (
+                "Albers_Conic_Equal_Area",
+                vec![
+                    ("standard_parallel_1", p.lat_1),
+                    ("standard_parallel_2", p.lat_2),
+                    ("latitude_of_center", p.lat_0),
+                    ("longitude_of_center", p.lon_0),
+                ],
+            ),
+            Method::PolarStereographic => match p.lat_ts {
+                Some(lat_ts) => ("Polar_Stereographic", vec![("latitude_of_origin", lat_ts), ("central_meridian", p.lon_0)]),
+                None => (
+                    "Polar_Stereographic",
+                    vec![("latitude_of_origin", p.lat_0), ("central_meridian", p.lon_0), ("scale_factor", p.k_0)],
+                ),
+            },
+        };
+
+        let mut wkt = format!("PROJCS[\"{}\",{}],PROJECTION[\"{}\"]", self.name, geogcs, method);
+        for (name, value) in params.into_iter().chain([("false_easting", x0), ("false_northing", y0)]) {
+            wkt += &format!(",PARAMETER[\"{}\",{}]", name, value);
+        }
+        wkt += &format!(",UNIT[\"{}\",{}]", unit_name(self.to_meter), self.to_meter);
+        if p.method == Method::WebMercator {
+            wkt += ",EXTENSION[\"PROJ4\",\"+proj=merc +a=6378137 +b=6378137 +lat_ts=0 +lon_0=0 +x_0=0 +y_0=0 +k=1 +units=m +nadgrids=@null +wktext +no_defs\"]";
+        }
+        format!("{}{}]", wkt, authority)
+    }
+}
+
+/// Name of a known ellipsoid, for WKT
+fn ellipsoid_name(ellipsoid: &Ellipsoid) -> &'static str {
+    [
+        (Ellipsoid::WGS84, "WGS 84"),
+        (Ellipsoid::GRS80, "GRS 1980"),
+        (Ellipsoid::CLARKE_1866, "Clarke 1866"),
+        (Ellipsoid::INTERNATIONAL_1924, "International 1924"),
+        (Ellipsoid::BESSEL_1841, "Bessel 1841"),
+        (Ellipsoid::AIRY_1830, "Airy 1830"),
+    ]
+    .into_iter()
+    .find(|(known, _)| known == ellipsoid)
+    .map_or("unnamed", |(_, name)| name)
+}
+
+/// Name of a linear unit, for WKT
+fn unit_name(to_meter: f64) -> &'static str {
+    if to_meter == 1.0 {
+        "metre"
+    } else if to_meter == 0.3048 {
+        "foot"
+    } else if (to_meter - 1200.0 / 3937.0).abs() < 1e-15 {
+        "US survey foot"
+    } else {
+        "unnamed"
+    }
+}
+
+/// Transformation between two CRS definitions.
+///
+/// Coordinates are converted to longitude/latitude on the source datum,
+/// shifted to the target datum through geocentric coordinates when both
+/// datums have a known Helmert transformation to WGS 84, and projected.
+/// Heights are ellipsoidal and only change with the datum shift.
+#[derive(Debug, Clone)]
+pub struct Transformer {
+    source: Endpoint,
+    target: Endpoint,
+    shift: Option<[(Ellipsoid, Helmert); 2]>,
+}
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    prepared: Prepared,
+    geographic: bool,
+    to_meter: f64,
+    prime_meridian: f64,
+}
+
+impl Endpoint {
+    fn new(definition: &CrsDefinition) -> Result<Self> {
+        if !(definition.to_meter.is_finite() && definition.to_meter > 0.0) {
+            return Err(MeridianError::InvalidCrs(format!(
+                "Invalid unit conversion factor {} for {}",
+                definition.to_meter, definition.name
+            )));
+        }
+        Ok(Self {
+            prepared: Prepared::new(&definition.projection, &definition.datum.ellipsoid)?,
+            geographic: definition.is_geographic(),
+            to_meter: definition.to_meter,
+            prime_meridian: definition.datum.prime_meridian.to_radians(),
+        })
+    }
+
+    /// Longitude (from Greenwich) and latitude in radians
+    fn to_lonlat(&self, x: f64, y: f64) -> (f64, f64) {
+        let (lon, lat) = if self.geographic {
+            (x.to_radians(), y.to_radians())
+        } else {
+            self.prepared.inverse(x * self.to_meter, y * self.to_meter)
+        };
+        (lon + self.prime_meridian, lat)
+    }
+
+    /// Coordinates in this CRS from longitude (from Greenwich) and latitude in radians
+    fn project(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let lon = lon - self.prime_meridian;
+        if self.geographic {
+            (lon.to_degrees(), lat.to_degrees())
+        } else {
+            let (x, y) = self.prepared.forward(lon, lat);
+            (x / self.to_meter, y / self.to_meter)
+        }
+    }
+}
+
+impl Transformer {
+    /// Creates a transformation from `source` to `target`.
+    pub fn new(source: &CrsDefinition, target: &CrsDefinition) -> Result<Self> {
+        let shift = source
+            .datum
+            .needs_shift(&target.datum)
+            .then(|| [&source.datum, &target.datum].map(|datum| (datum.ellipsoid, datum.to_wgs84.unwrap_or_default())));
+
+        Ok(Self {
+            source: Endpoint::new(source)?,
+            target: Endpoint::new(target)?,
+            shift,
+        })
+    }
+
+    /// Checks if the transformation shifts between datums, changing heights.
+    pub fn shifts_datum(&self) -> bool {
+        self.shift.is_some()
+    }
+
+    /// Transforms a coordinate.
+    ///
+    /// Points are taken to lie on the source ellipsoid; use
+    /// [`transform_3d`](Self::transform_3d) to keep track of heights.
+    pub fn transform(&self, x: f64, y: f64) -> Result<(f64, f64)> {
+        self.transform_3d(x, y, 0.0).map(|(x, y, _)| (x, y))
+    }
+
+    /// Transforms a coordinate and its ellipsoidal height in meters.
+    pub fn transform_3d(&self, x: f64, y: f64, z: f64) -> Result<(f64, f64, f64)> {
+        let (mut lon, mut lat) = self.source.to_lonlat(x, y);
+        let mut h = z;
+
+        if let Some([(source, to_wgs84), (target, from_wgs84)]) = &self.shift {
+            let wgs84 = to_wgs84.apply(source.to_geocentric(lon, lat, h));
+            (lon, lat, h) = target.from_geocentric(from_wgs84.apply_inverse(wgs84));
+        }
+
+        let (x2, y2) = self.target.project(lon, lat);
+        if x2.is_finite() && y2.is_finite() {
+            Ok((x2, y2, h))
+        } else {
+            Err(MeridianError::TransformError(format!(
+                "Coordinate ({}, {}) is outside the domain of the projection",
+                x, y
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crs::epsg;
+    use approx::assert_abs_diff_eq;
+
+    fn transform(from: u32, to: u32, x: f64, y: f64) -> (f64, f64) {
+        let source = epsg::lookup(from).unwrap();
+        let target = epsg::lookup(to).unwrap();
+        Transformer::new(&source, &target).unwrap().transform(x, y).unwrap()
+    }
+
+    #[test]
+    fn test_utm_and_web_mercator() {
+        let (x, y) = transform(4326, 32632, 9.0, 48.0);
+        assert_abs_diff_eq!(x, 500_000.0, epsilon = 1e-6);
+        // 0.9996 times the meridian arc length to 48°N
+        assert_abs_diff_eq!(y, 5_316_300.224, epsilon = 1e-3);
+
+        let (lon, lat) = transform(32632, 4326, x, y);
+        assert_abs_diff_eq!(lon, 9.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(lat, 48.0, epsilon = 1e-9);
+
+        let (x, y) = transform(4326, 3857, 180.0, 0.0);
+        assert_abs_diff_eq!(x, 20_037_508.342_789_244, epsilon = 1e-6);
+        assert_abs_diff_eq!(y, 0.0, epsilon = 1e-6);
+        assert!(Transformer::new(&epsg::lookup(4326).unwrap(), &epsg::lookup(3857).unwrap())
+            .unwrap()
+            .transform(0.0, 90.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_datum_shift() {
+        // OSGB36 has a 7 parameter shift to WGS 84 of roughly 100 m in Britain;
+        // heights are dropped, so the roundtrip is only good to millimeters
+        let (lon, lat) = transform(4326, 4277, -0.1275, 51.5072);
+        assert!((lon - -0.1275).abs() > 1e-4);
+        let (lon2, lat2) = transform(4277, 4326, lon, lat);
+        assert_abs_diff_eq!(lon2, -0.1275, epsilon = 1e-7);
+        assert_abs_diff_eq!(lat2, 51.5072, epsilon = 1e-7);
+
+        // Heights follow the shift and survive the roundtrip
+        let osgb36 = Transformer::new(&epsg::lookup(4326).unwrap(), &epsg::lookup(4277).unwrap()).unwrap();
+        let wgs84 = Transformer::new(&epsg::lookup(4277).unwrap(), &epsg::lookup(4326).unwrap()).unwrap();
+        assert!(osgb36.shifts_datum());
+        let (lon, lat, h) = osgb36.transform_3d(-0.1275, 51.5072, 100.0).unwrap();
+        assert!((h - 100.0).abs() > 1.0);
+        let (lon2, lat2, h2) = wgs84.transform_3d(lon, lat, h).unwrap();
+        assert_abs_diff_eq!(lon2, -0.1275, epsilon = 1e-9);
+        assert_abs_diff_eq!(lat2, 51.5072, epsilon = 1e-9);
+        assert_abs_diff_eq!(h2, 100.0, epsilon = 1e-3);
+
+        // ETRS89 and WGS 84 are treated as equivalent, as PROJ does
+        let (lon, lat) = transform(4258, 4326, 10.0, 50.0);
+        assert_abs_diff_eq!(lon, 10.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(lat, 50.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_to_wkt_roundtrip() {
+        use crate::crs::parse::parse_wkt;
+
+        for code in [4326, 4277, 32632, 32761, 3857, 27700, 2154, 5070, 3413, 3031, 2263] {
+            let definition = epsg::lookup(code).unwrap();
+            let wkt = definition.to_wkt();
+            assert!(wkt.ends_with(&format!("AUTHORITY[\"EPSG\",\"{}\"]]", code)), "{}", wkt);
+
+            let parsed = parse_wkt(&wkt).unwrap_or_else(|e| panic!("{}: {}", code, e));
+            assert_eq!(parsed.epsg, Some(code));
+            let forward = Transformer::new(&epsg::lookup(4326).unwrap(), &definition).unwrap();
+            let reparsed = Transformer::new(&epsg::lookup(4326).unwrap(), &parsed).unwrap();
+            let (lon, lat) = if code == 3031 { (10.0, -75.0) } else if code == 3413 { (-45.0, 75.0) } else { (-1.0, 51.0) };
+            let (x, y) = forward.transform(lon, lat).unwrap();
+            let (x2, y2) = reparsed.transform(lon, lat).unwrap();
+            assert_abs_diff_eq!(x, x2, epsilon = 1e-6);
+            assert_abs_diff_eq!(y, y2, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_feet() {
+        let (x, y) = transform(4326, 2263, -74.0, 40.7);
+        // The origin of the Long Island zone lies at 74°W, 300 km west of its false easting
+        assert_abs_diff_eq!(x, 984_250.0, epsilon = 1.0);
+        assert!(y > 190_000.0 && y < 200_000.0);
+    }
+}
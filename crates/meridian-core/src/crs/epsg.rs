@@ -0,0 +1,267 @@
+//! Embedded subset of the EPSG registry.
+//!
+//! Covers the common geographic CRSs, UTM zones on WGS 84, ETRS89, NAD83,
+//! NAD27, ED50 and GDA, and a selection of national and polar projections.
+//! Datum shifts use the Helmert parameters PROJ applies when no grid is
+//! available.
+
+use super::datum::{Datum, Ellipsoid, Helmert};
+use super::engine::CrsDefinition;
+use super::projection::{Method, Projection};
+
+/// US survey foot in meters
+const US_FOOT: f64 = 1200.0 / 3937.0;
+
+/// Geographic CRS codes in the embedded registry.
+const GEOGRAPHIC: [u32; 15] = [
+    4326, 4258, 4269, 4267, 4277, 4283, 7844, 4230, 4314, 4167, 4171, 4674, 4612, 4148, 4490,
+];
+
+/// Projected CRS codes other than UTM ranges in the embedded registry.
+const PROJECTED: [u32; 25] = [
+    3857, 3395, 32661, 32761, 27700, 2154, 3034, 5070, 3310, 3005, 3577, 3112, 3347, 2193, 3413, 3976, 3031, 3995,
+    31466, 31467, 31468, 31469, 2263, 900913, 3785,
+];
+
+/// Looks up an EPSG code.
+///
+/// # Returns
+///
+/// The CRS definition, or `None` if the code is not in the embedded subset
+pub fn lookup(code: u32) -> Option<CrsDefinition> {
+    if let Some(definition) = geographic(code) {
+        return Some(definition.with_epsg(code));
+    }
+    projected(code).map(|definition| definition.with_epsg(code))
+}
+
+/// Checks if an EPSG code is in the embedded subset.
+pub fn contains(code: u32) -> bool {
+    GEOGRAPHIC.contains(&code) || utm(code).is_some() || PROJECTED.contains(&code)
+}
+
+/// All EPSG codes in the embedded subset.
+pub fn codes() -> Vec<u32> {
+    let utm_codes = (32601..=32660)
+        .chain(32701..=32760)
+        .chain(25828..=25838)
+        .chain(26901..=26923)
+        .chain(26701..=26722)
+        .chain(23028..=23038)
+        .chain(28348..=28358)
+        .chain(7846..=7859);
+    let mut codes: Vec<u32> = GEOGRAPHIC.into_iter().chain(utm_codes).chain(PROJECTED).collect();
+    codes.sort_unstable();
+    codes
+}
+
+fn geographic(code: u32) -> Option<CrsDefinition> {
+    let zero = Some(Helmert::default());
+    let (name, datum) = match code {
+        4326 => ("WGS 84", Datum::wgs84()),
+        4258 => ("ETRS89", Datum::new("European Terrestrial Reference System 1989", Ellipsoid::GRS80, zero)),
+        4269 => ("NAD83", Datum::new("North American Datum 1983", Ellipsoid::GRS80, zero)),
+        4267 => (
+            "NAD27",
+            Datum::new(
+                "North American Datum 1927",
+                Ellipsoid::CLARKE_1866,
+                Some(Helmert::translation(-8.0, 160.0, 176.0)),
+            ),
+        ),
+        4277 => (
+            "OSGB36",
+            Datum::new(
+                "Ordnance Survey of Great Britain 1936",
+                Ellipsoid::AIRY_1830,
+                Some(Helmert::new(446.448, -125.157, 542.06, 0.15, 0.247, 0.842, -20.489)),
+            ),
+        ),
+        4283 => ("GDA94", Datum::new("Geocentric Datum of Australia 1994", Ellipsoid::GRS80, zero)),
+        7844 => ("GDA2020", Datum::new("Geocentric Datum of Australia 2020", Ellipsoid::GRS80, zero)),
+        4230 => (
+            "ED50",
+            Datum::new(
+                "European Datum 1950",
+                Ellipsoid::INTERNATIONAL_1924,
+                Some(Helmert::translation(-87.0, -98.0, -121.0)),
+            ),
+        ),
+        4314 => (
+            "DHDN",
+            Datum::new(
+                "Deutsches Hauptdreiecksnetz",
+                Ellipsoid::BESSEL_1841,
+                Some(Helmert::new(598.1, 73.7, 418.2, 0.202, 0.045, -2.455, 6.7)),
+            ),
+        ),
+        4167 => ("NZGD2000", Datum::new("New Zealand Geodetic Datum 2000", Ellipsoid::GRS80, zero)),
+        4171 => ("RGF93 v1", Datum::new("Reseau Geodesique Francais 1993 v1", Ellipsoid::GRS80, zero)),
+        4674 => ("SIRGAS 2000", Datum::new("Sistema de Referencia Geocentrico para las AmericaS 2000", Ellipsoid::GRS80, zero)),
+        4612 => ("JGD2000", Datum::new("Japanese Geodetic Datum 2000", Ellipsoid::GRS80, zero)),
+        4148 => ("Hartebeesthoek94", Datum::new("Hartebeesthoek94", Ellipsoid::WGS84, zero)),
+        4490 => ("China Geodetic Coordinate System 2000", Datum::new("China 2000", Ellipsoid::GRS80, zero)),
+        _ => return None,
+    };
+    Some(CrsDefinition::geographic(name, datum))
+}
+
+/// Geographic base of a projected CRS
+fn base(code: u32) -> (String, Datum) {
+    let definition = geographic(code).expect("base CRS is in the registry");
+    (definition.name, definition.datum)
+}
+
+/// UTM zones: (base CRS, zone, north)
+fn utm(code: u32) -> Option<(u32, u32, bool)> {
+    let (base, first, north) = match code {
+        32601..=32660 => (4326, 32600, true),
+        32701..=32760 => (4326, 32700, false),
+        25828..=25838 => (4258, 25800, true),
+        26901..=26923 => (4269, 26900, true),
+        26701..=26722 => (4267, 26700, true),
+        23028..=23038 => (4230, 23000, true),
+        28348..=28358 => (4283, 28300, false),
+        7846..=7859 => (7844, 7800, false),
+        _ => return None,
+    };
+    Some((base, code - first, north))
+}
+
+fn projected(code: u32) -> Option<CrsDefinition> {
+    if let Some((base_code, zone, north)) = utm(code) {
+        let (base_name, datum) = base(base_code);
+        let name = match base_code {
+            4283 | 7844 => format!("{} / MGA zone {}", base_name, zone),
+            _ => format!("{} / UTM zone {}{}", base_name, zone, if north { "N" } else { "S" }),
+        };
+        return Some(CrsDefinition::projected(name, datum, Projection::utm(zone as u8, north)));
+    }
+
+    let (base_code, name, projection) = match code {
+        3857 | 900913 | 3785 => (4326, "WGS 84 / Pseudo-Mercator", Projection::web_mercator()),
+        3395 => (4326, "WGS 84 / World Mercator", Projection::new(Method::Mercator)),
+        32661 | 32761 => {
+            let north = code == 32661;
+            let projection = Projection {
+                lat_0: if north { 90.0 } else { -90.0 },
+                k_0: 0.994,
+                false_easting: 2_000_000.0,
+                false_northing: 2_000_000.0,
+                ..Projection::new(Method::PolarStereographic)
+            };
+            let name = if north { "WGS 84 / UPS North (N,E)" } else { "WGS 84 / UPS South (N,E)" };
+            (4326, name, projection)
+        }
+        27700 => (
+            4277,
+            "OSGB36 / British National Grid",
+            Projection::transverse_mercator(49.0, -2.0, 0.999_601_271_7, 400_000.0, -100_000.0),
+        ),
+        2154 => (
+            4171,
+            "RGF93 v1 / Lambert-93",
+            Projection::lambert_conformal_conic(46.5, 3.0, 49.0, 44.0, 700_000.0, 6_600_000.0),
+        ),
+        3034 => (
+            4258,
+            "ETRS89-extended / LCC Europe",
+            Projection::lambert_conformal_conic(52.0, 10.0, 35.0, 65.0, 4_000_000.0, 2_800_000.0),
+        ),
+        5070 => (4269, "NAD83 / Conus Albers", Projection::albers_equal_area(23.0, -96.0, 29.5, 45.5, 0.0, 0.0)),
+        3310 => (
+            4269,
+            "NAD83 / California Albers",
+            Projection::albers_equal_area(0.0, -120.0, 34.0, 40.5, 0.0, -4_000_000.0),
+        ),
+        3005 => (
+            4269,
+            "NAD83 / BC Albers",
+            Projection::albers_equal_area(45.0, -126.0, 50.0, 58.5, 1_000_000.0, 0.0),
+        ),
+        3577 => (4283, "GDA94 / Australian Albers", Projection::albers_equal_area(0.0, 132.0, -18.0, -36.0, 0.0, 0.0)),
+        3112 => (
+            4283,
+            "GDA94 / Geoscience Australia Lambert",
+            Projection::lambert_conformal_conic(0.0, 134.0, -18.0, -36.0, 0.0, 0.0),
+        ),
+        3347 => (
+            4269,
+            "NAD83 / Statistics Canada Lambert",
+            Projection::lambert_conformal_conic(63.390675, -91.866_666_666_666_7, 49.0, 77.0, 6_200_000.0, 3_000_000.0),
+        ),
+        2193 => (
+            4167,
+            "NZGD2000 / New Zealand Transverse Mercator 2000",
+            Projection::transverse_mercator(0.0, 173.0, 0.9996, 1_600_000.0, 10_000_000.0),
+        ),
+        3413 => (
+            4326,
+            "WGS 84 / NSIDC Sea Ice Polar Stereographic North",
+            Projection::polar_stereographic(70.0, -45.0, 0.0, 0.0),
+        ),
+        3976 => (
+            4326,
+            "WGS 84 / NSIDC Sea Ice Polar Stereographic South",
+            Projection::polar_stereographic(-70.0, 0.0, 0.0, 0.0),
+        ),
+        3031 => (4326, "WGS 84 / Antarctic Polar Stereographic", Projection::polar_stereographic(-71.0, 0.0, 0.0, 0.0)),
+        3995 => (4326, "WGS 84 / Arctic Polar Stereographic", Projection::polar_stereographic(71.0, 0.0, 0.0, 0.0)),
+        31466..=31469 => {
+            let zone = code - 31464;
+            let projection =
+                Projection::transverse_mercator(0.0, zone as f64 * 3.0, 1.0, zone as f64 * 1_000_000.0 + 500_000.0, 0.0);
+            let (base_name, datum) = base(4314);
+            let name = format!("{} / 3-degree Gauss-Kruger zone {}", base_name, zone);
+            return Some(CrsDefinition::projected(name, datum, projection));
+        }
+        2263 => {
+            let projection = Projection::lambert_conformal_conic(
+                40.166_666_666_666_7,
+                -74.0,
+                41.033_333_333_333_3,
+                40.666_666_666_666_7,
+                300_000.0,
+                0.0,
+            );
+            let (_, datum) = base(4269);
+            return Some(
+                CrsDefinition::projected("NAD83 / New York Long Island (ftUS)", datum, projection).with_to_meter(US_FOOT),
+            );
+        }
+        _ => return None,
+    };
+
+    let (_, datum) = base(base_code);
+    Some(CrsDefinition::projected(name, datum, projection))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let utm = lookup(32633).unwrap();
+        assert_eq!(utm.name, "WGS 84 / UTM zone 33N");
+        assert_eq!(utm.epsg, Some(32633));
+        assert_eq!(utm.projection.lon_0, 15.0);
+
+        let mga = lookup(28356).unwrap();
+        assert_eq!(mga.name, "GDA94 / MGA zone 56");
+        assert_eq!(mga.projection.false_northing, 10_000_000.0);
+
+        assert!(lookup(4326).unwrap().is_geographic());
+        assert_eq!(lookup(31467).unwrap().projection.false_easting, 3_500_000.0);
+        assert!(lookup(2056).is_none());
+        assert!(lookup(32661).is_some());
+    }
+
+    #[test]
+    fn test_codes() {
+        let codes = codes();
+        assert!(codes.iter().all(|code| contains(*code) && lookup(*code).is_some()));
+        assert!(codes.contains(&25832));
+        assert!(!contains(32600));
+    }
+}
@@ -2,7 +2,13 @@
 //!
 //! This module provides comprehensive support for coordinate reference systems,
 //! including common projections (WGS84, Web Mercator, UTM) and custom projections
-//! using PROJ strings and EPSG codes.
+//! using PROJ strings, WKT, PROJJSON and EPSG codes.
+//!
+//! Transformations use PROJ when the `proj-transform` feature is enabled.
+//! Without it, a built-in pure Rust engine handles Transverse Mercator/UTM,
+//! Mercator, Web Mercator, Lambert Conformal Conic, Albers Equal Area and
+//! Polar Stereographic projections, Helmert datum shifts, and the EPSG codes
+//! in [`epsg`].
 //!
 //! # Examples
 //!
@@ -21,14 +27,29 @@
 //! let custom = Crs::from_proj_string("+proj=lcc +lat_0=40 +lon_0=-100")?;
 //! ```
 
+mod datum;
+mod engine;
+pub mod epsg;
+mod parse;
+mod projection;
+
+pub use datum::{Datum, Ellipsoid, Helmert};
+pub use engine::{CrsDefinition, Transformer};
+pub use projection::{Method, Projection};
+
 use crate::error::{MeridianError, Result};
 #[cfg(feature = "proj-transform")]
 use proj::Proj;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-#[cfg(feature = "proj-transform")]
 use std::sync::Arc;
 
+/// OGC URI of the CRS84 coordinate reference system
+pub const CRS84_URI: &str = "http://www.opengis.net/def/crs/OGC/1.3/CRS84";
+
+/// PROJ identifier of CRS84
+const CRS84_PROJ_STRING: &str = "OGC:CRS84";
+
 /// Represents a Coordinate Reference System.
 ///
 /// A CRS defines how coordinates map to locations on Earth (or other surfaces).
@@ -51,6 +72,15 @@ pub struct Crs {
     #[serde(skip)]
     #[allow(dead_code)]
     proj: Option<Arc<Proj>>,
+
+    /// Built-in definition, resolved on creation or first use
+    #[serde(skip)]
+    definition: Option<Arc<CrsDefinition>>,
+
+    /// Cached built-in transformation, keyed by the target PROJ string
+    #[serde(skip)]
+    #[cfg_attr(feature = "proj-transform", allow(dead_code))]
+    transformer: Option<(String, Arc<Transformer>)>,
 }
 
 impl Crs {
@@ -74,6 +104,8 @@ impl Crs {
             name: "WGS 84".to_string(),
             #[cfg(feature = "proj-transform")]
             proj: None,
+            definition: epsg::lookup(4326).map(Arc::new),
+            transformer: None,
         }
     }
 
@@ -97,6 +129,8 @@ impl Crs {
             name: "WGS 84 / Pseudo-Mercator".to_string(),
             #[cfg(feature = "proj-transform")]
             proj: None,
+            definition: epsg::lookup(3857).map(Arc::new),
+            transformer: None,
         }
     }
 
@@ -135,6 +169,8 @@ impl Crs {
             name: format!("WGS 84 / UTM zone {}{}", zone, if north { "N" } else { "S" }),
             #[cfg(feature = "proj-transform")]
             proj: None,
+            definition: epsg::lookup(epsg).map(Arc::new),
+            transformer: None,
         }
     }
 
@@ -162,15 +198,21 @@ impl Crs {
         Proj::new(&proj_string)
             .map_err(|e| MeridianError::InvalidCrs(format!("Invalid EPSG code {}: {}", code, e)))?;
 
+        let definition = epsg::lookup(code);
         Ok(Self {
             epsg: Some(code),
             proj_string,
-            name: format!("EPSG:{}", code),
+            name: definition.as_ref().map_or_else(|| format!("EPSG:{}", code), |d| d.name.clone()),
             proj: None,
+            definition: definition.map(Arc::new),
+            transformer: None,
         })
     }
 
-    /// Creates a CRS from an EPSG code (without validation when proj-transform feature is disabled).
+    /// Creates a CRS from an EPSG code in the built-in registry.
+    ///
+    /// Without the `proj-transform` feature only the codes of [`epsg`] are
+    /// available.
     ///
     /// # Arguments
     ///
@@ -178,7 +220,7 @@ impl Crs {
     ///
     /// # Returns
     ///
-    /// A CRS instance
+    /// A CRS instance or an error if the code is not in the built-in registry
     ///
     /// # Examples
     ///
@@ -188,11 +230,16 @@ impl Crs {
     /// ```
     #[cfg(not(feature = "proj-transform"))]
     pub fn from_epsg(code: u32) -> Result<Self> {
-        let proj_string = format!("EPSG:{}", code);
+        let definition = epsg::lookup(code).ok_or_else(|| {
+            MeridianError::InvalidCrs(format!("Invalid EPSG code {}: not in the built-in registry", code))
+        })?;
+
         Ok(Self {
             epsg: Some(code),
-            proj_string,
-            name: format!("EPSG:{}", code),
+            proj_string: format!("EPSG:{}", code),
+            name: definition.name.clone(),
+            definition: Some(Arc::new(definition)),
+            transformer: None,
         })
     }
 
@@ -228,12 +275,15 @@ impl Crs {
             proj_string: proj_string.clone(),
             name: proj_string,
             proj: None,
+            definition: None,
+            transformer: None,
         })
     }
 
-    /// Creates a CRS from a PROJ string (without validation when proj-transform feature is disabled).
+    /// Creates a CRS from a PROJ string using the built-in parser.
     ///
     /// PROJ strings define projections using a key-value parameter format.
+    /// `EPSG:` codes of the built-in registry are accepted as well.
     ///
     /// # Arguments
     ///
@@ -241,7 +291,7 @@ impl Crs {
     ///
     /// # Returns
     ///
-    /// A CRS instance
+    /// A CRS instance or an error if the PROJ string is invalid or unsupported
     ///
     /// # Examples
     ///
@@ -253,13 +303,121 @@ impl Crs {
     #[cfg(not(feature = "proj-transform"))]
     pub fn from_proj_string(proj_string: impl Into<String>) -> Result<Self> {
         let proj_string = proj_string.into();
+        let definition = parse::parse(&proj_string)?;
+
         Ok(Self {
-            epsg: None,
+            epsg: definition.epsg,
             proj_string: proj_string.clone(),
             name: proj_string,
+            definition: Some(Arc::new(definition)),
+            transformer: None,
+        })
+    }
+
+    /// Creates a CRS from a WKT 1 or WKT 2 definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `wkt` - The WKT definition
+    ///
+    /// # Returns
+    ///
+    /// A CRS instance or an error if the WKT is invalid or unsupported
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let crs = Crs::from_wkt(r#"GEOGCRS["WGS 84", ...]"#)?;
+    /// ```
+    pub fn from_wkt(wkt: &str) -> Result<Self> {
+        Self::from_definition(wkt, parse::parse_wkt)
+    }
+
+    /// Creates a CRS from a PROJJSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The PROJJSON definition
+    ///
+    /// # Returns
+    ///
+    /// A CRS instance or an error if the PROJJSON is invalid or unsupported
+    pub fn from_projjson(json: &str) -> Result<Self> {
+        Self::from_definition(json, parse::parse_projjson)
+    }
+
+    /// Creates a CRS from a WKT or PROJJSON text, kept as the PROJ string.
+    ///
+    /// With PROJ available, the text only has to be valid for PROJ; the
+    /// built-in definition is used when it can be parsed.
+    fn from_definition(text: &str, parser: fn(&str) -> Result<CrsDefinition>) -> Result<Self> {
+        let text = text.trim();
+
+        #[cfg(feature = "proj-transform")]
+        let definition = {
+            Proj::new(text).map_err(|e| MeridianError::InvalidCrs(format!("Invalid CRS definition: {}", e)))?;
+            parser(text).ok()
+        };
+        #[cfg(not(feature = "proj-transform"))]
+        let definition = Some(parser(text)?);
+
+        Ok(Self {
+            epsg: definition.as_ref().and_then(|d| d.epsg),
+            proj_string: text.to_string(),
+            name: definition.as_ref().map_or_else(|| text.to_string(), |d| d.name.clone()),
+            #[cfg(feature = "proj-transform")]
+            proj: None,
+            definition: definition.map(Arc::new),
+            transformer: None,
         })
     }
 
+    /// Returns the built-in definition of this CRS.
+    ///
+    /// # Returns
+    ///
+    /// The definition, or an error if the CRS is not supported by the
+    /// built-in engine
+    pub fn definition(&self) -> Result<Arc<CrsDefinition>> {
+        if let Some(definition) = &self.definition {
+            return Ok(Arc::clone(definition));
+        }
+        match self.epsg.and_then(epsg::lookup) {
+            Some(definition) => Ok(Arc::new(definition)),
+            None => parse::parse(&self.proj_string).map(Arc::new),
+        }
+    }
+
+    /// Creates a built-in transformation from this CRS to another CRS.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target CRS
+    ///
+    /// # Returns
+    ///
+    /// A transformer, or an error if either CRS is not supported by the
+    /// built-in engine
+    pub fn transformer(&self, target: &Crs) -> Result<Transformer> {
+        Transformer::new(&*self.definition()?, &*target.definition()?)
+    }
+
+    /// Returns the CRS as WKT.
+    ///
+    /// A CRS created from WKT keeps its text; others are written as WKT 1
+    /// from the built-in definition.
+    ///
+    /// # Returns
+    ///
+    /// The WKT, or an error if the CRS is not supported by the built-in
+    /// engine
+    pub fn to_wkt(&self) -> Result<String> {
+        if self.proj_string.ends_with(']') {
+            return Ok(self.proj_string.clone());
+        }
+        Ok(self.definition()?.to_wkt())
+    }
+
     /// Gets or creates the PROJ transformation object for this CRS.
     ///
     /// This is used internally for coordinate transformations. The PROJ object
@@ -317,17 +475,19 @@ impl Crs {
         Ok(result)
     }
 
-    /// Transforms a coordinate from this CRS to another CRS (stub when proj-transform feature is disabled).
+    /// Transforms a coordinate from this CRS to another CRS with the built-in engine.
+    ///
+    /// The transformation is cached for repeated calls with the same target.
     ///
     /// # Arguments
     ///
-    /// * `_x` - The x coordinate (longitude or easting)
-    /// * `_y` - The y coordinate (latitude or northing)
+    /// * `x` - The x coordinate (longitude or easting)
+    /// * `y` - The y coordinate (latitude or northing)
     /// * `target` - The target CRS
     ///
     /// # Returns
     ///
-    /// Returns the original coordinates unchanged or an error if CRS differs
+    /// A tuple (x, y) in the target CRS, or an error if transformation fails
     ///
     /// # Examples
     ///
@@ -344,10 +504,157 @@ impl Crs {
             return Ok((x, y));
         }
 
-        // Without proj feature, we cannot transform between different CRS
-        Err(MeridianError::TransformError(
-            "Coordinate transformation requires the 'proj-transform' feature to be enabled".to_string()
-        ))
+        self.cached_transformer(target)?.transform(x, y)
+    }
+
+    /// Transforms a coordinate and its height from this CRS to another CRS.
+    ///
+    /// Heights are ellipsoidal, in meters. PROJ transformations leave the
+    /// height unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x coordinate (longitude or easting)
+    /// * `y` - The y coordinate (latitude or northing)
+    /// * `z` - The height above the ellipsoid
+    /// * `target` - The target CRS
+    ///
+    /// # Returns
+    ///
+    /// A tuple (x, y, z) in the target CRS, or an error if transformation fails
+    #[cfg(feature = "proj-transform")]
+    pub fn transform_point_3d(&mut self, x: f64, y: f64, z: f64, target: &Crs) -> Result<(f64, f64, f64)> {
+        let (x, y) = self.transform_point(x, y, target)?;
+        Ok((x, y, z))
+    }
+
+    /// Transforms a coordinate and its height from this CRS to another CRS.
+    ///
+    /// Heights are ellipsoidal, in meters, and change only when the
+    /// transformation shifts between datums.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The x coordinate (longitude or easting)
+    /// * `y` - The y coordinate (latitude or northing)
+    /// * `z` - The height above the ellipsoid
+    /// * `target` - The target CRS
+    ///
+    /// # Returns
+    ///
+    /// A tuple (x, y, z) in the target CRS, or an error if transformation fails
+    #[cfg(not(feature = "proj-transform"))]
+    pub fn transform_point_3d(&mut self, x: f64, y: f64, z: f64, target: &Crs) -> Result<(f64, f64, f64)> {
+        if self.proj_string == target.proj_string {
+            return Ok((x, y, z));
+        }
+
+        self.cached_transformer(target)?.transform_3d(x, y, z)
+    }
+
+    /// Returns the built-in transformation to `target`, caching the last one.
+    #[cfg(not(feature = "proj-transform"))]
+    fn cached_transformer(&mut self, target: &Crs) -> Result<Arc<Transformer>> {
+        match &self.transformer {
+            Some((key, transformer)) if *key == target.proj_string => Ok(Arc::clone(transformer)),
+            _ => {
+                let transformer = self
+                    .transformer(target)
+                    .map(Arc::new)
+                    .map_err(|e| MeridianError::TransformError(format!("Failed to create transformer: {}", e)))?;
+                self.transformer = Some((target.proj_string.clone(), Arc::clone(&transformer)));
+                Ok(transformer)
+            }
+        }
+    }
+
+    /// Creates the OGC CRS84 coordinate reference system.
+    ///
+    /// CRS84 is WGS84 with longitude/latitude axis order. It is the default
+    /// CRS of GeoJSON and of the OGC API standards, whereas EPSG:4326 is
+    /// formally latitude first.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let crs84 = Crs::crs84();
+    /// assert!(crs84.is_crs84());
+    /// assert_eq!(crs84.epsg, Some(4326));
+    /// ```
+    pub fn crs84() -> Self {
+        Self {
+            epsg: Some(4326),
+            proj_string: CRS84_PROJ_STRING.to_string(),
+            name: "WGS 84 (CRS84)".to_string(),
+            #[cfg(feature = "proj-transform")]
+            proj: None,
+            definition: epsg::lookup(4326).map(Arc::new),
+            transformer: None,
+        }
+    }
+
+    /// Creates a CRS from an OGC CRS identifier.
+    ///
+    /// Accepts OGC URIs (`http://www.opengis.net/def/crs/EPSG/0/3857`),
+    /// URNs (`urn:ogc:def:crs:EPSG::3857`), the short `EPSG:3857` form and
+    /// the CRS84 identifiers (`CRS:84`, `OGC:CRS84` and their URIs).
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The CRS identifier
+    ///
+    /// # Returns
+    ///
+    /// A CRS instance or an error if the identifier is not recognized
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let crs = Crs::from_uri("http://www.opengis.net/def/crs/EPSG/0/3857")?;
+    /// assert_eq!(crs.epsg, Some(3857));
+    /// ```
+    pub fn from_uri(uri: &str) -> Result<Self> {
+        let trimmed = uri.trim();
+        let upper = trimmed.to_ascii_uppercase();
+
+        let crs84 = upper.ends_with("/OGC/1.3/CRS84")
+            || upper.ends_with(":OGC:1.3:CRS84")
+            || matches!(upper.as_str(), "CRS84" | "CRS:84" | "OGC:CRS84");
+        if crs84 {
+            return Ok(Self::crs84());
+        }
+
+        let code = if let Some(rest) = upper.strip_prefix("EPSG:") {
+            Some(rest)
+        } else if upper.starts_with("URN:OGC:DEF:CRS:EPSG:") {
+            upper.rsplit(':').next()
+        } else if upper.contains("/DEF/CRS/EPSG/") {
+            upper.trim_end_matches('/').rsplit('/').next()
+        } else {
+            None
+        };
+
+        code.and_then(|c| c.parse::<u32>().ok())
+            .ok_or_else(|| MeridianError::InvalidCrs(format!("Unrecognized CRS identifier: {}", trimmed)))
+            .and_then(Self::from_epsg)
+    }
+
+    /// Returns the OGC URI identifying this CRS.
+    ///
+    /// # Returns
+    ///
+    /// The URI, or `None` for CRSs without an EPSG code
+    pub fn uri(&self) -> Option<String> {
+        if self.is_crs84() {
+            return Some(CRS84_URI.to_string());
+        }
+        self.epsg
+            .map(|code| format!("http://www.opengis.net/def/crs/EPSG/0/{}", code))
+    }
+
+    /// Checks if this CRS is CRS84 (WGS84 in longitude/latitude order).
+    pub fn is_crs84(&self) -> bool {
+        self.proj_string == CRS84_PROJ_STRING
     }
 
     /// Checks if this CRS is geographic (uses lat/lon coordinates).
@@ -356,6 +663,10 @@ impl Crs {
     ///
     /// `true` if the CRS uses geographic coordinates (degrees)
     pub fn is_geographic(&self) -> bool {
+        if let Some(definition) = &self.definition {
+            return definition.is_geographic();
+        }
+
         // Common geographic EPSG codes
         if let Some(epsg) = self.epsg {
             matches!(epsg, 4326 | 4269 | 4258 | 4167)
@@ -444,6 +755,27 @@ mod tests {
         Crs::utm(61, true);
     }
 
+    #[test]
+    fn test_crs_uri() {
+        let crs84 = Crs::from_uri(CRS84_URI).unwrap();
+        assert!(crs84.is_crs84());
+        assert!(crs84.is_geographic());
+        assert_eq!(crs84.uri().as_deref(), Some(CRS84_URI));
+        assert!(!Crs::wgs84().is_crs84());
+
+        for uri in [
+            "http://www.opengis.net/def/crs/EPSG/0/3857",
+            "urn:ogc:def:crs:EPSG::3857",
+            "EPSG:3857",
+        ] {
+            let crs = Crs::from_uri(uri).unwrap();
+            assert_eq!(crs.epsg, Some(3857));
+            assert_eq!(crs.uri().as_deref(), Some("http://www.opengis.net/def/crs/EPSG/0/3857"));
+        }
+
+        assert!(Crs::from_uri("http://example.com/crs/foo").is_err());
+    }
+
     #[test]
     #[cfg(feature = "proj-transform")]
     fn test_from_epsg() {
@@ -458,4 +790,56 @@ mod tests {
         let wgs84_2 = Crs::from_epsg(4326).unwrap();
         assert_eq!(wgs84_1, wgs84_2);
     }
+
+    #[test]
+    #[cfg(not(feature = "proj-transform"))]
+    fn test_builtin_from_epsg() {
+        let crs = Crs::from_epsg(25832).unwrap();
+        assert_eq!(crs.name, "ETRS89 / UTM zone 32N");
+        assert!(crs.is_projected());
+        assert_eq!(crs, Crs::from_uri("urn:ogc:def:crs:EPSG::25832").unwrap());
+        let wkt = crs.to_wkt().unwrap();
+        assert!(wkt.starts_with(r#"PROJCS["ETRS89 / UTM zone 32N",GEOGCS["#), "{}", wkt);
+        assert_eq!(Crs::from_wkt(&wkt).unwrap(), crs);
+        assert_eq!(Crs::from_wkt(&wkt).unwrap().to_wkt().unwrap(), wkt);
+
+        assert!(Crs::from_epsg(2056).is_err());
+        assert!(Crs::from_epsg(0).is_err());
+        assert!(Crs::from_proj_string("+proj=longlat +datum=WGS84").unwrap().is_geographic());
+        assert!(Crs::from_proj_string("+proj=robin").is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "proj-transform"))]
+    fn test_builtin_transform() {
+        let mut wgs84 = Crs::wgs84();
+        let (x, y) = wgs84.transform_point(-122.4194, 37.7749, &Crs::web_mercator()).unwrap();
+        assert!((x - -13_627_665.27).abs() < 0.01);
+        assert!((y - 4_547_675.35).abs() < 0.01);
+
+        // Same definition from PROJJSON, WKT and a PROJ string
+        let projjson = r#"{"type": "ProjectedCRS", "name": "WGS 84 / UTM zone 33N",
+            "base_crs": {"name": "WGS 84", "datum": {"name": "World Geodetic System 1984",
+                "ellipsoid": {"name": "WGS 84", "semi_major_axis": 6378137, "inverse_flattening": 298.257223563}}},
+            "conversion": {"name": "UTM zone 33N", "method": {"name": "Transverse Mercator"},
+                "parameters": [
+                    {"name": "Longitude of natural origin", "value": 15, "unit": "degree"},
+                    {"name": "Scale factor at natural origin", "value": 0.9996, "unit": "unity"},
+                    {"name": "False easting", "value": 500000, "unit": "metre"}]}}"#;
+        let wkt = r#"PROJCS["UTM 33N",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]],
+            PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],
+            PARAMETER["central_meridian",15],PARAMETER["scale_factor",0.9996],PARAMETER["false_easting",500000],
+            UNIT["metre",1]]"#;
+        let expected = wgs84.transform_point(15.5, 52.0, &Crs::utm(33, true)).unwrap();
+        for target in [
+            Crs::from_projjson(projjson).unwrap(),
+            Crs::from_wkt(wkt).unwrap(),
+            Crs::from_proj_string("+proj=utm +zone=33 +datum=WGS84").unwrap(),
+        ] {
+            let (x, y) = wgs84.transform_point(15.5, 52.0, &target).unwrap();
+            assert!((x - expected.0).abs() < 1e-6 && (y - expected.1).abs() < 1e-6);
+        }
+
+        assert!(Crs::from_wkt("GEOGCS[").is_err());
+    }
 }
@@ -0,0 +1,1080 @@
+//! Parsing of WKT (1 and 2), PROJJSON and PROJ string CRS definitions.
+
+use super::datum::{Datum, Ellipsoid, Helmert};
+use super::engine::CrsDefinition;
+use super::epsg;
+use super::projection::{Method, Projection};
+use crate::error::{MeridianError, Result};
+use serde_json::Value;
+use std::f64::consts::PI;
+
+/// US survey foot in meters
+const US_FOOT: f64 = 1200.0 / 3937.0;
+
+/// Parses a CRS definition, detecting its syntax.
+///
+/// Accepts `EPSG:n` codes, PROJ strings, PROJJSON and WKT 1 or 2.
+pub fn parse(text: &str) -> Result<CrsDefinition> {
+    let text = text.trim();
+    if let Some(code) = epsg_code(text) {
+        return epsg::lookup(code).ok_or_else(|| unknown_epsg(code));
+    }
+    if text.starts_with('{') {
+        parse_projjson(text)
+    } else if text.starts_with('+') || text.contains("+proj=") || text.contains("+init=") {
+        parse_proj_string(text)
+    } else {
+        parse_wkt(text)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> MeridianError {
+    MeridianError::InvalidCrs(message.into())
+}
+
+fn unknown_epsg(code: u32) -> MeridianError {
+    invalid(format!("EPSG:{} is not in the built-in EPSG registry", code))
+}
+
+/// Code of an `EPSG:n` identifier
+fn epsg_code(text: &str) -> Option<u32> {
+    let (authority, code) = text.split_once(':')?;
+    authority.eq_ignore_ascii_case("EPSG").then(|| code.trim().parse().ok())?
+}
+
+/// Lowercase alphanumeric form of a name, for matching
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Angle unit names and their size in radians
+fn angle_unit(name: &str) -> Option<f64> {
+    match normalize(name).as_str() {
+        "degree" | "degrees" | "deg" => Some(PI / 180.0),
+        "radian" | "radians" | "rad" => Some(1.0),
+        "grad" | "gon" | "grads" => Some(PI / 200.0),
+        "arcsecond" | "arcseconds" => Some(PI / 648_000.0),
+        _ => None,
+    }
+}
+
+/// Converts an angle to degrees, keeping values already in degrees exact
+fn to_degrees(value: f64, radians_per_unit: f64) -> f64 {
+    if (radians_per_unit / (PI / 180.0) - 1.0).abs() < 1e-12 {
+        value
+    } else {
+        (value * radians_per_unit).to_degrees()
+    }
+}
+
+/// Length unit names and their size in meters
+fn length_unit(name: &str) -> Option<f64> {
+    match normalize(name).as_str() {
+        "m" | "metre" | "meter" | "metres" | "meters" => Some(1.0),
+        "km" | "kilometre" | "kilometer" => Some(1000.0),
+        "ft" | "foot" | "feet" | "internationalfoot" => Some(0.3048),
+        "usft" | "ussurveyfoot" | "footus" | "usfoot" => Some(US_FOOT),
+        _ => None,
+    }
+}
+
+/// Canonical projection parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    Lat0,
+    Lon0,
+    Lat1,
+    Lat2,
+    K0,
+    X0,
+    Y0,
+}
+
+impl Param {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match normalize(name).as_str() {
+            "latitudeofnaturalorigin" | "latitudeoffalseorigin" | "latitudeoforigin" | "latitudeofcenter"
+            | "latitudeofprojectioncentre" | "latitudeofprojectioncenter" => Param::Lat0,
+            "longitudeofnaturalorigin" | "longitudeoffalseorigin" | "longitudeoforigin" | "centralmeridian"
+            | "longitudeofcenter" | "longitudeofprojectioncentre" | "longitudeofprojectioncenter" => Param::Lon0,
+            "latitudeof1ststandardparallel" | "standardparallel1" | "latitudeofstandardparallel" => Param::Lat1,
+            "latitudeof2ndstandardparallel" | "standardparallel2" => Param::Lat2,
+            "scalefactoratnaturalorigin" | "scalefactor" | "scalefactoratprojectioncentre" => Param::K0,
+            "falseeasting" | "eastingatfalseorigin" | "eastingatprojectioncentre" => Param::X0,
+            "falsenorthing" | "northingatfalseorigin" | "northingatprojectioncentre" => Param::Y0,
+            _ => return None,
+        })
+    }
+
+    fn is_angle(self) -> bool {
+        matches!(self, Param::Lat0 | Param::Lon0 | Param::Lat1 | Param::Lat2)
+    }
+
+    fn is_length(self) -> bool {
+        matches!(self, Param::X0 | Param::Y0)
+    }
+}
+
+/// Projection parameters in degrees, meters and unity
+#[derive(Debug, Default)]
+struct Params(Vec<(Param, f64)>);
+
+impl Params {
+    fn get(&self, param: Param) -> Option<f64> {
+        self.0.iter().find(|(p, _)| *p == param).map(|(_, v)| *v)
+    }
+
+    fn set(&mut self, param: Param, value: f64) {
+        self.0.retain(|(p, _)| *p != param);
+        self.0.push((param, value));
+    }
+}
+
+/// Builds a projection from a method name and its parameters.
+fn build_projection(method: &str, params: &Params) -> Result<Projection> {
+    let get = |param| params.get(param).unwrap_or(0.0);
+    let normalized = normalize(method);
+
+    let method_kind = match normalized.as_str() {
+        "transversemercator" | "gausskruger" | "gaussschreibertransversemercator" => Method::TransverseMercator,
+        "mercator" | "mercator1sp" | "mercatorvarianta" | "mercator2sp" | "mercatorvariantb" => Method::Mercator,
+        "popularvisualisationpseudomercator" | "pseudomercator" | "mercatorauxiliarysphere" | "googlemercator"
+        | "webmercator" => Method::WebMercator,
+        "lambertconicconformal1sp" | "lambertconicconformal2sp" | "lambertconformalconic"
+        | "lambertconformalconic1sp" | "lambertconformalconic2sp" => Method::LambertConformalConic,
+        "albersequalarea" | "albersconicequalarea" | "albers" => Method::AlbersEqualArea,
+        "polarstereographicvarianta" | "polarstereographicvariantb" | "polarstereographic"
+        | "stereographicnorthpole" | "stereographicsouthpole" => Method::PolarStereographic,
+        _ => return Err(invalid(format!("Unsupported projection method: {}", method))),
+    };
+
+    let mut projection = Projection::new(method_kind);
+    projection.lat_0 = get(Param::Lat0);
+    projection.lon_0 = get(Param::Lon0);
+    projection.k_0 = params.get(Param::K0).unwrap_or(1.0);
+    projection.false_easting = get(Param::X0);
+    projection.false_northing = get(Param::Y0);
+
+    match method_kind {
+        Method::Mercator => {
+            projection.lat_ts = params.get(Param::Lat1);
+        }
+        Method::LambertConformalConic | Method::AlbersEqualArea => {
+            let lat_1 = params.get(Param::Lat1).unwrap_or(projection.lat_0);
+            projection.lat_1 = lat_1;
+            projection.lat_2 = params.get(Param::Lat2).unwrap_or(lat_1);
+        }
+        Method::PolarStereographic => {
+            if let Some(lat_ts) = params.get(Param::Lat1) {
+                projection.lat_ts = Some(lat_ts);
+                if normalized == "stereographicsouthpole" {
+                    projection.lat_ts = Some(-lat_ts.abs());
+                }
+            } else if (projection.lat_0.abs() - 90.0).abs() > 1e-9 {
+                // GDAL's Polar_Stereographic puts the latitude of true scale in latitude_of_origin
+                projection.lat_ts = Some(projection.lat_0);
+            }
+            projection.lat_0 = 90.0_f64.copysign(projection.lat_ts.unwrap_or(projection.lat_0));
+        }
+        _ => {}
+    }
+    Ok(projection)
+}
+
+/// Helmert transformation from named parameters in meters, arc-seconds and ppm
+fn build_helmert(method: &str, params: &[(String, f64)]) -> Result<Helmert> {
+    let get = |names: &[&str]| {
+        params
+            .iter()
+            .find(|(name, _)| names.contains(&normalize(name).as_str()))
+            .map_or(0.0, |(_, value)| *value)
+    };
+    let values = [
+        get(&["xaxistranslation", "dx"]),
+        get(&["yaxistranslation", "dy"]),
+        get(&["zaxistranslation", "dz"]),
+        get(&["xaxisrotation", "rx"]),
+        get(&["yaxisrotation", "ry"]),
+        get(&["zaxisrotation", "rz"]),
+        get(&["scaledifference", "ds"]),
+    ];
+    let [tx, ty, tz, rx, ry, rz, ds] = values;
+
+    let method = normalize(method);
+    if method.starts_with("coordinateframe") {
+        Ok(Helmert::from_coordinate_frame(tx, ty, tz, rx, ry, rz, ds))
+    } else if method.starts_with("positionvector") || method.starts_with("geocentrictranslation") {
+        Ok(Helmert::new(tx, ty, tz, rx, ry, rz, ds))
+    } else {
+        Err(invalid(format!("Unsupported datum transformation: {}", method)))
+    }
+}
+
+/// Datum of a known name, with its transformation to WGS 84
+fn known_datum(name: &str) -> Option<Datum> {
+    let mut name = normalize(name);
+    for suffix in ["ensemble", "v1"] {
+        if let Some(stripped) = name.strip_suffix(suffix) {
+            name = stripped.to_string();
+        }
+    }
+    let code = match name.as_str() {
+        "wgs84" | "wgs1984" | "dwgs1984" | "worldgeodeticsystem1984" => 4326,
+        "etrs89" | "etrs1989" | "detrs1989" | "europeanterrestrialreferencesystem1989" => 4258,
+        "nad83" | "northamericandatum1983" | "dnorthamerican1983" => 4269,
+        "nad27" | "northamericandatum1927" | "dnorthamerican1927" => 4267,
+        "osgb36" | "osgb1936" | "dosgb1936" | "ordnancesurveyofgreatbritain1936" => 4277,
+        "gda94" | "geocentricdatumofaustralia1994" | "dgda1994" => 4283,
+        "gda2020" | "geocentricdatumofaustralia2020" | "dgda2020" => 7844,
+        "ed50" | "europeandatum1950" | "deuropean1950" => 4230,
+        "dhdn" | "deutscheshauptdreiecksnetz" | "ddeutscheshauptdreiecksnetz" | "potsdam" => 4314,
+        "nzgd2000" | "newzealandgeodeticdatum2000" | "dnzgd2000" => 4167,
+        "rgf93" | "reseaugeodesiquefrancais1993" | "drgf1993" => 4171,
+        "sirgas2000" | "sistemadereferenciageocentricoparalasamericas2000" | "dsirgas2000" => 4674,
+        "jgd2000" | "japanesegeodeticdatum2000" | "djgd2000" => 4612,
+        "hartebeesthoek94" | "dhartebeesthoek1994" => 4148,
+        "cgcs2000" | "china2000" | "chinageodeticcoordinatesystem2000" | "dchina2000" => 4490,
+        _ => return None,
+    };
+    epsg::lookup(code).map(|definition| definition.datum)
+}
+
+// ---------------------------------------------------------------------------
+// WKT
+// ---------------------------------------------------------------------------
+
+/// A WKT value
+#[derive(Debug, Clone)]
+enum WktValue {
+    Text(String),
+    Number(f64),
+    /// Bare enumeration value, e.g. `east`
+    Keyword,
+    Node(WktNode),
+}
+
+/// A WKT keyword with its bracketed arguments
+#[derive(Debug, Clone)]
+struct WktNode {
+    keyword: String,
+    args: Vec<WktValue>,
+}
+
+impl WktNode {
+    fn text(&self, index: usize) -> Option<&str> {
+        match self.args.get(index)? {
+            WktValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn number(&self, index: usize) -> Option<f64> {
+        match self.args.get(index)? {
+            WktValue::Number(value) => Some(*value),
+            WktValue::Text(text) => text.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = &WktNode> {
+        self.args.iter().filter_map(|arg| match arg {
+            WktValue::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    fn child(&self, keywords: &[&str]) -> Option<&WktNode> {
+        self.children().find(|node| keywords.contains(&node.keyword.as_str()))
+    }
+
+    /// EPSG code from `ID` or `AUTHORITY`
+    fn epsg(&self) -> Option<u32> {
+        let id = self.child(&["ID", "AUTHORITY"])?;
+        if !id.text(0)?.eq_ignore_ascii_case("EPSG") {
+            return None;
+        }
+        id.number(1).map(|code| code as u32)
+    }
+
+    /// Size in SI units of a unit node child, e.g. `LENGTHUNIT["metre",1]`
+    fn unit(&self, keywords: &[&str]) -> Option<f64> {
+        self.child(keywords)?.number(1)
+    }
+}
+
+struct WktParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl WktParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '+'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let c = self.peek().ok_or_else(|| invalid("WKT: unterminated string"))?;
+            self.pos += 1;
+            if c != '"' {
+                text.push(c);
+            } else if self.peek() == Some('"') {
+                self.pos += 1;
+                text.push('"');
+            } else {
+                return Ok(text);
+            }
+        }
+    }
+
+    /// Parses `KEYWORD[arg, ...]`, with `(` and `)` accepted as brackets
+    fn node(&mut self, keyword: String) -> Result<WktNode> {
+        self.skip_whitespace();
+        if !matches!(self.peek(), Some('[' | '(')) {
+            return Err(invalid(format!("WKT: expected '[' after {}", keyword)));
+        }
+        self.pos += 1;
+
+        let mut args = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let value = match self.peek() {
+                Some('"') => WktValue::Text(self.quoted()?),
+                Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                    let word = self.word();
+                    WktValue::Number(word.parse().map_err(|_| invalid(format!("WKT: invalid number {}", word)))?)
+                }
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let word = self.word();
+                    self.skip_whitespace();
+                    if matches!(self.peek(), Some('[' | '(')) {
+                        WktValue::Node(self.node(word)?)
+                    } else {
+                        WktValue::Keyword
+                    }
+                }
+                _ => return Err(invalid("WKT: unexpected end of definition")),
+            };
+            args.push(value);
+
+            self.skip_whitespace();
+            let next = self.peek();
+            self.pos += 1;
+            match next {
+                Some(',') => continue,
+                Some(']' | ')') => break,
+                _ => return Err(invalid(format!("WKT: expected ',' or ']' in {}", keyword))),
+            }
+        }
+
+        Ok(WktNode {
+            keyword: keyword.to_ascii_uppercase(),
+            args,
+        })
+    }
+}
+
+fn parse_wkt_node(text: &str) -> Result<WktNode> {
+    let mut parser = WktParser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    parser.skip_whitespace();
+    let keyword = parser.word();
+    if keyword.is_empty() {
+        return Err(invalid("WKT: expected a keyword"));
+    }
+    let node = parser.node(keyword)?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(invalid("WKT: unexpected text after definition"));
+    }
+    Ok(node)
+}
+
+/// Parses a WKT 1 or WKT 2 CRS definition.
+pub fn parse_wkt(text: &str) -> Result<CrsDefinition> {
+    wkt_crs(&parse_wkt_node(text.trim())?)
+}
+
+const GEOGRAPHIC_KEYWORDS: &[&str] = &[
+    "GEOGCS",
+    "GEOGCRS",
+    "GEODCRS",
+    "GEOGRAPHICCRS",
+    "GEODETICCRS",
+    "BASEGEOGCRS",
+    "BASEGEODCRS",
+];
+
+fn wkt_crs(node: &WktNode) -> Result<CrsDefinition> {
+    // GDAL keeps the PROJ string of CRSs WKT 1 cannot express, e.g. Web Mercator
+    if let Some(extension) = node.child(&["EXTENSION"]) {
+        if extension.text(0).is_some_and(|name| name.eq_ignore_ascii_case("PROJ4")) {
+            if let Some(proj) = extension.text(1) {
+                let mut definition = parse_proj_string(proj)?;
+                definition.name = node.text(0).unwrap_or(proj).to_string();
+                definition.epsg = node.epsg();
+                return Ok(definition);
+            }
+        }
+    }
+
+    let mut definition = match node.keyword.as_str() {
+        keyword if GEOGRAPHIC_KEYWORDS.contains(&keyword) => wkt_geographic(node)?,
+        "PROJCS" | "PROJCRS" | "PROJECTEDCRS" => wkt_projected(node)?,
+        "BOUNDCRS" => return wkt_bound(node),
+        "COMPOUNDCRS" | "COMPD_CS" => {
+            let horizontal = node
+                .children()
+                .find(|child| child.keyword != "ID" && child.keyword != "AUTHORITY")
+                .ok_or_else(|| invalid("WKT: compound CRS without components"))?;
+            return wkt_crs(horizontal);
+        }
+        other => return Err(invalid(format!("Unsupported WKT CRS type: {}", other))),
+    };
+    definition.epsg = node.epsg();
+    Ok(definition)
+}
+
+fn wkt_geographic(node: &WktNode) -> Result<CrsDefinition> {
+    let name = node.text(0).unwrap_or_default().to_string();
+    let datum_node = node
+        .child(&["DATUM", "GEODETICDATUM", "TRF", "ENSEMBLE", "DATUMENSEMBLE"])
+        .ok_or_else(|| invalid(format!("WKT: {} has no datum", name)))?;
+    let datum_name = datum_node.text(0).unwrap_or_default();
+
+    let ellipsoid_node = datum_node
+        .child(&["ELLIPSOID", "SPHEROID"])
+        .ok_or_else(|| invalid(format!("WKT: datum {} has no ellipsoid", datum_name)))?;
+    let a = ellipsoid_node.number(1).ok_or_else(|| invalid("WKT: ellipsoid without semi-major axis"))?;
+    let inv_f = ellipsoid_node.number(2).unwrap_or(0.0);
+    let a = a * ellipsoid_node.unit(&["LENGTHUNIT", "UNIT"]).unwrap_or(1.0);
+    let ellipsoid = Ellipsoid::new(a, inv_f);
+
+    let to_wgs84 = match datum_node.child(&["TOWGS84"]) {
+        Some(towgs84) => {
+            let values: Vec<f64> = (0..towgs84.args.len()).filter_map(|i| towgs84.number(i)).collect();
+            Some(Helmert::from_towgs84(&values).ok_or_else(|| invalid("WKT: TOWGS84 needs 3 or 7 values"))?)
+        }
+        None => known_datum(datum_name)
+            .filter(|known| known.ellipsoid == ellipsoid)
+            .and_then(|known| known.to_wgs84),
+    };
+
+    let mut datum = Datum::new(datum_name, ellipsoid, to_wgs84);
+    if let Some(primem) = node.child(&["PRIMEM", "PRIMEMERIDIAN"]) {
+        let value = primem.number(1).unwrap_or(0.0);
+        let factor = primem.unit(&["ANGLEUNIT", "UNIT"]).unwrap_or(PI / 180.0);
+        datum.prime_meridian = to_degrees(value, factor);
+    }
+
+    Ok(CrsDefinition::geographic(name, datum))
+}
+
+fn wkt_projected(node: &WktNode) -> Result<CrsDefinition> {
+    let name = node.text(0).unwrap_or_default().to_string();
+    let base = node
+        .child(GEOGRAPHIC_KEYWORDS)
+        .ok_or_else(|| invalid(format!("WKT: {} has no base geographic CRS", name)))?;
+    let base_definition = wkt_geographic(base)?;
+
+    // Linear unit of the CRS, from UNIT (WKT 1), LENGTHUNIT or the axes (WKT 2)
+    let to_meter = node
+        .unit(&["UNIT", "LENGTHUNIT"])
+        .or_else(|| node.child(&["CS"]).and_then(|cs| cs.unit(&["LENGTHUNIT"])))
+        .or_else(|| {
+            node.children()
+                .filter(|child| child.keyword == "AXIS")
+                .find_map(|axis| axis.unit(&["LENGTHUNIT", "UNIT"]))
+        })
+        .unwrap_or(1.0);
+
+    let (method, params) = match node.child(&["CONVERSION"]) {
+        Some(conversion) => {
+            let method = conversion
+                .child(&["METHOD", "PROJECTION"])
+                .and_then(|method| method.text(0))
+                .ok_or_else(|| invalid("WKT: conversion without method"))?;
+            (method, wkt_params(conversion, PI / 180.0, 1.0))
+        }
+        None => {
+            let method = node
+                .child(&["PROJECTION"])
+                .and_then(|method| method.text(0))
+                .ok_or_else(|| invalid(format!("WKT: {} has no projection", name)))?;
+            let angle = base.unit(&["UNIT", "ANGLEUNIT"]).unwrap_or(PI / 180.0);
+            (method, wkt_params(node, angle, to_meter))
+        }
+    };
+
+    let projection = build_projection(method, &params)?;
+    Ok(CrsDefinition::projected(name, base_definition.datum, projection).with_to_meter(to_meter))
+}
+
+/// Projection parameters of a node, converted with the given default units
+fn wkt_params(node: &WktNode, angle: f64, length: f64) -> Params {
+    let mut params = Params::default();
+    for param in node.children().filter(|child| child.keyword == "PARAMETER") {
+        let (Some(kind), Some(value)) = (param.text(0).and_then(Param::from_name), param.number(1)) else {
+            continue;
+        };
+        let value = if kind.is_angle() {
+            to_degrees(value, param.unit(&["ANGLEUNIT", "UNIT"]).unwrap_or(angle))
+        } else if kind.is_length() {
+            value * param.unit(&["LENGTHUNIT", "UNIT"]).unwrap_or(length)
+        } else {
+            value * param.unit(&["SCALEUNIT", "UNIT"]).unwrap_or(1.0)
+        };
+        params.set(kind, value);
+    }
+    params
+}
+
+fn wkt_bound(node: &WktNode) -> Result<CrsDefinition> {
+    let source = node
+        .child(&["SOURCECRS"])
+        .and_then(|source| source.children().next())
+        .ok_or_else(|| invalid("WKT: bound CRS without source CRS"))?;
+    let mut definition = wkt_crs(source)?;
+
+    let transformation = node
+        .child(&["ABRIDGEDTRANSFORMATION"])
+        .ok_or_else(|| invalid("WKT: bound CRS without transformation"))?;
+    let method = transformation
+        .child(&["METHOD"])
+        .and_then(|method| method.text(0))
+        .unwrap_or_default();
+
+    let params: Vec<(String, f64)> = transformation
+        .children()
+        .filter(|child| child.keyword == "PARAMETER")
+        .filter_map(|param| {
+            let name = param.text(0)?.to_string();
+            let value = param.number(1)?;
+            let value = if let Some(factor) = param.unit(&["ANGLEUNIT"]) {
+                value * factor / (PI / 648_000.0)
+            } else if let Some(factor) = param.unit(&["SCALEUNIT"]) {
+                value * factor * 1e6
+            } else {
+                value * param.unit(&["LENGTHUNIT"]).unwrap_or(1.0)
+            };
+            Some((name, value))
+        })
+        .collect();
+
+    definition.datum.to_wgs84 = Some(build_helmert(method, &params)?);
+    Ok(definition)
+}
+
+// ---------------------------------------------------------------------------
+// PROJJSON
+// ---------------------------------------------------------------------------
+
+/// Parses a PROJJSON CRS definition.
+pub fn parse_projjson(text: &str) -> Result<CrsDefinition> {
+    let value: Value = serde_json::from_str(text).map_err(|e| invalid(format!("Invalid PROJJSON: {}", e)))?;
+    projjson_crs(&value)
+}
+
+/// Size in SI units of a PROJJSON unit, given as a name or an object
+fn projjson_unit(unit: Option<&Value>, default: f64) -> f64 {
+    match unit {
+        Some(Value::String(name)) => angle_unit(name)
+            .or_else(|| length_unit(name))
+            .or_else(|| match normalize(name).as_str() {
+                "unity" => Some(1.0),
+                "partspermillion" => Some(1e-6),
+                _ => None,
+            })
+            .unwrap_or(default),
+        Some(Value::Object(object)) => object
+            .get("conversion_factor")
+            .and_then(Value::as_f64)
+            .unwrap_or(default),
+        _ => default,
+    }
+}
+
+/// A PROJJSON number, either plain or as `{"value": .., "unit": ..}`
+fn projjson_number(value: Option<&Value>, default_unit: f64) -> Option<f64> {
+    match value? {
+        Value::Number(number) => number.as_f64().map(|v| v * default_unit),
+        Value::Object(object) => {
+            let number = object.get("value")?.as_f64()?;
+            Some(number * projjson_unit(object.get("unit"), default_unit))
+        }
+        _ => None,
+    }
+}
+
+fn projjson_epsg(value: &Value) -> Option<u32> {
+    let id = value.get("id").or_else(|| value.get("ids")?.get(0))?;
+    if !id.get("authority")?.as_str()?.eq_ignore_ascii_case("EPSG") {
+        return None;
+    }
+    match id.get("code")? {
+        Value::Number(code) => code.as_u64().map(|code| code as u32),
+        Value::String(code) => code.parse().ok(),
+        _ => None,
+    }
+}
+
+fn projjson_crs(value: &Value) -> Result<CrsDefinition> {
+    let kind = value.get("type").and_then(Value::as_str).unwrap_or_default();
+    let mut definition = match kind {
+        "GeographicCRS" | "GeodeticCRS" => projjson_geographic(value)?,
+        "ProjectedCRS" => projjson_projected(value)?,
+        "BoundCRS" => return projjson_bound(value),
+        "CompoundCRS" => {
+            let horizontal = value
+                .get("components")
+                .and_then(|components| components.get(0))
+                .ok_or_else(|| invalid("PROJJSON: compound CRS without components"))?;
+            return projjson_crs(horizontal);
+        }
+        other => return Err(invalid(format!("Unsupported PROJJSON CRS type: {}", other))),
+    };
+    definition.epsg = projjson_epsg(value);
+    Ok(definition)
+}
+
+fn projjson_geographic(value: &Value) -> Result<CrsDefinition> {
+    let name = value.get("name").and_then(Value::as_str).unwrap_or_default();
+    let datum_value = value
+        .get("datum")
+        .or_else(|| value.get("datum_ensemble"))
+        .ok_or_else(|| invalid(format!("PROJJSON: {} has no datum", name)))?;
+    let datum_name = datum_value.get("name").and_then(Value::as_str).unwrap_or_default();
+
+    let ellipsoid = match datum_value.get("ellipsoid") {
+        Some(ellipsoid) => {
+            if let Some(radius) = projjson_number(ellipsoid.get("radius"), 1.0) {
+                Ellipsoid::sphere(radius)
+            } else {
+                let a = projjson_number(ellipsoid.get("semi_major_axis"), 1.0)
+                    .ok_or_else(|| invalid("PROJJSON: ellipsoid without semi-major axis"))?;
+                match ellipsoid.get("inverse_flattening").and_then(Value::as_f64) {
+                    Some(inv_f) => Ellipsoid::new(a, inv_f),
+                    None => Ellipsoid::from_axes(a, projjson_number(ellipsoid.get("semi_minor_axis"), 1.0).unwrap_or(a)),
+                }
+            }
+        }
+        None => known_datum(datum_name)
+            .map(|datum| datum.ellipsoid)
+            .ok_or_else(|| invalid(format!("PROJJSON: datum {} has no ellipsoid", datum_name)))?,
+    };
+
+    let to_wgs84 = known_datum(datum_name)
+        .filter(|known| known.ellipsoid == ellipsoid)
+        .and_then(|known| known.to_wgs84);
+    let mut datum = Datum::new(datum_name, ellipsoid, to_wgs84);
+    if let Some(longitude) = datum_value.get("prime_meridian").and_then(|pm| pm.get("longitude")) {
+        datum.prime_meridian = projjson_number(Some(longitude), PI / 180.0).unwrap_or(0.0).to_degrees();
+    }
+
+    Ok(CrsDefinition::geographic(name, datum))
+}
+
+fn projjson_projected(value: &Value) -> Result<CrsDefinition> {
+    let name = value.get("name").and_then(Value::as_str).unwrap_or_default();
+    let base = value
+        .get("base_crs")
+        .ok_or_else(|| invalid(format!("PROJJSON: {} has no base CRS", name)))?;
+    let base_definition = projjson_geographic(base)?;
+
+    let conversion = value
+        .get("conversion")
+        .ok_or_else(|| invalid(format!("PROJJSON: {} has no conversion", name)))?;
+    let method = conversion
+        .get("method")
+        .and_then(|method| method.get("name"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("PROJJSON: conversion without method"))?;
+
+    let mut params = Params::default();
+    for param in conversion.get("parameters").and_then(Value::as_array).into_iter().flatten() {
+        let Some(kind) = param.get("name").and_then(Value::as_str).and_then(Param::from_name) else {
+            continue;
+        };
+        let Some(number) = param.get("value").and_then(Value::as_f64) else {
+            continue;
+        };
+        let value = if kind.is_angle() {
+            to_degrees(number, projjson_unit(param.get("unit"), PI / 180.0))
+        } else {
+            number * projjson_unit(param.get("unit"), 1.0)
+        };
+        params.set(kind, value);
+    }
+
+    let to_meter = value
+        .get("coordinate_system")
+        .and_then(|cs| cs.get("axis"))
+        .and_then(|axes| axes.get(0))
+        .map_or(1.0, |axis| projjson_unit(axis.get("unit"), 1.0));
+
+    let projection = build_projection(method, &params)?;
+    Ok(CrsDefinition::projected(name, base_definition.datum, projection).with_to_meter(to_meter))
+}
+
+fn projjson_bound(value: &Value) -> Result<CrsDefinition> {
+    let source = value
+        .get("source_crs")
+        .ok_or_else(|| invalid("PROJJSON: bound CRS without source CRS"))?;
+    let mut definition = projjson_crs(source)?;
+
+    let transformation = value
+        .get("transformation")
+        .ok_or_else(|| invalid("PROJJSON: bound CRS without transformation"))?;
+    let method = transformation
+        .get("method")
+        .and_then(|method| method.get("name"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let params: Vec<(String, f64)> = transformation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|param| {
+            let name = param.get("name")?.as_str()?.to_string();
+            let number = param.get("value")?.as_f64()?;
+            let kind = normalize(&name);
+            let value = if kind.contains("rotation") {
+                number * projjson_unit(param.get("unit"), PI / 648_000.0) / (PI / 648_000.0)
+            } else if kind.contains("scale") {
+                number * projjson_unit(param.get("unit"), 1e-6) * 1e6
+            } else {
+                number * projjson_unit(param.get("unit"), 1.0)
+            };
+            Some((name, value))
+        })
+        .collect();
+
+    definition.datum.to_wgs84 = Some(build_helmert(method, &params)?);
+    Ok(definition)
+}
+
+// ---------------------------------------------------------------------------
+// PROJ strings
+// ---------------------------------------------------------------------------
+
+/// Parses a PROJ string, e.g. `+proj=utm +zone=32 +datum=WGS84`.
+pub fn parse_proj_string(text: &str) -> Result<CrsDefinition> {
+    let mut options: Vec<(String, Option<String>)> = Vec::new();
+    for token in text.split_whitespace() {
+        let token = token.trim_start_matches('+');
+        if token.is_empty() {
+            continue;
+        }
+        match token.split_once('=') {
+            Some((key, value)) => options.push((key.to_ascii_lowercase(), Some(value.to_string()))),
+            None => options.push((token.to_ascii_lowercase(), None)),
+        }
+    }
+
+    let get = |key: &str| {
+        options
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_deref().unwrap_or_default())
+    };
+    let number = |key: &str| -> Result<Option<f64>> {
+        get(key)
+            .map(|value| {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| invalid(format!("PROJ string: invalid +{}={}", key, value)))
+            })
+            .transpose()
+    };
+
+    if let Some(init) = get("init") {
+        let code = epsg_code(init).ok_or_else(|| invalid(format!("PROJ string: unsupported +init={}", init)))?;
+        return epsg::lookup(code).ok_or_else(|| unknown_epsg(code));
+    }
+
+    // Datum and ellipsoid
+    let mut datum = match get("datum").map(str::to_ascii_lowercase).as_deref() {
+        Some("wgs84") => Datum::wgs84(),
+        Some(name) => known_datum(name).ok_or_else(|| invalid(format!("PROJ string: unknown datum {}", name)))?,
+        None => Datum::new("unknown", Ellipsoid::WGS84, None),
+    };
+    if let Some(ellps) = get("ellps") {
+        datum.ellipsoid = match ellps.to_ascii_lowercase().as_str() {
+            "wgs84" => Ellipsoid::WGS84,
+            "grs80" => Ellipsoid::GRS80,
+            "clrk66" => Ellipsoid::CLARKE_1866,
+            "intl" => Ellipsoid::INTERNATIONAL_1924,
+            "bessel" => Ellipsoid::BESSEL_1841,
+            "airy" => Ellipsoid::AIRY_1830,
+            other => return Err(invalid(format!("PROJ string: unknown ellipsoid {}", other))),
+        };
+    }
+    if let Some(radius) = number("r")? {
+        datum.ellipsoid = Ellipsoid::sphere(radius);
+    } else if let Some(a) = number("a")? {
+        datum.ellipsoid = if let Some(rf) = number("rf")? {
+            Ellipsoid::new(a, rf)
+        } else if let Some(f) = number("f")? {
+            Ellipsoid::new(a, if f == 0.0 { 0.0 } else { 1.0 / f })
+        } else {
+            Ellipsoid::from_axes(a, number("b")?.unwrap_or(a))
+        };
+    }
+    if let Some(towgs84) = get("towgs84") {
+        let values = towgs84
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| invalid(format!("PROJ string: invalid +towgs84={}", towgs84)))?;
+        datum.to_wgs84 =
+            Some(Helmert::from_towgs84(&values).ok_or_else(|| invalid("PROJ string: +towgs84 needs 3 or 7 values"))?);
+    }
+    if get("nadgrids") == Some("@null") {
+        datum.to_wgs84 = None;
+    }
+    if let Some(pm) = get("pm") {
+        datum.prime_meridian = match pm.to_ascii_lowercase().as_str() {
+            "greenwich" => 0.0,
+            "paris" => 2.337_229_166_666_667,
+            value => value
+                .parse()
+                .map_err(|_| invalid(format!("PROJ string: unsupported +pm={}", pm)))?,
+        };
+    }
+
+    let proj = get("proj").ok_or_else(|| invalid("PROJ string: missing +proj"))?;
+    let degrees = |key: &str| number(key).map(|v| v.unwrap_or(0.0));
+    let mut projection = match proj {
+        "longlat" | "latlong" | "lonlat" | "latlon" => {
+            return Ok(CrsDefinition::geographic(text.trim(), datum));
+        }
+        "utm" => {
+            let zone = get("zone")
+                .and_then(|zone| zone.parse::<u8>().ok())
+                .filter(|zone| (1..=60).contains(zone))
+                .ok_or_else(|| invalid("PROJ string: +proj=utm needs a +zone between 1 and 60"))?;
+            Projection::utm(zone, get("south").is_none())
+        }
+        "tmerc" | "etmerc" => Projection::new(Method::TransverseMercator),
+        "merc" => {
+            let mut mercator = Projection::new(Method::Mercator);
+            mercator.lat_ts = number("lat_ts")?;
+            mercator
+        }
+        "webmerc" => Projection::web_mercator(),
+        "lcc" => Projection::new(Method::LambertConformalConic),
+        "aea" => Projection::new(Method::AlbersEqualArea),
+        "stere" | "ups" => {
+            let mut stere = Projection::new(Method::PolarStereographic);
+            if proj == "ups" {
+                stere.lat_0 = if get("south").is_some() { -90.0 } else { 90.0 };
+                stere.k_0 = 0.994;
+                stere.false_easting = 2_000_000.0;
+                stere.false_northing = 2_000_000.0;
+            } else {
+                stere.lat_0 = degrees("lat_0")?;
+                if (stere.lat_0.abs() - 90.0).abs() > 1e-9 {
+                    return Err(invalid("PROJ string: only polar stereographic (+lat_0=90 or -90) is supported"));
+                }
+                stere.lat_ts = number("lat_ts")?;
+            }
+            stere
+        }
+        other => return Err(invalid(format!("PROJ string: unsupported projection {}", other))),
+    };
+
+    if proj != "utm" && proj != "ups" {
+        projection.lat_0 = degrees("lat_0")?;
+        projection.lon_0 = degrees("lon_0")?;
+        projection.false_easting = degrees("x_0")?;
+        projection.false_northing = degrees("y_0")?;
+        if let Some(k) = number("k_0")?.or(number("k")?) {
+            projection.k_0 = k;
+        }
+        if matches!(proj, "lcc" | "aea") {
+            projection.lat_1 = number("lat_1")?.unwrap_or(projection.lat_0);
+            projection.lat_2 = number("lat_2")?.unwrap_or(projection.lat_1);
+        }
+    }
+
+    let to_meter = match (number("to_meter")?, get("units")) {
+        (Some(to_meter), _) => to_meter,
+        (None, Some(units)) => {
+            length_unit(units).ok_or_else(|| invalid(format!("PROJ string: unsupported +units={}", units)))?
+        }
+        (None, None) => 1.0,
+    };
+
+    Ok(CrsDefinition::projected(text.trim(), datum, projection).with_to_meter(to_meter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    const UTM_32N_WKT2: &str = r#"PROJCRS["WGS 84 / UTM zone 32N",
+        BASEGEOGCRS["WGS 84",
+            ENSEMBLE["World Geodetic System 1984 ensemble",
+                MEMBER["World Geodetic System 1984 (G2139)"],
+                ELLIPSOID["WGS 84",6378137,298.257223563,LENGTHUNIT["metre",1]],
+                ENSEMBLEACCURACY[2.0]],
+            PRIMEM["Greenwich",0,ANGLEUNIT["degree",0.0174532925199433]],
+            ID["EPSG",4326]],
+        CONVERSION["UTM zone 32N",
+            METHOD["Transverse Mercator",ID["EPSG",9807]],
+            PARAMETER["Latitude of natural origin",0,ANGLEUNIT["degree",0.0174532925199433],ID["EPSG",8801]],
+            PARAMETER["Longitude of natural origin",9,ANGLEUNIT["degree",0.0174532925199433],ID["EPSG",8802]],
+            PARAMETER["Scale factor at natural origin",0.9996,SCALEUNIT["unity",1],ID["EPSG",8805]],
+            PARAMETER["False easting",500000,LENGTHUNIT["metre",1],ID["EPSG",8806]],
+            PARAMETER["False northing",0,LENGTHUNIT["metre",1],ID["EPSG",8807]]],
+        CS[Cartesian,2],
+            AXIS["(E)",east,ORDER[1],LENGTHUNIT["metre",1]],
+            AXIS["(N)",north,ORDER[2],LENGTHUNIT["metre",1]],
+        ID["EPSG",32632]]"#;
+
+    #[test]
+    fn test_parse_wkt2() {
+        let definition = parse_wkt(UTM_32N_WKT2).unwrap();
+        assert_eq!(definition.epsg, Some(32632));
+        assert_eq!(definition.name, "WGS 84 / UTM zone 32N");
+        assert_eq!(definition.projection, epsg::lookup(32632).unwrap().projection);
+        assert_eq!(definition.datum.to_wgs84, Some(Helmert::default()));
+    }
+
+    #[test]
+    fn test_parse_wkt1() {
+        let wkt = r#"PROJCS["OSGB 1936 / British National Grid",
+            GEOGCS["OSGB 1936",
+                DATUM["OSGB_1936",SPHEROID["Airy 1830",6377563.396,299.3249646,AUTHORITY["EPSG","7001"]],
+                    TOWGS84[446.448,-125.157,542.06,0.15,0.247,0.842,-20.489]],
+                PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433]],
+            PROJECTION["Transverse_Mercator"],
+            PARAMETER["latitude_of_origin",49],PARAMETER["central_meridian",-2],
+            PARAMETER["scale_factor",0.9996012717],PARAMETER["false_easting",400000],
+            PARAMETER["false_northing",-100000],UNIT["metre",1],AUTHORITY["EPSG","27700"]]"#;
+        let definition = parse_wkt(wkt).unwrap();
+        let expected = epsg::lookup(27700).unwrap();
+        assert_eq!(definition.epsg, Some(27700));
+        assert_eq!(definition.projection, expected.projection);
+        assert_eq!(definition.datum.to_wgs84, expected.datum.to_wgs84);
+
+        // ESRI style Web Mercator
+        let wkt = r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984",
+            DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],
+            UNIT["Degree",0.0174532925199433]],PROJECTION["Mercator_Auxiliary_Sphere"],
+            PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",0.0],
+            PARAMETER["Standard_Parallel_1",0.0],PARAMETER["Auxiliary_Sphere_Type",0.0],UNIT["Meter",1.0]]"#;
+        assert_eq!(parse_wkt(wkt).unwrap().projection.method, Method::WebMercator);
+
+        assert!(parse_wkt("PROJCS[\"x\"").is_err());
+        assert!(parse_wkt("LOCAL_CS[\"x\"]").is_err());
+    }
+
+    #[test]
+    fn test_parse_bound_crs() {
+        let wkt = r#"BOUNDCRS[
+            SOURCECRS[GEOGCRS["DHDN",DATUM["Deutsches Hauptdreiecksnetz",
+                ELLIPSOID["Bessel 1841",6377397.155,299.1528128,LENGTHUNIT["metre",1]]],
+                PRIMEM["Greenwich",0,ANGLEUNIT["degree",0.0174532925199433]]]],
+            TARGETCRS[GEOGCRS["WGS 84",DATUM["World Geodetic System 1984",
+                ELLIPSOID["WGS 84",6378137,298.257223563,LENGTHUNIT["metre",1]]]]],
+            ABRIDGEDTRANSFORMATION["Transformation from DHDN to WGS84",
+                METHOD["Position Vector transformation (geog2D domain)",ID["EPSG",9606]],
+                PARAMETER["X-axis translation",598.1,ID["EPSG",8605]],
+                PARAMETER["Y-axis translation",73.7,ID["EPSG",8606]],
+                PARAMETER["Z-axis translation",418.2,ID["EPSG",8607]],
+                PARAMETER["X-axis rotation",0.202,ID["EPSG",8608]],
+                PARAMETER["Y-axis rotation",0.045,ID["EPSG",8609]],
+                PARAMETER["Z-axis rotation",-2.455,ID["EPSG",8610]],
+                PARAMETER["Scale difference",0.0067,SCALEUNIT["parts per thousand",0.001]]]]"#;
+        let definition = parse_wkt(wkt).unwrap();
+        let helmert = definition.datum.to_wgs84.unwrap();
+        assert_eq!(helmert.tx, 598.1);
+        assert_eq!(helmert.rz, -2.455);
+        assert_abs_diff_eq!(helmert.ds, 6.7, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_parse_projjson() {
+        let json = r#"{
+            "type": "ProjectedCRS",
+            "name": "NAD83 / Conus Albers",
+            "base_crs": {
+                "name": "NAD83",
+                "datum": {
+                    "type": "GeodeticReferenceFrame",
+                    "name": "North American Datum 1983",
+                    "ellipsoid": {"name": "GRS 1980", "semi_major_axis": 6378137, "inverse_flattening": 298.257222101}
+                }
+            },
+            "conversion": {
+                "name": "Conus Albers",
+                "method": {"name": "Albers Equal Area", "id": {"authority": "EPSG", "code": 9822}},
+                "parameters": [
+                    {"name": "Latitude of false origin", "value": 23, "unit": "degree"},
+                    {"name": "Longitude of false origin", "value": -96, "unit": "degree"},
+                    {"name": "Latitude of 1st standard parallel", "value": 29.5, "unit": "degree"},
+                    {"name": "Latitude of 2nd standard parallel", "value": 45.5, "unit": "degree"},
+                    {"name": "Easting at false origin", "value": 0, "unit": "metre"},
+                    {"name": "Northing at false origin", "value": 0, "unit": "metre"}
+                ]
+            },
+            "coordinate_system": {"subtype": "Cartesian", "axis": [
+                {"name": "Easting", "abbreviation": "X", "direction": "east", "unit": "metre"},
+                {"name": "Northing", "abbreviation": "Y", "direction": "north", "unit": "metre"}
+            ]},
+            "id": {"authority": "EPSG", "code": 5070}
+        }"#;
+        let definition = parse_projjson(json).unwrap();
+        let expected = epsg::lookup(5070).unwrap();
+        assert_eq!(definition.epsg, Some(5070));
+        assert_eq!(definition.projection, expected.projection);
+        assert_eq!(definition.datum.ellipsoid, expected.datum.ellipsoid);
+        assert_eq!(definition.datum.to_wgs84, expected.datum.to_wgs84);
+    }
+
+    #[test]
+    fn test_parse_proj_string() {
+        let utm = parse("+proj=utm +zone=32 +datum=WGS84 +units=m +no_defs").unwrap();
+        assert_eq!(utm.projection, epsg::lookup(32632).unwrap().projection);
+
+        let lcc = parse("+proj=lcc +lat_0=46.5 +lon_0=3 +lat_1=49 +lat_2=44 +x_0=700000 +y_0=6600000 +ellps=GRS80")
+            .unwrap();
+        assert_eq!(lcc.projection, epsg::lookup(2154).unwrap().projection);
+
+        let feet = parse("+proj=tmerc +lon_0=-87.5 +k=0.99996 +x_0=300000 +units=us-ft +datum=NAD83").unwrap();
+        assert_abs_diff_eq!(feet.to_meter, US_FOOT);
+
+        assert_eq!(parse("EPSG:3857").unwrap().epsg, Some(3857));
+        assert!(parse("EPSG:2056").is_err());
+        assert!(parse("+proj=omerc +lat_0=46").is_err());
+        assert!(parse("+proj=utm +zone=61").is_err());
+    }
+}
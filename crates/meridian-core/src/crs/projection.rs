@@ -0,0 +1,623 @@
+//! Map projections implemented in pure Rust.
+//!
+//! Formulas follow EPSG Guidance Note 7-2; Transverse Mercator uses the
+//! Krüger series to sixth order in `n`, which matches PROJ's implementation
+//! to well below a millimeter inside a UTM zone.
+
+use super::datum::Ellipsoid;
+use crate::error::{MeridianError, Result};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+/// Projection method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Method {
+    /// Longitude/latitude in degrees
+    Geographic,
+    /// Transverse Mercator (EPSG 9807), including UTM
+    TransverseMercator,
+    /// Ellipsoidal Mercator (EPSG 9804/9805)
+    Mercator,
+    /// Spherical "Pseudo-Mercator" used by web maps (EPSG 1024)
+    WebMercator,
+    /// Lambert Conformal Conic with one or two standard parallels (EPSG 9801/9802)
+    LambertConformalConic,
+    /// Albers Equal Area (EPSG 9822)
+    AlbersEqualArea,
+    /// Polar Stereographic variants A and B (EPSG 9810/9829)
+    PolarStereographic,
+}
+
+/// A projection method with its parameters.
+///
+/// Angles are in degrees and false easting/northing in meters. Parameters
+/// a method does not use are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Projection {
+    /// Projection method
+    pub method: Method,
+
+    /// Latitude of origin
+    pub lat_0: f64,
+
+    /// Longitude of origin (central meridian)
+    pub lon_0: f64,
+
+    /// First standard parallel
+    pub lat_1: f64,
+
+    /// Second standard parallel
+    pub lat_2: f64,
+
+    /// Latitude of true scale (Mercator, Polar Stereographic variant B)
+    pub lat_ts: Option<f64>,
+
+    /// Scale factor at the origin
+    pub k_0: f64,
+
+    /// False easting
+    pub false_easting: f64,
+
+    /// False northing
+    pub false_northing: f64,
+}
+
+impl Projection {
+    /// Creates a projection with default parameters.
+    pub fn new(method: Method) -> Self {
+        Self {
+            method,
+            lat_0: 0.0,
+            lon_0: 0.0,
+            lat_1: 0.0,
+            lat_2: 0.0,
+            lat_ts: None,
+            k_0: 1.0,
+            false_easting: 0.0,
+            false_northing: 0.0,
+        }
+    }
+
+    /// Longitude/latitude coordinates.
+    pub fn geographic() -> Self {
+        Self::new(Method::Geographic)
+    }
+
+    /// Transverse Mercator.
+    pub fn transverse_mercator(lat_0: f64, lon_0: f64, k_0: f64, false_easting: f64, false_northing: f64) -> Self {
+        Self {
+            lat_0,
+            lon_0,
+            k_0,
+            false_easting,
+            false_northing,
+            ..Self::new(Method::TransverseMercator)
+        }
+    }
+
+    /// Universal Transverse Mercator zone.
+    pub fn utm(zone: u8, north: bool) -> Self {
+        let lon_0 = zone as f64 * 6.0 - 183.0;
+        Self::transverse_mercator(0.0, lon_0, 0.9996, 500_000.0, if north { 0.0 } else { 10_000_000.0 })
+    }
+
+    /// Web Mercator.
+    pub fn web_mercator() -> Self {
+        Self::new(Method::WebMercator)
+    }
+
+    /// Lambert Conformal Conic with two standard parallels.
+    pub fn lambert_conformal_conic(
+        lat_0: f64,
+        lon_0: f64,
+        lat_1: f64,
+        lat_2: f64,
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        Self {
+            lat_0,
+            lon_0,
+            lat_1,
+            lat_2,
+            false_easting,
+            false_northing,
+            ..Self::new(Method::LambertConformalConic)
+        }
+    }
+
+    /// Albers Equal Area.
+    pub fn albers_equal_area(
+        lat_0: f64,
+        lon_0: f64,
+        lat_1: f64,
+        lat_2: f64,
+        false_easting: f64,
+        false_northing: f64,
+    ) -> Self {
+        Self {
+            lat_0,
+            lon_0,
+            lat_1,
+            lat_2,
+            false_easting,
+            false_northing,
+            ..Self::new(Method::AlbersEqualArea)
+        }
+    }
+
+    /// Polar Stereographic with a latitude of true scale (variant B).
+    ///
+    /// The hemisphere follows the sign of `lat_ts`.
+    pub fn polar_stereographic(lat_ts: f64, lon_0: f64, false_easting: f64, false_northing: f64) -> Self {
+        Self {
+            lat_0: 90.0_f64.copysign(lat_ts),
+            lon_0,
+            lat_ts: Some(lat_ts),
+            false_easting,
+            false_northing,
+            ..Self::new(Method::PolarStereographic)
+        }
+    }
+
+    /// Checks if this is a geographic (unprojected) CRS.
+    pub fn is_geographic(&self) -> bool {
+        self.method == Method::Geographic
+    }
+}
+
+/// A projection with its constants computed for one ellipsoid.
+#[derive(Debug, Clone)]
+pub(crate) struct Prepared {
+    a: f64,
+    e: f64,
+    e2: f64,
+    lon_0: f64,
+    false_easting: f64,
+    false_northing: f64,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Geographic,
+    TransverseMercator {
+        /// Scaled rectifying radius, `k0 * A`
+        radius: f64,
+        /// Northing of the origin latitude
+        y_0: f64,
+        alpha: [f64; 6],
+        beta: [f64; 6],
+    },
+    Mercator {
+        k_0: f64,
+    },
+    WebMercator,
+    Conic {
+        /// Lambert Conformal Conic when true, Albers otherwise
+        conformal: bool,
+        n: f64,
+        /// `F` for Lambert, `C` for Albers
+        c: f64,
+        rho_0: f64,
+        k_0: f64,
+    },
+    PolarStereographic {
+        north: bool,
+        /// Distance from the pole is `scale * t`
+        scale: f64,
+    },
+}
+
+impl Prepared {
+    pub(crate) fn new(projection: &Projection, ellipsoid: &Ellipsoid) -> Result<Self> {
+        let a = ellipsoid.a;
+        let e2 = ellipsoid.e2();
+        let e = e2.sqrt();
+        let lat_0 = projection.lat_0.to_radians();
+
+        let kind = match projection.method {
+            Method::Geographic => Kind::Geographic,
+            Method::TransverseMercator => {
+                let f = ellipsoid.f();
+                let n = f / (2.0 - f);
+                let (alpha, beta) = kruger_coefficients(n);
+                let rectifying = a / (1.0 + n) * (1.0 + n.powi(2) / 4.0 + n.powi(4) / 64.0 + n.powi(6) / 256.0);
+                let radius = projection.k_0 * rectifying;
+                let (xi, _) = tm_forward_unit(0.0, lat_0, e, &alpha);
+                Kind::TransverseMercator {
+                    radius,
+                    y_0: radius * xi,
+                    alpha,
+                    beta,
+                }
+            }
+            Method::Mercator => {
+                let k_0 = match projection.lat_ts {
+                    Some(lat_ts) if lat_ts != 0.0 => {
+                        let lat_ts = lat_ts.to_radians();
+                        lat_ts.cos() / (1.0 - e2 * lat_ts.sin().powi(2)).sqrt()
+                    }
+                    _ => projection.k_0,
+                };
+                Kind::Mercator { k_0 }
+            }
+            Method::WebMercator => Kind::WebMercator,
+            Method::LambertConformalConic => {
+                let lat_1 = projection.lat_1.to_radians();
+                let lat_2 = projection.lat_2.to_radians();
+                let (m_1, t_1) = (conformal_m(lat_1, e2), conformal_t(lat_1, e));
+                let n = if (lat_1 - lat_2).abs() < 1e-10 {
+                    lat_1.sin()
+                } else {
+                    (m_1.ln() - conformal_m(lat_2, e2).ln()) / (t_1.ln() - conformal_t(lat_2, e).ln())
+                };
+                if n.abs() < 1e-10 {
+                    return Err(MeridianError::ProjectionError(
+                        "Lambert Conformal Conic standard parallels must not be symmetric about the equator".to_string(),
+                    ));
+                }
+                let c = m_1 / (n * t_1.powf(n));
+                let k_0 = projection.k_0;
+                Kind::Conic {
+                    conformal: true,
+                    n,
+                    c,
+                    rho_0: a * c * k_0 * conformal_t(lat_0, e).powf(n),
+                    k_0,
+                }
+            }
+            Method::AlbersEqualArea => {
+                let lat_1 = projection.lat_1.to_radians();
+                let lat_2 = projection.lat_2.to_radians();
+                let (m_1, q_1) = (conformal_m(lat_1, e2), authalic_q(lat_1, e, e2));
+                let n = if (lat_1 - lat_2).abs() < 1e-10 {
+                    lat_1.sin()
+                } else {
+                    (m_1.powi(2) - conformal_m(lat_2, e2).powi(2)) / (authalic_q(lat_2, e, e2) - q_1)
+                };
+                if n.abs() < 1e-10 {
+                    return Err(MeridianError::ProjectionError(
+                        "Albers standard parallels must not be symmetric about the equator".to_string(),
+                    ));
+                }
+                let c = m_1.powi(2) + n * q_1;
+                Kind::Conic {
+                    conformal: false,
+                    n,
+                    c,
+                    rho_0: a * (c - n * authalic_q(lat_0, e, e2)).max(0.0).sqrt() / n,
+                    k_0: 1.0,
+                }
+            }
+            Method::PolarStereographic => {
+                let lat_ts = projection.lat_ts.unwrap_or(projection.lat_0);
+                let north = if lat_ts != 0.0 { lat_ts > 0.0 } else { projection.lat_0 >= 0.0 };
+                let scale = if (lat_ts.abs() - 90.0).abs() < 1e-10 {
+                    // Variant A: scale factor at the pole
+                    2.0 * a * projection.k_0 / ((1.0 + e).powf(1.0 + e) * (1.0 - e).powf(1.0 - e)).sqrt()
+                } else {
+                    // Variant B: true scale along the standard parallel
+                    let lat_c = lat_ts.abs().to_radians();
+                    a * conformal_m(lat_c, e2) / conformal_t(lat_c, e)
+                };
+                Kind::PolarStereographic { north, scale }
+            }
+        };
+
+        Ok(Self {
+            a,
+            e,
+            e2,
+            lon_0: projection.lon_0.to_radians(),
+            false_easting: projection.false_easting,
+            false_northing: projection.false_northing,
+            kind,
+        })
+    }
+
+    /// Projects longitude/latitude in radians to easting/northing in meters.
+    pub(crate) fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let dlon = normalize_angle(lon - self.lon_0);
+        let (x, y) = match &self.kind {
+            Kind::Geographic => return (lon, lat),
+            Kind::TransverseMercator { radius, y_0, alpha, .. } => {
+                let (xi, eta) = tm_forward_unit(dlon, lat, self.e, alpha);
+                (radius * eta, radius * xi - y_0)
+            }
+            // The poles are at infinity
+            Kind::Mercator { .. } | Kind::WebMercator if lat.abs() >= FRAC_PI_2 - 1e-10 => (f64::NAN, f64::NAN),
+            Kind::Mercator { k_0 } => {
+                let y = -(conformal_t(lat, self.e)).ln();
+                (self.a * k_0 * dlon, self.a * k_0 * y)
+            }
+            Kind::WebMercator => (self.a * dlon, self.a * (FRAC_PI_4 + lat / 2.0).tan().ln()),
+            Kind::Conic { conformal, n, c, rho_0, k_0 } => {
+                let rho = if *conformal {
+                    self.a * c * k_0 * conformal_t(lat, self.e).powf(*n)
+                } else {
+                    self.a * (c - n * authalic_q(lat, self.e, self.e2)).max(0.0).sqrt() / n
+                };
+                let theta = n * dlon;
+                (rho * theta.sin(), rho_0 - rho * theta.cos())
+            }
+            Kind::PolarStereographic { north, scale } => {
+                if *north {
+                    let rho = scale * conformal_t(lat, self.e);
+                    (rho * dlon.sin(), -rho * dlon.cos())
+                } else {
+                    let rho = scale * conformal_t(-lat, self.e);
+                    (rho * dlon.sin(), rho * dlon.cos())
+                }
+            }
+        };
+        (x + self.false_easting, y + self.false_northing)
+    }
+
+    /// Converts easting/northing in meters to longitude/latitude in radians.
+    pub(crate) fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = match self.kind {
+            Kind::Geographic => return (x, y),
+            _ => (x - self.false_easting, y - self.false_northing),
+        };
+
+        let (dlon, lat) = match &self.kind {
+            Kind::Geographic => unreachable!(),
+            Kind::TransverseMercator { radius, y_0, beta, .. } => {
+                let xi = (y + y_0) / radius;
+                let eta = x / radius;
+                let (mut xi_p, mut eta_p) = (xi, eta);
+                for (j, b) in beta.iter().enumerate() {
+                    let k = 2.0 * (j + 1) as f64;
+                    xi_p -= b * (k * xi).sin() * (k * eta).cosh();
+                    eta_p -= b * (k * xi).cos() * (k * eta).sinh();
+                }
+                let tau_p = xi_p.sin() / (eta_p.sinh().powi(2) + xi_p.cos().powi(2)).sqrt();
+                let dlon = eta_p.sinh().atan2(xi_p.cos());
+                (dlon, tau_to_lat(tau_p, self.e, self.e2))
+            }
+            Kind::Mercator { k_0 } => {
+                let t = (-y / (self.a * k_0)).exp();
+                (x / (self.a * k_0), lat_from_t(t, self.e))
+            }
+            Kind::WebMercator => (x / self.a, FRAC_PI_2 - 2.0 * (-y / self.a).exp().atan()),
+            Kind::Conic { conformal, n, c, rho_0, k_0 } => {
+                let dy = rho_0 - y;
+                let rho = x.hypot(dy).copysign(*n);
+                let theta = if *n > 0.0 { x.atan2(dy) } else { (-x).atan2(-dy) };
+                let lat = if *conformal {
+                    let t = (rho / (self.a * c * k_0)).powf(1.0 / n);
+                    lat_from_t(t, self.e)
+                } else {
+                    let q = (c - (rho * n / self.a).powi(2)) / n;
+                    lat_from_q(q, self.e, self.e2)
+                };
+                (theta / n, lat)
+            }
+            Kind::PolarStereographic { north, scale } => {
+                let t = x.hypot(y) / scale;
+                if *north {
+                    (x.atan2(-y), lat_from_t(t, self.e))
+                } else {
+                    (x.atan2(y), -lat_from_t(t, self.e))
+                }
+            }
+        };
+        (normalize_angle(dlon + self.lon_0), lat)
+    }
+}
+
+/// Wrap an angle to [-π, π].
+fn normalize_angle(angle: f64) -> f64 {
+    if (-PI..=PI).contains(&angle) {
+        angle
+    } else {
+        angle - 2.0 * PI * ((angle + PI) / (2.0 * PI)).floor()
+    }
+}
+
+/// `m = cos φ / sqrt(1 - e² sin² φ)`
+fn conformal_m(lat: f64, e2: f64) -> f64 {
+    lat.cos() / (1.0 - e2 * lat.sin().powi(2)).sqrt()
+}
+
+/// `t = tan(π/4 - φ/2) / ((1 - e sin φ) / (1 + e sin φ))^(e/2)`
+fn conformal_t(lat: f64, e: f64) -> f64 {
+    let e_sin = e * lat.sin();
+    (FRAC_PI_4 - lat / 2.0).tan() / ((1.0 - e_sin) / (1.0 + e_sin)).powf(e / 2.0)
+}
+
+/// Inverse of [`conformal_t`].
+fn lat_from_t(t: f64, e: f64) -> f64 {
+    let mut lat = FRAC_PI_2 - 2.0 * t.atan();
+    for _ in 0..15 {
+        let e_sin = e * lat.sin();
+        let next = FRAC_PI_2 - 2.0 * (t * ((1.0 - e_sin) / (1.0 + e_sin)).powf(e / 2.0)).atan();
+        if (next - lat).abs() < 1e-14 {
+            return next;
+        }
+        lat = next;
+    }
+    lat
+}
+
+/// Authalic `q` of Albers Equal Area.
+fn authalic_q(lat: f64, e: f64, e2: f64) -> f64 {
+    let sin = lat.sin();
+    if e < 1e-12 {
+        return 2.0 * sin;
+    }
+    (1.0 - e2) * (sin / (1.0 - e2 * sin * sin) - (1.0 / (2.0 * e)) * ((1.0 - e * sin) / (1.0 + e * sin)).ln())
+}
+
+/// Inverse of [`authalic_q`].
+fn lat_from_q(q: f64, e: f64, e2: f64) -> f64 {
+    let mut lat = (q / 2.0).clamp(-1.0, 1.0).asin();
+    if e < 1e-12 {
+        return lat;
+    }
+    for _ in 0..15 {
+        let sin = lat.sin();
+        let one_es = 1.0 - e2 * sin * sin;
+        let delta = one_es.powi(2) / (2.0 * lat.cos())
+            * (q / (1.0 - e2) - sin / one_es + (1.0 / (2.0 * e)) * ((1.0 - e * sin) / (1.0 + e * sin)).ln());
+        if !delta.is_finite() {
+            break;
+        }
+        lat += delta;
+        if delta.abs() < 1e-14 {
+            break;
+        }
+    }
+    lat
+}
+
+/// Krüger series coefficients `α` (forward) and `β` (inverse).
+fn kruger_coefficients(n: f64) -> ([f64; 6], [f64; 6]) {
+    let (n2, n3, n4, n5, n6) = (n.powi(2), n.powi(3), n.powi(4), n.powi(5), n.powi(6));
+    let alpha = [
+        n / 2.0 - 2.0 / 3.0 * n2 + 5.0 / 16.0 * n3 + 41.0 / 180.0 * n4 - 127.0 / 288.0 * n5 + 7891.0 / 37800.0 * n6,
+        13.0 / 48.0 * n2 - 3.0 / 5.0 * n3 + 557.0 / 1440.0 * n4 + 281.0 / 630.0 * n5 - 1983433.0 / 1935360.0 * n6,
+        61.0 / 240.0 * n3 - 103.0 / 140.0 * n4 + 15061.0 / 26880.0 * n5 + 167603.0 / 181440.0 * n6,
+        49561.0 / 161280.0 * n4 - 179.0 / 168.0 * n5 + 6601661.0 / 7257600.0 * n6,
+        34729.0 / 80640.0 * n5 - 3418889.0 / 1995840.0 * n6,
+        212378941.0 / 319334400.0 * n6,
+    ];
+    let beta = [
+        n / 2.0 - 2.0 / 3.0 * n2 + 37.0 / 96.0 * n3 - 1.0 / 360.0 * n4 - 81.0 / 512.0 * n5 + 96199.0 / 604800.0 * n6,
+        1.0 / 48.0 * n2 + 1.0 / 15.0 * n3 - 437.0 / 1440.0 * n4 + 46.0 / 105.0 * n5 - 1118711.0 / 3870720.0 * n6,
+        17.0 / 480.0 * n3 - 37.0 / 840.0 * n4 - 209.0 / 4480.0 * n5 + 5569.0 / 90720.0 * n6,
+        4397.0 / 161280.0 * n4 - 11.0 / 504.0 * n5 - 830251.0 / 7257600.0 * n6,
+        4583.0 / 161280.0 * n5 - 108847.0 / 3991680.0 * n6,
+        20648693.0 / 638668800.0 * n6,
+    ];
+    (alpha, beta)
+}
+
+/// Transverse Mercator `(ξ, η)` on the unit rectifying sphere.
+fn tm_forward_unit(dlon: f64, lat: f64, e: f64, alpha: &[f64; 6]) -> (f64, f64) {
+    let tau = lat.tan();
+    let sigma = (e * (e * tau / (1.0 + tau * tau).sqrt()).atanh()).sinh();
+    let tau_p = tau * (1.0 + sigma * sigma).sqrt() - sigma * (1.0 + tau * tau).sqrt();
+
+    let xi_p = tau_p.atan2(dlon.cos());
+    let eta_p = (dlon.sin() / (tau_p * tau_p + dlon.cos().powi(2)).sqrt()).asinh();
+
+    let (mut xi, mut eta) = (xi_p, eta_p);
+    for (j, a) in alpha.iter().enumerate() {
+        let k = 2.0 * (j + 1) as f64;
+        xi += a * (k * xi_p).sin() * (k * eta_p).cosh();
+        eta += a * (k * xi_p).cos() * (k * eta_p).sinh();
+    }
+    (xi, eta)
+}
+
+/// Latitude from the conformal latitude's tangent, by Newton iteration.
+fn tau_to_lat(tau_p: f64, e: f64, e2: f64) -> f64 {
+    let mut tau = tau_p;
+    for _ in 0..10 {
+        let sigma = (e * (e * tau / (1.0 + tau * tau).sqrt()).atanh()).sinh();
+        let tau_i = tau * (1.0 + sigma * sigma).sqrt() - sigma * (1.0 + tau * tau).sqrt();
+        let delta = (tau_p - tau_i) / (1.0 + tau_i * tau_i).sqrt() * (1.0 + (1.0 - e2) * tau * tau)
+            / ((1.0 - e2) * (1.0 + tau * tau).sqrt());
+        tau += delta;
+        if delta.abs() < 1e-14 * tau.abs().max(1.0) {
+            break;
+        }
+    }
+    tau.atan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn dms(d: f64, m: f64, s: f64) -> f64 {
+        (d.abs() + m / 60.0 + s / 3600.0).copysign(d)
+    }
+
+    /// Project, check against the expected result, and invert.
+    fn check(projection: Projection, ellipsoid: Ellipsoid, lon: f64, lat: f64, expected: (f64, f64), tolerance: f64) {
+        let prepared = Prepared::new(&projection, &ellipsoid).unwrap();
+        let (x, y) = prepared.forward(lon.to_radians(), lat.to_radians());
+        assert_abs_diff_eq!(x, expected.0, epsilon = tolerance);
+        assert_abs_diff_eq!(y, expected.1, epsilon = tolerance);
+
+        let (lon2, lat2) = prepared.inverse(x, y);
+        assert_abs_diff_eq!(lon2.to_degrees(), lon, epsilon = 1e-9);
+        assert_abs_diff_eq!(lat2.to_degrees(), lat, epsilon = 1e-9);
+    }
+
+    // Expected values are the worked examples of EPSG Guidance Note 7-2.
+
+    #[test]
+    fn test_transverse_mercator() {
+        let bng = Projection::transverse_mercator(49.0, -2.0, 0.999_601_271_7, 400_000.0, -100_000.0);
+        check(bng, Ellipsoid::AIRY_1830, 0.5, 50.5, (577_274.99, 69_740.50), 0.01);
+
+        // UTM zone 31N
+        let prepared = Prepared::new(&Projection::utm(31, true), &Ellipsoid::WGS84).unwrap();
+        let (x, y) = prepared.forward(3.0_f64.to_radians(), 0.0);
+        assert_abs_diff_eq!(x, 500_000.0, epsilon = 1e-6);
+        assert_abs_diff_eq!(y, 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_mercator() {
+        let mut mercator = Projection::new(Method::Mercator);
+        mercator.lon_0 = 110.0;
+        mercator.k_0 = 0.997;
+        mercator.false_easting = 3_900_000.0;
+        mercator.false_northing = 900_000.0;
+        check(mercator, Ellipsoid::BESSEL_1841, 120.0, -3.0, (5_009_726.58, 569_150.82), 0.01);
+
+        let lat = dms(24.0, 22.0, 54.433);
+        let lon = dms(-100.0, 20.0, 0.0);
+        check(Projection::web_mercator(), Ellipsoid::WGS84, lon, lat, (-11_169_055.58, 2_800_000.00), 0.01);
+    }
+
+    #[test]
+    fn test_lambert_conformal_conic() {
+        // NAD27 / Texas South Central, in US survey feet
+        let lcc = Projection::lambert_conformal_conic(
+            dms(27.0, 50.0, 0.0),
+            -99.0,
+            dms(28.0, 23.0, 0.0),
+            dms(30.0, 17.0, 0.0),
+            2_000_000.0 * 1200.0 / 3937.0,
+            0.0,
+        );
+        let feet = 1200.0 / 3937.0;
+        check(
+            lcc,
+            Ellipsoid::CLARKE_1866,
+            -96.0,
+            28.5,
+            (2_963_503.91 * feet, 254_759.80 * feet),
+            0.01,
+        );
+    }
+
+    #[test]
+    fn test_albers_equal_area() {
+        // Snyder, Map Projections: A Working Manual, example 14
+        let albers = Projection::albers_equal_area(23.0, -96.0, 29.5, 45.5, 0.0, 0.0);
+        check(albers, Ellipsoid::CLARKE_1866, -75.0, 35.0, (1_885_472.7, 1_535_925.0), 0.1);
+    }
+
+    #[test]
+    fn test_polar_stereographic() {
+        // Variant A: WGS 84 / UPS North
+        let mut ups = Projection::new(Method::PolarStereographic);
+        ups.lat_0 = 90.0;
+        ups.k_0 = 0.994;
+        ups.false_easting = 2_000_000.0;
+        ups.false_northing = 2_000_000.0;
+        check(ups, Ellipsoid::WGS84, 44.0, 73.0, (3_320_416.75, 632_668.43), 0.01);
+
+        // Variant B: WGS 84 / Australian Antarctic Polar Stereographic
+        let antarctic = Projection::polar_stereographic(-71.0, 70.0, 6_000_000.0, 6_000_000.0);
+        check(antarctic, Ellipsoid::WGS84, 120.0, -75.0, (7_255_380.79, 7_053_389.56), 0.01);
+    }
+}
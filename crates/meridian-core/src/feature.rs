@@ -226,6 +226,9 @@ impl Feature {
 
     /// Converts this feature to a GeoJSON Value.
     ///
+    /// The geometry is encoded as an RFC 7946 geometry object, with Z values
+    /// as the third position element.
+    ///
     /// # Returns
     ///
     /// A serde_json::Value representing the GeoJSON feature
@@ -237,11 +240,22 @@ impl Feature {
     /// println!("{}", serde_json::to_string_pretty(&geojson)?);
     /// ```
     pub fn to_geojson(&self) -> Result<Value> {
-        serde_json::to_value(self).map_err(|e| MeridianError::SerializationError(e.to_string()))
+        let mut object = Map::new();
+        object.insert("type".to_string(), Value::String("Feature".to_string()));
+        if let Some(id) = &self.id {
+            object.insert("id".to_string(), id.clone());
+        }
+        object.insert("geometry".to_string(), self.geometry.to_geojson());
+        object.insert("properties".to_string(), Value::Object(self.properties.clone()));
+        Ok(Value::Object(object))
     }
 
     /// Creates a feature from a GeoJSON Value.
     ///
+    /// Standard GeoJSON features get WGS 84 coordinates. Values without a
+    /// `"type": "Feature"` member are read in the serde representation of
+    /// [`Feature`], which keeps the CRS.
+    ///
     /// # Arguments
     ///
     /// * `value` - A GeoJSON feature as a serde_json::Value
@@ -254,8 +268,28 @@ impl Feature {
     ///
     /// Returns an error if the value is not a valid GeoJSON feature
     pub fn from_geojson(value: &Value) -> Result<Self> {
-        serde_json::from_value(value.clone())
-            .map_err(|e| MeridianError::DeserializationError(e.to_string()))
+        if value.get("type").and_then(Value::as_str) != Some("Feature") {
+            return serde_json::from_value(value.clone())
+                .map_err(|e| MeridianError::DeserializationError(e.to_string()));
+        }
+
+        let geometry = value
+            .get("geometry")
+            .ok_or_else(|| MeridianError::DeserializationError("GeoJSON feature has no geometry".to_string()))?;
+        let properties = match value.get("properties") {
+            Some(Value::Object(properties)) => properties.clone(),
+            None | Some(Value::Null) => Map::new(),
+            Some(_) => {
+                return Err(MeridianError::DeserializationError(
+                    "GeoJSON feature properties must be an object".to_string(),
+                ))
+            }
+        };
+        Ok(Self {
+            id: value.get("id").cloned(),
+            geometry: Geometry::from_geojson(geometry, Crs::wgs84())?,
+            properties,
+        })
     }
 
     /// Returns a reference to the geometry.
@@ -464,4 +498,26 @@ mod tests {
         assert_eq!(bounds.min_y, 20.0);
         assert_eq!(bounds.max_y, 20.0);
     }
+
+    #[test]
+    fn test_feature_geojson() {
+        let point = Point::new_3d(10.0, 20.0, 30.0, Crs::wgs84());
+        let feature = FeatureBuilder::new(Geometry::Point(point)).id(7).property("name", json!("A")).build();
+
+        let value = feature.to_geojson().unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "Feature",
+                "id": 7,
+                "geometry": {"type": "Point", "coordinates": [10.0, 20.0, 30.0]},
+                "properties": {"name": "A"}
+            })
+        );
+        assert_eq!(Feature::from_geojson(&value).unwrap(), feature);
+
+        // The serde representation is still accepted
+        let legacy = serde_json::to_value(&feature).unwrap();
+        assert_eq!(Feature::from_geojson(&legacy).unwrap(), feature);
+    }
 }
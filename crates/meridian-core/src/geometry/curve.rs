@@ -0,0 +1,593 @@
+//! Circular arc geometries.
+//!
+//! Arcs are defined by three points as in ISO SQL/MM: start, any point on
+//! the arc, and end. A circular string chains arcs that share their end
+//! points; a compound curve chains line strings and circular strings.
+
+use super::{check_ordinates, CoordTransformer, Dimension, LineString};
+use crate::bbox::BoundingBox;
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+use crate::traits::{Bounded, Transformable};
+use geo_types::Coord;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+/// Default number of segments per 90° of arc used for linearization (as PostGIS).
+pub const DEFAULT_SEGMENTS_PER_QUADRANT: u32 = 32;
+
+/// A sequence of circular arcs with CRS support.
+///
+/// Holds `2n + 1` control points for `n` arcs. Each arc runs from an even
+/// control point through the next one to the following even one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircularString {
+    /// Control points
+    pub coords: Vec<Coord<f64>>,
+    /// Z ordinates, one per control point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per control point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
+    /// Coordinate reference system
+    pub crs: Crs,
+}
+
+/// Circle through the three control points of an arc
+#[derive(Debug, Clone, Copy)]
+enum Arc {
+    /// Collinear control points, treated as a straight segment
+    Straight,
+    /// Circle center, radius, start angle, signed sweep (positive counter-clockwise)
+    /// and the sweep fraction at the middle control point
+    Circle {
+        center: Coord<f64>,
+        radius: f64,
+        start: f64,
+        sweep: f64,
+        middle: f64,
+    },
+}
+
+impl Arc {
+    fn new(p0: Coord<f64>, p1: Coord<f64>, p2: Coord<f64>) -> Self {
+        // Closed arcs are full circles with the middle point diametrically opposite
+        if p0 == p2 {
+            if p0 == p1 {
+                return Arc::Straight;
+            }
+            let center = Coord {
+                x: (p0.x + p1.x) / 2.0,
+                y: (p0.y + p1.y) / 2.0,
+            };
+            let radius = (p0.x - center.x).hypot(p0.y - center.y);
+            let start = (p0.y - center.y).atan2(p0.x - center.x);
+            return Arc::Circle {
+                center,
+                radius,
+                start,
+                sweep: TAU,
+                middle: 0.5,
+            };
+        }
+
+        let (bx, by) = (p1.x - p0.x, p1.y - p0.y);
+        let (cx, cy) = (p2.x - p0.x, p2.y - p0.y);
+        let d = 2.0 * (bx * cy - by * cx);
+        let scale = (bx * bx + by * by).max(cx * cx + cy * cy);
+        if d.abs() <= 1e-12 * scale {
+            return Arc::Straight;
+        }
+
+        let b2 = bx * bx + by * by;
+        let c2 = cx * cx + cy * cy;
+        let center = Coord {
+            x: p0.x + (cy * b2 - by * c2) / d,
+            y: p0.y + (bx * c2 - cx * b2) / d,
+        };
+        let radius = (p0.x - center.x).hypot(p0.y - center.y);
+        let angle = |p: Coord<f64>| (p.y - center.y).atan2(p.x - center.x);
+        let start = angle(p0);
+        let counter_clockwise = d > 0.0;
+        let sweep_to = |p: Coord<f64>| {
+            let delta = (angle(p) - start).rem_euclid(TAU);
+            if counter_clockwise {
+                delta
+            } else {
+                delta - TAU
+            }
+        };
+        let sweep = sweep_to(p2);
+        Arc::Circle {
+            center,
+            radius,
+            start,
+            sweep,
+            middle: sweep_to(p1) / sweep,
+        }
+    }
+}
+
+/// Interpolates an ordinate along an arc from the sweep fraction
+fn interpolate(values: [f64; 3], middle: f64, t: f64) -> f64 {
+    if t <= middle {
+        values[0] + (values[1] - values[0]) * t / middle
+    } else {
+        values[1] + (values[2] - values[1]) * (t - middle) / (1.0 - middle)
+    }
+}
+
+impl CircularString {
+    /// Creates a new circular string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless there are no control points or an odd number
+    /// of at least three
+    pub fn new(coords: Vec<Coord<f64>>, crs: Crs) -> Result<Self> {
+        if !coords.is_empty() && (coords.len() < 3 || coords.len().is_multiple_of(2)) {
+            return Err(MeridianError::InvalidGeometry(format!(
+                "A circular string needs an odd number of at least 3 points, got {}",
+                coords.len()
+            )));
+        }
+        Ok(Self {
+            coords,
+            z: None,
+            m: None,
+            crs,
+        })
+    }
+
+    /// Sets the Z ordinates, one per control point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of control points.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.coords.len());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per control point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of control points.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.coords.len());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
+    /// Checks if the circular string has no control points.
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+
+    /// Returns the number of arcs.
+    pub fn num_arcs(&self) -> usize {
+        self.coords.len().saturating_sub(1) / 2
+    }
+
+    fn arcs(&self) -> impl Iterator<Item = (usize, Arc)> + '_ {
+        (0..self.num_arcs()).map(|i| {
+            let j = 2 * i;
+            (j, Arc::new(self.coords[j], self.coords[j + 1], self.coords[j + 2]))
+        })
+    }
+
+    /// Returns the length of the arcs.
+    pub fn length(&self) -> f64 {
+        self.arcs()
+            .map(|(j, arc)| match arc {
+                Arc::Straight => {
+                    let (a, b) = (self.coords[j], self.coords[j + 2]);
+                    (b.x - a.x).hypot(b.y - a.y)
+                }
+                Arc::Circle { radius, sweep, .. } => radius * sweep.abs(),
+            })
+            .sum()
+    }
+
+    /// Approximates the arcs by a line string.
+    ///
+    /// Z and M values are interpolated along each arc, piecewise linearly
+    /// in the swept angle between the control points.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments_per_quadrant` - Number of line segments per 90° of arc
+    pub fn linearize(&self, segments_per_quadrant: u32) -> LineString {
+        let ordinate = |values: &Option<Vec<f64>>, j: usize| {
+            values.as_ref().map(|v| [v[j], v[j + 1], v[j + 2]])
+        };
+        let mut coords = Vec::new();
+        let mut z = self.z.as_ref().map(|_| Vec::new());
+        let mut m = self.m.as_ref().map(|_| Vec::new());
+
+        if let Some(&first) = self.coords.first() {
+            coords.push(first);
+            if let Some(z) = &mut z {
+                z.push(self.z.as_ref().unwrap()[0]);
+            }
+            if let Some(m) = &mut m {
+                m.push(self.m.as_ref().unwrap()[0]);
+            }
+        }
+
+        for (j, arc) in self.arcs() {
+            let (arc_z, arc_m) = (ordinate(&self.z, j), ordinate(&self.m, j));
+            match arc {
+                Arc::Straight => {
+                    coords.extend([self.coords[j + 1], self.coords[j + 2]]);
+                    if let (Some(z), Some(values)) = (&mut z, arc_z) {
+                        z.extend([values[1], values[2]]);
+                    }
+                    if let (Some(m), Some(values)) = (&mut m, arc_m) {
+                        m.extend([values[1], values[2]]);
+                    }
+                }
+                Arc::Circle {
+                    center,
+                    radius,
+                    start,
+                    sweep,
+                    middle,
+                } => {
+                    let segments =
+                        ((sweep.abs() / FRAC_PI_2 * segments_per_quadrant.max(1) as f64).ceil() as usize).max(1);
+                    for step in 1..=segments {
+                        let t = step as f64 / segments as f64;
+                        let coord = if step == segments {
+                            self.coords[j + 2]
+                        } else {
+                            let angle = start + sweep * t;
+                            Coord {
+                                x: center.x + radius * angle.cos(),
+                                y: center.y + radius * angle.sin(),
+                            }
+                        };
+                        coords.push(coord);
+                        if let (Some(z), Some(values)) = (&mut z, arc_z) {
+                            z.push(interpolate(values, middle, t));
+                        }
+                        if let (Some(m), Some(values)) = (&mut m, arc_m) {
+                            m.push(interpolate(values, middle, t));
+                        }
+                    }
+                }
+            }
+        }
+
+        LineString {
+            z,
+            m,
+            ..LineString::new(coords, self.crs.clone())
+        }
+    }
+}
+
+impl Bounded for CircularString {
+    /// Exact bounds of the arcs, including their extreme points.
+    fn bounds(&self) -> BoundingBox {
+        let Some(&first) = self.coords.first() else {
+            return BoundingBox::new(0.0, 0.0, 0.0, 0.0);
+        };
+        let mut bbox = BoundingBox::new(first.x, first.y, first.x, first.y);
+        for (j, arc) in self.arcs() {
+            bbox.expand_to_include_point(&self.coords[j + 2]);
+            match arc {
+                Arc::Straight => bbox.expand_to_include_point(&self.coords[j + 1]),
+                Arc::Circle {
+                    center,
+                    radius,
+                    start,
+                    sweep,
+                    ..
+                } => {
+                    // Cardinal directions crossed by the sweep
+                    let (low, high) = if sweep > 0.0 { (start, start + sweep) } else { (start + sweep, start) };
+                    let mut quadrant = (low / FRAC_PI_2).ceil();
+                    while quadrant * FRAC_PI_2 <= high {
+                        let angle = quadrant * FRAC_PI_2;
+                        bbox.expand_to_include_point(&Coord {
+                            x: center.x + radius * angle.cos(),
+                            y: center.y + radius * angle.sin(),
+                        });
+                        quadrant += 1.0;
+                    }
+                }
+            }
+        }
+        for &z in self.z.iter().flatten() {
+            bbox.expand_to_include_z(z);
+        }
+        bbox
+    }
+}
+
+/// Transforms the control points; arcs are not re-fitted to the target CRS.
+impl Transformable for CircularString {
+    fn transform(&self, target_crs: &Crs) -> Result<Self> {
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
+        let coords: Result<Vec<_>> = self.coords.iter().map(|coord| transformer.coord(coord)).collect();
+        Ok(CircularString {
+            coords: coords?,
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            crs: target_crs.clone(),
+        })
+    }
+
+    fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
+        *self = self.transform(target_crs)?;
+        Ok(())
+    }
+}
+
+/// A section of a compound curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CurveSegment {
+    /// Straight line segments
+    LineString(LineString),
+    /// Circular arcs
+    CircularString(CircularString),
+}
+
+impl CurveSegment {
+    fn coords(&self) -> &[Coord<f64>] {
+        match self {
+            CurveSegment::LineString(line) => &line.geom.0,
+            CurveSegment::CircularString(arc) => &arc.coords,
+        }
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            CurveSegment::LineString(line) => line.dimension(),
+            CurveSegment::CircularString(arc) => arc.dimension(),
+        }
+    }
+
+    /// Returns the length of the segment.
+    pub fn length(&self) -> f64 {
+        match self {
+            CurveSegment::LineString(line) => line.length(),
+            CurveSegment::CircularString(arc) => arc.length(),
+        }
+    }
+
+    /// Approximates the segment by a line string.
+    pub fn linearize(&self, segments_per_quadrant: u32) -> LineString {
+        match self {
+            CurveSegment::LineString(line) => line.clone(),
+            CurveSegment::CircularString(arc) => arc.linearize(segments_per_quadrant),
+        }
+    }
+}
+
+impl Bounded for CurveSegment {
+    fn bounds(&self) -> BoundingBox {
+        match self {
+            CurveSegment::LineString(line) => line.bounds(),
+            CurveSegment::CircularString(arc) => arc.bounds(),
+        }
+    }
+}
+
+impl Transformable for CurveSegment {
+    fn transform(&self, target_crs: &Crs) -> Result<Self> {
+        Ok(match self {
+            CurveSegment::LineString(line) => CurveSegment::LineString(line.transform(target_crs)?),
+            CurveSegment::CircularString(arc) => CurveSegment::CircularString(arc.transform(target_crs)?),
+        })
+    }
+
+    fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
+        match self {
+            CurveSegment::LineString(line) => line.transform_inplace(target_crs),
+            CurveSegment::CircularString(arc) => arc.transform_inplace(target_crs),
+        }
+    }
+}
+
+/// A curve made of connected line strings and circular strings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompoundCurve {
+    /// Segments, each starting where the previous one ends
+    pub segments: Vec<CurveSegment>,
+    /// Coordinate reference system
+    pub crs: Crs,
+}
+
+impl CompoundCurve {
+    /// Creates a new compound curve.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a segment is empty, does not start where the
+    /// previous one ends, or has a different dimension than the others
+    pub fn new(segments: Vec<CurveSegment>, crs: Crs) -> Result<Self> {
+        for (i, segment) in segments.iter().enumerate() {
+            if segment.coords().len() < 2 {
+                return Err(MeridianError::InvalidGeometry(format!(
+                    "Compound curve segment {} has fewer than 2 points",
+                    i
+                )));
+            }
+            if segment.dimension() != segments[0].dimension() {
+                return Err(MeridianError::InvalidGeometry(
+                    "Compound curve segments must have the same dimension".to_string(),
+                ));
+            }
+            if i > 0 && segments[i - 1].coords().last() != segment.coords().first() {
+                return Err(MeridianError::InvalidGeometry(format!(
+                    "Compound curve segment {} does not start where the previous one ends",
+                    i
+                )));
+            }
+        }
+        Ok(Self { segments, crs })
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        self.segments.first().map_or(Dimension::Xy, CurveSegment::dimension)
+    }
+
+    /// Checks if the compound curve has no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the length of the curve.
+    pub fn length(&self) -> f64 {
+        self.segments.iter().map(CurveSegment::length).sum()
+    }
+
+    /// Approximates the curve by a single line string.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments_per_quadrant` - Number of line segments per 90° of arc
+    pub fn linearize(&self, segments_per_quadrant: u32) -> LineString {
+        let dimension = self.dimension();
+        let mut coords = Vec::new();
+        let mut z = dimension.has_z().then(Vec::new);
+        let mut m = dimension.has_m().then(Vec::new);
+
+        for segment in &self.segments {
+            let line = segment.linearize(segments_per_quadrant);
+            // Shared end points are only kept once
+            let skip = usize::from(!coords.is_empty());
+            coords.extend(line.geom.0.iter().skip(skip));
+            if let (Some(z), Some(values)) = (&mut z, &line.z) {
+                z.extend(values.iter().skip(skip));
+            }
+            if let (Some(m), Some(values)) = (&mut m, &line.m) {
+                m.extend(values.iter().skip(skip));
+            }
+        }
+
+        LineString {
+            z,
+            m,
+            ..LineString::new(coords, self.crs.clone())
+        }
+    }
+}
+
+impl Bounded for CompoundCurve {
+    fn bounds(&self) -> BoundingBox {
+        let mut segments = self.segments.iter();
+        let Some(first) = segments.next() else {
+            return BoundingBox::new(0.0, 0.0, 0.0, 0.0);
+        };
+        let mut bbox = first.bounds();
+        for segment in segments {
+            bbox.expand_to_include_bbox(&segment.bounds());
+        }
+        bbox
+    }
+}
+
+impl Transformable for CompoundCurve {
+    fn transform(&self, target_crs: &Crs) -> Result<Self> {
+        let segments: Result<Vec<_>> = self.segments.iter().map(|s| s.transform(target_crs)).collect();
+        Ok(CompoundCurve {
+            segments: segments?,
+            crs: target_crs.clone(),
+        })
+    }
+
+    fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
+        for segment in &mut self.segments {
+            segment.transform_inplace(target_crs)?;
+        }
+        self.crs = target_crs.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::f64::consts::PI;
+
+    fn coord(x: f64, y: f64) -> Coord<f64> {
+        Coord { x, y }
+    }
+
+    #[test]
+    fn test_circular_string() {
+        // Upper half of the unit circle, clockwise from (-1, 0) to (1, 0)
+        let arc = CircularString::new(vec![coord(-1.0, 0.0), coord(0.0, 1.0), coord(1.0, 0.0)], Crs::wgs84())
+            .unwrap()
+            .with_z(vec![0.0, 10.0, 20.0]);
+        assert_abs_diff_eq!(arc.length(), PI, epsilon = 1e-12);
+
+        let bounds = arc.bounds();
+        assert_abs_diff_eq!(bounds.max_y, 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(bounds.min_y, 0.0, epsilon = 1e-12);
+        assert_eq!((bounds.min_z, bounds.max_z), (Some(0.0), Some(20.0)));
+
+        let line = arc.linearize(4);
+        assert_eq!(line.len(), 9);
+        assert_eq!(line.geom.0[8], coord(1.0, 0.0));
+        assert_abs_diff_eq!(line.geom.0[4].y, 1.0, epsilon = 1e-12);
+        assert_eq!(line.z.as_ref().unwrap()[4], 10.0);
+        assert!(line.geom.0.iter().all(|c| (c.x.hypot(c.y) - 1.0).abs() < 1e-12));
+        assert!(line.length() < PI && line.length() > 3.12);
+
+        assert!(CircularString::new(vec![coord(0.0, 0.0), coord(1.0, 1.0)], Crs::wgs84()).is_err());
+    }
+
+    #[test]
+    fn test_full_circle_and_collinear_arcs() {
+        let circle =
+            CircularString::new(vec![coord(2.0, 0.0), coord(0.0, 0.0), coord(2.0, 0.0)], Crs::wgs84()).unwrap();
+        assert_abs_diff_eq!(circle.length(), TAU, epsilon = 1e-12);
+        let bounds = circle.bounds();
+        assert_abs_diff_eq!(bounds.min_y, -1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(bounds.max_y, 1.0, epsilon = 1e-12);
+
+        let straight =
+            CircularString::new(vec![coord(0.0, 0.0), coord(1.0, 1.0), coord(2.0, 2.0)], Crs::wgs84()).unwrap();
+        assert_eq!(straight.linearize(DEFAULT_SEGMENTS_PER_QUADRANT).len(), 3);
+        assert_abs_diff_eq!(straight.length(), 8.0_f64.sqrt(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_compound_curve() {
+        let line = LineString::new(vec![coord(-2.0, 0.0), coord(-1.0, 0.0)], Crs::wgs84());
+        let arc = CircularString::new(vec![coord(-1.0, 0.0), coord(0.0, 1.0), coord(1.0, 0.0)], Crs::wgs84()).unwrap();
+        let curve = CompoundCurve::new(
+            vec![CurveSegment::LineString(line.clone()), CurveSegment::CircularString(arc.clone())],
+            Crs::wgs84(),
+        )
+        .unwrap();
+        assert_abs_diff_eq!(curve.length(), 1.0 + PI, epsilon = 1e-12);
+
+        let linear = curve.linearize(2);
+        assert_eq!(linear.len(), 2 + 4);
+        assert_eq!(linear.geom.0[1], coord(-1.0, 0.0));
+
+        let gap = LineString::new(vec![coord(5.0, 5.0), coord(6.0, 6.0)], Crs::wgs84());
+        assert!(CompoundCurve::new(
+            vec![CurveSegment::CircularString(arc), CurveSegment::LineString(gap)],
+            Crs::wgs84()
+        )
+        .is_err());
+    }
+}
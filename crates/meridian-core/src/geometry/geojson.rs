@@ -0,0 +1,205 @@
+//! GeoJSON (RFC 7946) geometry encoding.
+//!
+//! Positions carry Z as their third element. GeoJSON has no measures, so M
+//! values are dropped, and curves are linearized on output.
+
+use super::shape::{Position, Shape};
+use super::{Dimension, Geometry, DEFAULT_SEGMENTS_PER_QUADRANT};
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+use serde_json::{json, Map, Value};
+
+impl Geometry {
+    /// Encodes the geometry as a GeoJSON geometry object.
+    ///
+    /// Z values are written as the third position element; M values are
+    /// dropped and circular arcs are linearized.
+    pub fn to_geojson(&self) -> Value {
+        let (shape, dimension) = Shape::from_geometry(&self.linearize(DEFAULT_SEGMENTS_PER_QUADRANT));
+        shape_to_json(&shape, dimension.has_z())
+    }
+
+    /// Parses a GeoJSON geometry object.
+    ///
+    /// A geometry has Z values if any of its positions has a third element;
+    /// positions without one get NaN.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - GeoJSON geometry object
+    /// * `crs` - Coordinate reference system of the coordinates, WGS 84 for RFC 7946 data
+    pub fn from_geojson(value: &Value, crs: Crs) -> Result<Geometry> {
+        let (shape, dimension) = json_to_shape(value, 0)?;
+        shape.into_geometry(dimension, &crs)
+    }
+}
+
+fn position_to_json(p: &Position, has_z: bool) -> Value {
+    if has_z {
+        json!([p[0], p[1], p[2]])
+    } else {
+        json!([p[0], p[1]])
+    }
+}
+
+fn positions_to_json(positions: &[Position], has_z: bool) -> Value {
+    Value::Array(positions.iter().map(|p| position_to_json(p, has_z)).collect())
+}
+
+fn rings_to_json(rings: &[Vec<Position>], has_z: bool) -> Value {
+    Value::Array(rings.iter().map(|ring| positions_to_json(ring, has_z)).collect())
+}
+
+fn shape_to_json(shape: &Shape, has_z: bool) -> Value {
+    let (kind, coordinates) = match shape {
+        Shape::Point(p) => (
+            "Point",
+            p.map_or_else(|| json!([]), |p| position_to_json(&p, has_z)),
+        ),
+        Shape::LineString(p) | Shape::CircularString(p) => ("LineString", positions_to_json(p, has_z)),
+        Shape::MultiPoint(p) => ("MultiPoint", positions_to_json(p, has_z)),
+        Shape::Polygon(rings) => ("Polygon", rings_to_json(rings, has_z)),
+        Shape::MultiLineString(lines) => ("MultiLineString", rings_to_json(lines, has_z)),
+        Shape::MultiPolygon(polygons) => (
+            "MultiPolygon",
+            Value::Array(polygons.iter().map(|rings| rings_to_json(rings, has_z)).collect()),
+        ),
+        Shape::CompoundCurve(segments) => {
+            let positions: Vec<Position> = segments
+                .iter()
+                .flat_map(|segment| match segment {
+                    Shape::LineString(p) | Shape::CircularString(p) => p.clone(),
+                    _ => vec![],
+                })
+                .collect();
+            ("LineString", positions_to_json(&positions, has_z))
+        }
+        Shape::GeometryCollection(members) => {
+            let geometries = members
+                .iter()
+                .map(|(member, dimension)| shape_to_json(member, dimension.has_z()))
+                .collect();
+            return json!({ "type": "GeometryCollection", "geometries": Value::Array(geometries) });
+        }
+    };
+    let mut object = Map::new();
+    object.insert("type".to_string(), json!(kind));
+    object.insert("coordinates".to_string(), coordinates);
+    Value::Object(object)
+}
+
+fn invalid(message: impl Into<String>) -> MeridianError {
+    MeridianError::DeserializationError(format!("Invalid GeoJSON geometry: {}", message.into()))
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>> {
+    value.as_array().ok_or_else(|| invalid("expected an array"))
+}
+
+/// Parses positions, tracking whether any has a Z value
+struct Positions {
+    has_z: bool,
+}
+
+impl Positions {
+    fn position(&mut self, value: &Value) -> Result<Position> {
+        let values = array(value)?
+            .iter()
+            .map(|v| v.as_f64().ok_or_else(|| invalid("position elements must be numbers")))
+            .collect::<Result<Vec<_>>>()?;
+        if values.len() < 2 {
+            return Err(invalid("positions need at least two elements"));
+        }
+        let z = values.get(2).copied();
+        self.has_z |= z.is_some();
+        Ok([values[0], values[1], z.unwrap_or(f64::NAN), 0.0])
+    }
+
+    fn line(&mut self, value: &Value) -> Result<Vec<Position>> {
+        array(value)?.iter().map(|p| self.position(p)).collect()
+    }
+
+    fn rings(&mut self, value: &Value) -> Result<Vec<Vec<Position>>> {
+        array(value)?.iter().map(|line| self.line(line)).collect()
+    }
+}
+
+fn json_to_shape(value: &Value, depth: usize) -> Result<(Shape, Dimension)> {
+    if depth > 32 {
+        return Err(invalid("geometry collections are nested too deeply"));
+    }
+    let kind = value
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("missing type"))?;
+
+    if kind == "GeometryCollection" {
+        let geometries = value.get("geometries").ok_or_else(|| invalid("missing geometries"))?;
+        let members = array(geometries)?
+            .iter()
+            .map(|member| json_to_shape(member, depth + 1))
+            .collect::<Result<Vec<_>>>()?;
+        let has_z = members.iter().any(|(_, dimension)| dimension.has_z());
+        return Ok((Shape::GeometryCollection(members), Dimension::new(has_z, false)));
+    }
+
+    let coordinates = value.get("coordinates").ok_or_else(|| invalid("missing coordinates"))?;
+    let mut positions = Positions { has_z: false };
+    let shape = match kind {
+        "Point" if array(coordinates)?.is_empty() => Shape::Point(None),
+        "Point" => Shape::Point(Some(positions.position(coordinates)?)),
+        "LineString" => Shape::LineString(positions.line(coordinates)?),
+        "Polygon" => Shape::Polygon(positions.rings(coordinates)?),
+        "MultiPoint" => Shape::MultiPoint(positions.line(coordinates)?),
+        "MultiLineString" => Shape::MultiLineString(positions.rings(coordinates)?),
+        "MultiPolygon" => Shape::MultiPolygon(
+            array(coordinates)?
+                .iter()
+                .map(|rings| positions.rings(rings))
+                .collect::<Result<_>>()?,
+        ),
+        other => return Err(invalid(format!("unsupported type {}", other))),
+    };
+    Ok((shape, Dimension::new(positions.has_z, false)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+
+    #[test]
+    fn test_geojson_z() {
+        let point = Geometry::Point(Point::new_3d(1.0, 2.0, 3.0, Crs::wgs84()).with_m(4.0));
+        assert_eq!(point.to_geojson(), json!({"type": "Point", "coordinates": [1.0, 2.0, 3.0]}));
+
+        let polygon = Geometry::from_wkt("POLYGON Z ((0 0 1, 4 0 2, 4 4 3, 0 0 1))", Crs::wgs84()).unwrap();
+        let value = polygon.to_geojson();
+        assert_eq!(value["coordinates"][0][2], json!([4.0, 4.0, 3.0]));
+        assert_eq!(Geometry::from_geojson(&value, Crs::wgs84()).unwrap(), polygon);
+
+        let collection = json!({
+            "type": "GeometryCollection",
+            "geometries": [{"type": "LineString", "coordinates": [[0, 0], [1, 1, 5]]}]
+        });
+        let Geometry::GeometryCollection(collection) = Geometry::from_geojson(&collection, Crs::wgs84()).unwrap()
+        else {
+            panic!("expected a geometry collection");
+        };
+        let Geometry::LineString(line) = &collection.geometries[0] else {
+            panic!("expected a line string");
+        };
+        assert!(line.z.as_ref().unwrap()[0].is_nan());
+        assert_eq!(line.z.as_ref().unwrap()[1], 5.0);
+
+        assert!(Geometry::from_geojson(&json!({"type": "Point", "coordinates": [1]}), Crs::wgs84()).is_err());
+    }
+
+    #[test]
+    fn test_geojson_curves() {
+        let arc = Geometry::from_wkt("CIRCULARSTRING (-1 0, 0 1, 1 0)", Crs::wgs84()).unwrap();
+        let value = arc.to_geojson();
+        assert_eq!(value["type"], "LineString");
+        assert_eq!(value["coordinates"].as_array().unwrap().len(), 65);
+    }
+}
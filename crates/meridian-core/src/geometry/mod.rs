@@ -3,6 +3,12 @@
 //! This module provides wrapper types around geo-types geometries with additional
 //! functionality for coordinate transformations, spatial indexing, and CRS support.
 //!
+//! Geometries carry optional Z (elevation) and M (measure) ordinates next to
+//! their 2D geo-types coordinates, one value per coordinate in the order the
+//! coordinates are stored. Curved geometries ([`CircularString`],
+//! [`CompoundCurve`]) can be linearized into line strings. WKT, WKB, TWKB
+//! and GeoJSON encodings are available on [`Geometry`].
+//!
 //! # Examples
 //!
 //! ```ignore
@@ -14,29 +20,153 @@
 //!
 //! // Transform to Web Mercator
 //! point.transform_inplace(&Crs::web_mercator())?;
+//!
+//! // A 3D point with a measure
+//! let survey = Point::new_3d(-122.4194, 37.7749, 16.0, Crs::wgs84()).with_m(1200.0);
 //! ```
 
+mod curve;
+mod geojson;
+mod shape;
+mod twkb;
+mod wkb;
+mod wkt;
+
+pub use curve::{CircularString, CompoundCurve, CurveSegment, DEFAULT_SEGMENTS_PER_QUADRANT};
+pub use twkb::TwkbOptions;
+pub use wkb::{ByteOrder, WkbDialect, WkbOptions};
+
 use crate::bbox::BoundingBox;
 use crate::crs::Crs;
 use crate::error::Result;
 use crate::traits::{Bounded, Transformable};
 use geo::{Area, BoundingRect, Contains, EuclideanLength};
 use geo_types::{
-    Coord, LineString as GeoLineString,
+    Coord, Geometry as GeoGeometry, GeometryCollection as GeoGeometryCollection, LineString as GeoLineString,
     MultiLineString as GeoMultiLineString, MultiPoint as GeoMultiPoint,
-    MultiPolygon as GeoMultiPolygon, Point as GeoPoint, Polygon as GeoPolygon,
+    MultiPolygon as GeoMultiPolygon, Point as GeoPoint, Polygon as GeoPolygon, Rect,
 };
 use rstar::{PointDistance, RTreeObject, AABB};
 use serde::{Deserialize, Serialize};
 
+/// Coordinate dimension of a geometry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Dimension {
+    /// X and Y
+    #[default]
+    Xy,
+    /// X, Y and Z
+    Xyz,
+    /// X, Y and M
+    Xym,
+    /// X, Y, Z and M
+    Xyzm,
+}
+
+impl Dimension {
+    /// Creates a dimension from its optional ordinates.
+    pub fn new(has_z: bool, has_m: bool) -> Self {
+        match (has_z, has_m) {
+            (false, false) => Dimension::Xy,
+            (true, false) => Dimension::Xyz,
+            (false, true) => Dimension::Xym,
+            (true, true) => Dimension::Xyzm,
+        }
+    }
+
+    /// Checks if the dimension has a Z ordinate.
+    pub fn has_z(self) -> bool {
+        matches!(self, Dimension::Xyz | Dimension::Xyzm)
+    }
+
+    /// Checks if the dimension has an M ordinate.
+    pub fn has_m(self) -> bool {
+        matches!(self, Dimension::Xym | Dimension::Xyzm)
+    }
+}
+
+/// Panics unless there is one ordinate per coordinate
+fn check_ordinates(name: &str, values: &[f64], coords: usize) {
+    assert_eq!(
+        values.len(),
+        coords,
+        "{} ordinates must match the number of coordinates",
+        name
+    );
+}
+
+/// Bounding box of a geometry's 2D extent and Z ordinates
+fn bounds_with_z(rect: Option<Rect<f64>>, z: Option<&[f64]>) -> BoundingBox {
+    let mut bbox = rect.map_or_else(|| BoundingBox::new(0.0, 0.0, 0.0, 0.0), BoundingBox::from_rect);
+    for &value in z.into_iter().flatten() {
+        bbox.expand_to_include_z(value);
+    }
+    bbox
+}
+
+/// Transforms coordinates together with their Z ordinates
+///
+/// Z ordinates are taken in coordinate order and are heights above the
+/// ellipsoid, so datum shifts move them with their positions. NaN ordinates
+/// are kept as they are.
+struct CoordTransformer<'a> {
+    source: Crs,
+    target: &'a Crs,
+    z: Option<std::slice::Iter<'a, f64>>,
+    transformed_z: Vec<f64>,
+}
+
+impl<'a> CoordTransformer<'a> {
+    fn new(source: &Crs, target: &'a Crs, z: Option<&'a [f64]>) -> Self {
+        Self {
+            source: source.clone(),
+            target,
+            transformed_z: Vec::with_capacity(z.map_or(0, <[f64]>::len)),
+            z: z.map(<[f64]>::iter),
+        }
+    }
+
+    fn coord(&mut self, coord: &Coord<f64>) -> Result<Coord<f64>> {
+        let (x, y) = match self.z.as_mut().and_then(Iterator::next) {
+            Some(&z) if !z.is_nan() => {
+                let (x, y, z) = self.source.transform_point_3d(coord.x, coord.y, z, self.target)?;
+                self.transformed_z.push(z);
+                (x, y)
+            }
+            z => {
+                self.transformed_z.extend(z);
+                self.source.transform_point(coord.x, coord.y, self.target)?
+            }
+        };
+        Ok(Coord { x, y })
+    }
+
+    /// Transformed Z ordinates, if the geometry has any
+    fn into_z(self) -> Option<Vec<f64>> {
+        self.z.map(|_| self.transformed_z)
+    }
+}
+
+/// Number of coordinates of a polygon, exterior ring first
+fn polygon_coord_count(polygon: &GeoPolygon<f64>) -> usize {
+    polygon.exterior().0.len() + polygon.interiors().iter().map(|ring| ring.0.len()).sum::<usize>()
+}
+
 /// A point geometry with CRS support.
 ///
 /// Represents a single location in space with x (longitude/easting) and
-/// y (latitude/northing) coordinates.
+/// y (latitude/northing) coordinates, and optional z (elevation) and m
+/// (measure) values.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     /// The underlying geo-types point
     pub geom: GeoPoint<f64>,
+    /// Z ordinate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<f64>,
+    /// M ordinate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<f64>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -59,10 +189,36 @@ impl Point {
     /// let point = Point::new(-122.4194, 37.7749, Crs::wgs84());
     /// ```
     pub fn new(x: f64, y: f64, crs: Crs) -> Self {
-        Self {
-            geom: GeoPoint::new(x, y),
-            crs,
-        }
+        Self::from_geo(GeoPoint::new(x, y), crs)
+    }
+
+    /// Creates a new point with a Z ordinate.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let summit = Point::new_3d(86.925, 27.9881, 8848.86, Crs::wgs84());
+    /// assert_eq!(summit.z, Some(8848.86));
+    /// ```
+    pub fn new_3d(x: f64, y: f64, z: f64, crs: Crs) -> Self {
+        Self::new(x, y, crs).with_z(z)
+    }
+
+    /// Sets the Z ordinate.
+    pub fn with_z(mut self, z: f64) -> Self {
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinate.
+    pub fn with_m(mut self, m: f64) -> Self {
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
     }
 
     /// Returns the x coordinate.
@@ -82,27 +238,40 @@ impl Point {
 
     /// Creates a point from a geo-types Point and CRS.
     pub fn from_geo(geom: GeoPoint<f64>, crs: Crs) -> Self {
-        Self { geom, crs }
+        Self {
+            geom,
+            z: None,
+            m: None,
+            crs,
+        }
     }
 }
 
 impl Bounded for Point {
     fn bounds(&self) -> BoundingBox {
-        BoundingBox::new(self.x(), self.y(), self.x(), self.y())
+        let bbox = BoundingBox::new(self.x(), self.y(), self.x(), self.y());
+        match self.z {
+            Some(z) if !z.is_nan() => bbox.with_z(z, z),
+            _ => bbox,
+        }
     }
 }
 
+/// Z ordinates are transformed as ellipsoidal heights, which change only
+/// with a datum shift; M ordinates are carried over unchanged.
 impl Transformable for Point {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
-        let (x, y) = source_crs.transform_point(self.x(), self.y(), target_crs)?;
-        Ok(Point::new(x, y, target_crs.clone()))
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_ref().map(std::slice::from_ref));
+        let coord = transformer.coord(&self.coord())?;
+        Ok(Point {
+            z: transformer.into_z().map(|z| z[0]),
+            m: self.m,
+            ..Point::new(coord.x, coord.y, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
-        let (x, y) = self.crs.transform_point(self.x(), self.y(), target_crs)?;
-        self.geom = GeoPoint::new(x, y);
-        self.crs = target_crs.clone();
+        *self = self.transform(target_crs)?;
         Ok(())
     }
 }
@@ -128,6 +297,12 @@ impl PointDistance for Point {
 pub struct MultiPoint {
     /// The underlying geo-types multipoint
     pub geom: GeoMultiPoint<f64>,
+    /// Z ordinates, one per point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -137,10 +312,39 @@ impl MultiPoint {
     pub fn new(points: Vec<GeoPoint<f64>>, crs: Crs) -> Self {
         Self {
             geom: GeoMultiPoint::new(points),
+            z: None,
+            m: None,
             crs,
         }
     }
 
+    /// Sets the Z ordinates, one per point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of points.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.len());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of points.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.len());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
     /// Returns the number of points.
     pub fn len(&self) -> usize {
         self.geom.0.len()
@@ -159,28 +363,26 @@ impl MultiPoint {
 
 impl Bounded for MultiPoint {
     fn bounds(&self) -> BoundingBox {
-        if let Some(rect) = self.geom.bounding_rect() {
-            BoundingBox::from_rect(rect)
-        } else {
-            BoundingBox::new(0.0, 0.0, 0.0, 0.0)
-        }
+        bounds_with_z(self.geom.bounding_rect(), self.z.as_deref())
     }
 }
 
 impl Transformable for MultiPoint {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
         let transformed_points: Result<Vec<_>> = self
             .geom
             .0
             .iter()
-            .map(|pt| {
-                let (x, y) = source_crs.transform_point(pt.x(), pt.y(), target_crs)?;
-                Ok(GeoPoint::new(x, y))
-            })
+            .map(|pt| Ok(GeoPoint(transformer.coord(&pt.0)?)))
             .collect();
+        let transformed_points = transformed_points?;
 
-        Ok(MultiPoint::new(transformed_points?, target_crs.clone()))
+        Ok(MultiPoint {
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            ..MultiPoint::new(transformed_points, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
@@ -197,6 +399,12 @@ impl Transformable for MultiPoint {
 pub struct LineString {
     /// The underlying geo-types linestring
     pub geom: GeoLineString<f64>,
+    /// Z ordinates, one per coordinate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per coordinate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -206,10 +414,55 @@ impl LineString {
     pub fn new(coords: Vec<Coord<f64>>, crs: Crs) -> Self {
         Self {
             geom: GeoLineString::new(coords),
+            z: None,
+            m: None,
             crs,
         }
     }
 
+    /// Sets the Z ordinates, one per coordinate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.len());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per coordinate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.len());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
+    /// Returns the 3D length of the linestring, or the 2D length without Z.
+    pub fn length_3d(&self) -> f64 {
+        let Some(z) = &self.z else {
+            return self.length();
+        };
+        self.geom
+            .0
+            .windows(2)
+            .zip(z.windows(2))
+            .map(|(c, z)| {
+                let (dx, dy, dz) = (c[1].x - c[0].x, c[1].y - c[0].y, z[1] - z[0]);
+                (dx * dx + dy * dy + dz * dz).sqrt()
+            })
+            .sum()
+    }
+
     /// Returns the number of coordinates.
     pub fn len(&self) -> usize {
         self.geom.0.len()
@@ -233,27 +486,21 @@ impl LineString {
 
 impl Bounded for LineString {
     fn bounds(&self) -> BoundingBox {
-        if let Some(rect) = self.geom.bounding_rect() {
-            BoundingBox::from_rect(rect)
-        } else {
-            BoundingBox::new(0.0, 0.0, 0.0, 0.0)
-        }
+        bounds_with_z(self.geom.bounding_rect(), self.z.as_deref())
     }
 }
 
 impl Transformable for LineString {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
-        let transformed_coords: Result<Vec<_>> = self
-            .geom
-            .coords()
-            .map(|coord| {
-                let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                Ok(Coord { x, y })
-            })
-            .collect();
-
-        Ok(LineString::new(transformed_coords?, target_crs.clone()))
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
+        let transformed_coords: Result<Vec<_>> = self.geom.coords().map(|coord| transformer.coord(coord)).collect();
+        let transformed_coords = transformed_coords?;
+
+        Ok(LineString {
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            ..LineString::new(transformed_coords, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
@@ -276,6 +523,12 @@ impl RTreeObject for LineString {
 pub struct MultiLineString {
     /// The underlying geo-types multilinestring
     pub geom: GeoMultiLineString<f64>,
+    /// Z ordinates, one per coordinate of all linestrings in order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per coordinate of all linestrings in order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -285,10 +538,44 @@ impl MultiLineString {
     pub fn new(lines: Vec<GeoLineString<f64>>, crs: Crs) -> Self {
         Self {
             geom: GeoMultiLineString::new(lines),
+            z: None,
+            m: None,
             crs,
         }
     }
 
+    /// Returns the number of coordinates of all linestrings.
+    pub fn coord_count(&self) -> usize {
+        self.geom.0.iter().map(|line| line.0.len()).sum()
+    }
+
+    /// Sets the Z ordinates, one per coordinate of all linestrings in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.coord_count());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per coordinate of all linestrings in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.coord_count());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
     /// Returns the number of linestrings.
     pub fn len(&self) -> usize {
         self.geom.0.len()
@@ -312,34 +599,30 @@ impl MultiLineString {
 
 impl Bounded for MultiLineString {
     fn bounds(&self) -> BoundingBox {
-        if let Some(rect) = self.geom.bounding_rect() {
-            BoundingBox::from_rect(rect)
-        } else {
-            BoundingBox::new(0.0, 0.0, 0.0, 0.0)
-        }
+        bounds_with_z(self.geom.bounding_rect(), self.z.as_deref())
     }
 }
 
 impl Transformable for MultiLineString {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
         let transformed_lines: Result<Vec<_>> = self
             .geom
             .0
             .iter()
             .map(|line| {
-                let transformed_coords: Result<Vec<_>> = line
-                    .coords()
-                    .map(|coord| {
-                        let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                        Ok(Coord { x, y })
-                    })
-                    .collect();
+                let transformed_coords: Result<Vec<_>> =
+                    line.coords().map(|coord| transformer.coord(coord)).collect();
                 Ok(GeoLineString::new(transformed_coords?))
             })
             .collect();
+        let transformed_lines = transformed_lines?;
 
-        Ok(MultiLineString::new(transformed_lines?, target_crs.clone()))
+        Ok(MultiLineString {
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            ..MultiLineString::new(transformed_lines, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
@@ -364,6 +647,12 @@ impl RTreeObject for MultiLineString {
 pub struct Polygon {
     /// The underlying geo-types polygon
     pub geom: GeoPolygon<f64>,
+    /// Z ordinates, one per coordinate of the exterior and then the interior rings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per coordinate of the exterior and then the interior rings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -373,10 +662,44 @@ impl Polygon {
     pub fn new(exterior: GeoLineString<f64>, interiors: Vec<GeoLineString<f64>>, crs: Crs) -> Self {
         Self {
             geom: GeoPolygon::new(exterior, interiors),
+            z: None,
+            m: None,
             crs,
         }
     }
 
+    /// Returns the number of coordinates of all rings.
+    pub fn coord_count(&self) -> usize {
+        polygon_coord_count(&self.geom)
+    }
+
+    /// Sets the Z ordinates, one per coordinate of the exterior and then the interior rings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.coord_count());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per coordinate of the exterior and then the interior rings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.coord_count());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
     /// Returns the area of the polygon.
     pub fn area(&self) -> f64 {
         self.geom.unsigned_area()
@@ -400,28 +723,17 @@ impl Polygon {
 
 impl Bounded for Polygon {
     fn bounds(&self) -> BoundingBox {
-        if let Some(rect) = self.geom.bounding_rect() {
-            BoundingBox::from_rect(rect)
-        } else {
-            BoundingBox::new(0.0, 0.0, 0.0, 0.0)
-        }
+        bounds_with_z(self.geom.bounding_rect(), self.z.as_deref())
     }
 }
 
 impl Transformable for Polygon {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
 
         // Transform exterior
-        let exterior_coords: Result<Vec<_>> = self
-            .geom
-            .exterior()
-            .coords()
-            .map(|coord| {
-                let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                Ok(Coord { x, y })
-            })
-            .collect();
+        let exterior_coords: Result<Vec<_>> =
+            self.geom.exterior().coords().map(|coord| transformer.coord(coord)).collect();
         let exterior = GeoLineString::new(exterior_coords?);
 
         // Transform interiors
@@ -430,18 +742,18 @@ impl Transformable for Polygon {
             .interiors()
             .iter()
             .map(|interior| {
-                let interior_coords: Result<Vec<_>> = interior
-                    .coords()
-                    .map(|coord| {
-                        let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                        Ok(Coord { x, y })
-                    })
-                    .collect();
+                let interior_coords: Result<Vec<_>> =
+                    interior.coords().map(|coord| transformer.coord(coord)).collect();
                 Ok(GeoLineString::new(interior_coords?))
             })
             .collect();
+        let interiors = interiors?;
 
-        Ok(Polygon::new(exterior, interiors?, target_crs.clone()))
+        Ok(Polygon {
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            ..Polygon::new(exterior, interiors, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
@@ -464,6 +776,12 @@ impl RTreeObject for Polygon {
 pub struct MultiPolygon {
     /// The underlying geo-types multipolygon
     pub geom: GeoMultiPolygon<f64>,
+    /// Z ordinates, one per coordinate of all polygon rings in order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub z: Option<Vec<f64>>,
+    /// M ordinates, one per coordinate of all polygon rings in order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub m: Option<Vec<f64>>,
     /// Coordinate reference system
     pub crs: Crs,
 }
@@ -473,10 +791,44 @@ impl MultiPolygon {
     pub fn new(polygons: Vec<GeoPolygon<f64>>, crs: Crs) -> Self {
         Self {
             geom: GeoMultiPolygon::new(polygons),
+            z: None,
+            m: None,
             crs,
         }
     }
 
+    /// Returns the number of coordinates of all polygon rings.
+    pub fn coord_count(&self) -> usize {
+        self.geom.0.iter().map(polygon_coord_count).sum()
+    }
+
+    /// Sets the Z ordinates, one per coordinate of all polygon rings in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_z(mut self, z: Vec<f64>) -> Self {
+        check_ordinates("Z", &z, self.coord_count());
+        self.z = Some(z);
+        self
+    }
+
+    /// Sets the M ordinates, one per coordinate of all polygon rings in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values differs from the number of coordinates.
+    pub fn with_m(mut self, m: Vec<f64>) -> Self {
+        check_ordinates("M", &m, self.coord_count());
+        self.m = Some(m);
+        self
+    }
+
+    /// Returns the coordinate dimension.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::new(self.z.is_some(), self.m.is_some())
+    }
+
     /// Returns the number of polygons.
     pub fn len(&self) -> usize {
         self.geom.0.len()
@@ -500,31 +852,21 @@ impl MultiPolygon {
 
 impl Bounded for MultiPolygon {
     fn bounds(&self) -> BoundingBox {
-        if let Some(rect) = self.geom.bounding_rect() {
-            BoundingBox::from_rect(rect)
-        } else {
-            BoundingBox::new(0.0, 0.0, 0.0, 0.0)
-        }
+        bounds_with_z(self.geom.bounding_rect(), self.z.as_deref())
     }
 }
 
 impl Transformable for MultiPolygon {
     fn transform(&self, target_crs: &Crs) -> Result<Self> {
-        let mut source_crs = self.crs.clone();
+        let mut transformer = CoordTransformer::new(&self.crs, target_crs, self.z.as_deref());
         let transformed_polygons: Result<Vec<_>> = self
             .geom
             .0
             .iter()
             .map(|poly| {
                 // Transform exterior
-                let exterior_coords: Result<Vec<_>> = poly
-                    .exterior()
-                    .coords()
-                    .map(|coord| {
-                        let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                        Ok(Coord { x, y })
-                    })
-                    .collect();
+                let exterior_coords: Result<Vec<_>> =
+                    poly.exterior().coords().map(|coord| transformer.coord(coord)).collect();
                 let exterior = GeoLineString::new(exterior_coords?);
 
                 // Transform interiors
@@ -532,13 +874,8 @@ impl Transformable for MultiPolygon {
                     .interiors()
                     .iter()
                     .map(|interior| {
-                        let interior_coords: Result<Vec<_>> = interior
-                            .coords()
-                            .map(|coord| {
-                                let (x, y) = source_crs.transform_point(coord.x, coord.y, target_crs)?;
-                                Ok(Coord { x, y })
-                            })
-                            .collect();
+                        let interior_coords: Result<Vec<_>> =
+                            interior.coords().map(|coord| transformer.coord(coord)).collect();
                         Ok(GeoLineString::new(interior_coords?))
                     })
                     .collect();
@@ -546,8 +883,13 @@ impl Transformable for MultiPolygon {
                 Ok(GeoPolygon::new(exterior, interiors?))
             })
             .collect();
+        let transformed_polygons = transformed_polygons?;
 
-        Ok(MultiPolygon::new(transformed_polygons?, target_crs.clone()))
+        Ok(MultiPolygon {
+            z: transformer.into_z(),
+            m: self.m.clone(),
+            ..MultiPolygon::new(transformed_polygons, target_crs.clone())
+        })
     }
 
     fn transform_inplace(&mut self, target_crs: &Crs) -> Result<()> {
@@ -628,6 +970,134 @@ pub enum Geometry {
     MultiPolygon(MultiPolygon),
     /// A collection of mixed geometry types
     GeometryCollection(GeometryCollection),
+    /// A sequence of circular arcs
+    CircularString(CircularString),
+    /// A sequence of connected line strings and circular arcs
+    CompoundCurve(CompoundCurve),
+}
+
+impl Geometry {
+    /// Returns the coordinate reference system.
+    pub fn crs(&self) -> &Crs {
+        match self {
+            Geometry::Point(g) => &g.crs,
+            Geometry::MultiPoint(g) => &g.crs,
+            Geometry::LineString(g) => &g.crs,
+            Geometry::MultiLineString(g) => &g.crs,
+            Geometry::Polygon(g) => &g.crs,
+            Geometry::MultiPolygon(g) => &g.crs,
+            Geometry::GeometryCollection(g) => &g.crs,
+            Geometry::CircularString(g) => &g.crs,
+            Geometry::CompoundCurve(g) => &g.crs,
+        }
+    }
+
+    /// Returns the coordinate dimension.
+    ///
+    /// Collections and compound curves have Z or M if any member has.
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Geometry::Point(g) => g.dimension(),
+            Geometry::MultiPoint(g) => g.dimension(),
+            Geometry::LineString(g) => g.dimension(),
+            Geometry::MultiLineString(g) => g.dimension(),
+            Geometry::Polygon(g) => g.dimension(),
+            Geometry::MultiPolygon(g) => g.dimension(),
+            Geometry::GeometryCollection(g) => {
+                let dimensions: Vec<_> = g.geometries.iter().map(Geometry::dimension).collect();
+                Dimension::new(
+                    dimensions.iter().any(|d| d.has_z()),
+                    dimensions.iter().any(|d| d.has_m()),
+                )
+            }
+            Geometry::CircularString(g) => g.dimension(),
+            Geometry::CompoundCurve(g) => g.dimension(),
+        }
+    }
+
+    /// Checks if the geometry has Z ordinates.
+    pub fn has_z(&self) -> bool {
+        self.dimension().has_z()
+    }
+
+    /// Checks if the geometry has M ordinates.
+    pub fn has_m(&self) -> bool {
+        self.dimension().has_m()
+    }
+
+    /// Checks if the geometry contains circular arcs.
+    pub fn is_curved(&self) -> bool {
+        match self {
+            Geometry::CircularString(_) | Geometry::CompoundCurve(_) => true,
+            Geometry::GeometryCollection(g) => g.geometries.iter().any(Geometry::is_curved),
+            _ => false,
+        }
+    }
+
+    /// Replaces circular arcs by line strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments_per_quadrant` - Number of line segments per 90° of arc
+    ///
+    /// # Returns
+    ///
+    /// The geometry with curves linearized; other geometries are unchanged
+    pub fn linearize(&self, segments_per_quadrant: u32) -> Geometry {
+        match self {
+            Geometry::CircularString(g) => Geometry::LineString(g.linearize(segments_per_quadrant)),
+            Geometry::CompoundCurve(g) => Geometry::LineString(g.linearize(segments_per_quadrant)),
+            Geometry::GeometryCollection(g) => Geometry::GeometryCollection(GeometryCollection::new(
+                g.geometries.iter().map(|g| g.linearize(segments_per_quadrant)).collect(),
+                g.crs.clone(),
+            )),
+            other => other.clone(),
+        }
+    }
+
+    /// Wraps a 2D geo-types geometry.
+    ///
+    /// Lines become line strings; rectangles and triangles become polygons.
+    pub fn from_geo(geometry: GeoGeometry<f64>, crs: Crs) -> Geometry {
+        match geometry {
+            GeoGeometry::Point(p) => Geometry::Point(Point::from_geo(p, crs)),
+            GeoGeometry::Line(line) => Geometry::LineString(LineString::new(vec![line.start, line.end], crs)),
+            GeoGeometry::LineString(line) => Geometry::LineString(LineString::new(line.0, crs)),
+            GeoGeometry::Polygon(polygon) => {
+                let (exterior, interiors) = polygon.into_inner();
+                Geometry::Polygon(Polygon::new(exterior, interiors, crs))
+            }
+            GeoGeometry::Rect(rect) => Self::from_geo(GeoGeometry::Polygon(rect.to_polygon()), crs),
+            GeoGeometry::Triangle(triangle) => Self::from_geo(GeoGeometry::Polygon(triangle.to_polygon()), crs),
+            GeoGeometry::MultiPoint(points) => Geometry::MultiPoint(MultiPoint::new(points.0, crs)),
+            GeoGeometry::MultiLineString(lines) => Geometry::MultiLineString(MultiLineString::new(lines.0, crs)),
+            GeoGeometry::MultiPolygon(polygons) => Geometry::MultiPolygon(MultiPolygon::new(polygons.0, crs)),
+            GeoGeometry::GeometryCollection(collection) => Geometry::GeometryCollection(GeometryCollection::new(
+                collection.0.into_iter().map(|g| Self::from_geo(g, crs.clone())).collect(),
+                crs,
+            )),
+        }
+    }
+
+    /// Returns the 2D geo-types geometry, without Z and M.
+    ///
+    /// Curves are linearized with [`DEFAULT_SEGMENTS_PER_QUADRANT`].
+    pub fn to_geo(&self) -> GeoGeometry<f64> {
+        match self {
+            Geometry::Point(g) => GeoGeometry::Point(g.geom),
+            Geometry::MultiPoint(g) => GeoGeometry::MultiPoint(g.geom.clone()),
+            Geometry::LineString(g) => GeoGeometry::LineString(g.geom.clone()),
+            Geometry::MultiLineString(g) => GeoGeometry::MultiLineString(g.geom.clone()),
+            Geometry::Polygon(g) => GeoGeometry::Polygon(g.geom.clone()),
+            Geometry::MultiPolygon(g) => GeoGeometry::MultiPolygon(g.geom.clone()),
+            Geometry::GeometryCollection(g) => {
+                GeoGeometry::GeometryCollection(GeoGeometryCollection(g.geometries.iter().map(Geometry::to_geo).collect()))
+            }
+            Geometry::CircularString(_) | Geometry::CompoundCurve(_) => {
+                self.linearize(DEFAULT_SEGMENTS_PER_QUADRANT).to_geo()
+            }
+        }
+    }
 }
 
 impl Bounded for Geometry {
@@ -640,6 +1110,8 @@ impl Bounded for Geometry {
             Geometry::Polygon(g) => g.bounds(),
             Geometry::MultiPolygon(g) => g.bounds(),
             Geometry::GeometryCollection(g) => g.bounds(),
+            Geometry::CircularString(g) => g.bounds(),
+            Geometry::CompoundCurve(g) => g.bounds(),
         }
     }
 }
@@ -664,6 +1136,8 @@ impl Transformable for Geometry {
                     target_crs.clone(),
                 ))
             }
+            Geometry::CircularString(g) => Geometry::CircularString(g.transform(target_crs)?),
+            Geometry::CompoundCurve(g) => Geometry::CompoundCurve(g.transform(target_crs)?),
         })
     }
 
@@ -682,6 +1156,8 @@ impl Transformable for Geometry {
                 gc.crs = target_crs.clone();
                 Ok(())
             }
+            Geometry::CircularString(g) => g.transform_inplace(target_crs),
+            Geometry::CompoundCurve(g) => g.transform_inplace(target_crs),
         }
     }
 }
@@ -730,4 +1206,82 @@ mod tests {
         let polygon = Polygon::new(exterior, vec![], Crs::wgs84());
         assert_eq!(polygon.area(), 100.0);
     }
+
+    #[test]
+    fn test_z_and_m_ordinates() {
+        let point = Point::new_3d(1.0, 2.0, 3.0, Crs::wgs84()).with_m(4.0);
+        assert_eq!(point.dimension(), Dimension::Xyzm);
+        assert_eq!((point.bounds().min_z, point.bounds().max_z), (Some(3.0), Some(3.0)));
+
+        let line = LineString::new(vec![Coord { x: 0.0, y: 0.0 }, Coord { x: 3.0, y: 0.0 }], Crs::wgs84())
+            .with_z(vec![0.0, 4.0]);
+        assert_eq!(line.dimension(), Dimension::Xyz);
+        assert_eq!(line.length_3d(), 5.0);
+
+        let collection = Geometry::GeometryCollection(GeometryCollection::new(
+            vec![Geometry::Point(point.clone()), Geometry::LineString(line)],
+            Crs::wgs84(),
+        ));
+        assert_eq!(collection.dimension(), Dimension::Xyzm);
+        let bounds = collection.bounds();
+        assert_eq!((bounds.min_z, bounds.max_z), (Some(0.0), Some(4.0)));
+
+        // Ordinates survive serialization and CRS transformations
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), point);
+        let projected = point.transform(&Crs::web_mercator()).unwrap();
+        assert_eq!((projected.z, projected.m), (Some(3.0), Some(4.0)));
+    }
+
+    #[test]
+    fn test_z_follows_datum_shift() {
+        // OSGB 1936 lies about 50 m below WGS 84 in London
+        let osgb36 = Crs::from_epsg(4277).unwrap();
+        let point = Point::new_3d(-0.1275, 51.5072, 100.0, Crs::wgs84()).with_m(7.0);
+        let shifted = point.transform(&osgb36).unwrap();
+        let z = shifted.z.unwrap();
+        assert!((z - 100.0).abs() > 10.0);
+        assert_eq!(shifted.m, Some(7.0));
+        let back = shifted.transform(&Crs::wgs84()).unwrap();
+        assert!((back.z.unwrap() - 100.0).abs() < 1e-3);
+
+        let line = LineString::new(
+            vec![Coord { x: -0.1275, y: 51.5072 }, Coord { x: -0.1, y: 51.5 }],
+            Crs::wgs84(),
+        )
+        .with_z(vec![100.0, f64::NAN]);
+        let shifted = Geometry::LineString(line).transform(&osgb36).unwrap();
+        let Geometry::LineString(shifted) = shifted else {
+            unreachable!()
+        };
+        let shifted_z = shifted.z.unwrap();
+        assert!((shifted_z[0] - z).abs() < 1e-6);
+        assert!(shifted_z[1].is_nan());
+
+        // Projections without a datum change keep heights
+        let projected = point.transform(&Crs::from_epsg(32630).unwrap()).unwrap();
+        assert_eq!(projected.z, Some(100.0));
+    }
+
+    #[test]
+    fn test_geo_conversion() {
+        let rect = geo_types::Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 2.0, y: 1.0 });
+        let polygon = Geometry::from_geo(GeoGeometry::Rect(rect), Crs::wgs84());
+        assert!(matches!(&polygon, Geometry::Polygon(p) if p.area() == 2.0));
+        assert_eq!(polygon.to_geo(), GeoGeometry::Polygon(rect.to_polygon()));
+
+        let line = Geometry::from_wkt("LINESTRING Z (0 0 1, 1 1 2)", Crs::wgs84()).unwrap();
+        assert_eq!(
+            line.to_geo(),
+            GeoGeometry::LineString(GeoLineString::from(vec![(0.0, 0.0), (1.0, 1.0)]))
+        );
+        let arc = Geometry::from_wkt("CIRCULARSTRING (0 0, 1 1, 2 0)", Crs::wgs84()).unwrap();
+        assert!(matches!(arc.to_geo(), GeoGeometry::LineString(l) if l.0.len() > 3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ordinate_count_mismatch() {
+        LineString::new(vec![Coord { x: 0.0, y: 0.0 }], Crs::wgs84()).with_z(vec![1.0, 2.0]);
+    }
 }
@@ -0,0 +1,254 @@
+//! Coordinate tree shared by the WKT, WKB and GeoJSON encodings.
+//!
+//! Geometries store their Z and M ordinates apart from the 2D coordinates;
+//! encodings interleave them per position. [`Shape`] holds the interleaved
+//! form so each encoding only deals with its own syntax.
+
+use super::{
+    CircularString, CompoundCurve, CurveSegment, Dimension, Geometry, GeometryCollection, LineString,
+    MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
+};
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+use geo_types::{Coord, LineString as GeoLineString, Point as GeoPoint, Polygon as GeoPolygon};
+
+/// X, Y, Z and M of a position; absent ordinates are ignored
+pub(super) type Position = [f64; 4];
+
+/// Geometry tree with interleaved ordinates
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Shape {
+    Point(Option<Position>),
+    LineString(Vec<Position>),
+    CircularString(Vec<Position>),
+    CompoundCurve(Vec<Shape>),
+    Polygon(Vec<Vec<Position>>),
+    MultiPoint(Vec<Position>),
+    MultiLineString(Vec<Vec<Position>>),
+    MultiPolygon(Vec<Vec<Vec<Position>>>),
+    GeometryCollection(Vec<(Shape, Dimension)>),
+}
+
+/// Reads positions out of separately stored coordinates and ordinates
+struct Zipper<'a> {
+    z: Option<&'a [f64]>,
+    m: Option<&'a [f64]>,
+    next: usize,
+}
+
+impl<'a> Zipper<'a> {
+    fn new(z: Option<&'a [f64]>, m: Option<&'a [f64]>) -> Self {
+        Self { z, m, next: 0 }
+    }
+
+    fn take(&mut self, coords: &[Coord<f64>]) -> Vec<Position> {
+        let positions = coords
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let i = self.next + i;
+                [
+                    c.x,
+                    c.y,
+                    self.z.map_or(0.0, |z| z[i]),
+                    self.m.map_or(0.0, |m| m[i]),
+                ]
+            })
+            .collect();
+        self.next += coords.len();
+        positions
+    }
+
+    fn polygon(&mut self, polygon: &GeoPolygon<f64>) -> Vec<Vec<Position>> {
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .map(|ring| self.take(&ring.0))
+            .collect()
+    }
+}
+
+/// Collects the 2D coordinates and ordinates of positions
+struct Unzipper {
+    dimension: Dimension,
+    z: Vec<f64>,
+    m: Vec<f64>,
+}
+
+impl Unzipper {
+    fn new(dimension: Dimension) -> Self {
+        Self {
+            dimension,
+            z: Vec::new(),
+            m: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, positions: &[Position]) -> Vec<Coord<f64>> {
+        positions
+            .iter()
+            .map(|p| {
+                if self.dimension.has_z() {
+                    self.z.push(p[2]);
+                }
+                if self.dimension.has_m() {
+                    self.m.push(p[3]);
+                }
+                Coord { x: p[0], y: p[1] }
+            })
+            .collect()
+    }
+
+    fn polygon(&mut self, rings: &[Vec<Position>]) -> Result<GeoPolygon<f64>> {
+        let mut rings = rings.iter().map(|ring| GeoLineString::new(self.push(ring)));
+        let exterior = rings
+            .next()
+            .ok_or_else(|| MeridianError::InvalidGeometry("Polygon has no exterior ring".to_string()))?;
+        Ok(GeoPolygon::new(exterior, rings.collect()))
+    }
+
+    fn finish(self) -> (Option<Vec<f64>>, Option<Vec<f64>>) {
+        (
+            self.dimension.has_z().then_some(self.z),
+            self.dimension.has_m().then_some(self.m),
+        )
+    }
+}
+
+impl Shape {
+    /// Converts a geometry, returning its shape and dimension.
+    pub(super) fn from_geometry(geometry: &Geometry) -> (Shape, Dimension) {
+        let shape = match geometry {
+            Geometry::Point(p) => Shape::Point(Some([p.x(), p.y(), p.z.unwrap_or(0.0), p.m.unwrap_or(0.0)])),
+            Geometry::LineString(g) => Shape::LineString(Zipper::new(g.z.as_deref(), g.m.as_deref()).take(&g.geom.0)),
+            Geometry::CircularString(g) => {
+                Shape::CircularString(Zipper::new(g.z.as_deref(), g.m.as_deref()).take(&g.coords))
+            }
+            Geometry::CompoundCurve(g) => Shape::CompoundCurve(
+                g.segments
+                    .iter()
+                    .map(|segment| match segment {
+                        CurveSegment::LineString(line) => Shape::from_geometry(&Geometry::LineString(line.clone())).0,
+                        CurveSegment::CircularString(arc) => {
+                            Shape::from_geometry(&Geometry::CircularString(arc.clone())).0
+                        }
+                    })
+                    .collect(),
+            ),
+            Geometry::Polygon(g) => Shape::Polygon(Zipper::new(g.z.as_deref(), g.m.as_deref()).polygon(&g.geom)),
+            Geometry::MultiPoint(g) => {
+                let coords: Vec<_> = g.geom.0.iter().map(|p| p.0).collect();
+                Shape::MultiPoint(Zipper::new(g.z.as_deref(), g.m.as_deref()).take(&coords))
+            }
+            Geometry::MultiLineString(g) => {
+                let mut zipper = Zipper::new(g.z.as_deref(), g.m.as_deref());
+                Shape::MultiLineString(g.geom.0.iter().map(|line| zipper.take(&line.0)).collect())
+            }
+            Geometry::MultiPolygon(g) => {
+                let mut zipper = Zipper::new(g.z.as_deref(), g.m.as_deref());
+                Shape::MultiPolygon(g.geom.0.iter().map(|polygon| zipper.polygon(polygon)).collect())
+            }
+            Geometry::GeometryCollection(g) => {
+                Shape::GeometryCollection(g.geometries.iter().map(Shape::from_geometry).collect())
+            }
+        };
+        (shape, geometry.dimension())
+    }
+
+    /// Builds a geometry with the given dimension and CRS.
+    ///
+    /// Fails for empty points, which geometries cannot represent.
+    pub(super) fn into_geometry(self, dimension: Dimension, crs: &Crs) -> Result<Geometry> {
+        let mut unzipper = Unzipper::new(dimension);
+        Ok(match self {
+            Shape::Point(position) => {
+                let [x, y, z, m] = position
+                    .ok_or_else(|| MeridianError::InvalidGeometry("Empty points are not supported".to_string()))?;
+                let mut point = Point::new(x, y, crs.clone());
+                point.z = dimension.has_z().then_some(z);
+                point.m = dimension.has_m().then_some(m);
+                Geometry::Point(point)
+            }
+            Shape::LineString(positions) => {
+                let coords = unzipper.push(&positions);
+                let (z, m) = unzipper.finish();
+                Geometry::LineString(LineString {
+                    z,
+                    m,
+                    ..LineString::new(coords, crs.clone())
+                })
+            }
+            Shape::CircularString(positions) => {
+                let coords = unzipper.push(&positions);
+                let (z, m) = unzipper.finish();
+                Geometry::CircularString(CircularString {
+                    z,
+                    m,
+                    ..CircularString::new(coords, crs.clone())?
+                })
+            }
+            Shape::CompoundCurve(segments) => {
+                let segments = segments
+                    .into_iter()
+                    .map(|segment| match segment.into_geometry(dimension, crs)? {
+                        Geometry::LineString(line) => Ok(CurveSegment::LineString(line)),
+                        Geometry::CircularString(arc) => Ok(CurveSegment::CircularString(arc)),
+                        _ => Err(MeridianError::InvalidGeometry(
+                            "Compound curves only contain line strings and circular strings".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Geometry::CompoundCurve(CompoundCurve::new(segments, crs.clone())?)
+            }
+            Shape::Polygon(rings) => {
+                if rings.is_empty() {
+                    return Ok(Geometry::Polygon(Polygon::new(GeoLineString::new(vec![]), vec![], crs.clone())));
+                }
+                let geom = unzipper.polygon(&rings)?;
+                let (z, m) = unzipper.finish();
+                Geometry::Polygon(Polygon {
+                    geom,
+                    z,
+                    m,
+                    crs: crs.clone(),
+                })
+            }
+            Shape::MultiPoint(positions) => {
+                let points = unzipper.push(&positions).into_iter().map(GeoPoint::from).collect();
+                let (z, m) = unzipper.finish();
+                Geometry::MultiPoint(MultiPoint {
+                    z,
+                    m,
+                    ..MultiPoint::new(points, crs.clone())
+                })
+            }
+            Shape::MultiLineString(lines) => {
+                let lines = lines.iter().map(|line| GeoLineString::new(unzipper.push(line))).collect();
+                let (z, m) = unzipper.finish();
+                Geometry::MultiLineString(MultiLineString {
+                    z,
+                    m,
+                    ..MultiLineString::new(lines, crs.clone())
+                })
+            }
+            Shape::MultiPolygon(polygons) => {
+                let polygons = polygons
+                    .iter()
+                    .map(|rings| unzipper.polygon(rings))
+                    .collect::<Result<Vec<_>>>()?;
+                let (z, m) = unzipper.finish();
+                Geometry::MultiPolygon(MultiPolygon {
+                    z,
+                    m,
+                    ..MultiPolygon::new(polygons, crs.clone())
+                })
+            }
+            Shape::GeometryCollection(members) => Geometry::GeometryCollection(GeometryCollection::new(
+                members
+                    .into_iter()
+                    .map(|(shape, dimension)| shape.into_geometry(dimension, crs))
+                    .collect::<Result<Vec<_>>>()?,
+                crs.clone(),
+            )),
+        })
+    }
+}
@@ -0,0 +1,500 @@
+//! Tiny Well-Known Binary encoding.
+//!
+//! Ordinates are scaled to integers at a fixed number of decimal digits,
+//! delta encoded within each geometry and written as zigzag varints. TWKB
+//! has no curve types, so curves are linearized before writing.
+
+use super::shape::{Position, Shape};
+use super::{Dimension, Geometry, DEFAULT_SEGMENTS_PER_QUADRANT};
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+
+const POINT: u8 = 1;
+const LINESTRING: u8 = 2;
+const POLYGON: u8 = 3;
+const MULTIPOINT: u8 = 4;
+const MULTILINESTRING: u8 = 5;
+const MULTIPOLYGON: u8 = 6;
+const GEOMETRYCOLLECTION: u8 = 7;
+
+/// Metadata header flags
+const BBOX: u8 = 0x01;
+const SIZE: u8 = 0x02;
+const IDLIST: u8 = 0x04;
+const EXTENDED: u8 = 0x08;
+const EMPTY: u8 = 0x10;
+
+/// Deepest nesting of collections accepted when reading
+const MAX_DEPTH: usize = 32;
+
+/// Options for writing TWKB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TwkbOptions {
+    /// Decimal digits kept for X and Y, from -7 to 7; negative values round
+    /// to tens, hundreds, ...
+    pub precision: i8,
+    /// Decimal digits kept for Z, from 0 to 7
+    pub precision_z: u8,
+    /// Decimal digits kept for M, from 0 to 7
+    pub precision_m: u8,
+    /// Include a bounding box
+    pub bbox: bool,
+    /// Include the encoded size, so readers can skip geometries
+    pub size: bool,
+}
+
+impl Default for TwkbOptions {
+    fn default() -> Self {
+        Self {
+            precision: 6,
+            precision_z: 3,
+            precision_m: 3,
+            bbox: false,
+            size: false,
+        }
+    }
+}
+
+impl Geometry {
+    /// Encodes the geometry as Tiny Well-Known Binary.
+    ///
+    /// Curves are linearized with [`DEFAULT_SEGMENTS_PER_QUADRANT`].
+    pub fn to_twkb(&self, options: &TwkbOptions) -> Vec<u8> {
+        let linear;
+        let geometry = if self.is_curved() {
+            linear = self.linearize(DEFAULT_SEGMENTS_PER_QUADRANT);
+            &linear
+        } else {
+            self
+        };
+        let (shape, dimension) = Shape::from_geometry(geometry);
+        encode(&shape, dimension, options)
+    }
+
+    /// Parses a geometry from TWKB.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or has trailing bytes, or has
+    /// an unknown geometry type
+    pub fn from_twkb(twkb: &[u8], crs: Crs) -> Result<Geometry> {
+        let (geometry, len) = Self::read_twkb(twkb, crs)?;
+        if len != twkb.len() {
+            return Err(MeridianError::DeserializationError(format!(
+                "{} trailing bytes after TWKB geometry",
+                twkb.len() - len
+            )));
+        }
+        Ok(geometry)
+    }
+
+    /// Parses the first of a sequence of TWKB geometries.
+    ///
+    /// Empty points are read as empty multi points.
+    ///
+    /// # Returns
+    ///
+    /// The geometry and the number of bytes it was encoded in
+    ///
+    /// # Errors
+    ///
+    /// Data that ends before the geometry does is reported as an
+    /// [`std::io::ErrorKind::UnexpectedEof`] I/O error, so that readers of a
+    /// stream can tell it from invalid data and read more
+    pub fn read_twkb(twkb: &[u8], crs: Crs) -> Result<(Geometry, usize)> {
+        let mut reader = Reader { data: twkb, pos: 0 };
+        let (shape, dimension) = reader.geometry(0)?;
+        Ok((shape.into_geometry(dimension, &crs)?, reader.pos))
+    }
+}
+
+/// Scale factors of one geometry
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    dimension: Dimension,
+    factors: [f64; 4],
+}
+
+impl Scale {
+    fn new(dimension: Dimension, precision: i32, precision_z: i32, precision_m: i32) -> Self {
+        let xy = 10f64.powi(precision);
+        Self {
+            dimension,
+            factors: [xy, xy, 10f64.powi(precision_z), 10f64.powi(precision_m)],
+        }
+    }
+
+    fn has(&self, ordinate: usize) -> bool {
+        has_ordinate(self.dimension, ordinate)
+    }
+}
+
+/// Whether a dimension has the X, Y, Z or M ordinate at `ordinate`
+fn has_ordinate(dimension: Dimension, ordinate: usize) -> bool {
+    match ordinate {
+        2 => dimension.has_z(),
+        3 => dimension.has_m(),
+        _ => true,
+    }
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn write_uvarint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn encode(shape: &Shape, dimension: Dimension, options: &TwkbOptions) -> Vec<u8> {
+    let precision = options.precision.clamp(-7, 7);
+    let (precision_z, precision_m) = (options.precision_z.min(7), options.precision_m.min(7));
+    let mut body = Body {
+        out: Vec::new(),
+        scale: Scale::new(dimension, precision as i32, precision_z as i32, precision_m as i32),
+        last: [0; 4],
+        min: [i64::MAX; 4],
+        max: [i64::MIN; 4],
+    };
+
+    let kind = match shape {
+        Shape::Point(p) => {
+            body.positions(p.as_slice());
+            POINT
+        }
+        Shape::LineString(p) | Shape::CircularString(p) => {
+            body.line(p);
+            LINESTRING
+        }
+        Shape::Polygon(rings) => {
+            body.polygon(rings);
+            POLYGON
+        }
+        Shape::MultiPoint(points) => {
+            body.uvarint(points.len() as u64);
+            body.positions(points);
+            MULTIPOINT
+        }
+        Shape::MultiLineString(lines) => {
+            body.uvarint(lines.len() as u64);
+            for line in lines {
+                body.line(line);
+            }
+            MULTILINESTRING
+        }
+        Shape::MultiPolygon(polygons) => {
+            body.uvarint(polygons.len() as u64);
+            for rings in polygons {
+                body.polygon(rings);
+            }
+            MULTIPOLYGON
+        }
+        Shape::GeometryCollection(members) => {
+            body.uvarint(members.len() as u64);
+            for (member, dimension) in members {
+                body.out.extend_from_slice(&encode(member, *dimension, options));
+            }
+            GEOMETRYCOLLECTION
+        }
+        Shape::CompoundCurve(_) => unreachable!("curves are linearized before encoding"),
+    };
+
+    // Collection members carry their own coordinates
+    let empty = match shape {
+        Shape::GeometryCollection(members) => members.is_empty(),
+        _ => body.min[0] > body.max[0],
+    };
+    let (min, max, body) = (body.min, body.max, body.out);
+
+    let mut metadata = 0u8;
+    if dimension != Dimension::Xy {
+        metadata |= EXTENDED;
+    }
+    if empty {
+        metadata |= EMPTY;
+    }
+    let has_bbox = options.bbox && !empty && kind != GEOMETRYCOLLECTION;
+    if has_bbox {
+        metadata |= BBOX;
+    }
+    if options.size {
+        metadata |= SIZE;
+    }
+
+    let mut out = vec![kind | ((zigzag_encode(precision as i64) as u8) << 4), metadata];
+    if dimension != Dimension::Xy {
+        out.push(
+            dimension.has_z() as u8 | (dimension.has_m() as u8) << 1 | (precision_z & 0x07) << 2 | (precision_m & 0x07) << 5,
+        );
+    }
+
+    let mut rest = Vec::new();
+    if has_bbox {
+        for i in (0..4).filter(|&i| has_ordinate(dimension, i)) {
+            write_uvarint(&mut rest, zigzag_encode(min[i]));
+            write_uvarint(&mut rest, zigzag_encode(max[i] - min[i]));
+        }
+    }
+    if !empty {
+        rest.extend_from_slice(&body);
+    }
+
+    if options.size {
+        write_uvarint(&mut out, rest.len() as u64);
+    }
+    out.extend_from_slice(&rest);
+    out
+}
+
+/// Delta encoded coordinates of one geometry
+struct Body {
+    out: Vec<u8>,
+    scale: Scale,
+    last: [i64; 4],
+    min: [i64; 4],
+    max: [i64; 4],
+}
+
+impl Body {
+    fn uvarint(&mut self, value: u64) {
+        write_uvarint(&mut self.out, value);
+    }
+
+    fn positions(&mut self, positions: &[Position]) {
+        for p in positions {
+            for i in (0..4).filter(|&i| self.scale.has(i)) {
+                let scaled = (p[i] * self.scale.factors[i]).round() as i64;
+                write_uvarint(&mut self.out, zigzag_encode(scaled - self.last[i]));
+                self.last[i] = scaled;
+                self.min[i] = self.min[i].min(scaled);
+                self.max[i] = self.max[i].max(scaled);
+            }
+        }
+    }
+
+    fn line(&mut self, positions: &[Position]) {
+        self.uvarint(positions.len() as u64);
+        self.positions(positions);
+    }
+
+    fn polygon(&mut self, rings: &[Vec<Position>]) {
+        let rings: Vec<_> = rings.iter().filter(|ring| !ring.is_empty()).collect();
+        self.uvarint(rings.len() as u64);
+        for ring in rings {
+            self.line(ring);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| truncated("Truncated TWKB".to_string()))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn uvarint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MeridianError::DeserializationError("TWKB varint is too long".to_string()))
+    }
+
+    fn varint(&mut self) -> Result<i64> {
+        self.uvarint().map(zigzag_decode)
+    }
+
+    /// Reads an element count, checked against the remaining data
+    fn count(&mut self) -> Result<usize> {
+        let count = self.uvarint()?;
+        if count > (self.data.len() - self.pos) as u64 {
+            return Err(truncated(format!("TWKB element count {} exceeds the data size", count)));
+        }
+        Ok(count as usize)
+    }
+
+    fn positions(&mut self, count: usize, scale: Scale, last: &mut [i64; 4]) -> Result<Vec<Position>> {
+        (0..count)
+            .map(|_| {
+                let mut p = [0.0; 4];
+                for i in (0..4).filter(|&i| scale.has(i)) {
+                    last[i] += self.varint()?;
+                    p[i] = last[i] as f64 / scale.factors[i];
+                }
+                Ok(p)
+            })
+            .collect()
+    }
+
+    fn line(&mut self, scale: Scale, last: &mut [i64; 4]) -> Result<Vec<Position>> {
+        let count = self.count()?;
+        self.positions(count, scale, last)
+    }
+
+    fn rings(&mut self, scale: Scale, last: &mut [i64; 4]) -> Result<Vec<Vec<Position>>> {
+        let count = self.count()?;
+        (0..count).map(|_| self.line(scale, last)).collect()
+    }
+
+    fn geometry(&mut self, depth: usize) -> Result<(Shape, Dimension)> {
+        if depth > MAX_DEPTH {
+            return Err(MeridianError::DeserializationError("TWKB nesting is too deep".to_string()));
+        }
+
+        let header = self.byte()?;
+        let kind = header & 0x0F;
+        let precision = zigzag_decode((header >> 4) as u64) as i32;
+        let metadata = self.byte()?;
+
+        let (dimension, precision_z, precision_m) = if metadata & EXTENDED != 0 {
+            let extended = self.byte()?;
+            (
+                Dimension::new(extended & 0x01 != 0, extended & 0x02 != 0),
+                ((extended >> 2) & 0x07) as i32,
+                ((extended >> 5) & 0x07) as i32,
+            )
+        } else {
+            (Dimension::Xy, 0, 0)
+        };
+        let scale = Scale::new(dimension, precision, precision_z, precision_m);
+
+        if metadata & SIZE != 0 {
+            self.uvarint()?;
+        }
+        if metadata & BBOX != 0 {
+            for _ in (0..4).filter(|&i| scale.has(i)) {
+                self.varint()?;
+                self.varint()?;
+            }
+        }
+
+        if metadata & EMPTY != 0 {
+            let shape = match kind {
+                POINT | MULTIPOINT => Shape::MultiPoint(Vec::new()),
+                LINESTRING => Shape::LineString(Vec::new()),
+                POLYGON => Shape::Polygon(Vec::new()),
+                MULTILINESTRING => Shape::MultiLineString(Vec::new()),
+                MULTIPOLYGON => Shape::MultiPolygon(Vec::new()),
+                GEOMETRYCOLLECTION => Shape::GeometryCollection(Vec::new()),
+                _ => return Err(unsupported(kind)),
+            };
+            return Ok((shape, dimension));
+        }
+
+        let mut last = [0i64; 4];
+        let parts = |reader: &mut Self| -> Result<usize> {
+            let count = reader.count()?;
+            if metadata & IDLIST != 0 {
+                for _ in 0..count {
+                    reader.varint()?;
+                }
+            }
+            Ok(count)
+        };
+
+        let shape = match kind {
+            POINT => Shape::Point(self.positions(1, scale, &mut last)?.pop()),
+            LINESTRING => Shape::LineString(self.line(scale, &mut last)?),
+            POLYGON => Shape::Polygon(self.rings(scale, &mut last)?),
+            MULTIPOINT => {
+                let count = parts(self)?;
+                Shape::MultiPoint(self.positions(count, scale, &mut last)?)
+            }
+            MULTILINESTRING => {
+                let count = parts(self)?;
+                Shape::MultiLineString((0..count).map(|_| self.line(scale, &mut last)).collect::<Result<_>>()?)
+            }
+            MULTIPOLYGON => {
+                let count = parts(self)?;
+                Shape::MultiPolygon((0..count).map(|_| self.rings(scale, &mut last)).collect::<Result<_>>()?)
+            }
+            GEOMETRYCOLLECTION => {
+                let count = parts(self)?;
+                Shape::GeometryCollection((0..count).map(|_| self.geometry(depth + 1)).collect::<Result<_>>()?)
+            }
+            _ => return Err(unsupported(kind)),
+        };
+        Ok((shape, dimension))
+    }
+}
+
+fn unsupported(kind: u8) -> MeridianError {
+    MeridianError::DeserializationError(format!("Unsupported TWKB geometry type {}", kind))
+}
+
+/// Error for data that ends before the geometry does
+fn truncated(message: String) -> MeridianError {
+    MeridianError::IoError(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twkb_specification_point() {
+        // POINT(1 2) at precision 0, from the TWKB specification
+        let point = Geometry::from_wkt("POINT (1 2)", Crs::wgs84()).unwrap();
+        let options = TwkbOptions {
+            precision: 0,
+            ..TwkbOptions::default()
+        };
+        assert_eq!(point.to_twkb(&options), vec![0x01, 0x00, 0x02, 0x04]);
+    }
+
+    #[test]
+    fn test_twkb_roundtrip() {
+        let options = TwkbOptions {
+            precision: 2,
+            bbox: true,
+            size: true,
+            ..TwkbOptions::default()
+        };
+        let line = Geometry::from_wkt("LINESTRING Z (10.123 -5.5 1.5, 10.2 -5.25 2.25, 11 0 3)", Crs::wgs84()).unwrap();
+        let decoded = Geometry::from_twkb(&line.to_twkb(&options), Crs::wgs84()).unwrap();
+        assert_eq!(
+            decoded,
+            Geometry::from_wkt("LINESTRING Z (10.12 -5.5 1.5, 10.2 -5.25 2.25, 11 0 3)", Crs::wgs84()).unwrap()
+        );
+
+        for wkt in [
+            "GEOMETRYCOLLECTION (POINT (1 2), MULTIPOINT ((-3 4), (5 6)), GEOMETRYCOLLECTION EMPTY)",
+            "MULTIPOLYGON M (((0 0 1, 4 0 2, 4 4 3, 0 0 1)), ((5 5 0, 6 5 0, 6 6 0, 5 5 0)))",
+            "MULTILINESTRING EMPTY",
+        ] {
+            let geometry = Geometry::from_wkt(wkt, Crs::wgs84()).unwrap();
+            let twkb = geometry.to_twkb(&TwkbOptions::default());
+            assert_eq!(Geometry::from_twkb(&twkb, Crs::wgs84()).unwrap(), geometry, "{}", wkt);
+
+            let mut data = twkb.clone();
+            data.push(0x01);
+            assert_eq!(Geometry::read_twkb(&data, Crs::wgs84()).unwrap().1, twkb.len());
+            assert!(Geometry::from_twkb(&data, Crs::wgs84()).is_err());
+        }
+
+        let arc = Geometry::from_wkt("CIRCULARSTRING (0 0, 1 1, 2 0)", Crs::wgs84()).unwrap();
+        let decoded = Geometry::from_twkb(&arc.to_twkb(&TwkbOptions::default()), Crs::wgs84()).unwrap();
+        assert!(matches!(decoded, Geometry::LineString(line) if line.len() > 3));
+    }
+}
@@ -0,0 +1,542 @@
+//! Well-Known Binary encoding.
+//!
+//! Writes ISO WKB, with Z and M flagged by adding 1000, 2000 or 3000 to the
+//! type code, or PostGIS EWKB, with Z, M and SRID flags in the type code.
+//! Reading accepts both dialects in either byte order.
+
+use super::shape::{Position, Shape};
+use super::{Dimension, Geometry};
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// Deepest nesting of collections accepted when reading
+const MAX_DEPTH: usize = 32;
+
+/// Byte order of encoded WKB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ByteOrder {
+    /// XDR, most significant byte first
+    BigEndian,
+    /// NDR, least significant byte first
+    #[default]
+    LittleEndian,
+}
+
+/// WKB dialect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WkbDialect {
+    /// ISO SQL/MM, where Z and M add 1000, 2000 or 3000 to the type code
+    #[default]
+    Iso,
+    /// PostGIS extended WKB, with Z, M and SRID flags in the type code
+    Ewkb,
+}
+
+/// Options for writing WKB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WkbOptions {
+    /// WKB dialect
+    pub dialect: WkbDialect,
+    /// Byte order
+    pub byte_order: ByteOrder,
+    /// SRID written in EWKB
+    pub srid: Option<i32>,
+}
+
+impl Geometry {
+    /// Encodes the geometry as little-endian ISO Well-Known Binary.
+    ///
+    /// Empty points are not representable; other empty geometries are written
+    /// with zero elements.
+    pub fn to_wkb(&self) -> Vec<u8> {
+        self.to_wkb_with(&WkbOptions::default())
+    }
+
+    /// Encodes the geometry as ISO WKB or EWKB in the given byte order.
+    ///
+    /// ISO WKB has no SRID, so `options.srid` only applies to EWKB.
+    pub fn to_wkb_with(&self, options: &WkbOptions) -> Vec<u8> {
+        let (shape, dimension) = Shape::from_geometry(self);
+        let srid = match options.dialect {
+            WkbDialect::Iso => None,
+            WkbDialect::Ewkb => options.srid,
+        };
+        let mut writer = Writer {
+            out: Vec::new(),
+            dialect: options.dialect,
+            byte_order: options.byte_order,
+        };
+        writer.shape(&shape, dimension, srid);
+        writer.out
+    }
+
+    /// Parses a geometry from ISO WKB or PostGIS EWKB.
+    ///
+    /// # Arguments
+    ///
+    /// * `wkb` - Encoded geometry
+    /// * `crs` - Coordinate reference system of the coordinates, unless an
+    ///   EWKB SRID names a known EPSG code
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or has trailing bytes, has an
+    /// unknown geometry type, or encodes an empty point
+    pub fn from_wkb(wkb: &[u8], crs: Crs) -> Result<Geometry> {
+        let (geometry, len) = Self::read_wkb(wkb, crs)?;
+        if len != wkb.len() {
+            return Err(MeridianError::DeserializationError(format!(
+                "{} trailing bytes after WKB geometry",
+                wkb.len() - len
+            )));
+        }
+        Ok(geometry)
+    }
+
+    /// Parses the first of a sequence of WKB geometries.
+    ///
+    /// # Returns
+    ///
+    /// The geometry and the number of bytes it was encoded in
+    ///
+    /// # Errors
+    ///
+    /// Data that ends before the geometry does is reported as an
+    /// [`std::io::ErrorKind::UnexpectedEof`] I/O error, so that readers of a
+    /// stream can tell it from invalid data and read more
+    pub fn read_wkb(wkb: &[u8], crs: Crs) -> Result<(Geometry, usize)> {
+        let mut reader = Reader::new(wkb);
+        let (shape, dimension) = reader.shape(0)?;
+        let crs = reader
+            .srid
+            .and_then(|srid| u32::try_from(srid).ok())
+            .and_then(|code| Crs::from_epsg(code).ok())
+            .unwrap_or(crs);
+        Ok((shape.into_geometry(dimension, &crs)?, reader.pos))
+    }
+
+    /// Reads the SRID of an EWKB geometry, `None` for ISO WKB.
+    pub fn wkb_srid(wkb: &[u8]) -> Result<Option<i32>> {
+        let mut reader = Reader::new(wkb);
+        reader.header()?;
+        Ok(reader.srid)
+    }
+}
+
+fn type_code(shape: &Shape) -> u32 {
+    match shape {
+        Shape::Point(_) => 1,
+        Shape::LineString(_) => 2,
+        Shape::Polygon(_) => 3,
+        Shape::MultiPoint(_) => 4,
+        Shape::MultiLineString(_) => 5,
+        Shape::MultiPolygon(_) => 6,
+        Shape::GeometryCollection(_) => 7,
+        Shape::CircularString(_) => 8,
+        Shape::CompoundCurve(_) => 9,
+    }
+}
+
+struct Writer {
+    out: Vec<u8>,
+    dialect: WkbDialect,
+    byte_order: ByteOrder,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        match self.byte_order {
+            ByteOrder::LittleEndian => self.out.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::BigEndian => self.out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn f64(&mut self, value: f64) {
+        match self.byte_order {
+            ByteOrder::LittleEndian => self.out.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::BigEndian => self.out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    /// Writes a byte order and type header; only top-level EWKB has an SRID
+    fn header(&mut self, code: u32, dimension: Dimension, srid: Option<i32>) {
+        self.out.push(match self.byte_order {
+            ByteOrder::BigEndian => 0,
+            ByteOrder::LittleEndian => 1,
+        });
+        match self.dialect {
+            WkbDialect::Iso => {
+                let offset = match dimension {
+                    Dimension::Xy => 0,
+                    Dimension::Xyz => 1000,
+                    Dimension::Xym => 2000,
+                    Dimension::Xyzm => 3000,
+                };
+                self.u32(code + offset);
+            }
+            WkbDialect::Ewkb => {
+                let mut flags = 0;
+                if dimension.has_z() {
+                    flags |= EWKB_Z;
+                }
+                if dimension.has_m() {
+                    flags |= EWKB_M;
+                }
+                if srid.is_some() {
+                    flags |= EWKB_SRID;
+                }
+                self.u32(code | flags);
+                if let Some(srid) = srid {
+                    self.u32(srid as u32);
+                }
+            }
+        }
+    }
+
+    fn count(&mut self, count: usize) {
+        self.u32(count as u32);
+    }
+
+    fn position(&mut self, p: &Position, dimension: Dimension) {
+        self.f64(p[0]);
+        self.f64(p[1]);
+        if dimension.has_z() {
+            self.f64(p[2]);
+        }
+        if dimension.has_m() {
+            self.f64(p[3]);
+        }
+    }
+
+    fn positions(&mut self, positions: &[Position], dimension: Dimension) {
+        self.count(positions.len());
+        for p in positions {
+            self.position(p, dimension);
+        }
+    }
+
+    fn rings(&mut self, rings: &[Vec<Position>], dimension: Dimension) {
+        self.count(rings.len());
+        for ring in rings {
+            self.positions(ring, dimension);
+        }
+    }
+
+    fn shape(&mut self, shape: &Shape, dimension: Dimension, srid: Option<i32>) {
+        self.header(type_code(shape), dimension, srid);
+        match shape {
+            Shape::Point(p) => self.position(&p.unwrap_or([f64::NAN; 4]), dimension),
+            Shape::LineString(p) | Shape::CircularString(p) => self.positions(p, dimension),
+            Shape::Polygon(rings) => self.rings(rings, dimension),
+            Shape::MultiPoint(points) => {
+                self.count(points.len());
+                for p in points {
+                    self.header(1, dimension, None);
+                    self.position(p, dimension);
+                }
+            }
+            Shape::MultiLineString(lines) => {
+                self.count(lines.len());
+                for line in lines {
+                    self.header(2, dimension, None);
+                    self.positions(line, dimension);
+                }
+            }
+            Shape::MultiPolygon(polygons) => {
+                self.count(polygons.len());
+                for rings in polygons {
+                    self.header(3, dimension, None);
+                    self.rings(rings, dimension);
+                }
+            }
+            Shape::CompoundCurve(segments) => {
+                self.count(segments.len());
+                for segment in segments {
+                    self.shape(segment, dimension, None);
+                }
+            }
+            Shape::GeometryCollection(members) => {
+                self.count(members.len());
+                for (member, dimension) in members {
+                    self.shape(member, *dimension, None);
+                }
+            }
+        }
+    }
+}
+
+/// Error for data that ends before the geometry does
+fn truncated(message: String) -> MeridianError {
+    MeridianError::IoError(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, message))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+    /// SRID of the first EWKB header
+    srid: Option<i32>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            little_endian: true,
+            srid: None,
+        }
+    }
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| truncated("Truncated WKB".to_string()))?;
+        self.pos += N;
+        Ok(bytes.try_into().expect("slice has N bytes"))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes::<4>()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let bytes = self.bytes::<8>()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    /// Reads an element count, checked against the remaining data
+    fn count(&mut self, min_size: usize) -> Result<usize> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(min_size) > self.data.len() - self.pos {
+            return Err(truncated(format!("WKB element count {} exceeds the data size", count)));
+        }
+        Ok(count)
+    }
+
+    fn position(&mut self, dimension: Dimension) -> Result<Position> {
+        let mut p = [self.f64()?, self.f64()?, 0.0, 0.0];
+        if dimension.has_z() {
+            p[2] = self.f64()?;
+        }
+        if dimension.has_m() {
+            p[3] = self.f64()?;
+        }
+        Ok(p)
+    }
+
+    fn positions(&mut self, dimension: Dimension) -> Result<Vec<Position>> {
+        let count = self.count(16)?;
+        (0..count).map(|_| self.position(dimension)).collect()
+    }
+
+    fn rings(&mut self, dimension: Dimension) -> Result<Vec<Vec<Position>>> {
+        let count = self.count(4)?;
+        (0..count).map(|_| self.positions(dimension)).collect()
+    }
+
+    /// Reads a byte order and type header: (base type code, dimension)
+    fn header(&mut self) -> Result<(u32, Dimension)> {
+        self.little_endian = match self.bytes::<1>()?[0] {
+            0 => false,
+            1 => true,
+            other => {
+                return Err(MeridianError::DeserializationError(format!(
+                    "Invalid WKB byte order {}",
+                    other
+                )))
+            }
+        };
+        let raw = self.u32()?;
+        if raw & EWKB_SRID != 0 {
+            let srid = self.u32()? as i32;
+            self.srid = self.srid.or(Some(srid));
+        }
+        let code = raw & 0x0FFF_FFFF;
+        let (base, iso) = (code % 1000, code / 1000);
+        let has_z = raw & EWKB_Z != 0 || iso == 1 || iso == 3;
+        let has_m = raw & EWKB_M != 0 || iso == 2 || iso == 3;
+        if iso > 3 {
+            return Err(MeridianError::DeserializationError(format!(
+                "Unknown WKB geometry type {}",
+                code
+            )));
+        }
+        Ok((base, Dimension::new(has_z, has_m)))
+    }
+
+    /// Reads a member of a multi geometry, which must have the expected type
+    fn member(&mut self, expected: u32) -> Result<Dimension> {
+        let (code, dimension) = self.header()?;
+        if code != expected {
+            return Err(MeridianError::DeserializationError(format!(
+                "Expected WKB geometry type {} in collection, found {}",
+                expected, code
+            )));
+        }
+        Ok(dimension)
+    }
+
+    fn shape(&mut self, depth: usize) -> Result<(Shape, Dimension)> {
+        if depth > MAX_DEPTH {
+            return Err(MeridianError::DeserializationError("WKB nesting is too deep".to_string()));
+        }
+        let (code, dimension) = self.header()?;
+        let shape = match code {
+            1 => {
+                let p = self.position(dimension)?;
+                Shape::Point((!(p[0].is_nan() && p[1].is_nan())).then_some(p))
+            }
+            2 => Shape::LineString(self.positions(dimension)?),
+            3 => Shape::Polygon(self.rings(dimension)?),
+            4 => {
+                let count = self.count(21)?;
+                let points = (0..count)
+                    .map(|_| {
+                        let member = self.member(1)?;
+                        self.position(member)
+                    })
+                    .collect::<Result<_>>()?;
+                Shape::MultiPoint(points)
+            }
+            5 => {
+                let count = self.count(9)?;
+                let lines = (0..count)
+                    .map(|_| {
+                        let member = self.member(2)?;
+                        self.positions(member)
+                    })
+                    .collect::<Result<_>>()?;
+                Shape::MultiLineString(lines)
+            }
+            6 => {
+                let count = self.count(9)?;
+                let polygons = (0..count)
+                    .map(|_| {
+                        let member = self.member(3)?;
+                        self.rings(member)
+                    })
+                    .collect::<Result<_>>()?;
+                Shape::MultiPolygon(polygons)
+            }
+            7 => {
+                let count = self.count(5)?;
+                let members = (0..count).map(|_| self.shape(depth + 1)).collect::<Result<_>>()?;
+                Shape::GeometryCollection(members)
+            }
+            8 => Shape::CircularString(self.positions(dimension)?),
+            9 => {
+                let count = self.count(9)?;
+                let segments = (0..count)
+                    .map(|_| {
+                        let (code, member) = self.header()?;
+                        match code {
+                            2 => Ok(Shape::LineString(self.positions(member)?)),
+                            8 => Ok(Shape::CircularString(self.positions(member)?)),
+                            _ => Err(MeridianError::DeserializationError(format!(
+                                "WKB geometry type {} is not a compound curve segment",
+                                code
+                            ))),
+                        }
+                    })
+                    .collect::<Result<_>>()?;
+                Shape::CompoundCurve(segments)
+            }
+            _ => {
+                return Err(MeridianError::DeserializationError(format!(
+                    "Unsupported WKB geometry type {}",
+                    code
+                )))
+            }
+        };
+        Ok((shape, dimension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wkb_roundtrip() {
+        for wkt in [
+            "POINT ZM (1 2 3 4)",
+            "LINESTRING M (0 0 10, 1 1 20)",
+            "POLYGON Z ((0 0 1, 4 0 2, 4 4 3, 0 0 1))",
+            "MULTIPOINT Z ((1 2 3), (4 5 6))",
+            "MULTILINESTRING ((0 0, 1 1), (2 2, 3 3))",
+            "MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))",
+            "GEOMETRYCOLLECTION Z (POINT Z (1 2 3), LINESTRING (0 0, 1 1))",
+            "COMPOUNDCURVE ((-1 0, 0 0), CIRCULARSTRING (0 0, 1 1, 2 0))",
+            "MULTIPOLYGON EMPTY",
+        ] {
+            let geometry = Geometry::from_wkt(wkt, Crs::wgs84()).unwrap();
+            let decoded = Geometry::from_wkb(&geometry.to_wkb(), Crs::wgs84()).unwrap();
+            assert_eq!(decoded, geometry, "{}", wkt);
+        }
+    }
+
+    #[test]
+    fn test_wkb_ewkb_and_byte_order() {
+        let point = Geometry::from_wkt("POINT Z (1 2 3)", Crs::wgs84()).unwrap();
+        assert_eq!(point.to_wkb()[1..5], 1001u32.to_le_bytes());
+
+        // Big-endian EWKB with Z flag and SRID 4326
+        let mut ewkb = vec![0];
+        ewkb.extend_from_slice(&(1 | EWKB_Z | EWKB_SRID).to_be_bytes());
+        ewkb.extend_from_slice(&4326u32.to_be_bytes());
+        for value in [1.0f64, 2.0, 3.0] {
+            ewkb.extend_from_slice(&value.to_be_bytes());
+        }
+        assert_eq!(Geometry::from_wkb(&ewkb, Crs::wgs84()).unwrap(), point);
+
+        assert!(matches!(
+            Geometry::from_wkb(&ewkb[..20], Crs::wgs84()),
+            Err(MeridianError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+        ));
+        let empty = [1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xF8, 0x7F, 0, 0, 0, 0, 0, 0, 0xF8, 0x7F];
+        assert!(Geometry::from_wkb(&empty, Crs::wgs84()).is_err());
+    }
+
+    #[test]
+    fn test_wkb_write_ewkb_and_read_sequence() {
+        let line = Geometry::from_wkt("LINESTRING ZM (0 0 1 5, 3 4 2 6)", Crs::web_mercator()).unwrap();
+        let options = WkbOptions {
+            dialect: WkbDialect::Ewkb,
+            byte_order: ByteOrder::BigEndian,
+            srid: Some(3857),
+        };
+        let ewkb = line.to_wkb_with(&options);
+        assert_eq!(ewkb[1..5], (2 | EWKB_Z | EWKB_M | EWKB_SRID).to_be_bytes());
+        assert_eq!(Geometry::wkb_srid(&ewkb).unwrap(), Some(3857));
+        assert_eq!(Geometry::wkb_srid(&line.to_wkb()).unwrap(), None);
+
+        // The SRID takes precedence over the CRS passed in
+        let mut data = ewkb.clone();
+        data.extend_from_slice(&line.to_wkb());
+        let (first, len) = Geometry::read_wkb(&data, Crs::wgs84()).unwrap();
+        assert_eq!(len, ewkb.len());
+        assert_eq!(first.crs(), &Crs::web_mercator());
+        assert_eq!(first, line);
+        let (second, _) = Geometry::read_wkb(&data[len..], Crs::wgs84()).unwrap();
+        assert_eq!(second.crs(), &Crs::wgs84());
+        assert_eq!(second.to_wkb(), line.to_wkb());
+        assert!(Geometry::from_wkb(&data, Crs::wgs84()).is_err());
+    }
+}
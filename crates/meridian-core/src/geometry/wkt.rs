@@ -0,0 +1,422 @@
+//! Well-Known Text encoding.
+//!
+//! Writes ISO WKT (`POINT ZM (1 2 3 4)`). Reading also accepts untagged
+//! coordinates with three or four ordinates, taken as Z and ZM.
+
+use super::shape::{Position, Shape};
+use super::{Dimension, Geometry};
+use crate::crs::Crs;
+use crate::error::{MeridianError, Result};
+use std::fmt::Write;
+
+impl Geometry {
+    /// Encodes the geometry as Well-Known Text.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let point = Geometry::Point(Point::new_3d(1.0, 2.0, 3.0, Crs::wgs84()));
+    /// assert_eq!(point.to_wkt(), "POINT Z (1 2 3)");
+    /// ```
+    pub fn to_wkt(&self) -> String {
+        let (shape, dimension) = Shape::from_geometry(self);
+        let mut out = String::new();
+        write_shape(&mut out, &shape, dimension);
+        out
+    }
+
+    /// Parses a geometry from Well-Known Text.
+    ///
+    /// # Arguments
+    ///
+    /// * `wkt` - WKT string
+    /// * `crs` - Coordinate reference system of the coordinates
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is malformed or describes an empty point
+    pub fn from_wkt(wkt: &str, crs: Crs) -> Result<Geometry> {
+        let mut parser = Parser {
+            tokens: tokenize(wkt)?,
+            pos: 0,
+            dimension: None,
+        };
+        let (shape, dimension) = parser.geometry()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        shape.into_geometry(dimension, &crs)
+    }
+}
+
+fn tag(dimension: Dimension) -> &'static str {
+    match dimension {
+        Dimension::Xy => "",
+        Dimension::Xyz => " Z",
+        Dimension::Xym => " M",
+        Dimension::Xyzm => " ZM",
+    }
+}
+
+fn write_list<T>(out: &mut String, items: &[T], mut write: impl FnMut(&mut String, &T)) {
+    out.push('(');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write(out, item);
+    }
+    out.push(')');
+}
+
+fn write_position(out: &mut String, p: &Position, dimension: Dimension) {
+    let _ = write!(out, "{} {}", p[0], p[1]);
+    if dimension.has_z() {
+        let _ = write!(out, " {}", p[2]);
+    }
+    if dimension.has_m() {
+        let _ = write!(out, " {}", p[3]);
+    }
+}
+
+fn write_positions(out: &mut String, positions: &[Position], dimension: Dimension) {
+    write_list(out, positions, |out, p| write_position(out, p, dimension));
+}
+
+fn write_rings(out: &mut String, rings: &[Vec<Position>], dimension: Dimension) {
+    write_list(out, rings, |out, ring| write_positions(out, ring, dimension));
+}
+
+fn write_shape(out: &mut String, shape: &Shape, dimension: Dimension) {
+    let (name, empty) = match shape {
+        Shape::Point(p) => ("POINT", p.is_none()),
+        Shape::LineString(p) => ("LINESTRING", p.is_empty()),
+        Shape::CircularString(p) => ("CIRCULARSTRING", p.is_empty()),
+        Shape::CompoundCurve(s) => ("COMPOUNDCURVE", s.is_empty()),
+        Shape::Polygon(r) => ("POLYGON", r.is_empty()),
+        Shape::MultiPoint(p) => ("MULTIPOINT", p.is_empty()),
+        Shape::MultiLineString(l) => ("MULTILINESTRING", l.is_empty()),
+        Shape::MultiPolygon(p) => ("MULTIPOLYGON", p.is_empty()),
+        Shape::GeometryCollection(g) => ("GEOMETRYCOLLECTION", g.is_empty()),
+    };
+    out.push_str(name);
+    out.push_str(tag(dimension));
+    if empty {
+        out.push_str(" EMPTY");
+        return;
+    }
+    out.push(' ');
+
+    match shape {
+        Shape::Point(p) => write_positions(out, &[p.expect("non-empty point")], dimension),
+        Shape::LineString(p) | Shape::CircularString(p) => write_positions(out, p, dimension),
+        Shape::MultiPoint(p) => write_list(out, p, |out, p| write_positions(out, &[*p], dimension)),
+        Shape::CompoundCurve(segments) => write_list(out, segments, |out, segment| match segment {
+            Shape::LineString(p) => write_positions(out, p, dimension),
+            other => write_shape(out, other, dimension),
+        }),
+        Shape::Polygon(rings) | Shape::MultiLineString(rings) => write_rings(out, rings, dimension),
+        Shape::MultiPolygon(polygons) => {
+            write_list(out, polygons, |out, rings| write_rings(out, rings, dimension))
+        }
+        Shape::GeometryCollection(members) => {
+            write_list(out, members, |out, (shape, dimension)| write_shape(out, shape, *dimension))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' | '[' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' | ']' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(Token::Word(word.to_ascii_uppercase()));
+            }
+            c if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                let start = i;
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit()
+                        || matches!(chars[i], '.' | 'e' | 'E')
+                        || (matches!(chars[i], '-' | '+') && matches!(chars[i - 1], 'e' | 'E')))
+                {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let value = number.parse().map_err(|_| {
+                    MeridianError::DeserializationError(format!("Invalid WKT number '{}'", number))
+                })?;
+                tokens.push(Token::Number(value));
+            }
+            other => {
+                return Err(MeridianError::DeserializationError(format!(
+                    "Unexpected character '{}' in WKT",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Dimension of the geometry being parsed, once known
+    dimension: Option<Dimension>,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> MeridianError {
+        MeridianError::DeserializationError(format!("Invalid WKT at token {}: {}", self.pos, message))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", token)))
+        }
+    }
+
+    fn word(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.pos += 1;
+                Ok(word)
+            }
+            _ => Err(self.error("expected a geometry type")),
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == word) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads an optional Z, M or ZM tag into the current dimension
+    fn dimension_tag(&mut self) -> Result<()> {
+        let tagged = if self.eat_word("ZM") {
+            Dimension::Xyzm
+        } else if self.eat_word("Z") {
+            Dimension::Xyz
+        } else if self.eat_word("M") {
+            Dimension::Xym
+        } else {
+            return Ok(());
+        };
+        match self.dimension {
+            Some(dimension) if dimension != tagged => Err(self.error("mixed dimensions")),
+            _ => {
+                self.dimension = Some(tagged);
+                Ok(())
+            }
+        }
+    }
+
+    fn geometry(&mut self) -> Result<(Shape, Dimension)> {
+        let outer = self.dimension.take();
+        let name = self.word()?;
+        self.dimension_tag()?;
+
+        let shape = if self.eat_word("EMPTY") {
+            match name.as_str() {
+                "POINT" => Shape::Point(None),
+                "LINESTRING" => Shape::LineString(vec![]),
+                "CIRCULARSTRING" => Shape::CircularString(vec![]),
+                "COMPOUNDCURVE" => Shape::CompoundCurve(vec![]),
+                "POLYGON" => Shape::Polygon(vec![]),
+                "MULTIPOINT" => Shape::MultiPoint(vec![]),
+                "MULTILINESTRING" => Shape::MultiLineString(vec![]),
+                "MULTIPOLYGON" => Shape::MultiPolygon(vec![]),
+                "GEOMETRYCOLLECTION" => Shape::GeometryCollection(vec![]),
+                _ => return Err(self.error(&format!("unsupported geometry type {}", name))),
+            }
+        } else {
+            match name.as_str() {
+                "POINT" => {
+                    self.expect(Token::Open)?;
+                    let position = self.position()?;
+                    self.expect(Token::Close)?;
+                    Shape::Point(Some(position))
+                }
+                "LINESTRING" => Shape::LineString(self.positions()?),
+                "CIRCULARSTRING" => Shape::CircularString(self.positions()?),
+                "COMPOUNDCURVE" => Shape::CompoundCurve(self.list(Parser::segment)?),
+                "POLYGON" => Shape::Polygon(self.list(Parser::positions)?),
+                "MULTIPOINT" => Shape::MultiPoint(self.list(|parser| {
+                    // Both MULTIPOINT ((1 2), (3 4)) and MULTIPOINT (1 2, 3 4)
+                    if parser.peek() == Some(&Token::Open) {
+                        parser.expect(Token::Open)?;
+                        let position = parser.position()?;
+                        parser.expect(Token::Close)?;
+                        Ok(position)
+                    } else {
+                        parser.position()
+                    }
+                })?),
+                "MULTILINESTRING" => Shape::MultiLineString(self.list(Parser::positions)?),
+                "MULTIPOLYGON" => Shape::MultiPolygon(self.list(|parser| parser.list(Parser::positions))?),
+                "GEOMETRYCOLLECTION" => Shape::GeometryCollection(self.list(Parser::geometry)?),
+                _ => return Err(self.error(&format!("unsupported geometry type {}", name))),
+            }
+        };
+
+        let dimension = self.dimension.unwrap_or_default();
+        self.dimension = outer;
+        Ok((shape, dimension))
+    }
+
+    /// Compound curve member, a bare coordinate list for line strings
+    fn segment(&mut self) -> Result<Shape> {
+        if self.peek() == Some(&Token::Open) {
+            return Ok(Shape::LineString(self.positions()?));
+        }
+        let name = self.word()?;
+        self.dimension_tag()?;
+        match name.as_str() {
+            "LINESTRING" => Ok(Shape::LineString(self.positions()?)),
+            "CIRCULARSTRING" => Ok(Shape::CircularString(self.positions()?)),
+            _ => Err(self.error(&format!("{} is not a compound curve segment", name))),
+        }
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect(Token::Open)?;
+        let mut items = vec![item(self)?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            items.push(item(self)?);
+        }
+        self.expect(Token::Close)?;
+        Ok(items)
+    }
+
+    fn positions(&mut self) -> Result<Vec<Position>> {
+        self.list(Parser::position)
+    }
+
+    fn position(&mut self) -> Result<Position> {
+        let mut values = Vec::with_capacity(4);
+        while let Some(&Token::Number(value)) = self.peek() {
+            values.push(value);
+            self.pos += 1;
+        }
+        let dimension = *self.dimension.get_or_insert(match values.len() {
+            3 => Dimension::Xyz,
+            4 => Dimension::Xyzm,
+            _ => Dimension::Xy,
+        });
+        let expected = 2 + usize::from(dimension.has_z()) + usize::from(dimension.has_m());
+        if values.len() != expected {
+            return Err(self.error(&format!("expected {} ordinates, found {}", expected, values.len())));
+        }
+        let mut position = [values[0], values[1], 0.0, 0.0];
+        match dimension {
+            Dimension::Xy => {}
+            Dimension::Xyz => position[2] = values[2],
+            Dimension::Xym => position[3] = values[2],
+            Dimension::Xyzm => {
+                position[2] = values[2];
+                position[3] = values[3];
+            }
+        }
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Point, Polygon};
+    use geo_types::LineString as GeoLineString;
+
+    fn roundtrip(wkt: &str) {
+        let geometry = Geometry::from_wkt(wkt, Crs::wgs84()).unwrap();
+        assert_eq!(geometry.to_wkt(), wkt);
+    }
+
+    #[test]
+    fn test_wkt_roundtrip() {
+        roundtrip("POINT (1 2)");
+        roundtrip("POINT ZM (1 2 3 4)");
+        roundtrip("LINESTRING M (0 0 10, 1 1 20)");
+        roundtrip("POLYGON Z ((0 0 1, 4 0 2, 4 4 3, 0 0 1), (1 1 5, 2 1 5, 2 2 5, 1 1 5))");
+        roundtrip("MULTIPOINT Z ((1 2 3), (4 5 6))");
+        roundtrip("MULTILINESTRING ((0 0, 1 1), (2 2, 3 3))");
+        roundtrip("MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))");
+        roundtrip("GEOMETRYCOLLECTION Z (POINT Z (1 2 3), LINESTRING (0 0, 1 1))");
+        roundtrip("CIRCULARSTRING (0 0, 1 1, 2 0)");
+        roundtrip("COMPOUNDCURVE Z ((-1 0 0, 0 0 1), CIRCULARSTRING Z (0 0 1, 1 1 2, 2 0 3))");
+        roundtrip("LINESTRING EMPTY");
+        roundtrip("GEOMETRYCOLLECTION EMPTY");
+    }
+
+    #[test]
+    fn test_wkt_parsing() {
+        let geometry = Geometry::from_wkt("point(1.5 -2e3 7)", Crs::wgs84()).unwrap();
+        let Geometry::Point(point) = geometry else {
+            panic!("expected a point");
+        };
+        assert_eq!((point.x(), point.y(), point.z, point.m), (1.5, -2000.0, Some(7.0), None));
+
+        let multipoint = Geometry::from_wkt("MULTIPOINT (1 2, 3 4)", Crs::wgs84()).unwrap();
+        assert_eq!(multipoint.to_wkt(), "MULTIPOINT ((1 2), (3 4))");
+
+        let polygon = Geometry::Polygon(
+            Polygon::new(GeoLineString::from(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 0.0)]), vec![], Crs::wgs84())
+                .with_m(vec![1.0, 2.0, 3.0]),
+        );
+        assert_eq!(polygon.to_wkt(), "POLYGON M ((0 0 1, 1 0 2, 0 0 3))");
+
+        assert!(Geometry::from_wkt("POINT EMPTY", Crs::wgs84()).is_err());
+        assert!(Geometry::from_wkt("POINT Z (1 2)", Crs::wgs84()).is_err());
+        assert!(Geometry::from_wkt("LINESTRING (0 0, 1 1 1)", Crs::wgs84()).is_err());
+        assert!(Geometry::from_wkt("POINT (1 2) x", Crs::wgs84()).is_err());
+        assert!(Geometry::from_wkt("CIRCULARSTRING (0 0, 1 1)", Crs::wgs84()).is_err());
+        assert_eq!(
+            Geometry::Point(Point::new(0.1, 1e20, Crs::wgs84())).to_wkt(),
+            "POINT (0.1 100000000000000000000)"
+        );
+    }
+}
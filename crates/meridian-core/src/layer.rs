@@ -513,7 +513,7 @@ impl fmt::Display for Layer {
 mod tests {
     use super::*;
     use crate::crs::Crs;
-    use crate::geometry::Point;
+    use crate::geometry::{Geometry, Point};
     use serde_json::json;
 
     #[test]
@@ -584,4 +584,19 @@ mod tests {
         let bounds = nearest.bounds();
         assert_eq!(bounds.min_x, 0.0);
     }
+
+    #[test]
+    fn test_layer_transform() {
+        let mut layer = Layer::new("test", Crs::wgs84());
+        layer.add_feature(Feature::new(Geometry::Point(Point::new(9.0, 48.0, Crs::wgs84()))));
+
+        let utm = layer.transform(&Crs::utm(32, true)).unwrap();
+        assert_eq!(utm.crs, Crs::utm(32, true));
+        let bounds = utm.bounds().unwrap();
+        assert!((bounds.min_x - 500_000.0).abs() < 1e-3);
+
+        let back = utm.transform(&Crs::wgs84()).unwrap().bounds().unwrap();
+        assert!((back.min_x - 9.0).abs() < 1e-9);
+        assert!((back.min_y - 48.0).abs() < 1e-9);
+    }
 }
@@ -130,8 +130,8 @@ pub mod prelude {
     pub use crate::error::{MeridianError, Result};
     pub use crate::feature::{Feature, FeatureBuilder};
     pub use crate::geometry::{
-        Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
-        Point, Polygon,
+        CircularString, CompoundCurve, CurveSegment, Dimension, Geometry, GeometryCollection,
+        LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon,
     };
     pub use crate::layer::Layer;
     pub use crate::spatial_index::SpatialIndex;
@@ -49,6 +49,10 @@ pub enum DbError {
     #[error("UUID error: {0}")]
     UuidError(#[from] uuid::Error),
 
+    /// Feature locked by another lock
+    #[error("Feature locked: {0}")]
+    Locked(String),
+
     /// Connection timeout
     #[error("Connection timeout")]
     Timeout,
@@ -54,10 +54,15 @@ pub mod transaction;
 pub use error::{DbError, DbResult};
 pub use migrations::{default_migrations, Migration, MigrationManager};
 pub use models::{
-    BBox, Feature, Layer, LayerStyle, Metadata, PaginatedResponse, Pagination, SpatialIndex,
+    BBox, Feature, Layer, LayerProperty, LayerStyle, Metadata, PaginatedResponse, Pagination,
+    SpatialIndex,
 };
 pub use pool::{HealthStatus, Pool, PoolConfig, PoolStats};
-pub use queries::{IndexHint, QueryOptimizer, SpatialAggregation, SpatialQuery};
+pub use queries::{
+    CompareOp, EditGeometry, EditSummary, FeatureEdit, FeatureFilter, FeatureLock, FeatureQuery,
+    FeatureQueryResult, FeatureUpdate, IndexHint, LockAction, NewFeature, QueryOptimizer,
+    ReleaseAction, SortKey, SpatialAggregation, SpatialOp, SpatialQuery,
+};
 pub use repository::{
     FeatureRepository, LayerRepository, SpatialQueryParams, SpatialRepository,
 };
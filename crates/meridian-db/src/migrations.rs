@@ -4,6 +4,7 @@ use crate::error::{DbError, DbResult};
 use crate::pool::Pool;
 use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Row};
+use std::collections::HashSet;
 
 /// Migration metadata
 #[derive(Debug, Clone)]
@@ -152,13 +153,21 @@ impl MigrationManager {
     }
 
     /// Run all pending migrations
+    ///
+    /// Pending means not yet recorded, so independent migration sets with
+    /// disjoint version ranges can share the tracking table.
     pub async fn migrate(&self, migrations: &[Migration]) -> DbResult<()> {
         self.init().await?;
 
-        let current = self.current_version().await?.unwrap_or(0);
+        let applied: HashSet<i32> = self
+            .applied_migrations()
+            .await?
+            .iter()
+            .map(|m| m.version)
+            .collect();
 
         for migration in migrations {
-            if migration.version > current {
+            if !applied.contains(&migration.version) {
                 println!("Applying migration {}: {}", migration.version, migration.name);
                 self.apply(migration).await?;
             }
@@ -332,12 +341,35 @@ pub fn create_triggers() -> Migration {
     )
 }
 
+/// Create feature locks migration
+pub fn create_feature_locks() -> Migration {
+    Migration::new(
+        4,
+        "create_feature_locks",
+        r#"
+        -- Short-lived edit locks held on features (WFS LockFeature)
+        CREATE TABLE feature_locks (
+            feature_id UUID PRIMARY KEY REFERENCES features(id) ON DELETE CASCADE,
+            lock_id TEXT NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL
+        );
+
+        CREATE INDEX idx_feature_locks_lock_id ON feature_locks(lock_id);
+        CREATE INDEX idx_feature_locks_expires_at ON feature_locks(expires_at);
+        "#,
+        r#"
+        DROP TABLE IF EXISTS feature_locks;
+        "#,
+    )
+}
+
 /// Get all default migrations
 pub fn default_migrations() -> Vec<Migration> {
     vec![
         create_base_schema(),
         create_spatial_indexes(),
         create_triggers(),
+        create_feature_locks(),
     ]
 }
 
@@ -356,9 +388,10 @@ mod tests {
     #[test]
     fn test_default_migrations() {
         let migrations = default_migrations();
-        assert_eq!(migrations.len(), 3);
+        assert_eq!(migrations.len(), 4);
         assert_eq!(migrations[0].version, 1);
         assert_eq!(migrations[1].version, 2);
         assert_eq!(migrations[2].version, 3);
+        assert_eq!(migrations[3].version, 4);
     }
 }
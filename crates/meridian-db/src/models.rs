@@ -193,6 +193,30 @@ impl BBox {
     }
 }
 
+/// An attribute found in the properties of a layer's features
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LayerProperty {
+    /// Property name
+    pub name: String,
+    /// JSON types seen for the property (`string`, `number`, `boolean`, ...)
+    pub types: Vec<String>,
+    /// Whether every numeric value is an integer
+    pub integral: bool,
+    /// Whether some features lack the property or have it set to null
+    pub nullable: bool,
+}
+
+impl LayerProperty {
+    /// The single non-null JSON type of the property, if it has one
+    pub fn json_type(&self) -> Option<&str> {
+        let mut types = self.types.iter().filter(|t| t.as_str() != "null");
+        match (types.next(), types.next()) {
+            (Some(t), None) => Some(t.as_str()),
+            _ => None,
+        }
+    }
+}
+
 /// Pagination parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pagination {
@@ -277,6 +301,23 @@ mod tests {
         assert!(wkt.contains("-180"));
     }
 
+    #[test]
+    fn test_layer_property_json_type() {
+        let property = LayerProperty {
+            name: "height".to_string(),
+            types: vec!["null".to_string(), "number".to_string()],
+            integral: false,
+            nullable: true,
+        };
+        assert_eq!(property.json_type(), Some("number"));
+
+        let mixed = LayerProperty {
+            types: vec!["number".to_string(), "string".to_string()],
+            ..property
+        };
+        assert_eq!(mixed.json_type(), None);
+    }
+
     #[test]
     fn test_pagination() {
         let pagination = Pagination::new(2, 50);
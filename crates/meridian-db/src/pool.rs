@@ -207,6 +207,31 @@ impl Pool {
         })
     }
 
+    /// Create a pool from a connection string without connecting
+    ///
+    /// Connections are opened on first use, so this succeeds even while the
+    /// database is unreachable.
+    pub fn from_url_lazy(url: &str, max_connections: u32) -> DbResult<Self> {
+        let connect_opts: PgConnectOptions = url
+            .parse()
+            .map_err(|e| DbError::ConfigError(format!("Invalid database URL: {}", e)))?;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect_lazy_with(connect_opts.disable_statement_logging());
+
+        let config = PoolConfig {
+            max_connections,
+            min_connections: 0,
+            ..PoolConfig::default()
+        };
+
+        Ok(Self {
+            inner: pool,
+            config,
+        })
+    }
+
     /// Get the underlying pool
     pub fn inner(&self) -> &PgPool {
         &self.inner
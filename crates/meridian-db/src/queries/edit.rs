@@ -0,0 +1,236 @@
+//! Transactional feature edits and feature locks
+//!
+//! A batch of [`FeatureEdit`]s is applied atomically by
+//! [`FeatureRepository::apply_edits`](crate::FeatureRepository::apply_edits).
+//! Features may be locked for a limited time with
+//! [`FeatureRepository::lock_features`](crate::FeatureRepository::lock_features);
+//! locked features can only be updated or deleted by edits presenting the
+//! lock id.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::filter::{push_geometry, FeatureFilter};
+
+/// A GeoJSON geometry in a given SRID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditGeometry {
+    /// GeoJSON geometry object
+    pub geojson: Value,
+    /// SRID of the coordinates
+    pub srid: i32,
+}
+
+/// A feature to insert
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewFeature {
+    /// Target layer
+    pub layer_id: Uuid,
+    /// Geometry, if any
+    pub geometry: Option<EditGeometry>,
+    /// Feature properties
+    pub properties: Map<String, Value>,
+}
+
+/// Changes applied to every feature matching a filter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureUpdate {
+    /// Target layer
+    pub layer_id: Uuid,
+    /// Features to update
+    pub filter: FeatureFilter,
+    /// Properties to set
+    pub set_properties: Map<String, Value>,
+    /// Properties to remove
+    pub remove_properties: Vec<String>,
+    /// New geometry; `Some(None)` clears it
+    pub geometry: Option<Option<EditGeometry>>,
+}
+
+/// A single edit within a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FeatureEdit {
+    Insert(NewFeature),
+    Update(FeatureUpdate),
+    Delete {
+        layer_id: Uuid,
+        filter: FeatureFilter,
+    },
+}
+
+impl FeatureEdit {
+    /// Layer the edit applies to
+    pub fn layer_id(&self) -> Uuid {
+        match self {
+            FeatureEdit::Insert(feature) => feature.layer_id,
+            FeatureEdit::Update(update) => update.layer_id,
+            FeatureEdit::Delete { layer_id, .. } => *layer_id,
+        }
+    }
+}
+
+/// Outcome of an applied batch of edits
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditSummary {
+    /// Ids of inserted features, in insert order
+    pub inserted: Vec<Uuid>,
+    /// Number of updated features
+    pub updated: u64,
+    /// Number of deleted features
+    pub deleted: u64,
+}
+
+/// How many of the requested features must be locked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LockAction {
+    /// Fail unless every feature can be locked
+    #[default]
+    All,
+    /// Lock the features that are not locked already
+    Some,
+}
+
+/// Which locks to release once a transaction commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReleaseAction {
+    /// Release every feature held by the lock
+    #[default]
+    All,
+    /// Release only the features that were updated or deleted
+    Some,
+}
+
+/// Result of a lock request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureLock {
+    /// Lock identifier to present with later edits
+    pub lock_id: String,
+    /// Time at which the lock lapses
+    pub expires_at: DateTime<Utc>,
+    /// Locked features as `(layer_id, feature_id)`
+    pub locked: Vec<(Uuid, Uuid)>,
+    /// Matching features held by other locks as `(layer_id, feature_id)`
+    pub not_locked: Vec<(Uuid, Uuid)>,
+}
+
+/// Build the INSERT statement for a new feature
+pub fn insert_sql(feature: &NewFeature, id: Uuid) -> QueryBuilder<'static, Postgres> {
+    let mut qb = QueryBuilder::new("INSERT INTO features (id, layer_id, geometry, properties) VALUES (");
+    qb.push_bind(id);
+    qb.push(", ");
+    qb.push_bind(feature.layer_id);
+    qb.push(", ");
+    match &feature.geometry {
+        Some(geometry) => push_geometry(&mut qb, &geometry.geojson, geometry.srid),
+        None => {
+            qb.push("NULL");
+        }
+    }
+    qb.push(", ");
+    qb.push_bind(Value::Object(feature.properties.clone()));
+    qb.push(")");
+    qb
+}
+
+/// Build the statement selecting and row-locking the features matched by a filter
+pub fn matching_ids_sql(layer_id: Uuid, filter: &FeatureFilter) -> QueryBuilder<'static, Postgres> {
+    let mut qb = QueryBuilder::new("SELECT id FROM features WHERE layer_id = ");
+    qb.push_bind(layer_id);
+    qb.push(" AND ");
+    filter.push_sql(&mut qb);
+    qb.push(" ORDER BY id FOR UPDATE");
+    qb
+}
+
+/// Build the UPDATE statement for the given feature ids
+pub fn update_sql(update: &FeatureUpdate, ids: &[Uuid]) -> QueryBuilder<'static, Postgres> {
+    let mut qb = QueryBuilder::new("UPDATE features SET properties = (properties - ");
+    qb.push_bind(update.remove_properties.clone());
+    qb.push("::text[]) || ");
+    qb.push_bind(Value::Object(update.set_properties.clone()));
+    qb.push("::jsonb");
+
+    if let Some(geometry) = &update.geometry {
+        qb.push(", geometry = ");
+        match geometry {
+            Some(geometry) => push_geometry(&mut qb, &geometry.geojson, geometry.srid),
+            None => {
+                qb.push("NULL");
+            }
+        }
+    }
+
+    qb.push(" WHERE id = ANY(");
+    qb.push_bind(ids.to_vec());
+    qb.push(")");
+    qb
+}
+
+/// Build the statement finding which of the given features are held by another lock
+///
+/// Expired locks are ignored. With `lock_id`, features held by that lock are
+/// not reported.
+pub fn conflicting_locks_sql(ids: &[Uuid], lock_id: Option<&str>) -> QueryBuilder<'static, Postgres> {
+    let mut qb = QueryBuilder::new("SELECT feature_id FROM feature_locks WHERE feature_id = ANY(");
+    qb.push_bind(ids.to_vec());
+    qb.push(") AND expires_at > NOW()");
+    if let Some(lock_id) = lock_id {
+        qb.push(" AND lock_id <> ");
+        qb.push_bind(lock_id.to_string());
+    }
+    qb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_insert_sql() {
+        let feature = NewFeature {
+            layer_id: Uuid::nil(),
+            geometry: Some(EditGeometry {
+                geojson: json!({"type": "Point", "coordinates": [1.0, 2.0]}),
+                srid: 3857,
+            }),
+            properties: Map::new(),
+        };
+        let sql = insert_sql(&feature, Uuid::nil()).into_sql();
+        assert_eq!(
+            sql,
+            "INSERT INTO features (id, layer_id, geometry, properties) VALUES ($1, $2, \
+             ST_Transform(ST_SetSRID(ST_GeomFromGeoJSON($3), $4), 4326), $5)"
+        );
+    }
+
+    #[test]
+    fn test_update_sql() {
+        let mut update = FeatureUpdate {
+            layer_id: Uuid::nil(),
+            filter: FeatureFilter::Include,
+            set_properties: Map::new(),
+            remove_properties: vec!["lanes".to_string()],
+            geometry: None,
+        };
+        assert_eq!(
+            update_sql(&update, &[Uuid::nil()]).into_sql(),
+            "UPDATE features SET properties = (properties - $1::text[]) || $2::jsonb WHERE id = ANY($3)"
+        );
+
+        update.geometry = Some(None);
+        assert!(update_sql(&update, &[]).into_sql().contains(", geometry = NULL WHERE"));
+    }
+
+    #[test]
+    fn test_lock_sql() {
+        let sql = matching_ids_sql(Uuid::nil(), &FeatureFilter::Include).into_sql();
+        assert_eq!(sql, "SELECT id FROM features WHERE layer_id = $1 AND TRUE ORDER BY id FOR UPDATE");
+
+        let sql = conflicting_locks_sql(&[], Some("abc")).into_sql();
+        assert!(sql.ends_with("expires_at > NOW() AND lock_id <> $2"));
+    }
+}
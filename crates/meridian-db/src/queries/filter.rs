@@ -0,0 +1,617 @@
+//! Attribute and spatial filters over the `features` table
+//!
+//! A [`FeatureFilter`] is a small expression tree (comparisons on feature
+//! properties, spatial predicates, id lookups and boolean combinations) that
+//! is rendered into a parameterized `WHERE` clause. OGC filter languages such
+//! as FES 2.0 and CQL2 are parsed into this type by the services.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::Feature;
+
+/// SRID of the `features.geometry` column
+pub const STORAGE_SRID: i32 = 4326;
+
+/// Comparison operators for property filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn sql(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::NotEq => "<>",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// Spatial predicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpatialOp {
+    /// Bounding box overlap
+    BBox,
+    Intersects,
+    Disjoint,
+    Contains,
+    Within,
+    Touches,
+    Crosses,
+    Overlaps,
+    Equals,
+    /// Within a distance (metres)
+    DWithin,
+    /// Further than a distance (metres)
+    Beyond,
+}
+
+impl SpatialOp {
+    fn function(&self) -> &'static str {
+        match self {
+            SpatialOp::BBox | SpatialOp::Intersects => "ST_Intersects",
+            SpatialOp::Disjoint => "ST_Disjoint",
+            SpatialOp::Contains => "ST_Contains",
+            SpatialOp::Within => "ST_Within",
+            SpatialOp::Touches => "ST_Touches",
+            SpatialOp::Crosses => "ST_Crosses",
+            SpatialOp::Overlaps => "ST_Overlaps",
+            SpatialOp::Equals => "ST_Equals",
+            SpatialOp::DWithin | SpatialOp::Beyond => "ST_DWithin",
+        }
+    }
+}
+
+/// Filter expression over feature properties and geometry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FeatureFilter {
+    /// All sub-filters match
+    And(Vec<FeatureFilter>),
+    /// Any sub-filter matches
+    Or(Vec<FeatureFilter>),
+    /// Negation
+    Not(Box<FeatureFilter>),
+    /// Compare a property against a literal
+    ///
+    /// Numbers, and strings that parse as numbers, are compared numerically
+    /// against numeric properties and as text otherwise.
+    Compare {
+        property: String,
+        op: CompareOp,
+        value: Value,
+        match_case: bool,
+    },
+    /// SQL `LIKE` style pattern match, using `%` and `_` wildcards
+    Like {
+        property: String,
+        pattern: String,
+        match_case: bool,
+    },
+    /// Inclusive range test
+    Between {
+        property: String,
+        lower: Value,
+        upper: Value,
+    },
+    /// Property is missing or null
+    IsNull(String),
+    /// Spatial predicate against a GeoJSON geometry given in `srid`
+    Spatial {
+        op: SpatialOp,
+        geometry: Value,
+        srid: i32,
+        distance: Option<f64>,
+    },
+    /// Timestamp within an inclusive interval; `None` leaves an end open
+    ///
+    /// With a property name the property is read as an ISO 8601 timestamp,
+    /// otherwise the feature's last update time is used.
+    Temporal {
+        property: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+    /// Feature id is one of the given ids
+    ResourceId(Vec<Uuid>),
+    /// Matches every feature
+    Include,
+    /// Matches no feature
+    Exclude,
+}
+
+impl FeatureFilter {
+    /// Conjunction of filters, flattening trivial cases
+    pub fn and(filters: Vec<FeatureFilter>) -> FeatureFilter {
+        let mut filters: Vec<FeatureFilter> = filters
+            .into_iter()
+            .filter(|f| *f != FeatureFilter::Include)
+            .collect();
+        match filters.len() {
+            0 => FeatureFilter::Include,
+            1 => filters.remove(0),
+            _ => FeatureFilter::And(filters),
+        }
+    }
+
+    /// Bounding box filter
+    pub fn bbox(min_x: f64, min_y: f64, max_x: f64, max_y: f64, srid: i32) -> FeatureFilter {
+        FeatureFilter::Spatial {
+            op: SpatialOp::BBox,
+            geometry: serde_json::json!({
+                "type": "Polygon",
+                "coordinates": [[
+                    [min_x, min_y], [max_x, min_y], [max_x, max_y], [min_x, max_y], [min_x, min_y]
+                ]]
+            }),
+            srid,
+            distance: None,
+        }
+    }
+
+    /// Append this filter as a boolean SQL expression
+    pub fn push_sql(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            FeatureFilter::And(filters) | FeatureFilter::Or(filters) if filters.is_empty() => {
+                qb.push(if matches!(self, FeatureFilter::And(_)) { "TRUE" } else { "FALSE" });
+            }
+            FeatureFilter::And(filters) | FeatureFilter::Or(filters) => {
+                let joiner = if matches!(self, FeatureFilter::And(_)) { " AND " } else { " OR " };
+                qb.push("(");
+                for (i, filter) in filters.iter().enumerate() {
+                    if i > 0 {
+                        qb.push(joiner);
+                    }
+                    filter.push_sql(qb);
+                }
+                qb.push(")");
+            }
+            FeatureFilter::Not(filter) => {
+                qb.push("NOT (");
+                filter.push_sql(qb);
+                qb.push(")");
+            }
+            FeatureFilter::Compare {
+                property,
+                op,
+                value,
+                match_case,
+            } => push_compare(qb, property, *op, value, *match_case),
+            FeatureFilter::Like {
+                property,
+                pattern,
+                match_case,
+            } => {
+                qb.push("(properties ->> ");
+                qb.push_bind(property.clone());
+                qb.push(if *match_case { ") LIKE " } else { ") ILIKE " });
+                qb.push_bind(pattern.clone());
+            }
+            FeatureFilter::Between {
+                property,
+                lower,
+                upper,
+            } => {
+                qb.push("(");
+                push_compare(qb, property, CompareOp::Ge, lower, true);
+                qb.push(" AND ");
+                push_compare(qb, property, CompareOp::Le, upper, true);
+                qb.push(")");
+            }
+            FeatureFilter::IsNull(property) => {
+                qb.push("coalesce(jsonb_typeof(properties -> ");
+                qb.push_bind(property.clone());
+                qb.push("), 'null') = 'null'");
+            }
+            FeatureFilter::Spatial {
+                op,
+                geometry,
+                srid,
+                distance,
+            } => push_spatial(qb, *op, geometry, *srid, *distance),
+            FeatureFilter::Temporal {
+                property,
+                start,
+                end,
+            } => push_temporal(qb, property.as_deref(), *start, *end),
+            FeatureFilter::ResourceId(ids) => {
+                qb.push("id = ANY(");
+                qb.push_bind(ids.clone());
+                qb.push(")");
+            }
+            FeatureFilter::Include => {
+                qb.push("TRUE");
+            }
+            FeatureFilter::Exclude => {
+                qb.push("FALSE");
+            }
+        }
+    }
+}
+
+fn push_compare(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    property: &str,
+    op: CompareOp,
+    value: &Value,
+    match_case: bool,
+) {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    };
+    // Literals from text based filter languages arrive as strings, so numeric
+    // looking strings are compared numerically against numeric properties
+    let number = match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    };
+
+    let push_text = |qb: &mut QueryBuilder<'_, Postgres>, text: String| {
+        if match_case {
+            qb.push("(properties ->> ");
+            qb.push_bind(property.to_string());
+            qb.push(") ");
+            qb.push(op.sql());
+            qb.push(" ");
+            qb.push_bind(text);
+        } else {
+            qb.push("lower(properties ->> ");
+            qb.push_bind(property.to_string());
+            qb.push(") ");
+            qb.push(op.sql());
+            qb.push(" lower(");
+            qb.push_bind(text);
+            qb.push(")");
+        }
+    };
+
+    match number {
+        Some(number) => {
+            qb.push("(CASE WHEN jsonb_typeof(properties -> ");
+            qb.push_bind(property.to_string());
+            qb.push(") = 'number' THEN (properties ->> ");
+            qb.push_bind(property.to_string());
+            qb.push(")::double precision ");
+            qb.push(op.sql());
+            qb.push(" ");
+            qb.push_bind(number);
+            qb.push(" ELSE ");
+            push_text(qb, text);
+            qb.push(" END)");
+        }
+        None => push_text(qb, text),
+    }
+}
+
+fn push_temporal(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    property: Option<&str>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+) {
+    // Property values that do not look like dates are treated as null
+    // instead of failing the cast
+    let push_timestamp = |qb: &mut QueryBuilder<'_, Postgres>| match property {
+        Some(property) => {
+            qb.push("(CASE WHEN (properties ->> ");
+            qb.push_bind(property.to_string());
+            qb.push(") ~ '^[0-9]{4}-[0-9]{2}-[0-9]{2}' THEN (properties ->> ");
+            qb.push_bind(property.to_string());
+            qb.push(")::timestamptz END)");
+        }
+        None => {
+            qb.push("updated_at");
+        }
+    };
+
+    match (start, end) {
+        (None, None) => {
+            qb.push("TRUE");
+        }
+        (Some(start), None) => {
+            push_timestamp(qb);
+            qb.push(" >= ");
+            qb.push_bind(start);
+        }
+        (None, Some(end)) => {
+            push_timestamp(qb);
+            qb.push(" <= ");
+            qb.push_bind(end);
+        }
+        (Some(start), Some(end)) => {
+            qb.push("(");
+            push_timestamp(qb);
+            qb.push(" >= ");
+            qb.push_bind(start);
+            qb.push(" AND ");
+            push_timestamp(qb);
+            qb.push(" <= ");
+            qb.push_bind(end);
+            qb.push(")");
+        }
+    }
+}
+
+fn push_spatial(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    op: SpatialOp,
+    geometry: &Value,
+    srid: i32,
+    distance: Option<f64>,
+) {
+    match op {
+        SpatialOp::DWithin | SpatialOp::Beyond => {
+            if op == SpatialOp::Beyond {
+                qb.push("NOT ");
+            }
+            qb.push("ST_DWithin(geometry::geography, ");
+            push_geometry(qb, geometry, srid);
+            qb.push("::geography, ");
+            qb.push_bind(distance.unwrap_or_default());
+            qb.push(")");
+        }
+        _ => {
+            qb.push(op.function());
+            qb.push("(geometry, ");
+            push_geometry(qb, geometry, srid);
+            qb.push(")");
+        }
+    }
+}
+
+/// Push a GeoJSON geometry in `srid`, transformed to the storage SRID
+pub(crate) fn push_geometry(qb: &mut QueryBuilder<'_, Postgres>, geometry: &Value, srid: i32) {
+    qb.push("ST_Transform(ST_SetSRID(ST_GeomFromGeoJSON(");
+    qb.push_bind(geometry.to_string());
+    qb.push("), ");
+    qb.push_bind(srid);
+    qb.push("), ");
+    qb.push(STORAGE_SRID.to_string());
+    qb.push(")");
+}
+
+/// Sort key on a feature property
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortKey {
+    /// Property name
+    pub property: String,
+    /// Sort descending
+    pub descending: bool,
+}
+
+/// A filtered, sorted and paged query for the features of a layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureQuery {
+    /// Filter expression
+    pub filter: FeatureFilter,
+    /// Sort order; features are otherwise ordered by creation time
+    pub sort: Vec<SortKey>,
+    /// Number of matching features to skip
+    pub offset: u64,
+    /// Maximum number of features to return
+    pub limit: Option<u64>,
+    /// SRID the geometries are returned in
+    pub output_srid: i32,
+}
+
+impl Default for FeatureQuery {
+    fn default() -> Self {
+        Self {
+            filter: FeatureFilter::Include,
+            sort: Vec::new(),
+            offset: 0,
+            limit: None,
+            output_srid: STORAGE_SRID,
+        }
+    }
+}
+
+impl FeatureQuery {
+    /// Create a query with the given filter
+    pub fn new(filter: FeatureFilter) -> Self {
+        Self {
+            filter,
+            ..Self::default()
+        }
+    }
+
+    /// Set the page of results
+    pub fn with_paging(mut self, offset: u64, limit: Option<u64>) -> Self {
+        self.offset = offset;
+        self.limit = limit;
+        self
+    }
+
+    /// Set the sort order
+    pub fn with_sort(mut self, sort: Vec<SortKey>) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Set the output SRID
+    pub fn with_output_srid(mut self, srid: i32) -> Self {
+        self.output_srid = srid;
+        self
+    }
+
+    /// Build the SELECT statement for a layer
+    pub fn select_sql(&self, layer_id: Uuid) -> QueryBuilder<'static, Postgres> {
+        let mut qb = QueryBuilder::new("SELECT id, layer_id, ST_AsBinary(ST_Transform(geometry, ");
+        qb.push_bind(self.output_srid);
+        qb.push(")) as geometry, ST_AsGeoJSON(ST_Transform(geometry, ");
+        qb.push_bind(self.output_srid);
+        qb.push("))::jsonb as geometry_json, properties, created_at, updated_at FROM features WHERE layer_id = ");
+        qb.push_bind(layer_id);
+        qb.push(" AND ");
+        self.filter.push_sql(&mut qb);
+
+        qb.push(" ORDER BY ");
+        for key in &self.sort {
+            qb.push("properties -> ");
+            qb.push_bind(key.property.clone());
+            qb.push(if key.descending { " DESC NULLS LAST, " } else { " ASC NULLS LAST, " });
+        }
+        qb.push("created_at ASC, id ASC");
+
+        if let Some(limit) = self.limit {
+            qb.push(" LIMIT ");
+            qb.push_bind(limit as i64);
+        }
+        if self.offset > 0 {
+            qb.push(" OFFSET ");
+            qb.push_bind(self.offset as i64);
+        }
+        qb
+    }
+
+    /// Build the COUNT statement for a layer
+    pub fn count_sql(&self, layer_id: Uuid) -> QueryBuilder<'static, Postgres> {
+        let mut qb = QueryBuilder::new("SELECT COUNT(*) FROM features WHERE layer_id = ");
+        qb.push_bind(layer_id);
+        qb.push(" AND ");
+        self.filter.push_sql(&mut qb);
+        qb
+    }
+}
+
+/// Result of a [`FeatureQuery`]
+#[derive(Debug, Clone)]
+pub struct FeatureQueryResult {
+    /// Features of the requested page
+    pub features: Vec<Feature>,
+    /// Number of features matching the filter, ignoring paging
+    pub number_matched: u64,
+}
+
+/// Convert an OGC style wildcard pattern to a SQL `LIKE` pattern
+pub fn like_pattern(pattern: &str, wildcard: char, single_char: char, escape: char) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == escape {
+            if let Some(next) = chars.next() {
+                if matches!(next, '%' | '_' | '\\') {
+                    out.push('\\');
+                }
+                out.push(next);
+            }
+        } else if c == wildcard {
+            out.push('%');
+        } else if c == single_char {
+            out.push('_');
+        } else if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+            out.push(c);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_sql() {
+        let filter = FeatureFilter::and(vec![
+            FeatureFilter::Compare {
+                property: "name".to_string(),
+                op: CompareOp::Eq,
+                value: Value::String("Main St".to_string()),
+                match_case: true,
+            },
+            FeatureFilter::Not(Box::new(FeatureFilter::IsNull("lanes".to_string()))),
+            FeatureFilter::bbox(0.0, 0.0, 1.0, 1.0, 3857),
+        ]);
+
+        let mut qb = QueryBuilder::new("");
+        filter.push_sql(&mut qb);
+        let sql = qb.sql();
+
+        assert!(sql.starts_with("((properties ->> $1) = $2 AND NOT ("));
+
+        let mut qb = QueryBuilder::new("");
+        FeatureFilter::Compare {
+            property: "lanes".to_string(),
+            op: CompareOp::Gt,
+            value: Value::String("2".to_string()),
+            match_case: true,
+        }
+        .push_sql(&mut qb);
+        assert!(qb.sql().starts_with("(CASE WHEN jsonb_typeof(properties -> $1) = 'number'"));
+        assert!(sql.contains("ST_Intersects(geometry, ST_Transform(ST_SetSRID(ST_GeomFromGeoJSON($4), $5), 4326))"));
+    }
+
+    #[test]
+    fn test_temporal_sql() {
+        let start = Utc::now();
+        let mut qb = QueryBuilder::new("");
+        FeatureFilter::Temporal {
+            property: None,
+            start: Some(start),
+            end: None,
+        }
+        .push_sql(&mut qb);
+        assert_eq!(qb.sql(), "updated_at >= $1");
+
+        let mut qb = QueryBuilder::new("");
+        FeatureFilter::Temporal {
+            property: Some("observed".to_string()),
+            start: Some(start),
+            end: Some(start),
+        }
+        .push_sql(&mut qb);
+        assert!(qb.sql().starts_with("((CASE WHEN (properties ->> $1) ~"));
+        assert!(qb.sql().ends_with("::timestamptz END) <= $6)"));
+    }
+
+    #[test]
+    fn test_and_flattening() {
+        assert_eq!(FeatureFilter::and(vec![]), FeatureFilter::Include);
+        assert_eq!(
+            FeatureFilter::and(vec![FeatureFilter::Include, FeatureFilter::Exclude]),
+            FeatureFilter::Exclude
+        );
+    }
+
+    #[test]
+    fn test_query_sql() {
+        let query = FeatureQuery::new(FeatureFilter::Include)
+            .with_sort(vec![SortKey {
+                property: "height".to_string(),
+                descending: true,
+            }])
+            .with_paging(20, Some(10))
+            .with_output_srid(3857);
+
+        let sql = query.select_sql(Uuid::nil()).into_sql();
+        assert!(sql.contains("ORDER BY properties -> $4 DESC NULLS LAST, created_at ASC, id ASC"));
+        assert!(sql.ends_with("LIMIT $5 OFFSET $6"));
+
+        let count = query.count_sql(Uuid::nil()).into_sql();
+        assert_eq!(count, "SELECT COUNT(*) FROM features WHERE layer_id = $1 AND TRUE");
+    }
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("Main*", '*', '.', '!'), "Main%");
+        assert_eq!(like_pattern("a.c_!*", '*', '.', '!'), "a_c\\_*");
+    }
+}
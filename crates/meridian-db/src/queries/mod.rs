@@ -1,5 +1,16 @@
 //! Spatial query builders with PostGIS support
 
+pub mod edit;
+pub mod filter;
+
+pub use edit::{
+    EditGeometry, EditSummary, FeatureEdit, FeatureLock, FeatureUpdate, LockAction, NewFeature,
+    ReleaseAction,
+};
+pub use filter::{
+    CompareOp, FeatureFilter, FeatureQuery, FeatureQueryResult, SortKey, SpatialOp,
+};
+
 use crate::models::BBox;
 
 /// Spatial query builder for PostGIS operations
@@ -1,11 +1,15 @@
 //! Generic spatial repository trait with CRUD operations
 
-use crate::error::DbResult;
-use crate::models::{BBox, Feature, Layer, PaginatedResponse, Pagination};
+use crate::error::{DbError, DbResult};
+use crate::models::{BBox, Feature, Layer, LayerProperty, LayerStyle, PaginatedResponse, Pagination};
 use crate::pool::Pool;
+use crate::queries::edit::{self, EditSummary, FeatureEdit, FeatureLock, LockAction, ReleaseAction};
+use crate::queries::{FeatureFilter, FeatureQuery, FeatureQueryResult};
+use crate::transaction::DbTransaction;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 use uuid::Uuid;
 
 /// Spatial query parameters
@@ -139,6 +143,43 @@ impl LayerRepository {
 
         Ok(PaginatedResponse::new(layers, total as u64, pagination))
     }
+
+    /// Find a layer by its name
+    pub async fn find_by_name(&self, name: &str) -> DbResult<Option<Layer>> {
+        let layer = sqlx::query_as::<_, Layer>(
+            "SELECT * FROM layers WHERE name = $1 ORDER BY created_at ASC LIMIT 1"
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(layer)
+    }
+
+    /// Find a named style for a layer, or its default style when no name is given
+    pub async fn find_style(&self, layer_id: Uuid, name: Option<&str>) -> DbResult<Option<LayerStyle>> {
+        let style = match name {
+            Some(name) => {
+                sqlx::query_as::<_, LayerStyle>(
+                    "SELECT * FROM layer_styles WHERE layer_id = $1 AND name = $2 LIMIT 1"
+                )
+                .bind(layer_id)
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as::<_, LayerStyle>(
+                    "SELECT * FROM layer_styles WHERE layer_id = $1 ORDER BY is_default DESC, created_at ASC LIMIT 1"
+                )
+                .bind(layer_id)
+                .fetch_optional(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(style)
+    }
 }
 
 #[async_trait]
@@ -326,6 +367,145 @@ impl FeatureRepository {
         Ok(PaginatedResponse::new(features, total as u64, pagination))
     }
 
+    /// Find features of a layer intersecting a bounding box
+    ///
+    /// The bounding box may be given in any SRID; it is transformed to the
+    /// storage SRID for the index lookup. Geometries are returned in
+    /// `target_srid`.
+    pub async fn find_by_layer_in_bbox(
+        &self,
+        layer_id: Uuid,
+        bbox: &BBox,
+        target_srid: i32,
+        limit: u32,
+    ) -> DbResult<Vec<Feature>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, layer_id, ST_AsBinary(ST_Transform(geometry, $7)) as geometry,
+                   ST_AsGeoJSON(ST_Transform(geometry, $7))::jsonb as geometry_json,
+                   properties, created_at, updated_at
+            FROM features
+            WHERE layer_id = $1
+              AND ST_Intersects(geometry, ST_Transform(ST_MakeEnvelope($2, $3, $4, $5, $6), 4326))
+            ORDER BY created_at ASC
+            LIMIT $8
+            "#
+        )
+        .bind(layer_id)
+        .bind(bbox.min_x)
+        .bind(bbox.min_y)
+        .bind(bbox.max_x)
+        .bind(bbox.max_y)
+        .bind(bbox.srid)
+        .bind(target_srid)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Feature {
+                id: row.get("id"),
+                layer_id: row.get("layer_id"),
+                geometry: row.get("geometry"),
+                geometry_json: row.get::<Option<serde_json::Value>, _>("geometry_json"),
+                properties: row.get("properties"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Run a filtered, sorted and paged query against a layer
+    pub async fn query(&self, layer_id: Uuid, query: &FeatureQuery) -> DbResult<FeatureQueryResult> {
+        let number_matched: i64 = query
+            .count_sql(layer_id)
+            .build_query_scalar()
+            .fetch_one(&self.pool)
+            .await?;
+
+        let features = if query.limit == Some(0) {
+            Vec::new()
+        } else {
+            query
+                .select_sql(layer_id)
+                .build()
+                .fetch_all(&self.pool)
+                .await?
+                .iter()
+                .map(|row| Feature {
+                    id: row.get("id"),
+                    layer_id: row.get("layer_id"),
+                    geometry: row.get("geometry"),
+                    geometry_json: row.get::<Option<serde_json::Value>, _>("geometry_json"),
+                    properties: row.get("properties"),
+                    created_at: row.get("created_at"),
+                    updated_at: row.get("updated_at"),
+                })
+                .collect()
+        };
+
+        Ok(FeatureQueryResult {
+            features,
+            number_matched: number_matched as u64,
+        })
+    }
+
+    /// Describe the properties used by a layer's features
+    pub async fn layer_properties(&self, layer_id: Uuid) -> DbResult<Vec<LayerProperty>> {
+        let properties = sqlx::query_as::<_, LayerProperty>(
+            r#"
+            SELECT p.key as name,
+                   array_agg(DISTINCT jsonb_typeof(p.value)) as types,
+                   bool_and(jsonb_typeof(p.value) <> 'number' OR p.value::text ~ '^-?[0-9]+$') as integral,
+                   (COUNT(*) FILTER (WHERE jsonb_typeof(p.value) <> 'null')
+                       < (SELECT COUNT(*) FROM features WHERE layer_id = $1)) as nullable
+            FROM features f, jsonb_each(f.properties) p
+            WHERE f.layer_id = $1
+            GROUP BY p.key
+            ORDER BY p.key
+            "#
+        )
+        .bind(layer_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(properties)
+    }
+
+    /// Compute the extent of a layer's features in the given SRID
+    ///
+    /// Returns `None` when the layer has no geometries.
+    pub async fn layer_extent(&self, layer_id: Uuid, srid: i32) -> DbResult<Option<BBox>> {
+        let row = sqlx::query(
+            r#"
+            SELECT ST_XMin(extent) as min_x, ST_YMin(extent) as min_y,
+                   ST_XMax(extent) as max_x, ST_YMax(extent) as max_y
+            FROM (
+                SELECT ST_Extent(ST_Transform(geometry, $2)) as extent
+                FROM features
+                WHERE layer_id = $1
+            ) e
+            "#
+        )
+        .bind(layer_id)
+        .bind(srid)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let min_x: Option<f64> = row.get("min_x");
+        let min_y: Option<f64> = row.get("min_y");
+        let max_x: Option<f64> = row.get("max_x");
+        let max_y: Option<f64> = row.get("max_y");
+
+        Ok(match (min_x, min_y, max_x, max_y) {
+            (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) => {
+                Some(BBox::new(min_x, min_y, max_x, max_y, srid))
+            }
+            _ => None,
+        })
+    }
+
     /// Batch insert features
     pub async fn batch_create(&self, features: &[Feature]) -> DbResult<usize> {
         let mut tx = self.pool.begin().await?;
@@ -353,6 +533,215 @@ impl FeatureRepository {
         tx.commit().await?;
         Ok(count)
     }
+
+    /// Apply a batch of inserts, updates and deletes in a single transaction
+    ///
+    /// Updates and deletes fail with [`DbError::Locked`] when a matched
+    /// feature is held by a lock other than `lock_id`; the whole batch is
+    /// then rolled back. Once the edits are applied, the features held by
+    /// `lock_id` are released according to `release`.
+    pub async fn apply_edits(
+        &self,
+        edits: &[FeatureEdit],
+        lock_id: Option<&str>,
+        release: ReleaseAction,
+    ) -> DbResult<EditSummary> {
+        let mut tx = DbTransaction::new(&self.pool).await?;
+
+        let result = Self::apply_edits_in(&mut tx, edits, lock_id, release).await;
+        match result {
+            Ok(summary) => {
+                tx.commit().await?;
+                Ok(summary)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn apply_edits_in(
+        conn: &mut PgConnection,
+        edits: &[FeatureEdit],
+        lock_id: Option<&str>,
+        release: ReleaseAction,
+    ) -> DbResult<EditSummary> {
+        let mut summary = EditSummary::default();
+        let mut touched = Vec::new();
+
+        for feature_edit in edits {
+            match feature_edit {
+                FeatureEdit::Insert(feature) => {
+                    let id = Uuid::new_v4();
+                    edit::insert_sql(feature, id).build().execute(&mut *conn).await?;
+                    summary.inserted.push(id);
+                }
+                FeatureEdit::Update(update) => {
+                    let ids = Self::editable_ids(conn, update.layer_id, &update.filter, lock_id).await?;
+                    if !ids.is_empty() {
+                        edit::update_sql(update, &ids).build().execute(&mut *conn).await?;
+                    }
+                    summary.updated += ids.len() as u64;
+                    touched.extend(ids);
+                }
+                FeatureEdit::Delete { layer_id, filter } => {
+                    let ids = Self::editable_ids(conn, *layer_id, filter, lock_id).await?;
+                    if !ids.is_empty() {
+                        sqlx::query("DELETE FROM features WHERE id = ANY($1)")
+                            .bind(&ids)
+                            .execute(&mut *conn)
+                            .await?;
+                    }
+                    summary.deleted += ids.len() as u64;
+                }
+            }
+        }
+
+        if let Some(lock_id) = lock_id {
+            match release {
+                ReleaseAction::All => {
+                    sqlx::query("DELETE FROM feature_locks WHERE lock_id = $1")
+                        .bind(lock_id)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+                ReleaseAction::Some => {
+                    sqlx::query("DELETE FROM feature_locks WHERE lock_id = $1 AND feature_id = ANY($2)")
+                        .bind(lock_id)
+                        .bind(&touched)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Row-lock the features matched by a filter, failing if any is held by another lock
+    async fn editable_ids(
+        conn: &mut PgConnection,
+        layer_id: Uuid,
+        filter: &FeatureFilter,
+        lock_id: Option<&str>,
+    ) -> DbResult<Vec<Uuid>> {
+        let ids: Vec<Uuid> = edit::matching_ids_sql(layer_id, filter)
+            .build_query_scalar()
+            .fetch_all(&mut *conn)
+            .await?;
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+
+        let held: Vec<Uuid> = edit::conflicting_locks_sql(&ids, lock_id)
+            .build_query_scalar()
+            .fetch_all(&mut *conn)
+            .await?;
+        match held.first() {
+            Some(id) => Err(DbError::Locked(format!("feature {} is locked", id))),
+            None => Ok(ids),
+        }
+    }
+
+    /// Lock the features matching a filter in each of the given layers
+    ///
+    /// Expired locks are purged first. With [`LockAction::All`] the request
+    /// fails with [`DbError::Locked`] if any matching feature is held by
+    /// another lock; with [`LockAction::Some`] such features are reported in
+    /// [`FeatureLock::not_locked`] instead.
+    pub async fn lock_features(
+        &self,
+        targets: &[(Uuid, FeatureFilter)],
+        expiry_secs: u64,
+        action: LockAction,
+    ) -> DbResult<FeatureLock> {
+        let mut tx = DbTransaction::new(&self.pool).await?;
+
+        let result = Self::lock_features_in(&mut tx, targets, expiry_secs, action).await;
+        match result {
+            Ok(lock) => {
+                tx.commit().await?;
+                Ok(lock)
+            }
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn lock_features_in(
+        conn: &mut PgConnection,
+        targets: &[(Uuid, FeatureFilter)],
+        expiry_secs: u64,
+        action: LockAction,
+    ) -> DbResult<FeatureLock> {
+        sqlx::query("DELETE FROM feature_locks WHERE expires_at <= NOW()")
+            .execute(&mut *conn)
+            .await?;
+
+        let lock_id = Uuid::new_v4().simple().to_string();
+        let mut locked = Vec::new();
+        let mut not_locked = Vec::new();
+
+        for (layer_id, filter) in targets {
+            let ids: Vec<Uuid> = edit::matching_ids_sql(*layer_id, filter)
+                .build_query_scalar()
+                .fetch_all(&mut *conn)
+                .await?;
+            if ids.is_empty() {
+                continue;
+            }
+
+            let held: Vec<Uuid> = edit::conflicting_locks_sql(&ids, None)
+                .build_query_scalar()
+                .fetch_all(&mut *conn)
+                .await?;
+            for id in ids {
+                let entry = (*layer_id, id);
+                if held.contains(&id) {
+                    not_locked.push(entry);
+                } else if !locked.contains(&entry) {
+                    locked.push(entry);
+                }
+            }
+        }
+
+        if action == LockAction::All && !not_locked.is_empty() {
+            return Err(DbError::Locked(format!(
+                "{} of the requested features are locked",
+                not_locked.len()
+            )));
+        }
+
+        // Expiry follows the database clock that purges expired locks; it is
+        // returned even when nothing was locked
+        let ids: Vec<Uuid> = locked.iter().map(|(_, id)| *id).collect();
+        let expires_at: DateTime<Utc> = sqlx::query_scalar(
+            r#"
+            WITH expiry AS (
+                SELECT NOW() + make_interval(secs => $3) AS expires_at
+            ), inserted AS (
+                INSERT INTO feature_locks (feature_id, lock_id, expires_at)
+                SELECT unnest($1::uuid[]), $2, expires_at FROM expiry
+            )
+            SELECT expires_at FROM expiry
+            "#
+        )
+        .bind(&ids)
+        .bind(&lock_id)
+        .bind(expiry_secs as f64)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(FeatureLock {
+            lock_id,
+            expires_at,
+            locked,
+            not_locked,
+        })
+    }
 }
 
 #[async_trait]
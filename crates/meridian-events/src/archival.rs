@@ -123,14 +123,23 @@ impl ArchivalDestination for FileArchive {
             return Ok(());
         }
 
-        let stream_id = &events[0].metadata.stream_id;
-        let path = self.archive_path(stream_id);
+        let stream_id = events[0].metadata.stream_id.clone();
+        let path = self.archive_path(&stream_id);
 
         // Create parent directory if it doesn't exist
         if let Some(parent) = path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
 
+        // Keep events archived earlier
+        let events = if tokio::fs::try_exists(&path).await? {
+            let mut archived = self.retrieve(&stream_id).await?;
+            archived.extend(events);
+            archived
+        } else {
+            events
+        };
+
         // Serialize events
         let serialized = serde_json::to_vec(&events)
             .map_err(|e| EventError::Serialization(e.to_string()))?;
@@ -289,7 +298,7 @@ where
         }
     }
 
-    /// Compact a stream by removing events before the last snapshot.
+    /// Compact a stream by removing the events covered by its snapshot.
     pub async fn compact_stream<A>(
         &self,
         stream_id: &StreamId,
@@ -304,18 +313,24 @@ where
         let event_count = events.len();
         let last_snapshot_version = snapshot.as_ref().map(|s| s.version());
 
-        if !self.strategy.should_compact(event_count, last_snapshot_version) {
-            return Ok(CompactionResult {
-                events_removed: 0,
-                events_retained: event_count,
-            });
-        }
+        let snapshot_version = match last_snapshot_version {
+            Some(version) if self.strategy.should_compact(event_count, last_snapshot_version) => version,
+            _ => {
+                return Ok(CompactionResult {
+                    events_removed: 0,
+                    events_retained: event_count,
+                })
+            }
+        };
+
+        let events_removed = self
+            .event_store
+            .truncate_stream(stream_id, snapshot_version + 1)
+            .await?;
 
-        // In a real implementation, you would remove events before the snapshot
-        // This is a placeholder since our event store doesn't support deletion
         Ok(CompactionResult {
-            events_removed: 0,
-            events_retained: event_count,
+            events_removed,
+            events_retained: event_count - events_removed,
         })
     }
 }
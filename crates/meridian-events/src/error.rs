@@ -113,6 +113,11 @@ pub enum EventError {
     #[error("Database error: {0}")]
     Database(#[from] rocksdb::Error),
 
+    /// PostgreSQL error
+    #[cfg(feature = "postgres-backend")]
+    #[error("PostgreSQL error: {0}")]
+    Postgres(#[from] sqlx::Error),
+
     /// JSON error
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
@@ -184,6 +189,16 @@ impl From<Box<bincode::ErrorKind>> for EventError {
     }
 }
 
+#[cfg(feature = "postgres-backend")]
+impl From<meridian_db::DbError> for EventError {
+    fn from(err: meridian_db::DbError) -> Self {
+        match err {
+            meridian_db::DbError::SqlError(e) => EventError::Postgres(e),
+            other => EventError::Store(other.to_string()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
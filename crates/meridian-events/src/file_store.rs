@@ -0,0 +1,1276 @@
+//! Durable file-based event store on a segmented append-only log.
+//!
+//! Events are appended as checksummed records to segment files named after
+//! the global position of their first event:
+//!
+//! ```text
+//! <dir>/00000000000000000000.segment
+//! <dir>/00000000000000052113.segment
+//!
+//! ┌──────────────┬──────────────┬────────────────┬─────────────────┐
+//! │ magic (8 B)  │ length (u32) │ checksum (u64) │ bincode entry   │ ...
+//! └──────────────┴──────────────┴────────────────┴─────────────────┘
+//! ```
+//!
+//! The checksum is the first eight bytes of the BLAKE3 hash of the entry.
+//! All events of one append are written at once and only the last one
+//! commits the batch, so recovery truncates a torn record together with the
+//! rest of its batch. The per-stream and global-position indexes are rebuilt
+//! from the segments on open.
+//!
+//! Compaction rewrites sealed segments without truncated events and events
+//! of deleted streams, optionally handing them to an [`ArchivalDestination`]
+//! first. Global positions never change, so subscribers keep their place.
+
+use crate::archival::{ArchivalDestination, CompactionResult};
+use crate::error::{EventError, Result};
+use crate::event::{EventEnvelope, EventMetadata, StoredEvent, StreamId};
+use crate::store::{AppendResult, EventStore, ExpectedVersion, ReadDirection, ReadOptions};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+
+/// Magic bytes at the start of every segment file.
+const SEGMENT_MAGIC: &[u8; 8] = b"MRDNLOG1";
+
+/// Extension of segment files.
+const SEGMENT_EXTENSION: &str = "segment";
+
+/// Length and checksum preceding each record.
+const RECORD_HEADER_SIZE: u64 = 12;
+
+/// Largest record accepted; larger lengths are treated as corruption.
+const MAX_RECORD_SIZE: u32 = 64 * 1024 * 1024;
+
+/// When appended events are flushed to stable storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Sync every append before acknowledging it
+    Always,
+    /// Sync on append once this much time passed since the last sync, and
+    /// from a background thread when appends stop
+    Interval(Duration),
+    /// Leave flushing to the operating system
+    Never,
+}
+
+/// Configuration for the file event store.
+#[derive(Debug, Clone)]
+pub struct FileStoreConfig {
+    /// Size in bytes after which a new segment is started
+    pub segment_size: u64,
+    /// Fsync policy for appends
+    pub fsync: FsyncPolicy,
+}
+
+impl Default for FileStoreConfig {
+    fn default() -> Self {
+        Self {
+            segment_size: 64 * 1024 * 1024,
+            fsync: FsyncPolicy::Always,
+        }
+    }
+}
+
+/// Entry of the log.
+#[derive(Debug, Serialize, Deserialize)]
+enum LogEntry {
+    /// Event with its JSON metadata; `commit` marks the last event of an append
+    Event {
+        position: u64,
+        commit: bool,
+        metadata: Vec<u8>,
+        payload: Vec<u8>,
+    },
+    /// Soft deletion of a stream
+    DeleteStream { stream_id: String, version: u64 },
+    /// Removal of stream events below a version
+    TruncateStream { stream_id: String, before: u64 },
+}
+
+impl LogEntry {
+    fn event(position: u64, commit: bool, event: &StoredEvent) -> Result<Self> {
+        Ok(LogEntry::Event {
+            position,
+            commit,
+            metadata: serde_json::to_vec(&event.metadata)?,
+            payload: event.payload.clone(),
+        })
+    }
+
+    /// Append the framed record to a buffer, returning its length.
+    fn encode(&self, out: &mut Vec<u8>) -> Result<u64> {
+        let body = bincode::serialize(self)?;
+        let length = u32::try_from(body.len())
+            .ok()
+            .filter(|&len| len <= MAX_RECORD_SIZE)
+            .ok_or_else(|| EventError::store(format!("Record of {} bytes is too large", body.len())))?;
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&checksum(&body).to_le_bytes());
+        out.extend_from_slice(&body);
+        Ok(RECORD_HEADER_SIZE + u64::from(length))
+    }
+}
+
+fn checksum(body: &[u8]) -> u64 {
+    let hash = blake3::hash(body);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn decode_event(metadata: &[u8], payload: Vec<u8>) -> Result<StoredEvent> {
+    let metadata: EventMetadata = serde_json::from_slice(metadata)
+        .map_err(|e| EventError::Deserialization(e.to_string()))?;
+    Ok(StoredEvent::new(metadata, payload))
+}
+
+/// Outcome of reading the next record of a segment.
+enum ReadOutcome {
+    /// Complete record with a valid checksum and its framed length
+    Record(LogEntry, u64),
+    /// Clean end of the segment
+    End,
+    /// Incomplete or damaged record
+    Torn(String),
+}
+
+/// Read into `buf` until it is full or the reader is exhausted.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_record(reader: &mut impl Read) -> Result<ReadOutcome> {
+    let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+    match read_full(reader, &mut header)? {
+        0 => return Ok(ReadOutcome::End),
+        n if n < header.len() => return Ok(ReadOutcome::Torn("incomplete record header".to_string())),
+        _ => {}
+    }
+
+    let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    if length > MAX_RECORD_SIZE {
+        return Ok(ReadOutcome::Torn(format!("record length {} out of range", length)));
+    }
+    let mut expected = [0u8; 8];
+    expected.copy_from_slice(&header[4..]);
+
+    let mut body = vec![0u8; length as usize];
+    if read_full(reader, &mut body)? < body.len() {
+        return Ok(ReadOutcome::Torn("incomplete record body".to_string()));
+    }
+    if checksum(&body) != u64::from_le_bytes(expected) {
+        return Ok(ReadOutcome::Torn("checksum mismatch".to_string()));
+    }
+
+    Ok(match bincode::deserialize(&body) {
+        Ok(entry) => ReadOutcome::Record(entry, RECORD_HEADER_SIZE + u64::from(length)),
+        Err(e) => ReadOutcome::Torn(format!("undecodable record: {}", e)),
+    })
+}
+
+fn segment_path(dir: &Path, base: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", base, SEGMENT_EXTENSION))
+}
+
+/// Make renames and new files in a directory durable.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Location of an event record.
+#[derive(Debug, Clone, Copy)]
+struct Location {
+    segment: u64,
+    offset: u64,
+}
+
+/// Index of one stream.
+#[derive(Debug, Default)]
+struct StreamIndex {
+    /// Global position of each retained event by sequence
+    positions: BTreeMap<u64, u64>,
+    version: u64,
+    deleted: bool,
+}
+
+/// Event of a batch not yet known to be committed during recovery.
+struct PendingEvent {
+    position: u64,
+    stream_id: StreamId,
+    sequence: u64,
+    location: Location,
+}
+
+/// Per-stream and global-position indexes of a log.
+#[derive(Default)]
+struct Indexes {
+    streams: HashMap<StreamId, StreamIndex>,
+    global: BTreeMap<u64, Location>,
+    next_position: u64,
+}
+
+impl Indexes {
+    fn index_event(&mut self, event: PendingEvent) {
+        let stream = self.streams.entry(event.stream_id).or_default();
+        stream.positions.insert(event.sequence, event.position);
+        stream.version = stream.version.max(event.sequence);
+        self.global.insert(event.position, event.location);
+        self.next_position = self.next_position.max(event.position + 1);
+    }
+
+    fn delete_stream(&mut self, stream_id: StreamId, version: u64) {
+        let stream = self.streams.entry(stream_id).or_default();
+        stream.version = stream.version.max(version);
+        stream.deleted = true;
+    }
+
+    fn truncate_stream(&mut self, stream_id: StreamId, before: u64) -> usize {
+        let stream = self.streams.entry(stream_id).or_default();
+        let retained = stream.positions.split_off(&before);
+        let removed = std::mem::replace(&mut stream.positions, retained);
+        stream.version = stream.version.max(before.saturating_sub(1));
+        for position in removed.values() {
+            self.global.remove(position);
+        }
+        removed.len()
+    }
+
+    fn apply_control(&mut self, entry: LogEntry) {
+        match entry {
+            LogEntry::DeleteStream { stream_id, version } => {
+                self.delete_stream(StreamId::new(stream_id), version);
+            }
+            LogEntry::TruncateStream { stream_id, before } => {
+                self.truncate_stream(StreamId::new(stream_id), before);
+            }
+            LogEntry::Event { .. } => unreachable!("events are indexed in batches"),
+        }
+    }
+
+    fn stream(&self, stream_id: &StreamId) -> Result<&StreamIndex> {
+        self.streams
+            .get(stream_id)
+            .filter(|s| !s.deleted)
+            .ok_or_else(|| EventError::StreamNotFound(stream_id.to_string()))
+    }
+}
+
+/// Segments and indexes of an open log.
+struct LogState {
+    /// Valid size of each segment by base position; the last one is active
+    segments: BTreeMap<u64, u64>,
+    /// Append handle of the active segment
+    active: File,
+    index: Indexes,
+    last_sync: Instant,
+    unsynced: bool,
+}
+
+impl LogState {
+    fn active_base(&self) -> u64 {
+        *self.segments.keys().next_back().expect("log has an active segment")
+    }
+}
+
+/// Reader of event records, keeping one handle per segment.
+struct SegmentReader<'a> {
+    dir: &'a Path,
+    files: HashMap<u64, File>,
+}
+
+impl<'a> SegmentReader<'a> {
+    fn new(dir: &'a Path) -> Self {
+        Self {
+            dir,
+            files: HashMap::new(),
+        }
+    }
+
+    fn read(&mut self, location: Location) -> Result<StoredEvent> {
+        let file = match self.files.entry(location.segment) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(File::open(segment_path(self.dir, location.segment))?),
+        };
+        file.seek(SeekFrom::Start(location.offset))?;
+        match read_record(file)? {
+            ReadOutcome::Record(LogEntry::Event { metadata, payload, .. }, _) => {
+                decode_event(&metadata, payload)
+            }
+            _ => Err(EventError::store(format!(
+                "No event record in segment {} at offset {}",
+                location.segment, location.offset
+            ))),
+        }
+    }
+}
+
+/// Durable event store on a segmented append-only log.
+///
+/// Appends, reads and index updates go through one lock and file I/O is
+/// synchronous, as in the other stores. Appends are announced through
+/// [`EventStore::watch_head`] so subscriptions can tail the log.
+pub struct FileEventStore {
+    dir: PathBuf,
+    config: FileStoreConfig,
+    state: Arc<RwLock<LogState>>,
+    head: watch::Sender<u64>,
+    compaction: Mutex<()>,
+}
+
+impl FileEventStore {
+    /// Open or create a store in a directory with the default configuration.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with_config(path, FileStoreConfig::default())
+    }
+
+    /// Open or create a store in a directory, recovering from torn writes.
+    pub fn open_with_config(path: impl AsRef<Path>, config: FileStoreConfig) -> Result<Self> {
+        if config.segment_size == 0 {
+            return Err(EventError::Configuration(
+                "Segment size must be positive".to_string(),
+            ));
+        }
+        let dir = path.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut bases = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                Some(SEGMENT_EXTENSION) => {
+                    let base = path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .and_then(|s| s.parse::<u64>().ok())
+                        .ok_or_else(|| {
+                            EventError::store(format!("Unexpected segment file {}", path.display()))
+                        })?;
+                    bases.push(base);
+                }
+                // Left behind by an interrupted compaction
+                Some("compacting") => fs::remove_file(&path)?,
+                _ => {}
+            }
+        }
+        bases.sort_unstable();
+        if bases.is_empty() {
+            create_segment(&dir, 0)?;
+            bases.push(0);
+        }
+
+        let mut index = Indexes::default();
+        let mut segments = BTreeMap::new();
+        for (i, &base) in bases.iter().enumerate() {
+            let size = recover_segment(&dir, base, i + 1 == bases.len(), &mut index)?;
+            segments.insert(base, size);
+        }
+
+        let active_base = *bases.last().expect("at least one segment");
+        let active = OpenOptions::new()
+            .append(true)
+            .open(segment_path(&dir, active_base))?;
+        index.next_position = index.next_position.max(active_base);
+
+        tracing::debug!(
+            "Opened event log {} with {} segments, {} events, next position {}",
+            dir.display(),
+            segments.len(),
+            index.global.len(),
+            index.next_position
+        );
+
+        let state = Arc::new(RwLock::new(LogState {
+            segments,
+            active,
+            index,
+            last_sync: Instant::now(),
+            unsynced: false,
+        }));
+        if let FsyncPolicy::Interval(interval) = config.fsync {
+            spawn_flusher(&dir, Arc::downgrade(&state), interval)?;
+        }
+
+        let (head, _) = watch::channel(state.read().index.next_position);
+        Ok(Self {
+            dir,
+            config,
+            state,
+            head,
+            compaction: Mutex::new(()),
+        })
+    }
+
+    /// Directory holding the segments.
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Global position the next appended event will get.
+    pub fn head_position(&self) -> u64 {
+        self.state.read().index.next_position
+    }
+
+    /// Number of segment files.
+    pub fn segment_count(&self) -> usize {
+        self.state.read().segments.len()
+    }
+
+    /// Flush appended events to stable storage.
+    pub fn sync(&self) -> Result<()> {
+        let mut state = self.state.write();
+        state.active.sync_data()?;
+        state.last_sync = Instant::now();
+        state.unsynced = false;
+        Ok(())
+    }
+
+    /// Write framed records to the active segment.
+    fn write_records(&self, state: &mut LogState, buffer: &[u8]) -> Result<()> {
+        let base = state.active_base();
+        let size = state.segments[&base];
+        if let Err(e) = state.active.write_all(buffer) {
+            // Do not leave a partial batch behind for later appends
+            state.active.set_len(size)?;
+            return Err(e.into());
+        }
+        let size = size + buffer.len() as u64;
+        state.segments.insert(base, size);
+        state.unsynced = true;
+
+        let sync = match self.config.fsync {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Interval(interval) => state.last_sync.elapsed() >= interval,
+            FsyncPolicy::Never => false,
+        };
+        if sync {
+            state.active.sync_data()?;
+            state.last_sync = Instant::now();
+            state.unsynced = false;
+        }
+        Ok(())
+    }
+
+    /// Seal the active segment once it grew past the segment size.
+    fn roll_if_full(&self, state: &mut LogState) -> Result<()> {
+        let base = state.active_base();
+        let size = state.segments[&base];
+        // A segment is named after its first event, so it needs one
+        let next = state.index.next_position;
+        if size >= self.config.segment_size && next > base {
+            state.active.sync_data()?;
+            create_segment(&self.dir, next)?;
+            state.active = OpenOptions::new()
+                .append(true)
+                .open(segment_path(&self.dir, next))?;
+            state.segments.insert(next, SEGMENT_MAGIC.len() as u64);
+            state.last_sync = Instant::now();
+            state.unsynced = false;
+        }
+        Ok(())
+    }
+
+    /// Remove truncated events and events of deleted streams from sealed
+    /// segments, archiving them first when a destination is given.
+    ///
+    /// The active segment is left alone; events in it are reclaimed once
+    /// it has been sealed.
+    pub async fn compact(
+        &self,
+        archive: Option<&dyn ArchivalDestination>,
+    ) -> Result<CompactionResult> {
+        let _guard = self.compaction.lock().await;
+
+        // Collect the events to drop from each sealed segment
+        let removable = {
+            let state = self.state.read();
+            let active = state.active_base();
+            let mut removable: BTreeMap<u64, Vec<(u64, StoredEvent)>> = BTreeMap::new();
+            for &base in state.segments.keys().filter(|&&base| base != active) {
+                let events = scan_segment(&self.dir, base)?
+                    .into_iter()
+                    .filter_map(|(_, entry)| match entry {
+                        LogEntry::Event {
+                            position,
+                            metadata,
+                            payload,
+                            ..
+                        } => Some((position, metadata, payload)),
+                        _ => None,
+                    });
+                for (position, metadata, payload) in events {
+                    let event = decode_event(&metadata, payload)?;
+                    let deleted = state
+                        .index
+                        .streams
+                        .get(&event.metadata.stream_id)
+                        .is_none_or(|s| s.deleted);
+                    if deleted || !state.index.global.contains_key(&position) {
+                        removable.entry(base).or_default().push((position, event));
+                    }
+                }
+            }
+            removable
+        };
+
+        if let Some(destination) = archive {
+            let mut by_stream: HashMap<StreamId, Vec<StoredEvent>> = HashMap::new();
+            for (_, event) in removable.values().flatten() {
+                by_stream
+                    .entry(event.metadata.stream_id.clone())
+                    .or_default()
+                    .push(event.clone());
+            }
+            for (_, mut events) in by_stream {
+                events.sort_by_key(|e| e.metadata.sequence);
+                destination.archive(events).await?;
+            }
+        }
+
+        let mut events_removed = 0;
+        let mut state = self.state.write();
+        for (base, events) in removable {
+            let positions: HashSet<u64> = events.iter().map(|(position, _)| *position).collect();
+            events_removed += positions.len();
+            self.rewrite_segment(&mut state, base, &positions)?;
+
+            for (position, event) in events {
+                state.index.global.remove(&position);
+                if let Some(stream) = state.index.streams.get_mut(&event.metadata.stream_id) {
+                    stream.positions.remove(&event.metadata.sequence);
+                }
+            }
+        }
+        sync_dir(&self.dir)?;
+
+        tracing::debug!(
+            "Compacted event log {}: {} events removed",
+            self.dir.display(),
+            events_removed
+        );
+
+        Ok(CompactionResult {
+            events_removed,
+            events_retained: state.index.global.len(),
+        })
+    }
+
+    /// Rewrite a sealed segment without the given event positions.
+    fn rewrite_segment(&self, state: &mut LogState, base: u64, drop: &HashSet<u64>) -> Result<()> {
+        let mut buffer = SEGMENT_MAGIC.to_vec();
+        let mut locations = Vec::new();
+        let mut records = 0;
+        for (_, entry) in scan_segment(&self.dir, base)? {
+            if let LogEntry::Event { position, .. } = &entry {
+                if drop.contains(position) {
+                    continue;
+                }
+                locations.push((
+                    *position,
+                    Location {
+                        segment: base,
+                        offset: buffer.len() as u64,
+                    },
+                ));
+            }
+            // Sealed segments hold committed batches only, so each event
+            // is its own batch once others of its batch may be dropped
+            let entry = match entry {
+                LogEntry::Event {
+                    position,
+                    metadata,
+                    payload,
+                    ..
+                } => LogEntry::Event {
+                    position,
+                    commit: true,
+                    metadata,
+                    payload,
+                },
+                other => other,
+            };
+            entry.encode(&mut buffer)?;
+            records += 1;
+        }
+
+        let path = segment_path(&self.dir, base);
+        if records == 0 {
+            fs::remove_file(&path)?;
+            state.segments.remove(&base);
+        } else {
+            let temporary = path.with_extension("compacting");
+            let mut file = File::create(&temporary)?;
+            file.write_all(&buffer)?;
+            file.sync_all()?;
+            fs::rename(&temporary, &path)?;
+            state.segments.insert(base, buffer.len() as u64);
+        }
+
+        for (position, location) in locations {
+            if let Some(current) = state.index.global.get_mut(&position) {
+                *current = location;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_positions(&self, index: &Indexes, positions: &[u64]) -> Result<Vec<StoredEvent>> {
+        let mut reader = SegmentReader::new(&self.dir);
+        positions
+            .iter()
+            .map(|position| reader.read(index.global[position]))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EventStore for FileEventStore {
+    async fn append(
+        &self,
+        stream_id: &StreamId,
+        expected_version: ExpectedVersion,
+        events: Vec<EventEnvelope>,
+    ) -> Result<AppendResult> {
+        if events.is_empty() {
+            return Ok(AppendResult {
+                stream_id: stream_id.clone(),
+                new_version: self.get_version(stream_id).await?.unwrap_or(0),
+                events_appended: 0,
+            });
+        }
+
+        let mut state = self.state.write();
+        let current_version = match state.index.streams.get(stream_id) {
+            Some(stream) if stream.deleted => {
+                return Err(EventError::StreamNotFound(stream_id.to_string()));
+            }
+            stream => stream.map(|s| s.version),
+        };
+        if !expected_version.matches(current_version) {
+            return Err(expected_version.conflict(current_version));
+        }
+
+        // Frame the whole batch so it reaches the log in one write
+        let segment = state.active_base();
+        let mut offset = state.segments[&segment];
+        let first_position = state.index.next_position;
+        let first_sequence = current_version.unwrap_or(0) + 1;
+        let events_appended = events.len();
+        let mut buffer = Vec::new();
+        let mut batch = Vec::with_capacity(events_appended);
+
+        for (i, envelope) in events.into_iter().enumerate() {
+            let payload = serde_json::to_vec(&envelope.payload)
+                .map_err(|e| EventError::Serialization(e.to_string()))?;
+
+            let position = first_position + i as u64;
+            let sequence = first_sequence + i as u64;
+            let mut metadata = envelope.metadata;
+            metadata.sequence = sequence;
+            // Recovery indexes events by the stream in their metadata
+            metadata.stream_id = stream_id.clone();
+
+            let event = StoredEvent::new(metadata, payload);
+            let commit = i + 1 == events_appended;
+            let length = LogEntry::event(position, commit, &event)?.encode(&mut buffer)?;
+            batch.push(PendingEvent {
+                position,
+                stream_id: stream_id.clone(),
+                sequence,
+                location: Location { segment, offset },
+            });
+            offset += length;
+        }
+
+        self.write_records(&mut state, &buffer)?;
+        for event in batch {
+            state.index.index_event(event);
+        }
+        self.roll_if_full(&mut state)?;
+
+        let head = state.index.next_position;
+        drop(state);
+        self.head.send_replace(head);
+
+        Ok(AppendResult {
+            stream_id: stream_id.clone(),
+            new_version: first_sequence + events_appended as u64 - 1,
+            events_appended,
+        })
+    }
+
+    async fn read(
+        &self,
+        stream_id: &StreamId,
+        options: ReadOptions,
+    ) -> Result<Vec<StoredEvent>> {
+        let state = self.state.read();
+        let stream = state.index.stream(stream_id)?;
+        let max_count = options.max_count.unwrap_or(usize::MAX);
+
+        let positions: Vec<u64> = match options.direction {
+            ReadDirection::Forward => stream
+                .positions
+                .range(options.from_version..)
+                .map(|(_, &position)| position)
+                .take(max_count)
+                .collect(),
+            ReadDirection::Backward => stream
+                .positions
+                .range(..=options.from_version)
+                .rev()
+                .map(|(_, &position)| position)
+                .take(max_count)
+                .collect(),
+        };
+
+        self.read_positions(&state.index, &positions)
+    }
+
+    async fn get_version(&self, stream_id: &StreamId) -> Result<Option<u64>> {
+        Ok(self.state.read().index.streams.get(stream_id).map(|s| s.version))
+    }
+
+    async fn stream_exists(&self, stream_id: &StreamId) -> Result<bool> {
+        Ok(self.state.read().index.stream(stream_id).is_ok())
+    }
+
+    async fn delete_stream(&self, stream_id: &StreamId) -> Result<()> {
+        let mut state = self.state.write();
+        let Ok(version) = state.index.stream(stream_id).map(|s| s.version) else {
+            return Ok(());
+        };
+
+        let mut buffer = Vec::new();
+        LogEntry::DeleteStream {
+            stream_id: stream_id.to_string(),
+            version,
+        }
+        .encode(&mut buffer)?;
+        self.write_records(&mut state, &buffer)?;
+        state.index.delete_stream(stream_id.clone(), version);
+        Ok(())
+    }
+
+    async fn list_streams(&self) -> Result<Vec<StreamId>> {
+        let state = self.state.read();
+        Ok(state
+            .index
+            .streams
+            .iter()
+            .filter(|(_, stream)| !stream.deleted)
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    async fn read_all(&self, from_position: u64, max_count: usize) -> Result<Vec<StoredEvent>> {
+        let events = self.read_all_positioned(from_position, max_count).await?;
+        Ok(events.into_iter().map(|(_, event)| event).collect())
+    }
+
+    async fn read_all_positioned(
+        &self,
+        from_position: u64,
+        max_count: usize,
+    ) -> Result<Vec<(u64, StoredEvent)>> {
+        let state = self.state.read();
+        let positions: Vec<u64> = state
+            .index
+            .global
+            .range(from_position..)
+            .map(|(&position, _)| position)
+            .take(max_count)
+            .collect();
+
+        let events = self.read_positions(&state.index, &positions)?;
+        Ok(positions.into_iter().zip(events).collect())
+    }
+
+    async fn truncate_stream(&self, stream_id: &StreamId, before_version: u64) -> Result<usize> {
+        let mut state = self.state.write();
+        let stream = state.index.stream(stream_id)?;
+        let before = before_version.min(stream.version + 1);
+        if stream.positions.range(..before).next().is_none() {
+            return Ok(0);
+        }
+
+        let mut buffer = Vec::new();
+        LogEntry::TruncateStream {
+            stream_id: stream_id.to_string(),
+            before,
+        }
+        .encode(&mut buffer)?;
+        self.write_records(&mut state, &buffer)?;
+        Ok(state.index.truncate_stream(stream_id.clone(), before))
+    }
+
+    fn watch_head(&self) -> Option<watch::Receiver<u64>> {
+        Some(self.head.subscribe())
+    }
+}
+
+impl Drop for FileEventStore {
+    fn drop(&mut self) {
+        let state = self.state.write();
+        if state.unsynced {
+            if let Err(e) = state.active.sync_data() {
+                tracing::warn!("Failed to sync event log {}: {}", self.dir.display(), e);
+            }
+        }
+    }
+}
+
+/// Sync appends left unsynced for an interval, until the store is dropped.
+fn spawn_flusher(dir: &Path, state: Weak<RwLock<LogState>>, interval: Duration) -> Result<()> {
+    let dir = dir.to_path_buf();
+    std::thread::Builder::new()
+        .name("event-log-flush".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(interval);
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            let mut state = state.write();
+            if state.unsynced && state.last_sync.elapsed() >= interval {
+                match state.active.sync_data() {
+                    Ok(()) => {
+                        state.last_sync = Instant::now();
+                        state.unsynced = false;
+                    }
+                    Err(e) => tracing::warn!("Failed to sync event log {}: {}", dir.display(), e),
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// Create an empty segment file.
+fn create_segment(dir: &Path, base: u64) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(segment_path(dir, base))?;
+    file.write_all(SEGMENT_MAGIC)?;
+    file.sync_all()?;
+    sync_dir(dir)
+}
+
+/// All complete records of a sealed segment with their offsets.
+fn scan_segment(dir: &Path, base: u64) -> Result<Vec<(u64, LogEntry)>> {
+    let path = segment_path(dir, base);
+    let mut reader = BufReader::new(File::open(&path)?);
+    let mut magic = [0u8; 8];
+    if read_full(&mut reader, &mut magic)? < magic.len() || &magic != SEGMENT_MAGIC {
+        return Err(EventError::store(format!("{} is not a segment", path.display())));
+    }
+
+    let mut offset = SEGMENT_MAGIC.len() as u64;
+    let mut entries = Vec::new();
+    loop {
+        match read_record(&mut reader)? {
+            ReadOutcome::Record(entry, length) => {
+                entries.push((offset, entry));
+                offset += length;
+            }
+            ReadOutcome::End => return Ok(entries),
+            ReadOutcome::Torn(reason) => {
+                return Err(EventError::store(format!(
+                    "Corrupt record in {} at offset {}: {}",
+                    path.display(),
+                    offset,
+                    reason
+                )))
+            }
+        }
+    }
+}
+
+/// Index a segment, truncating torn or uncommitted records at the tail of
+/// the active segment, and return its valid size.
+fn recover_segment(dir: &Path, base: u64, active: bool, index: &mut Indexes) -> Result<u64> {
+    let path = segment_path(dir, base);
+    let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+    let length = file.metadata()?.len();
+    let mut reader = BufReader::new(&file);
+
+    let mut magic = [0u8; 8];
+    let read = read_full(&mut reader, &mut magic)?;
+    if read < magic.len() && active && SEGMENT_MAGIC.starts_with(&magic[..read]) {
+        // Crashed while creating the segment
+        drop(reader);
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(SEGMENT_MAGIC)?;
+        file.sync_all()?;
+        return Ok(SEGMENT_MAGIC.len() as u64);
+    }
+    if read < magic.len() || &magic != SEGMENT_MAGIC {
+        return Err(EventError::store(format!("{} is not a segment", path.display())));
+    }
+
+    let mut offset = SEGMENT_MAGIC.len() as u64;
+    let mut committed = offset;
+    let mut pending = Vec::new();
+    let mut damage = None;
+
+    loop {
+        match read_record(&mut reader)? {
+            ReadOutcome::Record(LogEntry::Event { position, commit, metadata, .. }, size) => {
+                let metadata: EventMetadata = match serde_json::from_slice(&metadata) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        damage = Some(format!("undecodable event metadata: {}", e));
+                        break;
+                    }
+                };
+                pending.push(PendingEvent {
+                    position,
+                    stream_id: metadata.stream_id,
+                    sequence: metadata.sequence,
+                    location: Location { segment: base, offset },
+                });
+                offset += size;
+                if commit {
+                    for event in pending.drain(..) {
+                        index.index_event(event);
+                    }
+                    committed = offset;
+                }
+            }
+            ReadOutcome::Record(entry, size) => {
+                if !pending.is_empty() {
+                    damage = Some("control record inside an uncommitted batch".to_string());
+                    break;
+                }
+                index.apply_control(entry);
+                offset += size;
+                committed = offset;
+            }
+            ReadOutcome::End => break,
+            ReadOutcome::Torn(reason) => {
+                damage = Some(reason);
+                break;
+            }
+        }
+    }
+    drop(reader);
+
+    if committed < length {
+        let reason = damage.unwrap_or_else(|| "uncommitted batch".to_string());
+        if !active {
+            return Err(EventError::store(format!(
+                "Corrupt record in {} at offset {}: {}",
+                path.display(),
+                committed,
+                reason
+            )));
+        }
+        tracing::warn!(
+            "Truncating {} bytes after offset {} of {}: {}",
+            length - committed,
+            committed,
+            path.display(),
+            reason
+        );
+        file.set_len(committed)?;
+        file.sync_all()?;
+    }
+
+    Ok(committed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archival::FileArchive;
+    use crate::event::EventVersion;
+    use crate::projection::{CatchUpSubscription, Projection, ProjectionBuilder};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn events(stream_id: &StreamId, count: usize) -> Vec<EventEnvelope> {
+        (0..count)
+            .map(|i| {
+                EventEnvelope::new(
+                    EventMetadata::new(
+                        stream_id.clone(),
+                        0,
+                        EventVersion::new("TestEvent", semver::Version::new(1, 0, 0)),
+                    ),
+                    serde_json::json!({ "index": i }),
+                )
+            })
+            .collect()
+    }
+
+    fn last_segment(dir: &Path) -> PathBuf {
+        let mut segments: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some(SEGMENT_EXTENSION))
+            .collect();
+        segments.sort();
+        segments.pop().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_append_read_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let orders = StreamId::new("order-1");
+        let users = StreamId::new("user-1");
+
+        {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store
+                .append(&orders, ExpectedVersion::NoStream, events(&orders, 3))
+                .await
+                .unwrap();
+            store
+                .append(&users, ExpectedVersion::NoStream, events(&users, 2))
+                .await
+                .unwrap();
+            store
+                .append(&orders, ExpectedVersion::Exact(3), events(&orders, 1))
+                .await
+                .unwrap();
+
+            let result = store
+                .append(&orders, ExpectedVersion::Exact(3), events(&orders, 1))
+                .await;
+            assert!(matches!(result, Err(EventError::ConcurrencyConflict { .. })));
+        }
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert_eq!(store.head_position(), 6);
+        assert_eq!(store.get_version(&orders).await.unwrap(), Some(4));
+
+        let read = store.read(&orders, ReadOptions::default()).await.unwrap();
+        let sequences: Vec<u64> = read.iter().map(|e| e.metadata.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3, 4]);
+        let payload: serde_json::Value = read[3].deserialize_payload().unwrap();
+        assert_eq!(payload, serde_json::json!({ "index": 0 }));
+
+        let backward = ReadOptions {
+            from_version: 3,
+            max_count: Some(2),
+            direction: ReadDirection::Backward,
+        };
+        let read = store.read(&orders, backward).await.unwrap();
+        assert_eq!(read[0].metadata.sequence, 3);
+        assert_eq!(read[1].metadata.sequence, 2);
+
+        let all = store.read_all_positioned(2, 10).await.unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].0, 2);
+        assert_eq!(all[1].1.metadata.stream_id, users);
+
+        store.delete_stream(&users).await.unwrap();
+        drop(store);
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert!(!store.stream_exists(&users).await.unwrap());
+        assert_eq!(store.list_streams().await.unwrap(), vec![orders]);
+
+        let result = store
+            .append(&users, ExpectedVersion::Any, events(&users, 1))
+            .await;
+        assert!(matches!(result, Err(EventError::StreamNotFound(_))));
+        let result = store.truncate_stream(&users, 2).await;
+        assert!(matches!(result, Err(EventError::StreamNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_recovery_truncates_torn_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let stream_id = StreamId::new("stream");
+
+        {
+            let store = FileEventStore::open(dir.path()).unwrap();
+            store
+                .append(&stream_id, ExpectedVersion::Any, events(&stream_id, 2))
+                .await
+                .unwrap();
+            store
+                .append(&stream_id, ExpectedVersion::Any, events(&stream_id, 3))
+                .await
+                .unwrap();
+        }
+
+        // Cut into the last record of the second batch and add garbage
+        let path = last_segment(dir.path());
+        let length = fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(length - 5).unwrap();
+        drop(file);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[0xAB; 7]).unwrap();
+        drop(file);
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        assert_eq!(store.get_version(&stream_id).await.unwrap(), Some(2));
+        assert_eq!(store.head_position(), 2);
+
+        // The log accepts appends after the truncated batch
+        let result = store
+            .append(&stream_id, ExpectedVersion::Exact(2), events(&stream_id, 1))
+            .await
+            .unwrap();
+        assert_eq!(result.new_version, 3);
+        drop(store);
+
+        let store = FileEventStore::open(dir.path()).unwrap();
+        let read = store.read(&stream_id, ReadOptions::default()).await.unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(store.read_all(0, 10).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_compaction_archives_and_keeps_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_dir = tempfile::tempdir().unwrap();
+        let config = FileStoreConfig {
+            segment_size: 512,
+            fsync: FsyncPolicy::Never,
+        };
+        let kept = StreamId::new("kept");
+        let deleted = StreamId::new("deleted");
+
+        let store = FileEventStore::open_with_config(dir.path(), config.clone()).unwrap();
+        for _ in 0..10 {
+            store
+                .append(&kept, ExpectedVersion::Any, events(&kept, 2))
+                .await
+                .unwrap();
+            store
+                .append(&deleted, ExpectedVersion::Any, events(&deleted, 1))
+                .await
+                .unwrap();
+        }
+        assert!(store.segment_count() > 2);
+
+        assert_eq!(store.truncate_stream(&kept, 11).await.unwrap(), 10);
+        store.delete_stream(&deleted).await.unwrap();
+        let tail = store.read_all_positioned(0, 100).await.unwrap();
+
+        let archive = FileArchive::new(archive_dir.path().to_path_buf());
+        let result = store.compact(Some(&archive)).await.unwrap();
+        assert!(result.events_removed > 10);
+        let retained = store.read_all(0, 100).await.unwrap();
+        assert_eq!(result.events_retained, retained.len());
+
+        let archived = archive.retrieve(&kept).await.unwrap();
+        assert_eq!(archived.len(), 10);
+        assert_eq!(archived[0].metadata.sequence, 1);
+
+        // Positions of retained events survive compaction and reopening
+        let after = store.read_all_positioned(0, 100).await.unwrap();
+        let kept_positions: Vec<u64> = tail
+            .iter()
+            .filter(|(_, e)| e.metadata.stream_id == kept)
+            .map(|(p, _)| *p)
+            .collect();
+        assert_eq!(
+            after
+                .iter()
+                .filter(|(_, e)| e.metadata.stream_id == kept)
+                .map(|(p, _)| *p)
+                .collect::<Vec<_>>(),
+            kept_positions
+        );
+        let head = store.head_position();
+        drop(store);
+
+        let store = FileEventStore::open_with_config(dir.path(), config).unwrap();
+        assert_eq!(store.head_position(), head);
+        assert_eq!(store.get_version(&kept).await.unwrap(), Some(20));
+        let read = store.read(&kept, ReadOptions::default()).await.unwrap();
+        assert_eq!(read.first().unwrap().metadata.sequence, 11);
+        assert_eq!(read.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_subscription_follows_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(FileEventStore::open(dir.path()).unwrap());
+        let stream_id = StreamId::new("stream");
+        store
+            .append(&stream_id, ExpectedVersion::Any, events(&stream_id, 3))
+            .await
+            .unwrap();
+
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = handled.clone();
+        let projection: Arc<dyn Projection> = Arc::new(ProjectionBuilder::new("counter").build(
+            move |_event| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        ));
+
+        let subscription = CatchUpSubscription::new(store.clone(), projection.clone(), 2);
+        let follower = tokio::spawn(async move {
+            subscription.follow(Duration::from_secs(60)).await
+        });
+
+        store
+            .append(&stream_id, ExpectedVersion::Exact(3), events(&stream_id, 2))
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handled.load(Ordering::SeqCst) < 5 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        follower.abort();
+
+        assert_eq!(handled.load(Ordering::SeqCst), 5);
+        assert_eq!(projection.position().await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_interval_fsync_without_later_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let stream_id = StreamId::new("stream");
+        let config = FileStoreConfig {
+            fsync: FsyncPolicy::Interval(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let store = FileEventStore::open_with_config(dir.path(), config).unwrap();
+        store
+            .append(&stream_id, ExpectedVersion::NoStream, events(&stream_id, 1))
+            .await
+            .unwrap();
+        assert!(store.state.read().unsynced);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while store.state.read().unsynced {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
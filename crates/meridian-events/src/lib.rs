@@ -6,6 +6,8 @@
 //! ## Features
 //!
 //! - **Event Store**: Append-only event log with optimistic concurrency control
+//! - **Durable File Store**: Segmented, checksummed event log with crash recovery and no native dependencies
+//! - **PostgreSQL Store**: Event and snapshot stores on `meridian-db` with LISTEN/NOTIFY subscriptions
 //! - **Event Versioning**: Schema evolution support with semantic versioning
 //! - **Aggregate Root Pattern**: Domain-driven design aggregates with event sourcing
 //! - **Command Handlers**: Command validation and execution with middleware
@@ -107,6 +109,9 @@
 pub mod error;
 pub mod event;
 pub mod store;
+pub mod file_store;
+#[cfg(feature = "postgres-backend")]
+pub mod postgres_store;
 
 // DDD and Event Sourcing
 pub mod aggregate;
@@ -146,6 +151,11 @@ pub mod prelude {
     #[cfg(feature = "rocksdb-backend")]
     pub use crate::store::rocksdb_store::RocksDbEventStore;
 
+    pub use crate::file_store::{FileEventStore, FileStoreConfig, FsyncPolicy};
+
+    #[cfg(feature = "postgres-backend")]
+    pub use crate::postgres_store::{PostgresEventStore, PostgresSnapshotStore};
+
     // Aggregate types
     pub use crate::aggregate::{
         Aggregate, AggregateId, AggregateRepository, AggregateRoot, EventSourcedRepository,
@@ -0,0 +1,708 @@
+//! PostgreSQL event and snapshot stores built on `meridian-db`.
+//!
+//! Events live in `event_log`, whose identity column is the global position.
+//! Appends lock their stream with a transaction-scoped advisory lock keyed on
+//! the stream ID, so appends to different streams check versions
+//! concurrently. Position assignment alone takes a global advisory lock, held
+//! to commit, so positions become visible in commit order and catch-up
+//! subscriptions never skip an event.
+//! Each append sends a `NOTIFY` carrying the new head position; a listener
+//! task forwards it to [`EventStore::watch_head`] so subscribers in other
+//! processes are pushed new events instead of polling.
+//!
+//! The schema is installed with [`run_migrations`], which registers
+//! [`migrations`] with [`MigrationManager`].
+
+use crate::aggregate::{AggregateId, AggregateRoot};
+use crate::error::{EventError, Result};
+use crate::event::{EventEnvelope, EventMetadata, StoredEvent, StreamId};
+use crate::snapshot::{Snapshot, SnapshotMetadata, SnapshotStore};
+use crate::store::{AppendResult, EventStore, ExpectedVersion, ReadDirection, ReadOptions};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use meridian_db::{Migration, MigrationManager, Pool};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::postgres::{PgListener, PgPool, PgRow, Postgres};
+use sqlx::types::Json;
+use sqlx::{Row, Transaction};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Channel the head position is announced on.
+pub const NOTIFY_CHANNEL: &str = "meridian_events";
+
+/// First migration version used by the event store.
+///
+/// Kept clear of the core schema so both sets can share `_migrations`.
+pub const MIGRATION_BASE: i32 = 1000;
+
+/// Advisory lock serializing global position assignment ("MRDNEVTS").
+const POSITION_LOCK_KEY: i64 = 0x4d52_444e_4556_5453;
+
+/// Class of the per-stream advisory locks ("MRDN"), keyed by `hashtext` of
+/// the stream ID. The two-key form keeps them apart from [`POSITION_LOCK_KEY`].
+const STREAM_LOCK_CLASS: i32 = 0x4d52_444e;
+
+/// Delay before the listener reconnects after losing its connection.
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Event log and stream tables.
+fn create_event_log() -> Migration {
+    Migration::new(
+        MIGRATION_BASE + 1,
+        "create_event_log",
+        r#"
+        CREATE TABLE event_streams (
+            stream_id TEXT PRIMARY KEY,
+            version BIGINT NOT NULL,
+            deleted BOOLEAN NOT NULL DEFAULT false,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+
+        -- Positions start at 0 like the other stores
+        CREATE TABLE event_log (
+            position BIGINT GENERATED ALWAYS AS IDENTITY (MINVALUE 0 START WITH 0) PRIMARY KEY,
+            stream_id TEXT NOT NULL REFERENCES event_streams(stream_id) ON DELETE CASCADE,
+            sequence BIGINT NOT NULL,
+            event_id UUID NOT NULL UNIQUE,
+            event_type TEXT NOT NULL,
+            metadata JSONB NOT NULL,
+            payload JSONB NOT NULL,
+            recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            UNIQUE (stream_id, sequence)
+        );
+
+        CREATE INDEX idx_event_log_event_type ON event_log(event_type);
+        "#,
+        r#"
+        DROP TABLE IF EXISTS event_log;
+        DROP TABLE IF EXISTS event_streams;
+        "#,
+    )
+}
+
+/// Aggregate snapshot table.
+fn create_snapshots() -> Migration {
+    Migration::new(
+        MIGRATION_BASE + 2,
+        "create_event_snapshots",
+        r#"
+        CREATE TABLE event_snapshots (
+            aggregate_type TEXT NOT NULL,
+            aggregate_id TEXT NOT NULL,
+            version BIGINT NOT NULL,
+            state JSONB NOT NULL,
+            taken_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (aggregate_type, aggregate_id)
+        );
+        "#,
+        r#"
+        DROP TABLE IF EXISTS event_snapshots;
+        "#,
+    )
+}
+
+/// Schema migrations for the event and snapshot stores.
+pub fn migrations() -> Vec<Migration> {
+    vec![create_event_log(), create_snapshots()]
+}
+
+/// Apply any pending event store migrations.
+pub async fn run_migrations(pool: &Pool) -> Result<()> {
+    MigrationManager::new(pool).migrate(&migrations()).await?;
+    Ok(())
+}
+
+/// Convert a version or position to its column type.
+fn to_db(value: u64) -> Result<i64> {
+    i64::try_from(value).map_err(|_| EventError::store(format!("Value {} out of range", value)))
+}
+
+/// Convert a non-negative column back to a version or position.
+fn from_db(value: i64) -> u64 {
+    value.max(0) as u64
+}
+
+/// Decode an `event_log` row into its position and event.
+fn decode_event(row: &PgRow) -> Result<(u64, StoredEvent)> {
+    let position: i64 = row.try_get("position")?;
+    let Json(metadata): Json<EventMetadata> = row.try_get("metadata")?;
+    let payload: serde_json::Value = row.try_get("payload")?;
+    Ok((from_db(position), StoredEvent::new(metadata, serde_json::to_vec(&payload)?)))
+}
+
+/// Raise the head to `position` unless it is already past it.
+fn advance_head(head: &watch::Sender<u64>, position: u64) {
+    head.send_if_modified(|current| {
+        if position > *current {
+            *current = position;
+            true
+        } else {
+            false
+        }
+    });
+}
+
+/// Take the transaction-scoped advisory lock of a stream.
+async fn lock_stream(tx: &mut Transaction<'_, Postgres>, stream_id: &StreamId) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+        .bind(STREAM_LOCK_CLASS)
+        .bind(stream_id.as_str())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Position the next appended event will get, as far as committed rows tell.
+async fn query_head(pool: &PgPool) -> Result<u64> {
+    let last: Option<i64> = sqlx::query_scalar("SELECT MAX(position) FROM event_log")
+        .fetch_one(pool)
+        .await?;
+    Ok(last.map_or(0, |p| from_db(p) + 1))
+}
+
+/// Forward head notifications from other connections to `head`.
+async fn listen(pool: PgPool, head: Arc<watch::Sender<u64>>) {
+    loop {
+        let mut listener = match PgListener::connect_with(&pool).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::warn!("Failed to connect event listener: {}", e);
+                tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = listener.listen(NOTIFY_CHANNEL).await {
+            tracing::warn!("Failed to listen on {}: {}", NOTIFY_CHANNEL, e);
+            tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+            continue;
+        }
+
+        // Appends committed while disconnected were never announced
+        match query_head(&pool).await {
+            Ok(position) => advance_head(&head, position),
+            Err(e) => tracing::warn!("Failed to read event log head: {}", e),
+        }
+
+        loop {
+            match listener.recv().await {
+                Ok(notification) => match notification.payload().parse::<u64>() {
+                    Ok(position) => advance_head(&head, position),
+                    Err(_) => tracing::warn!(
+                        "Ignoring malformed head notification: {}",
+                        notification.payload()
+                    ),
+                },
+                Err(e) => {
+                    tracing::warn!("Event listener connection lost: {}", e);
+                    break;
+                }
+            }
+        }
+
+        tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+    }
+}
+
+/// PostgreSQL-backed event store.
+///
+/// Optimistic concurrency is checked against `event_streams.version` under a
+/// row lock, and the global position comes from `event_log`. The schema must
+/// be installed with [`run_migrations`] first.
+pub struct PostgresEventStore {
+    pool: PgPool,
+    head: Arc<watch::Sender<u64>>,
+    listener: JoinHandle<()>,
+}
+
+impl PostgresEventStore {
+    /// Open the store and start listening for appends from other processes.
+    pub async fn new(pool: &Pool) -> Result<Self> {
+        let pool = pool.inner().clone();
+        let (head, _) = watch::channel(query_head(&pool).await?);
+        let head = Arc::new(head);
+        let listener = tokio::spawn(listen(pool.clone(), Arc::clone(&head)));
+
+        Ok(Self { pool, head, listener })
+    }
+
+    /// Position the next appended event will get.
+    pub fn head_position(&self) -> u64 {
+        *self.head.borrow()
+    }
+
+    async fn stream_state(&self, stream_id: &StreamId) -> Result<Option<(u64, bool)>> {
+        let row = sqlx::query("SELECT version, deleted FROM event_streams WHERE stream_id = $1")
+            .bind(stream_id.as_str())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| -> Result<(u64, bool)> {
+            Ok((from_db(row.try_get("version")?), row.try_get("deleted")?))
+        })
+        .transpose()
+    }
+}
+
+impl Drop for PostgresEventStore {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+#[async_trait]
+impl EventStore for PostgresEventStore {
+    async fn append(
+        &self,
+        stream_id: &StreamId,
+        expected_version: ExpectedVersion,
+        events: Vec<EventEnvelope>,
+    ) -> Result<AppendResult> {
+        if events.is_empty() {
+            return Ok(AppendResult {
+                stream_id: stream_id.clone(),
+                new_version: self.get_version(stream_id).await?.unwrap_or(0),
+                events_appended: 0,
+            });
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize appends to this stream, including its creation
+        lock_stream(&mut tx, stream_id).await?;
+
+        let current: Option<(i64, bool)> = sqlx::query_as(
+            "SELECT version, deleted FROM event_streams WHERE stream_id = $1 FOR UPDATE",
+        )
+        .bind(stream_id.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let current_version = match current {
+            Some((_, true)) => return Err(EventError::StreamNotFound(stream_id.to_string())),
+            Some((version, false)) => Some(from_db(version)),
+            None => None,
+        };
+
+        if !expected_version.matches(current_version) {
+            return Err(expected_version.conflict(current_version));
+        }
+
+        let events_appended = events.len();
+        let new_version = current_version.unwrap_or(0) + events_appended as u64;
+
+        sqlx::query(
+            r#"
+            INSERT INTO event_streams (stream_id, version) VALUES ($1, $2)
+            ON CONFLICT (stream_id) DO UPDATE SET version = EXCLUDED.version, updated_at = NOW()
+            "#,
+        )
+        .bind(stream_id.as_str())
+        .bind(to_db(new_version)?)
+        .execute(&mut *tx)
+        .await?;
+
+        // Assign positions in commit order; held until the transaction ends
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(POSITION_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut last_position = 0;
+        for (sequence, envelope) in (current_version.unwrap_or(0) + 1..).zip(events) {
+            let mut metadata = envelope.metadata;
+            metadata.stream_id = stream_id.clone();
+            metadata.sequence = sequence;
+
+            let inserted = sqlx::query_scalar::<_, i64>(
+                r#"
+                INSERT INTO event_log (stream_id, sequence, event_id, event_type, metadata, payload)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING position
+                "#,
+            )
+            .bind(stream_id.as_str())
+            .bind(to_db(sequence)?)
+            .bind(*metadata.event_id.as_uuid())
+            .bind(&metadata.event_version.event_type)
+            .bind(Json(&metadata))
+            .bind(Json(&envelope.payload))
+            .fetch_one(&mut *tx)
+            .await;
+
+            last_position = match inserted {
+                Ok(position) => from_db(position),
+                // Only reachable by writers bypassing the advisory lock
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                    return Err(expected_version.conflict(current_version));
+                }
+                Err(e) => return Err(e.into()),
+            };
+        }
+
+        let head = last_position + 1;
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(NOTIFY_CHANNEL)
+            .bind(head.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        advance_head(&self.head, head);
+
+        Ok(AppendResult {
+            stream_id: stream_id.clone(),
+            new_version,
+            events_appended,
+        })
+    }
+
+    async fn read(
+        &self,
+        stream_id: &StreamId,
+        options: ReadOptions,
+    ) -> Result<Vec<StoredEvent>> {
+        match self.stream_state(stream_id).await? {
+            Some((_, false)) => {}
+            _ => return Err(EventError::StreamNotFound(stream_id.to_string())),
+        }
+
+        let sql = match options.direction {
+            ReadDirection::Forward => {
+                "SELECT position, metadata, payload FROM event_log \
+                 WHERE stream_id = $1 AND sequence >= $2 ORDER BY sequence LIMIT $3"
+            }
+            ReadDirection::Backward => {
+                "SELECT position, metadata, payload FROM event_log \
+                 WHERE stream_id = $1 AND sequence <= $2 ORDER BY sequence DESC LIMIT $3"
+            }
+        };
+
+        // A NULL limit returns every row
+        let limit = options.max_count.map(|n| n as i64);
+        let rows = sqlx::query(sql)
+            .bind(stream_id.as_str())
+            .bind(to_db(options.from_version)?)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| decode_event(row).map(|(_, event)| event))
+            .collect()
+    }
+
+    async fn get_version(&self, stream_id: &StreamId) -> Result<Option<u64>> {
+        Ok(self.stream_state(stream_id).await?.map(|(version, _)| version))
+    }
+
+    async fn stream_exists(&self, stream_id: &StreamId) -> Result<bool> {
+        Ok(matches!(self.stream_state(stream_id).await?, Some((_, false))))
+    }
+
+    async fn delete_stream(&self, stream_id: &StreamId) -> Result<()> {
+        sqlx::query("UPDATE event_streams SET deleted = true, updated_at = NOW() WHERE stream_id = $1")
+            .bind(stream_id.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_streams(&self) -> Result<Vec<StreamId>> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT stream_id FROM event_streams WHERE NOT deleted ORDER BY stream_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids.into_iter().map(StreamId::new).collect())
+    }
+
+    async fn read_all(&self, from_position: u64, max_count: usize) -> Result<Vec<StoredEvent>> {
+        let events = self.read_all_positioned(from_position, max_count).await?;
+        Ok(events.into_iter().map(|(_, event)| event).collect())
+    }
+
+    async fn read_all_positioned(
+        &self,
+        from_position: u64,
+        max_count: usize,
+    ) -> Result<Vec<(u64, StoredEvent)>> {
+        let rows = sqlx::query(
+            "SELECT position, metadata, payload FROM event_log \
+             WHERE position >= $1 ORDER BY position LIMIT $2",
+        )
+        .bind(to_db(from_position)?)
+        .bind(max_count as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(decode_event).collect()
+    }
+
+    async fn truncate_stream(&self, stream_id: &StreamId, before_version: u64) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+        lock_stream(&mut tx, stream_id).await?;
+
+        let deleted: Option<bool> =
+            sqlx::query_scalar("SELECT deleted FROM event_streams WHERE stream_id = $1 FOR UPDATE")
+                .bind(stream_id.as_str())
+                .fetch_optional(&mut *tx)
+                .await?;
+        if deleted != Some(false) {
+            return Err(EventError::StreamNotFound(stream_id.to_string()));
+        }
+
+        let result = sqlx::query("DELETE FROM event_log WHERE stream_id = $1 AND sequence < $2")
+            .bind(stream_id.as_str())
+            .bind(to_db(before_version)?)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    fn watch_head(&self) -> Option<watch::Receiver<u64>> {
+        Some(self.head.subscribe())
+    }
+}
+
+/// PostgreSQL-backed snapshot store.
+///
+/// Snapshots are keyed by aggregate type and ID and stored as JSON. Saving
+/// never replaces a snapshot with an older version.
+pub struct PostgresSnapshotStore<A> {
+    pool: PgPool,
+    _aggregate: PhantomData<fn() -> A>,
+}
+
+impl<A> PostgresSnapshotStore<A> {
+    /// Create a snapshot store on a pool.
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            pool: pool.inner().clone(),
+            _aggregate: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<A> SnapshotStore<A> for PostgresSnapshotStore<A>
+where
+    A: AggregateRoot + Serialize + DeserializeOwned,
+{
+    async fn save(&self, aggregate: &A) -> Result<()> {
+        let state = serde_json::to_value(aggregate)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO event_snapshots (aggregate_type, aggregate_id, version, state, taken_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (aggregate_type, aggregate_id) DO UPDATE
+            SET version = EXCLUDED.version, state = EXCLUDED.state, taken_at = EXCLUDED.taken_at
+            WHERE event_snapshots.version <= EXCLUDED.version
+            "#,
+        )
+        .bind(A::aggregate_type())
+        .bind(aggregate.aggregate_id().as_str())
+        .bind(to_db(aggregate.version())?)
+        .bind(Json(state))
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn load(&self, id: &AggregateId) -> Result<Snapshot<A>> {
+        let row = sqlx::query(
+            "SELECT version, state, taken_at FROM event_snapshots \
+             WHERE aggregate_type = $1 AND aggregate_id = $2",
+        )
+        .bind(A::aggregate_type())
+        .bind(id.as_str())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| EventError::SnapshotNotFound(id.to_string()))?;
+
+        let Json(state): Json<A> = row.try_get("state")?;
+        let metadata = SnapshotMetadata {
+            aggregate_id: id.clone(),
+            aggregate_type: A::aggregate_type().to_string(),
+            version: from_db(row.try_get("version")?),
+            timestamp: row.try_get("taken_at")?,
+        };
+
+        Ok(Snapshot::new(metadata, state))
+    }
+
+    async fn delete(&self, id: &AggregateId) -> Result<()> {
+        sqlx::query("DELETE FROM event_snapshots WHERE aggregate_type = $1 AND aggregate_id = $2")
+            .bind(A::aggregate_type())
+            .bind(id.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn exists(&self, id: &AggregateId) -> Result<bool> {
+        let found: Option<i32> = sqlx::query_scalar(
+            "SELECT 1 FROM event_snapshots WHERE aggregate_type = $1 AND aggregate_id = $2",
+        )
+        .bind(A::aggregate_type())
+        .bind(id.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(found.is_some())
+    }
+
+    async fn get_metadata(&self, id: &AggregateId) -> Result<SnapshotMetadata> {
+        let row = sqlx::query(
+            "SELECT version, taken_at FROM event_snapshots \
+             WHERE aggregate_type = $1 AND aggregate_id = $2",
+        )
+        .bind(A::aggregate_type())
+        .bind(id.as_str())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| EventError::SnapshotNotFound(id.to_string()))?;
+
+        let timestamp: DateTime<Utc> = row.try_get("taken_at")?;
+        Ok(SnapshotMetadata {
+            aggregate_id: id.clone(),
+            aggregate_type: A::aggregate_type().to_string(),
+            version: from_db(row.try_get("version")?),
+            timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventVersion;
+
+    #[test]
+    fn test_migrations_use_reserved_versions() {
+        let migrations = migrations();
+        assert_eq!(migrations.len(), 2);
+        assert!(migrations.iter().all(|m| m.version > MIGRATION_BASE));
+        assert!(migrations.windows(2).all(|w| w[0].version < w[1].version));
+    }
+
+    #[test]
+    fn test_version_conversion() {
+        assert_eq!(to_db(42).unwrap(), 42);
+        assert!(to_db(u64::MAX).is_err());
+        assert_eq!(from_db(-1), 0);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires PostgreSQL, see MERIDIAN_EVENTS_TEST_DATABASE_URL
+    async fn test_postgres_append_read_and_notify() -> Result<()> {
+        let url = std::env::var("MERIDIAN_EVENTS_TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/meridian_test".to_string());
+        let pool = Pool::from_url(&url).await?;
+        run_migrations(&pool).await?;
+
+        let store = PostgresEventStore::new(&pool).await?;
+        let mut head = store.watch_head().unwrap();
+        let stream_id = StreamId::new(format!("pg-test-{}", uuid::Uuid::new_v4()));
+
+        let envelope = |n: u64| {
+            let metadata = EventMetadata::new(
+                stream_id.clone(),
+                0,
+                EventVersion::new("Tested", semver::Version::new(1, 0, 0)),
+            );
+            EventEnvelope::new(metadata, serde_json::json!({ "n": n }))
+        };
+
+        let result = store
+            .append(&stream_id, ExpectedVersion::NoStream, vec![envelope(1), envelope(2)])
+            .await?;
+        assert_eq!(result.new_version, 2);
+        assert!(head.has_changed().unwrap());
+
+        let conflict = store
+            .append(&stream_id, ExpectedVersion::Exact(1), vec![envelope(3)])
+            .await;
+        assert!(matches!(conflict, Err(EventError::ConcurrencyConflict { .. })));
+
+        let events = store.read(&stream_id, ReadOptions::default()).await?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].metadata.sequence, 2);
+
+        let positioned = store.read_all_positioned(store.head_position() - 2, 10).await?;
+        assert!(positioned.windows(2).all(|w| w[0].0 < w[1].0));
+
+        assert_eq!(store.truncate_stream(&stream_id, 2).await?, 1);
+        assert_eq!(store.get_version(&stream_id).await?, Some(2));
+
+        // A second store sees the append through LISTEN/NOTIFY
+        let follower = PostgresEventStore::new(&pool).await?;
+        let mut follower_head = follower.watch_head().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        store
+            .append(&stream_id, ExpectedVersion::Exact(2), vec![envelope(3)])
+            .await?;
+        tokio::time::timeout(Duration::from_secs(5), follower_head.changed())
+            .await
+            .expect("notification not received")
+            .unwrap();
+        assert_eq!(follower.head_position(), store.head_position());
+
+        store.delete_stream(&stream_id).await?;
+        assert!(!store.stream_exists(&stream_id).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires PostgreSQL, see MERIDIAN_EVENTS_TEST_DATABASE_URL
+    async fn test_postgres_deleted_stream_rejects_writes() -> Result<()> {
+        let url = std::env::var("MERIDIAN_EVENTS_TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/meridian_test".to_string());
+        let pool = Pool::from_url(&url).await?;
+        run_migrations(&pool).await?;
+
+        let store = PostgresEventStore::new(&pool).await?;
+        let stream_id = StreamId::new(format!("pg-deleted-{}", uuid::Uuid::new_v4()));
+        let first_id = StreamId::new(format!("pg-other-{}", uuid::Uuid::new_v4()));
+        let second_id = StreamId::new(format!("pg-other-{}", uuid::Uuid::new_v4()));
+        let envelope = |stream_id: &StreamId| {
+            let metadata = EventMetadata::new(
+                stream_id.clone(),
+                0,
+                EventVersion::new("Tested", semver::Version::new(1, 0, 0)),
+            );
+            EventEnvelope::new(metadata, serde_json::json!({}))
+        };
+
+        let created = vec![envelope(&stream_id), envelope(&stream_id)];
+        store.append(&stream_id, ExpectedVersion::NoStream, created).await?;
+        store.delete_stream(&stream_id).await?;
+
+        let append = store
+            .append(&stream_id, ExpectedVersion::Any, vec![envelope(&stream_id)])
+            .await;
+        assert!(matches!(append, Err(EventError::StreamNotFound(_))));
+        let truncate = store.truncate_stream(&stream_id, 2).await;
+        assert!(matches!(truncate, Err(EventError::StreamNotFound(_))));
+        assert_eq!(store.get_version(&stream_id).await?, Some(2));
+
+        // Appends to other streams proceed concurrently under their own locks
+        let (a, b) = tokio::join!(
+            store.append(&first_id, ExpectedVersion::NoStream, vec![envelope(&first_id)]),
+            store.append(&second_id, ExpectedVersion::NoStream, vec![envelope(&second_id)]),
+        );
+        assert_eq!([a?.new_version, b?.new_version], [1, 1]);
+        assert_eq!(store.get_version(&first_id).await?, Some(1));
+        assert_eq!(store.get_version(&second_id).await?, Some(1));
+        Ok(())
+    }
+}
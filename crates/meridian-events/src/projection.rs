@@ -261,22 +261,26 @@ where
     }
 
     /// Run the catch-up subscription.
+    ///
+    /// The projection position is the global position of the next event
+    /// to handle.
     pub async fn run(&self) -> Result<()> {
         let mut position = self.projection.position().await?;
 
         loop {
             let events = self
                 .event_store
-                .read_all(position, self.batch_size)
+                .read_all_positioned(position, self.batch_size)
                 .await?;
 
             if events.is_empty() {
                 break;
             }
 
-            for event in &events {
+            for (event_position, event) in &events {
                 self.projection.handle(event).await?;
-                position = event.metadata.sequence;
+                position = event_position + 1;
+                self.projection.set_position(position).await?;
             }
 
             if events.len() < self.batch_size {
@@ -286,6 +290,28 @@ where
 
         Ok(())
     }
+
+    /// Catch up and keep following new events.
+    ///
+    /// Waits for appends announced by the store's head watch, or polls
+    /// every `poll_interval` for stores without one. Returns when the
+    /// store stops announcing appends.
+    pub async fn follow(&self, poll_interval: std::time::Duration) -> Result<()> {
+        let mut head = self.event_store.watch_head();
+
+        loop {
+            self.run().await?;
+
+            match &mut head {
+                Some(head) => {
+                    if head.changed().await.is_err() {
+                        return Ok(());
+                    }
+                }
+                None => tokio::time::sleep(poll_interval).await,
+            }
+        }
+    }
 }
 
 /// Live subscription for processing new events in real-time.
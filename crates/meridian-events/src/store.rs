@@ -6,6 +6,7 @@ use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::watch;
 
 /// Expected version for optimistic concurrency control.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,6 +34,18 @@ impl ExpectedVersion {
             (ExpectedVersion::Exact(_), None) => false,
         }
     }
+
+    /// Conflict error for an actual version that does not match.
+    pub fn conflict(&self, actual: Option<u64>) -> EventError {
+        EventError::ConcurrencyConflict {
+            expected: match self {
+                ExpectedVersion::Exact(v) => *v,
+                ExpectedVersion::NoStream => 0,
+                ExpectedVersion::StreamExists | ExpectedVersion::Any => actual.unwrap_or(0),
+            },
+            actual: actual.unwrap_or(0),
+        }
+    }
 }
 
 /// Options for reading events from a stream.
@@ -108,6 +121,36 @@ pub trait EventStore: Send + Sync {
 
     /// Get global event sequence for subscriptions.
     async fn read_all(&self, from_position: u64, max_count: usize) -> Result<Vec<StoredEvent>>;
+
+    /// Read the global event sequence along with each event's position.
+    ///
+    /// The default numbers the events returned by `read_all` consecutively,
+    /// which only holds for stores without gaps in their global log.
+    async fn read_all_positioned(
+        &self,
+        from_position: u64,
+        max_count: usize,
+    ) -> Result<Vec<(u64, StoredEvent)>> {
+        let events = self.read_all(from_position, max_count).await?;
+        Ok((from_position..).zip(events).collect())
+    }
+
+    /// Remove the events of a stream below a version, returning how many
+    /// were removed. The stream version is unaffected.
+    async fn truncate_stream(&self, stream_id: &StreamId, before_version: u64) -> Result<usize> {
+        let _ = before_version;
+        Err(EventError::store(format!(
+            "Truncating stream {} is not supported by this store",
+            stream_id
+        )))
+    }
+
+    /// Watch the global position the next appended event will get.
+    ///
+    /// Stores returning `None` have to be polled for new events.
+    fn watch_head(&self) -> Option<watch::Receiver<u64>> {
+        None
+    }
 }
 
 /// In-memory event store implementation (for testing and development).
@@ -115,13 +158,15 @@ pub trait EventStore: Send + Sync {
 pub struct InMemoryEventStore {
     /// Streams indexed by stream ID
     streams: Arc<RwLock<HashMap<StreamId, StreamData>>>,
-    /// Global event log for subscriptions
-    global_log: Arc<RwLock<Vec<StoredEvent>>>,
+    /// Global event log for subscriptions, `None` for truncated events
+    global_log: Arc<RwLock<Vec<Option<StoredEvent>>>>,
 }
 
 #[derive(Debug, Clone)]
 struct StreamData {
     events: Vec<StoredEvent>,
+    /// Global log position of each event
+    positions: Vec<usize>,
     version: u64,
     deleted: bool,
 }
@@ -169,19 +214,12 @@ impl EventStore for InMemoryEventStore {
         // Check expected version
         let current_version = stream_data.as_ref().map(|s| s.version);
         if !expected_version.matches(current_version) {
-            return Err(EventError::ConcurrencyConflict {
-                expected: match expected_version {
-                    ExpectedVersion::Exact(v) => v,
-                    ExpectedVersion::NoStream => 0,
-                    ExpectedVersion::StreamExists => current_version.unwrap_or(0),
-                    ExpectedVersion::Any => current_version.unwrap_or(0),
-                },
-                actual: current_version.unwrap_or(0),
-            });
+            return Err(expected_version.conflict(current_version));
         }
 
         // Convert envelopes to stored events
         let mut stored_events = Vec::new();
+        let mut positions = Vec::new();
         let mut next_version = current_version.unwrap_or(0) + 1;
 
         for envelope in events {
@@ -195,7 +233,9 @@ impl EventStore for InMemoryEventStore {
             stored_events.push(stored_event.clone());
 
             // Add to global log
-            self.global_log.write().push(stored_event);
+            let mut global_log = self.global_log.write();
+            positions.push(global_log.len());
+            global_log.push(Some(stored_event));
 
             next_version += 1;
         }
@@ -206,12 +246,14 @@ impl EventStore for InMemoryEventStore {
         // Update or create stream
         if let Some(data) = stream_data {
             data.events.extend(stored_events);
+            data.positions.extend(positions);
             data.version = new_version;
         } else {
             streams.insert(
                 stream_id.clone(),
                 StreamData {
                     events: stored_events,
+                    positions,
                     version: new_version,
                     deleted: false,
                 },
@@ -289,14 +331,44 @@ impl EventStore for InMemoryEventStore {
     }
 
     async fn read_all(&self, from_position: u64, max_count: usize) -> Result<Vec<StoredEvent>> {
+        let events = self.read_all_positioned(from_position, max_count).await?;
+        Ok(events.into_iter().map(|(_, event)| event).collect())
+    }
+
+    async fn read_all_positioned(
+        &self,
+        from_position: u64,
+        max_count: usize,
+    ) -> Result<Vec<(u64, StoredEvent)>> {
         let global_log = self.global_log.read();
-        Ok(global_log
-            .iter()
+        Ok((0u64..)
+            .zip(global_log.iter())
             .skip(from_position as usize)
+            .filter_map(|(position, event)| Some((position, event.clone()?)))
             .take(max_count)
-            .cloned()
             .collect())
     }
+
+    async fn truncate_stream(&self, stream_id: &StreamId, before_version: u64) -> Result<usize> {
+        let mut streams = self.streams.write();
+        let stream = streams
+            .get_mut(stream_id)
+            .ok_or_else(|| EventError::StreamNotFound(stream_id.to_string()))?;
+
+        let removed = stream
+            .events
+            .iter()
+            .take_while(|e| e.metadata.sequence < before_version)
+            .count();
+        stream.events.drain(..removed);
+
+        let mut global_log = self.global_log.write();
+        for position in stream.positions.drain(..removed) {
+            global_log[position] = None;
+        }
+
+        Ok(removed)
+    }
 }
 
 #[cfg(feature = "rocksdb-backend")]
@@ -355,15 +427,7 @@ pub mod rocksdb_store {
 
             // Check expected version
             if !expected_version.matches(current_version) {
-                return Err(EventError::ConcurrencyConflict {
-                    expected: match expected_version {
-                        ExpectedVersion::Exact(v) => v,
-                        ExpectedVersion::NoStream => 0,
-                        ExpectedVersion::StreamExists => current_version.unwrap_or(0),
-                        ExpectedVersion::Any => current_version.unwrap_or(0),
-                    },
-                    actual: current_version.unwrap_or(0),
-                });
+                return Err(expected_version.conflict(current_version));
             }
 
             let mut next_version = current_version.unwrap_or(0) + 1;
@@ -506,6 +570,24 @@ pub mod rocksdb_store {
                 .take(max_count)
                 .collect())
         }
+
+        async fn truncate_stream(&self, stream_id: &StreamId, before_version: u64) -> Result<usize> {
+            let version = self
+                .get_version(stream_id)
+                .await?
+                .ok_or_else(|| EventError::StreamNotFound(stream_id.to_string()))?;
+
+            let mut removed = 0;
+            for seq in 1..before_version.min(version + 1) {
+                let key = Self::event_key(stream_id, seq);
+                if self.db.get(&key)?.is_some() {
+                    self.db.delete(&key)?;
+                    removed += 1;
+                }
+            }
+
+            Ok(removed)
+        }
     }
 }
 
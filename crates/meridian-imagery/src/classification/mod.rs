@@ -8,8 +8,12 @@ pub mod unsupervised;
 pub use supervised::{SupervisedClassifier, MaximumLikelihood};
 pub use unsupervised::{UnsupervisedClassifier, KMeans, Isodata};
 
+use crate::dataset::RasterDataset;
 use crate::error::Result;
-use crate::MultiBandImage;
+use crate::{DataType, MultiBandImage};
+
+/// Label given to masked or nodata pixels of a streamed classification
+pub const UNCLASSIFIED: u16 = u16::MAX;
 
 /// Classification result
 #[derive(Debug, Clone)]
@@ -122,3 +126,38 @@ impl TrainingData {
         self.class_names.len()
     }
 }
+
+/// Classify a dataset block by block into a single-band `UInt16` label raster
+///
+/// Pixels that are invalid in the source are labelled [`UNCLASSIFIED`].
+pub(crate) fn classify_blocks<F>(dataset: &RasterDataset, classify: F) -> Result<RasterDataset>
+where
+    F: Fn(&MultiBandImage) -> Result<Vec<u32>> + Sync,
+{
+    let layout = dataset
+        .layout()
+        .derive(1, DataType::UInt16)
+        .with_no_data(UNCLASSIFIED as f64)
+        .with_band_names(vec!["class".to_string()]);
+
+    dataset.map_blocks(layout, |block, window| {
+        let labels = classify(block)?;
+        let valid = dataset.valid_mask(window)?;
+
+        let mut metadata = block.metadata.clone();
+        metadata.bands = 1;
+        let mut output = MultiBandImage::new(metadata, DataType::UInt16);
+        output.bands[0] = labels
+            .iter()
+            .zip(&valid)
+            .map(|(&label, &valid)| {
+                if valid {
+                    label.min(UNCLASSIFIED as u32 - 1) as f32
+                } else {
+                    UNCLASSIFIED as f32
+                }
+            })
+            .collect();
+        Ok(output)
+    })
+}
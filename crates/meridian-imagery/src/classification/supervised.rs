@@ -1,5 +1,6 @@
 //! Supervised classification algorithms
 
+use crate::dataset::RasterDataset;
 use crate::error::{ImageryError, Result};
 use crate::MultiBandImage;
 use super::{ClassificationResult, TrainingData, TrainingSample};
@@ -11,6 +12,16 @@ pub trait SupervisedClassifier {
 
     /// Classify an image
     fn classify(&self, image: &MultiBandImage) -> Result<ClassificationResult>;
+
+    /// Classify a dataset block by block into a `UInt16` label dataset
+    ///
+    /// Masked and nodata pixels are labelled [`UNCLASSIFIED`](super::UNCLASSIFIED).
+    fn classify_dataset(&self, dataset: &RasterDataset) -> Result<RasterDataset>
+    where
+        Self: Sync,
+    {
+        super::classify_blocks(dataset, |block| Ok(self.classify(block)?.labels))
+    }
 }
 
 /// Maximum Likelihood classifier
@@ -1,6 +1,7 @@
 //! Unsupervised classification algorithms
 
 use crate::error::{ImageryError, Result};
+use crate::dataset::RasterDataset;
 use crate::MultiBandImage;
 use super::ClassificationResult;
 use rayon::prelude::*;
@@ -143,22 +144,9 @@ impl KMeans {
             .map(|(old, new)| Self::euclidean_distance(old, new))
             .sum::<f32>() / old_centers.len() as f32
     }
-}
-
-impl Default for KMeans {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl UnsupervisedClassifier for KMeans {
-    fn classify(&mut self, image: &MultiBandImage, num_classes: usize) -> Result<ClassificationResult> {
-        if num_classes == 0 {
-            return Err(ImageryError::Classification("Number of classes must be > 0".to_string()));
-        }
-
-        log::info!("Starting K-Means classification with {} classes", num_classes);
 
+    /// Cluster centers for an image
+    fn fit(&self, image: &MultiBandImage, num_classes: usize) -> Vec<Vec<f32>> {
         // Initialize centers
         let mut centers = Self::initialize_centers(image, num_classes);
 
@@ -183,6 +171,88 @@ impl UnsupervisedClassifier for KMeans {
             centers = new_centers;
         }
 
+        centers
+    }
+
+    /// Cluster a dataset without loading it
+    ///
+    /// Centers are trained on at most `max_samples` valid pixels spread evenly
+    /// over the raster, then every block is labelled against them. Masked and
+    /// nodata pixels are labelled [`UNCLASSIFIED`](super::UNCLASSIFIED).
+    pub fn classify_dataset(
+        &self,
+        dataset: &RasterDataset,
+        num_classes: usize,
+        max_samples: usize,
+    ) -> Result<RasterDataset> {
+        if num_classes == 0 {
+            return Err(ImageryError::Classification("Number of classes must be > 0".to_string()));
+        }
+
+        let sample = Self::sample_pixels(dataset, max_samples)?;
+        if sample.bands.first().map_or(0, Vec::len) < num_classes {
+            return Err(ImageryError::Classification(format!(
+                "Need at least {} valid pixels to train {} classes",
+                num_classes, num_classes
+            )));
+        }
+
+        log::info!(
+            "Training K-Means with {} classes on {} sampled pixels",
+            num_classes,
+            sample.metadata.width
+        );
+        let centers = self.fit(&sample, num_classes);
+        super::classify_blocks(dataset, |block| Ok(Self::assign_clusters(block, &centers)))
+    }
+
+    /// Every n-th valid pixel of a dataset as a one-row image
+    fn sample_pixels(dataset: &RasterDataset, max_samples: usize) -> Result<MultiBandImage> {
+        let (width, height) = dataset.dimensions();
+        let stride = (width as usize * height as usize).div_ceil(max_samples.max(1)).max(1);
+        let mut bands = vec![Vec::new(); dataset.band_count()];
+
+        for (_, _, window) in dataset.layout().blocks() {
+            let block = dataset.read_image(&window)?;
+            let valid = dataset.valid_mask(&window)?;
+            for (index, _) in valid.iter().enumerate().filter(|(_, &valid)| valid) {
+                let x = window.x as usize + index % window.width as usize;
+                let y = window.y as usize + index / window.width as usize;
+                if (y * width as usize + x).is_multiple_of(stride) {
+                    for (band, samples) in bands.iter_mut().zip(&block.bands) {
+                        band.push(samples[index]);
+                    }
+                }
+            }
+        }
+
+        let mut metadata = dataset.metadata().clone();
+        metadata.width = bands.first().map_or(0, Vec::len) as u32;
+        metadata.height = 1;
+        Ok(MultiBandImage {
+            metadata,
+            bands,
+            data_type: dataset.layout().data_type,
+        })
+    }
+}
+
+impl Default for KMeans {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnsupervisedClassifier for KMeans {
+    fn classify(&mut self, image: &MultiBandImage, num_classes: usize) -> Result<ClassificationResult> {
+        if num_classes == 0 {
+            return Err(ImageryError::Classification("Number of classes must be > 0".to_string()));
+        }
+
+        log::info!("Starting K-Means classification with {} classes", num_classes);
+
+        let centers = self.fit(image, num_classes);
+
         // Final assignment
         let labels = Self::assign_clusters(image, &centers);
 
@@ -279,4 +349,28 @@ mod tests {
         assert_eq!(classification.labels.len(), 100);
         assert_eq!(classification.class_names.len(), 5);
     }
+
+    #[test]
+    fn test_kmeans_dataset_classification() {
+        let metadata = ImageMetadata {
+            width: 20,
+            height: 10,
+            bands: 1,
+            bits_per_sample: 8,
+            geo_transform: None,
+            crs: None,
+            no_data: Some(255.0),
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt8);
+        image.bands[0] = (0..200).map(|i| if i % 20 < 10 { 10.0 } else { 200.0 }).collect();
+        image.bands[0][0] = 255.0;
+        let dataset = RasterDataset::from_image(&image, 8, 8).unwrap();
+
+        let labels = KMeans::new().classify_dataset(&dataset, 2, 50).unwrap();
+        let labels = labels.to_image().unwrap();
+        assert_eq!(labels.bands[0][0], crate::classification::UNCLASSIFIED as f32);
+        assert_ne!(labels.bands[0][1], labels.bands[0][15]);
+        assert_eq!(labels.bands[0][1], labels.bands[0][189]);
+    }
 }
@@ -0,0 +1,340 @@
+//! Natively typed sample buffers
+
+use crate::error::{ImageryError, Result};
+use crate::streaming::Window;
+use crate::DataType;
+
+/// Primitive sample type that can back a [`RasterBuffer`]
+pub trait RasterSample: Copy + Default + PartialEq + Send + Sync + 'static {
+    /// Data type tag of the sample
+    const DATA_TYPE: DataType;
+
+    /// Widen the sample to `f64`
+    fn to_f64(self) -> f64;
+
+    /// Narrow a value to the sample type, rounding and saturating integers
+    fn from_f64(value: f64) -> Self;
+
+    /// Decode a sample from little-endian bytes
+    fn from_le(bytes: &[u8]) -> Self;
+
+    /// Append the little-endian encoding of the sample
+    fn write_le(self, out: &mut Vec<u8>);
+
+    /// Wrap a vector of samples as a buffer
+    fn wrap(data: Vec<Self>) -> RasterBuffer;
+
+    /// Borrow the samples of a buffer of this type
+    fn slice(buffer: &RasterBuffer) -> Option<&[Self]>;
+
+    /// Mutably borrow the samples of a buffer of this type
+    fn slice_mut(buffer: &mut RasterBuffer) -> Option<&mut [Self]>;
+}
+
+macro_rules! raster_sample {
+    ($ty:ty, $variant:ident, $from:expr) => {
+        impl RasterSample for $ty {
+            const DATA_TYPE: DataType = DataType::$variant;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(value: f64) -> Self {
+                let convert: fn(f64) -> Self = $from;
+                convert(value)
+            }
+
+            fn from_le(bytes: &[u8]) -> Self {
+                let mut raw = [0u8; std::mem::size_of::<$ty>()];
+                raw.copy_from_slice(&bytes[..std::mem::size_of::<$ty>()]);
+                <$ty>::from_le_bytes(raw)
+            }
+
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn wrap(data: Vec<Self>) -> RasterBuffer {
+                RasterBuffer::$variant(data)
+            }
+
+            fn slice(buffer: &RasterBuffer) -> Option<&[Self]> {
+                match buffer {
+                    RasterBuffer::$variant(data) => Some(data),
+                    _ => None,
+                }
+            }
+
+            fn slice_mut(buffer: &mut RasterBuffer) -> Option<&mut [Self]> {
+                match buffer {
+                    RasterBuffer::$variant(data) => Some(data),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+// `as` casts from f64 saturate at the integer bounds and map NaN to zero
+raster_sample!(u8, UInt8, |v| v.round() as u8);
+raster_sample!(u16, UInt16, |v| v.round() as u16);
+raster_sample!(i16, Int16, |v| v.round() as i16);
+raster_sample!(u32, UInt32, |v| v.round() as u32);
+raster_sample!(i32, Int32, |v| v.round() as i32);
+raster_sample!(f32, Float32, |v| v as f32);
+raster_sample!(f64, Float64, |v| v);
+
+/// Samples of one band stored in their native type
+#[derive(Debug, Clone, PartialEq)]
+pub enum RasterBuffer {
+    /// Unsigned 8-bit samples
+    UInt8(Vec<u8>),
+    /// Unsigned 16-bit samples
+    UInt16(Vec<u16>),
+    /// Signed 16-bit samples
+    Int16(Vec<i16>),
+    /// Unsigned 32-bit samples
+    UInt32(Vec<u32>),
+    /// Signed 32-bit samples
+    Int32(Vec<i32>),
+    /// 32-bit floating point samples
+    Float32(Vec<f32>),
+    /// 64-bit floating point samples
+    Float64(Vec<f64>),
+}
+
+/// Run `$body` with `$data` bound to the typed vector of any variant
+macro_rules! with_samples {
+    ($buffer:expr, $data:ident => $body:expr) => {
+        match $buffer {
+            RasterBuffer::UInt8($data) => $body,
+            RasterBuffer::UInt16($data) => $body,
+            RasterBuffer::Int16($data) => $body,
+            RasterBuffer::UInt32($data) => $body,
+            RasterBuffer::Int32($data) => $body,
+            RasterBuffer::Float32($data) => $body,
+            RasterBuffer::Float64($data) => $body,
+        }
+    };
+}
+
+/// Build a buffer of `$data_type` from an iterator expression over sample type `$t`
+macro_rules! build_typed {
+    ($data_type:expr, $t:ident => $body:expr) => {
+        match $data_type {
+            DataType::UInt8 => {
+                type $t = u8;
+                RasterBuffer::UInt8($body)
+            }
+            DataType::UInt16 => {
+                type $t = u16;
+                RasterBuffer::UInt16($body)
+            }
+            DataType::Int16 => {
+                type $t = i16;
+                RasterBuffer::Int16($body)
+            }
+            DataType::UInt32 => {
+                type $t = u32;
+                RasterBuffer::UInt32($body)
+            }
+            DataType::Int32 => {
+                type $t = i32;
+                RasterBuffer::Int32($body)
+            }
+            DataType::Float32 => {
+                type $t = f32;
+                RasterBuffer::Float32($body)
+            }
+            DataType::Float64 => {
+                type $t = f64;
+                RasterBuffer::Float64($body)
+            }
+        }
+    };
+}
+
+impl RasterBuffer {
+    /// Create a zero-filled buffer
+    pub fn new(data_type: DataType, len: usize) -> Self {
+        build_typed!(data_type, T => vec![T::default(); len])
+    }
+
+    /// Create a buffer with every sample set to `value`
+    pub fn filled(data_type: DataType, len: usize, value: f64) -> Self {
+        build_typed!(data_type, T => vec![<T as RasterSample>::from_f64(value); len])
+    }
+
+    /// Convert `f32` samples to a buffer of the given type
+    pub fn from_f32(data_type: DataType, values: &[f32]) -> Self {
+        build_typed!(data_type, T => values.iter().map(|&v| <T as RasterSample>::from_f64(v as f64)).collect())
+    }
+
+    /// Decode a buffer from little-endian bytes
+    pub fn from_le_bytes(data_type: DataType, bytes: &[u8]) -> Self {
+        build_typed!(data_type, T => bytes
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(<T as RasterSample>::from_le)
+            .collect())
+    }
+
+    /// Data type of the samples
+    pub fn data_type(&self) -> DataType {
+        match self {
+            RasterBuffer::UInt8(_) => DataType::UInt8,
+            RasterBuffer::UInt16(_) => DataType::UInt16,
+            RasterBuffer::Int16(_) => DataType::Int16,
+            RasterBuffer::UInt32(_) => DataType::UInt32,
+            RasterBuffer::Int32(_) => DataType::Int32,
+            RasterBuffer::Float32(_) => DataType::Float32,
+            RasterBuffer::Float64(_) => DataType::Float64,
+        }
+    }
+
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        with_samples!(self, data => data.len())
+    }
+
+    /// Whether the buffer holds no samples
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Memory used by the samples
+    pub fn size_in_bytes(&self) -> usize {
+        self.len() * self.data_type().size()
+    }
+
+    /// Sample at `index` widened to `f64`
+    pub fn get(&self, index: usize) -> Option<f64> {
+        with_samples!(self, data => data.get(index).map(|&v| RasterSample::to_f64(v)))
+    }
+
+    /// Set the sample at `index`, converting to the buffer type
+    pub fn set(&mut self, index: usize, value: f64) {
+        with_samples!(self, data => {
+            if let Some(slot) = data.get_mut(index) {
+                *slot = RasterSample::from_f64(value);
+            }
+        })
+    }
+
+    /// Borrow the samples as a typed slice
+    pub fn as_slice<T: RasterSample>(&self) -> Option<&[T]> {
+        T::slice(self)
+    }
+
+    /// Mutably borrow the samples as a typed slice
+    pub fn as_mut_slice<T: RasterSample>(&mut self) -> Option<&mut [T]> {
+        T::slice_mut(self)
+    }
+
+    /// Widen every sample to `f32`
+    pub fn to_f32(&self) -> Vec<f32> {
+        with_samples!(self, data => data.iter().map(|&v| RasterSample::to_f64(v) as f32).collect())
+    }
+
+    /// Little-endian encoding of the samples
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.size_in_bytes());
+        with_samples!(self, data => data.iter().for_each(|&v| v.write_le(&mut out)));
+        out
+    }
+
+    /// Split pixel-interleaved samples into one buffer per band
+    pub fn deinterleave(&self, bands: usize) -> Vec<RasterBuffer> {
+        let bands = bands.max(1);
+        with_samples!(self, data => (0..bands)
+            .map(|band| RasterSample::wrap(data.iter().skip(band).step_by(bands).copied().collect()))
+            .collect())
+    }
+
+    /// Copy a region of a row-major buffer into a new buffer
+    pub fn crop(&self, width: u32, region: &Window) -> RasterBuffer {
+        let mut out = RasterBuffer::new(self.data_type(), region.area());
+        let _ = out.copy_region(region.width, (0, 0), self, width, region);
+        out
+    }
+
+    /// Copy `region` of `src` into this buffer with its top-left corner at `origin`
+    ///
+    /// Both buffers are row-major with widths `width` and `src_width`. The
+    /// region is clipped against both buffers.
+    pub fn copy_region(
+        &mut self,
+        width: u32,
+        origin: (u32, u32),
+        src: &RasterBuffer,
+        src_width: u32,
+        region: &Window,
+    ) -> Result<()> {
+        if self.data_type() != src.data_type() {
+            return Err(ImageryError::Processing(format!(
+                "Cannot copy {:?} samples into a {:?} buffer",
+                src.data_type(),
+                self.data_type()
+            )));
+        }
+        if width == 0 || src_width == 0 {
+            return Ok(());
+        }
+
+        let dst_height = (self.len() / width as usize) as u32;
+        let src_height = (src.len() / src_width as usize) as u32;
+        let span = region
+            .width
+            .min(src_width.saturating_sub(region.x))
+            .min(width.saturating_sub(origin.0)) as usize;
+        let rows = region
+            .height
+            .min(src_height.saturating_sub(region.y))
+            .min(dst_height.saturating_sub(origin.1));
+        if span == 0 {
+            return Ok(());
+        }
+
+        with_samples!(self, dst => {
+            let src = RasterSample::slice(src).expect("data types checked above");
+            for row in 0..rows {
+                let from = ((region.y + row) * src_width + region.x) as usize;
+                let to = ((origin.1 + row) * width + origin.0) as usize;
+                dst[to..to + span].copy_from_slice(&src[from..from + span]);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_conversions() {
+        let buffer = RasterBuffer::from_f32(DataType::UInt8, &[-3.0, 12.6, 300.0, f32::NAN]);
+        assert_eq!(buffer, RasterBuffer::UInt8(vec![0, 13, 255, 0]));
+        assert_eq!(buffer.size_in_bytes(), 4);
+
+        let wide = RasterBuffer::from_f32(DataType::Int16, &[-1.0, 2.0]);
+        let bytes = wide.to_le_bytes();
+        assert_eq!(RasterBuffer::from_le_bytes(DataType::Int16, &bytes), wide);
+        assert_eq!(wide.as_slice::<i16>(), Some(&[-1i16, 2][..]));
+        assert!(wide.as_slice::<u16>().is_none());
+    }
+
+    #[test]
+    fn test_copy_region_and_crop() {
+        let src = RasterBuffer::UInt16((0..16).collect());
+        let crop = src.crop(4, &Window::new(1, 1, 2, 2));
+        assert_eq!(crop, RasterBuffer::UInt16(vec![5, 6, 9, 10]));
+
+        let mut dst = RasterBuffer::new(DataType::UInt16, 9);
+        dst.copy_region(3, (1, 1), &src, 4, &Window::new(2, 2, 4, 4)).unwrap();
+        assert_eq!(dst, RasterBuffer::UInt16(vec![0, 0, 0, 0, 10, 11, 0, 14, 15]));
+
+        let mut wrong = RasterBuffer::new(DataType::Float32, 4);
+        assert!(wrong.copy_region(2, (0, 0), &src, 4, &Window::new(0, 0, 2, 2)).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Shared least-recently-used block cache
+
+use super::buffer::RasterBuffer;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Default cache budget shared by datasets that are not given one
+pub const DEFAULT_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Identity of one cached band block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockKey {
+    /// Dataset the block belongs to
+    pub dataset: u64,
+    /// Band index
+    pub band: usize,
+    /// Block column
+    pub block_x: u32,
+    /// Block row
+    pub block_y: u32,
+}
+
+/// Counters describing cache effectiveness
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that had to read from the source
+    pub misses: u64,
+    /// Blocks currently cached
+    pub entries: usize,
+    /// Bytes currently cached
+    pub bytes: usize,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<BlockKey, (Arc<RasterBuffer>, u64)>,
+    recency: BTreeMap<u64, BlockKey>,
+    tick: u64,
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+/// Byte-bounded LRU cache of decoded blocks
+///
+/// One cache can be shared by many datasets so that the total memory spent
+/// on decoded blocks stays under a single budget.
+pub struct BlockCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+impl BlockCache {
+    /// Create a cache holding at most `capacity` bytes of samples
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Create a cache ready to be shared between datasets
+    pub fn shared(capacity: usize) -> Arc<Self> {
+        Arc::new(Self::new(capacity))
+    }
+
+    /// Byte budget of the cache
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Look up a block, marking it as recently used
+    pub fn get(&self, key: &BlockKey) -> Option<Arc<RasterBuffer>> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let found = state.entries.get_mut(key).map(|(block, used)| {
+            let previous = std::mem::replace(used, tick);
+            (Arc::clone(block), previous)
+        });
+
+        match found {
+            Some((block, previous)) => {
+                state.recency.remove(&previous);
+                state.recency.insert(tick, *key);
+                state.hits += 1;
+                Some(block)
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Insert a block, evicting the least recently used ones to stay in budget
+    ///
+    /// Blocks larger than the whole budget are not cached.
+    pub fn insert(&self, key: BlockKey, block: Arc<RasterBuffer>) {
+        let size = block.size_in_bytes();
+        if size > self.capacity {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some((old, used)) = state.entries.insert(key, (block, tick)) {
+            state.bytes -= old.size_in_bytes();
+            state.recency.remove(&used);
+        }
+        state.recency.insert(tick, key);
+        state.bytes += size;
+
+        while state.bytes > self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = state.entries.remove(&oldest) {
+                state.bytes -= evicted.size_in_bytes();
+            }
+        }
+    }
+
+    /// Drop every cached block of a dataset
+    pub fn evict_dataset(&self, dataset: u64) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let mut freed = 0;
+        state.entries.retain(|key, (block, used)| {
+            if key.dataset != dataset {
+                return true;
+            }
+            freed += block.size_in_bytes();
+            state.recency.remove(used);
+            false
+        });
+        state.bytes -= freed;
+    }
+
+    /// Drop every cached block
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.recency.clear();
+        state.bytes = 0;
+    }
+
+    /// Current hit, miss and occupancy counters
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+            bytes: state.bytes,
+        }
+    }
+}
+
+impl Default for BlockCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(block_x: u32) -> BlockKey {
+        BlockKey {
+            dataset: 1,
+            band: 0,
+            block_x,
+            block_y: 0,
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction_by_bytes() {
+        let cache = BlockCache::new(8);
+        for x in 0..2 {
+            cache.insert(key(x), Arc::new(RasterBuffer::UInt8(vec![x as u8; 4])));
+        }
+        // Touch block 0 so block 1 becomes the eviction candidate
+        assert!(cache.get(&key(0)).is_some());
+        cache.insert(key(2), Arc::new(RasterBuffer::UInt8(vec![2; 4])));
+
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(2)).is_some());
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.bytes, 8);
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+
+        cache.insert(key(3), Arc::new(RasterBuffer::UInt8(vec![0; 16])));
+        assert!(cache.get(&key(3)).is_none());
+        cache.evict_dataset(1);
+        assert_eq!(cache.stats().bytes, 0);
+    }
+}
@@ -0,0 +1,407 @@
+//! Lazy, block-cached raster datasets
+//!
+//! A [`RasterDataset`] describes a raster without loading it. Samples stay in
+//! their native type and are read one block at a time from a [`BlockSource`],
+//! with decoded blocks kept in a shared, byte-bounded [`BlockCache`]. Windowed
+//! reads assemble only the blocks they touch, so processing a scene costs a
+//! few blocks of memory rather than the whole image.
+
+pub mod buffer;
+pub mod cache;
+pub mod source;
+pub mod store;
+
+pub use buffer::{RasterBuffer, RasterSample};
+pub use cache::{BlockCache, BlockKey, CacheStats, DEFAULT_CACHE_BYTES};
+pub use source::{BlockSource, CogBlockSource, MemoryBlockSource, RasterLayout};
+pub use store::DiskBlockStore;
+
+use crate::error::{ImageryError, Result};
+use crate::streaming::{StreamingReader, Window};
+use crate::{ImageMetadata, MultiBandImage};
+use rayon::prelude::*;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+static NEXT_DATASET_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Cache used by datasets created without an explicit one
+fn global_cache() -> Arc<BlockCache> {
+    static CACHE: OnceLock<Arc<BlockCache>> = OnceLock::new();
+    Arc::clone(CACHE.get_or_init(|| Arc::new(BlockCache::default())))
+}
+
+/// Lazily read raster with typed, cached blocks
+#[derive(Clone)]
+pub struct RasterDataset {
+    id: u64,
+    source: Arc<dyn BlockSource>,
+    cache: Arc<BlockCache>,
+    mask: Option<Arc<RasterDataset>>,
+}
+
+impl RasterDataset {
+    /// Wrap a block source, sharing the process-wide block cache
+    pub fn new(source: impl BlockSource + 'static) -> Self {
+        Self {
+            id: NEXT_DATASET_ID.fetch_add(1, Ordering::Relaxed),
+            source: Arc::new(source),
+            cache: global_cache(),
+            mask: None,
+        }
+    }
+
+    /// Open a local COG, reading its tiles on demand
+    pub fn open_cog(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(CogBlockSource::open(path)?))
+    }
+
+    /// Wrap an in-memory image, converting it to its native data type
+    pub fn from_image(image: &MultiBandImage, block_width: u32, block_height: u32) -> Result<Self> {
+        Ok(Self::new(MemoryBlockSource::from_image(image, block_width, block_height)?))
+    }
+
+    /// Use a specific block cache
+    pub fn with_cache(mut self, cache: Arc<BlockCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Attach a mask band; pixels where it is zero are treated as invalid
+    pub fn with_mask_band(mut self, mask: RasterDataset) -> Result<Self> {
+        if mask.dimensions() != self.dimensions() {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Mask is {:?} but the dataset is {:?}",
+                mask.dimensions(),
+                self.dimensions()
+            )));
+        }
+        self.mask = Some(Arc::new(mask));
+        Ok(self)
+    }
+
+    /// Layout of the raster
+    pub fn layout(&self) -> &RasterLayout {
+        self.source.layout()
+    }
+
+    /// Raster metadata
+    pub fn metadata(&self) -> &ImageMetadata {
+        &self.layout().metadata
+    }
+
+    /// Raster width and height
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.layout().dimensions()
+    }
+
+    /// Number of bands
+    pub fn band_count(&self) -> usize {
+        self.layout().bands()
+    }
+
+    /// Block cache counters
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Read one band of a block, from the cache when possible
+    pub fn read_block(&self, band: usize, block_x: u32, block_y: u32) -> Result<Arc<RasterBuffer>> {
+        let mut blocks = self.read_block_bands(block_x, block_y, &[band])?;
+        Ok(blocks.remove(0))
+    }
+
+    /// Read several bands of a block, fetching the uncached ones in one call
+    pub fn read_block_bands(&self, block_x: u32, block_y: u32, bands: &[usize]) -> Result<Vec<Arc<RasterBuffer>>> {
+        let key = |band| BlockKey {
+            dataset: self.id,
+            band,
+            block_x,
+            block_y,
+        };
+        let mut blocks: Vec<Option<Arc<RasterBuffer>>> = bands.iter().map(|&band| self.cache.get(&key(band))).collect();
+
+        let missing: Vec<usize> = bands
+            .iter()
+            .zip(&blocks)
+            .filter(|(_, block)| block.is_none())
+            .map(|(&band, _)| band)
+            .collect();
+        if !missing.is_empty() {
+            let mut fetched = self.source.read_block(block_x, block_y, &missing)?.into_iter();
+            for (slot, &band) in blocks.iter_mut().zip(bands) {
+                if slot.is_none() {
+                    let block = Arc::new(fetched.next().ok_or_else(|| {
+                        ImageryError::processing("Block source returned fewer bands than requested")
+                    })?);
+                    self.cache.insert(key(band), Arc::clone(&block));
+                    *slot = Some(block);
+                }
+            }
+        }
+
+        Ok(blocks.into_iter().flatten().collect())
+    }
+
+    /// Read a window of one band in its native type
+    pub fn read_band(&self, band: usize, window: &Window) -> Result<RasterBuffer> {
+        Ok(self.read_bands(&[band], window)?.remove(0))
+    }
+
+    /// Read a window of several bands in their native type
+    pub fn read_bands(&self, bands: &[usize], window: &Window) -> Result<Vec<RasterBuffer>> {
+        let layout = self.layout();
+        layout.check_bands(bands)?;
+        let (width, height) = layout.dimensions();
+        if window.area() == 0 || !window.is_valid(width, height) {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Window ({}, {}, {}, {}) exceeds raster bounds ({}, {})",
+                window.x, window.y, window.width, window.height, width, height
+            )));
+        }
+
+        let mut out = vec![RasterBuffer::new(layout.data_type, window.area()); bands.len()];
+        let (bw, bh) = (layout.block_width, layout.block_height);
+        for block_y in window.y / bh..=(window.y + window.height - 1) / bh {
+            for block_x in window.x / bw..=(window.x + window.width - 1) / bw {
+                let block = layout.block_window(block_x, block_y);
+                let Some(overlap) = block.overlap(window) else {
+                    continue;
+                };
+                let region = Window::new(overlap.x - block.x, overlap.y - block.y, overlap.width, overlap.height);
+                let origin = (overlap.x - window.x, overlap.y - window.y);
+
+                let samples = self.read_block_bands(block_x, block_y, bands)?;
+                for (dst, src) in out.iter_mut().zip(&samples) {
+                    dst.copy_region(window.width, origin, src, block.width, &region)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Read a window of every band as an `f32` image
+    pub fn read_image(&self, window: &Window) -> Result<MultiBandImage> {
+        let bands: Vec<usize> = (0..self.band_count()).collect();
+        let buffers = self.read_bands(&bands, window)?;
+
+        let metadata = self.metadata();
+        let mut window_meta = metadata.clone();
+        window_meta.width = window.width;
+        window_meta.height = window.height;
+        window_meta.geo_transform = metadata.geo_transform.map(|gt| {
+            let (px, py) = (window.x as f64, window.y as f64);
+            [
+                gt[0] + px * gt[1] + py * gt[2],
+                gt[1],
+                gt[2],
+                gt[3] + px * gt[4] + py * gt[5],
+                gt[4],
+                gt[5],
+            ]
+        });
+
+        Ok(MultiBandImage {
+            metadata: window_meta,
+            bands: buffers.iter().map(RasterBuffer::to_f32).collect(),
+            data_type: self.layout().data_type,
+        })
+    }
+
+    /// Materialize the whole raster
+    pub fn to_image(&self) -> Result<MultiBandImage> {
+        let (width, height) = self.dimensions();
+        self.read_image(&Window::new(0, 0, width, height))
+    }
+
+    /// Validity of each pixel of one band in a window
+    ///
+    /// A pixel is invalid when it equals the nodata value, is NaN, or the
+    /// mask band is zero there.
+    pub fn band_mask(&self, band: usize, window: &Window) -> Result<Vec<bool>> {
+        let mut valid = self.mask_band_validity(window)?;
+        let samples = self.read_band(band, window)?;
+        let no_data = self.metadata().no_data;
+        for (index, flag) in valid.iter_mut().enumerate() {
+            let value = samples.get(index).unwrap_or(f64::NAN);
+            *flag &= !value.is_nan() && no_data != Some(value);
+        }
+        Ok(valid)
+    }
+
+    /// Pixels of a window that are valid in every band
+    pub fn valid_mask(&self, window: &Window) -> Result<Vec<bool>> {
+        let mut valid = self.mask_band_validity(window)?;
+        let bands: Vec<usize> = (0..self.band_count()).collect();
+        let no_data = self.metadata().no_data;
+        for samples in self.read_bands(&bands, window)? {
+            for (index, flag) in valid.iter_mut().enumerate() {
+                let value = samples.get(index).unwrap_or(f64::NAN);
+                *flag &= !value.is_nan() && no_data != Some(value);
+            }
+        }
+        Ok(valid)
+    }
+
+    fn mask_band_validity(&self, window: &Window) -> Result<Vec<bool>> {
+        match &self.mask {
+            Some(mask) => {
+                let samples = mask.read_band(0, window)?;
+                Ok((0..window.area()).map(|i| samples.get(i).is_some_and(|v| v != 0.0)).collect())
+            }
+            None => Ok(vec![true; window.area()]),
+        }
+    }
+
+    /// Apply `f` to every block in parallel, storing the results on disk
+    ///
+    /// `f` receives each block as an `f32` image and must return an image of
+    /// the same size with the bands of `output`. Blocks are processed in
+    /// batches sized to the thread pool so only a few are in memory at once.
+    /// The result shares this dataset's cache.
+    pub fn map_blocks<F>(&self, output: RasterLayout, f: F) -> Result<RasterDataset>
+    where
+        F: Fn(&MultiBandImage, &Window) -> Result<MultiBandImage> + Sync,
+    {
+        let layout = self.layout();
+        if output.dimensions() != layout.dimensions()
+            || (output.block_width, output.block_height) != (layout.block_width, layout.block_height)
+        {
+            return Err(ImageryError::InvalidDimensions(
+                "Output layout must share the dataset's size and block grid".to_string()
+            ));
+        }
+
+        let bands = output.bands();
+        let data_type = output.data_type;
+        let store = DiskBlockStore::create(output)?;
+        let blocks = layout.blocks();
+        let batch = rayon::current_num_threads().max(1) * 2;
+
+        for chunk in blocks.chunks(batch) {
+            let results = chunk
+                .par_iter()
+                .map(|(block_x, block_y, window)| {
+                    let processed = f(&self.read_image(window)?, window)?;
+                    if processed.bands.len() != bands
+                        || processed.bands.iter().any(|band| band.len() != window.area())
+                    {
+                        return Err(ImageryError::InvalidDimensions(format!(
+                            "Block processor must return {} bands of {} pixels",
+                            bands,
+                            window.area()
+                        )));
+                    }
+                    Ok((*block_x, *block_y, processed))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            for (block_x, block_y, processed) in results {
+                for (band, samples) in processed.bands.iter().enumerate() {
+                    store.write_block(band, block_x, block_y, &RasterBuffer::from_f32(data_type, samples))?;
+                }
+            }
+        }
+
+        Ok(store.into_dataset().with_cache(Arc::clone(&self.cache)))
+    }
+}
+
+impl StreamingReader for RasterDataset {
+    fn read_window(&mut self, window: &Window) -> Result<MultiBandImage> {
+        self.read_image(window)
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.layout().dimensions()
+    }
+
+    fn bands(&self) -> usize {
+        self.band_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataType;
+
+    fn gradient(width: u32, height: u32) -> MultiBandImage {
+        let metadata = ImageMetadata {
+            width,
+            height,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: Some([100.0, 10.0, 0.0, 200.0, 0.0, -10.0]),
+            crs: None,
+            no_data: Some(0.0),
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        let pixels = (width * height) as usize;
+        image.bands[0] = (0..pixels).map(|i| i as f32).collect();
+        image.bands[1] = (0..pixels).map(|i| (i % 7) as f32).collect();
+        image
+    }
+
+    #[test]
+    fn test_windowed_reads_match_source() {
+        let image = gradient(10, 7);
+        let dataset = RasterDataset::from_image(&image, 4, 3)
+            .unwrap()
+            .with_cache(BlockCache::shared(1 << 20));
+
+        let window = Window::new(3, 2, 5, 4);
+        let read = dataset.read_image(&window).unwrap();
+        assert_eq!(read.metadata.geo_transform.unwrap()[0], 130.0);
+        for y in 0..4 {
+            for x in 0..5 {
+                let expected = image.get_pixel(0, 3 + x, 2 + y);
+                assert_eq!(read.get_pixel(0, x, y), expected);
+            }
+        }
+
+        let typed = dataset.read_band(1, &window).unwrap();
+        assert_eq!(typed.data_type(), DataType::UInt16);
+
+        // The second pass over the same blocks is served from the cache
+        let before = dataset.cache_stats();
+        dataset.read_image(&window).unwrap();
+        let after = dataset.cache_stats();
+        assert_eq!(after.misses, before.misses);
+        assert!(after.hits > before.hits);
+    }
+
+    #[test]
+    fn test_masks_and_map_blocks() {
+        let image = gradient(6, 5);
+        let mut mask_image = gradient(6, 5);
+        mask_image.metadata.bands = 1;
+        mask_image.bands = vec![(0..30).map(|i| if i == 29 { 0.0 } else { 1.0 }).collect()];
+        let mask = RasterDataset::from_image(&mask_image, 4, 4).unwrap();
+        let dataset = RasterDataset::from_image(&image, 4, 4)
+            .unwrap()
+            .with_mask_band(mask)
+            .unwrap();
+
+        let full = Window::new(0, 0, 6, 5);
+        let valid = dataset.valid_mask(&full).unwrap();
+        // Pixel 0 is nodata in both bands, pixel 7 only in the second, 29 is masked
+        assert!(!valid[0] && !valid[7] && !valid[29]);
+        assert!(valid[1]);
+        assert!(dataset.band_mask(0, &full).unwrap()[7]);
+
+        let layout = dataset.layout().derive(1, DataType::Float32);
+        let doubled = dataset
+            .map_blocks(layout, |block, _| {
+                let mut out = block.clone();
+                out.bands = vec![block.bands[0].iter().map(|v| v * 2.0).collect()];
+                Ok(out)
+            })
+            .unwrap();
+        let result = doubled.to_image().unwrap();
+        assert_eq!(result.data_type, DataType::Float32);
+        assert_eq!(result.bands[0][13], 26.0);
+    }
+}
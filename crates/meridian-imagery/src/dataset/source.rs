@@ -0,0 +1,287 @@
+//! Block layouts and the sources that serve them
+
+use super::buffer::RasterBuffer;
+use crate::error::{ImageryError, Result};
+use crate::format::{CogReader, ImageReader};
+use crate::streaming::Window;
+use crate::{DataType, ImageMetadata, MultiBandImage};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Shape, sample type and block grid of a raster
+#[derive(Debug, Clone)]
+pub struct RasterLayout {
+    /// Raster metadata
+    pub metadata: ImageMetadata,
+    /// Native sample type of every band
+    pub data_type: DataType,
+    /// Block width in pixels
+    pub block_width: u32,
+    /// Block height in pixels
+    pub block_height: u32,
+}
+
+impl RasterLayout {
+    /// Create a layout, clamping the block size to the raster size
+    pub fn new(metadata: ImageMetadata, data_type: DataType, block_width: u32, block_height: u32) -> Self {
+        let block_width = block_width.clamp(1, metadata.width.max(1));
+        let block_height = block_height.clamp(1, metadata.height.max(1));
+        Self {
+            metadata,
+            data_type,
+            block_width,
+            block_height,
+        }
+    }
+
+    /// Layout for derived output with the same grid and georeferencing
+    ///
+    /// Band names and the nodata value are cleared since they rarely carry
+    /// over to a different number or type of bands.
+    pub fn derive(&self, bands: u32, data_type: DataType) -> Self {
+        let mut metadata = self.metadata.clone();
+        metadata.bands = bands;
+        metadata.bits_per_sample = (data_type.size() * 8) as u16;
+        metadata.no_data = None;
+        metadata.band_names = Vec::new();
+        Self {
+            metadata,
+            data_type,
+            block_width: self.block_width,
+            block_height: self.block_height,
+        }
+    }
+
+    /// Set the nodata value
+    pub fn with_no_data(mut self, no_data: f64) -> Self {
+        self.metadata.no_data = Some(no_data);
+        self
+    }
+
+    /// Set the band names
+    pub fn with_band_names(mut self, names: Vec<String>) -> Self {
+        self.metadata.band_names = names;
+        self
+    }
+
+    /// Raster width and height
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.metadata.width, self.metadata.height)
+    }
+
+    /// Number of bands
+    pub fn bands(&self) -> usize {
+        self.metadata.bands as usize
+    }
+
+    /// Number of block columns and rows
+    pub fn block_grid(&self) -> (u32, u32) {
+        (
+            self.metadata.width.div_ceil(self.block_width),
+            self.metadata.height.div_ceil(self.block_height),
+        )
+    }
+
+    /// Pixel window of a block, clipped to the raster extent
+    pub fn block_window(&self, block_x: u32, block_y: u32) -> Window {
+        let x = block_x * self.block_width;
+        let y = block_y * self.block_height;
+        Window::new(
+            x,
+            y,
+            self.block_width.min(self.metadata.width.saturating_sub(x)),
+            self.block_height.min(self.metadata.height.saturating_sub(y)),
+        )
+    }
+
+    /// Every block of the grid in row-major order
+    pub fn blocks(&self) -> Vec<(u32, u32, Window)> {
+        let (across, down) = self.block_grid();
+        (0..down)
+            .flat_map(|by| (0..across).map(move |bx| (bx, by)))
+            .map(|(bx, by)| (bx, by, self.block_window(bx, by)))
+            .collect()
+    }
+
+    pub(crate) fn check_block(&self, block_x: u32, block_y: u32) -> Result<()> {
+        let (across, down) = self.block_grid();
+        if block_x >= across || block_y >= down {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Block ({}, {}) outside the {}x{} block grid",
+                block_x, block_y, across, down
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_bands(&self, bands: &[usize]) -> Result<()> {
+        match bands.iter().find(|&&band| band >= self.bands()) {
+            Some(&band) => Err(ImageryError::InvalidBand {
+                band,
+                total: self.bands(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Random access to the blocks of a raster
+///
+/// Blocks on the right and bottom edges are clipped to the raster extent, so
+/// a block holds `block_window(bx, by).area()` samples per band.
+pub trait BlockSource: Send + Sync {
+    /// Layout of the raster
+    fn layout(&self) -> &RasterLayout;
+
+    /// Read the requested bands of one block
+    fn read_block(&self, block_x: u32, block_y: u32, bands: &[usize]) -> Result<Vec<RasterBuffer>>;
+}
+
+/// Block source over buffers already held in memory
+pub struct MemoryBlockSource {
+    layout: RasterLayout,
+    bands: Vec<RasterBuffer>,
+}
+
+impl MemoryBlockSource {
+    /// Wrap whole-raster band buffers
+    pub fn new(layout: RasterLayout, bands: Vec<RasterBuffer>) -> Result<Self> {
+        let pixels = layout.metadata.width as usize * layout.metadata.height as usize;
+        if bands.len() != layout.bands() {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Layout has {} bands but {} buffers were given",
+                layout.bands(),
+                bands.len()
+            )));
+        }
+        if let Some(band) = bands
+            .iter()
+            .find(|band| band.len() != pixels || band.data_type() != layout.data_type)
+        {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Expected {} {:?} samples per band, got {} {:?}",
+                pixels,
+                layout.data_type,
+                band.len(),
+                band.data_type()
+            )));
+        }
+        Ok(Self { layout, bands })
+    }
+
+    /// Convert an image to its native data type, split into blocks
+    pub fn from_image(image: &MultiBandImage, block_width: u32, block_height: u32) -> Result<Self> {
+        let layout = RasterLayout::new(image.metadata.clone(), image.data_type, block_width, block_height);
+        let bands = image
+            .bands
+            .iter()
+            .map(|band| RasterBuffer::from_f32(image.data_type, band))
+            .collect();
+        Self::new(layout, bands)
+    }
+}
+
+impl BlockSource for MemoryBlockSource {
+    fn layout(&self) -> &RasterLayout {
+        &self.layout
+    }
+
+    fn read_block(&self, block_x: u32, block_y: u32, bands: &[usize]) -> Result<Vec<RasterBuffer>> {
+        self.layout.check_block(block_x, block_y)?;
+        self.layout.check_bands(bands)?;
+        let window = self.layout.block_window(block_x, block_y);
+        Ok(bands
+            .iter()
+            .map(|&band| self.bands[band].crop(self.layout.metadata.width, &window))
+            .collect())
+    }
+}
+
+/// Block source reading the tiles of one COG level on demand
+///
+/// Blocks are the tiles of the level, so each block read decodes exactly one
+/// tile and fetches only its bytes from the underlying range source.
+pub struct CogBlockSource {
+    layout: RasterLayout,
+    overview: usize,
+    reader: Mutex<CogReader>,
+}
+
+impl CogBlockSource {
+    /// Open the full-resolution level of a local COG
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(CogReader::open(path)?, 0)
+    }
+
+    /// Serve the blocks of a level of an opened reader
+    pub fn new(reader: CogReader, overview: usize) -> Result<Self> {
+        let (width, height) = reader.overview_size(overview).ok_or_else(|| {
+            ImageryError::InvalidParameter(format!("Overview {} does not exist", overview))
+        })?;
+        let (tile_width, tile_height) = reader.overview_tile_size(overview).unwrap_or(reader.tile_size());
+
+        let full = reader.metadata();
+        let mut metadata = full.clone();
+        metadata.width = width;
+        metadata.height = height;
+        metadata.geo_transform = full.geo_transform.map(|gt| {
+            let sx = full.width as f64 / width as f64;
+            let sy = full.height as f64 / height as f64;
+            [gt[0], gt[1] * sx, gt[2] * sy, gt[3], gt[4] * sx, gt[5] * sy]
+        });
+
+        Ok(Self {
+            layout: RasterLayout::new(metadata, reader.data_type(), tile_width, tile_height),
+            overview,
+            reader: Mutex::new(reader),
+        })
+    }
+}
+
+impl BlockSource for CogBlockSource {
+    fn layout(&self) -> &RasterLayout {
+        &self.layout
+    }
+
+    fn read_block(&self, block_x: u32, block_y: u32, bands: &[usize]) -> Result<Vec<RasterBuffer>> {
+        self.layout.check_bands(bands)?;
+        let mut tile = self
+            .reader
+            .lock()
+            .unwrap()
+            .read_tile_buffers(block_x, block_y, self.overview)?;
+        Ok(bands
+            .iter()
+            .map(|&band| std::mem::replace(&mut tile[band], RasterBuffer::UInt8(Vec::new())))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_source_edge_blocks() {
+        let metadata = ImageMetadata {
+            width: 5,
+            height: 3,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: None,
+            crs: None,
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        image.bands[1] = (0..15).map(|v| v as f32).collect();
+
+        let source = MemoryBlockSource::from_image(&image, 4, 2).unwrap();
+        assert_eq!(source.layout().block_grid(), (2, 2));
+
+        let block = source.read_block(1, 1, &[1]).unwrap();
+        assert_eq!(block, vec![RasterBuffer::UInt16(vec![14])]);
+        assert!(source.read_block(2, 0, &[0]).is_err());
+        assert!(source.read_block(0, 0, &[2]).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! Disk-backed block storage for derived rasters
+
+use super::buffer::RasterBuffer;
+use super::source::{BlockSource, RasterLayout};
+use super::RasterDataset;
+use crate::error::{ImageryError, Result};
+use crate::streaming::{StreamingWriter, Window};
+use crate::MultiBandImage;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+static NEXT_STORE_ID: AtomicU64 = AtomicU64::new(0);
+
+struct StoreFile {
+    file: File,
+    written: Vec<bool>,
+}
+
+/// Scratch raster that keeps its blocks in a temporary file
+///
+/// Every block has a fixed slot in the file, so blocks can be written in any
+/// order and rewritten in place. Blocks that were never written read back as
+/// the nodata value, or zero without one. The file is removed on drop.
+pub struct DiskBlockStore {
+    layout: RasterLayout,
+    path: PathBuf,
+    slot_bytes: u64,
+    state: Mutex<StoreFile>,
+}
+
+impl DiskBlockStore {
+    /// Create a store in the system temporary directory
+    pub fn create(layout: RasterLayout) -> Result<Self> {
+        Self::create_in(layout, std::env::temp_dir())
+    }
+
+    /// Create a store in a specific directory
+    pub fn create_in(layout: RasterLayout, dir: impl AsRef<Path>) -> Result<Self> {
+        let name = format!(
+            "meridian-blocks-{}-{}.tmp",
+            std::process::id(),
+            NEXT_STORE_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = dir.as_ref().join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let (across, down) = layout.block_grid();
+        let blocks = across as usize * down as usize * layout.bands();
+        let slot_bytes = layout.block_width as u64 * layout.block_height as u64 * layout.data_type.size() as u64;
+
+        Ok(Self {
+            layout,
+            path,
+            slot_bytes,
+            state: Mutex::new(StoreFile {
+                file,
+                written: vec![false; blocks],
+            }),
+        })
+    }
+
+    /// Path of the backing file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write one band of a block
+    pub fn write_block(&self, band: usize, block_x: u32, block_y: u32, block: &RasterBuffer) -> Result<()> {
+        let slot = self.slot(band, block_x, block_y)?;
+        let expected = self.layout.block_window(block_x, block_y).area();
+        if block.len() != expected || block.data_type() != self.layout.data_type {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Block ({}, {}) needs {} {:?} samples, got {} {:?}",
+                block_x,
+                block_y,
+                expected,
+                self.layout.data_type,
+                block.len(),
+                block.data_type()
+            )));
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.file.seek(SeekFrom::Start(slot as u64 * self.slot_bytes))?;
+        state.file.write_all(&block.to_le_bytes())?;
+        state.written[slot] = true;
+        Ok(())
+    }
+
+    /// Reopen the finished store as a read-only dataset
+    pub fn into_dataset(self) -> RasterDataset {
+        RasterDataset::new(self)
+    }
+
+    fn slot(&self, band: usize, block_x: u32, block_y: u32) -> Result<usize> {
+        self.layout.check_block(block_x, block_y)?;
+        self.layout.check_bands(&[band])?;
+        let (across, down) = self.layout.block_grid();
+        Ok((band * down as usize + block_y as usize) * across as usize + block_x as usize)
+    }
+
+    fn read_slot(&self, state: &mut StoreFile, slot: usize, len: usize) -> Result<RasterBuffer> {
+        if !state.written[slot] {
+            let fill = self.layout.metadata.no_data.unwrap_or(0.0);
+            return Ok(RasterBuffer::filled(self.layout.data_type, len, fill));
+        }
+        let mut bytes = vec![0u8; len * self.layout.data_type.size()];
+        state.file.seek(SeekFrom::Start(slot as u64 * self.slot_bytes))?;
+        state.file.read_exact(&mut bytes)?;
+        Ok(RasterBuffer::from_le_bytes(self.layout.data_type, &bytes))
+    }
+}
+
+impl BlockSource for DiskBlockStore {
+    fn layout(&self) -> &RasterLayout {
+        &self.layout
+    }
+
+    fn read_block(&self, block_x: u32, block_y: u32, bands: &[usize]) -> Result<Vec<RasterBuffer>> {
+        let len = self.layout.block_window(block_x, block_y).area();
+        let slots = bands
+            .iter()
+            .map(|&band| self.slot(band, block_x, block_y))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut state = self.state.lock().unwrap();
+        slots
+            .into_iter()
+            .map(|slot| self.read_slot(&mut state, slot, len))
+            .collect()
+    }
+}
+
+impl StreamingWriter for DiskBlockStore {
+    /// Write an arbitrary window, merging it into the blocks it overlaps
+    fn write_window(&mut self, window: &Window, data: &MultiBandImage) -> Result<()> {
+        let (width, height) = self.layout.dimensions();
+        if !window.is_valid(width, height) {
+            return Err(ImageryError::InvalidDimensions(
+                "Window exceeds image bounds".to_string()
+            ));
+        }
+        if data.bands.len() != self.layout.bands()
+            || data.metadata.width != window.width
+            || data.metadata.height != window.height
+        {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Expected {} bands of {}x{}, got {} bands of {}x{}",
+                self.layout.bands(),
+                window.width,
+                window.height,
+                data.bands.len(),
+                data.metadata.width,
+                data.metadata.height
+            )));
+        }
+        if window.area() == 0 {
+            return Ok(());
+        }
+
+        let (bw, bh) = (self.layout.block_width, self.layout.block_height);
+        for block_y in window.y / bh..=(window.y + window.height - 1) / bh {
+            for block_x in window.x / bw..=(window.x + window.width - 1) / bw {
+                let block = self.layout.block_window(block_x, block_y);
+                let Some(overlap) = block.overlap(window) else {
+                    continue;
+                };
+                let region = Window::new(overlap.x - window.x, overlap.y - window.y, overlap.width, overlap.height);
+                let origin = (overlap.x - block.x, overlap.y - block.y);
+
+                for (band, samples) in data.bands.iter().enumerate() {
+                    let slot = self.slot(band, block_x, block_y)?;
+                    let mut merged = {
+                        let mut state = self.state.lock().unwrap();
+                        self.read_slot(&mut state, slot, block.area())?
+                    };
+                    let source = RasterBuffer::from_f32(self.layout.data_type, samples);
+                    merged.copy_region(block.width, origin, &source, window.width, &region)?;
+                    self.write_block(band, block_x, block_y, &merged)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        self.state.lock().unwrap().file.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for DiskBlockStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, ImageMetadata};
+
+    fn layout() -> RasterLayout {
+        let metadata = ImageMetadata {
+            width: 6,
+            height: 5,
+            bands: 1,
+            bits_per_sample: 16,
+            geo_transform: None,
+            crs: None,
+            no_data: Some(9.0),
+            band_names: vec![],
+        };
+        RasterLayout::new(metadata, DataType::Int16, 4, 4)
+    }
+
+    #[test]
+    fn test_window_writes_span_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = DiskBlockStore::create_in(layout(), dir.path()).unwrap();
+
+        let mut metadata = layout().metadata;
+        metadata.width = 3;
+        metadata.height = 2;
+        let mut patch = MultiBandImage::new(metadata, DataType::Int16);
+        patch.bands[0] = vec![-1.0, -2.0, -3.0, -4.0, -5.0, -6.0];
+        store.write_window(&Window::new(2, 3, 3, 2), &patch).unwrap();
+        store.finalize().unwrap();
+
+        let top_left = store.read_block(0, 0, &[0]).unwrap().remove(0);
+        assert_eq!(top_left.get(14), Some(-1.0));
+        assert_eq!(top_left.get(0), Some(9.0));
+        let bottom_right = store.read_block(1, 1, &[0]).unwrap().remove(0);
+        assert_eq!(bottom_right, RasterBuffer::Int16(vec![-6, 9]));
+
+        let path = store.path().to_path_buf();
+        drop(store);
+        assert!(!path.exists());
+    }
+}
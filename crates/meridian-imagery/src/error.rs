@@ -57,6 +57,15 @@ pub enum ImageryError {
     #[error("Compression error: {0}")]
     Compression(String),
 
+    /// Raster expression syntax, type or binding error
+    #[error("Expression error at column {column}: {message}")]
+    Expression {
+        /// 1-based column in the expression source
+        column: usize,
+        /// What went wrong
+        message: String,
+    },
+
     /// GDAL error
     #[cfg(feature = "gdal")]
     #[error("GDAL error: {0}")]
@@ -103,4 +112,12 @@ impl ImageryError {
     pub fn processing(msg: impl Into<String>) -> Self {
         Self::Processing(msg.into())
     }
+
+    /// Create a new expression error
+    pub fn expression(column: usize, msg: impl Into<String>) -> Self {
+        Self::Expression {
+            column,
+            message: msg.into(),
+        }
+    }
 }
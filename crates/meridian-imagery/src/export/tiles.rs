@@ -1,9 +1,20 @@
 //! Tile pyramid generation for web mapping
+//!
+//! Georeferenced images are warped into Web Mercator XYZ tiles, whatever
+//! their source CRS. Images without georeferencing are cut into a plain pixel
+//! pyramid instead.
 
 use crate::error::{ImageryError, Result};
+use crate::processing::warp::{TargetGrid, Warper};
+use crate::processing::ResamplingMethod;
 use crate::MultiBandImage;
+use meridian_core::crs::Crs;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
+/// Half the width of the Web Mercator world in meters
+const MERCATOR_EXTENT: f64 = 20_037_508.342_789_244;
+
 /// Tile pyramid generator
 pub struct TilePyramid {
     tile_size: u32,
@@ -11,6 +22,7 @@ pub struct TilePyramid {
     max_zoom: u8,
     output_dir: PathBuf,
     format: TileFormat,
+    resampling: ResamplingMethod,
 }
 
 /// Tile format
@@ -33,6 +45,7 @@ impl TilePyramid {
             max_zoom: 18,
             output_dir: output_dir.as_ref().to_path_buf(),
             format: TileFormat::Png,
+            resampling: ResamplingMethod::Bilinear,
         }
     }
 
@@ -55,8 +68,18 @@ impl TilePyramid {
         self
     }
 
+    /// Set resampling used when warping into tiles
+    pub fn with_resampling(mut self, method: ResamplingMethod) -> Self {
+        self.resampling = method;
+        self
+    }
+
     /// Generate tile pyramid
     pub fn generate(&self, image: &MultiBandImage) -> Result<TilePyramidInfo> {
+        if image.metadata.geo_transform.is_some() && image.metadata.crs.is_some() {
+            return self.generate_mosaic(std::slice::from_ref(image));
+        }
+
         log::info!(
             "Generating tile pyramid: zoom {}-{}, tile size {}",
             self.min_zoom,
@@ -85,6 +108,162 @@ impl TilePyramid {
         })
     }
 
+    /// Generate Web Mercator tiles over several georeferenced images
+    ///
+    /// Each image is warped from its own CRS into every tile it touches.
+    /// Where images overlap the earlier one wins, and pixels no image covers
+    /// are left transparent. Tiles without any data are not written.
+    pub fn generate_mosaic(&self, images: &[MultiBandImage]) -> Result<TilePyramidInfo> {
+        let Some(first) = images.first() else {
+            return Err(ImageryError::InvalidParameter("No images to tile".to_string()));
+        };
+        if let Some(image) = images.iter().find(|image| image.bands.len() != first.bands.len()) {
+            return Err(ImageryError::InvalidParameter(format!(
+                "All images need {} bands, got {}",
+                first.bands.len(),
+                image.bands.len()
+            )));
+        }
+
+        let warper = Warper::new(Crs::web_mercator())
+            .with_resampling(self.resampling)
+            .with_no_data(f64::NAN);
+        let footprints = images
+            .iter()
+            .map(|image| Ok(warper.suggest_grid(&image.metadata)?.bounds()))
+            .collect::<Result<Vec<_>>>()?;
+
+        log::info!(
+            "Generating Web Mercator tiles for {} images: zoom {}-{}, tile size {}",
+            images.len(),
+            self.min_zoom,
+            self.max_zoom,
+            self.tile_size
+        );
+        std::fs::create_dir_all(&self.output_dir)?;
+
+        let mut total_tiles = 0;
+        for zoom in self.min_zoom..=self.max_zoom {
+            let tiles_count = self.warp_zoom_level(images, &footprints, &warper, zoom)?;
+            total_tiles += tiles_count;
+            log::debug!("Zoom level {}: {} tiles", zoom, tiles_count);
+        }
+
+        Ok(TilePyramidInfo {
+            min_zoom: self.min_zoom,
+            max_zoom: self.max_zoom,
+            tile_size: self.tile_size,
+            total_tiles,
+            format: self.format,
+        })
+    }
+
+    /// Warp the images into the XYZ tiles of one zoom level
+    fn warp_zoom_level(
+        &self,
+        images: &[MultiBandImage],
+        footprints: &[[f64; 4]],
+        warper: &Warper,
+        zoom: u8,
+    ) -> Result<usize> {
+        let tiles = 1u32 << zoom;
+        let span = 2.0 * MERCATOR_EXTENT / tiles as f64;
+        let resolution = span / self.tile_size as f64;
+        let tile_index = |offset: f64| ((offset / span).floor().max(0.0) as u32).min(tiles - 1);
+
+        let zoom_dir = self.output_dir.join(zoom.to_string());
+        let mut visited = HashSet::new();
+        let mut count = 0;
+
+        for (index, (image, bounds)) in images.iter().zip(footprints).enumerate() {
+            for ty in tile_index(MERCATOR_EXTENT - bounds[3])..=tile_index(MERCATOR_EXTENT - bounds[1]) {
+                for tx in tile_index(bounds[0] + MERCATOR_EXTENT)..=tile_index(bounds[2] + MERCATOR_EXTENT) {
+                    // Tiles shared with an earlier image were already composited
+                    if !visited.insert((tx, ty)) {
+                        continue;
+                    }
+
+                    let grid = TargetGrid {
+                        geo_transform: [
+                            -MERCATOR_EXTENT + tx as f64 * span,
+                            resolution,
+                            0.0,
+                            MERCATOR_EXTENT - ty as f64 * span,
+                            0.0,
+                            -resolution,
+                        ],
+                        width: self.tile_size,
+                        height: self.tile_size,
+                    };
+                    let mut tile = warper.warp_to_grid(image, &grid)?;
+                    for other in &images[index + 1..] {
+                        Self::fill_gaps(&mut tile, &warper.warp_to_grid(other, &grid)?);
+                    }
+
+                    if tile.bands.iter().all(|band| band.iter().all(|v| v.is_nan())) {
+                        continue;
+                    }
+                    let y_dir = zoom_dir.join(ty.to_string());
+                    std::fs::create_dir_all(&y_dir)?;
+                    self.save_warped_tile(&tile, &y_dir.join(format!("{}.{}", tx, self.get_extension())))?;
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Copy pixels of `other` into the nodata pixels of `tile`
+    fn fill_gaps(tile: &mut MultiBandImage, other: &MultiBandImage) {
+        let pixels = tile.bands.first().map_or(0, Vec::len);
+        for idx in 0..pixels {
+            if tile.bands.iter().any(|band| band[idx].is_nan()) {
+                for (band, source) in tile.bands.iter_mut().zip(&other.bands) {
+                    band[idx] = source[idx];
+                }
+            }
+        }
+    }
+
+    /// Encode a warped tile, with nodata as transparency where supported
+    ///
+    /// One- and two-band images become grayscale, anything with three or more
+    /// bands uses the first three as RGB. Values are clamped to 0-255.
+    fn save_warped_tile(&self, tile: &MultiBandImage, path: &Path) -> Result<()> {
+        let channels = if tile.bands.len() >= 3 { 3 } else { 1 };
+        let alpha = !matches!(self.format, TileFormat::Jpeg);
+        let pixels = (tile.metadata.width * tile.metadata.height) as usize;
+
+        let mut data = Vec::with_capacity(pixels * (channels + 1));
+        for idx in 0..pixels {
+            let valid = tile.bands[..channels].iter().all(|band| !band[idx].is_nan());
+            for band in &tile.bands[..channels] {
+                let value = if valid { band[idx].clamp(0.0, 255.0).round() as u8 } else { 0 };
+                data.push(value);
+            }
+            if alpha {
+                data.push(if valid { 255 } else { 0 });
+            }
+        }
+
+        let (width, height) = (tile.metadata.width, tile.metadata.height);
+        let invalid = || ImageryError::processing("Tile buffer does not match its size");
+        let encoded = match (channels, alpha) {
+            (1, false) => image::DynamicImage::ImageLuma8(image::ImageBuffer::from_raw(width, height, data).ok_or_else(invalid)?),
+            (1, true) => image::DynamicImage::ImageLumaA8(image::ImageBuffer::from_raw(width, height, data).ok_or_else(invalid)?),
+            (_, false) => image::DynamicImage::ImageRgb8(image::ImageBuffer::from_raw(width, height, data).ok_or_else(invalid)?),
+            (_, true) => image::DynamicImage::ImageRgba8(image::ImageBuffer::from_raw(width, height, data).ok_or_else(invalid)?),
+        };
+        let format = match self.format {
+            TileFormat::Png => image::ImageFormat::Png,
+            TileFormat::Jpeg => image::ImageFormat::Jpeg,
+            TileFormat::WebP => image::ImageFormat::WebP,
+        };
+        encoded.save_with_format(path, format)?;
+        Ok(())
+    }
+
     /// Generate tiles for a specific zoom level
     fn generate_zoom_level(&self, image: &MultiBandImage, zoom: u8) -> Result<usize> {
         // Calculate number of tiles at this zoom level
@@ -244,4 +423,40 @@ mod tests {
         assert_eq!(bbox[0], -180.0); // min_lon
         assert_eq!(bbox[2], 180.0);  // max_lon
     }
+
+    #[test]
+    fn test_web_mercator_tiles_from_utm() {
+        let metadata = crate::ImageMetadata {
+            width: 40,
+            height: 40,
+            bands: 1,
+            bits_per_sample: 8,
+            geo_transform: Some([500_000.0, 250.0, 0.0, 5_320_000.0, 0.0, -250.0]),
+            crs: Some("EPSG:32632".to_string()),
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, crate::DataType::UInt8);
+        image.bands[0].fill(120.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let info = TilePyramid::new(dir.path())
+            .with_tile_size(64)
+            .with_zoom_levels(6, 8)
+            .generate(&image)
+            .unwrap();
+        assert!(info.total_tiles >= 3);
+
+        // The scene center sits at 9E on the UTM 32 central meridian
+        let (lon, lat) = Crs::utm(32, true)
+            .transformer(&Crs::wgs84())
+            .unwrap()
+            .transform(505_000.0, 5_315_000.0)
+            .unwrap();
+        let (x, y) = TmsCalculator::lonlat_to_tile(lon, lat, 8);
+        let tile = image::open(dir.path().join(format!("8/{}/{}.png", y, x))).unwrap().to_luma_alpha8();
+        assert_eq!(tile.dimensions(), (64, 64));
+        assert!(tile.pixels().any(|p| p.0 == [120, 255]));
+        assert!(tile.pixels().any(|p| p.0[1] == 0));
+    }
 }
@@ -3,70 +3,598 @@
 //! COG is a GeoTIFF variant optimized for cloud storage with:
 //! - Tiled organization
 //! - Internal overviews
-//! - HTTP range request support
+//! - All IFDs at the start of the file, followed by tile data from the
+//!   smallest overview up to full resolution
+//!
+//! Reads go through a [`RangeSource`], so a windowed read only fetches the
+//! IFDs and the tiles it touches whether the bytes come from a local file,
+//! memory or a remote store.
 
 use crate::error::{ImageryError, Result};
-use crate::{ImageMetadata, MultiBandImage, DataType};
+use crate::format::range::{RangeSource, ReaderSource};
+use crate::format::tiff_ifd::{self, tag, GeoReference, Ifd, IfdBuilder, TiffHeader};
+use crate::format::tile_codec::{self, method, photometric, predictor};
+use crate::dataset::RasterBuffer;
 use crate::format::{Compression, ImageReader, ImageWriter};
+use crate::streaming::{StreamingReader, Window};
+use crate::{ImageMetadata, MultiBandImage, DataType};
+use rayon::prelude::*;
+use std::fmt;
+use std::io::{Read, Seek};
 use std::path::Path;
 
-/// COG reader with HTTP range request support
+/// Images larger than this in either dimension must be tiled and should have overviews
+const COG_UNTILED_LIMIT: u32 = 512;
+
+/// Prefix of the GDAL structural metadata block that may follow the header
+const GHOST_PREFIX: &str = "GDAL_STRUCTURAL_METADATA_SIZE=";
+
+/// Length of the GDAL structural metadata size line
+const GHOST_LINE_LEN: usize = 43;
+
+/// Layout of one resolution level inside the file
+#[derive(Debug, Clone)]
+struct Level {
+    ifd_offset: u64,
+    width: u32,
+    height: u32,
+    tiled: bool,
+    tile_width: u32,
+    tile_height: u32,
+    samples: u16,
+    bits_per_sample: u16,
+    sample_format: u16,
+    compression: u16,
+    predictor: u16,
+    planar: u16,
+    photometric: u16,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+    jpeg_tables: Option<Vec<u8>>,
+}
+
+impl Level {
+    fn parse(ifd: &Ifd, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<Self> {
+        fn required<T>(value: Option<T>, ifd: &Ifd, name: &str) -> Result<T> {
+            value.ok_or_else(|| ImageryError::InvalidFormat(format!("IFD at {} has no {}", ifd.offset, name)))
+        }
+        let width = required(ifd.first_u64(source, header, tag::IMAGE_WIDTH)?, ifd, "ImageWidth")? as u32;
+        let height = required(ifd.first_u64(source, header, tag::IMAGE_LENGTH)?, ifd, "ImageLength")? as u32;
+        let samples = ifd.first_u64(source, header, tag::SAMPLES_PER_PIXEL)?.unwrap_or(1) as u16;
+
+        let bits = ifd.u64s(source, header, tag::BITS_PER_SAMPLE)?.unwrap_or_else(|| vec![1]);
+        if bits.iter().any(|&b| b != bits[0]) {
+            return Err(ImageryError::unsupported_format("Bands with differing bit depths"));
+        }
+
+        let tiled = ifd.get(tag::TILE_WIDTH).is_some();
+        let (tile_width, tile_height, offsets, byte_counts) = if tiled {
+            (
+                required(ifd.first_u64(source, header, tag::TILE_WIDTH)?, ifd, "TileWidth")? as u32,
+                required(ifd.first_u64(source, header, tag::TILE_LENGTH)?, ifd, "TileLength")? as u32,
+                required(ifd.u64s(source, header, tag::TILE_OFFSETS)?, ifd, "TileOffsets")?,
+                required(ifd.u64s(source, header, tag::TILE_BYTE_COUNTS)?, ifd, "TileByteCounts")?,
+            )
+        } else {
+            let rows = ifd.first_u64(source, header, tag::ROWS_PER_STRIP)?.unwrap_or(height as u64);
+            (
+                width,
+                rows.min(height as u64) as u32,
+                required(ifd.u64s(source, header, tag::STRIP_OFFSETS)?, ifd, "StripOffsets")?,
+                required(ifd.u64s(source, header, tag::STRIP_BYTE_COUNTS)?, ifd, "StripByteCounts")?,
+            )
+        };
+
+        let jpeg_tables = ifd
+            .get(tag::JPEG_TABLES)
+            .map(|entry| entry.data(source, header))
+            .transpose()?;
+
+        let level = Self {
+            ifd_offset: ifd.offset,
+            width,
+            height,
+            tiled,
+            tile_width,
+            tile_height,
+            samples,
+            bits_per_sample: bits[0] as u16,
+            sample_format: ifd.first_u64(source, header, tag::SAMPLE_FORMAT)?.unwrap_or(1) as u16,
+            compression: ifd.first_u64(source, header, tag::COMPRESSION)?.unwrap_or(1) as u16,
+            predictor: ifd.first_u64(source, header, tag::PREDICTOR)?.unwrap_or(1) as u16,
+            planar: ifd.first_u64(source, header, tag::PLANAR_CONFIG)?.unwrap_or(1) as u16,
+            photometric: ifd.first_u64(source, header, tag::PHOTOMETRIC)?.unwrap_or(1) as u16,
+            offsets,
+            byte_counts,
+            jpeg_tables,
+        };
+
+        if width == 0 || height == 0 || level.tile_width == 0 || level.tile_height == 0 || samples == 0 {
+            return Err(ImageryError::InvalidFormat(format!(
+                "IFD at {} has empty image or tile dimensions",
+                ifd.offset
+            )));
+        }
+        let expected = level.chunk_count();
+        if level.offsets.len() != expected || level.byte_counts.len() != expected {
+            return Err(ImageryError::InvalidFormat(format!(
+                "IFD at {} lists {} offsets and {} byte counts for {} chunks",
+                ifd.offset,
+                level.offsets.len(),
+                level.byte_counts.len(),
+                expected
+            )));
+        }
+
+        Ok(level)
+    }
+
+    fn tiles_across(&self) -> u32 {
+        self.width.div_ceil(self.tile_width)
+    }
+
+    fn tiles_down(&self) -> u32 {
+        self.height.div_ceil(self.tile_height)
+    }
+
+    fn chunk_count(&self) -> usize {
+        let per_plane = self.tiles_across() as usize * self.tiles_down() as usize;
+        if self.planar == 2 {
+            per_plane * self.samples as usize
+        } else {
+            per_plane
+        }
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        self.bits_per_sample as usize / 8
+    }
+
+    fn data_type(&self) -> Result<DataType> {
+        match (self.sample_format, self.bits_per_sample) {
+            (1, 8) => Ok(DataType::UInt8),
+            (1, 16) => Ok(DataType::UInt16),
+            (2, 8) | (2, 16) => Ok(DataType::Int16),
+            (1, 32) => Ok(DataType::UInt32),
+            (2, 32) => Ok(DataType::Int32),
+            (3, 32) => Ok(DataType::Float32),
+            (3, 64) => Ok(DataType::Float64),
+            (format, bits) => Err(ImageryError::UnsupportedFormat(format!(
+                "{}-bit samples with sample format {}",
+                bits, format
+            ))),
+        }
+    }
+
+    /// Offset of the first byte of tile data, ignoring sparse tiles
+    fn first_data_offset(&self) -> Option<u64> {
+        self.offsets
+            .iter()
+            .zip(&self.byte_counts)
+            .filter(|(_, &count)| count > 0)
+            .map(|(&offset, _)| offset)
+            .min()
+    }
+
+    fn same_layout(&self, other: &Level) -> bool {
+        self.samples == other.samples
+            && self.bits_per_sample == other.bits_per_sample
+            && self.sample_format == other.sample_format
+    }
+}
+
+/// A rule of the COG layout that a file breaks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CogViolation {
+    /// A level larger than 512 pixels, or any overview, is stored in strips
+    NotTiled {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+    /// An image larger than 512 pixels has no internal overviews
+    MissingOverviews,
+    /// The full-resolution IFD does not directly follow the header
+    MainIfdNotAtStart {
+        /// Offset of the full-resolution IFD
+        offset: u64,
+    },
+    /// An overview IFD does not follow the IFD of the level above it
+    IfdOutOfOrder {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+    /// Tile data of a level starts before the last IFD
+    IfdAfterData {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+    /// An overview is not smaller than the level above it
+    OverviewNotSmaller {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+    /// Tile data of a level is not stored after that of the next smaller overview
+    DataOutOfOrder {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+    /// Tiles of a level are not stored in row-major order
+    TilesOutOfOrder {
+        /// Level index (0 is full resolution)
+        level: usize,
+    },
+}
+
+impl fmt::Display for CogViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotTiled { level } => write!(f, "level {} is not tiled", level),
+            Self::MissingOverviews => write!(f, "image is larger than {} pixels but has no overviews", COG_UNTILED_LIMIT),
+            Self::MainIfdNotAtStart { offset } => {
+                write!(f, "main IFD is at byte {} instead of directly after the header", offset)
+            }
+            Self::IfdOutOfOrder { level } => write!(f, "IFD of level {} precedes the IFD of the level above it", level),
+            Self::IfdAfterData { level } => write!(f, "tile data of level {} starts before the last IFD", level),
+            Self::OverviewNotSmaller { level } => write!(f, "overview level {} is not smaller than the level above it", level),
+            Self::DataOutOfOrder { level } => {
+                write!(f, "tile data of level {} is not after the data of level {}", level, level + 1)
+            }
+            Self::TilesOutOfOrder { level } => write!(f, "tiles of level {} are not in row-major order", level),
+        }
+    }
+}
+
+/// Outcome of checking a file against the COG layout rules
+#[derive(Debug, Clone, Default)]
+pub struct CogValidationReport {
+    /// Violations that make the file an invalid COG
+    pub errors: Vec<CogViolation>,
+    /// Deviations that are allowed but make range reads less efficient
+    pub warnings: Vec<CogViolation>,
+}
+
+impl CogValidationReport {
+    /// Whether the file satisfies every required layout rule
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// COG reader with pluggable byte-range access
 pub struct CogReader {
     path: String,
+    source: Box<dyn RangeSource>,
+    header: TiffHeader,
+    levels: Vec<Level>,
     metadata: ImageMetadata,
-    is_remote: bool,
+    data_type: DataType,
     tile_size: (u32, u32),
 }
 
 impl CogReader {
     /// Check if this is a valid COG
     pub fn is_valid_cog(path: &str) -> Result<bool> {
-        // Check for:
-        // 1. Tiled organization
-        // 2. Overview levels
-        // 3. IFD offsets at end of file
+        let mut reader = Self::open(path)?;
+        Ok(reader.validate()?.is_valid())
+    }
+
+    /// Open a COG served by a custom range source
+    pub fn from_source(source: impl RangeSource + 'static) -> Result<Self> {
+        Self::with_source("<range source>".to_string(), Box::new(source))
+    }
 
-        Ok(true) // Placeholder
+    /// Open a COG from any seekable reader
+    pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self> {
+        Self::from_source(ReaderSource::new(reader))
+    }
+
+    fn with_source(path: String, mut source: Box<dyn RangeSource>) -> Result<Self> {
+        let header = TiffHeader::read(source.as_mut())?;
+        let ifds = tiff_ifd::read_ifd_chain(source.as_mut(), &header)?;
+        let main_ifd = ifds
+            .first()
+            .ok_or_else(|| ImageryError::invalid_format("TIFF file contains no images"))?;
+
+        let mut levels: Vec<Level> = Vec::new();
+        for ifd in &ifds {
+            // Bit 2 of NewSubfileType marks transparency masks
+            let subfile = ifd.first_u64(source.as_mut(), &header, tag::NEW_SUBFILE_TYPE)?.unwrap_or(0);
+            if subfile & 4 != 0 {
+                continue;
+            }
+            let level = Level::parse(ifd, source.as_mut(), &header)?;
+            if let Some(main) = levels.first() {
+                if !main.same_layout(&level) {
+                    log::warn!("{}: skipping IFD at {} with a different sample layout", path, ifd.offset);
+                    continue;
+                }
+            }
+            levels.push(level);
+        }
+
+        let main = levels
+            .first()
+            .ok_or_else(|| ImageryError::invalid_format("TIFF file contains no image levels"))?;
+        let data_type = main.data_type()?;
+
+        let geo = tiff_ifd::read_geo_reference(main_ifd, source.as_mut(), &header)?;
+        let no_data = main_ifd
+            .ascii(source.as_mut(), &header, tag::GDAL_NODATA)?
+            .and_then(|value| value.trim().parse().ok());
+        let band_names = main_ifd
+            .ascii(source.as_mut(), &header, tag::GDAL_METADATA)?
+            .map(|xml| parse_band_names(&xml, main.samples as usize))
+            .unwrap_or_default();
+
+        let metadata = ImageMetadata {
+            width: main.width,
+            height: main.height,
+            bands: main.samples as u32,
+            bits_per_sample: main.bits_per_sample,
+            geo_transform: geo.geo_transform,
+            crs: geo.crs,
+            no_data,
+            band_names,
+        };
+        let tile_size = (main.tile_width, main.tile_height);
+
+        Ok(Self {
+            path,
+            source,
+            header,
+            levels,
+            metadata,
+            data_type,
+            tile_size,
+        })
     }
 
     /// Read tile at specific coordinates
+    ///
+    /// `overview` 0 is full resolution and 1.. index the internal overviews.
+    /// Edge tiles are clipped to the image extent.
     pub fn read_tile(&mut self, tile_x: u32, tile_y: u32, overview: usize) -> Result<MultiBandImage> {
-        // Calculate byte range for tile
-        // Make HTTP range request if remote
-        // Decompress and return tile data
+        let level = level_at(&self.levels, overview)?;
+        if tile_x >= level.tiles_across() || tile_y >= level.tiles_down() {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Tile ({}, {}) outside the {}x{} grid of level {}",
+                tile_x,
+                tile_y,
+                level.tiles_across(),
+                level.tiles_down(),
+                overview
+            )));
+        }
 
-        Err(ImageryError::NotImplemented("Tile reading not yet implemented".to_string()))
+        let x = tile_x * level.tile_width;
+        let y = tile_y * level.tile_height;
+        let width = level.tile_width.min(level.width - x);
+        let height = level.tile_height.min(level.height - y);
+        self.read_overview_region(overview, x, y, width, height)
+    }
+
+    /// Read one tile in its native sample type, one buffer per band
+    ///
+    /// Unlike [`read_tile`](Self::read_tile) the samples are not widened to
+    /// `f32`. Edge tiles are clipped to the image extent.
+    pub fn read_tile_buffers(&mut self, tile_x: u32, tile_y: u32, overview: usize) -> Result<Vec<RasterBuffer>> {
+        let level = level_at(&self.levels, overview)?;
+        if tile_x >= level.tiles_across() || tile_y >= level.tiles_down() {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Tile ({}, {}) outside the {}x{} grid of level {}",
+                tile_x,
+                tile_y,
+                level.tiles_across(),
+                level.tiles_down(),
+                overview
+            )));
+        }
+
+        let fill = self.metadata.no_data.unwrap_or(0.0);
+        let tile = decode_tile(self.source.as_mut(), &self.header, level, fill, tile_x, tile_y)?;
+        let x = tile_x * level.tile_width;
+        let y = tile_y * level.tile_height;
+        let width = level.tile_width.min(level.width - x);
+        let height = level.tile_height.min(level.height - y);
+        if width == level.tile_width && height == level.tile_height {
+            return Ok(tile);
+        }
+
+        let region = Window::new(0, 0, width, height);
+        Ok(tile.iter().map(|band| band.crop(level.tile_width, &region)).collect())
     }
 
     /// Get tile grid dimensions
     pub fn tile_grid(&self) -> (u32, u32) {
-        let tiles_x = (self.metadata.width + self.tile_size.0 - 1) / self.tile_size.0;
-        let tiles_y = (self.metadata.height + self.tile_size.1 - 1) / self.tile_size.1;
+        let tiles_x = self.metadata.width.div_ceil(self.tile_size.0);
+        let tiles_y = self.metadata.height.div_ceil(self.tile_size.1);
         (tiles_x, tiles_y)
     }
 
+    /// Tile grid dimensions of an overview level
+    pub fn overview_tile_grid(&self, overview: usize) -> Option<(u32, u32)> {
+        self.levels
+            .get(overview)
+            .map(|level| (level.tiles_across(), level.tiles_down()))
+    }
+
+    /// Tile size of the full-resolution image
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.tile_size
+    }
+
+    /// Tile size of an overview level
+    pub fn overview_tile_size(&self, overview: usize) -> Option<(u32, u32)> {
+        self.levels
+            .get(overview)
+            .map(|level| (level.tile_width, level.tile_height))
+    }
+
+    /// Number of internal overviews
+    pub fn overview_count(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Pixel dimensions of a level (0 is full resolution)
+    pub fn overview_size(&self, overview: usize) -> Option<(u32, u32)> {
+        self.levels.get(overview).map(|level| (level.width, level.height))
+    }
+
+    /// Sample data type of the image
+    pub fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    /// Read a whole overview level
+    pub fn read_overview(&mut self, overview: usize) -> Result<MultiBandImage> {
+        let (width, height) = self.overview_size(overview).ok_or_else(|| {
+            ImageryError::InvalidParameter(format!("Overview {} does not exist", overview))
+        })?;
+        self.read_overview_region(overview, 0, 0, width, height)
+    }
+
+    /// Read a window of an overview level, fetching only the tiles it covers
+    pub fn read_overview_region(&mut self, overview: usize, x: u32, y: u32, width: u32, height: u32) -> Result<MultiBandImage> {
+        let level = level_at(&self.levels, overview)?;
+        if width == 0 || height == 0 || x + width > level.width || y + height > level.height {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Region ({}, {}, {}, {}) exceeds level bounds ({}, {})",
+                x, y, width, height, level.width, level.height
+            )));
+        }
+
+        let mut metadata = self.metadata.clone();
+        metadata.width = width;
+        metadata.height = height;
+        metadata.geo_transform = self.metadata.geo_transform.map(|gt| {
+            let sx = self.metadata.width as f64 / level.width as f64;
+            let sy = self.metadata.height as f64 / level.height as f64;
+            let (px, py) = (x as f64 * sx, y as f64 * sy);
+            [
+                gt[0] + px * gt[1] + py * gt[2],
+                gt[1] * sx,
+                gt[2] * sy,
+                gt[3] + px * gt[4] + py * gt[5],
+                gt[4] * sx,
+                gt[5] * sy,
+            ]
+        });
+        let mut image = MultiBandImage::new(metadata, self.data_type);
+        let fill = self.metadata.no_data.unwrap_or(0.0);
+
+        let (tw, th) = (level.tile_width, level.tile_height);
+        for tile_y in y / th..=(y + height - 1) / th {
+            for tile_x in x / tw..=(x + width - 1) / tw {
+                let tile = decode_tile(self.source.as_mut(), &self.header, level, fill, tile_x, tile_y)?;
+
+                let x0 = (tile_x * tw).max(x);
+                let x1 = ((tile_x + 1) * tw).min(x + width);
+                let y0 = (tile_y * th).max(y);
+                let y1 = ((tile_y + 1) * th).min(y + height);
+                let span = (x1 - x0) as usize;
+
+                for (band, samples) in tile.iter().map(RasterBuffer::to_f32).enumerate() {
+                    for row in y0..y1 {
+                        let src = ((row - tile_y * th) * tw + (x0 - tile_x * tw)) as usize;
+                        let dst = ((row - y) * width + (x0 - x)) as usize;
+                        image.bands[band][dst..dst + span].copy_from_slice(&samples[src..src + span]);
+                    }
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Check the file against the COG layout rules
+    pub fn validate(&mut self) -> Result<CogValidationReport> {
+        let mut report = CogValidationReport::default();
+        let expected_ifd = self.header.size() + self.ghost_size()?;
+        let main = &self.levels[0];
+
+        if main.width > COG_UNTILED_LIMIT || main.height > COG_UNTILED_LIMIT {
+            if !main.tiled {
+                report.errors.push(CogViolation::NotTiled { level: 0 });
+            }
+            if self.levels.len() == 1 {
+                report.warnings.push(CogViolation::MissingOverviews);
+            }
+        }
+
+        if main.ifd_offset != expected_ifd {
+            report.errors.push(CogViolation::MainIfdNotAtStart { offset: main.ifd_offset });
+        }
+
+        let last_ifd = self.levels.iter().map(|level| level.ifd_offset).max().unwrap_or(0);
+        for (index, level) in self.levels.iter().enumerate() {
+            if index > 0 {
+                let above = &self.levels[index - 1];
+                if !level.tiled {
+                    report.errors.push(CogViolation::NotTiled { level: index });
+                }
+                if level.width > above.width
+                    || level.height > above.height
+                    || (level.width == above.width && level.height == above.height)
+                {
+                    report.errors.push(CogViolation::OverviewNotSmaller { level: index });
+                }
+                if level.ifd_offset <= above.ifd_offset {
+                    report.errors.push(CogViolation::IfdOutOfOrder { level: index });
+                }
+            }
+
+            let Some(first_data) = level.first_data_offset() else {
+                continue;
+            };
+            if first_data < last_ifd {
+                report.errors.push(CogViolation::IfdAfterData { level: index });
+            }
+            let below = self.levels.get(index + 1).and_then(Level::first_data_offset);
+            if below.is_some_and(|below| first_data < below) {
+                report.errors.push(CogViolation::DataOutOfOrder { level: index });
+            }
+
+            let mut stored = level
+                .offsets
+                .iter()
+                .zip(&level.byte_counts)
+                .filter(|(_, &count)| count > 0)
+                .map(|(&offset, _)| offset);
+            let in_order = stored
+                .try_fold(0u64, |prev, offset| (offset >= prev).then_some(offset))
+                .is_some();
+            if !in_order {
+                report.warnings.push(CogViolation::TilesOutOfOrder { level: index });
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Open from URL with HTTP range requests
+    ///
+    /// No HTTP client is bundled; wrap the remote object in a [`RangeSource`]
+    /// and use [`CogReader::from_source`] instead.
     pub fn open_url(url: impl Into<String>) -> Result<Self> {
-        let url = url.into();
-
-        // Read header to get metadata
-        // Validate COG structure
+        Err(ImageryError::UnsupportedFormat(format!(
+            "No built-in HTTP range source for {}; open it with CogReader::from_source",
+            url.into()
+        )))
+    }
 
-        Ok(Self {
-            path: url,
-            metadata: ImageMetadata {
-                width: 0,
-                height: 0,
-                bands: 0,
-                bits_per_sample: 8,
-                geo_transform: None,
-                crs: None,
-                no_data: None,
-                band_names: vec![],
-            },
-            is_remote: true,
-            tile_size: (512, 512),
-        })
+    /// Size of the GDAL structural metadata block after the header, if any
+    fn ghost_size(&mut self) -> Result<u64> {
+        let start = self.header.size();
+        if self.source.size()? < start + GHOST_LINE_LEN as u64 {
+            return Ok(0);
+        }
+        let line = self.source.read_vec(start, GHOST_LINE_LEN)?;
+        let line = String::from_utf8_lossy(&line);
+        let size = line
+            .strip_prefix(GHOST_PREFIX)
+            .and_then(|rest| rest.get(..6))
+            .and_then(|digits| digits.parse::<u64>().ok());
+        Ok(size.map_or(0, |size| GHOST_LINE_LEN as u64 + size))
     }
 }
 
@@ -79,26 +607,20 @@ impl ImageReader for CogReader {
             return Self::open_url(path_str);
         }
 
+        let source = ReaderSource::open(path.as_ref())?;
+        let mut reader = Self::with_source(path_str, Box::new(source))?;
+
         // Validate it's a proper COG
-        if !Self::is_valid_cog(&path_str)? {
-            log::warn!("File may not be a valid Cloud Optimized GeoTIFF");
+        let report = reader.validate()?;
+        if !report.is_valid() {
+            log::warn!(
+                "{} is not a valid Cloud Optimized GeoTIFF: {}",
+                reader.path,
+                report.errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+            );
         }
 
-        Ok(Self {
-            path: path_str,
-            metadata: ImageMetadata {
-                width: 0,
-                height: 0,
-                bands: 0,
-                bits_per_sample: 8,
-                geo_transform: None,
-                crs: None,
-                no_data: None,
-                band_names: vec![],
-            },
-            is_remote,
-            tile_size: (512, 512),
-        })
+        Ok(reader)
     }
 
     fn read(&mut self) -> Result<MultiBandImage> {
@@ -108,29 +630,168 @@ impl ImageReader for CogReader {
     }
 
     fn read_region(&mut self, x: u32, y: u32, width: u32, height: u32) -> Result<MultiBandImage> {
-        // Calculate which tiles are needed
-        let tile_x_start = x / self.tile_size.0;
-        let tile_y_start = y / self.tile_size.1;
-        let tile_x_end = (x + width + self.tile_size.0 - 1) / self.tile_size.0;
-        let tile_y_end = (y + height + self.tile_size.1 - 1) / self.tile_size.1;
+        self.read_overview_region(0, x, y, width, height)
+    }
 
-        // Read required tiles and assemble region
-        let mut metadata = self.metadata.clone();
-        metadata.width = width;
-        metadata.height = height;
+    fn metadata(&self) -> &ImageMetadata {
+        &self.metadata
+    }
+}
 
-        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+impl StreamingReader for CogReader {
+    fn read_window(&mut self, window: &Window) -> Result<MultiBandImage> {
+        self.read_overview_region(0, window.x, window.y, window.width, window.height)
+    }
 
-        // Placeholder: would read and assemble tiles
+    fn dimensions(&self) -> (u32, u32) {
+        (self.metadata.width, self.metadata.height)
+    }
 
-        Ok(image)
+    fn bands(&self) -> usize {
+        self.metadata.bands as usize
     }
+}
 
-    fn metadata(&self) -> &ImageMetadata {
-        &self.metadata
+/// Level `overview` of a reader, or a parameter error naming the available count
+fn level_at(levels: &[Level], overview: usize) -> Result<&Level> {
+    levels.get(overview).ok_or_else(|| {
+        ImageryError::InvalidParameter(format!(
+            "Overview {} does not exist ({} available)",
+            overview,
+            levels.len() - 1
+        ))
+    })
+}
+
+/// Decode one tile of a level into per-band sample buffers of full tile size
+fn decode_tile(
+    source: &mut dyn RangeSource,
+    header: &TiffHeader,
+    level: &Level,
+    fill: f64,
+    tile_x: u32,
+    tile_y: u32,
+) -> Result<Vec<RasterBuffer>> {
+    let pixels = (level.tile_width * level.tile_height) as usize;
+    let bands = level.samples as usize;
+    let data_type = level.data_type()?;
+    let index = (tile_y * level.tiles_across() + tile_x) as usize;
+
+    if level.planar == 2 {
+        let plane = level.tiles_across() as usize * level.tiles_down() as usize;
+        (0..bands)
+            .map(|band| {
+                let chunk = read_chunk(source, header, level, band * plane + index, 1)?;
+                Ok(chunk.map_or_else(
+                    || RasterBuffer::filled(data_type, pixels, fill),
+                    |bytes| decode_samples(level, data_type, &bytes, header.order),
+                ))
+            })
+            .collect()
+    } else {
+        let Some(bytes) = read_chunk(source, header, level, index, bands)? else {
+            return Ok(vec![RasterBuffer::filled(data_type, pixels, fill); bands]);
+        };
+        Ok(decode_samples(level, data_type, &bytes, header.order).deinterleave(bands))
+    }
+}
+
+/// Fetch, decompress and un-predict one chunk; `None` for sparse chunks
+fn read_chunk(
+    source: &mut dyn RangeSource,
+    header: &TiffHeader,
+    level: &Level,
+    index: usize,
+    samples: usize,
+) -> Result<Option<Vec<u8>>> {
+    let (offset, count) = (level.offsets[index], level.byte_counts[index]);
+    if offset == 0 || count == 0 {
+        return Ok(None);
+    }
+
+    // Byte counts come from the file, so check them before allocating
+    let size = source.size()?;
+    let count = usize::try_from(count).ok().filter(|_| count <= size).ok_or_else(|| {
+        ImageryError::InvalidFormat(format!("Tile {} byte count {} exceeds source size {}", index, count, size))
+    })?;
+    let compressed = source.read_vec(offset, count)?;
+    let bytes_per_sample = level.bytes_per_sample();
+    let row_samples = level.tile_width as usize * samples;
+    let expected = row_samples * level.tile_height as usize * bytes_per_sample;
+
+    let mut raw = tile_codec::decompress(
+        level.compression,
+        &compressed,
+        level.jpeg_tables.as_deref(),
+        level.photometric,
+        expected,
+    )?;
+    if level.compression != method::JPEG {
+        tile_codec::undo_predictor(&mut raw, level.predictor, header.order, row_samples, samples, bytes_per_sample)?;
+    }
+    Ok(Some(raw))
+}
+
+/// Convert decoded chunk bytes to samples of the level's data type
+fn decode_samples(level: &Level, data_type: DataType, bytes: &[u8], order: tiff_ifd::ByteOrder) -> RasterBuffer {
+    let size = level.bytes_per_sample();
+    let chunks = bytes.chunks_exact(size);
+    match (level.sample_format, level.bits_per_sample) {
+        (1, 8) => RasterBuffer::UInt8(bytes.to_vec()),
+        (2, 8) => RasterBuffer::Int16(bytes.iter().map(|&b| b as i8 as i16).collect()),
+        (1, 16) => RasterBuffer::UInt16(chunks.map(|c| order.read_u16(c)).collect()),
+        (2, 16) => RasterBuffer::Int16(chunks.map(|c| order.read_i16(c)).collect()),
+        (1, 32) => RasterBuffer::UInt32(chunks.map(|c| order.read_u32(c)).collect()),
+        (2, 32) => RasterBuffer::Int32(chunks.map(|c| order.read_i32(c)).collect()),
+        (3, 32) => RasterBuffer::Float32(chunks.map(|c| order.read_f32(c)).collect()),
+        (3, 64) => RasterBuffer::Float64(chunks.map(|c| order.read_f64(c)).collect()),
+        _ => RasterBuffer::new(data_type, bytes.len() / size.max(1)),
     }
 }
 
+/// Band descriptions from a GDAL_METADATA XML block
+fn parse_band_names(xml: &str, bands: usize) -> Vec<String> {
+    let mut names = vec![String::new(); bands];
+    let mut found = false;
+
+    for item in xml.split("<Item ").skip(1) {
+        let Some((attributes, rest)) = item.split_once('>') else {
+            continue;
+        };
+        if !attributes.contains("role=\"description\"") {
+            continue;
+        }
+        let sample = attributes
+            .split("sample=\"")
+            .nth(1)
+            .and_then(|s| s.split('"').next())
+            .and_then(|s| s.parse::<usize>().ok());
+        let value = rest.split("</Item>").next().unwrap_or_default();
+        if let Some(name) = sample.and_then(|sample| names.get_mut(sample)) {
+            *name = xml_unescape(value);
+            found = true;
+        }
+    }
+
+    if found { names } else { Vec::new() }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
 /// COG writer with optimized structure
 pub struct CogWriter {
     path: std::path::PathBuf,
@@ -155,6 +816,30 @@ pub enum ResamplingMethod {
     Mode,
 }
 
+/// Sample encoding shared by every level of a written file
+#[derive(Debug, Clone, Copy)]
+struct SampleLayout {
+    data_type: DataType,
+    bands: u16,
+    compression: u16,
+    predictor: u16,
+    photometric: u16,
+}
+
+impl SampleLayout {
+    fn bits(&self) -> u16 {
+        self.data_type.size() as u16 * 8
+    }
+
+    fn sample_format(&self) -> u16 {
+        match self.data_type {
+            DataType::Int16 | DataType::Int32 => 2,
+            DataType::Float32 | DataType::Float64 => 3,
+            _ => 1,
+        }
+    }
+}
+
 impl CogWriter {
     /// Set tile size (default 512x512)
     pub fn with_tile_size(&mut self, width: u32, height: u32) -> &mut Self {
@@ -179,30 +864,335 @@ impl CogWriter {
         CogReader::is_valid_cog(&self.path.to_string_lossy())
     }
 
+    /// Encode an image as COG bytes without touching the filesystem
+    pub fn encode(&self, image: &MultiBandImage) -> Result<Vec<u8>> {
+        let layout = self.sample_layout(image)?;
+        let overviews = self.build_overviews(image)?;
+        let levels: Vec<&MultiBandImage> = std::iter::once(image).chain(&overviews).collect();
+
+        let tiles = levels
+            .iter()
+            .map(|level| self.encode_tiles(level, &layout))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(bytes) = self.assemble(&levels, &tiles, &layout, false) {
+            return Ok(bytes);
+        }
+        self.assemble(&levels, &tiles, &layout, true)
+            .ok_or_else(|| ImageryError::Other("Image exceeds BigTIFF size limits".to_string()))
+    }
+
+    /// Check the image against the writer settings and pick the TIFF encoding
+    fn sample_layout(&self, image: &MultiBandImage) -> Result<SampleLayout> {
+        let meta = &image.metadata;
+        let pixels = meta.width as usize * meta.height as usize;
+        if meta.width == 0 || meta.height == 0 || image.bands.is_empty() || image.bands.iter().any(|b| b.len() != pixels) {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Cannot write a {}x{} image with {} bands of unequal or zero size",
+                meta.width,
+                meta.height,
+                image.bands.len()
+            )));
+        }
+        let (tw, th) = self.tile_size;
+        if tw == 0 || th == 0 || !tw.is_multiple_of(16) || !th.is_multiple_of(16) {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Tile size {}x{} must be a positive multiple of 16",
+                tw, th
+            )));
+        }
+
+        let bands = u16::try_from(image.bands.len())
+            .map_err(|_| ImageryError::InvalidParameter("Too many bands for a TIFF file".to_string()))?;
+        let compression = tile_codec::compression_code(self.compression)?;
+        let is_jpeg = compression == method::JPEG;
+        if is_jpeg && (image.data_type != DataType::UInt8 || !(bands == 1 || bands == 3)) {
+            return Err(ImageryError::InvalidParameter(
+                "JPEG compression requires 1 or 3 bands of UInt8 data".to_string(),
+            ));
+        }
+
+        let predictor = match (self.compression, image.data_type) {
+            (Compression::Deflate | Compression::Lzw | Compression::Zstd { .. }, DataType::Float32 | DataType::Float64) => {
+                predictor::FLOATING_POINT
+            }
+            (Compression::Deflate | Compression::Lzw | Compression::Zstd { .. }, _) => predictor::HORIZONTAL,
+            _ => predictor::NONE,
+        };
+        let photometric = match (is_jpeg, bands >= 3 && image.data_type == DataType::UInt8) {
+            (true, true) => photometric::YCBCR,
+            (false, true) => photometric::RGB,
+            _ => photometric::MIN_IS_BLACK,
+        };
+
+        Ok(SampleLayout {
+            data_type: image.data_type,
+            bands,
+            compression,
+            predictor,
+            photometric,
+        })
+    }
+
+    /// Compress every tile of a level in row-major order
+    fn encode_tiles(&self, image: &MultiBandImage, layout: &SampleLayout) -> Result<Vec<Vec<u8>>> {
+        let (tw, th) = self.tile_size;
+        let across = image.metadata.width.div_ceil(tw);
+        let down = image.metadata.height.div_ceil(th);
+        let fill = image.metadata.no_data.unwrap_or(0.0) as f32;
+        let size = layout.data_type.size();
+        let bands = layout.bands as usize;
+
+        (0..across * down)
+            .into_par_iter()
+            .map(|index| {
+                let (x0, y0) = ((index % across) * tw, (index / across) * th);
+                let mut raw = Vec::with_capacity((tw * th) as usize * bands * size);
+                for y in y0..y0 + th {
+                    for x in x0..x0 + tw {
+                        let inside = x < image.metadata.width && y < image.metadata.height;
+                        let pixel = (y as usize * image.metadata.width as usize) + x as usize;
+                        for band in &image.bands {
+                            let value = if inside { band[pixel] } else { fill };
+                            encode_sample(&mut raw, layout.data_type, value);
+                        }
+                    }
+                }
+
+                tile_codec::apply_predictor(&mut raw, layout.predictor, tw as usize * bands, bands, size);
+                tile_codec::compress(self.compression, &raw, tw, th, layout.bands)
+            })
+            .collect()
+    }
+
+    /// Lay out header, IFDs and tile data; `None` if classic TIFF offsets would overflow
+    fn assemble(
+        &self,
+        levels: &[&MultiBandImage],
+        tiles: &[Vec<Vec<u8>>],
+        layout: &SampleLayout,
+        big: bool,
+    ) -> Option<Vec<u8>> {
+        let ghost = ghost_area();
+        let header_size: u64 = if big { 16 } else { 8 };
+
+        let byte_counts: Vec<Vec<u64>> = tiles
+            .iter()
+            .map(|level| level.iter().map(|tile| tile.len() as u64).collect())
+            .collect();
+
+        // IFD sizes only depend on tile counts, so size them with placeholder offsets
+        let mut ifd_offsets = Vec::with_capacity(levels.len());
+        let mut position = header_size + ghost.len() as u64;
+        for (index, level) in levels.iter().enumerate() {
+            let placeholder = vec![0; tiles[index].len()];
+            let builder = self.level_ifd(index, level, layout, &placeholder, &byte_counts[index], big);
+            ifd_offsets.push(position);
+            position += builder.byte_size(big);
+        }
+
+        // Tile data follows the IFDs, smallest overview first
+        let mut tile_offsets = vec![Vec::new(); levels.len()];
+        for index in (0..levels.len()).rev() {
+            for tile in &tiles[index] {
+                tile_offsets[index].push(position);
+                position += tile.len() as u64;
+            }
+        }
+        if !big && position > u32::MAX as u64 {
+            return None;
+        }
+
+        let mut out = Vec::with_capacity(position as usize);
+        out.extend_from_slice(b"II");
+        if big {
+            out.extend_from_slice(&43u16.to_le_bytes());
+            out.extend_from_slice(&8u16.to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes());
+            out.extend_from_slice(&ifd_offsets[0].to_le_bytes());
+        } else {
+            out.extend_from_slice(&42u16.to_le_bytes());
+            out.extend_from_slice(&(ifd_offsets[0] as u32).to_le_bytes());
+        }
+        out.extend_from_slice(ghost.as_bytes());
+
+        for (index, level) in levels.iter().enumerate() {
+            let builder = self.level_ifd(index, level, layout, &tile_offsets[index], &byte_counts[index], big);
+            let next = ifd_offsets.get(index + 1).copied().unwrap_or(0);
+            debug_assert_eq!(out.len() as u64, ifd_offsets[index]);
+            builder.write(&mut out, big, next);
+        }
+        for level in tiles.iter().rev() {
+            for tile in level {
+                out.extend_from_slice(tile);
+            }
+        }
+
+        Some(out)
+    }
+
+    /// Directory describing one level
+    fn level_ifd(
+        &self,
+        index: usize,
+        image: &MultiBandImage,
+        layout: &SampleLayout,
+        offsets: &[u64],
+        byte_counts: &[u64],
+        big: bool,
+    ) -> IfdBuilder {
+        let bands = layout.bands as usize;
+        let mut ifd = IfdBuilder::new();
+
+        ifd.long(tag::NEW_SUBFILE_TYPE, &[if index == 0 { 0 } else { 1 }]);
+        ifd.long(tag::IMAGE_WIDTH, &[image.metadata.width]);
+        ifd.long(tag::IMAGE_LENGTH, &[image.metadata.height]);
+        ifd.short(tag::BITS_PER_SAMPLE, &vec![layout.bits(); bands]);
+        ifd.short(tag::COMPRESSION, &[layout.compression]);
+        ifd.short(tag::PHOTOMETRIC, &[layout.photometric]);
+        ifd.short(tag::SAMPLES_PER_PIXEL, &[layout.bands]);
+        ifd.short(tag::PLANAR_CONFIG, &[1]);
+        if layout.predictor != predictor::NONE {
+            ifd.short(tag::PREDICTOR, &[layout.predictor]);
+        }
+        ifd.long(tag::TILE_WIDTH, &[self.tile_size.0]);
+        ifd.long(tag::TILE_LENGTH, &[self.tile_size.1]);
+        ifd.offsets(tag::TILE_OFFSETS, offsets, big);
+        ifd.offsets(tag::TILE_BYTE_COUNTS, byte_counts, big);
+
+        let color_samples = if layout.photometric == photometric::MIN_IS_BLACK { 1 } else { 3 };
+        if bands > color_samples {
+            ifd.short(tag::EXTRA_SAMPLES, &vec![0; bands - color_samples]);
+        }
+        ifd.short(tag::SAMPLE_FORMAT, &vec![layout.sample_format(); bands]);
+        if layout.photometric == photometric::YCBCR {
+            ifd.short(tag::YCBCR_SUBSAMPLING, &[1, 1]);
+        }
+        if let Some(no_data) = image.metadata.no_data {
+            ifd.ascii(tag::GDAL_NODATA, &no_data.to_string());
+        }
+
+        if index == 0 {
+            let geo = GeoReference {
+                geo_transform: image.metadata.geo_transform,
+                crs: image.metadata.crs.clone(),
+            };
+            tiff_ifd::write_geo_reference(&mut ifd, &geo);
+            if image.metadata.band_names.iter().any(|name| !name.is_empty()) {
+                ifd.ascii(tag::GDAL_METADATA, &band_metadata(&image.metadata.band_names));
+            }
+        }
+
+        ifd
+    }
+
     /// Build internal overviews
     fn build_overviews(&self, image: &MultiBandImage) -> Result<Vec<MultiBandImage>> {
         let mut overviews = Vec::new();
 
-        for &level in &self.overview_levels {
-            let width = image.metadata.width / level;
-            let height = image.metadata.height / level;
+        let mut levels: Vec<u32> = self.overview_levels.iter().copied().filter(|&l| l > 1).collect();
+        levels.sort_unstable();
+        levels.dedup();
 
-            if width < 1 || height < 1 {
+        for level in levels {
+            if image.metadata.width / level < 1 || image.metadata.height / level < 1 {
                 break;
             }
 
-            let mut overview_meta = image.metadata.clone();
-            overview_meta.width = width;
-            overview_meta.height = height;
-
-            let overview = MultiBandImage::new(overview_meta, image.data_type);
-            // Would resample image data here
-
-            overviews.push(overview);
+            overviews.push(self.resample(image, level));
         }
 
         Ok(overviews)
     }
+
+    /// Downsample an image by an integer factor
+    fn resample(&self, image: &MultiBandImage, factor: u32) -> MultiBandImage {
+        let (width, height) = (image.metadata.width, image.metadata.height);
+        let mut overview_meta = image.metadata.clone();
+        overview_meta.width = width.div_ceil(factor);
+        overview_meta.height = height.div_ceil(factor);
+
+        let sx = width as f64 / overview_meta.width as f64;
+        let sy = height as f64 / overview_meta.height as f64;
+        overview_meta.geo_transform = image
+            .metadata
+            .geo_transform
+            .map(|gt| [gt[0], gt[1] * sx, gt[2] * sy, gt[3], gt[4] * sx, gt[5] * sy]);
+
+        let (ow, oh) = (overview_meta.width, overview_meta.height);
+        let no_data = image.metadata.no_data.map(|v| v as f32);
+        let method = self.overview_resampling;
+        let mut overview = MultiBandImage::new(overview_meta, image.data_type);
+
+        overview.bands.par_iter_mut().zip(&image.bands).for_each(|(out, band)| {
+            let at = |x: i64, y: i64| {
+                let x = x.clamp(0, width as i64 - 1) as usize;
+                let y = y.clamp(0, height as i64 - 1) as usize;
+                band[y * width as usize + x]
+            };
+            let is_valid = |v: f32| !v.is_nan() && Some(v) != no_data;
+            let fill = no_data.unwrap_or(0.0);
+
+            for oy in 0..oh {
+                for ox in 0..ow {
+                    let x0 = ox * factor;
+                    let y0 = oy * factor;
+                    let x1 = (x0 + factor).min(width);
+                    let y1 = (y0 + factor).min(height);
+                    // Source-pixel coordinates of the overview pixel centre
+                    let cx = (ox as f64 + 0.5) * sx - 0.5;
+                    let cy = (oy as f64 + 0.5) * sy - 0.5;
+
+                    let value = match method {
+                        ResamplingMethod::Nearest => at(cx.round() as i64, cy.round() as i64),
+                        ResamplingMethod::Average => {
+                            let (sum, count) = (y0..y1)
+                                .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                                .map(|(x, y)| at(x as i64, y as i64))
+                                .filter(|&v| is_valid(v))
+                                .fold((0.0f64, 0u32), |(s, n), v| (s + v as f64, n + 1));
+                            if count == 0 { fill } else { (sum / count as f64) as f32 }
+                        }
+                        ResamplingMethod::Mode => {
+                            let mut values: Vec<f32> = (y0..y1)
+                                .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+                                .map(|(x, y)| at(x as i64, y as i64))
+                                .filter(|&v| is_valid(v))
+                                .collect();
+                            values.sort_by(f32::total_cmp);
+                            values
+                                .chunk_by(|a, b| a == b)
+                                .max_by_key(|run| run.len())
+                                .map_or(fill, |run| run[0])
+                        }
+                        ResamplingMethod::Bilinear => {
+                            let (fx, fy) = (cx.floor(), cy.floor());
+                            let (tx, ty) = ((cx - fx) as f32, (cy - fy) as f32);
+                            let (ix, iy) = (fx as i64, fy as i64);
+                            let top = at(ix, iy) * (1.0 - tx) + at(ix + 1, iy) * tx;
+                            let bottom = at(ix, iy + 1) * (1.0 - tx) + at(ix + 1, iy + 1) * tx;
+                            top * (1.0 - ty) + bottom * ty
+                        }
+                        ResamplingMethod::Cubic => {
+                            let (fx, fy) = (cx.floor(), cy.floor());
+                            let (tx, ty) = ((cx - fx) as f32, (cy - fy) as f32);
+                            let (ix, iy) = (fx as i64, fy as i64);
+                            let rows: Vec<f32> = (-1..=2)
+                                .map(|dy| {
+                                    let row: Vec<f32> = (-1..=2).map(|dx| at(ix + dx, iy + dy)).collect();
+                                    cubic(&row, tx)
+                                })
+                                .collect();
+                            cubic(&rows, ty)
+                        }
+                    };
+                    out[(oy * ow + ox) as usize] = value;
+                }
+            }
+        });
+
+        overview
+    }
 }
 
 impl ImageWriter for CogWriter {
@@ -227,20 +1217,10 @@ impl ImageWriter for CogWriter {
             self.tile_size.1
         );
 
-        // COG writing steps:
-        // 1. Build overviews
-        let overviews = self.build_overviews(image)?;
-
-        // 2. Write in COG-compliant order:
-        //    - Small overviews first
-        //    - Full resolution last
-        //    - IFD offsets at end
-
-        // 3. Use tiling for all levels
-
-        // 4. Apply compression
+        let bytes = self.encode(image)?;
+        std::fs::write(&self.path, &bytes)?;
 
-        log::info!("COG written with {} overview levels", overviews.len());
+        log::info!("COG written: {} bytes", bytes.len());
 
         Ok(())
     }
@@ -251,30 +1231,270 @@ impl ImageWriter for CogWriter {
     }
 }
 
+/// Catmull-Rom interpolation through four samples at fraction `t` past the second
+fn cubic(p: &[f32], t: f32) -> f32 {
+    let a = -0.5 * p[0] + 1.5 * p[1] - 1.5 * p[2] + 0.5 * p[3];
+    let b = p[0] - 2.5 * p[1] + 2.0 * p[2] - 0.5 * p[3];
+    let c = -0.5 * p[0] + 0.5 * p[2];
+    ((a * t + b) * t + c) * t + p[1]
+}
+
+/// Append one little-endian sample, rounding and saturating integer types
+fn encode_sample(out: &mut Vec<u8>, data_type: DataType, value: f32) {
+    match data_type {
+        DataType::UInt8 => out.push(value.round() as u8),
+        DataType::UInt16 => out.extend_from_slice(&(value.round() as u16).to_le_bytes()),
+        DataType::Int16 => out.extend_from_slice(&(value.round() as i16).to_le_bytes()),
+        DataType::UInt32 => out.extend_from_slice(&(value.round() as u32).to_le_bytes()),
+        DataType::Int32 => out.extend_from_slice(&(value.round() as i32).to_le_bytes()),
+        DataType::Float32 => out.extend_from_slice(&value.to_le_bytes()),
+        DataType::Float64 => out.extend_from_slice(&(value as f64).to_le_bytes()),
+    }
+}
+
+/// GDAL structural metadata announcing the COG layout
+fn ghost_area() -> String {
+    let body = "LAYOUT=IFDS_BEFORE_DATA\nBLOCK_ORDER=ROW_MAJOR\nKNOWN_INCOMPATIBLE_EDITION=NO\n";
+    format!("{}{:06} bytes\n{}", GHOST_PREFIX, body.len(), body)
+}
+
+/// GDAL_METADATA XML carrying band descriptions
+fn band_metadata(names: &[String]) -> String {
+    let mut xml = String::from("<GDALMetadata>\n");
+    for (band, name) in names.iter().enumerate().filter(|(_, name)| !name.is_empty()) {
+        xml.push_str(&format!(
+            "  <Item name=\"DESCRIPTION\" sample=\"{}\" role=\"description\">{}</Item>\n",
+            band,
+            xml_escape(name)
+        ));
+    }
+    xml.push_str("</GDALMetadata>\n");
+    xml
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Cursor;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    fn test_image(width: u32, height: u32, bands: u32, data_type: DataType) -> MultiBandImage {
+        let metadata = ImageMetadata {
+            width,
+            height,
+            bands,
+            bits_per_sample: (data_type.size() * 8) as u16,
+            geo_transform: Some([500000.0, 10.0, 0.0, 4200000.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, data_type);
+        for (b, band) in image.bands.iter_mut().enumerate() {
+            for (i, value) in band.iter_mut().enumerate() {
+                let (x, y) = ((i as u32 % width) as f32, (i as u32 / width) as f32);
+                *value = match data_type {
+                    DataType::Float32 | DataType::Float64 => (x * 0.37).sin() * 100.0 + y * 0.5 + b as f32,
+                    _ => ((x + 2.0 * y) as u32 % 200 + b as u32 * 20) as f32,
+                };
+            }
+        }
+        image
+    }
+
+    fn writer(compression: Compression, tile: u32, levels: Vec<u32>) -> CogWriter {
+        let mut writer = CogWriter::new("unused.tif").unwrap();
+        writer
+            .with_compression(compression)
+            .with_tile_size(tile, tile)
+            .with_overview_levels(levels);
+        writer
+    }
+
+    /// Range source that records how many bytes were requested
+    struct CountingSource {
+        inner: ReaderSource<Cursor<Vec<u8>>>,
+        bytes_read: Arc<AtomicU64>,
+    }
+
+    impl RangeSource for CountingSource {
+        fn size(&mut self) -> Result<u64> {
+            self.inner.size()
+        }
+
+        fn read_range(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+            self.bytes_read.fetch_add(buf.len() as u64, Ordering::SeqCst);
+            self.inner.read_range(offset, buf)
+        }
+    }
 
     #[test]
     fn test_cog_tile_grid() {
-        let reader = CogReader {
-            path: "test.tif".to_string(),
-            metadata: ImageMetadata {
-                width: 1024,
-                height: 2048,
-                bands: 3,
-                bits_per_sample: 8,
-                geo_transform: None,
-                crs: None,
-                no_data: None,
-                band_names: vec![],
-            },
-            is_remote: false,
-            tile_size: (512, 512),
-        };
+        let image = test_image(1024, 2048, 3, DataType::UInt8);
+        let bytes = writer(Compression::Deflate, 512, vec![]).encode(&image).unwrap();
+        let reader = CogReader::from_reader(Cursor::new(bytes)).unwrap();
 
         let (tiles_x, tiles_y) = reader.tile_grid();
         assert_eq!(tiles_x, 2);
         assert_eq!(tiles_y, 4);
     }
+
+    #[test]
+    fn test_cog_roundtrip_with_overviews() {
+        let mut image = test_image(300, 200, 2, DataType::UInt16);
+        image.metadata.no_data = Some(0.0);
+        image.metadata.band_names = vec!["Red".to_string(), "NIR & <edge>".to_string()];
+
+        let bytes = writer(Compression::Deflate, 128, vec![2, 4]).encode(&image).unwrap();
+        let mut reader = CogReader::from_reader(Cursor::new(bytes)).unwrap();
+
+        let report = reader.validate().unwrap();
+        assert!(report.is_valid(), "{:?}", report);
+        assert!(report.warnings.is_empty(), "{:?}", report);
+
+        assert_eq!(reader.overview_count(), 2);
+        assert_eq!(reader.overview_size(1), Some((150, 100)));
+        assert_eq!(reader.overview_size(2), Some((75, 50)));
+        assert_eq!(reader.data_type(), DataType::UInt16);
+
+        let metadata = reader.metadata().clone();
+        assert_eq!(metadata.geo_transform, image.metadata.geo_transform);
+        assert_eq!(metadata.crs.as_deref(), Some("EPSG:32633"));
+        assert_eq!(metadata.no_data, Some(0.0));
+        assert_eq!(metadata.band_names, image.metadata.band_names);
+
+        let restored = reader.read().unwrap();
+        assert_eq!(restored.bands, image.bands);
+
+        let overview = reader.read_overview(1).unwrap();
+        let gt = overview.metadata.geo_transform.unwrap();
+        assert_eq!(gt[1], 20.0);
+        let expected = (image.bands[1][0] + image.bands[1][1] + image.bands[1][300] + image.bands[1][301]) / 4.0;
+        assert_eq!(overview.bands[1][0], expected.round());
+
+        let tile = reader.read_tile(2, 1, 0).unwrap();
+        assert_eq!((tile.metadata.width, tile.metadata.height), (44, 72));
+        assert_eq!(tile.get_pixel(1, 0, 0), image.get_pixel(1, 256, 128));
+        assert_eq!(tile.metadata.geo_transform.unwrap()[0], 500000.0 + 256.0 * 10.0);
+    }
+
+    #[test]
+    fn test_lossless_codecs_and_predictors() {
+        for data_type in [DataType::Float32, DataType::Int16, DataType::UInt8] {
+            let image = test_image(100, 70, 3, data_type);
+            for compression in [Compression::None, Compression::Lzw, Compression::Zstd { level: 3 }] {
+                let bytes = writer(compression, 32, vec![2]).encode(&image).unwrap();
+                let mut reader = CogReader::from_reader(Cursor::new(bytes)).unwrap();
+                assert_eq!(reader.data_type(), data_type);
+                assert_eq!(reader.read().unwrap().bands, image.bands, "{:?} {:?}", data_type, compression);
+            }
+        }
+    }
+
+    #[test]
+    fn test_output_readable_by_tiff_crate() {
+        let image = test_image(300, 200, 1, DataType::UInt16);
+        let bytes = writer(Compression::Deflate, 128, vec![2]).encode(&image).unwrap();
+
+        let mut decoder = tiff::decoder::Decoder::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (300, 200));
+        match decoder.read_image().unwrap() {
+            tiff::decoder::DecodingResult::U16(samples) => {
+                let samples: Vec<f32> = samples.into_iter().map(|v| v as f32).collect();
+                assert_eq!(samples, image.bands[0]);
+            }
+            _ => panic!("expected 16-bit samples"),
+        }
+        assert!(decoder.more_images());
+    }
+
+    #[test]
+    fn test_jpeg_tiles() {
+        let image = test_image(64, 48, 3, DataType::UInt8);
+        let bytes = writer(Compression::Jpeg { quality: 95 }, 32, vec![]).encode(&image).unwrap();
+        let mut reader = CogReader::from_reader(Cursor::new(bytes)).unwrap();
+        let restored = reader.read().unwrap();
+
+        for (original, decoded) in image.bands.iter().zip(&restored.bands) {
+            let mean_error: f32 =
+                original.iter().zip(decoded).map(|(a, b)| (a - b).abs()).sum::<f32>() / original.len() as f32;
+            assert!(mean_error < 4.0, "mean error {}", mean_error);
+        }
+
+        let float = test_image(64, 48, 1, DataType::Float32);
+        assert!(writer(Compression::Jpeg { quality: 90 }, 32, vec![]).encode(&float).is_err());
+    }
+
+    #[test]
+    fn test_windowed_read_fetches_only_needed_tiles() {
+        let image = test_image(1024, 1024, 1, DataType::Float32);
+        let bytes = writer(Compression::None, 256, vec![2, 4]).encode(&image).unwrap();
+        let total = bytes.len() as u64;
+
+        let bytes_read = Arc::new(AtomicU64::new(0));
+        let source = CountingSource {
+            inner: ReaderSource::new(Cursor::new(bytes)),
+            bytes_read: bytes_read.clone(),
+        };
+        let mut reader = CogReader::from_source(source).unwrap();
+        let opened = bytes_read.load(Ordering::SeqCst);
+
+        let window = reader.read_region(300, 300, 100, 100).unwrap();
+        assert_eq!(window.get_pixel(0, 0, 0), image.get_pixel(0, 300, 300));
+        assert_eq!(window.get_pixel(0, 99, 99), image.get_pixel(0, 399, 399));
+
+        let tile_bytes = 256 * 256 * 4;
+        assert_eq!(bytes_read.load(Ordering::SeqCst) - opened, tile_bytes);
+        assert!(opened < total / 100);
+
+        // A corrupt byte count fails instead of allocating
+        reader.levels[0].byte_counts[0] = u64::MAX;
+        assert!(matches!(reader.read_region(0, 0, 10, 10), Err(ImageryError::InvalidFormat(_))));
+        assert_eq!(bytes_read.load(Ordering::SeqCst) - opened, tile_bytes);
+    }
+
+    #[test]
+    fn test_validation_reports_layout_violations() {
+        // Striped TIFF with the IFD written after the pixel data
+        let width = 600u32;
+        let height = 600u32;
+        let mut bytes = b"II".to_vec();
+        bytes.extend_from_slice(&42u16.to_le_bytes());
+        let ifd_offset = 8 + width * height;
+        bytes.extend_from_slice(&ifd_offset.to_le_bytes());
+        bytes.extend((0..width * height).map(|i| (i % 251) as u8));
+
+        let mut ifd = IfdBuilder::new();
+        ifd.long(tag::IMAGE_WIDTH, &[width]);
+        ifd.long(tag::IMAGE_LENGTH, &[height]);
+        ifd.short(tag::BITS_PER_SAMPLE, &[8]);
+        ifd.short(tag::COMPRESSION, &[method::NONE]);
+        ifd.short(tag::PHOTOMETRIC, &[photometric::MIN_IS_BLACK]);
+        ifd.long(tag::STRIP_OFFSETS, &[8]);
+        ifd.short(tag::SAMPLES_PER_PIXEL, &[1]);
+        ifd.long(tag::ROWS_PER_STRIP, &[height]);
+        ifd.long(tag::STRIP_BYTE_COUNTS, &[width * height]);
+        ifd.write(&mut bytes, false, 0);
+
+        let mut reader = CogReader::from_reader(Cursor::new(bytes)).unwrap();
+        let report = reader.validate().unwrap();
+        assert!(!report.is_valid());
+        assert!(report.errors.contains(&CogViolation::NotTiled { level: 0 }));
+        assert!(report.errors.contains(&CogViolation::MainIfdNotAtStart { offset: ifd_offset as u64 }));
+        assert!(report.errors.contains(&CogViolation::IfdAfterData { level: 0 }));
+        assert_eq!(report.warnings, vec![CogViolation::MissingOverviews]);
+
+        // Striped files are still readable
+        let region = reader.read_region(10, 20, 5, 5).unwrap();
+        assert_eq!(region.get_pixel(0, 0, 0), Some(((20 * width + 10) % 251) as f32));
+    }
+
+    #[test]
+    fn test_open_url_requires_range_source() {
+        assert!(matches!(
+            CogReader::open_url("https://example.com/image.tif"),
+            Err(ImageryError::UnsupportedFormat(_))
+        ));
+    }
 }
@@ -10,9 +10,13 @@ pub mod geotiff;
 pub mod cog;
 pub mod jpeg2000;
 pub mod nitf;
+pub mod range;
+pub(crate) mod tiff_ifd;
+pub(crate) mod tile_codec;
 
 pub use geotiff::{GeoTiffReader, GeoTiffWriter};
-pub use cog::{CogReader, CogWriter};
+pub use cog::{CogReader, CogWriter, CogValidationReport, CogViolation};
+pub use range::{RangeSource, ReaderSource};
 
 use crate::error::Result;
 use crate::MultiBandImage;
@@ -69,6 +73,11 @@ pub enum Compression {
     Deflate,
     /// LZW compression
     Lzw,
+    /// Zstandard compression
+    Zstd {
+        /// Compression level (1-22)
+        level: i32,
+    },
     /// JPEG compression
     Jpeg {
         /// Quality (1-100)
@@ -0,0 +1,105 @@
+//! Byte-range sources for random-access reads
+//!
+//! Tiled formats such as COG only need a handful of byte ranges to answer a
+//! windowed read. [`RangeSource`] abstracts where those bytes come from so the
+//! same reader works over local files, in-memory buffers or any remote store
+//! that can serve range requests.
+
+use crate::error::{ImageryError, Result};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Random-access source of bytes
+pub trait RangeSource: Send {
+    /// Total size of the underlying object in bytes
+    fn size(&mut self) -> Result<u64>;
+
+    /// Fill `buf` with the bytes starting at `offset`
+    fn read_range(&mut self, offset: u64, buf: &mut [u8]) -> Result<()>;
+
+    /// Read `length` bytes starting at `offset` into a new buffer
+    ///
+    /// The range is checked against [`size`](Self::size) before the buffer
+    /// is allocated.
+    fn read_vec(&mut self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        check_range(offset, length as u64, self.size()?)?;
+        let mut buf = vec![0u8; length];
+        self.read_range(offset, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Range source backed by any `Read + Seek` implementation
+pub struct ReaderSource<R> {
+    inner: R,
+    size: Option<u64>,
+}
+
+impl<R: Read + Seek + Send> ReaderSource<R> {
+    /// Wrap a seekable reader
+    pub fn new(inner: R) -> Self {
+        Self { inner, size: None }
+    }
+
+    /// Consume the source and return the wrapped reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl ReaderSource<BufReader<File>> {
+    /// Open a local file as a range source
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: Read + Seek + Send> RangeSource for ReaderSource<R> {
+    fn size(&mut self) -> Result<u64> {
+        if let Some(size) = self.size {
+            return Ok(size);
+        }
+        let size = self.inner.seek(SeekFrom::End(0))?;
+        self.size = Some(size);
+        Ok(size)
+    }
+
+    fn read_range(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        check_range(offset, buf.len() as u64, self.size()?)?;
+        self.inner.seek(SeekFrom::Start(offset))?;
+        self.inner.read_exact(buf)?;
+        Ok(())
+    }
+}
+
+/// Fail unless `length` bytes from `offset` lie within a source of `size` bytes
+fn check_range(offset: u64, length: u64, size: u64) -> Result<()> {
+    if offset.checked_add(length).is_none_or(|end| end > size) {
+        return Err(ImageryError::InvalidFormat(format!(
+            "Byte range {}..{} exceeds source size {}",
+            offset,
+            offset.saturating_add(length),
+            size
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reader_source_ranges() {
+        let mut source = ReaderSource::new(Cursor::new((0u8..100).collect::<Vec<_>>()));
+        assert_eq!(source.size().unwrap(), 100);
+        assert_eq!(source.read_vec(10, 4).unwrap(), vec![10, 11, 12, 13]);
+        assert!(source.read_vec(98, 4).is_err());
+        assert!(source.read_vec(u64::MAX - 1, 4).is_err());
+        // Rejected before the buffer is allocated
+        assert!(source.read_vec(0, usize::MAX).is_err());
+    }
+}
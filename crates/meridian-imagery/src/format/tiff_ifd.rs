@@ -0,0 +1,730 @@
+//! Low-level TIFF structures
+//!
+//! Parses classic and BigTIFF headers and IFD chains through a
+//! [`RangeSource`], serializes IFDs for writers and translates between the
+//! GeoTIFF key directory and [`ImageMetadata`](crate::ImageMetadata) fields.
+
+use crate::error::{ImageryError, Result};
+use crate::format::range::RangeSource;
+use std::collections::{BTreeMap, HashSet};
+
+/// TIFF tag numbers used by the readers and writers
+pub(crate) mod tag {
+    pub const NEW_SUBFILE_TYPE: u16 = 254;
+    pub const IMAGE_WIDTH: u16 = 256;
+    pub const IMAGE_LENGTH: u16 = 257;
+    pub const BITS_PER_SAMPLE: u16 = 258;
+    pub const COMPRESSION: u16 = 259;
+    pub const PHOTOMETRIC: u16 = 262;
+    pub const STRIP_OFFSETS: u16 = 273;
+    pub const SAMPLES_PER_PIXEL: u16 = 277;
+    pub const ROWS_PER_STRIP: u16 = 278;
+    pub const STRIP_BYTE_COUNTS: u16 = 279;
+    pub const PLANAR_CONFIG: u16 = 284;
+    pub const PREDICTOR: u16 = 317;
+    pub const TILE_WIDTH: u16 = 322;
+    pub const TILE_LENGTH: u16 = 323;
+    pub const TILE_OFFSETS: u16 = 324;
+    pub const TILE_BYTE_COUNTS: u16 = 325;
+    pub const EXTRA_SAMPLES: u16 = 338;
+    pub const SAMPLE_FORMAT: u16 = 339;
+    pub const JPEG_TABLES: u16 = 347;
+    pub const YCBCR_SUBSAMPLING: u16 = 530;
+    pub const MODEL_PIXEL_SCALE: u16 = 33550;
+    pub const MODEL_TIEPOINT: u16 = 33922;
+    pub const MODEL_TRANSFORMATION: u16 = 34264;
+    pub const GEO_KEY_DIRECTORY: u16 = 34735;
+    pub const GEO_DOUBLE_PARAMS: u16 = 34736;
+    pub const GEO_ASCII_PARAMS: u16 = 34737;
+    pub const GDAL_METADATA: u16 = 42112;
+    pub const GDAL_NODATA: u16 = 42113;
+}
+
+/// TIFF field types
+mod field_type {
+    pub const BYTE: u16 = 1;
+    pub const ASCII: u16 = 2;
+    pub const SHORT: u16 = 3;
+    pub const LONG: u16 = 4;
+    pub const RATIONAL: u16 = 5;
+    pub const SBYTE: u16 = 6;
+    pub const UNDEFINED: u16 = 7;
+    pub const SSHORT: u16 = 8;
+    pub const SLONG: u16 = 9;
+    pub const SRATIONAL: u16 = 10;
+    pub const FLOAT: u16 = 11;
+    pub const DOUBLE: u16 = 12;
+    pub const IFD: u16 = 13;
+    pub const LONG8: u16 = 16;
+    pub const SLONG8: u16 = 17;
+    pub const IFD8: u16 = 18;
+
+    /// Size in bytes of one value of the given type
+    pub fn size(field_type: u16) -> Option<u64> {
+        match field_type {
+            BYTE | ASCII | SBYTE | UNDEFINED => Some(1),
+            SHORT | SSHORT => Some(2),
+            LONG | SLONG | FLOAT | IFD => Some(4),
+            RATIONAL | SRATIONAL | DOUBLE | LONG8 | SLONG8 | IFD8 => Some(8),
+            _ => None,
+        }
+    }
+}
+
+/// GeoTIFF key identifiers
+mod geo_key {
+    pub const MODEL_TYPE: u16 = 1024;
+    pub const RASTER_TYPE: u16 = 1025;
+    pub const CITATION: u16 = 1026;
+    pub const GEOGRAPHIC_TYPE: u16 = 2048;
+    pub const PROJECTED_TYPE: u16 = 3072;
+
+    pub const MODEL_PROJECTED: u16 = 1;
+    pub const MODEL_GEOGRAPHIC: u16 = 2;
+    pub const RASTER_PIXEL_IS_AREA: u16 = 1;
+    pub const RASTER_PIXEL_IS_POINT: u16 = 2;
+    pub const USER_DEFINED: u16 = 32767;
+}
+
+/// Largest out-of-line value accepted when parsing an IFD entry
+const MAX_VALUE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Upper bound on the length of an IFD chain
+const MAX_IFDS: usize = 4096;
+
+/// Byte order of a TIFF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteOrder {
+    /// "II" little-endian
+    Little,
+    /// "MM" big-endian
+    Big,
+}
+
+macro_rules! read_number {
+    ($name:ident, $ty:ty) => {
+        pub(crate) fn $name(self, bytes: &[u8]) -> $ty {
+            let mut raw = [0u8; std::mem::size_of::<$ty>()];
+            raw.copy_from_slice(&bytes[..std::mem::size_of::<$ty>()]);
+            match self {
+                ByteOrder::Little => <$ty>::from_le_bytes(raw),
+                ByteOrder::Big => <$ty>::from_be_bytes(raw),
+            }
+        }
+    };
+}
+
+impl ByteOrder {
+    read_number!(read_u16, u16);
+    read_number!(read_u32, u32);
+    read_number!(read_u64, u64);
+    read_number!(read_i16, i16);
+    read_number!(read_i32, i32);
+    read_number!(read_i64, i64);
+    read_number!(read_f32, f32);
+    read_number!(read_f64, f64);
+}
+
+/// Parsed TIFF file header
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TiffHeader {
+    /// Byte order of all multi-byte values
+    pub order: ByteOrder,
+    /// Whether the file is BigTIFF
+    pub big: bool,
+    /// Offset of the first IFD
+    pub first_ifd: u64,
+}
+
+impl TiffHeader {
+    /// Read and validate the header at the start of the source
+    pub fn read(source: &mut dyn RangeSource) -> Result<Self> {
+        let size = source.size()?;
+        if size < 8 {
+            return Err(ImageryError::invalid_format("File too small to be a TIFF"));
+        }
+        let bytes = source.read_vec(0, size.min(16) as usize)?;
+
+        let order = match &bytes[..2] {
+            b"II" => ByteOrder::Little,
+            b"MM" => ByteOrder::Big,
+            _ => return Err(ImageryError::invalid_format("Missing TIFF byte order mark")),
+        };
+
+        match order.read_u16(&bytes[2..]) {
+            42 => Ok(Self {
+                order,
+                big: false,
+                first_ifd: order.read_u32(&bytes[4..]) as u64,
+            }),
+            43 if bytes.len() == 16 => {
+                if order.read_u16(&bytes[4..]) != 8 {
+                    return Err(ImageryError::invalid_format("Unsupported BigTIFF offset size"));
+                }
+                Ok(Self {
+                    order,
+                    big: true,
+                    first_ifd: order.read_u64(&bytes[8..]),
+                })
+            }
+            version => Err(ImageryError::InvalidFormat(format!(
+                "Unsupported TIFF version {}",
+                version
+            ))),
+        }
+    }
+
+    /// Size of the header in bytes
+    pub fn size(&self) -> u64 {
+        if self.big { 16 } else { 8 }
+    }
+
+    /// Size of offsets and inline value slots
+    fn offset_size(&self) -> u64 {
+        if self.big { 8 } else { 4 }
+    }
+
+    fn read_offset(&self, bytes: &[u8]) -> u64 {
+        if self.big {
+            self.order.read_u64(bytes)
+        } else {
+            self.order.read_u32(bytes) as u64
+        }
+    }
+}
+
+/// One entry of an IFD
+#[derive(Debug, Clone)]
+pub(crate) struct IfdEntry {
+    /// Tag number
+    pub tag: u16,
+    /// Field type
+    pub field_type: u16,
+    /// Number of values
+    pub count: u64,
+    /// Inline value or offset of the values
+    raw: [u8; 8],
+}
+
+impl IfdEntry {
+    /// Raw value bytes, fetching them from the source when stored out of line
+    pub fn data(&self, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<Vec<u8>> {
+        let type_size = field_type::size(self.field_type).ok_or_else(|| {
+            ImageryError::InvalidFormat(format!(
+                "Tag {} has unknown field type {}",
+                self.tag, self.field_type
+            ))
+        })?;
+        let length = type_size.checked_mul(self.count).filter(|&len| len <= MAX_VALUE_BYTES);
+        let length = length.ok_or_else(|| {
+            ImageryError::InvalidFormat(format!("Tag {} has an implausible value count", self.tag))
+        })?;
+
+        if length <= header.offset_size() {
+            Ok(self.raw[..length as usize].to_vec())
+        } else {
+            source.read_vec(header.read_offset(&self.raw), length as usize)
+        }
+    }
+
+    /// Values as unsigned integers
+    pub fn values_u64(&self, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<Vec<u64>> {
+        let data = self.data(source, header)?;
+        let order = header.order;
+        let values = match self.field_type {
+            field_type::BYTE | field_type::UNDEFINED => data.iter().map(|&b| b as u64).collect(),
+            field_type::SHORT => data.chunks_exact(2).map(|c| order.read_u16(c) as u64).collect(),
+            field_type::LONG | field_type::IFD => {
+                data.chunks_exact(4).map(|c| order.read_u32(c) as u64).collect()
+            }
+            field_type::LONG8 | field_type::IFD8 => {
+                data.chunks_exact(8).map(|c| order.read_u64(c)).collect()
+            }
+            _ => {
+                return Err(ImageryError::InvalidFormat(format!(
+                    "Tag {} is not an unsigned integer field",
+                    self.tag
+                )))
+            }
+        };
+        Ok(values)
+    }
+
+    /// Values as floating point numbers
+    pub fn values_f64(&self, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<Vec<f64>> {
+        let data = self.data(source, header)?;
+        let order = header.order;
+        let values = match self.field_type {
+            field_type::DOUBLE => data.chunks_exact(8).map(|c| order.read_f64(c)).collect(),
+            field_type::FLOAT => data.chunks_exact(4).map(|c| order.read_f32(c) as f64).collect(),
+            field_type::RATIONAL => data
+                .chunks_exact(8)
+                .map(|c| order.read_u32(c) as f64 / order.read_u32(&c[4..]) as f64)
+                .collect(),
+            field_type::SRATIONAL => data
+                .chunks_exact(8)
+                .map(|c| order.read_i32(c) as f64 / order.read_i32(&c[4..]) as f64)
+                .collect(),
+            field_type::SBYTE => data.iter().map(|&b| b as i8 as f64).collect(),
+            field_type::SSHORT => data.chunks_exact(2).map(|c| order.read_i16(c) as f64).collect(),
+            field_type::SLONG => data.chunks_exact(4).map(|c| order.read_i32(c) as f64).collect(),
+            field_type::SLONG8 => data.chunks_exact(8).map(|c| order.read_i64(c) as f64).collect(),
+            _ => self
+                .values_u64(source, header)?
+                .into_iter()
+                .map(|v| v as f64)
+                .collect(),
+        };
+        Ok(values)
+    }
+
+    /// Value as an ASCII string without trailing NULs
+    pub fn ascii(&self, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<String> {
+        let data = self.data(source, header)?;
+        Ok(String::from_utf8_lossy(&data).trim_end_matches('\0').to_string())
+    }
+}
+
+/// Parsed image file directory
+#[derive(Debug, Clone)]
+pub(crate) struct Ifd {
+    /// File offset of the directory
+    pub offset: u64,
+    /// Entries in file order
+    pub entries: Vec<IfdEntry>,
+    /// Offset of the next directory, zero at the end of the chain
+    pub next: u64,
+}
+
+impl Ifd {
+    /// Read the directory at `offset`
+    pub fn read(source: &mut dyn RangeSource, header: &TiffHeader, offset: u64) -> Result<Self> {
+        let (count_size, entry_size) = if header.big { (8, 20) } else { (2, 12) };
+        let count_bytes = source.read_vec(offset, count_size)?;
+        let count = if header.big {
+            header.order.read_u64(&count_bytes)
+        } else {
+            header.order.read_u16(&count_bytes) as u64
+        };
+        if count > u16::MAX as u64 {
+            return Err(ImageryError::InvalidFormat(format!(
+                "IFD at {} declares {} entries",
+                offset, count
+            )));
+        }
+
+        let table_len = count as usize * entry_size + header.offset_size() as usize;
+        let table = source.read_vec(offset + count_size as u64, table_len)?;
+        let order = header.order;
+
+        let entries = table
+            .chunks_exact(entry_size)
+            .take(count as usize)
+            .map(|chunk| {
+                let (count, value) = if header.big {
+                    (order.read_u64(&chunk[4..]), &chunk[12..20])
+                } else {
+                    (order.read_u32(&chunk[4..]) as u64, &chunk[8..12])
+                };
+                let mut raw = [0u8; 8];
+                raw[..value.len()].copy_from_slice(value);
+                IfdEntry {
+                    tag: order.read_u16(chunk),
+                    field_type: order.read_u16(&chunk[2..]),
+                    count,
+                    raw,
+                }
+            })
+            .collect();
+
+        let next = header.read_offset(&table[count as usize * entry_size..]);
+        Ok(Self { offset, entries, next })
+    }
+
+    /// Entry for `tag`, if present
+    pub fn get(&self, tag: u16) -> Option<&IfdEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+
+    /// Unsigned integer values of `tag`, if present
+    pub fn u64s(&self, source: &mut dyn RangeSource, header: &TiffHeader, tag: u16) -> Result<Option<Vec<u64>>> {
+        self.get(tag).map(|entry| entry.values_u64(source, header)).transpose()
+    }
+
+    /// First unsigned integer value of `tag`, if present
+    pub fn first_u64(&self, source: &mut dyn RangeSource, header: &TiffHeader, tag: u16) -> Result<Option<u64>> {
+        Ok(self.u64s(source, header, tag)?.and_then(|values| values.first().copied()))
+    }
+
+    /// Floating point values of `tag`, if present
+    pub fn f64s(&self, source: &mut dyn RangeSource, header: &TiffHeader, tag: u16) -> Result<Option<Vec<f64>>> {
+        self.get(tag).map(|entry| entry.values_f64(source, header)).transpose()
+    }
+
+    /// ASCII value of `tag`, if present
+    pub fn ascii(&self, source: &mut dyn RangeSource, header: &TiffHeader, tag: u16) -> Result<Option<String>> {
+        self.get(tag).map(|entry| entry.ascii(source, header)).transpose()
+    }
+}
+
+/// Read every IFD reachable from the header
+pub(crate) fn read_ifd_chain(source: &mut dyn RangeSource, header: &TiffHeader) -> Result<Vec<Ifd>> {
+    let mut ifds = Vec::new();
+    let mut seen = HashSet::new();
+    let mut offset = header.first_ifd;
+
+    while offset != 0 {
+        if !seen.insert(offset) || ifds.len() >= MAX_IFDS {
+            return Err(ImageryError::InvalidFormat(format!(
+                "IFD chain loops or exceeds {} directories",
+                MAX_IFDS
+            )));
+        }
+        let ifd = Ifd::read(source, header, offset)?;
+        offset = ifd.next;
+        ifds.push(ifd);
+    }
+
+    Ok(ifds)
+}
+
+/// A field queued for serialization
+#[derive(Debug, Clone)]
+struct Field {
+    field_type: u16,
+    count: u64,
+    data: Vec<u8>,
+}
+
+/// Little-endian IFD serializer
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IfdBuilder {
+    fields: BTreeMap<u16, Field>,
+}
+
+impl IfdBuilder {
+    /// Create an empty directory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert(&mut self, tag: u16, field_type: u16, count: usize, data: Vec<u8>) {
+        self.fields.insert(tag, Field { field_type, count: count as u64, data });
+    }
+
+    /// Set a SHORT field
+    pub fn short(&mut self, tag: u16, values: &[u16]) {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.insert(tag, field_type::SHORT, values.len(), data);
+    }
+
+    /// Set a LONG field
+    pub fn long(&mut self, tag: u16, values: &[u32]) {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.insert(tag, field_type::LONG, values.len(), data);
+    }
+
+    /// Set an offset or byte count field, using LONG8 for BigTIFF
+    pub fn offsets(&mut self, tag: u16, values: &[u64], big: bool) {
+        if big {
+            let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+            self.insert(tag, field_type::LONG8, values.len(), data);
+        } else {
+            let data = values.iter().flat_map(|&v| (v as u32).to_le_bytes()).collect();
+            self.insert(tag, field_type::LONG, values.len(), data);
+        }
+    }
+
+    /// Set a DOUBLE field
+    pub fn double(&mut self, tag: u16, values: &[f64]) {
+        let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        self.insert(tag, field_type::DOUBLE, values.len(), data);
+    }
+
+    /// Set a NUL-terminated ASCII field
+    pub fn ascii(&mut self, tag: u16, value: &str) {
+        let mut data = value.as_bytes().to_vec();
+        data.push(0);
+        self.insert(tag, field_type::ASCII, data.len(), data);
+    }
+
+    /// Size of the entry table without out-of-line values
+    fn table_size(&self, big: bool) -> u64 {
+        let n = self.fields.len() as u64;
+        if big { 8 + n * 20 + 8 } else { 2 + n * 12 + 4 }
+    }
+
+    /// Total serialized size including word-aligned out-of-line values
+    pub fn byte_size(&self, big: bool) -> u64 {
+        let inline = if big { 8 } else { 4 };
+        let values: u64 = self
+            .fields
+            .values()
+            .map(|field| field.data.len() as u64)
+            .filter(|&len| len > inline)
+            .map(|len| len + len % 2)
+            .sum();
+        self.table_size(big) + values
+    }
+
+    /// Append the directory to `out`, which must end at the directory's offset
+    pub fn write(&self, out: &mut Vec<u8>, big: bool, next: u64) {
+        let start = out.len() as u64;
+        let inline = if big { 8 } else { 4 };
+        let mut value_offset = start + self.table_size(big);
+        let mut values = Vec::new();
+
+        if big {
+            out.extend_from_slice(&(self.fields.len() as u64).to_le_bytes());
+        } else {
+            out.extend_from_slice(&(self.fields.len() as u16).to_le_bytes());
+        }
+
+        for (&tag, field) in &self.fields {
+            out.extend_from_slice(&tag.to_le_bytes());
+            out.extend_from_slice(&field.field_type.to_le_bytes());
+
+            let mut slot = vec![0u8; inline];
+            if field.data.len() <= inline {
+                slot[..field.data.len()].copy_from_slice(&field.data);
+            } else {
+                slot.copy_from_slice(&value_offset.to_le_bytes()[..inline]);
+                values.extend_from_slice(&field.data);
+                if field.data.len() % 2 == 1 {
+                    values.push(0);
+                }
+                value_offset = start + self.table_size(big) + values.len() as u64;
+            }
+
+            if big {
+                out.extend_from_slice(&field.count.to_le_bytes());
+            } else {
+                out.extend_from_slice(&(field.count as u32).to_le_bytes());
+            }
+            out.extend_from_slice(&slot);
+        }
+
+        if big {
+            out.extend_from_slice(&next.to_le_bytes());
+        } else {
+            out.extend_from_slice(&(next as u32).to_le_bytes());
+        }
+        out.extend_from_slice(&values);
+    }
+}
+
+/// Georeferencing recovered from or destined for GeoTIFF tags
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct GeoReference {
+    /// Affine geotransform in GDAL order
+    pub geo_transform: Option<[f64; 6]>,
+    /// CRS as `EPSG:<code>` or the citation of a user-defined system
+    pub crs: Option<String>,
+}
+
+/// Read the geotransform and CRS of an IFD
+pub(crate) fn read_geo_reference(ifd: &Ifd, source: &mut dyn RangeSource, header: &TiffHeader) -> Result<GeoReference> {
+    let mut keys = BTreeMap::new();
+    let mut citation = None;
+
+    if let Some(directory) = ifd.u64s(source, header, tag::GEO_KEY_DIRECTORY)? {
+        let ascii = ifd.ascii(source, header, tag::GEO_ASCII_PARAMS)?.unwrap_or_default();
+
+        for key in directory.chunks_exact(4).skip(1) {
+            let (id, location, count, value) = (key[0] as u16, key[1] as u16, key[2] as usize, key[3] as usize);
+            match location {
+                0 => {
+                    keys.insert(id, value as u16);
+                }
+                tag::GEO_ASCII_PARAMS if id == geo_key::CITATION || citation.is_none() => {
+                    let text = ascii.get(value..value + count).unwrap_or_default();
+                    let text = text.trim_end_matches(['|', '\0']).to_string();
+                    if !text.is_empty() {
+                        citation = Some(text);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let crs = [geo_key::PROJECTED_TYPE, geo_key::GEOGRAPHIC_TYPE]
+        .iter()
+        .filter_map(|id| keys.get(id))
+        .find(|&&code| code != 0 && code != geo_key::USER_DEFINED)
+        .map(|code| format!("EPSG:{}", code))
+        .or(citation);
+
+    let mut geo_transform = if let Some(m) = ifd.f64s(source, header, tag::MODEL_TRANSFORMATION)? {
+        (m.len() >= 16).then(|| [m[3], m[0], m[1], m[7], m[4], m[5]])
+    } else {
+        let scale = ifd.f64s(source, header, tag::MODEL_PIXEL_SCALE)?;
+        let tiepoint = ifd.f64s(source, header, tag::MODEL_TIEPOINT)?;
+        match (scale, tiepoint) {
+            (Some(s), Some(t)) if s.len() >= 2 && t.len() >= 6 => Some([
+                t[3] - t[0] * s[0],
+                s[0],
+                0.0,
+                t[4] + t[1] * s[1],
+                0.0,
+                -s[1],
+            ]),
+            _ => None,
+        }
+    };
+
+    if keys.get(&geo_key::RASTER_TYPE) == Some(&geo_key::RASTER_PIXEL_IS_POINT) {
+        if let Some(gt) = geo_transform.as_mut() {
+            gt[0] -= 0.5 * (gt[1] + gt[2]);
+            gt[3] -= 0.5 * (gt[4] + gt[5]);
+        }
+    }
+
+    Ok(GeoReference { geo_transform, crs })
+}
+
+/// Add the GeoTIFF tags describing `geo` to a directory
+pub(crate) fn write_geo_reference(builder: &mut IfdBuilder, geo: &GeoReference) {
+    if let Some(gt) = geo.geo_transform {
+        if gt[2] == 0.0 && gt[4] == 0.0 {
+            builder.double(tag::MODEL_PIXEL_SCALE, &[gt[1], -gt[5], 0.0]);
+            builder.double(tag::MODEL_TIEPOINT, &[0.0, 0.0, 0.0, gt[0], gt[3], 0.0]);
+        } else {
+            builder.double(
+                tag::MODEL_TRANSFORMATION,
+                &[
+                    gt[1], gt[2], 0.0, gt[0],
+                    gt[4], gt[5], 0.0, gt[3],
+                    0.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0,
+                ],
+            );
+        }
+    }
+
+    let mut keys: Vec<[u16; 4]> = Vec::new();
+    let mut ascii = String::new();
+
+    if geo.geo_transform.is_some() || geo.crs.is_some() {
+        keys.push([geo_key::RASTER_TYPE, 0, 1, geo_key::RASTER_PIXEL_IS_AREA]);
+    }
+
+    match geo.crs.as_deref().map(|crs| (crs, epsg_code(crs))) {
+        Some((_, Some(code))) => {
+            let geographic = (4000..5000).contains(&code);
+            let (model, key) = if geographic {
+                (geo_key::MODEL_GEOGRAPHIC, geo_key::GEOGRAPHIC_TYPE)
+            } else {
+                (geo_key::MODEL_PROJECTED, geo_key::PROJECTED_TYPE)
+            };
+            keys.push([geo_key::MODEL_TYPE, 0, 1, model]);
+            keys.push([key, 0, 1, code]);
+        }
+        Some((crs, None)) => {
+            ascii = format!("{}|", crs.replace('|', " "));
+            keys.push([geo_key::CITATION, tag::GEO_ASCII_PARAMS, ascii.len() as u16, 0]);
+        }
+        None => {}
+    }
+
+    if keys.is_empty() {
+        return;
+    }
+
+    keys.sort_by_key(|key| key[0]);
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    directory.extend(keys.iter().flatten());
+    builder.short(tag::GEO_KEY_DIRECTORY, &directory);
+    if !ascii.is_empty() {
+        builder.ascii(tag::GEO_ASCII_PARAMS, &ascii);
+    }
+}
+
+/// EPSG code of a CRS written as `EPSG:<code>` or a bare number
+fn epsg_code(crs: &str) -> Option<u16> {
+    let crs = crs.trim();
+    let code = match crs.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("epsg:") => &crs[5..],
+        _ => crs,
+    };
+    code.parse().ok().filter(|&code| code != 0 && code != geo_key::USER_DEFINED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::range::ReaderSource;
+    use std::io::Cursor;
+
+    fn write_single(builder: &IfdBuilder, big: bool) -> Vec<u8> {
+        let mut out = if big {
+            let mut header = b"II".to_vec();
+            header.extend_from_slice(&43u16.to_le_bytes());
+            header.extend_from_slice(&8u16.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&16u64.to_le_bytes());
+            header
+        } else {
+            let mut header = b"II".to_vec();
+            header.extend_from_slice(&42u16.to_le_bytes());
+            header.extend_from_slice(&8u32.to_le_bytes());
+            header
+        };
+        builder.write(&mut out, big, 0);
+        out
+    }
+
+    #[test]
+    fn test_ifd_roundtrip_classic_and_big() {
+        for big in [false, true] {
+            let mut builder = IfdBuilder::new();
+            builder.long(tag::IMAGE_WIDTH, &[300]);
+            builder.short(tag::BITS_PER_SAMPLE, &[16, 16, 16]);
+            builder.offsets(tag::TILE_OFFSETS, &[10, 20, 30, 40], big);
+            builder.ascii(tag::GDAL_NODATA, "-9999");
+
+            let bytes = write_single(&builder, big);
+            assert_eq!(bytes.len() as u64, if big { 16 } else { 8 } + builder.byte_size(big));
+
+            let mut source = ReaderSource::new(Cursor::new(bytes));
+            let header = TiffHeader::read(&mut source).unwrap();
+            assert_eq!(header.big, big);
+
+            let ifds = read_ifd_chain(&mut source, &header).unwrap();
+            assert_eq!(ifds.len(), 1);
+            let ifd = &ifds[0];
+            assert_eq!(ifd.first_u64(&mut source, &header, tag::IMAGE_WIDTH).unwrap(), Some(300));
+            assert_eq!(ifd.u64s(&mut source, &header, tag::BITS_PER_SAMPLE).unwrap(), Some(vec![16, 16, 16]));
+            assert_eq!(ifd.u64s(&mut source, &header, tag::TILE_OFFSETS).unwrap(), Some(vec![10, 20, 30, 40]));
+            assert_eq!(ifd.ascii(&mut source, &header, tag::GDAL_NODATA).unwrap().as_deref(), Some("-9999"));
+        }
+    }
+
+    #[test]
+    fn test_geo_reference_roundtrip() {
+        let cases = [
+            GeoReference {
+                geo_transform: Some([500000.0, 30.0, 0.0, 4200000.0, 0.0, -30.0]),
+                crs: Some("EPSG:32633".to_string()),
+            },
+            GeoReference {
+                geo_transform: Some([10.0, 0.5, 0.1, 50.0, 0.1, -0.5]),
+                crs: Some("EPSG:4326".to_string()),
+            },
+            GeoReference {
+                geo_transform: None,
+                crs: Some("Local grid".to_string()),
+            },
+        ];
+
+        for geo in cases {
+            let mut builder = IfdBuilder::new();
+            write_geo_reference(&mut builder, &geo);
+            let mut source = ReaderSource::new(Cursor::new(write_single(&builder, false)));
+            let header = TiffHeader::read(&mut source).unwrap();
+            let ifd = Ifd::read(&mut source, &header, header.first_ifd).unwrap();
+
+            assert_eq!(read_geo_reference(&ifd, &mut source, &header).unwrap(), geo);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Tile compression codecs and predictors for TIFF-based formats
+
+use crate::error::{ImageryError, Result};
+use crate::format::tiff_ifd::ByteOrder;
+use crate::format::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+
+/// TIFF compression scheme codes
+pub(crate) mod method {
+    pub const NONE: u16 = 1;
+    pub const LZW: u16 = 5;
+    pub const JPEG: u16 = 7;
+    pub const DEFLATE: u16 = 8;
+    pub const PACKBITS: u16 = 32773;
+    pub const DEFLATE_LEGACY: u16 = 32946;
+    pub const ZSTD: u16 = 50000;
+}
+
+/// TIFF predictor codes
+pub(crate) mod predictor {
+    pub const NONE: u16 = 1;
+    pub const HORIZONTAL: u16 = 2;
+    pub const FLOATING_POINT: u16 = 3;
+}
+
+/// TIFF photometric interpretations relevant to JPEG tiles
+pub(crate) mod photometric {
+    pub const MIN_IS_BLACK: u16 = 1;
+    pub const RGB: u16 = 2;
+    pub const YCBCR: u16 = 6;
+}
+
+/// TIFF compression code for a writer compression setting
+pub(crate) fn compression_code(compression: Compression) -> Result<u16> {
+    match compression {
+        Compression::None => Ok(method::NONE),
+        Compression::Deflate => Ok(method::DEFLATE),
+        Compression::Lzw => Ok(method::LZW),
+        Compression::Zstd { .. } => Ok(method::ZSTD),
+        Compression::Jpeg { .. } => Ok(method::JPEG),
+        Compression::Jpeg2000 { .. } => Err(ImageryError::unsupported_format(
+            "JPEG2000 tiles are not supported in TIFF-based formats",
+        )),
+    }
+}
+
+/// Decompress one tile or strip
+///
+/// `expected` is the decoded size of a full tile; shorter streams such as the
+/// last strip of an image are zero-padded to that size.
+pub(crate) fn decompress(
+    code: u16,
+    data: &[u8],
+    jpeg_tables: Option<&[u8]>,
+    photometric_interpretation: u16,
+    expected: usize,
+) -> Result<Vec<u8>> {
+    let mut decoded = match code {
+        method::NONE => data.to_vec(),
+        method::DEFLATE | method::DEFLATE_LEGACY => {
+            let mut out = Vec::with_capacity(expected);
+            ZlibDecoder::new(data)
+                .read_to_end(&mut out)
+                .map_err(|e| ImageryError::Compression(format!("DEFLATE tile: {}", e)))?;
+            out
+        }
+        method::LZW => weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .decode(data)
+            .map_err(|e| ImageryError::Compression(format!("LZW tile: {:?}", e)))?,
+        method::ZSTD => zstd::stream::decode_all(data)
+            .map_err(|e| ImageryError::Compression(format!("ZSTD tile: {}", e)))?,
+        method::PACKBITS => unpack_bits(data, expected)?,
+        method::JPEG => decode_jpeg(data, jpeg_tables, photometric_interpretation)?,
+        other => {
+            return Err(ImageryError::UnsupportedFormat(format!(
+                "TIFF compression scheme {}",
+                other
+            )))
+        }
+    };
+
+    decoded.resize(expected, 0);
+    Ok(decoded)
+}
+
+/// Compress one full tile of pixel-interleaved samples
+pub(crate) fn compress(compression: Compression, data: &[u8], width: u32, height: u32, samples: u16) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(data.to_vec()),
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Compression::Lzw => weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .encode(data)
+            .map_err(|e| ImageryError::Compression(format!("LZW tile: {:?}", e))),
+        Compression::Zstd { level } => zstd::bulk::compress(data, level)
+            .map_err(|e| ImageryError::Compression(format!("ZSTD tile: {}", e))),
+        Compression::Jpeg { quality } => {
+            let color = match samples {
+                1 => image::ExtendedColorType::L8,
+                3 => image::ExtendedColorType::Rgb8,
+                n => {
+                    return Err(ImageryError::InvalidParameter(format!(
+                        "JPEG tiles need 1 or 3 bands, got {}",
+                        n
+                    )))
+                }
+            };
+            let mut out = Vec::new();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality.clamp(1, 100))
+                .encode(data, width, height, color)?;
+            Ok(out)
+        }
+        Compression::Jpeg2000 { .. } => Err(ImageryError::unsupported_format(
+            "JPEG2000 tiles are not supported in TIFF-based formats",
+        )),
+    }
+}
+
+/// Decode a JPEG tile, merging the shared tables stream when present
+fn decode_jpeg(data: &[u8], tables: Option<&[u8]>, photometric_interpretation: u16) -> Result<Vec<u8>> {
+    let stream = match tables {
+        // Both streams carry SOI/EOI markers; splice the tables in front of the tile body
+        Some(tables) if tables.len() > 4 && data.len() > 2 => {
+            let mut merged = tables[..tables.len() - 2].to_vec();
+            merged.extend_from_slice(&data[2..]);
+            merged
+        }
+        _ => data.to_vec(),
+    };
+
+    let mut decoder = jpeg_decoder::Decoder::new(stream.as_slice());
+    match photometric_interpretation {
+        photometric::RGB => decoder.set_color_transform(jpeg_decoder::ColorTransform::RGB),
+        photometric::YCBCR => decoder.set_color_transform(jpeg_decoder::ColorTransform::YCbCr),
+        _ => {}
+    }
+    decoder
+        .decode()
+        .map_err(|e| ImageryError::Compression(format!("JPEG tile: {}", e)))
+}
+
+/// Decode a PackBits run-length stream
+fn unpack_bits(data: &[u8], expected: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(expected);
+    let mut i = 0;
+
+    while i < data.len() && out.len() < expected {
+        let header = data[i] as i8;
+        i += 1;
+        match header {
+            0..=127 => {
+                let len = header as usize + 1;
+                let literal = data.get(i..i + len).ok_or_else(|| {
+                    ImageryError::Compression("PackBits literal run past end of tile".to_string())
+                })?;
+                out.extend_from_slice(literal);
+                i += len;
+            }
+            -127..=-1 => {
+                let value = *data.get(i).ok_or_else(|| {
+                    ImageryError::Compression("PackBits repeat run past end of tile".to_string())
+                })?;
+                out.extend(std::iter::repeat_n(value, (1 - header as isize) as usize));
+                i += 1;
+            }
+            -128 => {}
+        }
+    }
+
+    Ok(out)
+}
+
+/// Reverse the predictor applied to a decoded tile in place
+///
+/// `row_samples` is the number of samples in one row of the tile and
+/// `stride` the number of samples per pixel within the chunk.
+pub(crate) fn undo_predictor(
+    buf: &mut [u8],
+    scheme: u16,
+    order: ByteOrder,
+    row_samples: usize,
+    stride: usize,
+    bytes_per_sample: usize,
+) -> Result<()> {
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return Ok(());
+    }
+
+    match scheme {
+        predictor::NONE => Ok(()),
+        predictor::HORIZONTAL => {
+            for row in buf.chunks_exact_mut(row_bytes) {
+                match bytes_per_sample {
+                    1 => {
+                        for i in stride..row.len() {
+                            row[i] = row[i].wrapping_add(row[i - stride]);
+                        }
+                    }
+                    2 | 4 | 8 => {
+                        for i in stride..row_samples {
+                            let prev = read_uint(order, &row[(i - stride) * bytes_per_sample..], bytes_per_sample);
+                            let cur = read_uint(order, &row[i * bytes_per_sample..], bytes_per_sample);
+                            write_uint(order, &mut row[i * bytes_per_sample..], bytes_per_sample, cur.wrapping_add(prev));
+                        }
+                    }
+                    n => {
+                        return Err(ImageryError::UnsupportedFormat(format!(
+                            "Horizontal predictor with {}-byte samples",
+                            n
+                        )))
+                    }
+                }
+            }
+            Ok(())
+        }
+        predictor::FLOATING_POINT => {
+            let mut shuffled = vec![0u8; row_bytes];
+            for row in buf.chunks_exact_mut(row_bytes) {
+                for i in stride..row_bytes {
+                    row[i] = row[i].wrapping_add(row[i - stride]);
+                }
+                shuffled.copy_from_slice(row);
+                // Bytes are stored most significant plane first
+                for i in 0..row_samples {
+                    for k in 0..bytes_per_sample {
+                        let dst = match order {
+                            ByteOrder::Big => k,
+                            ByteOrder::Little => bytes_per_sample - 1 - k,
+                        };
+                        row[i * bytes_per_sample + dst] = shuffled[k * row_samples + i];
+                    }
+                }
+            }
+            Ok(())
+        }
+        other => Err(ImageryError::UnsupportedFormat(format!("TIFF predictor {}", other))),
+    }
+}
+
+/// Apply a predictor to a little-endian tile in place before compression
+pub(crate) fn apply_predictor(buf: &mut [u8], scheme: u16, row_samples: usize, stride: usize, bytes_per_sample: usize) {
+    let row_bytes = row_samples * bytes_per_sample;
+    if row_bytes == 0 {
+        return;
+    }
+
+    match scheme {
+        predictor::HORIZONTAL => {
+            for row in buf.chunks_exact_mut(row_bytes) {
+                for i in (stride..row_samples).rev() {
+                    let prev = read_uint(ByteOrder::Little, &row[(i - stride) * bytes_per_sample..], bytes_per_sample);
+                    let cur = read_uint(ByteOrder::Little, &row[i * bytes_per_sample..], bytes_per_sample);
+                    write_uint(ByteOrder::Little, &mut row[i * bytes_per_sample..], bytes_per_sample, cur.wrapping_sub(prev));
+                }
+            }
+        }
+        predictor::FLOATING_POINT => {
+            let mut shuffled = vec![0u8; row_bytes];
+            for row in buf.chunks_exact_mut(row_bytes) {
+                for i in 0..row_samples {
+                    for k in 0..bytes_per_sample {
+                        shuffled[k * row_samples + i] = row[i * bytes_per_sample + bytes_per_sample - 1 - k];
+                    }
+                }
+                for i in (stride..row_bytes).rev() {
+                    shuffled[i] = shuffled[i].wrapping_sub(shuffled[i - stride]);
+                }
+                row.copy_from_slice(&shuffled);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn read_uint(order: ByteOrder, bytes: &[u8], size: usize) -> u64 {
+    match size {
+        1 => bytes[0] as u64,
+        2 => order.read_u16(bytes) as u64,
+        4 => order.read_u32(bytes) as u64,
+        _ => order.read_u64(bytes),
+    }
+}
+
+fn write_uint(order: ByteOrder, bytes: &mut [u8], size: usize, value: u64) {
+    let le = value.to_le_bytes();
+    for k in 0..size {
+        bytes[k] = match order {
+            ByteOrder::Little => le[k],
+            ByteOrder::Big => le[size - 1 - k],
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_predictor_roundtrip() {
+        let row_samples = 12;
+        let ints: Vec<u8> = (0..row_samples * 2).flat_map(|i| (i as u16 * 300).to_le_bytes()).collect();
+        let floats: Vec<u8> = (0..row_samples * 2).flat_map(|i| (i as f32 * 0.25 - 1.0).to_le_bytes()).collect();
+
+        for (original, scheme, size) in [(&ints, predictor::HORIZONTAL, 2), (&floats, predictor::FLOATING_POINT, 4)] {
+            let mut buf = original.clone();
+            apply_predictor(&mut buf, scheme, row_samples, 3, size);
+            assert_ne!(&buf, original);
+            undo_predictor(&mut buf, scheme, ByteOrder::Little, row_samples, 3, size).unwrap();
+            assert_eq!(&buf, original);
+        }
+    }
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let data: Vec<u8> = (0..64 * 64).map(|i| ((i / 7) % 256) as u8).collect();
+        for compression in [Compression::None, Compression::Deflate, Compression::Lzw, Compression::Zstd { level: 3 }] {
+            let code = compression_code(compression).unwrap();
+            let encoded = compress(compression, &data, 64, 64, 1).unwrap();
+            let decoded = decompress(code, &encoded, None, photometric::MIN_IS_BLACK, data.len()).unwrap();
+            assert_eq!(decoded, data);
+        }
+
+        let packed = [0xFEu8, 7, 0x01, 1, 2];
+        assert_eq!(unpack_bits(&packed, 5).unwrap(), vec![7, 7, 7, 1, 2]);
+    }
+}
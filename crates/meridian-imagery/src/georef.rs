@@ -0,0 +1,47 @@
+//! Georeferencing helpers shared by warping, mosaicking and vector operations
+
+use crate::error::{ImageryError, Result};
+use crate::ImageMetadata;
+use meridian_core::crs::Crs;
+
+/// Parse a CRS as stored in image metadata: an EPSG or OGC identifier, WKT or a PROJ string
+pub(crate) fn parse_crs(definition: &str) -> Result<Crs> {
+    Crs::from_uri(definition)
+        .or_else(|_| Crs::from_wkt(definition))
+        .or_else(|_| Crs::from_proj_string(definition))
+        .map_err(|e| ImageryError::Metadata(format!("Unsupported CRS '{}': {}", definition, e)))
+}
+
+/// CRS identifier to store in image metadata
+pub(crate) fn crs_label(crs: &Crs) -> String {
+    crs.epsg
+        .map(|code| format!("EPSG:{}", code))
+        .unwrap_or_else(|| crs.proj_string.clone())
+}
+
+/// CRS and geotransform of an image, failing if either is missing
+pub(crate) fn source_reference(metadata: &ImageMetadata) -> Result<(Crs, [f64; 6])> {
+    let gt = metadata
+        .geo_transform
+        .ok_or_else(|| ImageryError::metadata("Image has no geotransform"))?;
+    let crs = metadata
+        .crs
+        .as_deref()
+        .ok_or_else(|| ImageryError::metadata("Image has no CRS"))?;
+    Ok((parse_crs(crs)?, gt))
+}
+
+/// Map a pixel position to georeferenced coordinates
+pub(crate) fn apply_geo_transform(gt: &[f64; 6], px: f64, py: f64) -> (f64, f64) {
+    (gt[0] + px * gt[1] + py * gt[2], gt[3] + px * gt[4] + py * gt[5])
+}
+
+/// Geotransform mapping coordinates back to pixel positions, if the grid is not degenerate
+pub(crate) fn invert_geo_transform(gt: &[f64; 6]) -> Option<[f64; 6]> {
+    let det = gt[1] * gt[5] - gt[2] * gt[4];
+    if det.abs() < f64::EPSILON * (gt[1].abs() + gt[5].abs()).max(f64::MIN_POSITIVE) {
+        return None;
+    }
+    let (a, b, d, e) = (gt[5] / det, -gt[2] / det, -gt[4] / det, gt[1] / det);
+    Some([-(a * gt[0] + b * gt[3]), a, b, -(d * gt[0] + e * gt[3]), d, e])
+}
@@ -4,6 +4,7 @@
 
 use crate::error::{ImageryError, Result};
 use crate::MultiBandImage;
+use super::expression::RasterExpression;
 use super::IndexResult;
 
 /// Custom band math calculator
@@ -52,6 +53,20 @@ impl BandMath {
         })
     }
 
+    /// Evaluate a band math expression such as `(B8 - B4) / (B8 + B4)`
+    ///
+    /// See [`RasterExpression`] for the syntax. Nodata pixels are NaN.
+    pub fn evaluate(image: &MultiBandImage, expression: &str) -> Result<IndexResult> {
+        let expression = RasterExpression::parse(expression)?;
+        let mut result = expression.evaluate_image(&[("image", image)])?;
+        Ok(IndexResult {
+            values: result.bands.remove(0),
+            width: image.metadata.width,
+            height: image.metadata.height,
+            name: expression.name().to_string(),
+        })
+    }
+
     /// Add two bands
     pub fn add(
         image: &MultiBandImage,
@@ -0,0 +1,254 @@
+//! Vectorized evaluation of bound raster expressions
+
+use super::parser::{BinaryOp, Expr, FocalOp, Function, UnaryOp};
+
+/// Expression tree with band references replaced by input slots
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Constant(f64),
+    Slot(usize),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+    Focal(FocalOp, u32, Box<Node>),
+}
+
+impl Node {
+    /// Lower a checked expression, resolving band references with `slot`
+    pub fn compile(expr: &Expr, slot: &mut impl FnMut(Option<&str>, &str) -> usize) -> Self {
+        match expr {
+            Expr::Number(value) => Node::Constant(*value),
+            Expr::Bool(value) => Node::Constant(if *value { 1.0 } else { 0.0 }),
+            Expr::NoData => Node::Constant(f64::NAN),
+            Expr::Band { input, band, .. } => Node::Slot(slot(input.as_deref(), band)),
+            Expr::Unary { op, operand, .. } => Node::Unary(*op, Box::new(Self::compile(operand, slot))),
+            Expr::Binary { op, left, right, .. } => Node::Binary(
+                *op,
+                Box::new(Self::compile(left, slot)),
+                Box::new(Self::compile(right, slot)),
+            ),
+            Expr::Call {
+                function: Function::Focal(op),
+                args,
+                ..
+            } => Node::Focal(
+                *op,
+                super::parser::focal_radius(&args[1]).unwrap_or(0),
+                Box::new(Self::compile(&args[0], slot)),
+            ),
+            Expr::Call { function, args, .. } => {
+                Node::Call(*function, args.iter().map(|arg| Self::compile(arg, slot)).collect())
+            }
+        }
+    }
+}
+
+/// Input samples of one block, with NaN marking nodata
+pub(crate) struct Block {
+    pub width: usize,
+    pub height: usize,
+    pub slots: Vec<Vec<f64>>,
+}
+
+impl Block {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// Evaluate a node over every pixel of a block
+///
+/// Booleans are 1 and 0. Nodata is NaN and propagates through every
+/// operation except `isnodata`, `coalesce`, the branch `if` does not take,
+/// and the neighbourhoods of focal functions.
+pub(crate) fn evaluate(node: &Node, block: &Block) -> Vec<f64> {
+    match node {
+        Node::Constant(value) => vec![*value; block.len()],
+        Node::Slot(slot) => block.slots[*slot].clone(),
+        Node::Unary(op, operand) => {
+            let mut values = evaluate(operand, block);
+            for value in &mut values {
+                *value = match op {
+                    UnaryOp::Neg => -*value,
+                    UnaryOp::Not => truth(*value, |v| !v),
+                };
+            }
+            values
+        }
+        Node::Binary(op, left, right) => {
+            let mut values = evaluate(left, block);
+            let right = evaluate(right, block);
+            for (a, &b) in values.iter_mut().zip(&right) {
+                *a = binary(*op, *a, b);
+            }
+            values
+        }
+        Node::Call(function, args) => call(*function, args, block),
+        Node::Focal(op, radius, operand) => {
+            let values = evaluate(operand, block);
+            focal(*op, *radius as usize, &values, block.width, block.height)
+        }
+    }
+}
+
+fn truth(value: f64, f: impl Fn(bool) -> bool) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else if f(value != 0.0) {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn binary(op: BinaryOp, a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        return f64::NAN;
+    }
+    let flag = |condition: bool| if condition { 1.0 } else { 0.0 };
+    match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div if b == 0.0 => f64::NAN,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem if b == 0.0 => f64::NAN,
+        BinaryOp::Rem => a % b,
+        BinaryOp::Pow => a.powf(b),
+        BinaryOp::Lt => flag(a < b),
+        BinaryOp::Le => flag(a <= b),
+        BinaryOp::Gt => flag(a > b),
+        BinaryOp::Ge => flag(a >= b),
+        BinaryOp::Eq => flag(a == b),
+        BinaryOp::Ne => flag(a != b),
+        BinaryOp::And => flag(a != 0.0 && b != 0.0),
+        BinaryOp::Or => flag(a != 0.0 || b != 0.0),
+    }
+}
+
+fn call(function: Function, args: &[Node], block: &Block) -> Vec<f64> {
+    let unary = |f: fn(f64) -> f64| {
+        let mut values = evaluate(&args[0], block);
+        values.iter_mut().for_each(|v| *v = f(*v));
+        values
+    };
+
+    match function {
+        Function::Abs => unary(f64::abs),
+        Function::Sqrt => unary(f64::sqrt),
+        Function::Exp => unary(f64::exp),
+        Function::Ln => unary(f64::ln),
+        Function::Log10 => unary(f64::log10),
+        Function::Floor => unary(f64::floor),
+        Function::Ceil => unary(f64::ceil),
+        Function::Round => unary(f64::round),
+        Function::Sin => unary(f64::sin),
+        Function::Cos => unary(f64::cos),
+        Function::Tan => unary(f64::tan),
+        Function::Pow => {
+            let mut values = evaluate(&args[0], block);
+            let exponents = evaluate(&args[1], block);
+            for (v, &e) in values.iter_mut().zip(&exponents) {
+                *v = v.powf(e);
+            }
+            values
+        }
+        Function::Min | Function::Max => {
+            let mut values = evaluate(&args[0], block);
+            for arg in &args[1..] {
+                for (v, other) in values.iter_mut().zip(evaluate(arg, block)) {
+                    *v = if v.is_nan() || other.is_nan() {
+                        f64::NAN
+                    } else if function == Function::Min {
+                        v.min(other)
+                    } else {
+                        v.max(other)
+                    };
+                }
+            }
+            values
+        }
+        Function::Clamp => {
+            let mut values = evaluate(&args[0], block);
+            let low = evaluate(&args[1], block);
+            let high = evaluate(&args[2], block);
+            for ((v, &lo), &hi) in values.iter_mut().zip(&low).zip(&high) {
+                *v = if v.is_nan() || lo.is_nan() || hi.is_nan() {
+                    f64::NAN
+                } else {
+                    v.max(lo).min(hi)
+                };
+            }
+            values
+        }
+        Function::If => {
+            let mut values = evaluate(&args[0], block);
+            let then = evaluate(&args[1], block);
+            let otherwise = evaluate(&args[2], block);
+            for (index, v) in values.iter_mut().enumerate() {
+                if !v.is_nan() {
+                    *v = if *v != 0.0 { then[index] } else { otherwise[index] };
+                }
+            }
+            values
+        }
+        Function::IsNoData => unary(|v| if v.is_nan() { 1.0 } else { 0.0 }),
+        Function::Coalesce => {
+            let mut values = evaluate(&args[0], block);
+            for arg in &args[1..] {
+                if !values.iter().any(|v| v.is_nan()) {
+                    break;
+                }
+                for (v, other) in values.iter_mut().zip(evaluate(arg, block)) {
+                    if v.is_nan() {
+                        *v = other;
+                    }
+                }
+            }
+            values
+        }
+        Function::Focal(op) => unreachable!("focal {:?} is compiled to its own node", op),
+    }
+}
+
+/// Reduce the valid values in a square window around every pixel
+///
+/// The window is clipped to the block; pixels with no valid neighbours
+/// become nodata.
+fn focal(op: FocalOp, radius: usize, values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let mut output = vec![f64::NAN; values.len()];
+    for y in 0..height {
+        let rows = y.saturating_sub(radius)..(y + radius + 1).min(height);
+        for x in 0..width {
+            let columns = x.saturating_sub(radius)..(x + radius + 1).min(width);
+
+            let (mut count, mut sum, mut sum_sq) = (0usize, 0.0, 0.0);
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            for row in rows.clone() {
+                for &value in &values[row * width + columns.start..row * width + columns.end] {
+                    if value.is_nan() {
+                        continue;
+                    }
+                    count += 1;
+                    sum += value;
+                    sum_sq += value * value;
+                    min = min.min(value);
+                    max = max.max(value);
+                }
+            }
+            if count == 0 {
+                continue;
+            }
+
+            let mean = sum / count as f64;
+            output[y * width + x] = match op {
+                FocalOp::Mean => mean,
+                FocalOp::Sum => sum,
+                FocalOp::Min => min,
+                FocalOp::Max => max,
+                FocalOp::Std => (sum_sq / count as f64 - mean * mean).max(0.0).sqrt(),
+            };
+        }
+    }
+    output
+}
@@ -0,0 +1,125 @@
+//! Band math over raster files
+
+use super::RasterExpression;
+use crate::dataset::RasterDataset;
+use crate::error::{ImageryError, Result};
+use crate::format::{CogWriter, ImageWriter};
+use crate::ImageMetadata;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A raster file and the name expressions refer to it by
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpressionInput {
+    /// Name used to qualify band references, as in `name.B4`
+    pub name: String,
+    /// Path of a Cloud Optimized GeoTIFF
+    pub path: PathBuf,
+}
+
+/// Parses `name=path`, or a bare path named after its file stem
+impl FromStr for ExpressionInput {
+    type Err = ImageryError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some((name, path)) = s.split_once('=') {
+            return Ok(Self {
+                name: name.trim().to_string(),
+                path: PathBuf::from(path.trim()),
+            });
+        }
+
+        let path = PathBuf::from(s);
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| ImageryError::InvalidParameter(format!("Cannot name input '{}'", s)))?
+            .to_string();
+        Ok(Self { name, path })
+    }
+}
+
+/// An expression evaluated over raster files and written to a COG
+///
+/// This is the shape of band math steps in workflow and pipeline
+/// definitions, e.g.
+/// `{ "expression": "(B8 - B4) / (B8 + B4)", "inputs": [{ "name": "s2", "path": "scene.tif" }], "output": "ndvi.tif" }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandMathJob {
+    /// Expression to evaluate, optionally named
+    pub expression: RasterExpression,
+    /// Inputs on a common grid; the first one is the default for unqualified bands
+    pub inputs: Vec<ExpressionInput>,
+    /// Output file
+    pub output: PathBuf,
+}
+
+impl BandMathJob {
+    /// Evaluate the expression and write the result, returning its metadata
+    pub fn run(&self) -> Result<ImageMetadata> {
+        if self.inputs.is_empty() {
+            return Err(ImageryError::InvalidParameter("Band math needs at least one input".to_string()));
+        }
+
+        let datasets = self
+            .inputs
+            .iter()
+            .map(|input| RasterDataset::open_cog(&input.path))
+            .collect::<Result<Vec<_>>>()?;
+        let inputs: Vec<(&str, &RasterDataset)> = self
+            .inputs
+            .iter()
+            .zip(&datasets)
+            .map(|(input, dataset)| (input.name.as_str(), dataset))
+            .collect();
+
+        log::info!("Evaluating '{}' into {}", self.expression.source(), self.output.display());
+        let result = self.expression.evaluate(&inputs)?.to_image()?;
+        CogWriter::new(&self.output)?.write(&result)?;
+        Ok(result.metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, MultiBandImage};
+
+    #[test]
+    fn test_job_from_definition() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = ImageMetadata {
+            width: 40,
+            height: 30,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: Some([500_000.0, 10.0, 0.0, 4_000_300.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: Some(0.0),
+            band_names: vec![],
+        };
+        let mut scene = MultiBandImage::new(metadata, DataType::UInt16);
+        scene.bands[0].fill(100.0);
+        scene.bands[1].fill(300.0);
+        scene.bands[0][7] = 0.0;
+        let scene_path = dir.path().join("scene.tif");
+        CogWriter::new(&scene_path).unwrap().write(&scene).unwrap();
+
+        let input: ExpressionInput = scene_path.to_str().unwrap().parse().unwrap();
+        assert_eq!(input.name, "scene");
+        let job: BandMathJob = serde_json::from_value(serde_json::json!({
+            "expression": { "expression": "(scene.B2 - B1) / (B2 + B1)", "name": "NDVI" },
+            "inputs": [input],
+            "output": dir.path().join("ndvi.tif"),
+        }))
+        .unwrap();
+        let metadata = job.run().unwrap();
+        assert_eq!((metadata.width, metadata.height), (40, 30));
+
+        let ndvi = RasterDataset::open_cog(&job.output).unwrap().to_image().unwrap();
+        assert!((ndvi.bands[0][0] - 0.5).abs() < 1e-6);
+        assert!(ndvi.bands[0][7].is_nan());
+        assert_eq!(ndvi.metadata.geo_transform, scene.metadata.geo_transform);
+    }
+}
@@ -0,0 +1,120 @@
+//! Tokenizer for raster expressions
+
+use crate::error::{ImageryError, Result};
+
+/// Kind of a lexical token
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    EqEq,
+    Ne,
+    AndAnd,
+    OrOr,
+    Bang,
+    Eof,
+}
+
+/// A token and the 1-based column it starts at
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub column: usize,
+}
+
+/// Split an expression into tokens, ending with `Eof`
+pub(crate) fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| ImageryError::expression(column, format!("Invalid number '{}'", text)))?;
+            tokens.push(Token { kind: TokenKind::Number(value), column });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name = chars[start..i].iter().collect();
+            tokens.push(Token { kind: TokenKind::Ident(name), column });
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (kind, width) = match (c, next) {
+            ('<', Some('=')) => (TokenKind::Le, 2),
+            ('>', Some('=')) => (TokenKind::Ge, 2),
+            ('=', Some('=')) => (TokenKind::EqEq, 2),
+            ('!', Some('=')) => (TokenKind::Ne, 2),
+            ('&', Some('&')) => (TokenKind::AndAnd, 2),
+            ('|', Some('|')) => (TokenKind::OrOr, 2),
+            ('+', _) => (TokenKind::Plus, 1),
+            ('-', _) => (TokenKind::Minus, 1),
+            ('*', _) => (TokenKind::Star, 1),
+            ('/', _) => (TokenKind::Slash, 1),
+            ('%', _) => (TokenKind::Percent, 1),
+            ('^', _) => (TokenKind::Caret, 1),
+            ('(', _) => (TokenKind::LParen, 1),
+            (')', _) => (TokenKind::RParen, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            ('.', _) => (TokenKind::Dot, 1),
+            ('<', _) => (TokenKind::Lt, 1),
+            ('>', _) => (TokenKind::Gt, 1),
+            ('!', _) => (TokenKind::Bang, 1),
+            _ => return Err(ImageryError::expression(column, format!("Unexpected character '{}'", c))),
+        };
+        tokens.push(Token { kind, column });
+        i += width;
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        column: chars.len() + 1,
+    });
+    Ok(tokens)
+}
@@ -0,0 +1,440 @@
+//! Raster algebra expressions
+//!
+//! A small language for band math defined at runtime, such as
+//! `(B8 - B4) / (B8 + B4)`. Expressions are parsed and type-checked once,
+//! bound to one or more named rasters, then evaluated block by block in
+//! parallel.
+//!
+//! - Bands are referenced by name (`nir`) or 1-based position (`B8`).
+//!   With several inputs, `name.band` picks a band of a specific input and
+//!   unqualified bands refer to the first input. An input with a single
+//!   band can be referenced by its name alone.
+//! - Arithmetic `+ - * / % ^`, comparisons `< <= > >= == !=` and logic
+//!   `&& || !` (or `and`, `or`, `not`).
+//! - Functions: `abs`, `sqrt`, `exp`, `ln`, `log10`, `floor`, `ceil`,
+//!   `round`, `sin`, `cos`, `tan`, `pow`, `min`, `max`, `clamp`,
+//!   `if(cond, then, else)`, `isnodata` and `coalesce`.
+//! - Focal functions `focal_mean`, `focal_sum`, `focal_min`, `focal_max`
+//!   and `focal_std` reduce the valid pixels of a square window whose
+//!   radius is given as an integer literal, e.g. `focal_mean(B1, 2)`.
+//! - `nodata` is a literal for a missing value. Nodata inputs propagate
+//!   through every operation except `isnodata`, `coalesce`, the branch `if`
+//!   does not take and focal windows. Division by zero and other
+//!   non-finite results are nodata as well.
+
+mod eval;
+mod job;
+mod lexer;
+mod parser;
+#[cfg(feature = "workflow")]
+mod workflow;
+
+pub use job::{BandMathJob, ExpressionInput};
+#[cfg(feature = "workflow")]
+pub use workflow::BandMathTask;
+
+use self::eval::{Block, Node};
+use self::parser::Expr;
+use crate::dataset::RasterDataset;
+use crate::error::{ImageryError, Result};
+use crate::processing::ProcessingStep;
+use crate::streaming::Window;
+use crate::{DataType, ImageMetadata, MultiBandImage};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Block size used when wrapping in-memory images
+const IMAGE_BLOCK_SIZE: u32 = 256;
+
+/// Type of an expression or operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// Pixel values and numeric literals
+    Number,
+    /// Results of comparisons and logic, stored as 1 and 0
+    Boolean,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::Number => write!(f, "number"),
+            ValueType::Boolean => write!(f, "boolean"),
+        }
+    }
+}
+
+/// A parsed and type-checked raster expression
+///
+/// Serializes as its source text, or as `{ "expression", "name" }` when
+/// named, so it can be embedded directly in pipeline and workflow
+/// definitions and is validated when they are loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "ExpressionDefinition", into = "ExpressionDefinition")]
+pub struct RasterExpression {
+    source: String,
+    name: Option<String>,
+    expr: Expr,
+    value_type: ValueType,
+    halo: u32,
+}
+
+/// Serialized forms of an expression
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ExpressionDefinition {
+    Source(String),
+    Named {
+        expression: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+impl TryFrom<ExpressionDefinition> for RasterExpression {
+    type Error = ImageryError;
+
+    fn try_from(definition: ExpressionDefinition) -> Result<Self> {
+        match definition {
+            ExpressionDefinition::Source(source) => Self::parse(&source),
+            ExpressionDefinition::Named { expression, name } => {
+                let parsed = Self::parse(&expression)?;
+                Ok(match name {
+                    Some(name) => parsed.with_name(name),
+                    None => parsed,
+                })
+            }
+        }
+    }
+}
+
+impl From<RasterExpression> for ExpressionDefinition {
+    fn from(expression: RasterExpression) -> Self {
+        match expression.name {
+            Some(name) => ExpressionDefinition::Named {
+                expression: expression.source,
+                name: Some(name),
+            },
+            None => ExpressionDefinition::Source(expression.source),
+        }
+    }
+}
+
+/// Expression lowered against concrete inputs
+struct Binding {
+    node: Node,
+    /// (input, band) read into each slot
+    slots: Vec<(usize, usize)>,
+}
+
+impl RasterExpression {
+    /// Parse and type-check an expression
+    pub fn parse(source: &str) -> Result<Self> {
+        let expr = parser::parse(source)?;
+        let value_type = expr.check()?;
+        let halo = expr.halo();
+        Ok(Self {
+            source: source.to_string(),
+            name: None,
+            expr,
+            value_type,
+            halo,
+        })
+    }
+
+    /// Name the output band, which defaults to the expression source
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Expression source text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Name of the output band
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.source)
+    }
+
+    /// Type of the result; booleans are written as 1 and 0
+    pub fn value_type(&self) -> ValueType {
+        self.value_type
+    }
+
+    /// Pixels of context each output pixel depends on, in every direction
+    pub fn halo(&self) -> u32 {
+        self.halo
+    }
+
+    /// Evaluate over named datasets sharing one grid
+    ///
+    /// The result is a single `Float32` band with NaN as nodata, computed
+    /// block by block in parallel on the first input's block grid. Pixels
+    /// that are nodata, NaN or masked in an input are nodata in that input.
+    pub fn evaluate(&self, inputs: &[(&str, &RasterDataset)]) -> Result<RasterDataset> {
+        let metadata: Vec<(&str, &ImageMetadata)> = inputs.iter().map(|&(name, dataset)| (name, dataset.metadata())).collect();
+        let binding = self.bind(&metadata)?;
+
+        let primary = inputs[0].1;
+        let (width, height) = primary.dimensions();
+        let layout = primary
+            .layout()
+            .derive(1, DataType::Float32)
+            .with_no_data(f64::NAN)
+            .with_band_names(vec![self.name().to_string()]);
+
+        log::debug!("Evaluating '{}' over {} inputs with a halo of {}", self.source, inputs.len(), self.halo);
+        primary.map_blocks(layout, |block, window| {
+            let (x0, y0) = (window.x.saturating_sub(self.halo), window.y.saturating_sub(self.halo));
+            let padded = Window::new(
+                x0,
+                y0,
+                (window.x + window.width).saturating_add(self.halo).min(width) - x0,
+                (window.y + window.height).saturating_add(self.halo).min(height) - y0,
+            );
+
+            let slots = binding
+                .slots
+                .iter()
+                .map(|&(input, band)| read_slot(inputs[input].1, band, &padded))
+                .collect::<Result<Vec<_>>>()?;
+            let values = eval::evaluate(
+                &binding.node,
+                &Block {
+                    width: padded.width as usize,
+                    height: padded.height as usize,
+                    slots,
+                },
+            );
+
+            let mut metadata = block.metadata.clone();
+            metadata.bands = 1;
+            metadata.bits_per_sample = 32;
+            metadata.no_data = Some(f64::NAN);
+            metadata.band_names = vec![self.name().to_string()];
+            let mut output = MultiBandImage::new(metadata, DataType::Float32);
+
+            let (offset_x, offset_y) = ((window.x - padded.x) as usize, (window.y - padded.y) as usize);
+            for row in 0..window.height as usize {
+                let start = (row + offset_y) * padded.width as usize + offset_x;
+                let source = &values[start..start + window.width as usize];
+                let target = &mut output.bands[0][row * window.width as usize..(row + 1) * window.width as usize];
+                for (out, &value) in target.iter_mut().zip(source) {
+                    *out = if value.is_finite() { value as f32 } else { f32::NAN };
+                }
+            }
+            Ok(output)
+        })
+    }
+
+    /// Evaluate over named in-memory images sharing one grid
+    pub fn evaluate_image(&self, inputs: &[(&str, &MultiBandImage)]) -> Result<MultiBandImage> {
+        let datasets = inputs
+            .iter()
+            .map(|&(name, image)| Ok((name, RasterDataset::from_image(image, IMAGE_BLOCK_SIZE, IMAGE_BLOCK_SIZE)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let inputs: Vec<(&str, &RasterDataset)> = datasets.iter().map(|(name, dataset)| (*name, dataset)).collect();
+        self.evaluate(&inputs)?.to_image()
+    }
+
+    /// Resolve band references against the inputs and check they line up
+    fn bind(&self, inputs: &[(&str, &ImageMetadata)]) -> Result<Binding> {
+        let Some(&(_, primary)) = inputs.first() else {
+            return Err(ImageryError::InvalidParameter("Expression needs at least one input".to_string()));
+        };
+        for (index, &(name, metadata)) in inputs.iter().enumerate() {
+            if inputs[..index].iter().any(|&(other, _)| other == name) {
+                return Err(ImageryError::InvalidParameter(format!("Duplicate input name '{}'", name)));
+            }
+            if (metadata.width, metadata.height) != (primary.width, primary.height) {
+                return Err(ImageryError::InvalidDimensions(format!(
+                    "Input '{}' is {}x{} but '{}' is {}x{}; warp inputs onto a common grid first",
+                    name, metadata.width, metadata.height, inputs[0].0, primary.width, primary.height
+                )));
+            }
+            if let (Some(a), Some(b)) = (metadata.geo_transform, primary.geo_transform) {
+                let tolerance = b[1].abs().max(b[5].abs()) * 1e-6;
+                if a.iter().zip(&b).any(|(x, y)| (x - y).abs() > tolerance) {
+                    return Err(ImageryError::InvalidDimensions(format!(
+                        "Input '{}' is not aligned with '{}'; warp inputs onto a common grid first",
+                        name, inputs[0].0
+                    )));
+                }
+            }
+        }
+
+        let mut lookup: HashMap<(Option<&str>, &str), usize> = HashMap::new();
+        let mut slots = Vec::new();
+        self.expr.for_each_band(&mut |input, band, column| {
+            if let Entry::Vacant(entry) = lookup.entry((input, band)) {
+                entry.insert(slots.len());
+                slots.push(resolve_band(inputs, input, band, column)?);
+            }
+            Ok(())
+        })?;
+
+        let node = Node::compile(&self.expr, &mut |input, band| lookup[&(input, band)]);
+        Ok(Binding { node, slots })
+    }
+}
+
+/// Find the (input, band) a reference points to
+fn resolve_band(inputs: &[(&str, &ImageMetadata)], input: Option<&str>, band: &str, column: usize) -> Result<(usize, usize)> {
+    let index = match input {
+        Some(name) => inputs
+            .iter()
+            .position(|&(other, _)| other == name)
+            .ok_or_else(|| ImageryError::expression(column, format!("Unknown input '{}'", name)))?,
+        None => {
+            if let Some(index) = inputs.iter().position(|&(name, metadata)| name == band && metadata.bands == 1) {
+                return Ok((index, 0));
+            }
+            0
+        }
+    };
+
+    let (name, metadata) = inputs[index];
+    let bands = metadata.bands as usize;
+    metadata
+        .band_names
+        .iter()
+        .position(|other| other == band)
+        .or_else(|| {
+            band.strip_prefix(['B', 'b'])
+                .and_then(|number| number.parse::<usize>().ok())
+                .filter(|number| (1..=bands).contains(number))
+                .map(|number| number - 1)
+        })
+        .map(|band| (index, band))
+        .ok_or_else(|| {
+            ImageryError::expression(
+                column,
+                format!("Input '{}' has no band '{}' (bands B1 to B{} or {:?})", name, band, bands, metadata.band_names),
+            )
+        })
+}
+
+/// Samples of one band with nodata, NaN and masked pixels set to NaN
+fn read_slot(dataset: &RasterDataset, band: usize, window: &Window) -> Result<Vec<f64>> {
+    let samples = dataset.read_band(band, window)?;
+    let valid = dataset.band_mask(band, window)?;
+    Ok(valid
+        .iter()
+        .enumerate()
+        .map(|(index, &valid)| if valid { samples.get(index).unwrap_or(f64::NAN) } else { f64::NAN })
+        .collect())
+}
+
+/// Appends the result as a new band, named after the expression
+///
+/// Nodata pixels take the image's nodata value when it has one. Integer
+/// images are promoted to `Float32` so fractional results survive.
+impl ProcessingStep for RasterExpression {
+    fn process(&self, image: &mut MultiBandImage) -> Result<()> {
+        let mut result = self.evaluate_image(&[("image", &*image)])?;
+        let mut band = result.bands.remove(0);
+        if let Some(no_data) = image.metadata.no_data {
+            band.iter_mut().filter(|v| v.is_nan()).for_each(|v| *v = no_data as f32);
+        }
+
+        if !matches!(image.data_type, DataType::Float32 | DataType::Float64) {
+            image.data_type = DataType::Float32;
+            image.metadata.bits_per_sample = 32;
+        }
+        if image.metadata.band_names.len() == image.bands.len() {
+            image.metadata.band_names.push(RasterExpression::name(self).to_string());
+        }
+        image.bands.push(band);
+        image.metadata.bands += 1;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        RasterExpression::name(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processing::ProcessingPipeline;
+
+    fn image(width: u32, height: u32, bands: Vec<Vec<f32>>, names: &[&str]) -> MultiBandImage {
+        let metadata = ImageMetadata {
+            width,
+            height,
+            bands: bands.len() as u32,
+            bits_per_sample: 16,
+            geo_transform: Some([0.0, 10.0, 0.0, 100.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: Some(0.0),
+            band_names: names.iter().map(|name| name.to_string()).collect(),
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        image.bands = bands;
+        image
+    }
+
+    #[test]
+    fn test_multi_raster_conditional_with_nodata() {
+        let red = vec![100.0, 200.0, 0.0, 50.0];
+        let nir = vec![300.0, 200.0, 400.0, 50.0];
+        let scene = image(2, 2, vec![red, nir], &["red", "nir"]);
+        let cloud = image(2, 2, vec![vec![1.0, 2.0, 1.0, 1.0]], &[]);
+
+        let expression = RasterExpression::parse("if(cloud.B1 > 1, nodata, (nir - B1) / (B2 + red))")
+            .unwrap()
+            .with_name("NDVI");
+        let result = expression.evaluate_image(&[("s2", &scene), ("cloud", &cloud)]).unwrap();
+
+        let ndvi = &result.bands[0];
+        assert!((ndvi[0] - 0.5).abs() < 1e-6);
+        assert!(ndvi[1].is_nan() && ndvi[2].is_nan());
+        assert_eq!(ndvi[3], 0.0);
+        assert_eq!(result.metadata.band_names, vec!["NDVI".to_string()]);
+        assert_eq!(result.metadata.geo_transform, scene.metadata.geo_transform);
+
+        let error = RasterExpression::parse("nir - s2.B3")
+            .unwrap()
+            .evaluate_image(&[("s2", &scene)])
+            .unwrap_err();
+        assert!(matches!(error, ImageryError::Expression { column: 7, .. }), "{}", error);
+
+        // Definitions deserialize straight into a pipeline step
+        let step: RasterExpression =
+            serde_json::from_value(serde_json::json!({ "expression": "coalesce(red, 1) * 2", "name": "double" })).unwrap();
+        let mut pipeline = ProcessingPipeline::new();
+        pipeline.add_step(Box::new(step));
+        let mut scene = scene;
+        pipeline.execute(&mut scene).unwrap();
+        assert_eq!(scene.bands[2], vec![200.0, 400.0, 2.0, 100.0]);
+        assert_eq!(scene.metadata.band_names[2], "double");
+        assert!(serde_json::from_str::<RasterExpression>("\"red +\"").is_err());
+    }
+
+    #[test]
+    fn test_focal_halo_crosses_blocks() {
+        let (width, height) = (9u32, 7u32);
+        let values: Vec<f32> = (0..width * height).map(|i| (i % 5 + 1) as f32).collect();
+        let mut source = image(width, height, vec![values.clone()], &["v"]);
+        source.bands[0][20] = 0.0;
+        let dataset = RasterDataset::from_image(&source, 4, 3).unwrap();
+
+        let expression = RasterExpression::parse("focal_max(focal_mean(v, 1), 1) - v").unwrap();
+        assert_eq!(expression.halo(), 2);
+        let result = expression.evaluate(&[("a", &dataset)]).unwrap().to_image().unwrap();
+
+        // Reference evaluation over the whole raster as a single block
+        let whole = RasterDataset::from_image(&source, width, height).unwrap();
+        let expected = expression.evaluate(&[("a", &whole)]).unwrap().to_image().unwrap();
+        for (index, (&got, &want)) in result.bands[0].iter().zip(&expected.bands[0]).enumerate() {
+            assert!(got == want || (got.is_nan() && want.is_nan()), "pixel {}: {} != {}", index, got, want);
+        }
+        assert!(result.bands[0][20].is_nan());
+        assert!(!result.bands[0][21].is_nan());
+    }
+}
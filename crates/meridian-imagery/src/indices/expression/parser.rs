@@ -0,0 +1,572 @@
+//! Parsing and type checking of raster expressions
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::ValueType;
+use crate::error::{ImageryError, Result};
+
+/// Deepest nesting of parentheses, calls and operators the parser accepts
+const MAX_DEPTH: usize = 64;
+
+/// Largest focal window radius, in pixels
+const MAX_FOCAL_RADIUS: u32 = 1024;
+
+/// Unary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+    Neg,
+    Not,
+}
+
+/// Binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+/// Neighbourhood reductions over a square window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FocalOp {
+    Mean,
+    Sum,
+    Min,
+    Max,
+    Std,
+}
+
+/// Built-in functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Function {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log10,
+    Floor,
+    Ceil,
+    Round,
+    Sin,
+    Cos,
+    Tan,
+    Pow,
+    Min,
+    Max,
+    Clamp,
+    If,
+    IsNoData,
+    Coalesce,
+    Focal(FocalOp),
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Self::Abs,
+            "sqrt" => Self::Sqrt,
+            "exp" => Self::Exp,
+            "ln" | "log" => Self::Ln,
+            "log10" => Self::Log10,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "round" => Self::Round,
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "pow" => Self::Pow,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "clamp" => Self::Clamp,
+            "if" => Self::If,
+            "isnodata" => Self::IsNoData,
+            "coalesce" => Self::Coalesce,
+            "focal_mean" => Self::Focal(FocalOp::Mean),
+            "focal_sum" => Self::Focal(FocalOp::Sum),
+            "focal_min" => Self::Focal(FocalOp::Min),
+            "focal_max" => Self::Focal(FocalOp::Max),
+            "focal_std" => Self::Focal(FocalOp::Std),
+            _ => return None,
+        })
+    }
+
+    /// Accepted number of arguments as (min, max)
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Self::Pow | Self::Focal(_) => (2, 2),
+            Self::Clamp | Self::If => (3, 3),
+            Self::Min | Self::Max | Self::Coalesce => (1, usize::MAX),
+            _ => (1, 1),
+        }
+    }
+}
+
+/// Expression tree
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Number(f64),
+    Bool(bool),
+    NoData,
+    Band {
+        input: Option<String>,
+        band: String,
+        column: usize,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+        column: usize,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        column: usize,
+    },
+    Call {
+        function: Function,
+        args: Vec<Expr>,
+        column: usize,
+    },
+}
+
+impl Expr {
+    /// Pixels of context a block needs on each side for the focal functions
+    pub fn halo(&self) -> u32 {
+        match self {
+            Expr::Number(_) | Expr::Bool(_) | Expr::NoData | Expr::Band { .. } => 0,
+            Expr::Unary { operand, .. } => operand.halo(),
+            Expr::Binary { left, right, .. } => left.halo().max(right.halo()),
+            Expr::Call {
+                function: Function::Focal(_),
+                args,
+                ..
+            } => focal_radius(&args[1]).unwrap_or(0).saturating_add(args[0].halo()),
+            Expr::Call { args, .. } => args.iter().map(Expr::halo).max().unwrap_or(0),
+        }
+    }
+
+    /// Check operand types, returning the type of the expression
+    pub fn check(&self) -> Result<ValueType> {
+        match self {
+            Expr::Number(_) | Expr::NoData | Expr::Band { .. } => Ok(ValueType::Number),
+            Expr::Bool(_) => Ok(ValueType::Boolean),
+            Expr::Unary { op, operand, column } => {
+                let expected = match op {
+                    UnaryOp::Neg => ValueType::Number,
+                    UnaryOp::Not => ValueType::Boolean,
+                };
+                expect(operand, expected, *column)?;
+                Ok(expected)
+            }
+            Expr::Binary { op, left, right, column } => match op {
+                BinaryOp::And | BinaryOp::Or => {
+                    expect(left, ValueType::Boolean, *column)?;
+                    expect(right, ValueType::Boolean, *column)?;
+                    Ok(ValueType::Boolean)
+                }
+                BinaryOp::Eq | BinaryOp::Ne => {
+                    let left_type = left.check()?;
+                    expect(right, left_type, *column)?;
+                    Ok(ValueType::Boolean)
+                }
+                BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                    expect(left, ValueType::Number, *column)?;
+                    expect(right, ValueType::Number, *column)?;
+                    Ok(ValueType::Boolean)
+                }
+                _ => {
+                    expect(left, ValueType::Number, *column)?;
+                    expect(right, ValueType::Number, *column)?;
+                    Ok(ValueType::Number)
+                }
+            },
+            Expr::Call { function, args, column } => match function {
+                Function::If => {
+                    expect(&args[0], ValueType::Boolean, *column)?;
+                    let branch = args[1].check()?;
+                    expect(&args[2], branch, *column)?;
+                    Ok(branch)
+                }
+                Function::IsNoData => {
+                    args[0].check()?;
+                    Ok(ValueType::Boolean)
+                }
+                Function::Coalesce => {
+                    let first = args[0].check()?;
+                    for arg in &args[1..] {
+                        expect(arg, first, *column)?;
+                    }
+                    Ok(first)
+                }
+                Function::Focal(_) => {
+                    expect(&args[0], ValueType::Number, *column)?;
+                    Ok(ValueType::Number)
+                }
+                _ => {
+                    for arg in args {
+                        expect(arg, ValueType::Number, *column)?;
+                    }
+                    Ok(ValueType::Number)
+                }
+            },
+        }
+    }
+
+    /// Visit every band reference
+    pub fn for_each_band<'a>(&'a self, visit: &mut impl FnMut(Option<&'a str>, &'a str, usize) -> Result<()>) -> Result<()> {
+        match self {
+            Expr::Band { input, band, column } => visit(input.as_deref(), band, *column),
+            Expr::Unary { operand, .. } => operand.for_each_band(visit),
+            Expr::Binary { left, right, .. } => {
+                left.for_each_band(visit)?;
+                right.for_each_band(visit)
+            }
+            Expr::Call { args, .. } => args.iter().try_for_each(|arg| arg.for_each_band(visit)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Radius of a focal window, which must be written as a literal
+pub(crate) fn focal_radius(expr: &Expr) -> Option<u32> {
+    match expr {
+        Expr::Number(r) if *r >= 0.0 && r.fract() == 0.0 && *r <= MAX_FOCAL_RADIUS as f64 => Some(*r as u32),
+        _ => None,
+    }
+}
+
+fn expect(expr: &Expr, expected: ValueType, column: usize) -> Result<()> {
+    let found = expr.check()?;
+    if found != expected {
+        return Err(ImageryError::expression(
+            column,
+            format!("Expected a {} operand, found a {}", expected, found),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse an expression into a tree
+pub(crate) fn parse(source: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        depth: 0,
+    };
+    let expr = parser.or()?;
+    let token = parser.peek();
+    if token.kind != TokenKind::Eof {
+        return Err(ImageryError::expression(token.column, "Unexpected input after expression"));
+    }
+    Ok(expr)
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(name) if name == keyword)
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<Token> {
+        let token = self.advance();
+        if token.kind != kind {
+            return Err(ImageryError::expression(token.column, format!("Expected {}", what)));
+        }
+        Ok(token)
+    }
+
+    /// Go one level deeper into the tree, failing past `MAX_DEPTH`
+    fn descend(&mut self, column: usize) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ImageryError::expression(
+                column,
+                format!("Expression is nested more than {} levels deep", MAX_DEPTH),
+            ));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut left = self.and()?;
+        while self.peek().kind == TokenKind::OrOr || self.is_keyword("or") {
+            let column = self.advance().column;
+            self.descend(column)?;
+            let right = self.and()?;
+            left = binary(BinaryOp::Or, left, right, column);
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut left = self.comparison()?;
+        while self.peek().kind == TokenKind::AndAnd || self.is_keyword("and") {
+            let column = self.advance().column;
+            self.descend(column)?;
+            let right = self.comparison()?;
+            left = binary(BinaryOp::And, left, right, column);
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.additive()?;
+        let op = match self.peek().kind {
+            TokenKind::Lt => BinaryOp::Lt,
+            TokenKind::Le => BinaryOp::Le,
+            TokenKind::Gt => BinaryOp::Gt,
+            TokenKind::Ge => BinaryOp::Ge,
+            TokenKind::EqEq => BinaryOp::Eq,
+            TokenKind::Ne => BinaryOp::Ne,
+            _ => return Ok(left),
+        };
+        let column = self.advance().column;
+        self.descend(column)?;
+        let right = self.additive()?;
+        self.depth -= 1;
+        Ok(binary(op, left, right, column))
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut left = self.multiplicative()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Plus => BinaryOp::Add,
+                TokenKind::Minus => BinaryOp::Sub,
+                _ => break,
+            };
+            let column = self.advance().column;
+            self.descend(column)?;
+            let right = self.multiplicative()?;
+            left = binary(op, left, right, column);
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Star => BinaryOp::Mul,
+                TokenKind::Slash => BinaryOp::Div,
+                TokenKind::Percent => BinaryOp::Rem,
+                _ => break,
+            };
+            let column = self.advance().column;
+            self.descend(column)?;
+            let right = self.unary()?;
+            left = binary(op, left, right, column);
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let op = if self.peek().kind == TokenKind::Minus {
+            UnaryOp::Neg
+        } else if self.peek().kind == TokenKind::Bang || self.is_keyword("not") {
+            UnaryOp::Not
+        } else {
+            return self.power();
+        };
+        let column = self.advance().column;
+        self.descend(column)?;
+        let operand = self.unary()?;
+        self.depth -= 1;
+        Ok(Expr::Unary {
+            op,
+            operand: Box::new(operand),
+            column,
+        })
+    }
+
+    /// Exponentiation binds tighter than unary minus on its left and is right associative
+    fn power(&mut self) -> Result<Expr> {
+        let base = self.primary()?;
+        if self.peek().kind != TokenKind::Caret {
+            return Ok(base);
+        }
+        let column = self.advance().column;
+        self.descend(column)?;
+        let exponent = self.unary()?;
+        self.depth -= 1;
+        Ok(binary(BinaryOp::Pow, base, exponent, column))
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Number(value) => Ok(Expr::Number(value)),
+            TokenKind::LParen => {
+                self.descend(token.column)?;
+                let expr = self.or()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            TokenKind::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                "nodata" => Ok(Expr::NoData),
+                "and" | "or" | "not" => Err(ImageryError::expression(
+                    token.column,
+                    format!("Unexpected keyword '{}'", name),
+                )),
+                _ if self.peek().kind == TokenKind::LParen => self.call(name, token.column),
+                _ if self.peek().kind == TokenKind::Dot => {
+                    self.advance();
+                    match self.advance() {
+                        Token {
+                            kind: TokenKind::Ident(band),
+                            ..
+                        } => Ok(Expr::Band {
+                            input: Some(name),
+                            band,
+                            column: token.column,
+                        }),
+                        other => Err(ImageryError::expression(other.column, "Expected a band name after '.'")),
+                    }
+                }
+                _ => Ok(Expr::Band {
+                    input: None,
+                    band: name,
+                    column: token.column,
+                }),
+            },
+            TokenKind::Eof => Err(ImageryError::expression(token.column, "Unexpected end of expression")),
+            _ => Err(ImageryError::expression(token.column, "Expected a value")),
+        }
+    }
+
+    fn call(&mut self, name: String, column: usize) -> Result<Expr> {
+        let function = Function::from_name(&name)
+            .ok_or_else(|| ImageryError::expression(column, format!("Unknown function '{}'", name)))?;
+        self.expect(TokenKind::LParen, "'('")?;
+        self.descend(column)?;
+
+        let mut args = Vec::new();
+        if self.peek().kind != TokenKind::RParen {
+            loop {
+                args.push(self.or()?);
+                if self.peek().kind != TokenKind::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        self.expect(TokenKind::RParen, "',' or ')'")?;
+        self.depth -= 1;
+
+        let (min, max) = function.arity();
+        if args.len() < min || args.len() > max {
+            let expected = if min == max {
+                min.to_string()
+            } else {
+                format!("at least {}", min)
+            };
+            return Err(ImageryError::expression(
+                column,
+                format!("{}() takes {} arguments, got {}", name, expected, args.len()),
+            ));
+        }
+        if matches!(function, Function::Focal(_)) && focal_radius(&args[1]).is_none() {
+            return Err(ImageryError::expression(
+                column,
+                format!("Focal radius must be an integer literal from 0 to {}", MAX_FOCAL_RADIUS),
+            ));
+        }
+        Ok(Expr::Call { function, args, column })
+    }
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr, column: usize) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+        column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_and_type_errors() {
+        // -2^2 is -(2^2) and comparison binds looser than arithmetic
+        let expr = parse("-2^2 + 1 < a.B3 * 2 and not false").unwrap();
+        assert_eq!(expr.check().unwrap(), ValueType::Boolean);
+        let Expr::Binary { op: BinaryOp::And, left, .. } = &expr else {
+            panic!("expected a conjunction, got {:?}", expr);
+        };
+        let Expr::Binary { op: BinaryOp::Lt, left: sum, right: product, .. } = left.as_ref() else {
+            panic!("expected a comparison");
+        };
+        assert!(matches!(sum.as_ref(), Expr::Binary { op: BinaryOp::Add, left, .. }
+            if matches!(left.as_ref(), Expr::Unary { op: UnaryOp::Neg, .. })));
+        assert!(matches!(product.as_ref(), Expr::Binary { op: BinaryOp::Mul, left, .. }
+            if matches!(left.as_ref(), Expr::Band { input: Some(input), band, .. } if input == "a" && band == "B3")));
+
+        assert_eq!(parse("focal_mean(focal_max(B1, 2) - B2, 1)").unwrap().halo(), 3);
+
+        let error = |source: &str| match parse(source).and_then(|expr| expr.check()) {
+            Err(ImageryError::Expression { column, .. }) => column,
+            other => panic!("expected an expression error for {:?}, got {:?}", source, other),
+        };
+        assert_eq!(error("B1 + (B2 > 3)"), 4);
+        assert_eq!(error("if(B1, 1, 2)"), 1);
+        assert_eq!(error("focal_mean(B1, B2)"), 1);
+        assert_eq!(error("clamp(B1, 0)"), 1);
+        assert_eq!(error("B1 $ B2"), 4);
+        assert_eq!(error("(B1 + B2"), 9);
+        assert_eq!(error("sqr(B1)"), 1);
+        assert_eq!(error("focal_max(B1, 4294967295)"), 1);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("{}B1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(parse(&nested(500)), Err(ImageryError::Expression { .. })));
+        assert!(parse(&format!("{}B1", "-".repeat(500))).is_err());
+        assert!(parse(&vec!["B1"; 500].join(" + ")).is_err());
+        assert!(parse(&vec!["B1"; 20].join(" + ")).is_ok());
+    }
+}
@@ -0,0 +1,97 @@
+//! Band math as a workflow task
+
+use super::BandMathJob;
+use async_trait::async_trait;
+use meridian_workflow::{TaskContext, TaskHandler, WorkflowError, WorkflowResult};
+use serde_json::json;
+
+/// Runs a [`BandMathJob`] given as the task configuration
+///
+/// Registered under the task type `imagery.band_math`. The job is checked
+/// when the task starts and runs on the blocking thread pool; the result
+/// describes the written raster.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BandMathTask;
+
+#[async_trait]
+impl TaskHandler for BandMathTask {
+    async fn execute(&self, ctx: TaskContext) -> WorkflowResult<serde_json::Value> {
+        let job: BandMathJob = serde_json::from_value(ctx.config.clone())
+            .map_err(|e| WorkflowError::InvalidDefinition(format!("band math task: {}", e)))?;
+        let failed = |reason: String| WorkflowError::TaskExecutionFailed {
+            task_id: ctx.task_id.to_string(),
+            reason,
+        };
+
+        let output = job.output.clone();
+        let band = job.expression.name().to_string();
+        let metadata = tokio::task::spawn_blocking(move || job.run())
+            .await
+            .map_err(|e| failed(e.to_string()))?
+            .map_err(|e| failed(e.to_string()))?;
+
+        Ok(json!({
+            "output": output,
+            "band": band,
+            "width": metadata.width,
+            "height": metadata.height,
+            "crs": metadata.crs,
+        }))
+    }
+
+    fn task_type(&self) -> &str {
+        "imagery.band_math"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::RasterDataset;
+    use crate::format::{CogWriter, ImageWriter};
+    use crate::{DataType, ImageMetadata, MultiBandImage};
+    use meridian_workflow::{ExecutionOptions, StateManager, Task, WorkflowDag, WorkflowExecutor};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_band_math_workflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = ImageMetadata {
+            width: 16,
+            height: 8,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: Some([500_000.0, 10.0, 0.0, 4_000_080.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut scene = MultiBandImage::new(metadata, DataType::UInt16);
+        scene.bands[0].fill(100.0);
+        scene.bands[1].fill(300.0);
+        let scene_path = dir.path().join("scene.tif");
+        CogWriter::new(&scene_path).unwrap().write(&scene).unwrap();
+
+        let state_manager = Arc::new(StateManager::new());
+        let executor = WorkflowExecutor::new(state_manager.clone());
+        executor.register_handler(Arc::new(BandMathTask)).await;
+
+        let output = dir.path().join("ndvi.tif");
+        let mut workflow = WorkflowDag::new("ndvi");
+        let task_id = workflow.add_task(Task::new("ndvi", "imagery.band_math").with_config(json!({
+            "expression": { "expression": "(B2 - B1) / (B2 + B1)", "name": "NDVI" },
+            "inputs": [{ "name": "scene", "path": scene_path }],
+            "output": output,
+        })));
+
+        let execution_id = executor.execute(&workflow, ExecutionOptions::default()).await.unwrap();
+        let execution = state_manager.get_execution(&execution_id).await.unwrap();
+        let result = execution.tasks[&task_id].result.as_ref().unwrap();
+        assert_eq!(result.output["band"], "NDVI");
+        assert_eq!((result.output["width"].as_u64(), result.output["height"].as_u64()), (Some(16), Some(8)));
+        assert_eq!(result.output["crs"], "EPSG:32633");
+
+        let ndvi = RasterDataset::open_cog(&output).unwrap().to_image().unwrap();
+        assert!((ndvi.bands[0][0] - 0.5).abs() < 1e-6);
+    }
+}
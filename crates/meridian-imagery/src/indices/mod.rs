@@ -7,14 +7,17 @@ pub mod vegetation;
 pub mod water;
 pub mod built_up;
 pub mod custom;
+pub mod expression;
 
 pub use vegetation::VegetationIndices;
 pub use water::WaterIndices;
 pub use built_up::BuiltUpIndices;
 pub use custom::BandMath;
+pub use expression::{BandMathJob, RasterExpression};
 
+use crate::dataset::RasterDataset;
 use crate::error::Result;
-use crate::MultiBandImage;
+use crate::{DataType, MultiBandImage};
 
 /// Generic index calculation result
 #[derive(Debug, Clone)]
@@ -58,6 +61,44 @@ impl IndexResult {
     }
 }
 
+/// Evaluate an index block by block over a dataset
+///
+/// The result is a single `Float32` band named after the index, with NaN
+/// wherever the source is masked or nodata.
+pub fn stream_index<F>(dataset: &RasterDataset, index: F) -> Result<RasterDataset>
+where
+    F: Fn(&MultiBandImage) -> Result<IndexResult> + Sync,
+{
+    // Index functions name their result independently of the pixels, so a
+    // single pixel is enough to learn the band name up front
+    let mut probe_meta = dataset.metadata().clone();
+    probe_meta.width = 1;
+    probe_meta.height = 1;
+    let name = index(&MultiBandImage::new(probe_meta, dataset.layout().data_type))?.name;
+
+    let layout = dataset
+        .layout()
+        .derive(1, DataType::Float32)
+        .with_no_data(f64::NAN)
+        .with_band_names(vec![name]);
+
+    dataset.map_blocks(layout, |block, window| {
+        let result = index(block)?;
+        let valid = dataset.valid_mask(window)?;
+
+        let mut metadata = block.metadata.clone();
+        metadata.bands = 1;
+        let mut output = MultiBandImage::new(metadata, DataType::Float32);
+        output.bands[0] = result
+            .values
+            .iter()
+            .zip(&valid)
+            .map(|(&value, &valid)| if valid { value } else { f32::NAN })
+            .collect();
+        Ok(output)
+    })
+}
+
 /// Helper function to calculate normalized difference
 pub fn normalized_difference(band1: &[f32], band2: &[f32]) -> Result<Vec<f32>> {
     if band1.len() != band2.len() {
@@ -119,6 +160,31 @@ mod tests {
         assert!((result[2] - 0.333).abs() < 0.01); // (200-100)/(200+100)
     }
 
+    #[test]
+    fn test_stream_index() {
+        let metadata = crate::ImageMetadata {
+            width: 12,
+            height: 9,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: None,
+            crs: None,
+            no_data: Some(0.0),
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        image.bands[0].fill(300.0);
+        image.bands[1].fill(100.0);
+        image.bands[1][5] = 0.0;
+        let dataset = RasterDataset::from_image(&image, 5, 5).unwrap();
+
+        let ndvi = stream_index(&dataset, |block| VegetationIndices::ndvi(block, 0, 1)).unwrap();
+        assert_eq!(ndvi.metadata().band_names, vec!["NDVI".to_string()]);
+        let values = ndvi.to_image().unwrap();
+        assert!((values.bands[0][0] - 0.5).abs() < 1e-6);
+        assert!(values.bands[0][5].is_nan());
+    }
+
     #[test]
     fn test_ratio() {
         let band1 = vec![100.0, 150.0, 200.0];
@@ -5,13 +5,15 @@
 //! ## Features
 //!
 //! - **Multi-format Support**: GeoTIFF, COG, JPEG2000, NITF
-//! - **Image Processing**: Radiometric correction, atmospheric correction, orthorectification
-//! - **Spectral Indices**: NDVI, EVI, NDWI, MNDWI, NDBI
+//! - **Image Processing**: Radiometric correction, atmospheric correction, orthorectification, reprojection
+//! - **Spectral Indices**: NDVI, EVI, NDWI, MNDWI, NDBI and runtime band math expressions
 //! - **Classification**: Supervised and unsupervised classification
 //! - **Object Detection**: Change detection, image segmentation
 //! - **STAC Integration**: Catalog search and metadata management
 //! - **Streaming Processing**: Memory-efficient windowed and parallel processing
-//! - **Cloud Optimized**: COG support with HTTP range requests
+//! - **Cloud Optimized**: COG reader and writer over pluggable byte-range sources
+//! - **Out-of-core Datasets**: Lazily read, block-cached rasters in native sample types
+//! - **Raster/Vector Integration**: Zonal statistics, rasterization and polygonization
 //!
 //! ## Example
 //!
@@ -47,7 +49,10 @@ pub mod classification;
 pub mod detection;
 pub mod catalog;
 pub mod streaming;
+pub mod dataset;
+pub mod vector;
 pub mod export;
+mod georef;
 
 pub use error::{ImageryError, Result};
 
@@ -65,12 +70,14 @@ pub mod prelude {
         Pansharpening,
         Orthorectification,
         MosaicBuilder,
+        Warper,
     };
     pub use crate::indices::{
         VegetationIndices,
         WaterIndices,
         BuiltUpIndices,
         BandMath,
+        RasterExpression,
     };
     pub use crate::classification::{
         SupervisedClassifier,
@@ -93,6 +100,16 @@ pub mod prelude {
         ParallelProcessor,
         StreamingReader,
     };
+    pub use crate::dataset::{
+        RasterDataset,
+        RasterBuffer,
+        BlockCache,
+    };
+    pub use crate::vector::{
+        ZonalStatistics,
+        Rasterizer,
+        Polygonizer,
+    };
     pub use crate::export::{
         TilePyramid,
         PreviewGenerator,
@@ -6,18 +6,21 @@
 //! - Pan-sharpening
 //! - Orthorectification
 //! - Mosaicking
+//! - Reprojection and warping
 
 pub mod radiometric;
 pub mod atmospheric;
 pub mod pansharpening;
 pub mod orthorectification;
 pub mod mosaic;
+pub mod warp;
 
 pub use radiometric::RadiometricCorrection;
 pub use atmospheric::AtmosphericCorrection;
 pub use pansharpening::Pansharpening;
 pub use orthorectification::Orthorectification;
 pub use mosaic::MosaicBuilder;
+pub use warp::{TargetGrid, Warper};
 
 use crate::error::Result;
 use crate::MultiBandImage;
@@ -33,6 +36,10 @@ pub enum ResamplingMethod {
     Cubic,
     /// Lanczos resampling
     Lanczos,
+    /// Mean of the valid source pixels under the output pixel
+    Average,
+    /// Most frequent valid source value under the output pixel
+    Mode,
 }
 
 /// Image processing pipeline
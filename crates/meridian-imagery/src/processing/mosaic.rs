@@ -1,9 +1,15 @@
 //! Image mosaicking
 //!
 //! Seamlessly combine multiple overlapping images into a single composite.
+//! Inputs in different coordinate reference systems or at different
+//! resolutions are warped onto a common grid first.
 
 use crate::error::{ImageryError, Result};
+use crate::georef::{crs_label, parse_crs};
+use crate::processing::warp::Warper;
+use crate::processing::ResamplingMethod;
 use crate::MultiBandImage;
+use meridian_core::crs::Crs;
 use std::collections::HashMap;
 
 /// Mosaic builder for combining multiple images
@@ -12,6 +18,9 @@ pub struct MosaicBuilder {
     blend_method: BlendMethod,
     feathering_width: u32,
     color_balance: bool,
+    target_crs: Option<Crs>,
+    resolution: Option<(f64, f64)>,
+    resampling: ResamplingMethod,
 }
 
 /// Blending methods for mosaics
@@ -41,6 +50,9 @@ impl MosaicBuilder {
             blend_method: BlendMethod::DistanceWeighted,
             feathering_width: 50,
             color_balance: true,
+            target_crs: None,
+            resolution: None,
+            resampling: ResamplingMethod::NearestNeighbor,
         }
     }
 
@@ -68,6 +80,28 @@ impl MosaicBuilder {
         self
     }
 
+    /// Set the CRS of the mosaic
+    ///
+    /// Defaults to the CRS of the first image that has one.
+    pub fn with_target_crs(&mut self, crs: Crs) -> &mut Self {
+        self.target_crs = Some(crs);
+        self
+    }
+
+    /// Set the output resolution in target CRS units
+    ///
+    /// Defaults to the finest resolution among the inputs.
+    pub fn with_resolution(&mut self, res_x: f64, res_y: f64) -> &mut Self {
+        self.resolution = Some((res_x, res_y));
+        self
+    }
+
+    /// Set the resampling used when inputs have to be warped
+    pub fn with_resampling(&mut self, method: ResamplingMethod) -> &mut Self {
+        self.resampling = method;
+        self
+    }
+
     /// Build the mosaic
     pub fn build(&self) -> Result<MultiBandImage> {
         if self.images.is_empty() {
@@ -76,53 +110,150 @@ impl MosaicBuilder {
             ));
         }
 
-        // Calculate output bounds
-        let (min_x, min_y, max_x, max_y) = self.calculate_bounds()?;
+        // Color balance images if enabled
+        let images = if self.color_balance {
+            self.balance_colors(&self.images)?
+        } else {
+            self.images.clone()
+        };
 
-        let output_width = (max_x - min_x) as u32;
-        let output_height = (max_y - min_y) as u32;
+        // Bring every input onto the same CRS and pixel grid
+        let (images, target_crs) = self.align_inputs(images)?;
+
+        // Calculate output bounds
+        let (min_x, min_y, max_x, max_y) = Self::calculate_bounds(&images)?;
 
         let mut output_meta = self.images[0].metadata.clone();
-        output_meta.width = output_width;
-        output_meta.height = output_height;
+        output_meta.geo_transform = images
+            .iter()
+            .find_map(|image| image.metadata.geo_transform)
+            .or(output_meta.geo_transform);
+
+        let (pixel_width, pixel_height) = match output_meta.geo_transform {
+            Some(gt) => (gt[1], -gt[5]),
+            None => (1.0, 1.0),
+        };
+        output_meta.width = ((max_x - min_x) / pixel_width).round() as u32;
+        output_meta.height = ((max_y - min_y) / pixel_height).round() as u32;
 
         // Update geotransform if available
         if let Some(ref mut gt) = output_meta.geo_transform {
             gt[0] = min_x;
             gt[3] = max_y;
         }
+        if let Some(crs) = target_crs {
+            output_meta.crs = Some(crs_label(&crs));
+        }
 
         let mut output = MultiBandImage::new(output_meta, self.images[0].data_type);
 
-        // Color balance images if enabled
-        let images = if self.color_balance {
-            self.balance_colors(&self.images)?
-        } else {
-            self.images.clone()
-        };
-
         // Blend images
         match self.blend_method {
-            BlendMethod::Average => self.blend_average(&images, &mut output, min_x, min_y)?,
-            BlendMethod::Maximum => self.blend_max(&images, &mut output, min_x, min_y)?,
-            BlendMethod::Minimum => self.blend_min(&images, &mut output, min_x, min_y)?,
-            BlendMethod::First => self.blend_first(&images, &mut output, min_x, min_y)?,
-            BlendMethod::Last => self.blend_last(&images, &mut output, min_x, min_y)?,
-            BlendMethod::DistanceWeighted => self.blend_distance_weighted(&images, &mut output, min_x, min_y)?,
-            BlendMethod::MultiBand => self.blend_multiband(&images, &mut output, min_x, min_y)?,
+            BlendMethod::Average => self.blend_average(&images, &mut output, min_x, max_y)?,
+            BlendMethod::Maximum => self.blend_max(&images, &mut output, min_x, max_y)?,
+            BlendMethod::Minimum => self.blend_min(&images, &mut output, min_x, max_y)?,
+            BlendMethod::First => self.blend_first(&images, &mut output, min_x, max_y)?,
+            BlendMethod::Last => self.blend_last(&images, &mut output, min_x, max_y)?,
+            BlendMethod::DistanceWeighted => self.blend_distance_weighted(&images, &mut output, min_x, max_y)?,
+            BlendMethod::MultiBand => self.blend_multiband(&images, &mut output, min_x, max_y)?,
         }
 
         Ok(output)
     }
 
+    /// Warp inputs onto a common grid when their CRSs or resolutions differ
+    ///
+    /// Images without a CRS are assumed to already be in the target CRS.
+    /// Warped images mark uncovered pixels as NaN so blending skips them.
+    fn align_inputs(&self, images: Vec<MultiBandImage>) -> Result<(Vec<MultiBandImage>, Option<Crs>)> {
+        let target = match &self.target_crs {
+            Some(crs) => Some(crs.clone()),
+            None => images
+                .iter()
+                .find_map(|image| image.metadata.crs.as_deref())
+                .map(parse_crs)
+                .transpose()?,
+        };
+        let Some(target) = target else {
+            return Ok((images, None));
+        };
+        let label = crs_label(&target);
+
+        let mut grids = Vec::with_capacity(images.len());
+        for image in &images {
+            let same_crs = match image.metadata.crs.as_deref() {
+                Some(crs) => parse_crs(crs)? == target,
+                None => true,
+            };
+            grids.push((same_crs, image.metadata.geo_transform));
+        }
+
+        let resolution = match self.resolution {
+            Some(resolution) => resolution,
+            None => {
+                let mut finest = (f64::INFINITY, f64::INFINITY);
+                for (image, (same_crs, gt)) in images.iter().zip(&grids) {
+                    let Some(gt) = gt else { continue };
+                    let res = if *same_crs && gt[2] == 0.0 && gt[4] == 0.0 {
+                        (gt[1], -gt[5])
+                    } else {
+                        let grid = Warper::new(target.clone()).suggest_grid(&Self::with_crs(image, &label).metadata)?;
+                        (grid.geo_transform[1], -grid.geo_transform[5])
+                    };
+                    finest = (finest.0.min(res.0), finest.1.min(res.1));
+                }
+                finest
+            }
+        };
+
+        let needs_warp = grids.iter().any(|(same_crs, gt)| match gt {
+            Some(gt) => {
+                !same_crs || gt[2] != 0.0 || gt[4] != 0.0 || gt[1] != resolution.0 || -gt[5] != resolution.1
+            }
+            None => false,
+        });
+        if !needs_warp {
+            return Ok((images, Some(target)));
+        }
+
+        log::info!("Warping {} mosaic inputs to {} at {:?}", images.len(), label, resolution);
+        let warper = Warper::new(target.clone())
+            .with_resolution(resolution.0, resolution.1)
+            .with_target_aligned_pixels(true)
+            .with_resampling(self.resampling)
+            .with_no_data(f64::NAN);
+
+        let aligned = images
+            .into_iter()
+            .map(|image| match image.metadata.geo_transform {
+                Some(_) => warper.warp(&Self::with_crs(&image, &label)),
+                None => Ok(image),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((aligned, Some(target)))
+    }
+
+    /// Copy of an image that falls back to `crs` when it has none
+    fn with_crs(image: &MultiBandImage, crs: &str) -> MultiBandImage {
+        let mut image = image.clone();
+        image.metadata.crs.get_or_insert_with(|| crs.to_string());
+        image
+    }
+
+    /// Whether every band of a pixel holds data
+    fn is_valid(image: &MultiBandImage, idx: usize) -> bool {
+        let no_data = image.metadata.no_data.map(|v| v as f32);
+        image.bands.iter().all(|band| !band[idx].is_nan() && Some(band[idx]) != no_data)
+    }
+
     /// Calculate mosaic bounds
-    fn calculate_bounds(&self) -> Result<(f64, f64, f64, f64)> {
+    fn calculate_bounds(images: &[MultiBandImage]) -> Result<(f64, f64, f64, f64)> {
         let mut min_x = f64::INFINITY;
         let mut min_y = f64::INFINITY;
         let mut max_x = f64::NEG_INFINITY;
         let mut max_y = f64::NEG_INFINITY;
 
-        for image in &self.images {
+        for image in images {
             if let Some(gt) = &image.metadata.geo_transform {
                 let x1 = gt[0];
                 let y1 = gt[3];
@@ -268,8 +399,8 @@ impl MosaicBuilder {
 
             // Add weighted values
             if let Some(gt) = &image.metadata.geo_transform {
-                let img_offset_x = ((gt[0] - offset_x) / gt[1]) as i32;
-                let img_offset_y = ((gt[3] - offset_y) / gt[5]) as i32;
+                let img_offset_x = ((gt[0] - offset_x) / gt[1]).round() as i32;
+                let img_offset_y = ((gt[3] - offset_y) / gt[5]).round() as i32;
 
                 for y in 0..image.metadata.height {
                     for x in 0..image.metadata.width {
@@ -280,6 +411,9 @@ impl MosaicBuilder {
                            out_y >= 0 && out_y < output.metadata.height as i32 {
                             let src_idx = (y * image.metadata.width + x) as usize;
                             let dst_idx = (out_y as u32 * output.metadata.width + out_x as u32) as usize;
+                            if !Self::is_valid(image, src_idx) {
+                                continue;
+                            }
 
                             let weight = distance_map[src_idx];
 
@@ -326,14 +460,14 @@ impl MosaicBuilder {
         counts: &mut [u32],
         offset_x: f64,
         offset_y: f64,
-        blend_fn: F,
+        mut blend_fn: F,
     ) -> Result<()>
     where
-        F: Fn(f32, f32, usize) -> f32,
+        F: FnMut(f32, f32, usize) -> f32,
     {
         if let Some(gt) = &image.metadata.geo_transform {
-            let img_offset_x = ((gt[0] - offset_x) / gt[1]) as i32;
-            let img_offset_y = ((gt[3] - offset_y) / gt[5]) as i32;
+            let img_offset_x = ((gt[0] - offset_x) / gt[1]).round() as i32;
+            let img_offset_y = ((gt[3] - offset_y) / gt[5]).round() as i32;
 
             for y in 0..image.metadata.height {
                 for x in 0..image.metadata.width {
@@ -344,6 +478,9 @@ impl MosaicBuilder {
                        out_y >= 0 && out_y < output.metadata.height as i32 {
                         let src_idx = (y * image.metadata.width + x) as usize;
                         let dst_idx = (out_y as u32 * output.metadata.width + out_x as u32) as usize;
+                        if !Self::is_valid(image, src_idx) {
+                            continue;
+                        }
 
                         for (band_idx, band) in image.bands.iter().enumerate() {
                             let current = output.bands[band_idx][dst_idx];
@@ -500,4 +637,49 @@ mod tests {
 
         assert_eq!(builder.images.len(), 1);
     }
+
+    #[test]
+    fn test_mosaic_across_utm_zones() {
+        let scene = |crs: &str, origin: (f64, f64), value: f32| {
+            let metadata = ImageMetadata {
+                width: 10,
+                height: 10,
+                bands: 1,
+                bits_per_sample: 8,
+                geo_transform: Some([origin.0, 1000.0, 0.0, origin.1, 0.0, -1000.0]),
+                crs: Some(crs.to_string()),
+                no_data: None,
+                band_names: vec![],
+            };
+            let mut image = MultiBandImage::new(metadata, DataType::UInt8);
+            image.bands[0].fill(value);
+            image
+        };
+
+        // Place the second scene half overlapping the first, across the 12E zone edge
+        let west = scene("EPSG:32632", (720_000.0, 5_320_000.0), 50.0);
+        let to_east = Crs::utm(32, true).transformer(&Crs::utm(33, true)).unwrap();
+        let (east_x, east_y) = to_east.transform(725_000.0, 5_320_000.0).unwrap();
+        let east = scene("EPSG:32633", (east_x, east_y), 150.0);
+
+        let mut builder = MosaicBuilder::new();
+        builder
+            .add_image(west)
+            .add_image(east)
+            .with_blend_method(BlendMethod::First)
+            .with_color_balance(false);
+        let mosaic = builder.build().unwrap();
+
+        assert_eq!(mosaic.metadata.crs.as_deref(), Some("EPSG:32632"));
+        let gt = mosaic.metadata.geo_transform.unwrap();
+        assert_eq!((gt[1], gt[5]), (1000.0, -1000.0));
+        assert!(mosaic.metadata.width >= 15 && mosaic.metadata.width <= 17);
+
+        // Western pixels come from the first scene, the far east from the second
+        let width = mosaic.metadata.width as usize;
+        let row = &mosaic.bands[0][5 * width..6 * width];
+        assert_eq!(row[0], 50.0);
+        assert_eq!(row[width - 2], 150.0);
+        assert!(mosaic.bands[0].iter().all(|v| !v.is_nan()));
+    }
 }
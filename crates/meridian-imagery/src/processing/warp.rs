@@ -0,0 +1,550 @@
+//! Raster reprojection and warping
+//!
+//! [`Warper`] resamples an image onto a pixel grid in another coordinate
+//! reference system. Output pixel centers are mapped back into the source
+//! through an approximate transformer: exact transforms are computed at the
+//! ends and middle of a row segment, and linear interpolation is used wherever
+//! it stays within an error threshold, splitting the segment otherwise.
+
+use crate::error::{ImageryError, Result};
+use crate::georef::{apply_geo_transform, crs_label, invert_geo_transform, source_reference};
+use crate::processing::ResamplingMethod;
+use crate::{ImageMetadata, MultiBandImage};
+use meridian_core::crs::{Crs, Transformer};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Default approximation error, in source pixels
+const DEFAULT_ERROR_THRESHOLD: f64 = 0.125;
+
+/// Points sampled along each side when projecting a raster footprint
+const FOOTPRINT_SAMPLES: u32 = 20;
+
+/// Pixel grid a warp writes into
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetGrid {
+    /// Geotransform of the grid
+    pub geo_transform: [f64; 6],
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl TargetGrid {
+    /// North-up grid covering `bounds` ([min_x, min_y, max_x, max_y])
+    ///
+    /// With `aligned` the bounds are first snapped outwards to multiples of
+    /// the resolution, so grids with the same resolution share pixel edges.
+    pub fn from_bounds(bounds: [f64; 4], res_x: f64, res_y: f64, aligned: bool) -> Result<Self> {
+        let [mut min_x, mut min_y, mut max_x, mut max_y] = bounds;
+        let valid = res_x > 0.0 && res_y > 0.0 && max_x > min_x && max_y > min_y;
+        if !valid {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Cannot build a grid over {:?} with resolution ({}, {})",
+                bounds, res_x, res_y
+            )));
+        }
+        if aligned {
+            min_x = (min_x / res_x).floor() * res_x;
+            max_x = (max_x / res_x).ceil() * res_x;
+            min_y = (min_y / res_y).floor() * res_y;
+            max_y = (max_y / res_y).ceil() * res_y;
+        }
+
+        // Tolerate rounding noise so exact multiples do not gain a pixel
+        let width = ((max_x - min_x) / res_x - 1e-6).ceil().max(1.0) as u32;
+        let height = ((max_y - min_y) / res_y - 1e-6).ceil().max(1.0) as u32;
+        Ok(Self {
+            geo_transform: [min_x, res_x, 0.0, max_y, 0.0, -res_y],
+            width,
+            height,
+        })
+    }
+
+    /// Extent of the grid as [min_x, min_y, max_x, max_y]
+    pub fn bounds(&self) -> [f64; 4] {
+        let corners = [(0.0, 0.0), (self.width as f64, 0.0), (0.0, self.height as f64), (self.width as f64, self.height as f64)];
+        let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+        for (px, py) in corners {
+            let (x, y) = apply_geo_transform(&self.geo_transform, px, py);
+            bounds = [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)];
+        }
+        bounds
+    }
+}
+
+/// Reprojects images between coordinate reference systems
+#[derive(Debug, Clone)]
+pub struct Warper {
+    target_crs: Crs,
+    resolution: Option<(f64, f64)>,
+    bounds: Option<[f64; 4]>,
+    aligned: bool,
+    resampling: ResamplingMethod,
+    error_threshold: f64,
+    no_data: Option<f64>,
+}
+
+impl Warper {
+    /// Create a warper into the given CRS
+    pub fn new(target_crs: Crs) -> Self {
+        Self {
+            target_crs,
+            resolution: None,
+            bounds: None,
+            aligned: false,
+            resampling: ResamplingMethod::NearestNeighbor,
+            error_threshold: DEFAULT_ERROR_THRESHOLD,
+            no_data: None,
+        }
+    }
+
+    /// Set the output pixel size in target CRS units
+    pub fn with_resolution(mut self, res_x: f64, res_y: f64) -> Self {
+        self.resolution = Some((res_x, res_y));
+        self
+    }
+
+    /// Set the output extent as [min_x, min_y, max_x, max_y] in the target CRS
+    pub fn with_bounds(mut self, bounds: [f64; 4]) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Snap the output extent to multiples of the resolution
+    pub fn with_target_aligned_pixels(mut self, aligned: bool) -> Self {
+        self.aligned = aligned;
+        self
+    }
+
+    /// Set the resampling kernel
+    pub fn with_resampling(mut self, method: ResamplingMethod) -> Self {
+        self.resampling = method;
+        self
+    }
+
+    /// Set the approximate transformer tolerance in source pixels
+    ///
+    /// Zero transforms every output pixel exactly.
+    pub fn with_error_threshold(mut self, pixels: f64) -> Self {
+        self.error_threshold = pixels.max(0.0);
+        self
+    }
+
+    /// Set the value written where no source data is available
+    ///
+    /// Defaults to the source nodata value, or zero without one.
+    pub fn with_no_data(mut self, no_data: f64) -> Self {
+        self.no_data = Some(no_data);
+        self
+    }
+
+    /// Target CRS of the warp
+    pub fn target_crs(&self) -> &Crs {
+        &self.target_crs
+    }
+
+    /// Output grid for an image under the configured extent and resolution
+    ///
+    /// Missing settings are derived from the source: the extent from its
+    /// projected footprint and the resolution from the size of a source pixel
+    /// at the image center.
+    pub fn suggest_grid(&self, metadata: &ImageMetadata) -> Result<TargetGrid> {
+        let (source_crs, src_gt) = source_reference(metadata)?;
+        let transformer = transformer_between(&source_crs, &self.target_crs)?;
+        let forward = |px: f64, py: f64| {
+            let (x, y) = apply_geo_transform(&src_gt, px, py);
+            match &transformer {
+                Some(t) => t.transform(x, y).ok(),
+                None => Some((x, y)),
+            }
+        };
+
+        let (width, height) = (metadata.width as f64, metadata.height as f64);
+        let mut footprint = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+        for i in 0..=FOOTPRINT_SAMPLES {
+            for j in 0..=FOOTPRINT_SAMPLES {
+                let px = width * i as f64 / FOOTPRINT_SAMPLES as f64;
+                let py = height * j as f64 / FOOTPRINT_SAMPLES as f64;
+                if let Some((x, y)) = forward(px, py) {
+                    footprint = [footprint[0].min(x), footprint[1].min(y), footprint[2].max(x), footprint[3].max(y)];
+                }
+            }
+        }
+        if !footprint.iter().all(|v| v.is_finite()) {
+            return Err(ImageryError::processing("Image footprint lies outside the target CRS domain"));
+        }
+
+        let (res_x, res_y) = match self.resolution {
+            Some(resolution) => resolution,
+            None => {
+                // Size of one source pixel at the image center, kept square
+                let (cx, cy) = (width / 2.0, height / 2.0);
+                let res = match (forward(cx, cy), forward(cx + 1.0, cy + 1.0)) {
+                    (Some(a), Some(b)) => ((b.0 - a.0).hypot(b.1 - a.1)) / std::f64::consts::SQRT_2,
+                    _ => 0.0,
+                };
+                let res = if res > 0.0 {
+                    res
+                } else {
+                    ((footprint[2] - footprint[0]) * (footprint[3] - footprint[1]) / (width * height)).sqrt()
+                };
+                (res, res)
+            }
+        };
+
+        TargetGrid::from_bounds(self.bounds.unwrap_or(footprint), res_x, res_y, self.aligned)
+    }
+
+    /// Reproject an image onto its suggested grid
+    pub fn warp(&self, image: &MultiBandImage) -> Result<MultiBandImage> {
+        let grid = self.suggest_grid(&image.metadata)?;
+        self.warp_to_grid(image, &grid)
+    }
+
+    /// Reproject an image onto a specific grid in the target CRS
+    pub fn warp_to_grid(&self, image: &MultiBandImage, grid: &TargetGrid) -> Result<MultiBandImage> {
+        let (source_crs, src_gt) = source_reference(&image.metadata)?;
+        let src_inv = invert_geo_transform(&src_gt)
+            .ok_or_else(|| ImageryError::metadata("Source geotransform is not invertible"))?;
+        let transform = PixelTransform {
+            dst_gt: grid.geo_transform,
+            src_inv,
+            // Output pixels are pulled from the source, so transform backwards
+            transformer: transformer_between(&self.target_crs, &source_crs)?,
+        };
+
+        let no_data = self.no_data.or(image.metadata.no_data);
+        let fill = no_data.unwrap_or(0.0) as f32;
+        let source = SourceImage {
+            bands: &image.bands,
+            width: image.metadata.width as i64,
+            height: image.metadata.height as i64,
+            no_data: image.metadata.no_data.map(|v| v as f32),
+        };
+        let scale = transform.scale(grid.width, grid.height);
+
+        let width = grid.width as usize;
+        let rows: Vec<Vec<Vec<f32>>> = (0..grid.height)
+            .into_par_iter()
+            .map(|row| {
+                let positions = transform.row(row, grid.width, self.error_threshold);
+                (0..source.bands.len())
+                    .map(|band| {
+                        positions
+                            .iter()
+                            .map(|position| {
+                                position
+                                    .and_then(|(sx, sy)| source.sample(band, self.resampling, sx, sy, scale))
+                                    .unwrap_or(fill)
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut metadata = image.metadata.clone();
+        metadata.width = grid.width;
+        metadata.height = grid.height;
+        metadata.geo_transform = Some(grid.geo_transform);
+        metadata.crs = Some(crs_label(&self.target_crs));
+        metadata.no_data = no_data;
+
+        let mut output = MultiBandImage::new(metadata, image.data_type);
+        for (row, bands) in rows.into_iter().enumerate() {
+            for (band, values) in bands.into_iter().enumerate() {
+                output.bands[band][row * width..(row + 1) * width].copy_from_slice(&values);
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Built-in transformer between two CRSs, or `None` when they are the same
+fn transformer_between(source: &Crs, target: &Crs) -> Result<Option<Transformer>> {
+    if source == target {
+        return Ok(None);
+    }
+    source
+        .transformer(target)
+        .map(Some)
+        .map_err(|e| ImageryError::Processing(format!("Cannot transform {} to {}: {}", source, target, e)))
+}
+
+/// Maps output pixel positions to source pixel positions
+struct PixelTransform {
+    dst_gt: [f64; 6],
+    src_inv: [f64; 6],
+    transformer: Option<Transformer>,
+}
+
+type Position = Option<(f64, f64)>;
+
+impl PixelTransform {
+    /// Exact source position of an output position
+    fn exact(&self, x: f64, y: f64) -> Position {
+        let (gx, gy) = apply_geo_transform(&self.dst_gt, x, y);
+        let (sx, sy) = match &self.transformer {
+            Some(transformer) => transformer.transform(gx, gy).ok()?,
+            None => (gx, gy),
+        };
+        Some(apply_geo_transform(&self.src_inv, sx, sy))
+    }
+
+    /// Source positions of the pixel centers of an output row
+    fn row(&self, row: u32, width: u32, threshold: f64) -> Vec<Position> {
+        let y = row as f64 + 0.5;
+        let mut out = vec![None; width as usize];
+        if out.is_empty() {
+            return out;
+        }
+        let last = out.len() - 1;
+        out[0] = self.exact(0.5, y);
+        out[last] = self.exact(last as f64 + 0.5, y);
+        self.approximate(y, 0, last, threshold, &mut out);
+        out
+    }
+
+    /// Fill positions strictly between `start` and `end`, which are already exact
+    fn approximate(&self, y: f64, start: usize, end: usize, threshold: f64, out: &mut [Position]) {
+        if end - start < 2 {
+            return;
+        }
+        let mid = (start + end) / 2;
+        out[mid] = self.exact(mid as f64 + 0.5, y);
+
+        if let (Some(a), Some(b), Some(m)) = (out[start], out[end], out[mid]) {
+            let lerp = |i: usize| {
+                let t = (i - start) as f64 / (end - start) as f64;
+                (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+            };
+            let (lx, ly) = lerp(mid);
+            if (lx - m.0).abs() + (ly - m.1).abs() <= threshold {
+                for (i, slot) in out.iter_mut().enumerate().take(end).skip(start + 1) {
+                    if i != mid {
+                        *slot = Some(lerp(i));
+                    }
+                }
+                return;
+            }
+        }
+
+        self.approximate(y, start, mid, threshold, out);
+        self.approximate(y, mid, end, threshold, out);
+    }
+
+    /// Source pixels covered by one output pixel near the grid center
+    fn scale(&self, width: u32, height: u32) -> (f64, f64) {
+        let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+        let distance = |a: Position, b: Position| match (a, b) {
+            (Some(a), Some(b)) => (b.0 - a.0).hypot(b.1 - a.1),
+            _ => 1.0,
+        };
+        let center = self.exact(cx, cy);
+        (
+            distance(center, self.exact(cx + 1.0, cy)),
+            distance(center, self.exact(cx, cy + 1.0)),
+        )
+    }
+}
+
+/// Source bands with nodata-aware sampling
+struct SourceImage<'a> {
+    bands: &'a [Vec<f32>],
+    width: i64,
+    height: i64,
+    no_data: Option<f32>,
+}
+
+impl SourceImage<'_> {
+    fn value(&self, band: usize, x: i64, y: i64) -> Option<f32> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        let value = self.bands[band][(y * self.width + x) as usize];
+        (!value.is_nan() && self.no_data != Some(value)).then_some(value)
+    }
+
+    /// Resample one band at a source position, `None` where there is no data
+    fn sample(&self, band: usize, method: ResamplingMethod, sx: f64, sy: f64, scale: (f64, f64)) -> Option<f32> {
+        if sx < 0.0 || sy < 0.0 || sx >= self.width as f64 || sy >= self.height as f64 {
+            return None;
+        }
+        match method {
+            ResamplingMethod::NearestNeighbor => self.value(band, sx.floor() as i64, sy.floor() as i64),
+            ResamplingMethod::Bilinear => self.convolve(band, sx, sy, scale, 1.0, triangle),
+            ResamplingMethod::Cubic => self.convolve(band, sx, sy, scale, 2.0, cubic),
+            ResamplingMethod::Lanczos => self.convolve(band, sx, sy, scale, 3.0, lanczos),
+            ResamplingMethod::Average => {
+                let values = self.footprint(band, sx, sy, scale);
+                if values.is_empty() {
+                    return None;
+                }
+                Some((values.iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64) as f32)
+            }
+            ResamplingMethod::Mode => {
+                let values = self.footprint(band, sx, sy, scale);
+                let mut counts: HashMap<u32, usize> = HashMap::new();
+                for value in &values {
+                    *counts.entry(value.to_bits()).or_default() += 1;
+                }
+                // The first value reaching the highest count wins ties
+                let best = counts.values().copied().max()?;
+                values.into_iter().find(|value| counts[&value.to_bits()] == best)
+            }
+        }
+    }
+
+    /// Valid samples of the source pixels covered by an output pixel
+    fn footprint(&self, band: usize, sx: f64, sy: f64, scale: (f64, f64)) -> Vec<f32> {
+        let (hx, hy) = (scale.0.max(1.0) / 2.0, scale.1.max(1.0) / 2.0);
+        let x0 = (sx - hx).floor() as i64;
+        let x1 = ((sx + hx).ceil() as i64).max(x0 + 1);
+        let y0 = (sy - hy).floor() as i64;
+        let y1 = ((sy + hy).ceil() as i64).max(y0 + 1);
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .filter_map(|(x, y)| self.value(band, x, y))
+            .collect()
+    }
+
+    /// Separable kernel resampling, widened by the downsampling factor
+    fn convolve(&self, band: usize, sx: f64, sy: f64, scale: (f64, f64), radius: f64, kernel: fn(f64) -> f64) -> Option<f32> {
+        let (fx, fy) = (scale.0.max(1.0), scale.1.max(1.0));
+        // Kernels are centered on pixel centers
+        let (px, py) = (sx - 0.5, sy - 0.5);
+        let x0 = (px - radius * fx).ceil() as i64;
+        let x1 = (px + radius * fx).floor() as i64;
+        let y0 = (py - radius * fy).ceil() as i64;
+        let y1 = (py + radius * fy).floor() as i64;
+
+        let (mut sum, mut weights) = (0.0f64, 0.0f64);
+        for y in y0..=y1 {
+            let wy = kernel((y as f64 - py) / fy);
+            if wy == 0.0 {
+                continue;
+            }
+            for x in x0..=x1 {
+                let wx = kernel((x as f64 - px) / fx);
+                if wx == 0.0 {
+                    continue;
+                }
+                if let Some(value) = self.value(band, x, y) {
+                    sum += value as f64 * wx * wy;
+                    weights += wx * wy;
+                }
+            }
+        }
+        (weights.abs() > 1e-9).then(|| (sum / weights) as f32)
+    }
+}
+
+fn triangle(t: f64) -> f64 {
+    (1.0 - t.abs()).max(0.0)
+}
+
+/// Keys cubic convolution with a = -0.5
+fn cubic(t: f64) -> f64 {
+    let t = t.abs();
+    if t < 1.0 {
+        1.5 * t.powi(3) - 2.5 * t.powi(2) + 1.0
+    } else if t < 2.0 {
+        -0.5 * t.powi(3) + 2.5 * t.powi(2) - 4.0 * t + 2.0
+    } else {
+        0.0
+    }
+}
+
+fn lanczos(t: f64) -> f64 {
+    const A: f64 = 3.0;
+    if t == 0.0 {
+        return 1.0;
+    }
+    if t.abs() >= A {
+        return 0.0;
+    }
+    let pt = std::f64::consts::PI * t;
+    A * pt.sin() * (pt / A).sin() / (pt * pt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataType;
+
+    fn image(crs: &str, gt: [f64; 6], width: u32, height: u32, values: impl Fn(u32, u32) -> f32) -> MultiBandImage {
+        let metadata = ImageMetadata {
+            width,
+            height,
+            bands: 1,
+            bits_per_sample: 32,
+            geo_transform: Some(gt),
+            crs: Some(crs.to_string()),
+            no_data: Some(-1.0),
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::Float32);
+        for y in 0..height {
+            for x in 0..width {
+                image.bands[0][(y * width + x) as usize] = values(x, y);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn test_same_crs_resampling() {
+        let source = image("EPSG:32632", [500_000.0, 10.0, 0.0, 5_000_000.0, 0.0, -10.0], 4, 4, |x, y| (y * 4 + x) as f32);
+        let grid = TargetGrid::from_bounds([500_000.0, 4_999_960.0, 500_040.0, 5_000_000.0], 5.0, 5.0, false).unwrap();
+        assert_eq!((grid.width, grid.height), (8, 8));
+
+        let warper = Warper::new(Crs::utm(32, true));
+        let nearest = warper.warp_to_grid(&source, &grid).unwrap();
+        assert_eq!(nearest.bands[0][2], 1.0);
+        assert_eq!(nearest.metadata.crs.as_deref(), Some("EPSG:32632"));
+
+        let bilinear = warper.clone().with_resampling(ResamplingMethod::Bilinear).warp_to_grid(&source, &grid).unwrap();
+        // Output pixel (2, 2) sits a quarter pixel from source pixel (1, 1) towards (0, 0)
+        assert!((bilinear.bands[0][2 * 8 + 2] - 3.75).abs() < 1e-5);
+
+        let coarse = TargetGrid::from_bounds([500_000.0, 4_999_960.0, 500_040.0, 5_000_000.0], 20.0, 20.0, false).unwrap();
+        let mut holes = source.clone();
+        holes.bands[0][1] = -1.0;
+        let average = warper.clone().with_resampling(ResamplingMethod::Average).warp_to_grid(&holes, &coarse).unwrap();
+        // Source pixel 1 is nodata, leaving 0, 4 and 5 under the first output pixel
+        assert_eq!(average.bands[0][0], 3.0);
+        let mode = warper.with_resampling(ResamplingMethod::Mode).warp_to_grid(&holes, &coarse).unwrap();
+        assert_eq!(mode.bands[0][0], 0.0);
+    }
+
+    #[test]
+    fn test_utm_to_geographic() {
+        // A 1 km square of UTM 32N around 9°E 48°N with an east-west gradient
+        let source = image("EPSG:32632", [499_500.0, 10.0, 0.0, 5_316_800.0, 0.0, -10.0], 100, 100, |x, _| x as f32);
+        let warper = Warper::new(Crs::wgs84()).with_resolution(0.0002, 0.0001).with_target_aligned_pixels(true);
+        let grid = warper.suggest_grid(&source.metadata).unwrap();
+        assert!(grid.geo_transform[0] < 9.0 && grid.bounds()[2] > 9.0);
+        let snapped = (grid.geo_transform[0] / 0.0002).round() * 0.0002;
+        assert!((grid.geo_transform[0] - snapped).abs() < 1e-12);
+
+        let warped = warper.warp(&source).unwrap();
+        let exact = warper.clone().with_error_threshold(0.0).warp(&source).unwrap();
+        assert_eq!(warped.metadata.crs.as_deref(), Some("EPSG:4326"));
+        assert_eq!(warped.metadata.no_data, Some(-1.0));
+
+        let differing = warped.bands[0].iter().zip(&exact.bands[0]).filter(|(a, b)| a != b).count();
+        assert!(differing * 100 < warped.bands[0].len());
+
+        // Sample the center and compare with the source column it maps to
+        let mut wgs84 = Crs::wgs84();
+        let (lon, lat) = (9.0, 48.0);
+        let (x, _) = wgs84.transform_point(lon, lat, &Crs::utm(32, true)).unwrap();
+        let gt = warped.metadata.geo_transform.unwrap();
+        let col = ((lon - gt[0]) / gt[1]) as u32;
+        let row = ((lat - gt[3]) / gt[5]) as u32;
+        let value = warped.get_pixel(0, col, row).unwrap();
+        assert!((value - ((x - 499_500.0) / 10.0).floor() as f32).abs() <= 1.0);
+    }
+}
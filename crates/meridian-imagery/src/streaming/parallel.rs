@@ -1,8 +1,10 @@
 //! Parallel processing for multi-core systems
 
+use crate::dataset::{DiskBlockStore, RasterDataset};
 use crate::error::Result;
 use crate::MultiBandImage;
 use crate::streaming::window::{Window, WindowedProcessor};
+use crate::streaming::StreamingWriter;
 use rayon::prelude::*;
 
 /// Parallel processor
@@ -72,6 +74,60 @@ impl ParallelProcessor {
         Ok(output)
     }
 
+    /// Process a dataset in parallel without loading it
+    ///
+    /// Windows are read from the dataset and processed in batches sized to
+    /// the thread pool, then written in order to a disk-backed output that
+    /// uses the dataset's block grid. When the processor keeps the band count
+    /// the output keeps the band names and nodata value.
+    pub fn process_dataset<F>(
+        &self,
+        dataset: &RasterDataset,
+        processor: F,
+    ) -> Result<RasterDataset>
+    where
+        F: Fn(&MultiBandImage, &Window) -> Result<MultiBandImage> + Sync + Send,
+    {
+        let (width, height) = dataset.dimensions();
+        let windows = self.window_processor.generate_windows(width, height);
+        let batch = rayon::current_num_threads().max(1) * 2;
+        let mut output: Option<DiskBlockStore> = None;
+
+        for chunk in windows.chunks(batch) {
+            let results: Vec<(Window, MultiBandImage)> = chunk
+                .par_iter()
+                .map(|window| {
+                    let window_image = dataset.read_image(window)?;
+                    let processed = processor(&window_image, window)?;
+                    Ok::<_, crate::error::ImageryError>((*window, processed))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            for (window, window_data) in results {
+                let store = match &mut output {
+                    Some(store) => store,
+                    None => {
+                        let bands = window_data.bands.len() as u32;
+                        let mut layout = dataset.layout().derive(bands, window_data.data_type);
+                        if bands as usize == dataset.band_count() {
+                            layout.metadata.no_data = dataset.metadata().no_data;
+                            layout.metadata.band_names = dataset.metadata().band_names.clone();
+                        }
+                        output.insert(DiskBlockStore::create(layout)?)
+                    }
+                };
+                store.write_window(&window, &window_data)?;
+            }
+        }
+
+        let mut store = match output {
+            Some(store) => store,
+            None => DiskBlockStore::create(dataset.layout().clone())?,
+        };
+        store.finalize()?;
+        Ok(store.into_dataset())
+    }
+
     /// Process each band in parallel
     pub fn process_bands<F>(
         image: &MultiBandImage,
@@ -227,4 +283,37 @@ mod tests {
         let output = result.unwrap();
         assert_eq!(output.bands[0][0], 200.0);
     }
+
+    #[test]
+    fn test_parallel_dataset_processing() {
+        let metadata = ImageMetadata {
+            width: 50,
+            height: 30,
+            bands: 2,
+            bits_per_sample: 16,
+            geo_transform: None,
+            crs: None,
+            no_data: None,
+            band_names: vec!["A".to_string(), "B".to_string()],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        image.bands[0] = (0..1500).map(|v| v as f32).collect();
+        image.bands[1].fill(3.0);
+        let dataset = RasterDataset::from_image(&image, 16, 16).unwrap();
+
+        let sums = ParallelProcessor::new(20, 12)
+            .with_overlap(4)
+            .process_dataset(&dataset, |block, _| {
+                let mut out = MultiBandImage::new(block.metadata.clone(), DataType::Float32);
+                out.bands = vec![block.bands[0].iter().zip(&block.bands[1]).map(|(a, b)| a + b).collect()];
+                Ok(out)
+            })
+            .unwrap();
+
+        assert_eq!(sums.band_count(), 1);
+        assert_eq!(sums.layout().data_type, DataType::Float32);
+        let result = sums.to_image().unwrap();
+        assert_eq!(result.bands[0][0], 3.0);
+        assert_eq!(result.bands[0][1499], 1502.0);
+    }
 }
@@ -1,6 +1,7 @@
 //! Windowed processing for large images
 
 use crate::error::{ImageryError, Result};
+use crate::streaming::{StreamingReader, StreamingWriter};
 use crate::MultiBandImage;
 
 /// Window definition
@@ -130,6 +131,30 @@ impl WindowedProcessor {
         Ok(output)
     }
 
+    /// Process a streaming source window by window into a streaming sink
+    ///
+    /// Only one window is held in memory at a time. The writer is finalized
+    /// once every window has been written.
+    pub fn process_stream<R, W, F>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        mut processor: F,
+    ) -> Result<()>
+    where
+        R: StreamingReader + ?Sized,
+        W: StreamingWriter + ?Sized,
+        F: FnMut(&MultiBandImage, &Window) -> Result<MultiBandImage>,
+    {
+        let (width, height) = reader.dimensions();
+        for window in self.generate_windows(width, height) {
+            let window_image = reader.read_window(&window)?;
+            let processed = processor(&window_image, &window)?;
+            writer.write_window(&window, &processed)?;
+        }
+        writer.finalize()
+    }
+
     /// Extract a window from an image
     fn extract_window(
         &self,
@@ -226,6 +251,41 @@ mod tests {
         assert_eq!(overlap.height, 50);
     }
 
+    #[test]
+    fn test_process_stream() {
+        use crate::dataset::{DiskBlockStore, RasterDataset};
+        use crate::streaming::StreamingReader;
+        use crate::{DataType, ImageMetadata};
+
+        let metadata = ImageMetadata {
+            width: 9,
+            height: 6,
+            bands: 1,
+            bits_per_sample: 8,
+            geo_transform: None,
+            crs: None,
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt8);
+        image.bands[0] = (0..54).map(|v| v as f32).collect();
+        let mut dataset = RasterDataset::from_image(&image, 4, 4).unwrap();
+        let mut store = DiskBlockStore::create(dataset.layout().clone()).unwrap();
+
+        WindowedProcessor::new(5, 5)
+            .process_stream(&mut dataset, &mut store, |block, _| {
+                let mut out = block.clone();
+                out.bands[0].iter_mut().for_each(|v| *v += 1.0);
+                Ok(out)
+            })
+            .unwrap();
+
+        let mut output = store.into_dataset();
+        let result = output.read_window(&Window::new(0, 0, 9, 6)).unwrap();
+        assert_eq!(result.bands[0][0], 1.0);
+        assert_eq!(result.bands[0][53], 54.0);
+    }
+
     #[test]
     fn test_window_generation() {
         let processor = WindowedProcessor::new(512, 512);
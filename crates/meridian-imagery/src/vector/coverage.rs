@@ -0,0 +1,364 @@
+//! Vector geometries in pixel space
+//!
+//! Features are converted once into the pixel coordinates of a raster, where
+//! a pixel is the unit square `[x, x + 1) x [y, y + 1)`. Exact coverage
+//! fractions come from clipping every polygon ring against each pixel, while
+//! burning uses pixel centers and a grid traversal of line segments.
+
+use crate::error::{ImageryError, Result};
+use crate::georef::{apply_geo_transform, invert_geo_transform};
+use crate::streaming::Window;
+use meridian_core::crs::Crs;
+use meridian_core::geometry::{Geometry, DEFAULT_SEGMENTS_PER_QUADRANT};
+use meridian_core::geo_types::LineString as GeoLineString;
+use meridian_core::layer::Layer;
+use std::borrow::Cow;
+
+/// Coverage below this is treated as rounding noise
+const COVERAGE_EPSILON: f64 = 1e-12;
+
+/// Polygon with its rings in pixel coordinates, without closing points
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PixelPolygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
+}
+
+/// Any geometry split into its areal, linear and point parts in pixel space
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PixelGeometry {
+    pub polygons: Vec<PixelPolygon>,
+    pub lines: Vec<Vec<(f64, f64)>>,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl PixelGeometry {
+    /// Convert a geometry with the inverse geotransform of a raster
+    pub fn from_geometry(geometry: &Geometry, inverse: &[f64; 6]) -> Self {
+        let mut pixels = Self::default();
+        pixels.add(geometry, inverse);
+        pixels
+    }
+
+    fn add(&mut self, geometry: &Geometry, inverse: &[f64; 6]) {
+        let ring = |line: &GeoLineString<f64>| {
+            let mut ring: Vec<_> = line.coords().map(|c| apply_geo_transform(inverse, c.x, c.y)).collect();
+            if ring.len() > 1 && ring.first() == ring.last() {
+                ring.pop();
+            }
+            ring
+        };
+        let polygon = |polygon: &meridian_core::geo_types::Polygon<f64>| PixelPolygon {
+            exterior: ring(polygon.exterior()),
+            holes: polygon.interiors().iter().map(ring).collect(),
+        };
+        let line = |line: &GeoLineString<f64>| -> Vec<(f64, f64)> {
+            line.coords().map(|c| apply_geo_transform(inverse, c.x, c.y)).collect()
+        };
+
+        match geometry {
+            Geometry::Point(g) => self.points.push(apply_geo_transform(inverse, g.geom.x(), g.geom.y())),
+            Geometry::MultiPoint(g) => self
+                .points
+                .extend(g.geom.iter().map(|p| apply_geo_transform(inverse, p.x(), p.y()))),
+            Geometry::LineString(g) => self.lines.push(line(&g.geom)),
+            Geometry::MultiLineString(g) => self.lines.extend(g.geom.iter().map(line)),
+            Geometry::Polygon(g) => self.polygons.push(polygon(&g.geom)),
+            Geometry::MultiPolygon(g) => self.polygons.extend(g.geom.iter().map(polygon)),
+            Geometry::GeometryCollection(g) => {
+                for member in g.iter() {
+                    self.add(member, inverse);
+                }
+            }
+            Geometry::CircularString(g) => self.lines.push(line(&g.linearize(DEFAULT_SEGMENTS_PER_QUADRANT).geom)),
+            Geometry::CompoundCurve(g) => self.lines.push(line(&g.linearize(DEFAULT_SEGMENTS_PER_QUADRANT).geom)),
+        }
+    }
+
+    /// Pixel window touched by the geometry, clipped to the raster
+    pub fn window(&self, width: u32, height: u32) -> Option<Window> {
+        let vertices = self
+            .polygons
+            .iter()
+            .flat_map(|polygon| polygon.exterior.iter())
+            .chain(self.lines.iter().flatten())
+            .chain(&self.points);
+
+        let mut bounds = [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY];
+        for &(x, y) in vertices {
+            bounds = [bounds[0].min(x), bounds[1].min(y), bounds[2].max(x), bounds[3].max(y)];
+        }
+        if !bounds.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+
+        let x0 = bounds[0].floor().max(0.0);
+        let y0 = bounds[1].floor().max(0.0);
+        let x1 = (bounds[2].floor() + 1.0).min(width as f64);
+        let y1 = (bounds[3].floor() + 1.0).min(height as f64);
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some(Window::new(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32))
+    }
+
+    /// Fraction of each pixel of `window` covered by the geometry
+    ///
+    /// Polygons contribute the exact area they cover, holes excluded. A point
+    /// covers the pixel it falls in. Lines have no area and cover nothing.
+    pub fn coverage(&self, window: &Window) -> Vec<f64> {
+        let mut coverage = vec![0.0; window.area()];
+        for polygon in &self.polygons {
+            add_ring_coverage(&mut coverage, window, &polygon.exterior, 1.0);
+            for hole in &polygon.holes {
+                add_ring_coverage(&mut coverage, window, hole, -1.0);
+            }
+        }
+        for value in coverage.iter_mut() {
+            *value = if *value < COVERAGE_EPSILON { 0.0 } else { value.min(1.0) };
+        }
+        for &point in &self.points {
+            if let Some(index) = window_index(window, point.0.floor(), point.1.floor()) {
+                coverage[index] = 1.0;
+            }
+        }
+        coverage
+    }
+
+    /// Pixels of `window` the geometry burns into
+    ///
+    /// Polygons burn the pixels whose centers they contain, or with
+    /// `all_touched` also every pixel their boundary passes through. Lines
+    /// always burn every pixel they pass through and points their own pixel.
+    pub fn burn_mask(&self, window: &Window, all_touched: bool) -> Vec<bool> {
+        let mut mask = vec![false; window.area()];
+        let mut burn_segment = |a: (f64, f64), b: (f64, f64)| {
+            traverse_segment(a, b, |x, y| {
+                if let Some(index) = window_index(window, x as f64, y as f64) {
+                    mask[index] = true;
+                }
+            })
+        };
+
+        if all_touched {
+            for ring in self.polygons.iter().flat_map(|p| std::iter::once(&p.exterior).chain(&p.holes)) {
+                for (i, &a) in ring.iter().enumerate() {
+                    burn_segment(a, ring[(i + 1) % ring.len()]);
+                }
+            }
+        }
+        for line in &self.lines {
+            for pair in line.windows(2) {
+                burn_segment(pair[0], pair[1]);
+            }
+        }
+        for &point in &self.points {
+            if let Some(index) = window_index(window, point.0.floor(), point.1.floor()) {
+                mask[index] = true;
+            }
+        }
+
+        for polygon in &self.polygons {
+            let rings: Vec<&Vec<(f64, f64)>> = std::iter::once(&polygon.exterior).chain(&polygon.holes).collect();
+            for row in 0..window.height {
+                let center_y = (window.y + row) as f64 + 0.5;
+                for (start, end) in scanline_spans(&rings, center_y) {
+                    // Pixels whose centers fall in [start, end)
+                    let first = (start - 0.5).ceil().max(window.x as f64);
+                    let last = (end - 0.5).ceil().min((window.x + window.width) as f64);
+                    let mut x = first;
+                    while x < last {
+                        mask[row as usize * window.width as usize + (x as u32 - window.x) as usize] = true;
+                        x += 1.0;
+                    }
+                }
+            }
+        }
+        mask
+    }
+}
+
+/// Features of a layer in the pixel space of a raster
+///
+/// The layer is reprojected first when `crs` is given and differs from the
+/// layer CRS.
+pub(crate) fn layer_to_pixels(layer: &Layer, geo_transform: &[f64; 6], crs: Option<&Crs>) -> Result<Vec<PixelGeometry>> {
+    let inverse = invert_geo_transform(geo_transform)
+        .ok_or_else(|| ImageryError::metadata("Raster geotransform is not invertible"))?;
+
+    let layer = match crs {
+        Some(crs) if *crs != layer.crs => Cow::Owned(layer.transform(crs).map_err(|e| {
+            ImageryError::Projection(format!("Cannot reproject layer '{}' to {}: {}", layer.name, crs, e))
+        })?),
+        _ => Cow::Borrowed(layer),
+    };
+
+    Ok(layer
+        .iter()
+        .map(|feature| PixelGeometry::from_geometry(&feature.geometry, &inverse))
+        .collect())
+}
+
+/// Signed area of a ring, positive when clockwise with y pointing down
+pub(crate) fn ring_area(ring: &[(f64, f64)]) -> f64 {
+    let mut twice_area = 0.0;
+    for (i, &(x0, y0)) in ring.iter().enumerate() {
+        let (x1, y1) = ring[(i + 1) % ring.len()];
+        twice_area += x0 * y1 - x1 * y0;
+    }
+    twice_area / 2.0
+}
+
+/// Add the area of a ring inside each pixel of the window, times `sign`
+fn add_ring_coverage(coverage: &mut [f64], window: &Window, ring: &[(f64, f64)], sign: f64) {
+    if ring.len() < 3 {
+        return;
+    }
+    for row in 0..window.height {
+        let top = (window.y + row) as f64;
+        let strip = clip_slab(ring, 1, top, top + 1.0);
+        if strip.len() < 3 {
+            continue;
+        }
+
+        let (min_x, max_x) = strip
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.0), hi.max(p.0)));
+        let first = min_x.floor().max(window.x as f64) as u32;
+        let last = (max_x.ceil() as i64).min((window.x + window.width) as i64);
+        for col in first as i64..last {
+            let left = col as f64;
+            let cell = clip_slab(&strip, 0, left, left + 1.0);
+            if cell.len() >= 3 {
+                let index = row as usize * window.width as usize + (col as u32 - window.x) as usize;
+                coverage[index] += sign * ring_area(&cell).abs();
+            }
+        }
+    }
+}
+
+/// Clip a ring to `lo <= coordinate <= hi` along one axis
+///
+/// Sutherland-Hodgman against a convex slab keeps the area of concave rings
+/// exact, even where the output gains degenerate edges.
+fn clip_slab(ring: &[(f64, f64)], axis: usize, lo: f64, hi: f64) -> Vec<(f64, f64)> {
+    let lower = clip_half_plane(ring, axis, lo, true);
+    clip_half_plane(&lower, axis, hi, false)
+}
+
+fn clip_half_plane(ring: &[(f64, f64)], axis: usize, bound: f64, keep_above: bool) -> Vec<(f64, f64)> {
+    let coord = |p: (f64, f64)| if axis == 0 { p.0 } else { p.1 };
+    let inside = |p: (f64, f64)| if keep_above { coord(p) >= bound } else { coord(p) <= bound };
+    let crossing = |a: (f64, f64), b: (f64, f64)| {
+        let t = (bound - coord(a)) / (coord(b) - coord(a));
+        let x = a.0 + t * (b.0 - a.0);
+        let y = a.1 + t * (b.1 - a.1);
+        if axis == 0 { (bound, y) } else { (x, bound) }
+    };
+
+    let mut clipped = Vec::with_capacity(ring.len() + 2);
+    for (i, &current) in ring.iter().enumerate() {
+        let previous = ring[(i + ring.len() - 1) % ring.len()];
+        match (inside(previous), inside(current)) {
+            (true, true) => clipped.push(current),
+            (true, false) => clipped.push(crossing(previous, current)),
+            (false, true) => {
+                clipped.push(crossing(previous, current));
+                clipped.push(current);
+            }
+            (false, false) => {}
+        }
+    }
+    clipped
+}
+
+/// Even-odd spans of a horizontal line through a set of rings
+fn scanline_spans(rings: &[&Vec<(f64, f64)>], y: f64) -> Vec<(f64, f64)> {
+    let mut crossings = Vec::new();
+    for ring in rings {
+        for (i, &a) in ring.iter().enumerate() {
+            let b = ring[(i + 1) % ring.len()];
+            if (a.1 <= y) != (b.1 <= y) {
+                crossings.push(a.0 + (y - a.1) * (b.0 - a.0) / (b.1 - a.1));
+            }
+        }
+    }
+    crossings.sort_by(|a, b| a.total_cmp(b));
+    crossings.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// Visit every pixel a segment passes through
+fn traverse_segment(a: (f64, f64), b: (f64, f64), mut visit: impl FnMut(i64, i64)) {
+    let (mut x, mut y) = (a.0.floor() as i64, a.1.floor() as i64);
+    let (end_x, end_y) = (b.0.floor() as i64, b.1.floor() as i64);
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let step_x = if dx > 0.0 { 1 } else { -1 };
+    let step_y = if dy > 0.0 { 1 } else { -1 };
+
+    // Distance along the segment, as a fraction, to the next vertical and horizontal pixel edge
+    let next_edge = |start: f64, cell: i64, delta: f64, step: i64| {
+        if delta == 0.0 {
+            f64::INFINITY
+        } else {
+            let edge = if step > 0 { (cell + 1) as f64 } else { cell as f64 };
+            (edge - start) / delta
+        }
+    };
+    let mut t_x = next_edge(a.0, x, dx, step_x);
+    let mut t_y = next_edge(a.1, y, dy, step_y);
+    let t_delta_x = if dx == 0.0 { f64::INFINITY } else { 1.0 / dx.abs() };
+    let t_delta_y = if dy == 0.0 { f64::INFINITY } else { 1.0 / dy.abs() };
+
+    let steps = (end_x - x).abs() + (end_y - y).abs();
+    visit(x, y);
+    for _ in 0..steps {
+        if t_x < t_y {
+            x += step_x;
+            t_x += t_delta_x;
+        } else {
+            y += step_y;
+            t_y += t_delta_y;
+        }
+        visit(x, y);
+    }
+}
+
+/// Index of a pixel inside a window
+fn window_index(window: &Window, x: f64, y: f64) -> Option<usize> {
+    let (col, row) = (x - window.x as f64, y - window.y as f64);
+    if col < 0.0 || row < 0.0 || col >= window.width as f64 || row >= window.height as f64 {
+        return None;
+    }
+    Some(row as usize * window.width as usize + col as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_coverage_with_hole() {
+        // A 3x3 pixel square offset by half a pixel, with a 1x1 hole in the middle
+        let geometry = PixelGeometry {
+            polygons: vec![PixelPolygon {
+                exterior: vec![(0.5, 0.5), (3.5, 0.5), (3.5, 3.5), (0.5, 3.5)],
+                holes: vec![vec![(1.5, 1.5), (2.5, 1.5), (2.5, 2.5), (1.5, 2.5)]],
+            }],
+            ..Default::default()
+        };
+        let window = geometry.window(10, 10).unwrap();
+        assert_eq!((window.x, window.y, window.width, window.height), (0, 0, 4, 4));
+
+        let coverage = geometry.coverage(&window);
+        assert_eq!(coverage[0], 0.25);
+        assert_eq!(coverage[1], 0.5);
+        assert_eq!(coverage[5], 0.75);
+        assert!((coverage.iter().sum::<f64>() - 8.0).abs() < 1e-9);
+
+        let burned = geometry.burn_mask(&window, false);
+        assert_eq!(burned.iter().filter(|&&b| b).count(), 8);
+        assert!(burned[0] && !burned[5] && !burned[15]);
+        let touched = geometry.burn_mask(&window, true);
+        assert_eq!(touched.iter().filter(|&&b| b).count(), 16);
+    }
+}
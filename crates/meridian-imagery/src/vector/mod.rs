@@ -0,0 +1,14 @@
+//! Raster and vector integration
+//!
+//! Zonal statistics of raster bands under vector features, rasterization of
+//! feature layers onto a pixel grid, and polygonization of classified rasters
+//! back into features.
+
+mod coverage;
+pub mod zonal;
+pub mod rasterize;
+pub mod polygonize;
+
+pub use zonal::{ZonalStatistics, ZoneStatistics};
+pub use rasterize::{BurnValue, MergeAlgorithm, Rasterizer};
+pub use polygonize::Polygonizer;
@@ -0,0 +1,457 @@
+//! Polygonization of classified rasters
+//!
+//! Pixels are grouped into 4-connected regions of equal value, and the
+//! pixel edges on the boundary of each region are chained into rings. Rings
+//! follow pixel edges exactly, with collinear vertices removed.
+
+use super::coverage::ring_area;
+use crate::classification::ClassificationResult;
+use crate::error::{ImageryError, Result};
+use crate::georef::{apply_geo_transform, source_reference};
+use crate::{ImageMetadata, MultiBandImage};
+use meridian_core::crs::Crs;
+use meridian_core::feature::Feature;
+use meridian_core::geo_types::{Coord, LineString, Polygon as GeoPolygon};
+use meridian_core::geometry::{Geometry, MultiPolygon, Polygon};
+use meridian_core::layer::Layer;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+
+type Vertex = (i64, i64);
+type Ring = Vec<(f64, f64)>;
+
+/// Converts regions of equal pixel value into polygon features
+#[derive(Debug, Clone)]
+pub struct Polygonizer {
+    field: String,
+}
+
+impl Polygonizer {
+    /// Create a polygonizer writing region values to a `value` property
+    pub fn new() -> Self {
+        Self {
+            field: "value".to_string(),
+        }
+    }
+
+    /// Set the property that receives the region value
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = field.into();
+        self
+    }
+
+    /// Polygonize one band of an image
+    ///
+    /// Nodata and NaN pixels do not form regions. The image needs a
+    /// geotransform and CRS, which the features are created in.
+    pub fn polygonize(&self, image: &MultiBandImage, band: usize) -> Result<Layer> {
+        let values = image.bands.get(band).ok_or(ImageryError::InvalidBand {
+            band,
+            total: image.bands.len(),
+        })?;
+        let no_data = image.metadata.no_data.map(|v| v as f32);
+        let keys: Vec<Option<f64>> = values
+            .iter()
+            .map(|&v| (!v.is_nan() && Some(v) != no_data).then_some(v as f64))
+            .collect();
+
+        self.build_layer(&keys, &image.metadata, |value| json!(as_number(value)), None)
+    }
+
+    /// Polygonize the labels of a classification
+    ///
+    /// `metadata` is the georeferencing of the classified image. Labels outside
+    /// the class list, such as [`UNCLASSIFIED`](crate::classification::UNCLASSIFIED),
+    /// do not form regions. Features also get a `class` property with the class name.
+    pub fn polygonize_classification(&self, result: &ClassificationResult, metadata: &ImageMetadata) -> Result<Layer> {
+        if result.width != metadata.width || result.height != metadata.height {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Classification is {}x{} but the image is {}x{}",
+                result.width, result.height, metadata.width, metadata.height
+            )));
+        }
+        let keys: Vec<Option<f64>> = result
+            .labels
+            .iter()
+            .map(|&label| ((label as usize) < result.class_names.len()).then_some(label as f64))
+            .collect();
+
+        let class_name = |value: f64| json!(result.class_names[value as usize]);
+        self.build_layer(&keys, metadata, |value| json!(value as u32), Some(&class_name))
+    }
+
+    fn build_layer(
+        &self,
+        keys: &[Option<f64>],
+        metadata: &ImageMetadata,
+        value_json: impl Fn(f64) -> Value,
+        class_json: Option<&dyn Fn(f64) -> Value>,
+    ) -> Result<Layer> {
+        let (crs, gt) = source_reference(metadata)?;
+        let (width, height) = (metadata.width as usize, metadata.height as usize);
+        if keys.len() != width * height {
+            return Err(ImageryError::InvalidDimensions(format!(
+                "Expected {} pixels, got {}",
+                width * height,
+                keys.len()
+            )));
+        }
+
+        let (regions, region_values) = label_regions(keys, width, height);
+        let rings = trace_rings(&regions, region_values.len(), width, height);
+
+        let mut layer = Layer::with_capacity("polygons", crs.clone(), region_values.len());
+        for (value, rings) in region_values.into_iter().zip(rings) {
+            let Some(geometry) = assemble(rings, &gt, &crs) else {
+                continue;
+            };
+            let mut feature = Feature::new(geometry);
+            feature.set_property(self.field.clone(), value_json(value));
+            if let Some(class_json) = class_json {
+                feature.set_property("class", class_json(value));
+            }
+            layer.add_feature(feature);
+        }
+
+        log::info!("Polygonized {} regions", layer.len());
+        Ok(layer)
+    }
+}
+
+impl Default for Polygonizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Integral values as integers so they serialize without a fraction
+fn as_number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+/// Label 4-connected regions of equal value, returning the region of each pixel and each region's value
+fn label_regions(keys: &[Option<f64>], width: usize, height: usize) -> (Vec<Option<usize>>, Vec<f64>) {
+    let mut regions = vec![None; keys.len()];
+    let mut values = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for start in 0..keys.len() {
+        let Some(value) = keys[start] else { continue };
+        if regions[start].is_some() {
+            continue;
+        }
+        let region = values.len();
+        values.push(value);
+        regions[start] = Some(region);
+        queue.push_back(start);
+
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = (index % width, index / width);
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if regions[neighbor].is_none() && keys[neighbor] == Some(value) {
+                    regions[neighbor] = Some(region);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    (regions, values)
+}
+
+/// Boundary rings of every region in pixel corner coordinates
+///
+/// Edges keep their region on the right with y pointing down, so outer rings
+/// come out clockwise and holes counter-clockwise on screen.
+fn trace_rings(regions: &[Option<usize>], count: usize, width: usize, height: usize) -> Vec<Vec<Vec<Vertex>>> {
+    let mut edges: Vec<Vec<(Vertex, Vertex)>> = vec![Vec::new(); count];
+    let region_at = |x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            None
+        } else {
+            regions[y as usize * width + x as usize]
+        }
+    };
+
+    for (index, region) in regions.iter().enumerate() {
+        let Some(region) = *region else { continue };
+        let (x, y) = ((index % width) as i64, (index / width) as i64);
+        let sides = [
+            ((x, y - 1), (x, y), (x + 1, y)),
+            ((x + 1, y), (x + 1, y), (x + 1, y + 1)),
+            ((x, y + 1), (x + 1, y + 1), (x, y + 1)),
+            ((x - 1, y), (x, y + 1), (x, y)),
+        ];
+        for ((nx, ny), from, to) in sides {
+            if region_at(nx, ny) != Some(region) {
+                edges[region].push((from, to));
+            }
+        }
+    }
+
+    edges.into_iter().map(chain_edges).collect()
+}
+
+/// Chain directed edges into closed rings
+///
+/// Where a region touches itself diagonally a vertex has two outgoing edges.
+/// Taking the left turn there keeps the region connected across the corner.
+fn chain_edges(edges: Vec<(Vertex, Vertex)>) -> Vec<Vec<Vertex>> {
+    let mut outgoing: HashMap<Vertex, Vec<usize>> = HashMap::new();
+    for (index, (from, _)) in edges.iter().enumerate() {
+        outgoing.entry(*from).or_default().push(index);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut rings = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let start = edges[first].0;
+        let mut ring = vec![start];
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (from, to) = edges[current];
+            if to == start {
+                break;
+            }
+            ring.push(to);
+
+            let heading = (to.0 - from.0, to.1 - from.1);
+            let left = (heading.1, -heading.0);
+            let candidates: Vec<usize> = outgoing[&to].iter().copied().filter(|&e| !used[e]).collect();
+            let Some(&next) = candidates
+                .iter()
+                .find(|&&e| (edges[e].1 .0 - to.0, edges[e].1 .1 - to.1) == left)
+                .or(candidates.first())
+            else {
+                break;
+            };
+            current = next;
+        }
+        rings.push(remove_collinear(ring));
+    }
+    rings
+}
+
+/// Drop vertices in the middle of straight runs
+fn remove_collinear(ring: Vec<Vertex>) -> Vec<Vertex> {
+    let n = ring.len();
+    let direction = |a: Vertex, b: Vertex| ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+    (0..n)
+        .filter(|&i| {
+            let (prev, here, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            direction(prev, here) != direction(here, next)
+        })
+        .map(|i| ring[i])
+        .collect()
+}
+
+/// Build a polygon from the rings of one region, attaching holes to the outer ring around them
+fn assemble(rings: Vec<Vec<Vertex>>, gt: &[f64; 6], crs: &Crs) -> Option<Geometry> {
+    let to_pixels = |ring: &[Vertex]| ring.iter().map(|&(x, y)| (x as f64, y as f64)).collect::<Vec<_>>();
+    let (outers, holes): (Vec<_>, Vec<_>) = rings
+        .iter()
+        .map(|ring| to_pixels(ring))
+        .filter(|ring| ring.len() >= 4)
+        .partition(|ring| ring_area(ring) > 0.0);
+
+    let mut polygons: Vec<(Ring, Vec<Ring>)> = outers.into_iter().map(|outer| (outer, Vec::new())).collect();
+    for hole in holes {
+        // The pixel left of the first hole edge lies inside the hole
+        let (a, b) = (hole[0], hole[1]);
+        // Edges are axis-aligned, and `f64::signum` would turn the zero component into 1
+        let unit = |d: f64| if d == 0.0 { 0.0 } else { d.signum() };
+        let step = (unit(b.0 - a.0), unit(b.1 - a.1));
+        let probe = (a.0 + 0.5 * step.0 + 0.5 * step.1, a.1 + 0.5 * step.1 - 0.5 * step.0);
+        let owner = polygons
+            .iter_mut()
+            .filter(|(outer, _)| contains(outer, probe))
+            .min_by(|a, b| ring_area(&a.0).total_cmp(&ring_area(&b.0)));
+        if let Some((_, owned)) = owner {
+            owned.push(hole);
+        }
+    }
+
+    let to_world = |ring: &[(f64, f64)]| {
+        let mut coords: Vec<Coord<f64>> = ring
+            .iter()
+            .map(|&(px, py)| {
+                let (x, y) = apply_geo_transform(gt, px, py);
+                Coord { x, y }
+            })
+            .collect();
+        coords.push(coords[0]);
+        LineString::new(coords)
+    };
+    let mut polygons: Vec<GeoPolygon<f64>> = polygons
+        .iter()
+        .map(|(outer, holes)| GeoPolygon::new(to_world(outer), holes.iter().map(|h| to_world(h)).collect()))
+        .collect();
+
+    match polygons.len() {
+        0 => None,
+        1 => {
+            let polygon = polygons.remove(0);
+            let (exterior, interiors) = polygon.into_inner();
+            Some(Geometry::Polygon(Polygon::new(exterior, interiors, crs.clone())))
+        }
+        _ => Some(Geometry::MultiPolygon(MultiPolygon::new(polygons, crs.clone()))),
+    }
+}
+
+/// Even-odd point in ring test
+fn contains(ring: &[(f64, f64)], point: (f64, f64)) -> bool {
+    let mut inside = false;
+    for (i, &a) in ring.iter().enumerate() {
+        let b = ring[(i + 1) % ring.len()];
+        if (a.1 > point.1) != (b.1 > point.1) && point.0 < a.0 + (point.1 - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classification::{KMeans, UnsupervisedClassifier};
+    use crate::DataType;
+
+    #[test]
+    fn test_polygonize_classified_ring_with_hole() {
+        // A 5x5 frame of 200 around a 3x3 block of 10 with a 200 pixel at its center
+        let metadata = ImageMetadata {
+            width: 5,
+            height: 5,
+            bands: 1,
+            bits_per_sample: 8,
+            geo_transform: Some([300_000.0, 30.0, 0.0, 5_000_150.0, 0.0, -30.0]),
+            crs: Some("EPSG:32610".to_string()),
+            no_data: None,
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata.clone(), DataType::UInt8);
+        image.bands[0] = (0..25)
+            .map(|i| {
+                let (x, y) = (i % 5, i / 5);
+                let inner = (1..4).contains(&x) && (1..4).contains(&y) && (x, y) != (2, 2);
+                if inner { 10.0 } else { 200.0 }
+            })
+            .collect();
+
+        let classes = KMeans::new().classify(&image, 2).unwrap();
+        let layer = Polygonizer::new()
+            .with_field("label")
+            .polygonize_classification(&classes, &metadata)
+            .unwrap();
+
+        // The outer frame, the inner ring with a hole, and the center pixel
+        assert_eq!(layer.len(), 3);
+        let areas: Vec<(f64, usize)> = layer
+            .iter()
+            .map(|feature| match &feature.geometry {
+                Geometry::Polygon(p) => (p.area(), p.interiors().len()),
+                other => panic!("unexpected geometry {:?}", other),
+            })
+            .collect();
+        assert!(areas.contains(&(16.0 * 900.0, 1)));
+        assert!(areas.contains(&(8.0 * 900.0, 1)));
+        assert!(areas.contains(&(900.0, 0)));
+
+        let by_area = |area: f64| {
+            layer
+                .iter()
+                .find(|f| matches!(&f.geometry, Geometry::Polygon(p) if p.area() == area))
+                .unwrap()
+        };
+        let Geometry::Polygon(inner) = &by_area(7200.0).geometry else { unreachable!() };
+        assert_eq!(inner.exterior().0.len(), 5);
+        assert_eq!(inner.exterior().0[0], Coord { x: 300_030.0, y: 5_000_120.0 });
+
+        let label = |area: f64| by_area(area).get_property("label").cloned();
+        assert_eq!(label(900.0), label(14_400.0));
+        assert_ne!(label(900.0), label(7200.0));
+        assert!(by_area(900.0).get_property("class").is_some());
+    }
+
+    fn utm_metadata(width: u32, height: u32) -> ImageMetadata {
+        ImageMetadata {
+            width,
+            height,
+            bands: 1,
+            bits_per_sample: 32,
+            geo_transform: Some([500_000.0, 10.0, 0.0, 4_000_000.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: Some(0.0),
+            band_names: vec![],
+        }
+    }
+
+    #[test]
+    fn test_polygonize_band_skips_nodata() {
+        // Diagonal neighbours are separate regions; nodata and NaN form none
+        let mut image = MultiBandImage::new(utm_metadata(3, 3), DataType::Float32);
+        image.bands[0] = vec![1.0, 0.0, f32::NAN, 0.0, 1.0, 0.0, 0.0, 0.0, 2.5];
+
+        let layer = Polygonizer::new().polygonize(&image, 0).unwrap();
+        assert_eq!(layer.len(), 3);
+        let mut values: Vec<Value> = layer.iter().map(|f| f.get_property("value").cloned().unwrap()).collect();
+        values.sort_by(|a, b| a.as_f64().unwrap().total_cmp(&b.as_f64().unwrap()));
+        assert_eq!(values, vec![json!(1), json!(1), json!(2.5)]);
+        assert!(layer
+            .iter()
+            .all(|f| matches!(&f.geometry, Geometry::Polygon(p) if p.area() == 100.0)));
+        assert_eq!(layer.crs, Crs::utm(33, true));
+    }
+
+    #[test]
+    fn test_polygonize_region_touching_itself() {
+        // The region meets itself at the corner shared by pixels (1, 2) and (2, 1)
+        let mut image = MultiBandImage::new(utm_metadata(3, 3), DataType::Float32);
+        image.bands[0] = vec![7.0, 7.0, 7.0, 7.0, 0.0, 7.0, 7.0, 7.0, 0.0];
+
+        let layer = Polygonizer::new().polygonize(&image, 0).unwrap();
+        assert_eq!(layer.len(), 1);
+        let feature = layer.iter().next().unwrap();
+        let area = match &feature.geometry {
+            Geometry::Polygon(p) => p.area(),
+            Geometry::MultiPolygon(mp) => mp.area(),
+            other => panic!("unexpected geometry {:?}", other),
+        };
+        assert_eq!(area, 700.0);
+    }
+
+    #[test]
+    fn test_polygonize_errors() {
+        let image = MultiBandImage::new(utm_metadata(2, 2), DataType::Float32);
+        assert!(matches!(
+            Polygonizer::new().polygonize(&image, 1),
+            Err(ImageryError::InvalidBand { band: 1, total: 1 })
+        ));
+
+        let mut unreferenced = image.clone();
+        unreferenced.metadata.geo_transform = None;
+        assert!(matches!(
+            Polygonizer::new().polygonize(&unreferenced, 0),
+            Err(ImageryError::Metadata(_))
+        ));
+
+        let classes = KMeans::new().classify(&image, 1).unwrap();
+        assert!(matches!(
+            Polygonizer::new().polygonize_classification(&classes, &utm_metadata(3, 2)),
+            Err(ImageryError::InvalidDimensions(_))
+        ));
+    }
+}
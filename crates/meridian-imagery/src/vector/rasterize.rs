@@ -0,0 +1,290 @@
+//! Rasterization of feature layers onto a pixel grid
+
+use super::coverage::layer_to_pixels;
+use crate::error::{ImageryError, Result};
+use crate::georef::{crs_label, parse_crs};
+use crate::processing::warp::TargetGrid;
+use crate::{DataType, ImageMetadata, MultiBandImage};
+use meridian_core::crs::Crs;
+use meridian_core::feature::Feature;
+use meridian_core::layer::Layer;
+
+/// Value burned for each feature
+#[derive(Debug, Clone, PartialEq)]
+pub enum BurnValue {
+    /// The same value for every feature
+    Constant(f64),
+    /// A numeric feature property; features without it are skipped
+    Attribute(String),
+}
+
+/// How a burned value combines with what is already in the pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeAlgorithm {
+    /// Later features overwrite earlier ones
+    Replace,
+    /// Values of overlapping features are summed
+    Add,
+}
+
+/// Burns the features of a layer into a single-band raster
+///
+/// Polygons burn the pixels whose centers they contain, lines every pixel
+/// they pass through and points the pixel they fall in. Features are
+/// reprojected to the grid CRS first when the layer uses another one.
+#[derive(Debug, Clone)]
+pub struct Rasterizer {
+    grid: TargetGrid,
+    crs: Crs,
+    burn: BurnValue,
+    all_touched: bool,
+    fill: f64,
+    data_type: DataType,
+    merge: MergeAlgorithm,
+}
+
+impl Rasterizer {
+    /// Create a rasterizer for a grid in a CRS
+    pub fn new(grid: TargetGrid, crs: Crs) -> Self {
+        Self {
+            grid,
+            crs,
+            burn: BurnValue::Constant(1.0),
+            all_touched: false,
+            fill: 0.0,
+            data_type: DataType::Float32,
+            merge: MergeAlgorithm::Replace,
+        }
+    }
+
+    /// Create a rasterizer for the grid of an existing image
+    pub fn like(metadata: &ImageMetadata) -> Result<Self> {
+        let geo_transform = metadata
+            .geo_transform
+            .ok_or_else(|| ImageryError::metadata("Image has no geotransform"))?;
+        let crs = metadata
+            .crs
+            .as_deref()
+            .ok_or_else(|| ImageryError::metadata("Image has no CRS"))?;
+        let grid = TargetGrid {
+            geo_transform,
+            width: metadata.width,
+            height: metadata.height,
+        };
+        Ok(Self::new(grid, parse_crs(crs)?))
+    }
+
+    /// Burn the same value for every feature
+    pub fn with_burn_value(mut self, value: f64) -> Self {
+        self.burn = BurnValue::Constant(value);
+        self
+    }
+
+    /// Burn the value of a numeric feature property
+    pub fn with_attribute(mut self, name: impl Into<String>) -> Self {
+        self.burn = BurnValue::Attribute(name.into());
+        self
+    }
+
+    /// Also burn every pixel a polygon boundary passes through
+    pub fn with_all_touched(mut self, all_touched: bool) -> Self {
+        self.all_touched = all_touched;
+        self
+    }
+
+    /// Set the background value, which also becomes the nodata value
+    pub fn with_fill(mut self, fill: f64) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Set the output data type
+    pub fn with_data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = data_type;
+        self
+    }
+
+    /// Set how overlapping features combine
+    pub fn with_merge(mut self, merge: MergeAlgorithm) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Rasterize a layer, burning features in layer order
+    pub fn rasterize(&self, layer: &Layer) -> Result<MultiBandImage> {
+        let (width, height) = (self.grid.width, self.grid.height);
+        let metadata = ImageMetadata {
+            width,
+            height,
+            bands: 1,
+            bits_per_sample: (self.data_type.size() * 8) as u16,
+            geo_transform: Some(self.grid.geo_transform),
+            crs: Some(crs_label(&self.crs)),
+            no_data: Some(self.fill),
+            band_names: vec![match &self.burn {
+                BurnValue::Constant(_) => "burn".to_string(),
+                BurnValue::Attribute(name) => name.clone(),
+            }],
+        };
+        let mut output = MultiBandImage::new(metadata, self.data_type);
+        output.bands[0].fill(self.fill as f32);
+
+        let shapes = layer_to_pixels(layer, &self.grid.geo_transform, Some(&self.crs))?;
+        let mut skipped = 0;
+        for (feature, shape) in layer.iter().zip(&shapes) {
+            let Some(value) = self.burn_value(feature) else {
+                skipped += 1;
+                continue;
+            };
+            let Some(window) = shape.window(width, height) else {
+                continue;
+            };
+
+            let mask = shape.burn_mask(&window, self.all_touched);
+            for (index, _) in mask.iter().enumerate().filter(|(_, &burn)| burn) {
+                let x = window.x as usize + index % window.width as usize;
+                let y = window.y as usize + index / window.width as usize;
+                let pixel = &mut output.bands[0][y * width as usize + x];
+                *pixel = match self.merge {
+                    MergeAlgorithm::Replace => value as f32,
+                    MergeAlgorithm::Add if *pixel == self.fill as f32 => value as f32,
+                    MergeAlgorithm::Add => *pixel + value as f32,
+                };
+            }
+        }
+
+        if skipped > 0 {
+            log::warn!("Skipped {} features without a numeric burn value", skipped);
+        }
+        Ok(output)
+    }
+
+    fn burn_value(&self, feature: &Feature) -> Option<f64> {
+        match &self.burn {
+            BurnValue::Constant(value) => Some(*value),
+            BurnValue::Attribute(name) => feature.get_property(name).and_then(|value| value.as_f64()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meridian_core::geo_types::{coord, LineString};
+    use meridian_core::geometry::{Geometry, LineString as Line, Point, Polygon};
+    use serde_json::json;
+
+    #[test]
+    fn test_rasterize_attribute_across_crs() {
+        let grid = TargetGrid::from_bounds([0.0, 0.0, 8.0, 8.0], 1.0, 1.0, false).unwrap();
+        let wgs84 = Crs::wgs84();
+        let square = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            LineString::new(vec![
+                coord! { x: x0, y: y0 },
+                coord! { x: x1, y: y0 },
+                coord! { x: x1, y: y1 },
+                coord! { x: x0, y: y1 },
+                coord! { x: x0, y: y0 },
+            ])
+        };
+
+        let mut field = Feature::new(Geometry::Polygon(Polygon::new(square(1.0, 1.0, 4.0, 4.0), vec![], wgs84.clone())));
+        field.set_property("crop", json!(3));
+        let mut road = Feature::new(Geometry::LineString(Line::new(
+            vec![coord! { x: 0.5, y: 6.5 }, coord! { x: 7.5, y: 6.5 }],
+            wgs84.clone(),
+        )));
+        road.set_property("crop", json!(9));
+        let unlabeled = Feature::new(Geometry::Polygon(Polygon::new(square(5.0, 1.0, 7.0, 3.0), vec![], wgs84.clone())));
+        let layer = Layer::from_features("parcels", wgs84.clone(), vec![field, road, unlabeled]);
+
+        let raster = Rasterizer::new(grid, wgs84)
+            .with_attribute("crop")
+            .with_data_type(DataType::UInt8)
+            .with_fill(255.0)
+            .rasterize(&layer)
+            .unwrap();
+
+        let band = &raster.bands[0];
+        let at = |x: usize, y: usize| band[y * 8 + x];
+        // Row 0 is the top of the grid at y = 8
+        assert_eq!(at(1, 6), 3.0);
+        assert_eq!(at(3, 4), 3.0);
+        assert_eq!(at(4, 4), 255.0);
+        assert!((0..8).all(|x| at(x, 1) == 9.0));
+        assert_eq!(at(6, 6), 255.0);
+        assert_eq!(band.iter().filter(|&&v| v == 3.0).count(), 9);
+        assert_eq!(raster.metadata.no_data, Some(255.0));
+    }
+
+    #[test]
+    fn test_all_touched_and_merge() {
+        let grid = TargetGrid::from_bounds([0.0, 0.0, 8.0, 8.0], 1.0, 1.0, false).unwrap();
+        let wgs84 = Crs::wgs84();
+        let square = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            let ring = LineString::new(vec![
+                coord! { x: x0, y: y0 },
+                coord! { x: x1, y: y0 },
+                coord! { x: x1, y: y1 },
+                coord! { x: x0, y: y1 },
+                coord! { x: x0, y: y0 },
+            ]);
+            Feature::new(Geometry::Polygon(Polygon::new(ring, vec![], wgs84.clone())))
+        };
+        let count = |raster: &MultiBandImage, value: f32| raster.bands[0].iter().filter(|&&v| v == value).count();
+
+        // Only the center of pixel (2, 2) lies inside, but the square touches 3x3 pixels
+        let small = Layer::from_features("small", wgs84.clone(), vec![square(1.6, 1.6, 3.4, 3.4)]);
+        let rasterizer = Rasterizer::new(grid.clone(), wgs84.clone());
+        assert_eq!(count(&rasterizer.rasterize(&small).unwrap(), 1.0), 1);
+        assert_eq!(count(&rasterizer.with_all_touched(true).rasterize(&small).unwrap(), 1.0), 9);
+
+        let point = Feature::new(Geometry::Point(Point::new(6.5, 6.5, wgs84.clone())));
+        let overlapping = Layer::from_features(
+            "overlapping",
+            wgs84.clone(),
+            vec![square(0.0, 0.0, 4.0, 4.0), square(2.0, 2.0, 6.0, 6.0), point],
+        );
+        let replaced = Rasterizer::new(grid.clone(), wgs84.clone())
+            .with_burn_value(5.0)
+            .rasterize(&overlapping)
+            .unwrap();
+        assert_eq!(count(&replaced, 5.0), 29);
+        assert_eq!(replaced.bands[0][8 + 6], 5.0);
+
+        let added = Rasterizer::new(grid, wgs84)
+            .with_merge(MergeAlgorithm::Add)
+            .rasterize(&overlapping)
+            .unwrap();
+        assert_eq!(count(&added, 2.0), 4);
+        assert_eq!(count(&added, 1.0), 25);
+        assert_eq!(count(&added, 0.0), 35);
+    }
+
+    #[test]
+    fn test_rasterize_like_image() {
+        let metadata = ImageMetadata {
+            width: 4,
+            height: 4,
+            bands: 3,
+            bits_per_sample: 16,
+            geo_transform: Some([500_000.0, 10.0, 0.0, 4_000_040.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: None,
+            band_names: vec![],
+        };
+        let utm = Crs::utm(33, true);
+        let well = Feature::new(Geometry::Point(Point::new(500_025.0, 4_000_005.0, utm.clone())));
+        let layer = Layer::from_features("wells", utm, vec![well]);
+
+        let raster = Rasterizer::like(&metadata).unwrap().rasterize(&layer).unwrap();
+        assert_eq!((raster.metadata.width, raster.metadata.height, raster.metadata.bands), (4, 4, 1));
+        assert_eq!(raster.metadata.geo_transform, metadata.geo_transform);
+        assert_eq!(raster.metadata.crs.as_deref(), Some("EPSG:32633"));
+        assert_eq!(raster.bands[0][3 * 4 + 2], 1.0);
+        assert_eq!(raster.bands[0].iter().sum::<f32>(), 1.0);
+
+        let unreferenced = ImageMetadata { crs: None, ..metadata };
+        assert!(matches!(Rasterizer::like(&unreferenced), Err(ImageryError::Metadata(_))));
+    }
+}
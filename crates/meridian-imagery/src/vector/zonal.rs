@@ -0,0 +1,363 @@
+//! Zonal statistics of a raster band under vector features
+
+use super::coverage::{layer_to_pixels, PixelGeometry};
+use crate::dataset::RasterDataset;
+use crate::error::{ImageryError, Result};
+use crate::georef::parse_crs;
+use crate::MultiBandImage;
+use meridian_core::layer::Layer;
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Block size used when wrapping in-memory images
+const IMAGE_BLOCK_SIZE: u32 = 256;
+
+/// Statistics of the pixels under one feature
+///
+/// Every pixel is weighted by the fraction of it the feature covers, so
+/// `count` is the covered area in pixels. Nodata, NaN and masked pixels are
+/// left out entirely.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ZoneStatistics {
+    /// Sum of the coverage fractions of valid pixels
+    pub count: f64,
+    /// Coverage-weighted sum of values
+    pub sum: f64,
+    /// Coverage-weighted mean
+    pub mean: Option<f64>,
+    /// Smallest value of any covered pixel
+    pub min: Option<f64>,
+    /// Largest value of any covered pixel
+    pub max: Option<f64>,
+    /// Coverage-weighted population standard deviation
+    pub std: Option<f64>,
+    /// Requested percentiles as (percentile, value) pairs
+    pub percentiles: Vec<(f64, f64)>,
+    /// Value with the largest total coverage
+    pub majority: Option<f64>,
+    /// Total coverage of each distinct value, in ascending value order
+    ///
+    /// Only filled in when categorical output is enabled.
+    pub histogram: Vec<(f64, f64)>,
+}
+
+impl ZoneStatistics {
+    /// Reduce the (value, coverage) pairs of a zone
+    fn from_samples(mut samples: Vec<(f64, f64)>, percentiles: &[f64], categorical: bool) -> Self {
+        let count: f64 = samples.iter().map(|&(_, w)| w).sum();
+        if samples.is_empty() || count <= 0.0 {
+            return Self::default();
+        }
+
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let sum: f64 = samples.iter().map(|&(v, w)| v * w).sum();
+        let mean = sum / count;
+        let variance = samples.iter().map(|&(v, w)| w * (v - mean).powi(2)).sum::<f64>() / count;
+
+        // Samples are sorted, so equal values are adjacent
+        let mut histogram: Vec<(f64, f64)> = Vec::new();
+        for &(value, weight) in &samples {
+            match histogram.last_mut() {
+                Some((last, total)) if *last == value => *total += weight,
+                _ => histogram.push((value, weight)),
+            }
+        }
+        let majority = histogram
+            .iter()
+            .fold(None, |best: Option<(f64, f64)>, &(value, total)| match best {
+                Some((_, best_total)) if best_total >= total => best,
+                _ => Some((value, total)),
+            })
+            .map(|(value, _)| value);
+
+        let percentiles = percentiles
+            .iter()
+            .map(|&p| (p, weighted_percentile(&samples, count, p)))
+            .collect();
+
+        Self {
+            count,
+            sum,
+            mean: Some(mean),
+            min: samples.first().map(|&(v, _)| v),
+            max: samples.last().map(|&(v, _)| v),
+            std: Some(variance.sqrt()),
+            percentiles,
+            majority,
+            histogram: if categorical { histogram } else { Vec::new() },
+        }
+    }
+
+    /// Statistics as feature properties named `{prefix}{stat}`
+    pub fn to_properties(&self, prefix: &str) -> Map<String, Value> {
+        let mut properties = Map::new();
+        properties.insert(format!("{}count", prefix), json!(self.count));
+        properties.insert(format!("{}sum", prefix), json!(self.sum));
+        properties.insert(format!("{}mean", prefix), json!(self.mean));
+        properties.insert(format!("{}min", prefix), json!(self.min));
+        properties.insert(format!("{}max", prefix), json!(self.max));
+        properties.insert(format!("{}std", prefix), json!(self.std));
+        properties.insert(format!("{}majority", prefix), json!(self.majority));
+        for &(p, value) in &self.percentiles {
+            properties.insert(format!("{}p{}", prefix, p), json!(value));
+        }
+        if !self.histogram.is_empty() {
+            let histogram: Map<String, Value> = self
+                .histogram
+                .iter()
+                .map(|&(value, coverage)| (value.to_string(), json!(coverage)))
+                .collect();
+            properties.insert(format!("{}histogram", prefix), Value::Object(histogram));
+        }
+        properties
+    }
+}
+
+/// Smallest value whose cumulative coverage reaches `p` percent of the total
+fn weighted_percentile(sorted: &[(f64, f64)], total: f64, p: f64) -> f64 {
+    let target = (p / 100.0).clamp(0.0, 1.0) * total;
+    let mut cumulative = 0.0;
+    for &(value, weight) in sorted {
+        cumulative += weight;
+        if cumulative >= target {
+            return value;
+        }
+    }
+    sorted.last().map_or(f64::NAN, |&(v, _)| v)
+}
+
+/// Computes statistics of a raster band for every feature of a layer
+///
+/// Features are reprojected to the raster CRS when both have one and they
+/// differ. Polygons weight each pixel by the exact fraction they cover,
+/// points take the pixel they fall in and lines are ignored. Features are
+/// processed in parallel, each reading only the raster window it overlaps.
+#[derive(Debug, Clone)]
+pub struct ZonalStatistics {
+    band: usize,
+    percentiles: Vec<f64>,
+    categorical: bool,
+}
+
+impl ZonalStatistics {
+    /// Create a calculator for one band
+    pub fn new(band: usize) -> Self {
+        Self {
+            band,
+            percentiles: Vec::new(),
+            categorical: false,
+        }
+    }
+
+    /// Set the percentiles to compute, between 0 and 100
+    pub fn with_percentiles(mut self, percentiles: Vec<f64>) -> Self {
+        self.percentiles = percentiles;
+        self
+    }
+
+    /// Enable per-value coverage histograms for categorical rasters
+    pub fn with_categorical(mut self, categorical: bool) -> Self {
+        self.categorical = categorical;
+        self
+    }
+
+    /// Statistics for every feature of the layer, in feature order
+    pub fn compute(&self, dataset: &RasterDataset, layer: &Layer) -> Result<Vec<ZoneStatistics>> {
+        if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            return Err(ImageryError::InvalidParameter(format!(
+                "Percentiles must be between 0 and 100, got {:?}",
+                self.percentiles
+            )));
+        }
+        if self.band >= dataset.band_count() {
+            return Err(ImageryError::InvalidBand {
+                band: self.band,
+                total: dataset.band_count(),
+            });
+        }
+
+        let metadata = dataset.metadata();
+        let geo_transform = metadata
+            .geo_transform
+            .ok_or_else(|| ImageryError::metadata("Raster has no geotransform"))?;
+        let crs = metadata.crs.as_deref().map(parse_crs).transpose()?;
+        let zones = layer_to_pixels(layer, &geo_transform, crs.as_ref())?;
+
+        log::info!("Computing zonal statistics for {} features", zones.len());
+        zones.par_iter().map(|zone| self.zone(dataset, zone)).collect()
+    }
+
+    /// Statistics for every feature over an in-memory image
+    pub fn compute_image(&self, image: &MultiBandImage, layer: &Layer) -> Result<Vec<ZoneStatistics>> {
+        let dataset = RasterDataset::from_image(image, IMAGE_BLOCK_SIZE, IMAGE_BLOCK_SIZE)?;
+        self.compute(&dataset, layer)
+    }
+
+    /// Write the statistics of each feature into its properties
+    pub fn annotate(&self, dataset: &RasterDataset, layer: &mut Layer, prefix: &str) -> Result<()> {
+        let stats = self.compute(dataset, layer)?;
+        for (feature, stats) in layer.iter_mut().zip(stats) {
+            for (key, value) in stats.to_properties(prefix) {
+                feature.set_property(key, value);
+            }
+        }
+        Ok(())
+    }
+
+    fn zone(&self, dataset: &RasterDataset, zone: &PixelGeometry) -> Result<ZoneStatistics> {
+        let (width, height) = dataset.dimensions();
+        let Some(window) = zone.window(width, height) else {
+            return Ok(ZoneStatistics::default());
+        };
+
+        let coverage = zone.coverage(&window);
+        let values = dataset.read_band(self.band, &window)?;
+        let valid = dataset.valid_mask(&window)?;
+
+        let samples = coverage
+            .iter()
+            .zip(&valid)
+            .enumerate()
+            .filter(|(_, (&weight, &valid))| valid && weight > 0.0)
+            .filter_map(|(index, (&weight, _))| values.get(index).map(|value| (value, weight)))
+            .collect();
+        Ok(ZoneStatistics::from_samples(samples, &self.percentiles, self.categorical))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, ImageMetadata};
+    use meridian_core::crs::Crs;
+    use meridian_core::feature::Feature;
+    use meridian_core::geo_types::{coord, LineString};
+    use meridian_core::geometry::{Geometry, Point, Polygon};
+
+    /// 4x4 pixels of 10m, values 0..16 in row-major order, with pixel 5 nodata
+    fn sample_image() -> MultiBandImage {
+        let metadata = ImageMetadata {
+            width: 4,
+            height: 4,
+            bands: 1,
+            bits_per_sample: 16,
+            geo_transform: Some([500_000.0, 10.0, 0.0, 4_000_040.0, 0.0, -10.0]),
+            crs: Some("EPSG:32633".to_string()),
+            no_data: Some(99.0),
+            band_names: vec![],
+        };
+        let mut image = MultiBandImage::new(metadata, DataType::UInt16);
+        image.bands[0] = (0..16).map(|v| v as f32).collect();
+        image.bands[0][5] = 99.0;
+        image
+    }
+
+    /// Covers the left half of pixels 0 and 4, and all of pixels 1 and 5
+    fn sample_field() -> Feature {
+        let ring = LineString::new(vec![
+            coord! { x: 500_005.0, y: 4_000_040.0 },
+            coord! { x: 500_020.0, y: 4_000_040.0 },
+            coord! { x: 500_020.0, y: 4_000_020.0 },
+            coord! { x: 500_005.0, y: 4_000_020.0 },
+            coord! { x: 500_005.0, y: 4_000_040.0 },
+        ]);
+        Feature::new(Geometry::Polygon(Polygon::new(ring, vec![], Crs::utm(33, true))))
+    }
+
+    #[test]
+    fn test_fractional_zone_statistics() {
+        let image = sample_image();
+        let layer = Layer::from_features("fields", Crs::utm(33, true), vec![sample_field()]);
+
+        let stats = ZonalStatistics::new(0)
+            .with_percentiles(vec![50.0])
+            .with_categorical(true)
+            .compute_image(&image, &layer)
+            .unwrap();
+        let zone = &stats[0];
+
+        // Pixel 5 is nodata: 0 and 4 at half weight, 1 at full weight
+        assert_eq!(zone.count, 2.0);
+        assert_eq!(zone.sum, 0.0 * 0.5 + 1.0 + 4.0 * 0.5);
+        assert_eq!(zone.mean, Some(1.5));
+        assert_eq!((zone.min, zone.max), (Some(0.0), Some(4.0)));
+        assert_eq!(zone.percentiles, vec![(50.0, 1.0)]);
+        assert_eq!(zone.majority, Some(1.0));
+        assert_eq!(zone.histogram, vec![(0.0, 0.5), (1.0, 1.0), (4.0, 0.5)]);
+    }
+
+    #[test]
+    fn test_points_and_features_off_the_raster() {
+        let utm = Crs::utm(33, true);
+        let point = |x: f64, y: f64| Feature::new(Geometry::Point(Point::new(x, y, utm.clone())));
+        let far_field = LineString::new(vec![
+            coord! { x: 600_000.0, y: 4_000_040.0 },
+            coord! { x: 600_020.0, y: 4_000_040.0 },
+            coord! { x: 600_020.0, y: 4_000_020.0 },
+            coord! { x: 600_000.0, y: 4_000_040.0 },
+        ]);
+        let features = vec![
+            point(500_035.0, 4_000_005.0),
+            point(500_015.0, 4_000_025.0),
+            Feature::new(Geometry::Polygon(Polygon::new(far_field, vec![], utm.clone()))),
+        ];
+        let layer = Layer::from_features("sites", utm, features);
+
+        let stats = ZonalStatistics::new(0).compute_image(&sample_image(), &layer).unwrap();
+        // A point takes its whole pixel
+        assert_eq!(stats[0].count, 1.0);
+        assert_eq!(stats[0].mean, Some(15.0));
+        // Points on nodata and features outside the raster have no statistics
+        assert_eq!(stats[1], ZoneStatistics::default());
+        assert_eq!(stats[2], ZoneStatistics::default());
+        assert_eq!(stats[2].mean, None);
+    }
+
+    #[test]
+    fn test_layer_reprojected_to_raster_crs() {
+        let layer = Layer::from_features("fields", Crs::utm(33, true), vec![sample_field()])
+            .transform(&Crs::wgs84())
+            .unwrap();
+
+        let stats = ZonalStatistics::new(0).compute_image(&sample_image(), &layer).unwrap();
+        assert!((stats[0].count - 2.0).abs() < 1e-6);
+        assert!((stats[0].mean.unwrap() - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_annotate_layer() {
+        let dataset = RasterDataset::from_image(&sample_image(), 2, 2).unwrap();
+        let outside = Feature::new(Geometry::Point(Point::new(0.0, 0.0, Crs::utm(33, true))));
+        let mut layer = Layer::from_features("fields", Crs::utm(33, true), vec![sample_field(), outside]);
+
+        ZonalStatistics::new(0)
+            .with_percentiles(vec![25.0, 50.0])
+            .with_categorical(true)
+            .annotate(&dataset, &mut layer, "dem_")
+            .unwrap();
+
+        let features: Vec<_> = layer.iter().collect();
+        assert_eq!(features[0].get_property("dem_mean"), Some(&json!(1.5)));
+        assert_eq!(features[0].get_property("dem_p25"), Some(&json!(0.0)));
+        assert_eq!(features[0].get_property("dem_p50"), Some(&json!(1.0)));
+        assert_eq!(
+            features[0].get_property("dem_histogram"),
+            Some(&json!({ "0": 0.5, "1": 1.0, "4": 0.5 }))
+        );
+        assert_eq!(features[1].get_property("dem_count"), Some(&json!(0.0)));
+        assert_eq!(features[1].get_property("dem_mean"), Some(&Value::Null));
+        assert!(features[1].get_property("dem_histogram").is_none());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let image = sample_image();
+        let layer = Layer::from_features("fields", Crs::utm(33, true), vec![sample_field()]);
+
+        let result = ZonalStatistics::new(0).with_percentiles(vec![50.0, 101.0]).compute_image(&image, &layer);
+        assert!(matches!(result, Err(ImageryError::InvalidParameter(_))));
+        let result = ZonalStatistics::new(1).compute_image(&image, &layer);
+        assert!(matches!(result, Err(ImageryError::InvalidBand { band: 1, total: 1 })));
+    }
+}
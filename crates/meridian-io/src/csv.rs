@@ -1,9 +1,10 @@
 //! CSV file support with coordinate columns
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
 use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
 use crate::wkt::WktReader;
-use futures::stream;
+use futures::{stream, StreamExt};
 use geo_types::{Geometry, Point};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -337,6 +338,31 @@ impl CsvWriter {
         self.config.delimiter = delimiter;
         self
     }
+
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["GEOMETRY", "SEPARATOR"];
+
+    /// Create a writer from layer creation options
+    ///
+    /// `GEOMETRY` is `AS_WKT` or `AS_XY`; `SEPARATOR` is `COMMA`, `SEMICOLON` or `TAB`.
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        let mut writer = Self::new();
+
+        match options.get("GEOMETRY").map(str::to_ascii_uppercase).as_deref() {
+            None | Some("AS_WKT") => {}
+            Some("AS_XY") => writer = writer.with_xy(),
+            Some(other) => return Err(IoError::InvalidOption(format!("Invalid GEOMETRY: '{}'", other))),
+        }
+        match options.get("SEPARATOR").map(str::to_ascii_uppercase).as_deref() {
+            None | Some("COMMA") => {}
+            Some("SEMICOLON") => writer = writer.with_delimiter(b';'),
+            Some("TAB") => writer = writer.with_delimiter(b'\t'),
+            Some(other) => return Err(IoError::InvalidOption(format!("Invalid SEPARATOR: '{}'", other))),
+        }
+
+        Ok(writer)
+    }
 }
 
 impl Default for CsvWriter {
@@ -418,11 +444,17 @@ impl Writer for CsvWriter {
         Ok(())
     }
 
-    fn write_stream<S>(&self, _path: &Path, _stream: S) -> Result<()>
-    where
-        S: futures::Stream<Item = Result<Feature>> + Send + 'static,
-    {
-        Err(IoError::UnsupportedFormat("CSV streaming write not yet implemented".to_string()))
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
+        // Records are written from a whole collection, so the stream is collected first
+        let features = futures::executor::block_on(async {
+            let mut features = Vec::new();
+            while let Some(result) = stream.next().await {
+                features.push(result?);
+            }
+            Ok::<_, IoError>(features)
+        })?;
+
+        self.write(path, &FeatureCollection::from_features(features))
     }
 
     fn append(&self, _path: &Path, _collection: &FeatureCollection) -> Result<()> {
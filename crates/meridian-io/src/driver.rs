@@ -0,0 +1,284 @@
+//! Format drivers and layer creation options
+//!
+//! A driver supplies the reader and writer of one format. The built-in
+//! formats are registered by default; other crates, such as plugins loaded by
+//! `meridian-plugin`, can add drivers with [`crate::FormatRegistry::register_driver`]
+//! to support further formats or to replace a built-in implementation.
+
+use crate::csv::{CsvReader, CsvWriter};
+use crate::error::{IoError, Result};
+use crate::geojson::{GeoJsonReader, GeoJsonWriter};
+use crate::gpkg::{GeoPackageReader, GeoPackageWriter};
+use crate::kml::{KmlReader, KmlWriter, KmzReader};
+use crate::shapefile::{ShapefileReader, ShapefileWriter};
+use crate::traits::{Format, Reader, Writer};
+use crate::wkt::{TwkbReader, TwkbWriter, WkbReader, WkbWriter, WktReader, WktWriter};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Layer creation options
+///
+/// Options are `KEY=VALUE` pairs interpreted by the writer of a format, for
+/// example `SPATIAL_INDEX=NO` for GeoPackage. Keys are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayerOptions {
+    options: BTreeMap<String, String>,
+}
+
+impl LayerOptions {
+    /// Create an empty set of options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `KEY=VALUE` pairs
+    pub fn parse<'a>(pairs: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut options = Self::new();
+        for pair in pairs {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| IoError::InvalidOption(format!("Expected KEY=VALUE, got '{}'", pair)))?;
+            options.set(key.trim(), value.trim());
+        }
+        Ok(options)
+    }
+
+    /// Add an option
+    pub fn with(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Set an option
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        self.options.insert(key.to_ascii_uppercase(), value.into());
+    }
+
+    /// Get an option value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.options.get(&key.to_ascii_uppercase()).map(String::as_str)
+    }
+
+    /// Get a `YES`/`NO` option
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        self.get(key)
+            .map(|value| match value.to_ascii_uppercase().as_str() {
+                "YES" | "TRUE" | "ON" | "1" => Ok(true),
+                "NO" | "FALSE" | "OFF" | "0" => Ok(false),
+                _ => Err(IoError::InvalidOption(format!("{} must be YES or NO, got '{}'", key, value))),
+            })
+            .transpose()
+    }
+
+    /// Get an option parsed as `T`
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| IoError::InvalidOption(format!("Invalid value for {}: '{}'", key, value)))
+            })
+            .transpose()
+    }
+
+    /// Fail on options outside `supported`
+    pub fn check(&self, supported: &[&str]) -> Result<()> {
+        match self.options.keys().find(|key| !supported.contains(&key.as_str())) {
+            Some(key) => Err(IoError::InvalidOption(format!(
+                "Unsupported layer creation option {} (supported: {})",
+                key,
+                supported.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Iterate over the options
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Check if no options are set
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+/// Reader and writer factory for a file format
+pub trait FormatDriver: Send + Sync {
+    /// Driver name, e.g. `GeoJSON`
+    fn name(&self) -> &str;
+
+    /// Built-in format implemented by this driver, if any
+    fn format(&self) -> Option<Format> {
+        None
+    }
+
+    /// File extensions handled by this driver, without the dot
+    fn extensions(&self) -> &[&str];
+
+    /// Layer creation options understood by the writer
+    fn layer_options(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Create a reader, if the format can be read
+    fn reader(&self) -> Option<Box<dyn Reader>>;
+
+    /// Create a writer, if the format can be written
+    fn writer(&self, options: &LayerOptions) -> Option<Result<Box<dyn Writer>>>;
+}
+
+/// Driver for one of the formats implemented in this crate
+struct BuiltinDriver(Format);
+
+impl FormatDriver for BuiltinDriver {
+    fn name(&self) -> &str {
+        match self.0 {
+            Format::GeoJson => "GeoJSON",
+            Format::Shapefile => "ESRI Shapefile",
+            Format::Kml => "KML",
+            Format::Kmz => "KMZ",
+            Format::GeoTiff => "GTiff",
+            Format::GeoPackage => "GPKG",
+            Format::Wkt => "WKT",
+            Format::Wkb => "WKB",
+            Format::Csv => "CSV",
+            Format::Gml => "GML",
+        }
+    }
+
+    fn format(&self) -> Option<Format> {
+        Some(self.0)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.0.extensions()
+    }
+
+    fn layer_options(&self) -> &[&str] {
+        match self.0 {
+            Format::GeoJson => GeoJsonWriter::LAYER_OPTIONS,
+            Format::Shapefile => ShapefileWriter::LAYER_OPTIONS,
+            Format::Kml => KmlWriter::LAYER_OPTIONS,
+            Format::GeoPackage => GeoPackageWriter::LAYER_OPTIONS,
+            Format::Wkt => WktWriter::LAYER_OPTIONS,
+            Format::Wkb => WkbWriter::LAYER_OPTIONS,
+            Format::Csv => CsvWriter::LAYER_OPTIONS,
+            _ => &[],
+        }
+    }
+
+    fn reader(&self) -> Option<Box<dyn Reader>> {
+        match self.0 {
+            Format::GeoJson => Some(Box::new(GeoJsonReader::new())),
+            Format::Shapefile => Some(Box::new(ShapefileReader::new())),
+            Format::Kml => Some(Box::new(KmlReader::new())),
+            Format::Kmz => Some(Box::new(KmzReader::new())),
+            Format::GeoPackage => Some(Box::new(GeoPackageReader::new())),
+            Format::Wkt => Some(Box::new(WktReader::new())),
+            Format::Wkb => Some(Box::new(WkbReader::new())),
+            Format::Csv => Some(Box::new(CsvReader::new())),
+            Format::GeoTiff | Format::Gml => None,
+        }
+    }
+
+    fn writer(&self, options: &LayerOptions) -> Option<Result<Box<dyn Writer>>> {
+        match self.0 {
+            Format::GeoJson => boxed(GeoJsonWriter::from_options(options)),
+            Format::Shapefile => boxed(ShapefileWriter::from_options(options)),
+            Format::Kml => boxed(KmlWriter::from_options(options)),
+            Format::GeoPackage => boxed(GeoPackageWriter::from_options(options)),
+            Format::Wkt => boxed(WktWriter::from_options(options)),
+            Format::Wkb => boxed(WkbWriter::from_options(options)),
+            Format::Csv => boxed(CsvWriter::from_options(options)),
+            Format::Kmz | Format::GeoTiff | Format::Gml => None,
+        }
+    }
+}
+
+fn boxed<W: Writer + 'static>(writer: Result<W>) -> Option<Result<Box<dyn Writer>>> {
+    Some(writer.map(|w| Box::new(w) as Box<dyn Writer>))
+}
+
+/// Driver for TWKB files, which have no [`Format`] of their own
+struct TwkbDriver;
+
+impl FormatDriver for TwkbDriver {
+    fn name(&self) -> &str {
+        "TWKB"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["twkb"]
+    }
+
+    fn layer_options(&self) -> &[&str] {
+        TwkbWriter::LAYER_OPTIONS
+    }
+
+    fn reader(&self) -> Option<Box<dyn Reader>> {
+        Some(Box::new(TwkbReader::new()))
+    }
+
+    fn writer(&self, options: &LayerOptions) -> Option<Result<Box<dyn Writer>>> {
+        boxed(TwkbWriter::from_options(options))
+    }
+}
+
+/// Registered drivers, most recently registered first
+fn registry() -> &'static RwLock<Vec<Arc<dyn FormatDriver>>> {
+    static DRIVERS: OnceLock<RwLock<Vec<Arc<dyn FormatDriver>>>> = OnceLock::new();
+
+    DRIVERS.get_or_init(|| {
+        let builtin = [
+            Format::GeoJson,
+            Format::Shapefile,
+            Format::Kml,
+            Format::Kmz,
+            Format::GeoTiff,
+            Format::GeoPackage,
+            Format::Wkt,
+            Format::Wkb,
+            Format::Csv,
+            Format::Gml,
+        ];
+        RwLock::new(
+            builtin
+                .into_iter()
+                .map(|format| Arc::new(BuiltinDriver(format)) as Arc<dyn FormatDriver>)
+                .chain([Arc::new(TwkbDriver) as Arc<dyn FormatDriver>])
+                .collect(),
+        )
+    })
+}
+
+/// Add a driver, taking precedence over those registered before it
+pub(crate) fn register(driver: Arc<dyn FormatDriver>) {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(0, driver);
+}
+
+/// All registered drivers, most recently registered first
+pub(crate) fn drivers() -> Vec<Arc<dyn FormatDriver>> {
+    registry().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_options() {
+        let options = LayerOptions::parse(["spatial_index=NO", "LAYER_NAME = roads"]).unwrap();
+        assert_eq!(options.get_bool("SPATIAL_INDEX").unwrap(), Some(false));
+        assert_eq!(options.get("layer_name"), Some("roads"));
+        assert!(options.check(&["SPATIAL_INDEX", "LAYER_NAME"]).is_ok());
+        assert!(options.check(&["LAYER_NAME"]).is_err());
+        assert!(LayerOptions::parse(["PRETTY"]).is_err());
+        assert!(LayerOptions::new().with("MAX_FILE_SIZE", "big").get_parsed::<u64>("MAX_FILE_SIZE").is_err());
+    }
+}
@@ -86,6 +86,10 @@ pub enum IoError {
     #[error("Attribute error: {0}")]
     Attribute(String),
 
+    /// Invalid layer creation option
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+
     /// Generic error
     #[error("{0}")]
     Other(String),
@@ -1,8 +1,9 @@
 //! GeoJSON reading and writing support
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
 use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
-use futures::{stream, Stream, StreamExt};
+use futures::{stream, StreamExt};
 use geo_types::Geometry;
 use serde_json::Value;
 use std::fs::File;
@@ -176,6 +177,17 @@ impl GeoJsonWriter {
         Self { pretty: false }
     }
 
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["PRETTY"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        Ok(Self {
+            pretty: options.get_bool("PRETTY")?.unwrap_or(false),
+        })
+    }
+
     /// Enable pretty printing
     pub fn with_pretty(mut self) -> Self {
         self.pretty = true;
@@ -262,20 +274,12 @@ impl Writer for GeoJsonWriter {
         Ok(())
     }
 
-    fn write_stream<S>(&self, path: &Path, stream: S) -> Result<()>
-    where
-        S: Stream<Item = Result<Feature>> + Send + 'static,
-    {
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
         // For streaming, write newline-delimited GeoJSON
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
 
-        // Use tokio runtime for async stream processing
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| IoError::Other(format!("Failed to create runtime: {}", e)))?;
-
-        rt.block_on(async {
-            let mut stream = Box::pin(stream);
+        futures::executor::block_on(async {
             while let Some(result) = stream.next().await {
                 let feature = result?;
                 let gj_feature = Self::convert_feature(&feature)?;
@@ -1,11 +1,19 @@
 //! GeoPackage (SQLite-based) support
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
-use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader};
-use futures::stream;
+use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
+use crate::wkt::core_geometry;
+use futures::{stream, StreamExt};
+use geo::BoundingRect;
+use geo_types::{Geometry, Rect};
+use meridian_core::crs::Crs;
+use meridian_core::geometry::Geometry as CoreGeometry;
+use meridian_core::traits::Bounded;
+use rusqlite::types::Value as SqlValue;
 use rusqlite::{Connection, OpenFlags};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 /// GeoPackage reader
@@ -39,7 +47,7 @@ impl GeoPackageReader {
         // Get geometry column name
         let geom_column = self.get_geometry_column(&conn, layer_name)?;
 
-        // Get all column names
+        // Get all column names and declared types
         let columns = self.get_columns(&conn, layer_name)?;
 
         // Build query
@@ -53,26 +61,37 @@ impl GeoPackageReader {
         let rows = stmt.query_map([], |row| {
             let mut properties = HashMap::new();
             let mut geometry = None;
+            let mut id = None;
 
             for i in 0..column_count {
-                let col_name = columns.get(i).map(|s| s.as_str()).unwrap_or("");
+                let (col_name, col_type) = columns
+                    .get(i)
+                    .map(|(name, ty)| (name.as_str(), ty.as_str()))
+                    .unwrap_or(("", ""));
 
                 if col_name == geom_column {
                     // Parse geometry (stored as WKB in GeoPackage)
                     if let Ok(wkb_data) = row.get::<_, Vec<u8>>(i) {
-                        geometry = Self::parse_gpkg_geometry(&wkb_data).ok();
+                        geometry = decode_gpkg_geometry(&wkb_data, Crs::wgs84()).ok().map(|g| g.to_geo());
                     }
+                } else if col_name == "fid" {
+                    id = row.get::<_, i64>(i).ok().map(|fid| fid.to_string());
                 } else {
                     // Store property
-                    let value = Self::get_column_value(row, i);
-                    if col_name != "fid" && col_name != "id" {
+                    let value = match Self::get_column_value(row, i) {
+                        Value::Number(n) if col_type.eq_ignore_ascii_case("BOOLEAN") => {
+                            Value::Bool(n.as_i64() != Some(0))
+                        }
+                        value => value,
+                    };
+                    if col_name != "id" {
                         properties.insert(col_name.to_string(), value);
                     }
                 }
             }
 
             Ok(Feature {
-                id: None,
+                id,
                 geometry,
                 properties,
                 crs: None,
@@ -105,14 +124,14 @@ impl GeoPackageReader {
         Ok(geom_column)
     }
 
-    /// Get all column names for a table
-    fn get_columns(&self, conn: &Connection, table_name: &str) -> Result<Vec<String>> {
+    /// Get all column names and declared types for a table
+    fn get_columns(&self, conn: &Connection, table_name: &str) -> Result<Vec<(String, String)>> {
         let query = format!("PRAGMA table_info(\"{}\")", table_name);
         let mut stmt = conn.prepare(&query)?;
 
         let columns = stmt
-            .query_map([], |row| row.get::<_, String>(1))? // Column 1 is the name
-            .collect::<rusqlite::Result<Vec<String>>>()?;
+            .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))? // Name and type
+            .collect::<rusqlite::Result<Vec<_>>>()?;
 
         Ok(columns)
     }
@@ -120,64 +139,23 @@ impl GeoPackageReader {
     /// Get CRS for a layer
     fn get_layer_crs(&self, conn: &Connection, layer_name: &str) -> Result<Option<String>> {
         let mut stmt = conn.prepare(
-            "SELECT srs_id FROM gpkg_geometry_columns WHERE table_name = ?"
+            "SELECT s.organization, s.organization_coordsys_id, s.definition \
+             FROM gpkg_geometry_columns g JOIN gpkg_spatial_ref_sys s ON s.srs_id = g.srs_id \
+             WHERE g.table_name = ?"
         )?;
 
-        if let Ok(srs_id) = stmt.query_row([layer_name], |row| row.get::<_, i32>(0)) {
-            // Convert SRS ID to EPSG code
-            if srs_id > 0 {
-                return Ok(Some(format!("EPSG:{}", srs_id)));
+        let srs = stmt.query_row([layer_name], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?))
+        });
+
+        match srs {
+            // Convert EPSG entries to codes, custom ones keep their WKT
+            Ok((organization, code, _)) if organization.eq_ignore_ascii_case("EPSG") && code > 0 => {
+                Ok(Some(format!("EPSG:{}", code)))
             }
+            Ok((_, _, definition)) if definition != "undefined" => Ok(Some(definition)),
+            _ => Ok(None),
         }
-
-        Ok(None)
-    }
-
-    /// Parse GeoPackage geometry (WKB with GeoPackage header)
-    fn parse_gpkg_geometry(data: &[u8]) -> Result<geo_types::Geometry<f64>> {
-        if data.len() < 8 {
-            return Err(IoError::GeoPackage("Invalid GeoPackage geometry: too short".to_string()));
-        }
-
-        // GeoPackage Binary Format has a header:
-        // Bytes 0-1: Magic number (0x47, 0x50) = "GP"
-        // Byte 2: Version
-        // Byte 3: Flags
-        // Bytes 4-7: SRS ID
-        // Then WKB data follows
-
-        // Verify magic number
-        if data[0] != 0x47 || data[1] != 0x50 {
-            return Err(IoError::GeoPackage("Invalid GeoPackage geometry magic number".to_string()));
-        }
-
-        // Extract flags to determine envelope size
-        let flags = data[3];
-        let envelope_type = (flags >> 1) & 0x07;
-
-        // Calculate envelope size
-        let envelope_size = match envelope_type {
-            0 => 0,   // No envelope
-            1 => 32,  // XY
-            2 => 48,  // XYZ
-            3 => 48,  // XYM
-            4 => 64,  // XYZM
-            _ => return Err(IoError::GeoPackage("Invalid envelope type".to_string())),
-        };
-
-        // WKB data starts after header (8 bytes) + envelope
-        let wkb_offset = 8 + envelope_size;
-
-        if data.len() < wkb_offset {
-            return Err(IoError::GeoPackage("Invalid GeoPackage geometry: header overflow".to_string()));
-        }
-
-        let _wkb_data = &data[wkb_offset..];
-
-        // Parse WKB using wkt crate (it also supports WKB)
-        // For now, we'll use a simplified approach
-        // TODO: Implement proper WKB parsing
-        Err(IoError::GeoPackage("WKB parsing not yet implemented".to_string()))
     }
 
     /// Get value from SQLite row
@@ -269,13 +247,182 @@ impl Reader for GeoPackageReader {
     }
 }
 
+/// How a layer that already exists is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Replace the layer
+    #[default]
+    Overwrite,
+
+    /// Add features to the layer, adding columns for new properties
+    Append,
+}
+
 /// GeoPackage writer
-pub struct GeoPackageWriter;
+///
+/// Writes GeoPackage 1.3 feature layers with R-tree spatial indexes. Each
+/// layer is a table of its own, so layers already in the file are kept.
+pub struct GeoPackageWriter {
+    /// Layer name; defaults to the file stem
+    pub layer_name: Option<String>,
+
+    /// Name of the geometry column of new layers
+    pub geometry_column: String,
+
+    /// Create an R-tree spatial index for new layers
+    pub spatial_index: bool,
+}
 
 impl GeoPackageWriter {
     /// Create a new GeoPackage writer
     pub fn new() -> Self {
-        Self
+        Self {
+            layer_name: None,
+            geometry_column: "geom".to_string(),
+            spatial_index: true,
+        }
+    }
+
+    /// Set the layer name
+    pub fn with_layer_name(mut self, layer_name: impl Into<String>) -> Self {
+        self.layer_name = Some(layer_name.into());
+        self
+    }
+
+    /// Set the geometry column name
+    pub fn with_geometry_column(mut self, geometry_column: impl Into<String>) -> Self {
+        self.geometry_column = geometry_column.into();
+        self
+    }
+
+    /// Enable or disable the R-tree spatial index
+    pub fn with_spatial_index(mut self, spatial_index: bool) -> Self {
+        self.spatial_index = spatial_index;
+        self
+    }
+
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["LAYER_NAME", "GEOMETRY_NAME", "SPATIAL_INDEX"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        let mut writer = Self::new();
+        writer.layer_name = options.get("LAYER_NAME").map(str::to_string);
+        if let Some(geometry_column) = options.get("GEOMETRY_NAME") {
+            writer.geometry_column = geometry_column.to_string();
+        }
+        if let Some(spatial_index) = options.get_bool("SPATIAL_INDEX")? {
+            writer.spatial_index = spatial_index;
+        }
+        Ok(writer)
+    }
+
+    /// Write a collection to a layer, creating the GeoPackage if needed
+    pub fn write_layer(
+        &self,
+        path: &Path,
+        layer_name: &str,
+        collection: &FeatureCollection,
+        mode: WriteMode,
+    ) -> Result<()> {
+        self.write_layers(path, &[(layer_name, collection)], mode)
+    }
+
+    /// Write several layers to one GeoPackage in a single transaction
+    pub fn write_layers(
+        &self,
+        path: &Path,
+        layers: &[(&str, &FeatureCollection)],
+        mode: WriteMode,
+    ) -> Result<()> {
+        let mut conn = open_geopackage(path)?;
+        let tx = conn.transaction()?;
+
+        for (layer_name, collection) in layers {
+            let exists = tx.query_row(
+                "SELECT COUNT(*) FROM gpkg_contents WHERE table_name = ?",
+                [layer_name],
+                |row| row.get::<_, i64>(0),
+            )? > 0;
+
+            if exists && mode == WriteMode::Overwrite {
+                drop_layer(&tx, layer_name)?;
+            }
+            let layer = if exists && mode == WriteMode::Append {
+                let layer = LayerTable::load(&tx, layer_name)?;
+                // Geometry blobs take the layer's SRS, so features in another CRS can't join it
+                if let Some(crs) = collection_crs(collection) {
+                    if register_srs(&tx, Some(crs))? != layer.srs_id {
+                        return Err(IoError::Crs(format!(
+                            "Features in {} cannot be appended to layer {} with SRS ID {}",
+                            crs, layer_name, layer.srs_id
+                        )));
+                    }
+                }
+                layer
+            } else {
+                self.create_layer(&tx, layer_name, collection)?
+            };
+
+            layer.insert(&tx, collection)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn layer_name(&self, path: &Path) -> String {
+        self.layer_name.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("features")
+                .to_string()
+        })
+    }
+
+    /// Create a layer table and register it in the GeoPackage metadata tables
+    fn create_layer(
+        &self,
+        conn: &Connection,
+        layer_name: &str,
+        collection: &FeatureCollection,
+    ) -> Result<LayerTable> {
+        let srs_id = register_srs(conn, collection_crs(collection))?;
+        let geometry_type = layer_geometry_type(&collection.features);
+
+        let mut layer = LayerTable {
+            name: layer_name.to_string(),
+            geometry_column: self.geometry_column.clone(),
+            geometry_type: geometry_type.to_string(),
+            srs_id,
+            columns: Vec::new(),
+        };
+
+        conn.execute_batch(&format!(
+            "CREATE TABLE {} (\"fid\" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL, {} {})",
+            quote(layer_name),
+            quote(&self.geometry_column),
+            geometry_type,
+        ))?;
+        layer.add_columns(conn, collection)?;
+
+        conn.execute(
+            "INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id) \
+             VALUES (?1, 'features', ?1, ?2)",
+            rusqlite::params![layer_name, srs_id],
+        )?;
+        conn.execute(
+            "INSERT INTO gpkg_geometry_columns (table_name, column_name, geometry_type_name, srs_id, z, m) \
+             VALUES (?1, ?2, ?3, ?4, 0, 0)",
+            rusqlite::params![layer_name, self.geometry_column, geometry_type, srs_id],
+        )?;
+
+        if self.spatial_index {
+            create_spatial_index(conn, layer_name, &self.geometry_column)?;
+        }
+
+        Ok(layer)
     }
 }
 
@@ -285,9 +432,648 @@ impl Default for GeoPackageWriter {
     }
 }
 
+impl Writer for GeoPackageWriter {
+    fn write(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        self.write_layer(path, &self.layer_name(path), collection, WriteMode::Overwrite)
+    }
+
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
+        let features = futures::executor::block_on(async {
+            let mut features = Vec::new();
+            while let Some(result) = stream.next().await {
+                features.push(result?);
+            }
+            Ok::<_, IoError>(features)
+        })?;
+
+        self.write(path, &FeatureCollection::from_features(features))
+    }
+
+    fn append(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        self.write_layer(path, &self.layer_name(path), collection, WriteMode::Append)
+    }
+}
+
+/// `application_id` of a GeoPackage, "GPKG" in ASCII
+const APPLICATION_ID: i32 = 0x4750_4B47;
+
+/// `user_version` of GeoPackage 1.3.0
+const USER_VERSION: i32 = 10300;
+
+/// Definitions of the spatial reference systems written with their WKT
+const SRS_DEFINITIONS: &[(i32, &str, &str)] = &[
+    (
+        4326,
+        "WGS 84 geodetic",
+        r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AXIS["Latitude",NORTH],AXIS["Longitude",EAST],AUTHORITY["EPSG","4326"]]"#,
+    ),
+    (
+        3857,
+        "WGS 84 / Pseudo-Mercator",
+        r#"PROJCS["WGS 84 / Pseudo-Mercator",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]],PROJECTION["Mercator_1SP"],PARAMETER["central_meridian",0],PARAMETER["scale_factor",1],PARAMETER["false_easting",0],PARAMETER["false_northing",0],UNIT["metre",1,AUTHORITY["EPSG","9001"]],AXIS["Easting",EAST],AXIS["Northing",NORTH],AUTHORITY["EPSG","3857"]]"#,
+    ),
+];
+
+/// First SRS ID given to coordinate systems without an EPSG code
+const CUSTOM_SRS_ID: i32 = 100_000;
+
+const CORE_TABLES: &str = r#"
+CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_extensions (
+    table_name TEXT,
+    column_name TEXT,
+    extension_name TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    CONSTRAINT ge_tce UNIQUE (table_name, column_name, extension_name)
+);
+INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system');
+"#;
+
+/// Open a GeoPackage for writing, creating its metadata tables if needed
+fn open_geopackage(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    register_functions(&conn)?;
+
+    let application_id: i32 = conn.query_row("PRAGMA application_id", [], |row| row.get(0))?;
+    if application_id != APPLICATION_ID {
+        conn.execute_batch(&format!(
+            "PRAGMA application_id = {}; PRAGMA user_version = {};",
+            APPLICATION_ID, USER_VERSION
+        ))?;
+    }
+    conn.execute_batch(CORE_TABLES)?;
+    register_srs(&conn, Some("EPSG:4326"))?;
+
+    Ok(conn)
+}
+
+/// Register the SQL functions used by the R-tree index triggers
+fn register_functions(conn: &Connection) -> Result<()> {
+    use rusqlite::functions::FunctionFlags;
+
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    type Bound = fn(&Rect<f64>) -> f64;
+
+    let bounds: [(&str, Bound); 4] = [
+        ("ST_MinX", |r| r.min().x),
+        ("ST_MaxX", |r| r.max().x),
+        ("ST_MinY", |r| r.min().y),
+        ("ST_MaxY", |r| r.max().y),
+    ];
+
+    for (name, bound) in bounds {
+        conn.create_scalar_function(name, 1, flags, move |ctx| {
+            let envelope = ctx.get_raw(0).as_blob_or_null()?.and_then(|blob| gpkg_envelope(blob).ok().flatten());
+            Ok(envelope.as_ref().map(bound))
+        })?;
+    }
+    conn.create_scalar_function("ST_IsEmpty", 1, flags, |ctx| {
+        let blob = ctx.get_raw(0).as_blob_or_null()?;
+        Ok(blob.map(|blob| matches!(gpkg_envelope(blob), Ok(None))))
+    })?;
+
+    Ok(())
+}
+
+/// Drop a layer with its spatial index and metadata
+fn drop_layer(conn: &Connection, layer_name: &str) -> Result<()> {
+    let indexes = {
+        let mut stmt = conn.prepare(
+            "SELECT column_name FROM gpkg_extensions WHERE table_name = ? AND extension_name = 'gpkg_rtree_index'",
+        )?;
+        let columns = stmt
+            .query_map([layer_name], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        columns
+    };
+    for column in indexes {
+        conn.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {}",
+            quote(&format!("rtree_{}_{}", layer_name, column))
+        ))?;
+    }
+
+    conn.execute_batch(&format!("DROP TABLE IF EXISTS {}", quote(layer_name)))?;
+    for table in ["gpkg_extensions", "gpkg_geometry_columns", "gpkg_contents"] {
+        conn.execute(&format!("DELETE FROM {} WHERE table_name = ?", table), [layer_name])?;
+    }
+    Ok(())
+}
+
+/// Create the R-tree spatial index of a layer and the triggers maintaining it
+fn create_spatial_index(conn: &Connection, table: &str, column: &str) -> Result<()> {
+    let rtree = quote(&format!("rtree_{}_{}", table, column));
+    let trigger = |suffix: &str| quote(&format!("rtree_{}_{}_{}", table, column, suffix));
+    let (t, c) = (quote(table), quote(column));
+    let insert = format!(
+        "INSERT OR REPLACE INTO {rtree} VALUES (NEW.\"fid\", ST_MinX(NEW.{c}), ST_MaxX(NEW.{c}), ST_MinY(NEW.{c}), ST_MaxY(NEW.{c}));"
+    );
+
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE {rtree} USING rtree(id, minx, maxx, miny, maxy);
+         CREATE TRIGGER {insert_trigger} AFTER INSERT ON {t}
+           WHEN (NEW.{c} NOT NULL AND NOT ST_IsEmpty(NEW.{c}))
+         BEGIN {insert} END;
+         CREATE TRIGGER {update1} AFTER UPDATE OF {c} ON {t}
+           WHEN OLD.\"fid\" = NEW.\"fid\" AND (NEW.{c} NOTNULL AND NOT ST_IsEmpty(NEW.{c}))
+         BEGIN {insert} END;
+         CREATE TRIGGER {update2} AFTER UPDATE OF {c} ON {t}
+           WHEN OLD.\"fid\" = NEW.\"fid\" AND (NEW.{c} ISNULL OR ST_IsEmpty(NEW.{c}))
+         BEGIN DELETE FROM {rtree} WHERE id = OLD.\"fid\"; END;
+         CREATE TRIGGER {update3} AFTER UPDATE ON {t}
+           WHEN OLD.\"fid\" != NEW.\"fid\" AND (NEW.{c} NOTNULL AND NOT ST_IsEmpty(NEW.{c}))
+         BEGIN DELETE FROM {rtree} WHERE id = OLD.\"fid\"; {insert} END;
+         CREATE TRIGGER {update4} AFTER UPDATE ON {t}
+           WHEN OLD.\"fid\" != NEW.\"fid\" AND (NEW.{c} ISNULL OR ST_IsEmpty(NEW.{c}))
+         BEGIN DELETE FROM {rtree} WHERE id IN (OLD.\"fid\", NEW.\"fid\"); END;
+         CREATE TRIGGER {delete} AFTER DELETE ON {t}
+           WHEN OLD.{c} NOT NULL
+         BEGIN DELETE FROM {rtree} WHERE id = OLD.\"fid\"; END;",
+        insert_trigger = trigger("insert"),
+        update1 = trigger("update1"),
+        update2 = trigger("update2"),
+        update3 = trigger("update3"),
+        update4 = trigger("update4"),
+        delete = trigger("delete"),
+    ))?;
+
+    conn.execute(
+        "INSERT INTO gpkg_extensions (table_name, column_name, extension_name, definition, scope) \
+         VALUES (?1, ?2, 'gpkg_rtree_index', 'http://www.geopackage.org/spec120/#extension_rtree', 'write-only')",
+        [table, column],
+    )?;
+    Ok(())
+}
+
+/// Find or add the spatial reference system of a CRS, returning its SRS ID
+fn register_srs(conn: &Connection, crs: Option<&str>) -> Result<i32> {
+    let Some(crs) = crs else {
+        return Ok(-1);
+    };
+
+    let (code, wkt) = if crs.contains('[') {
+        let code = crs
+            .rfind("AUTHORITY[\"EPSG\",\"")
+            .and_then(|i| crs[i + 18..].split('"').next())
+            .and_then(|code| code.parse::<i32>().ok());
+        (code, Some(crs))
+    } else {
+        let code = crs.rsplit([':', '/']).next().unwrap_or("").trim();
+        let code = if code.eq_ignore_ascii_case("CRS84") { "4326" } else { code };
+        match code.parse::<i32>() {
+            Ok(code) => (Some(code), None),
+            Err(_) => return Err(IoError::Crs(format!("Unsupported CRS: {}", crs))),
+        }
+    };
+
+    if let Some(code) = code {
+        let exists: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM gpkg_spatial_ref_sys WHERE srs_id = ?",
+            [code],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            // 'undefined' is reserved for SRS IDs -1 and 0, so other codes need real WKT
+            let known = SRS_DEFINITIONS.iter().find(|(id, _, _)| *id == code);
+            let (name, definition) = match (known, wkt) {
+                (Some((_, name, known)), wkt) => (name.to_string(), wkt.unwrap_or(known).to_string()),
+                (None, Some(wkt)) => (wkt.split('"').nth(1).unwrap_or("Custom").to_string(), wkt.to_string()),
+                (None, None) => {
+                    let engine = u32::try_from(code)
+                        .map_err(|_| IoError::Crs(format!("Invalid EPSG code: {}", code)))
+                        .and_then(|code| Crs::from_epsg(code).map_err(|e| IoError::Crs(e.to_string())))?;
+                    let definition = engine.to_wkt().map_err(|e| IoError::Crs(e.to_string()))?;
+                    (engine.name, definition)
+                }
+            };
+            conn.execute(
+                "INSERT INTO gpkg_spatial_ref_sys (srs_name, srs_id, organization, organization_coordsys_id, definition) \
+                 VALUES (?1, ?2, 'EPSG', ?2, ?3)",
+                rusqlite::params![name, code, definition],
+            )?;
+        }
+        return Ok(code);
+    }
+
+    // WKT without an EPSG code gets an SRS ID of its own
+    let wkt = wkt.unwrap_or_default();
+    let existing = conn.query_row(
+        "SELECT srs_id FROM gpkg_spatial_ref_sys WHERE organization = 'NONE' AND definition = ?",
+        [wkt],
+        |row| row.get::<_, i32>(0),
+    );
+    match existing {
+        Ok(srs_id) => Ok(srs_id),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            let srs_id: i32 = conn.query_row(
+                "SELECT MAX(COALESCE(MAX(srs_id) + 1, 0), ?) FROM gpkg_spatial_ref_sys",
+                [CUSTOM_SRS_ID],
+                |row| row.get(0),
+            )?;
+            let name = wkt.split('"').nth(1).unwrap_or("Custom").to_string();
+            conn.execute(
+                "INSERT INTO gpkg_spatial_ref_sys (srs_name, srs_id, organization, organization_coordsys_id, definition) \
+                 VALUES (?1, ?2, 'NONE', ?2, ?3)",
+                rusqlite::params![name, srs_id, wkt],
+            )?;
+            Ok(srs_id)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Feature table of a layer
+struct LayerTable {
+    name: String,
+    geometry_column: String,
+    geometry_type: String,
+    srs_id: i32,
+    columns: Vec<String>,
+}
+
+impl LayerTable {
+    /// Load an existing layer
+    fn load(conn: &Connection, layer_name: &str) -> Result<Self> {
+        let (geometry_column, geometry_type, srs_id) = conn
+            .query_row(
+                "SELECT column_name, geometry_type_name, srs_id FROM gpkg_geometry_columns WHERE table_name = ?",
+                [layer_name],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)?)),
+            )
+            .map_err(|_| IoError::GeoPackage(format!("No geometry column found for layer: {}", layer_name)))?;
+
+        let columns = {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote(layer_name)))?;
+            let columns = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, i32>(5)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            columns
+                .into_iter()
+                .filter(|(name, pk)| *pk == 0 && *name != geometry_column)
+                .map(|(name, _)| name)
+                .collect()
+        };
+
+        Ok(Self {
+            name: layer_name.to_string(),
+            geometry_column,
+            geometry_type,
+            srs_id,
+            columns,
+        })
+    }
+
+    /// Add columns for properties the table does not have yet
+    fn add_columns(&mut self, conn: &Connection, collection: &FeatureCollection) -> Result<()> {
+        let mut types: BTreeMap<&str, Option<ColumnType>> = BTreeMap::new();
+        for feature in &collection.features {
+            for (key, value) in &feature.properties {
+                let seen = types.entry(key).or_default();
+                *seen = ColumnType::merge(*seen, ColumnType::of(value));
+            }
+        }
+
+        for (key, column_type) in types {
+            let taken = |name: &str| name.eq_ignore_ascii_case(key);
+            if taken("fid") || taken(&self.geometry_column) || self.columns.iter().any(|c| taken(c)) {
+                continue;
+            }
+            conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                quote(&self.name),
+                quote(key),
+                column_type.unwrap_or(ColumnType::Text).sql_type(),
+            ))?;
+            self.columns.push(key.to_string());
+        }
+        Ok(())
+    }
+
+    /// Insert features and update the layer extent
+    fn insert(mut self, conn: &Connection, collection: &FeatureCollection) -> Result<()> {
+        self.add_columns(conn, collection)?;
+
+        let incoming = layer_geometry_type(&collection.features);
+        if self.geometry_type != "GEOMETRY" && !collection.features.is_empty() && incoming != self.geometry_type {
+            conn.execute(
+                "UPDATE gpkg_geometry_columns SET geometry_type_name = 'GEOMETRY' WHERE table_name = ?",
+                [&self.name],
+            )?;
+        }
+
+        let mut names = vec![quote(&self.geometry_column)];
+        names.extend(self.columns.iter().map(|c| quote(c)));
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(&self.name),
+            names.join(", "),
+            placeholders
+        ))?;
+
+        let mut extent: Option<Rect<f64>> = None;
+        for feature in &collection.features {
+            let mut values = Vec::with_capacity(names.len());
+            values.push(match &feature.geometry {
+                Some(geometry) => {
+                    if let Some(rect) = geometry.bounding_rect() {
+                        extent = Some(match extent {
+                            Some(e) => Rect::new(
+                                (e.min().x.min(rect.min().x), e.min().y.min(rect.min().y)),
+                                (e.max().x.max(rect.max().x), e.max().y.max(rect.max().y)),
+                            ),
+                            None => rect,
+                        });
+                    }
+                    let geometry = core_geometry(geometry, feature.crs.as_deref());
+                    SqlValue::Blob(encode_gpkg_geometry(&geometry, self.srs_id))
+                }
+                None => SqlValue::Null,
+            });
+            for column in &self.columns {
+                values.push(sql_value(feature.properties.get(column)));
+            }
+            stmt.execute(rusqlite::params_from_iter(values))?;
+        }
+
+        if let Some(extent) = extent {
+            conn.execute(
+                "UPDATE gpkg_contents SET \
+                 min_x = MIN(COALESCE(min_x, ?1), ?1), min_y = MIN(COALESCE(min_y, ?2), ?2), \
+                 max_x = MAX(COALESCE(max_x, ?3), ?3), max_y = MAX(COALESCE(max_y, ?4), ?4) \
+                 WHERE table_name = ?5",
+                rusqlite::params![extent.min().x, extent.min().y, extent.max().x, extent.max().y, self.name],
+            )?;
+        }
+        conn.execute(
+            "UPDATE gpkg_contents SET last_change = strftime('%Y-%m-%dT%H:%M:%fZ','now') WHERE table_name = ?",
+            [&self.name],
+        )?;
+        Ok(())
+    }
+}
+
+/// Column type of a property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Boolean,
+    Integer,
+    Real,
+    Date,
+    DateTime,
+    Text,
+}
+
+impl ColumnType {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(ColumnType::Boolean),
+            Value::Number(n) if n.is_f64() => Some(ColumnType::Real),
+            Value::Number(_) => Some(ColumnType::Integer),
+            Value::String(s) if is_date(s) => Some(ColumnType::Date),
+            Value::String(s) if is_datetime(s) => Some(ColumnType::DateTime),
+            _ => Some(ColumnType::Text),
+        }
+    }
+
+    /// Type able to hold values of both types
+    fn merge(a: Option<Self>, b: Option<Self>) -> Option<Self> {
+        use ColumnType::*;
+
+        match (a, b) {
+            (None, t) | (t, None) => t,
+            (Some(a), Some(b)) if a == b => Some(a),
+            (Some(Integer | Real), Some(Integer | Real)) => Some(Real),
+            _ => Some(Text),
+        }
+    }
+
+    fn sql_type(self) -> &'static str {
+        match self {
+            ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Date => "DATE",
+            ColumnType::DateTime => "DATETIME",
+            ColumnType::Text => "TEXT",
+        }
+    }
+}
+
+/// Whether text is an ISO 8601 `YYYY-MM-DD` date
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes.iter().enumerate().all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit())
+}
+
+/// Whether text is an ISO 8601 `YYYY-MM-DDTHH:MM:SS` timestamp
+fn is_datetime(text: &str) -> bool {
+    text.len() >= 19
+        && text.is_char_boundary(10)
+        && is_date(&text[..10])
+        && text.as_bytes()[10] == b'T'
+        && text.as_bytes()[13] == b':'
+        && text.as_bytes()[16] == b':'
+}
+
+/// SQLite value of a property
+fn sql_value(value: Option<&Value>) -> SqlValue {
+    match value {
+        None | Some(Value::Null) => SqlValue::Null,
+        Some(Value::Bool(b)) => SqlValue::Integer(*b as i64),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Some(Value::String(s)) => SqlValue::Text(s.clone()),
+        Some(other) => SqlValue::Text(other.to_string()),
+    }
+}
+
+/// Quote an SQL identifier
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// CRS of a collection, or of its first feature that has one
+fn collection_crs(collection: &FeatureCollection) -> Option<&str> {
+    collection
+        .crs
+        .as_deref()
+        .or_else(|| collection.features.iter().find_map(|f| f.crs.as_deref()))
+}
+
+/// GeoPackage geometry type name of a geometry
+fn geometry_type_name(geometry: &Geometry<f64>) -> &'static str {
+    match geometry {
+        Geometry::Point(_) => "POINT",
+        Geometry::Line(_) | Geometry::LineString(_) => "LINESTRING",
+        Geometry::Polygon(_) | Geometry::Rect(_) | Geometry::Triangle(_) => "POLYGON",
+        Geometry::MultiPoint(_) => "MULTIPOINT",
+        Geometry::MultiLineString(_) => "MULTILINESTRING",
+        Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+        Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+    }
+}
+
+/// Geometry type name of a layer: the common type, or `GEOMETRY` when mixed
+fn layer_geometry_type(features: &[Feature]) -> &'static str {
+    let mut types = features
+        .iter()
+        .filter_map(|f| f.geometry.as_ref())
+        .map(geometry_type_name);
+
+    match types.next() {
+        Some(first) if types.all(|t| t == first) => first,
+        _ => "GEOMETRY",
+    }
+}
+
+/// Decode a GeoPackage binary geometry, keeping Z and M
+///
+/// `crs` is the CRS of the geometry's `srs_id`, which the blob only refers to.
+pub fn decode_gpkg_geometry(data: &[u8], crs: Crs) -> Result<CoreGeometry> {
+    if data.len() < 8 {
+        return Err(IoError::GeoPackage("Invalid GeoPackage geometry: too short".to_string()));
+    }
+
+    // GeoPackage Binary Format has a header:
+    // Bytes 0-1: Magic number (0x47, 0x50) = "GP"
+    // Byte 2: Version
+    // Byte 3: Flags
+    // Bytes 4-7: SRS ID
+    // Then WKB data follows
+
+    // Verify magic number
+    if data[0] != 0x47 || data[1] != 0x50 {
+        return Err(IoError::GeoPackage("Invalid GeoPackage geometry magic number".to_string()));
+    }
+
+    // Extract flags to determine envelope size
+    let flags = data[3];
+    let envelope_type = (flags >> 1) & 0x07;
+
+    // Calculate envelope size
+    let envelope_size = match envelope_type {
+        0 => 0,   // No envelope
+        1 => 32,  // XY
+        2 => 48,  // XYZ
+        3 => 48,  // XYM
+        4 => 64,  // XYZM
+        _ => return Err(IoError::GeoPackage("Invalid envelope type".to_string())),
+    };
+
+    // WKB data starts after header (8 bytes) + envelope
+    let wkb_offset = 8 + envelope_size;
+
+    if data.len() < wkb_offset {
+        return Err(IoError::GeoPackage("Invalid GeoPackage geometry: header overflow".to_string()));
+    }
+
+    CoreGeometry::from_wkb(&data[wkb_offset..], crs).map_err(|e| IoError::GeoPackage(e.to_string()))
+}
+
+/// Encode a geometry as a GeoPackage binary blob
+///
+/// The envelope is XYZ for geometries with Z and XY otherwise.
+pub fn encode_gpkg_geometry(geometry: &CoreGeometry, srs_id: i32) -> Vec<u8> {
+    let envelope = geometry.to_geo().bounding_rect().map(|_| geometry.bounds());
+
+    // Little endian, with an envelope or the empty flag
+    let flags = match &envelope {
+        Some(bbox) if bbox.has_z() => 0x01 | (2 << 1),
+        Some(_) => 0x01 | (1 << 1),
+        None => 0x01 | (1 << 4),
+    };
+
+    let mut data = vec![0x47, 0x50, 0x00, flags];
+    data.extend_from_slice(&srs_id.to_le_bytes());
+    if let Some(bbox) = envelope {
+        let z = bbox.min_z.zip(bbox.max_z).map(|(min, max)| [min, max]);
+        for value in [bbox.min_x, bbox.max_x, bbox.min_y, bbox.max_y].into_iter().chain(z.into_iter().flatten()) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&geometry.to_wkb());
+    data
+}
+
+/// Bounds of a GeoPackage binary geometry, `None` when it is empty
+fn gpkg_envelope(data: &[u8]) -> Result<Option<Rect<f64>>> {
+    if data.len() < 8 || data[0] != 0x47 || data[1] != 0x50 {
+        return Err(IoError::GeoPackage("Invalid GeoPackage geometry".to_string()));
+    }
+
+    let flags = data[3];
+    if flags & (1 << 4) != 0 {
+        return Ok(None);
+    }
+
+    if (flags >> 1) & 0x07 == 0 {
+        return Ok(decode_gpkg_geometry(data, Crs::wgs84())?.to_geo().bounding_rect());
+    }
+
+    if data.len() < 40 {
+        return Err(IoError::GeoPackage("Invalid GeoPackage geometry: header overflow".to_string()));
+    }
+    let little_endian = flags & 0x01 != 0;
+    let value = |i: usize| {
+        let bytes: [u8; 8] = data[8 + i * 8..16 + i * 8].try_into().unwrap_or_default();
+        if little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        }
+    };
+
+    // Envelopes are ordered min x, max x, min y, max y
+    Ok(Some(Rect::new((value(0), value(2)), (value(1), value(3)))))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use geo_types::{line_string, point, polygon, MultiPolygon};
+    use serde_json::json;
 
     #[test]
     fn test_geopackage_reader_creation() {
@@ -298,4 +1084,137 @@ mod tests {
     fn test_geopackage_writer_creation() {
         let _writer = GeoPackageWriter::new();
     }
+
+    #[test]
+    fn test_wkb_roundtrip() {
+        let geometry = Geometry::MultiPolygon(MultiPolygon(vec![polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 0.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0), (x: 1.0, y: 1.0)]],
+        )]));
+
+        let blob = encode_gpkg_geometry(&core_geometry(&geometry, None), 4326);
+        assert_eq!(decode_gpkg_geometry(&blob, Crs::wgs84()).unwrap().to_geo(), geometry);
+        assert_eq!(
+            gpkg_envelope(&blob).unwrap(),
+            Some(Rect::new((0.0, 0.0), (4.0, 4.0)))
+        );
+    }
+
+    #[test]
+    fn test_blob_keeps_z_and_m() {
+        let line = CoreGeometry::from_wkt("LINESTRING ZM (0 0 10 1, 3 4 12 2)", Crs::web_mercator()).unwrap();
+        let blob = encode_gpkg_geometry(&line, 3857);
+        // XYZ envelope
+        assert_eq!((blob[3] >> 1) & 0x07, 2);
+        assert_eq!(blob[40..48], 10f64.to_le_bytes());
+        assert_eq!(blob[48..56], 12f64.to_le_bytes());
+        assert_eq!(decode_gpkg_geometry(&blob, Crs::web_mercator()).unwrap(), line);
+        assert_eq!(gpkg_envelope(&blob).unwrap(), Some(Rect::new((0.0, 0.0), (3.0, 4.0))));
+    }
+
+    #[test]
+    fn test_write_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.gpkg");
+
+        let mut roads = FeatureCollection::from_features(vec![
+            Feature::new(Some(Geometry::LineString(line_string![(x: 0.0, y: 0.0), (x: 3.0, y: 4.0)])))
+                .with_property("name", json!("Main St"))
+                .with_property("lanes", json!(2))
+                .with_property("paved", json!(true)),
+        ]);
+        roads.crs = Some("EPSG:3857".to_string());
+        let stops = FeatureCollection::from_features(vec![
+            Feature::new(Some(Geometry::Point(point!(x: 1.0, y: 2.0)))).with_property("name", json!("Depot")),
+        ]);
+
+        let writer = GeoPackageWriter::new();
+        writer
+            .write_layers(&path, &[("roads", &roads), ("stops", &stops)], WriteMode::Overwrite)
+            .unwrap();
+
+        let reader = GeoPackageReader::new();
+        assert_eq!(reader.list_layers(&path).unwrap(), vec!["roads", "stops"]);
+
+        let read = reader.read_layer(&path, "roads").unwrap();
+        assert_eq!(read.crs.as_deref(), Some("EPSG:3857"));
+        assert_eq!(read.features[0].geometry, roads.features[0].geometry);
+        assert_eq!(read.features[0].properties["lanes"], json!(2));
+        assert_eq!(read.features[0].properties["paved"], json!(true));
+
+        let conn = Connection::open(&path).unwrap();
+        let application_id: i32 = conn.query_row("PRAGMA application_id", [], |r| r.get(0)).unwrap();
+        assert_eq!(application_id, APPLICATION_ID);
+        let indexed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM rtree_stops_geom WHERE minx = 1.0 AND maxy = 2.0", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(indexed, 1);
+        let extent: (f64, f64) = conn
+            .query_row("SELECT max_x, max_y FROM gpkg_contents WHERE table_name = 'roads'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(extent, (3.0, 4.0));
+    }
+
+    #[test]
+    fn test_append_and_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sites.gpkg");
+        let site = |x: f64| Feature::new(Some(Geometry::Point(point!(x: x, y: 0.0))));
+
+        let writer = GeoPackageWriter::new();
+        writer
+            .write(&path, &FeatureCollection::from_features(vec![site(1.0).with_property("a", json!(1))]))
+            .unwrap();
+        writer
+            .append(&path, &FeatureCollection::from_features(vec![site(2.0).with_property("b", json!("x"))]))
+            .unwrap();
+
+        let read = GeoPackageReader::new().read_layer(&path, "sites").unwrap();
+        assert_eq!(read.features.len(), 2);
+        assert_eq!(read.features[1].properties["b"], json!("x"));
+        assert_eq!(read.features[1].properties["a"], Value::Null);
+        assert_eq!(read.features[1].id.as_deref(), Some("2"));
+
+        writer
+            .write(&path, &FeatureCollection::from_features(vec![site(5.0)]))
+            .unwrap();
+        let read = GeoPackageReader::new().read_layer(&path, "sites").unwrap();
+        assert_eq!(read.features.len(), 1);
+        assert!(!read.features[0].properties.contains_key("a"));
+
+        let conn = Connection::open(&path).unwrap();
+        let indexed: i64 = conn.query_row("SELECT COUNT(*) FROM rtree_sites_geom", [], |r| r.get(0)).unwrap();
+        assert_eq!(indexed, 1);
+    }
+
+    #[test]
+    fn test_srs_definitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("utm.gpkg");
+        let mut collection =
+            FeatureCollection::from_features(vec![Feature::new(Some(Geometry::Point(point!(x: 500000.0, y: 0.0))))]);
+        collection.crs = Some("EPSG:32632".to_string());
+
+        let writer = GeoPackageWriter::new();
+        writer.write(&path, &collection).unwrap();
+        let conn = Connection::open(&path).unwrap();
+        let definition: String = conn
+            .query_row("SELECT definition FROM gpkg_spatial_ref_sys WHERE srs_id = 32632", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(Crs::from_wkt(&definition).unwrap().epsg, Some(32632));
+
+        // Appends must match the layer's SRS; features without a CRS take it
+        let mut mercator = collection.clone();
+        mercator.crs = Some("EPSG:3857".to_string());
+        assert!(matches!(writer.append(&path, &mercator), Err(IoError::Crs(_))));
+        mercator.crs = None;
+        writer.append(&path, &mercator).unwrap();
+        assert_eq!(GeoPackageReader::new().read_layer(&path, "utm").unwrap().features.len(), 2);
+
+        // Codes without a definition are refused rather than stored as 'undefined'
+        collection.crs = Some("EPSG:999999".to_string());
+        assert!(writer.write(&dir.path().join("unknown.gpkg"), &collection).is_err());
+    }
 }
@@ -1,8 +1,9 @@
 //! KML and KMZ file support
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
 use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
-use futures::stream;
+use futures::{stream, StreamExt};
 use geo_types::{Coord, Geometry, LineString, Point, Polygon};
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::{Reader as XmlReader, Writer as XmlWriter};
@@ -303,6 +304,17 @@ impl KmlWriter {
         Self { pretty: true }
     }
 
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["PRETTY"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        Ok(Self {
+            pretty: options.get_bool("PRETTY")?.unwrap_or(true),
+        })
+    }
+
     /// Write geometry as KML
     fn write_geometry<W: Write>(
         writer: &mut XmlWriter<W>,
@@ -405,11 +417,17 @@ impl Writer for KmlWriter {
         Ok(())
     }
 
-    fn write_stream<S>(&self, _path: &Path, _stream: S) -> Result<()>
-    where
-        S: futures::Stream<Item = Result<Feature>> + Send + 'static,
-    {
-        Err(IoError::UnsupportedFormat("KML streaming write not yet implemented".to_string()))
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
+        // The document is written in one pass, so the stream is collected first
+        let features = futures::executor::block_on(async {
+            let mut features = Vec::new();
+            while let Some(result) = stream.next().await {
+                features.push(result?);
+            }
+            Ok::<_, IoError>(features)
+        })?;
+
+        self.write(path, &FeatureCollection::from_features(features))
     }
 
     fn append(&self, _path: &Path, _collection: &FeatureCollection) -> Result<()> {
@@ -60,6 +60,7 @@
 
 pub mod csv;
 pub mod detection;
+pub mod driver;
 pub mod error;
 pub mod geojson;
 pub mod geotiff;
@@ -70,40 +71,63 @@ pub mod traits;
 pub mod wkt;
 
 // Re-export commonly used types
+pub use driver::{FormatDriver, LayerOptions};
 pub use error::{IoError, Result};
-pub use traits::{Feature, FeatureCollection, Format, Metadata, Reader, Writer};
+pub use traits::{Feature, FeatureCollection, FeatureStream, Format, Metadata, Reader, Writer};
 
-use csv::{CsvReader, CsvWriter};
 use detection::detect_format;
-use geojson::{GeoJsonReader, GeoJsonWriter};
-use gpkg::GeoPackageReader;
-use kml::{KmlReader, KmlWriter, KmzReader};
-use shapefile::{ShapefileReader, ShapefileWriter};
 use std::path::Path;
-use wkt::{WktReader, WktWriter};
+use std::sync::Arc;
 
 /// Central registry for all supported formats
+///
+/// Readers and writers come from the registered [`FormatDriver`]s, so formats
+/// added by other crates are handled like the built-in ones.
 pub struct FormatRegistry;
 
 impl FormatRegistry {
+    /// Register a format driver
+    ///
+    /// Drivers registered later take precedence, so a driver for a built-in
+    /// format replaces the built-in reader or writer.
+    pub fn register_driver(driver: impl FormatDriver + 'static) {
+        driver::register(Arc::new(driver));
+    }
+
+    /// All registered drivers, most recently registered first
+    pub fn drivers() -> Vec<Arc<dyn FormatDriver>> {
+        driver::drivers()
+    }
+
+    /// Find a driver by name or file extension
+    pub fn driver_by_name(name: &str) -> Option<Arc<dyn FormatDriver>> {
+        driver::drivers().into_iter().find(|driver| {
+            driver.name().eq_ignore_ascii_case(name)
+                || driver.extensions().iter().any(|ext| ext.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Driver of a format not in [`Format`] that claims the file extension
+    fn external_driver(path: &Path) -> Option<Arc<dyn FormatDriver>> {
+        let ext = path.extension()?.to_str()?;
+        driver::drivers().into_iter().find(|driver| {
+            driver.format().is_none() && driver.extensions().iter().any(|e| e.eq_ignore_ascii_case(ext))
+        })
+    }
+
     /// Auto-detect format and read file
     pub fn read_auto(path: &Path) -> Result<FeatureCollection> {
+        if let Some(reader) = Self::external_driver(path).and_then(|driver| driver.reader()) {
+            return reader.read(path);
+        }
+
         let format = detect_format(path)?;
         Self::read_with_format(path, format)
     }
 
     /// Read file with specified format
     pub fn read_with_format(path: &Path, format: Format) -> Result<FeatureCollection> {
-        match format {
-            Format::GeoJson => GeoJsonReader::new().read(path),
-            Format::Shapefile => ShapefileReader::new().read(path),
-            Format::Kml => KmlReader::new().read(path),
-            Format::Kmz => KmzReader::new().read(path),
-            Format::GeoPackage => GeoPackageReader::new().read(path),
-            Format::Wkt => WktReader::new().read(path),
-            Format::Csv => CsvReader::new().read(path),
-            _ => Err(IoError::UnsupportedFormat(format!("{:?}", format))),
-        }
+        Self::get_reader(format)?.read(path)
     }
 
     /// Write file with specified format
@@ -112,14 +136,17 @@ impl FormatRegistry {
         collection: &FeatureCollection,
         format: Format,
     ) -> Result<()> {
-        match format {
-            Format::GeoJson => GeoJsonWriter::new().write(path, collection),
-            Format::Shapefile => ShapefileWriter::new().write(path, collection),
-            Format::Kml => KmlWriter::new().write(path, collection),
-            Format::Wkt => WktWriter::new().write(path, collection),
-            Format::Csv => CsvWriter::new().write(path, collection),
-            _ => Err(IoError::UnsupportedFormat(format!("{:?}", format))),
-        }
+        Self::get_writer(format)?.write(path, collection)
+    }
+
+    /// Write file with specified format and layer creation options
+    pub fn write_with_options(
+        path: &Path,
+        collection: &FeatureCollection,
+        format: Format,
+        options: &LayerOptions,
+    ) -> Result<()> {
+        Self::get_writer_with_options(format, options)?.write(path, collection)
     }
 
     /// Auto-detect format from extension and write
@@ -129,39 +156,40 @@ impl FormatRegistry {
             .and_then(|e| e.to_str())
             .ok_or_else(|| IoError::UnknownFormat(path.to_path_buf()))?;
 
-        let format = Format::from_extension(ext)
-            .ok_or_else(|| IoError::UnknownFormat(path.to_path_buf()))?;
+        if let Some(format) = Format::from_extension(ext) {
+            return Self::write_with_format(path, collection, format);
+        }
 
-        Self::write_with_format(path, collection, format)
+        match Self::external_driver(path).and_then(|driver| driver.writer(&LayerOptions::new())) {
+            Some(writer) => writer?.write(path, collection),
+            None => Err(IoError::UnknownFormat(path.to_path_buf())),
+        }
     }
 
     /// Get reader for format
-    pub fn get_reader(format: Format) -> Box<dyn Reader> {
-        match format {
-            Format::GeoJson => Box::new(GeoJsonReader::new()),
-            Format::Shapefile => Box::new(ShapefileReader::new()),
-            Format::Kml => Box::new(KmlReader::new()),
-            Format::Kmz => Box::new(KmzReader::new()),
-            Format::GeoPackage => Box::new(GeoPackageReader::new()),
-            Format::Wkt => Box::new(WktReader::new()),
-            Format::Csv => Box::new(CsvReader::new()),
-            _ => Box::new(GeoJsonReader::new()), // Default fallback
-        }
+    pub fn get_reader(format: Format) -> Result<Box<dyn Reader>> {
+        driver::drivers()
+            .iter()
+            .filter(|driver| driver.format() == Some(format))
+            .find_map(|driver| driver.reader())
+            .ok_or_else(|| IoError::UnsupportedFormat(format!("Reading {:?} is not supported", format)))
     }
 
     /// Get writer for format
-    /// Note: This function is commented out because Writer trait is not object-safe
-    /// due to the generic write_stream method. Use concrete writer types instead.
-    // pub fn get_writer(format: Format) -> Box<dyn Writer> {
-    //     match format {
-    //         Format::GeoJson => Box::new(GeoJsonWriter::new()),
-    //         Format::Shapefile => Box::new(ShapefileWriter::new()),
-    //         Format::Kml => Box::new(KmlWriter::new()),
-    //         Format::Wkt => Box::new(WktWriter::new()),
-    //         Format::Csv => Box::new(CsvWriter::new()),
-    //         _ => Box::new(GeoJsonWriter::new()), // Default fallback
-    //     }
-    // }
+    pub fn get_writer(format: Format) -> Result<Box<dyn Writer>> {
+        Self::get_writer_with_options(format, &LayerOptions::new())
+    }
+
+    /// Get writer for format, configured with layer creation options
+    pub fn get_writer_with_options(format: Format, options: &LayerOptions) -> Result<Box<dyn Writer>> {
+        driver::drivers()
+            .iter()
+            .filter(|driver| driver.format() == Some(format))
+            .find_map(|driver| driver.writer(options))
+            .unwrap_or_else(|| {
+                Err(IoError::UnsupportedFormat(format!("Writing {:?} is not supported", format)))
+            })
+    }
 
     /// List all supported formats
     pub fn supported_formats() -> Vec<Format> {
@@ -181,24 +209,14 @@ impl FormatRegistry {
 
     /// Check if a format is supported for reading
     pub fn supports_reading(format: Format) -> bool {
-        matches!(
-            format,
-            Format::GeoJson
-                | Format::Shapefile
-                | Format::Kml
-                | Format::Kmz
-                | Format::GeoPackage
-                | Format::Wkt
-                | Format::Csv
-        )
+        Self::get_reader(format).is_ok()
     }
 
     /// Check if a format is supported for writing
     pub fn supports_writing(format: Format) -> bool {
-        matches!(
-            format,
-            Format::GeoJson | Format::Kml | Format::Wkt | Format::Csv
-        )
+        driver::drivers()
+            .iter()
+            .any(|driver| driver.format() == Some(format) && driver.writer(&LayerOptions::new()).is_some())
     }
 }
 
@@ -239,6 +257,85 @@ mod tests {
         assert!(FormatRegistry::supports_writing(Format::GeoJson));
         assert!(FormatRegistry::supports_writing(Format::Kml));
         assert!(FormatRegistry::supports_writing(Format::Wkt));
+        assert!(FormatRegistry::supports_writing(Format::Shapefile));
+        assert!(FormatRegistry::supports_writing(Format::GeoPackage));
+    }
+
+    #[test]
+    fn test_get_writer() {
+        for format in [Format::GeoJson, Format::Shapefile, Format::GeoPackage, Format::Csv] {
+            assert!(FormatRegistry::get_writer(format).is_ok());
+        }
+        assert!(FormatRegistry::get_writer(Format::Kmz).is_err());
+
+        let options = LayerOptions::new().with("SPATIAL_INDEX", "NO");
+        assert!(FormatRegistry::get_writer_with_options(Format::GeoPackage, &options).is_ok());
+        assert!(FormatRegistry::get_writer_with_options(Format::GeoJson, &options).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_inside_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let formats = [
+            (Format::GeoJson, "points.geojson"),
+            (Format::Shapefile, "points.shp"),
+            (Format::Kml, "points.kml"),
+            (Format::GeoPackage, "points.gpkg"),
+            (Format::Wkt, "points.wkt"),
+            (Format::Wkb, "points.wkb"),
+            (Format::Csv, "points.csv"),
+        ];
+
+        for (format, name) in formats {
+            let features: Vec<_> = (0..3)
+                .map(|i| {
+                    Ok(Feature::new(Some(geo_types::Point::new(i as f64, 1.0).into()))
+                        .with_property("id", serde_json::json!(i)))
+                })
+                .collect();
+            let path = dir.path().join(name);
+            FormatRegistry::get_writer(format)
+                .unwrap()
+                .write_stream(&path, Box::pin(futures::stream::iter(features)))
+                .unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+            assert!(path.exists(), "{:?}", format);
+        }
+
+        let points = FormatRegistry::read_with_format(&dir.path().join("points.csv"), Format::Csv).unwrap();
+        assert_eq!(points.len(), 3);
+    }
+
+    #[test]
+    fn test_register_driver() {
+        struct GmlDriver;
+
+        impl FormatDriver for GmlDriver {
+            fn name(&self) -> &str {
+                "Test GML"
+            }
+
+            fn format(&self) -> Option<Format> {
+                Some(Format::Gml)
+            }
+
+            fn extensions(&self) -> &[&str] {
+                &["gml"]
+            }
+
+            fn reader(&self) -> Option<Box<dyn Reader>> {
+                Some(Box::new(geojson::GeoJsonReader::new()))
+            }
+
+            fn writer(&self, _options: &LayerOptions) -> Option<Result<Box<dyn Writer>>> {
+                None
+            }
+        }
+
+        assert!(!FormatRegistry::supports_reading(Format::Gml));
+        FormatRegistry::register_driver(GmlDriver);
+        assert!(FormatRegistry::supports_reading(Format::Gml));
+        assert!(!FormatRegistry::supports_writing(Format::Gml));
+        assert_eq!(FormatRegistry::driver_by_name("test gml").unwrap().name(), "Test GML");
     }
 
     #[test]
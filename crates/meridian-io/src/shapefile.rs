@@ -1,14 +1,38 @@
 //! Shapefile reading and writing support
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
 use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
-use futures::{stream, Stream};
-use geo_types::Geometry;
+use futures::{stream, StreamExt};
+use geo::orient::{Direction, Orient};
+use geo::{Contains, Winding};
+use geo_types::{Coord, Geometry, LineString, Polygon};
+use meridian_core::crs::Crs;
 use serde_json::Value;
-use std::collections::HashMap;
+use shapefile::dbase::{self, FieldName, FieldValue, TableWriter, TableWriterBuilder};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Maximum size of a .shp or .dbf file, set by the 32-bit offsets in the format
+pub const MAX_FILE_SIZE: u64 = i32::MAX as u64;
+
+/// Maximum length of a DBF field name
+pub const MAX_FIELD_NAME_LENGTH: usize = 10;
+
+/// Size of the .shp and .shx file headers
+const HEADER_LENGTH: u64 = 100;
+
+/// Maximum width of a DBF character field
+const MAX_CHARACTER_WIDTH: usize = 254;
+
+/// Width of character fields whose values are all null
+const DEFAULT_CHARACTER_WIDTH: usize = 80;
+
+/// Maximum width and decimal places of a DBF numeric field
+const MAX_NUMERIC_WIDTH: usize = 24;
+const MAX_DECIMALS: usize = 15;
 
 /// Shapefile reader
 pub struct ShapefileReader;
@@ -95,80 +119,15 @@ impl ShapefileReader {
                     Ok(Some(Geometry::MultiLineString(geo_types::MultiLineString::new(lines))))
                 }
             }
-            Shape::Polygon(pg) => {
-                let rings = pg.rings();
-                if rings.len() == 1 {
-                    // Single Polygon
-                    let exterior: Vec<_> = rings[0]
-                        .points()
-                        .iter()
-                        .map(|p| geo_types::Coord { x: p.x, y: p.y })
-                        .collect();
-                    let polygon = geo_types::Polygon::new(
-                        geo_types::LineString::from(exterior),
-                        vec![],
-                    );
-                    Ok(Some(Geometry::Polygon(polygon)))
-                } else {
-                    // MultiPolygon or Polygon with holes
-                    // Note: Shapefile polygon parts can be exterior rings or holes
-                    // This is a simplified conversion
-                    let mut polygons = Vec::new();
-                    for ring in rings {
-                        let coords: Vec<_> = ring
-                            .points()
-                            .iter()
-                            .map(|p| geo_types::Coord { x: p.x, y: p.y })
-                            .collect();
-                        let polygon = geo_types::Polygon::new(
-                            geo_types::LineString::from(coords),
-                            vec![],
-                        );
-                        polygons.push(polygon);
-                    }
-                    Ok(Some(Geometry::MultiPolygon(geo_types::MultiPolygon::from(polygons))))
-                }
-            }
-            Shape::PolygonM(pg) => {
-                let mut polygons = Vec::new();
-                for ring in pg.rings() {
-                    let coords: Vec<_> = ring
-                        .points()
-                        .iter()
-                        .map(|p| geo_types::Coord { x: p.x, y: p.y })
-                        .collect();
-                    let polygon = geo_types::Polygon::new(
-                        geo_types::LineString::from(coords),
-                        vec![],
-                    );
-                    polygons.push(polygon);
-                }
-                if polygons.len() == 1 {
-                    Ok(Some(Geometry::Polygon(polygons.into_iter().next().unwrap())))
-                } else {
-                    Ok(Some(Geometry::MultiPolygon(geo_types::MultiPolygon::from(polygons))))
-                }
-            }
-            Shape::PolygonZ(pg) => {
-                let mut polygons = Vec::new();
-                for ring in pg.rings() {
-                    let coords: Vec<_> = ring
-                        .points()
-                        .iter()
-                        .map(|p| geo_types::Coord { x: p.x, y: p.y })
-                        .collect();
-                    let polygon = geo_types::Polygon::new(
-                        geo_types::LineString::from(coords),
-                        vec![],
-                    );
-                    polygons.push(polygon);
-                }
-                if polygons.len() == 1 {
-                    Ok(Some(Geometry::Polygon(polygons.into_iter().next().unwrap())))
-                } else {
-                    Ok(Some(Geometry::MultiPolygon(geo_types::MultiPolygon::from(polygons))))
-                }
-            }
+            Shape::Polygon(pg) => Ok(polygon_from_rings(
+                pg.rings().iter().map(|ring| Self::ring_coords(ring.points().iter().map(|p| (p.x, p.y)))),
+            )),
+            Shape::PolygonM(pg) => Ok(polygon_from_rings(
+                pg.rings().iter().map(|ring| Self::ring_coords(ring.points().iter().map(|p| (p.x, p.y)))),
+            )),
+            Shape::PolygonZ(pg) => Ok(polygon_from_rings(
+                pg.rings().iter().map(|ring| Self::ring_coords(ring.points().iter().map(|p| (p.x, p.y)))),
+            )),
             Shape::Multipoint(mp) => {
                 let points: Vec<_> = mp.points()
                     .iter()
@@ -195,6 +154,11 @@ impl ShapefileReader {
         }
     }
 
+    /// Collect ring points as a line string
+    fn ring_coords(points: impl Iterator<Item = (f64, f64)>) -> LineString<f64> {
+        points.map(|(x, y)| Coord { x, y }).collect()
+    }
+
     /// Convert dbase field value to JSON value
     fn convert_field_value(value: &shapefile::dbase::FieldValue) -> Value {
         use shapefile::dbase::FieldValue;
@@ -206,12 +170,23 @@ impl ShapefileReader {
             FieldValue::Numeric(n) => n.map(Value::from).unwrap_or(Value::Null),
             FieldValue::Logical(b) => b.map(Value::from).unwrap_or(Value::Null),
             FieldValue::Date(d) => d.as_ref()
-                .map(|d| Value::String(d.to_string()))
+                .map(|d| Value::String(format!("{:04}-{:02}-{:02}", d.year(), d.month(), d.day())))
                 .unwrap_or(Value::Null),
             FieldValue::Float(f) => f.map(Value::from).unwrap_or(Value::Null),
             FieldValue::Integer(i) => Value::from(*i),
             FieldValue::Double(d) => Value::from(*d),
-            FieldValue::DateTime(_) => Value::Null, // TODO: proper datetime conversion
+            FieldValue::DateTime(dt) => {
+                let (date, time) = (dt.date(), dt.time());
+                Value::String(format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                    date.year(),
+                    date.month(),
+                    date.day(),
+                    time.hours(),
+                    time.minutes(),
+                    time.seconds()
+                ))
+            }
             FieldValue::Currency(_) => Value::Null,
             FieldValue::Memo(_) => Value::Null,
         }
@@ -310,13 +285,250 @@ impl Reader for ShapefileReader {
     }
 }
 
+/// Build a polygon geometry from shapefile rings
+///
+/// Clockwise rings are exteriors and counter-clockwise rings are holes of the
+/// exterior that contains them.
+fn polygon_from_rings(rings: impl Iterator<Item = LineString<f64>>) -> Option<Geometry<f64>> {
+    let mut exteriors: Vec<(LineString<f64>, Vec<LineString<f64>>)> = Vec::new();
+    let mut holes = Vec::new();
+    for ring in rings {
+        if ring.is_cw() {
+            exteriors.push((ring, Vec::new()));
+        } else {
+            holes.push(ring);
+        }
+    }
+
+    for hole in holes {
+        let shell = hole.0.first().and_then(|first| {
+            exteriors
+                .iter()
+                .position(|(exterior, _)| Polygon::new(exterior.clone(), vec![]).contains(first))
+        });
+        match shell {
+            Some(index) => exteriors[index].1.push(hole),
+            // Unmatched holes are most likely exteriors with the wrong winding
+            None => exteriors.push((hole, Vec::new())),
+        }
+    }
+
+    let mut polygons: Vec<Polygon<f64>> = exteriors
+        .into_iter()
+        .map(|(exterior, interiors)| Polygon::new(exterior, interiors))
+        .collect();
+
+    match polygons.len() {
+        0 => None,
+        1 => polygons.pop().map(Geometry::Polygon),
+        _ => Some(Geometry::MultiPolygon(geo_types::MultiPolygon::new(
+            polygons,
+        ))),
+    }
+}
+
+/// Outcome of writing a shapefile
+#[derive(Debug, Clone, Default)]
+pub struct ShapefileWriteReport {
+    /// Every .shp written, one per geometry type and per 2 GB part
+    pub files: Vec<PathBuf>,
+
+    /// Properties renamed to fit DBF field names (property -> field)
+    pub renamed_fields: HashMap<String, String>,
+}
+
 /// Shapefile writer
-pub struct ShapefileWriter;
+///
+/// A shapefile holds a single geometry type, so mixed collections are split
+/// into one shapefile per type, suffixed `_point`, `_multipoint`, `_line` and
+/// `_polygon`. Features without geometry are written as null shapes alongside
+/// the first geometry type. Files that would exceed the size limit continue
+/// in numbered parts (`name_2.shp`, ...). Properties renamed to fit DBF field
+/// names are recorded in `name.fields.json`, so appends keep them in the same
+/// fields.
+pub struct ShapefileWriter {
+    /// Maximum size of each .shp and .dbf file
+    pub max_file_size: u64,
+}
 
 impl ShapefileWriter {
     /// Create a new shapefile writer
     pub fn new() -> Self {
-        Self
+        Self {
+            max_file_size: MAX_FILE_SIZE,
+        }
+    }
+
+    /// Set the maximum size of each .shp and .dbf file
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size.min(MAX_FILE_SIZE);
+        self
+    }
+
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["MAX_FILE_SIZE"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        let writer = Self::new();
+        Ok(match options.get_parsed("MAX_FILE_SIZE")? {
+            Some(max_file_size) => writer.with_max_file_size(max_file_size),
+            None => writer,
+        })
+    }
+
+    /// Write a collection, reporting the files written and renamed fields
+    pub fn write_with_report(
+        &self,
+        path: &Path,
+        collection: &FeatureCollection,
+    ) -> Result<ShapefileWriteReport> {
+        let (fields, renamed_fields) = infer_schema(&collection.features, &HashMap::new());
+        let prj = collection_crs(collection).map(prj_wkt).transpose()?;
+        let groups = group_by_kind(&collection.features);
+
+        let mut report = ShapefileWriteReport {
+            files: Vec::new(),
+            renamed_fields,
+        };
+
+        if groups.is_empty() {
+            report.files = self.write_group(path, ShapeKind::Null, &[], &fields, prj.as_deref())?;
+        }
+        for (kind, features) in &groups {
+            let target = if groups.len() == 1 {
+                path.to_path_buf()
+            } else {
+                kind_path(path, *kind)
+            };
+            report.files.extend(self.write_group(
+                &target,
+                *kind,
+                features,
+                &fields,
+                prj.as_deref(),
+            )?);
+        }
+
+        Ok(report)
+    }
+
+    /// Append a collection, reporting the files rewritten and renamed fields
+    ///
+    /// Each geometry type is appended to the existing shapefile of that type,
+    /// which is read back and rewritten so the DBF schema can widen.
+    pub fn append_with_report(
+        &self,
+        path: &Path,
+        collection: &FeatureCollection,
+    ) -> Result<ShapefileWriteReport> {
+        let groups = group_by_kind(&collection.features);
+        let mut report = ShapefileWriteReport::default();
+
+        for (kind, features) in groups.iter() {
+            let (target, kind) = append_target(path, *kind, groups.len() == 1)?;
+
+            // Existing records are read back under their DBF field names, which
+            // the field map turns back into the properties they were written from
+            let renamed = read_field_map(&target)?;
+            let properties: HashMap<String, String> = renamed
+                .iter()
+                .map(|(property, field)| (field.clone(), property.clone()))
+                .collect();
+
+            let mut existing = FeatureCollection::new();
+            for part in existing_parts(&target) {
+                let collection = ShapefileReader::new().read(&part)?;
+                existing.crs = existing.crs.or(collection.crs);
+                existing.features.extend(
+                    collection
+                        .features
+                        .iter()
+                        .map(|feature| rename_properties(feature, &properties)),
+                );
+            }
+            let prj = match existing.crs.take() {
+                Some(wkt) => Some(wkt),
+                None => collection_crs(collection).map(prj_wkt).transpose()?,
+            };
+
+            existing.features.extend(features.iter().cloned());
+            let (fields, renamed_fields) = infer_schema(&existing.features, &renamed);
+            report.renamed_fields.extend(renamed_fields);
+            report.files.extend(self.write_group(
+                &target,
+                kind,
+                &existing.features,
+                &fields,
+                prj.as_deref(),
+            )?);
+        }
+
+        Ok(report)
+    }
+
+    /// Write one shapefile, continuing in numbered parts at the size limit
+    fn write_group(
+        &self,
+        path: &Path,
+        kind: ShapeKind,
+        features: &[Feature],
+        fields: &[DbfField],
+        prj: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        let record_length = 1 + fields.iter().map(|f| f.width as u64).sum::<u64>();
+        let dbf_header = 32 + 32 * fields.len() as u64 + 1;
+
+        let mut files = vec![path.to_path_buf()];
+        let mut part = ShapefilePart::create(path, kind, fields, prj)?;
+
+        for feature in features {
+            let (content, bbox) = encode_shape(feature.geometry.as_ref());
+            let shp_record = 8 + content.len() as u64;
+
+            if HEADER_LENGTH + shp_record > self.max_file_size
+                || dbf_header + record_length + 1 > self.max_file_size
+            {
+                return Err(IoError::Shapefile(format!(
+                    "Feature record exceeds the {} byte file size limit",
+                    self.max_file_size
+                )));
+            }
+            if part.shp.length + shp_record > self.max_file_size
+                || part.dbf_length + record_length + 1 > self.max_file_size
+            {
+                part.finish()?;
+                let next = part_path(path, files.len());
+                part = ShapefilePart::create(&next, kind, fields, prj)?;
+                files.push(next);
+            }
+
+            let mut record = dbase::Record::default();
+            for field in fields {
+                record.insert(
+                    field.name.clone(),
+                    field.value(feature.properties.get(&field.property)),
+                );
+            }
+            part.write(&content, bbox, &record, record_length)?;
+        }
+        part.finish()?;
+
+        // Drop parts left over from an earlier, larger write
+        let mut index = files.len();
+        while part_path(path, index).exists() {
+            for ext in ["shp", "shx", "dbf", "prj", "cpg"] {
+                let stale = part_path(path, index).with_extension(ext);
+                if stale.exists() {
+                    std::fs::remove_file(stale)?;
+                }
+            }
+            index += 1;
+        }
+
+        write_field_map(path, fields)?;
+        Ok(files)
     }
 }
 
@@ -327,30 +539,986 @@ impl Default for ShapefileWriter {
 }
 
 impl Writer for ShapefileWriter {
-    fn write(&self, _path: &Path, _collection: &FeatureCollection) -> Result<()> {
-        // TODO: Implement shapefile writing
-        // This requires determining shapefile type, creating .shp, .shx, .dbf files
-        Err(IoError::UnsupportedFormat("Shapefile writing not yet implemented".to_string()))
+    fn write(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        self.write_with_report(path, collection).map(|_| ())
+    }
+
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
+        // The DBF schema depends on every feature, so the stream is collected first
+        let features = futures::executor::block_on(async {
+            let mut features = Vec::new();
+            while let Some(result) = stream.next().await {
+                features.push(result?);
+            }
+            Ok::<_, IoError>(features)
+        })?;
+
+        self.write(path, &FeatureCollection::from_features(features))
+    }
+
+    fn append(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        self.append_with_report(path, collection).map(|_| ())
+    }
+}
+
+/// Shapefile geometry type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ShapeKind {
+    Null,
+    Point,
+    MultiPoint,
+    Polyline,
+    Polygon,
+}
+
+impl ShapeKind {
+    fn of(geometry: &Geometry<f64>) -> Option<Self> {
+        match geometry {
+            Geometry::Point(_) => Some(ShapeKind::Point),
+            Geometry::MultiPoint(_) => Some(ShapeKind::MultiPoint),
+            Geometry::Line(_) | Geometry::LineString(_) | Geometry::MultiLineString(_) => {
+                Some(ShapeKind::Polyline)
+            }
+            Geometry::Polygon(_)
+            | Geometry::MultiPolygon(_)
+            | Geometry::Rect(_)
+            | Geometry::Triangle(_) => Some(ShapeKind::Polygon),
+            Geometry::GeometryCollection(_) => None,
+        }
+    }
+
+    fn from_shape_type(shape_type: shapefile::ShapeType) -> Self {
+        use shapefile::ShapeType;
+
+        match shape_type {
+            ShapeType::Point | ShapeType::PointM | ShapeType::PointZ => ShapeKind::Point,
+            ShapeType::Multipoint | ShapeType::MultipointM | ShapeType::MultipointZ => {
+                ShapeKind::MultiPoint
+            }
+            ShapeType::Polyline | ShapeType::PolylineM | ShapeType::PolylineZ => {
+                ShapeKind::Polyline
+            }
+            ShapeType::Polygon | ShapeType::PolygonM | ShapeType::PolygonZ => ShapeKind::Polygon,
+            ShapeType::NullShape | ShapeType::Multipatch => ShapeKind::Null,
+        }
+    }
+
+    fn code(self) -> i32 {
+        match self {
+            ShapeKind::Null => 0,
+            ShapeKind::Point => 1,
+            ShapeKind::Polyline => 3,
+            ShapeKind::Polygon => 5,
+            ShapeKind::MultiPoint => 8,
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            ShapeKind::Null => "null",
+            ShapeKind::Point => "point",
+            ShapeKind::MultiPoint => "multipoint",
+            ShapeKind::Polyline => "line",
+            ShapeKind::Polygon => "polygon",
+        }
+    }
+}
+
+/// Split features by shapefile geometry type
+///
+/// Geometry collections are exploded into one feature per member. Features
+/// without geometry join the first geometry type present.
+fn group_by_kind(features: &[Feature]) -> BTreeMap<ShapeKind, Vec<Feature>> {
+    fn push(
+        groups: &mut BTreeMap<ShapeKind, Vec<Feature>>,
+        feature: &Feature,
+        geometry: Option<&Geometry<f64>>,
+    ) {
+        match geometry {
+            Some(Geometry::GeometryCollection(collection)) => {
+                for member in collection.iter() {
+                    push(groups, feature, Some(member));
+                }
+            }
+            Some(geometry) => {
+                let kind = ShapeKind::of(geometry).unwrap_or(ShapeKind::Null);
+                groups.entry(kind).or_default().push(Feature {
+                    geometry: Some(geometry.clone()),
+                    ..feature.clone()
+                });
+            }
+            None => groups
+                .entry(ShapeKind::Null)
+                .or_default()
+                .push(feature.clone()),
+        }
+    }
+
+    let mut groups = BTreeMap::new();
+    for feature in features {
+        push(&mut groups, feature, feature.geometry.as_ref());
+    }
+
+    if groups.len() > 1 {
+        if let Some(nulls) = groups.remove(&ShapeKind::Null) {
+            if let Some((_, first)) = groups.iter_mut().next() {
+                first.extend(nulls);
+            }
+        }
+    }
+    groups
+}
+
+/// Path of the shapefile holding one geometry type of a mixed collection
+fn kind_path(path: &Path, kind: ShapeKind) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("layer");
+    path.with_file_name(format!("{}_{}.shp", stem, kind.suffix()))
+}
+
+/// Path of a numbered part; the first part is the path itself
+fn part_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("layer");
+    path.with_file_name(format!("{}_{}.shp", stem, index + 1))
+}
+
+/// Existing parts of a shapefile, in order
+fn existing_parts(path: &Path) -> Vec<PathBuf> {
+    (0..)
+        .map(|index| part_path(path, index))
+        .take_while(|part| part.exists())
+        .collect()
+}
+
+/// Choose the shapefile, and its geometry type, that features of a kind are appended to
+fn append_target(path: &Path, kind: ShapeKind, single: bool) -> Result<(PathBuf, ShapeKind)> {
+    let suffixed = kind_path(path, kind);
+    if suffixed.exists() {
+        return Ok((suffixed, kind));
+    }
+
+    if path.exists() {
+        let existing =
+            ShapeKind::from_shape_type(shapefile::Reader::from_path(path)?.header().shape_type);
+        if existing == kind || kind == ShapeKind::Null {
+            return Ok((path.to_path_buf(), existing));
+        }
+        if existing == ShapeKind::Null {
+            return Ok((path.to_path_buf(), kind));
+        }
+        return Ok((suffixed, kind));
+    }
+
+    Ok((if single { path.to_path_buf() } else { suffixed }, kind))
+}
+
+/// The .shp, .shx, .dbf, .prj and .cpg of one shapefile part
+struct ShapefilePart {
+    shp: ShapeFileWriter,
+    dbf: TableWriter<BufWriter<File>>,
+    dbf_length: u64,
+}
+
+impl ShapefilePart {
+    fn create(
+        path: &Path,
+        kind: ShapeKind,
+        fields: &[DbfField],
+        prj: Option<&str>,
+    ) -> Result<Self> {
+        let mut builder = TableWriterBuilder::new();
+        for field in fields {
+            let name = FieldName::try_from(field.name.as_str())
+                .map_err(|e| IoError::Attribute(format!("{}: {}", field.name, e)))?;
+            builder = match field.kind {
+                FieldKind::Logical => builder.add_logical_field(name),
+                FieldKind::Date => builder.add_date_field(name),
+                FieldKind::Integer | FieldKind::Real => {
+                    builder.add_numeric_field(name, field.width as u8, field.decimals as u8)
+                }
+                FieldKind::Character => builder.add_character_field(name, field.width as u8),
+            };
+        }
+        let dbf = builder
+            .build_with_file_dest(path.with_extension("dbf"))
+            .map_err(|e| IoError::Shapefile(e.to_string()))?;
+
+        // Without a CRS the data has none, so an earlier .prj would be wrong
+        match prj {
+            Some(wkt) => std::fs::write(path.with_extension("prj"), wkt)?,
+            None if path.with_extension("prj").exists() => {
+                std::fs::remove_file(path.with_extension("prj"))?
+            }
+            None => {}
+        }
+        std::fs::write(path.with_extension("cpg"), "UTF-8")?;
+
+        Ok(Self {
+            shp: ShapeFileWriter::create(path, kind)?,
+            dbf,
+            dbf_length: 32 + 32 * fields.len() as u64 + 1,
+        })
     }
 
-    fn write_stream<S>(&self, _path: &Path, _stream: S) -> Result<()>
-    where
-        S: Stream<Item = Result<Feature>> + Send + 'static,
-    {
-        Err(IoError::UnsupportedFormat("Shapefile streaming write not yet implemented".to_string()))
+    fn write(
+        &mut self,
+        content: &[u8],
+        bbox: Option<[f64; 4]>,
+        record: &dbase::Record,
+        record_length: u64,
+    ) -> Result<()> {
+        self.shp.write_record(content, bbox)?;
+        self.dbf
+            .write_record(record)
+            .map_err(|e| IoError::Shapefile(e.to_string()))?;
+        self.dbf_length += record_length;
+        Ok(())
     }
 
-    fn append(&self, _path: &Path, _collection: &FeatureCollection) -> Result<()> {
-        Err(IoError::UnsupportedFormat("Shapefile append not yet implemented".to_string()))
+    fn finish(mut self) -> Result<()> {
+        self.dbf
+            .close()
+            .map_err(|e| IoError::Shapefile(e.to_string()))?;
+        self.shp.finish()
     }
 }
 
+/// Writer for the .shp main file and .shx index
+struct ShapeFileWriter {
+    shp: BufWriter<File>,
+    shx: BufWriter<File>,
+    shape_type: i32,
+    bbox: Option<[f64; 4]>,
+    length: u64,
+    records: u32,
+}
+
+impl ShapeFileWriter {
+    fn create(path: &Path, kind: ShapeKind) -> Result<Self> {
+        let mut shp = BufWriter::new(File::create(path.with_extension("shp"))?);
+        let mut shx = BufWriter::new(File::create(path.with_extension("shx"))?);
+
+        // Headers are rewritten with the final lengths and bounds on finish
+        shp.write_all(&[0u8; HEADER_LENGTH as usize])?;
+        shx.write_all(&[0u8; HEADER_LENGTH as usize])?;
+
+        Ok(Self {
+            shp,
+            shx,
+            shape_type: kind.code(),
+            bbox: None,
+            length: HEADER_LENGTH,
+            records: 0,
+        })
+    }
+
+    fn write_record(&mut self, content: &[u8], bbox: Option<[f64; 4]>) -> Result<()> {
+        self.records += 1;
+        let words = (content.len() / 2) as i32;
+
+        self.shx
+            .write_all(&((self.length / 2) as i32).to_be_bytes())?;
+        self.shx.write_all(&words.to_be_bytes())?;
+
+        self.shp.write_all(&(self.records as i32).to_be_bytes())?;
+        self.shp.write_all(&words.to_be_bytes())?;
+        self.shp.write_all(content)?;
+        self.length += 8 + content.len() as u64;
+
+        if let Some(b) = bbox {
+            self.bbox = Some(match self.bbox {
+                Some(a) => [
+                    a[0].min(b[0]),
+                    a[1].min(b[1]),
+                    a[2].max(b[2]),
+                    a[3].max(b[3]),
+                ],
+                None => b,
+            });
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        let shx_length = HEADER_LENGTH + 8 * self.records as u64;
+        let header = |length: u64| {
+            let mut header = Vec::with_capacity(HEADER_LENGTH as usize);
+            header.extend_from_slice(&9994i32.to_be_bytes());
+            header.extend_from_slice(&[0u8; 20]);
+            header.extend_from_slice(&((length / 2) as i32).to_be_bytes());
+            header.extend_from_slice(&1000i32.to_le_bytes());
+            header.extend_from_slice(&self.shape_type.to_le_bytes());
+            for value in self.bbox.unwrap_or([0.0; 4]) {
+                header.extend_from_slice(&value.to_le_bytes());
+            }
+            // Z and M ranges
+            header.extend_from_slice(&[0u8; 32]);
+            header
+        };
+
+        let (shp_header, shx_header) = (header(self.length), header(shx_length));
+        for (dst, header) in [(&mut self.shp, shp_header), (&mut self.shx, shx_header)] {
+            dst.seek(SeekFrom::Start(0))?;
+            dst.write_all(&header)?;
+            dst.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Encode a shape record's content and bounding box
+///
+/// Empty geometries are written as null shapes.
+fn encode_shape(geometry: Option<&Geometry<f64>>) -> (Vec<u8>, Option<[f64; 4]>) {
+    let null = || (ShapeKind::Null.code().to_le_bytes().to_vec(), None);
+
+    match geometry {
+        Some(Geometry::Point(p)) => {
+            let mut content = ShapeKind::Point.code().to_le_bytes().to_vec();
+            content.extend_from_slice(&p.x().to_le_bytes());
+            content.extend_from_slice(&p.y().to_le_bytes());
+            (content, Some([p.x(), p.y(), p.x(), p.y()]))
+        }
+        Some(Geometry::MultiPoint(mp)) => {
+            let coords: Vec<Coord<f64>> = mp.iter().map(|p| p.0).collect();
+            let Some(bbox) = coords_bbox(coords.iter()) else {
+                return null();
+            };
+            let mut content = ShapeKind::MultiPoint.code().to_le_bytes().to_vec();
+            for value in bbox {
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            content.extend_from_slice(&(coords.len() as i32).to_le_bytes());
+            for coord in &coords {
+                content.extend_from_slice(&coord.x.to_le_bytes());
+                content.extend_from_slice(&coord.y.to_le_bytes());
+            }
+            (content, Some(bbox))
+        }
+        Some(Geometry::Line(line)) => {
+            encode_parts(ShapeKind::Polyline, vec![vec![line.start, line.end]]).unwrap_or_else(null)
+        }
+        Some(Geometry::LineString(ls)) => {
+            encode_parts(ShapeKind::Polyline, vec![ls.0.clone()]).unwrap_or_else(null)
+        }
+        Some(Geometry::MultiLineString(mls)) => encode_parts(
+            ShapeKind::Polyline,
+            mls.iter().map(|ls| ls.0.clone()).collect(),
+        )
+        .unwrap_or_else(null),
+        Some(Geometry::Polygon(p)) => encode_polygons(std::slice::from_ref(p)).unwrap_or_else(null),
+        Some(Geometry::MultiPolygon(mp)) => encode_polygons(&mp.0).unwrap_or_else(null),
+        Some(Geometry::Rect(r)) => encode_polygons(&[r.to_polygon()]).unwrap_or_else(null),
+        Some(Geometry::Triangle(t)) => encode_polygons(&[t.to_polygon()]).unwrap_or_else(null),
+        Some(Geometry::GeometryCollection(_)) | None => null(),
+    }
+}
+
+/// Encode polygons with clockwise exteriors and counter-clockwise holes
+fn encode_polygons(polygons: &[Polygon<f64>]) -> Option<(Vec<u8>, Option<[f64; 4]>)> {
+    let mut rings = Vec::new();
+    for polygon in polygons {
+        let polygon = polygon.orient(Direction::Reversed);
+        if polygon.exterior().0.is_empty() {
+            continue;
+        }
+        rings.push(polygon.exterior().0.clone());
+        rings.extend(polygon.interiors().iter().map(|ring| ring.0.clone()));
+    }
+    encode_parts(ShapeKind::Polygon, rings)
+}
+
+/// Encode a polyline or polygon record from its parts
+fn encode_parts(
+    kind: ShapeKind,
+    parts: Vec<Vec<Coord<f64>>>,
+) -> Option<(Vec<u8>, Option<[f64; 4]>)> {
+    let parts: Vec<Vec<Coord<f64>>> = parts.into_iter().filter(|part| !part.is_empty()).collect();
+    let bbox = coords_bbox(parts.iter().flatten())?;
+    let point_count: usize = parts.iter().map(Vec::len).sum();
+
+    let mut content = kind.code().to_le_bytes().to_vec();
+    for value in bbox {
+        content.extend_from_slice(&value.to_le_bytes());
+    }
+    content.extend_from_slice(&(parts.len() as i32).to_le_bytes());
+    content.extend_from_slice(&(point_count as i32).to_le_bytes());
+
+    let mut start = 0i32;
+    for part in &parts {
+        content.extend_from_slice(&start.to_le_bytes());
+        start += part.len() as i32;
+    }
+    for coord in parts.iter().flatten() {
+        content.extend_from_slice(&coord.x.to_le_bytes());
+        content.extend_from_slice(&coord.y.to_le_bytes());
+    }
+
+    Some((content, Some(bbox)))
+}
+
+fn coords_bbox<'a>(coords: impl Iterator<Item = &'a Coord<f64>>) -> Option<[f64; 4]> {
+    coords.fold(None, |bbox, c| {
+        Some(match bbox {
+            Some([min_x, min_y, max_x, max_y]) => [
+                min_x.min(c.x),
+                min_y.min(c.y),
+                max_x.max(c.x),
+                max_y.max(c.y),
+            ],
+            None => [c.x, c.y, c.x, c.y],
+        })
+    })
+}
+
+/// DBF field type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Logical,
+    Integer,
+    Real,
+    Date,
+    Character,
+}
+
+/// DBF field definition for a feature property
+#[derive(Debug, Clone)]
+struct DbfField {
+    property: String,
+    name: String,
+    kind: FieldKind,
+    width: usize,
+    decimals: usize,
+}
+
+impl DbfField {
+    fn value(&self, value: Option<&Value>) -> FieldValue {
+        let value = value.filter(|v| !v.is_null());
+        match self.kind {
+            FieldKind::Logical => FieldValue::Logical(value.and_then(Value::as_bool)),
+            FieldKind::Integer | FieldKind::Real => {
+                FieldValue::Numeric(value.and_then(Value::as_f64))
+            }
+            FieldKind::Date => FieldValue::Date(value.and_then(Value::as_str).and_then(parse_date)),
+            FieldKind::Character => FieldValue::Character(value.map(|v| {
+                let mut text = character_value(v);
+                if text.len() > self.width {
+                    let mut end = self.width;
+                    while !text.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    text.truncate(end);
+                }
+                text
+            })),
+        }
+    }
+}
+
+/// Value kinds and widths seen for one property
+#[derive(Debug, Default)]
+struct FieldStats {
+    logical: bool,
+    integer: bool,
+    real: bool,
+    date: bool,
+    text: bool,
+    integer_digits: usize,
+    decimals: usize,
+    text_width: usize,
+}
+
+impl FieldStats {
+    fn observe(&mut self, value: &Value) {
+        match value {
+            Value::Null => return,
+            Value::Bool(_) => self.logical = true,
+            Value::Number(n) => {
+                let repr = match n.as_f64() {
+                    Some(v) if !n.is_f64() || (v.fract() == 0.0 && v.abs() < 1e15) => {
+                        self.integer = true;
+                        format!("{}", v as i64)
+                    }
+                    Some(v) => {
+                        self.real = true;
+                        format!("{}", v)
+                    }
+                    None => n.to_string(),
+                };
+                let (integer, fraction) = repr.split_once('.').unwrap_or((&repr, ""));
+                self.integer_digits = self.integer_digits.max(integer.len());
+                self.decimals = self.decimals.max(fraction.len().min(MAX_DECIMALS));
+            }
+            Value::String(s) if parse_date(s).is_some() => self.date = true,
+            _ => self.text = true,
+        }
+        self.text_width = self.text_width.max(character_value(value).len());
+    }
+
+    /// Field type, width and decimals able to hold every value seen
+    fn field_type(&self) -> (FieldKind, usize, usize) {
+        let numeric = self.integer || self.real;
+        let kinds = [self.logical, numeric, self.date, self.text]
+            .iter()
+            .filter(|seen| **seen)
+            .count();
+        let character = (
+            FieldKind::Character,
+            self.text_width.clamp(1, MAX_CHARACTER_WIDTH),
+            0,
+        );
+
+        if kinds == 0 {
+            return (FieldKind::Character, DEFAULT_CHARACTER_WIDTH, 0);
+        }
+        if kinds > 1 || self.text {
+            return character;
+        }
+        if self.logical {
+            return (FieldKind::Logical, 1, 0);
+        }
+        if self.date {
+            return (FieldKind::Date, 8, 0);
+        }
+        if self.integer_digits >= MAX_NUMERIC_WIDTH {
+            return character;
+        }
+        if !self.real {
+            return (FieldKind::Integer, self.integer_digits.max(1), 0);
+        }
+
+        let decimals = self
+            .decimals
+            .min(MAX_NUMERIC_WIDTH - self.integer_digits - 1);
+        (
+            FieldKind::Real,
+            self.integer_digits + 1 + decimals,
+            decimals,
+        )
+    }
+}
+
+/// Infer DBF fields from feature properties
+///
+/// Properties in `pinned` keep the field they were given earlier. Returns the
+/// fields, ordered by property name, and the properties that had to be
+/// renamed to fit DBF field names.
+fn infer_schema(
+    features: &[Feature],
+    pinned: &HashMap<String, String>,
+) -> (Vec<DbfField>, HashMap<String, String>) {
+    let mut stats: BTreeMap<&str, FieldStats> = BTreeMap::new();
+    for feature in features {
+        for (key, value) in &feature.properties {
+            stats.entry(key).or_default().observe(value);
+        }
+    }
+
+    let properties: Vec<&str> = stats.keys().copied().collect();
+    let names = dbf_field_names(&properties, pinned);
+
+    let mut renamed = HashMap::new();
+    let fields = stats
+        .iter()
+        .zip(names)
+        .map(|((property, stats), name)| {
+            if name != *property {
+                renamed.insert(property.to_string(), name.clone());
+            }
+            let (kind, width, decimals) = stats.field_type();
+            DbfField {
+                property: property.to_string(),
+                name,
+                kind,
+                width,
+                decimals,
+            }
+        })
+        .collect();
+
+    (fields, renamed)
+}
+
+/// Map property names to unique DBF field names of at most ten ASCII characters
+///
+/// Properties in `pinned` get the field given there and names that already
+/// fit are kept; the rest are sanitized and truncated, with a numeric suffix
+/// when that collides with another field.
+fn dbf_field_names(properties: &[&str], pinned: &HashMap<String, String>) -> Vec<String> {
+    let fits = |name: &str| {
+        !name.is_empty()
+            && name.len() <= MAX_FIELD_NAME_LENGTH
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    let mut used: HashSet<String> = pinned
+        .values()
+        .map(|name| name.to_ascii_uppercase())
+        .collect();
+    let mut names: Vec<Option<String>> = properties
+        .iter()
+        .map(|property| {
+            if let Some(name) = pinned.get(*property) {
+                return Some(name.clone());
+            }
+            (fits(property) && used.insert(property.to_ascii_uppercase()))
+                .then(|| property.to_string())
+        })
+        .collect();
+
+    for (property, name) in properties.iter().zip(names.iter_mut()) {
+        if name.is_some() {
+            continue;
+        }
+
+        let mut base: String = property
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(MAX_FIELD_NAME_LENGTH)
+            .collect();
+        if base.is_empty() {
+            base = "field".to_string();
+        }
+
+        let mut candidate = base.clone();
+        let mut suffix = 1;
+        while !used.insert(candidate.to_ascii_uppercase()) {
+            let tail = format!("_{}", suffix);
+            candidate = base
+                .chars()
+                .take(MAX_FIELD_NAME_LENGTH - tail.len())
+                .collect::<String>()
+                + &tail;
+            suffix += 1;
+        }
+        *name = Some(candidate);
+    }
+
+    names.into_iter().flatten().collect()
+}
+
+/// Apply a rename map to a feature's properties
+fn rename_properties(feature: &Feature, renamed: &HashMap<String, String>) -> Feature {
+    let properties = feature
+        .properties
+        .iter()
+        .map(|(key, value)| (renamed.get(key).unwrap_or(key).clone(), value.clone()))
+        .collect();
+
+    Feature {
+        properties,
+        ..feature.clone()
+    }
+}
+
+/// Sidecar recording the properties renamed to fit DBF field names
+fn field_map_path(path: &Path) -> PathBuf {
+    path.with_extension("fields.json")
+}
+
+/// Properties renamed when a shapefile was written (property -> field)
+fn read_field_map(path: &Path) -> Result<HashMap<String, String>> {
+    match std::fs::read_to_string(field_map_path(path)) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| IoError::Shapefile(format!("Invalid field map: {}", e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Record the renamed fields of a shapefile, removing a stale record
+fn write_field_map(path: &Path, fields: &[DbfField]) -> Result<()> {
+    let renamed: BTreeMap<&str, &str> = fields
+        .iter()
+        .filter(|field| field.name != field.property)
+        .map(|field| (field.property.as_str(), field.name.as_str()))
+        .collect();
+
+    let map_path = field_map_path(path);
+    if renamed.is_empty() {
+        if map_path.exists() {
+            std::fs::remove_file(map_path)?;
+        }
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(&renamed)
+        .map_err(|e| IoError::Shapefile(e.to_string()))?;
+    std::fs::write(map_path, json)?;
+    Ok(())
+}
+
+/// Text stored in a character field
+fn character_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse an ISO 8601 `YYYY-MM-DD` date
+fn parse_date(text: &str) -> Option<dbase::Date> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+
+    let year: u32 = text[0..4].parse().ok()?;
+    let month: u32 = text[5..7].parse().ok()?;
+    let day: u32 = text[8..10].parse().ok()?;
+    ((1..=12).contains(&month) && (1..=31).contains(&day))
+        .then(|| dbase::Date::new(day, month, year))
+}
+
+/// CRS of a collection, or of its first feature that has one
+fn collection_crs(collection: &FeatureCollection) -> Option<&str> {
+    collection
+        .crs
+        .as_deref()
+        .or_else(|| collection.features.iter().find_map(|f| f.crs.as_deref()))
+}
+
+/// WKT for a .prj file
+///
+/// WKT is written as given; CRS identifiers are formatted by the CRS engine.
+fn prj_wkt(crs: &str) -> Result<String> {
+    if crs.contains('[') {
+        return Ok(crs.to_string());
+    }
+
+    Crs::from_uri(crs)
+        .and_then(|parsed| parsed.to_wkt())
+        .map_err(|e| IoError::Crs(format!("Cannot write a .prj for {}: {}", crs, e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use geo_types::{line_string, point, polygon};
+    use serde_json::json;
 
     #[test]
     fn test_shapefile_reader_creation() {
         let _reader = ShapefileReader::new();
     }
+
+    #[test]
+    fn test_datetime_field_value() {
+        let value = FieldValue::DateTime(dbase::DateTime::new(
+            dbase::Date::new(15, 1, 2024),
+            dbase::Time::new(13, 45, 30),
+        ));
+        assert_eq!(
+            ShapefileReader::convert_field_value(&value),
+            json!("2024-01-15T13:45:30")
+        );
+    }
+
+    #[test]
+    fn test_dbf_field_names() {
+        let properties = ["name", "population_2020", "population_2021", "höhe"];
+        let names = dbf_field_names(&properties, &HashMap::new());
+        assert_eq!(names, vec!["name", "population", "populati_1", "h_he"]);
+
+        let pinned = HashMap::from([("population_2021".to_string(), "populati_1".to_string())]);
+        assert_eq!(dbf_field_names(&["population_2021", "population_2022"], &pinned), vec!["populati_1", "population"]);
+    }
+
+    #[test]
+    fn test_field_types() {
+        let features = vec![
+            Feature::new(None)
+                .with_property("count", json!(12))
+                .with_property("ratio", json!(0.25))
+                .with_property("open", json!(true))
+                .with_property("since", json!("2021-03-04"))
+                .with_property("mixed", json!(1)),
+            Feature::new(None)
+                .with_property("count", json!(-1234))
+                .with_property("ratio", json!(10.5))
+                .with_property("mixed", json!("many")),
+        ];
+        let (fields, renamed) = infer_schema(&features, &HashMap::new());
+        assert!(renamed.is_empty());
+
+        let field = |name: &str| fields.iter().find(|f| f.name == name).unwrap();
+        assert_eq!(
+            (field("count").kind, field("count").width),
+            (FieldKind::Integer, 5)
+        );
+        assert_eq!(
+            (
+                field("ratio").kind,
+                field("ratio").width,
+                field("ratio").decimals
+            ),
+            (FieldKind::Real, 5, 2)
+        );
+        assert_eq!(field("open").kind, FieldKind::Logical);
+        assert_eq!(field("since").kind, FieldKind::Date);
+        assert_eq!(
+            (field("mixed").kind, field("mixed").width),
+            (FieldKind::Character, 4)
+        );
+    }
+
+    #[test]
+    fn test_write_mixed_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mixed.shp");
+
+        let square =
+            polygon![(x: 0.0, y: 0.0), (x: 10.0, y: 0.0), (x: 10.0, y: 10.0), (x: 0.0, y: 10.0)];
+        let hole = polygon![(x: 2.0, y: 2.0), (x: 4.0, y: 2.0), (x: 4.0, y: 4.0), (x: 2.0, y: 4.0)];
+        let collection = FeatureCollection {
+            features: vec![
+                Feature::new(Some(Geometry::Point(point!(x: 1.0, y: 2.0))))
+                    .with_property("station_name", json!("North")),
+                Feature::new(Some(Geometry::LineString(
+                    line_string![(x: 0.0, y: 0.0), (x: 5.0, y: 5.0)],
+                )))
+                .with_property("station_name", json!("Link")),
+                Feature::new(Some(Geometry::Polygon(Polygon::new(
+                    square.exterior().clone(),
+                    vec![hole.exterior().clone()],
+                ))))
+                .with_property("area", json!(96.0)),
+            ],
+            crs: Some("EPSG:4326".to_string()),
+            bbox: None,
+        };
+
+        let report = ShapefileWriter::new()
+            .write_with_report(&path, &collection)
+            .unwrap();
+        assert_eq!(report.files.len(), 3);
+        assert_eq!(
+            report
+                .renamed_fields
+                .get("station_name")
+                .map(String::as_str),
+            Some("station_na")
+        );
+
+        let polygons = ShapefileReader::new()
+            .read(&dir.path().join("mixed_polygon.shp"))
+            .unwrap();
+        assert_eq!(polygons.crs, Some(Crs::wgs84().to_wkt().unwrap()));
+        match &polygons.features[0].geometry {
+            Some(Geometry::Polygon(p)) => assert_eq!(p.interiors().len(), 1),
+            other => panic!("expected a polygon with a hole, got {:?}", other),
+        }
+        assert!(dir.path().join("mixed_point.cpg").exists());
+
+        let points = ShapefileReader::new()
+            .read(&dir.path().join("mixed_point.shp"))
+            .unwrap();
+        assert_eq!(points.features[0].properties["station_na"], json!("North"));
+    }
+
+    #[test]
+    fn test_append_and_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("points.shp");
+
+        let points = |from: usize, to: usize| {
+            FeatureCollection::from_features(
+                (from..to)
+                    .map(|i| {
+                        Feature::new(Some(Geometry::Point(point!(x: i as f64, y: 0.0))))
+                            .with_property("label_text", json!(format!("point {}", i)))
+                    })
+                    .collect(),
+            )
+        };
+
+        // Each point record is 28 bytes, so 10 fit in a 400 byte .shp
+        let writer = ShapefileWriter::new().with_max_file_size(400);
+        let report = writer.write_with_report(&path, &points(0, 15)).unwrap();
+        assert_eq!(
+            report.files,
+            vec![path.clone(), dir.path().join("points_2.shp")]
+        );
+
+        let report = writer.append_with_report(&path, &points(15, 25)).unwrap();
+        assert_eq!(report.files.len(), 3);
+
+        let total: usize = report
+            .files
+            .iter()
+            .map(|file| ShapefileReader::new().read(file).unwrap().features.len())
+            .sum();
+        assert_eq!(total, 25);
+
+        let last = ShapefileReader::new().read(&report.files[2]).unwrap();
+        assert_eq!(last.features[4].properties["label_text"], json!("point 24"));
+    }
+
+    #[test]
+    fn test_append_keeps_renamed_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("towns.shp");
+        let town = |properties: &[(&str, i64)]| {
+            properties.iter().fold(
+                Feature::new(Some(Geometry::Point(point!(x: 1.0, y: 2.0)))),
+                |feature, (key, value)| feature.with_property(*key, json!(value)),
+            )
+        };
+
+        let writer = ShapefileWriter::new();
+        writer
+            .write(
+                &path,
+                &FeatureCollection::from_features(vec![town(&[
+                    ("population_2020", 100),
+                    ("population_2021", 110),
+                ])]),
+            )
+            .unwrap();
+
+        // Only the 2021 figure, which was written to the second field
+        let report = writer
+            .append_with_report(
+                &path,
+                &FeatureCollection::from_features(vec![town(&[("population_2021", 120)])]),
+            )
+            .unwrap();
+        assert_eq!(
+            report.renamed_fields.get("population_2021").map(String::as_str),
+            Some("populati_1")
+        );
+
+        let towns = ShapefileReader::new().read(&path).unwrap();
+        assert_eq!(towns.features.len(), 2);
+        assert_eq!(towns.features[1].properties["populati_1"], json!(120.0));
+        assert_eq!(towns.features[1].properties["population"], Value::Null);
+        assert_eq!(towns.features[0].properties["population"], json!(100.0));
+    }
+
+    #[test]
+    fn test_prj_from_crs_engine() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("utm.shp");
+        let mut collection = FeatureCollection::from_features(vec![Feature::new(Some(
+            Geometry::Point(point!(x: 500000.0, y: 5300000.0)),
+        ))]);
+
+        collection.crs = Some("EPSG:32632".to_string());
+        ShapefileWriter::new().write(&path, &collection).unwrap();
+        let prj = std::fs::read_to_string(path.with_extension("prj")).unwrap();
+        assert_eq!(Crs::from_wkt(&prj).unwrap().epsg, Some(32632));
+
+        // A CRS without a definition fails before the .prj is touched
+        collection.crs = Some("EPSG:999999".to_string());
+        assert!(ShapefileWriter::new().write(&path, &collection).is_err());
+        assert_eq!(std::fs::read_to_string(path.with_extension("prj")).unwrap(), prj);
+    }
 }
@@ -157,9 +157,7 @@ pub trait Writer: Send + Sync {
     fn write(&self, path: &Path, collection: &FeatureCollection) -> Result<()>;
 
     /// Write features as a stream (for large datasets)
-    fn write_stream<S>(&self, path: &Path, stream: S) -> Result<()>
-    where
-        S: Stream<Item = Result<Feature>> + Send + 'static;
+    fn write_stream(&self, path: &Path, stream: FeatureStream) -> Result<()>;
 
     /// Append features to an existing file
     fn append(&self, path: &Path, collection: &FeatureCollection) -> Result<()>;
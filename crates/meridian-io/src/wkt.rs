@@ -1,12 +1,16 @@
-//! WKT (Well-Known Text) and WKB (Well-Known Binary) support
+//! WKT (Well-Known Text), WKB (Well-Known Binary) and TWKB (Tiny WKB) support
 
+use crate::driver::LayerOptions;
 use crate::error::{IoError, Result};
 use crate::traits::{Feature, FeatureCollection, FeatureStream, Metadata, Reader, Writer};
 use futures::stream;
 use geo_types::Geometry;
+use meridian_core::crs::Crs;
+use meridian_core::error::MeridianError;
+use meridian_core::geometry::{Geometry as CoreGeometry, TwkbOptions, WkbOptions};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write as _};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write as _};
 use std::path::Path;
 use wkt::Wkt;
 
@@ -155,6 +159,17 @@ impl WktWriter {
         }
     }
 
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["PROPERTIES"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        Ok(Self {
+            include_properties: options.get_bool("PROPERTIES")?.unwrap_or(false),
+        })
+    }
+
     /// Include properties in output (tab-separated)
     pub fn with_properties(mut self) -> Self {
         self.include_properties = true;
@@ -202,20 +217,13 @@ impl Writer for WktWriter {
         Ok(())
     }
 
-    fn write_stream<S>(&self, path: &Path, stream: S) -> Result<()>
-    where
-        S: futures::Stream<Item = Result<Feature>> + Send + 'static,
-    {
+    fn write_stream(&self, path: &Path, mut stream: FeatureStream) -> Result<()> {
         use futures::StreamExt;
 
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
 
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| IoError::Other(format!("Failed to create runtime: {}", e)))?;
-
-        rt.block_on(async {
-            let mut stream = Box::pin(stream);
+        futures::executor::block_on(async {
             while let Some(result) = stream.next().await {
                 let feature = result?;
                 if let Some(ref geom) = feature.geometry {
@@ -251,7 +259,13 @@ impl Writer for WktWriter {
     }
 }
 
-/// WKB (Well-Known Binary) support
+pub use meridian_core::geometry::{ByteOrder, WkbDialect};
+
+/// WKB (Well-Known Binary) reader
+///
+/// Decodes ISO WKB and PostGIS EWKB in either byte order into core
+/// geometries, keeping Z and M. A `.wkb` file is read as a sequence of
+/// geometries, one feature each; features hold their 2D geometry.
 pub struct WkbReader;
 
 impl WkbReader {
@@ -259,6 +273,29 @@ impl WkbReader {
     pub fn new() -> Self {
         Self
     }
+
+    /// Decode a WKB or EWKB geometry
+    ///
+    /// `crs` applies unless the geometry has an EWKB SRID with a known EPSG code.
+    pub fn decode(bytes: &[u8], crs: Crs) -> Result<CoreGeometry> {
+        CoreGeometry::from_wkb(bytes, crs).map_err(codec_error)
+    }
+
+    /// Decode hex encoded WKB, as returned by PostGIS
+    pub fn decode_hex(hex: &str, crs: Crs) -> Result<CoreGeometry> {
+        Self::decode(&decode_hex(hex)?, crs)
+    }
+
+    /// SRID of an EWKB geometry, `None` for ISO WKB
+    pub fn srid(bytes: &[u8]) -> Result<Option<i32>> {
+        CoreGeometry::wkb_srid(bytes).map_err(codec_error)
+    }
+
+    fn decode_next(data: &[u8]) -> Result<Decoded> {
+        let srid = Self::srid(data)?;
+        let (geometry, len) = CoreGeometry::read_wkb(data, Crs::wgs84()).map_err(codec_error)?;
+        Ok((geometry.to_geo(), srid, len))
+    }
 }
 
 impl Default for WkbReader {
@@ -267,13 +304,108 @@ impl Default for WkbReader {
     }
 }
 
-/// WKB writer
-pub struct WkbWriter;
+impl Reader for WkbReader {
+    fn read(&self, path: &Path) -> Result<FeatureCollection> {
+        read_geometries(path, Self::decode_next)
+    }
+
+    fn read_stream(&self, path: &Path) -> Result<FeatureStream> {
+        stream_geometries(path, Self::decode_next)
+    }
+
+    fn read_crs(&self, path: &Path) -> Result<Option<String>> {
+        let mut file = GeometryFile::open(path, Self::decode_next)?;
+        Ok(file.next_geometry()?.and_then(|(_, srid)| srid).map(srid_crs))
+    }
+
+    fn read_metadata(&self, path: &Path) -> Result<Metadata> {
+        Ok(geometry_metadata(&self.read(path)?))
+    }
+}
+
+/// WKB (Well-Known Binary) writer
+///
+/// Writes ISO WKB or PostGIS EWKB. A `.wkb` file holds the geometries of all
+/// features one after another; properties are not written.
+pub struct WkbWriter {
+    /// WKB dialect
+    pub dialect: WkbDialect,
+
+    /// Byte order
+    pub byte_order: ByteOrder,
+}
 
 impl WkbWriter {
-    /// Create a new WKB writer
+    /// Create a new WKB writer producing little endian ISO WKB
     pub fn new() -> Self {
-        Self
+        Self {
+            dialect: WkbDialect::Iso,
+            byte_order: ByteOrder::LittleEndian,
+        }
+    }
+
+    /// Write PostGIS EWKB, including SRIDs
+    pub fn with_ewkb(mut self) -> Self {
+        self.dialect = WkbDialect::Ewkb;
+        self
+    }
+
+    /// Set the byte order
+    pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = byte_order;
+        self
+    }
+
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] = &["DIALECT", "BYTE_ORDER"];
+
+    /// Create a writer from layer creation options
+    ///
+    /// `DIALECT` is `ISO` or `EWKB`; `BYTE_ORDER` is `NDR` (little endian) or `XDR`.
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        let mut writer = Self::new();
+
+        match options.get("DIALECT").map(str::to_ascii_uppercase).as_deref() {
+            None | Some("ISO") => {}
+            Some("EWKB") => writer = writer.with_ewkb(),
+            Some(other) => return Err(IoError::InvalidOption(format!("Invalid DIALECT: '{}'", other))),
+        }
+        match options.get("BYTE_ORDER").map(str::to_ascii_uppercase).as_deref() {
+            None | Some("NDR") => {}
+            Some("XDR") => writer = writer.with_byte_order(ByteOrder::BigEndian),
+            Some(other) => return Err(IoError::InvalidOption(format!("Invalid BYTE_ORDER: '{}'", other))),
+        }
+
+        Ok(writer)
+    }
+
+    /// Encode a geometry; EWKB carries the EPSG code of its CRS as SRID
+    pub fn encode(&self, geometry: &CoreGeometry) -> Vec<u8> {
+        geometry.to_wkb_with(&self.options(crs_srid(geometry.crs())))
+    }
+
+    /// Encode a geometry as hex, as accepted by PostGIS
+    pub fn encode_hex(&self, geometry: &CoreGeometry) -> String {
+        self.encode(geometry).iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    fn options(&self, srid: Option<i32>) -> WkbOptions {
+        WkbOptions {
+            dialect: self.dialect,
+            byte_order: self.byte_order,
+            srid,
+        }
+    }
+
+    /// Features without a known CRS are written without an SRID
+    fn feature_wkb(&self, feature: &Feature, crs: Option<&str>) -> Result<Option<Vec<u8>>> {
+        let crs = feature.crs.as_deref().or(crs).and_then(|crs| Crs::from_uri(crs).ok());
+        Ok(feature.geometry.as_ref().map(|geometry| {
+            let srid = crs.as_ref().and_then(crs_srid);
+            let crs = crs.clone().unwrap_or_else(Crs::wgs84);
+            CoreGeometry::from_geo(geometry.clone(), crs).to_wkb_with(&self.options(srid))
+        }))
     }
 }
 
@@ -283,6 +415,374 @@ impl Default for WkbWriter {
     }
 }
 
+impl Writer for WkbWriter {
+    fn write(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        write_geometries(File::create(path)?, collection, |f, crs| self.feature_wkb(f, crs))
+    }
+
+    fn write_stream(&self, path: &Path, stream: FeatureStream) -> Result<()> {
+        stream_to_file(path, stream, |f| self.feature_wkb(f, None))
+    }
+
+    fn append(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        write_geometries(file, collection, |f, crs| self.feature_wkb(f, crs))
+    }
+}
+
+/// TWKB (Tiny Well-Known Binary) reader
+///
+/// A `.twkb` file is read as a sequence of geometries, one feature each.
+pub struct TwkbReader;
+
+impl TwkbReader {
+    /// Create a new TWKB reader
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Decode a TWKB geometry, which has no CRS of its own
+    pub fn decode(bytes: &[u8], crs: Crs) -> Result<CoreGeometry> {
+        CoreGeometry::from_twkb(bytes, crs).map_err(codec_error)
+    }
+
+    fn decode_next(data: &[u8]) -> Result<Decoded> {
+        let (geometry, len) = CoreGeometry::read_twkb(data, Crs::wgs84()).map_err(codec_error)?;
+        Ok((geometry.to_geo(), None, len))
+    }
+}
+
+impl Default for TwkbReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reader for TwkbReader {
+    fn read(&self, path: &Path) -> Result<FeatureCollection> {
+        read_geometries(path, Self::decode_next)
+    }
+
+    fn read_stream(&self, path: &Path) -> Result<FeatureStream> {
+        stream_geometries(path, Self::decode_next)
+    }
+
+    fn read_crs(&self, _path: &Path) -> Result<Option<String>> {
+        // TWKB does not carry a CRS
+        Ok(None)
+    }
+
+    fn read_metadata(&self, path: &Path) -> Result<Metadata> {
+        Ok(geometry_metadata(&self.read(path)?))
+    }
+}
+
+/// TWKB (Tiny Well-Known Binary) writer
+///
+/// Coordinates are rounded to `precision` decimal digits (negative values
+/// round to tens, hundreds, ...) and delta encoded as varints.
+pub struct TwkbWriter {
+    /// Decimal digits kept for X and Y, from -7 to 7
+    pub precision: i8,
+
+    /// Decimal digits kept for Z, from 0 to 7
+    pub precision_z: u8,
+
+    /// Decimal digits kept for M, from 0 to 7
+    pub precision_m: u8,
+
+    /// Include a bounding box
+    pub include_bbox: bool,
+
+    /// Include the encoded size, so readers can skip geometries
+    pub include_size: bool,
+}
+
+impl TwkbWriter {
+    /// Create a new TWKB writer keeping six decimal digits
+    pub fn new() -> Self {
+        Self {
+            precision: 6,
+            precision_z: 3,
+            precision_m: 3,
+            include_bbox: false,
+            include_size: false,
+        }
+    }
+
+    /// Set the decimal digits kept for X and Y
+    pub fn with_precision(mut self, precision: i8) -> Self {
+        self.precision = precision.clamp(-7, 7);
+        self
+    }
+
+    /// Set the decimal digits kept for Z and M
+    pub fn with_precision_zm(mut self, precision_z: u8, precision_m: u8) -> Self {
+        self.precision_z = precision_z.min(7);
+        self.precision_m = precision_m.min(7);
+        self
+    }
+
+    /// Include bounding boxes
+    pub fn with_bbox(mut self) -> Self {
+        self.include_bbox = true;
+        self
+    }
+
+    /// Include encoded sizes
+    pub fn with_size(mut self) -> Self {
+        self.include_size = true;
+        self
+    }
+
+    /// Layer creation options understood by [`Self::from_options`]
+    pub const LAYER_OPTIONS: &'static [&'static str] =
+        &["PRECISION", "PRECISION_Z", "PRECISION_M", "BBOX", "SIZE"];
+
+    /// Create a writer from layer creation options
+    pub fn from_options(options: &LayerOptions) -> Result<Self> {
+        options.check(Self::LAYER_OPTIONS)?;
+        let mut writer = Self::new()
+            .with_precision(options.get_parsed("PRECISION")?.unwrap_or(6))
+            .with_precision_zm(
+                options.get_parsed("PRECISION_Z")?.unwrap_or(3),
+                options.get_parsed("PRECISION_M")?.unwrap_or(3),
+            );
+        writer.include_bbox = options.get_bool("BBOX")?.unwrap_or(false);
+        writer.include_size = options.get_bool("SIZE")?.unwrap_or(false);
+        Ok(writer)
+    }
+
+    /// Encode a geometry
+    pub fn encode(&self, geometry: &CoreGeometry) -> Vec<u8> {
+        geometry.to_twkb(&TwkbOptions {
+            precision: self.precision,
+            precision_z: self.precision_z,
+            precision_m: self.precision_m,
+            bbox: self.include_bbox,
+            size: self.include_size,
+        })
+    }
+
+    fn feature_twkb(&self, feature: &Feature, crs: Option<&str>) -> Result<Option<Vec<u8>>> {
+        let crs = feature.crs.as_deref().or(crs);
+        Ok(feature
+            .geometry
+            .as_ref()
+            .map(|geometry| self.encode(&core_geometry(geometry, crs))))
+    }
+}
+
+impl Default for TwkbWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Writer for TwkbWriter {
+    fn write(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        write_geometries(File::create(path)?, collection, |f, crs| self.feature_twkb(f, crs))
+    }
+
+    fn write_stream(&self, path: &Path, stream: FeatureStream) -> Result<()> {
+        stream_to_file(path, stream, |f| self.feature_twkb(f, None))
+    }
+
+    fn append(&self, path: &Path, collection: &FeatureCollection) -> Result<()> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        write_geometries(file, collection, |f, crs| self.feature_twkb(f, crs))
+    }
+}
+
+fn codec_error(err: MeridianError) -> IoError {
+    match err {
+        MeridianError::IoError(e) => IoError::Io(e),
+        err => IoError::Wkt(err.to_string()),
+    }
+}
+
+/// Core geometry of a feature, in its CRS when that is known
+pub(crate) fn core_geometry(geometry: &Geometry<f64>, crs: Option<&str>) -> CoreGeometry {
+    let crs = crs.and_then(|crs| Crs::from_uri(crs).ok()).unwrap_or_else(Crs::wgs84);
+    CoreGeometry::from_geo(geometry.clone(), crs)
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    let hex = hex.trim();
+    if !hex.len().is_multiple_of(2) {
+        return Err(IoError::Wkt("Hex WKB has an odd number of digits".to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| IoError::Wkt(format!("Invalid hex WKB at offset {}", i)))
+        })
+        .collect()
+}
+
+/// EWKB SRID of a CRS, its EPSG code
+fn crs_srid(crs: &Crs) -> Option<i32> {
+    crs.epsg.and_then(|code| i32::try_from(code).ok())
+}
+
+fn srid_crs(srid: i32) -> String {
+    format!("EPSG:{}", srid)
+}
+
+/// 2D geometry, EWKB SRID and encoded length of one binary geometry
+type Decoded = (Geometry<f64>, Option<i32>, usize);
+
+type DecodeFn = fn(&[u8]) -> Result<Decoded>;
+
+/// Bytes read from a geometry file at a time, at least
+const READ_CHUNK: usize = 64 * 1024;
+
+/// File of consecutive binary geometries, read a chunk at a time
+///
+/// The length of a geometry is only known once it is decoded, so a geometry
+/// cut off at the end of the buffer is retried with more of the file. Any
+/// other decode error is returned at once.
+struct GeometryFile {
+    input: File,
+    buffer: Vec<u8>,
+    start: usize,
+    eof: bool,
+    decode: DecodeFn,
+}
+
+impl GeometryFile {
+    fn open(path: &Path, decode: DecodeFn) -> Result<Self> {
+        Ok(Self {
+            input: File::open(path)?,
+            buffer: Vec::new(),
+            start: 0,
+            eof: false,
+            decode,
+        })
+    }
+
+    /// The next geometry and its SRID, `None` at the end of the file
+    fn next_geometry(&mut self) -> Result<Option<(Geometry<f64>, Option<i32>)>> {
+        loop {
+            if self.start < self.buffer.len() {
+                match (self.decode)(&self.buffer[self.start..]) {
+                    Ok((geometry, srid, len)) => {
+                        self.start += len;
+                        return Ok(Some((geometry, srid)));
+                    }
+                    Err(IoError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof && !self.eof => {}
+                    Err(e) => return Err(e),
+                }
+            } else if self.eof {
+                return Ok(None);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Drop decoded bytes and read at least as many as are still buffered
+    fn fill(&mut self) -> Result<()> {
+        self.buffer.drain(..self.start);
+        self.start = 0;
+        let wanted = self.buffer.len().max(READ_CHUNK);
+        let read = (&mut self.input).take(wanted as u64).read_to_end(&mut self.buffer)?;
+        self.eof = read < wanted;
+        Ok(())
+    }
+}
+
+/// Read a file of consecutive binary geometries
+fn read_geometries(path: &Path, decode: DecodeFn) -> Result<FeatureCollection> {
+    let mut file = GeometryFile::open(path, decode)?;
+    let mut collection = FeatureCollection::new();
+    while let Some((geometry, srid)) = file.next_geometry()? {
+        collection.crs = collection.crs.or(srid.map(srid_crs));
+        collection.add_feature(Feature::new(Some(geometry)));
+    }
+    Ok(collection)
+}
+
+/// Stream a file of consecutive binary geometries
+fn stream_geometries(path: &Path, decode: DecodeFn) -> Result<FeatureStream> {
+    let mut file = GeometryFile::open(path, decode)?;
+    let mut failed = false;
+
+    let features = std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        match file.next_geometry() {
+            Ok(Some((geometry, srid))) => {
+                let feature = Feature::new(Some(geometry));
+                Some(Ok(match srid {
+                    Some(srid) => feature.with_crs(srid_crs(srid)),
+                    None => feature,
+                }))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                failed = true;
+                Some(Err(e))
+            }
+        }
+    });
+    Ok(Box::pin(stream::iter(features)))
+}
+
+/// Write the geometries of a collection one after another
+fn write_geometries<F>(file: File, collection: &FeatureCollection, encode: F) -> Result<()>
+where
+    F: Fn(&Feature, Option<&str>) -> Result<Option<Vec<u8>>>,
+{
+    let mut writer = BufWriter::new(file);
+    for feature in &collection.features {
+        if let Some(bytes) = encode(feature, collection.crs.as_deref())? {
+            writer.write_all(&bytes)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the geometries of a stream one after another
+fn stream_to_file<F>(path: &Path, mut stream: FeatureStream, encode: F) -> Result<()>
+where
+    F: Fn(&Feature) -> Result<Option<Vec<u8>>>,
+{
+    use futures::StreamExt;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    futures::executor::block_on(async {
+        while let Some(result) = stream.next().await {
+            if let Some(bytes) = encode(&result?)? {
+                writer.write_all(&bytes)?;
+            }
+        }
+        Ok::<_, IoError>(())
+    })?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Metadata of a file of geometries without attributes
+fn geometry_metadata(collection: &FeatureCollection) -> Metadata {
+    let mut metadata = Metadata::new();
+    metadata.layers.push("default".to_string());
+    metadata.feature_counts.insert("default".to_string(), collection.features.len());
+    metadata.crs = collection.crs.clone();
+
+    let mut geom_types = std::collections::HashSet::new();
+    for geometry in collection.features.iter().filter_map(|f| f.geometry.as_ref()) {
+        geom_types.insert(geometry_type_name(geometry));
+    }
+    metadata.geometry_types = geom_types.into_iter().collect();
+    metadata
+}
+
 /// Get geometry type name
 fn geometry_type_name(geom: &Geometry<f64>) -> String {
     match geom {
@@ -301,6 +801,7 @@ fn geometry_type_name(geom: &Geometry<f64>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use geo_types::{Coord, LineString, Point};
 
     #[test]
     fn test_parse_wkt_point() {
@@ -321,4 +822,126 @@ mod tests {
         let wkt = WktWriter::geometry_to_wkt(&point).unwrap();
         assert!(wkt.contains("POINT"));
     }
+
+    #[test]
+    fn test_wkb_dialects_and_byte_orders() {
+        let geometry = CoreGeometry::from_wkt(
+            "GEOMETRYCOLLECTION ZM (POINT ZM (1 2 0 -1), LINESTRING ZM (0 0 1 -1, 3 4 2 -1), \
+             MULTIPOLYGON ZM (((0 0 3 -1, 1 0 4 -1, 1 1 5 -1, 0 0 6 -1))))",
+            Crs::web_mercator(),
+        )
+        .unwrap();
+
+        for order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            let ewkb = WkbWriter::new().with_ewkb().with_byte_order(order).encode(&geometry);
+            assert_eq!(WkbReader::srid(&ewkb).unwrap(), Some(3857));
+            let decoded = WkbReader::decode(&ewkb, Crs::wgs84()).unwrap();
+            assert_eq!(decoded.crs(), &Crs::web_mercator());
+            assert_eq!(decoded, geometry);
+
+            let iso = WkbWriter::new().with_byte_order(order).encode(&geometry);
+            assert_eq!(WkbReader::srid(&iso).unwrap(), None);
+            let decoded = WkbReader::decode(&iso, Crs::web_mercator()).unwrap();
+            assert_eq!(decoded.dimension(), meridian_core::geometry::Dimension::Xyzm);
+            assert_eq!(decoded, geometry);
+        }
+    }
+
+    #[test]
+    fn test_decode_postgis_hex() {
+        // SRID=4326;POINT(1 2)
+        let hex = "0101000020E6100000000000000000F03F0000000000000040";
+        assert_eq!(WkbReader::srid(&decode_hex(hex).unwrap()).unwrap(), Some(4326));
+        let point = WkbReader::decode_hex(hex, Crs::web_mercator()).unwrap();
+        assert_eq!(point.to_geo(), Geometry::Point(Point::new(1.0, 2.0)));
+        assert_eq!(WkbWriter::new().with_ewkb().encode_hex(&point), hex);
+
+        // ISO POINT Z (1 2 3), big endian
+        let point = WkbReader::decode_hex("00000003E93FF000000000000040000000000000004008000000000000", Crs::wgs84())
+            .unwrap();
+        assert!(matches!(point, CoreGeometry::Point(p) if p.z == Some(3.0)));
+
+        assert!(WkbReader::decode_hex("0101000000000000000000F03F", Crs::wgs84()).is_err());
+        assert!(WkbReader::decode_hex("0201000000", Crs::wgs84()).is_err());
+    }
+
+    #[test]
+    fn test_twkb() {
+        // POINT(1 2) at precision 0, from the TWKB specification
+        let point = CoreGeometry::from_geo(Geometry::Point(Point::new(1.0, 2.0)), Crs::wgs84());
+        assert_eq!(TwkbWriter::new().with_precision(0).encode(&point), vec![0x01, 0x00, 0x02, 0x04]);
+
+        let line = CoreGeometry::from_wkt("LINESTRING Z (10.123 -5.5 1.5, 10.2 -5.25 2.25, 11 0 3)", Crs::wgs84())
+            .unwrap();
+        let writer = TwkbWriter::new().with_precision(2).with_bbox().with_size();
+        let decoded = TwkbReader::decode(&writer.encode(&line), Crs::wgs84()).unwrap();
+        let CoreGeometry::LineString(ls) = decoded else { panic!("expected a line string") };
+        assert_eq!(ls.z, Some(vec![1.5, 2.25, 3.0]));
+        assert_eq!(ls.geom.0[0], Coord { x: 10.12, y: -5.5 });
+        assert_eq!(ls.geom.0[2], Coord { x: 11.0, y: 0.0 });
+    }
+
+    #[test]
+    fn test_wkb_file_roundtrip() {
+        use futures::StreamExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut collection = FeatureCollection::from_features(vec![
+            Feature::new(Some(Geometry::Point(Point::new(1.0, 2.0)))),
+            Feature::new(None),
+            Feature::new(Some(Geometry::Point(Point::new(3.0, 4.0)))),
+        ]);
+        collection.crs = Some("EPSG:4326".to_string());
+
+        let path = dir.path().join("points.wkb");
+        let writer = WkbWriter::from_options(&LayerOptions::new().with("DIALECT", "EWKB")).unwrap();
+        writer.write(&path, &collection).unwrap();
+        writer.append(&path, &collection).unwrap();
+
+        let read = WkbReader::new().read(&path).unwrap();
+        assert_eq!(read.len(), 4);
+        assert_eq!(read.crs.as_deref(), Some("EPSG:4326"));
+        assert_eq!(WkbReader::new().read_crs(&path).unwrap().as_deref(), Some("EPSG:4326"));
+
+        let features: Vec<_> = futures::executor::block_on(WkbReader::new().read_stream(&path).unwrap().collect());
+        assert_eq!(features.len(), 4);
+        assert_eq!(features[3].as_ref().unwrap().crs.as_deref(), Some("EPSG:4326"));
+
+        let path = dir.path().join("points.twkb");
+        crate::FormatRegistry::write_auto(&path, &collection).unwrap();
+        assert_eq!(crate::FormatRegistry::read_auto(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_geometry_file_reads_past_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lines.wkb");
+
+        // Geometries larger than a read chunk, and straddling chunk ends
+        let coords: Vec<Coord<f64>> = (0..READ_CHUNK / 12).map(|i| Coord { x: i as f64, y: 0.5 }).collect();
+        let line = Feature::new(Some(Geometry::LineString(LineString(coords))));
+        let point = Feature::new(Some(Geometry::Point(Point::new(1.0, 2.0))));
+        let collection = FeatureCollection::from_features(vec![line.clone(), point, line]);
+        WkbWriter::new().write(&path, &collection).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 2 * READ_CHUNK as u64);
+
+        let read = WkbReader::new().read(&path).unwrap();
+        assert_eq!(read.len(), 3);
+        assert_eq!(read.features[2].geometry, collection.features[2].geometry);
+
+        let mut truncated = std::fs::read(&path).unwrap();
+        truncated.truncate(truncated.len() - 1);
+        std::fs::write(&path, truncated).unwrap();
+        assert!(matches!(WkbReader::new().read(&path), Err(IoError::Io(_))));
+
+        // Invalid data fails without reading the rest of the file
+        let mut invalid = WkbWriter::new().encode(&core_geometry(&Point::new(1.0, 2.0).into(), None));
+        invalid.push(7);
+        invalid.resize(4 * READ_CHUNK, 0);
+        std::fs::write(&path, invalid).unwrap();
+        let mut file = GeometryFile::open(&path, WkbReader::decode_next).unwrap();
+        assert!(file.next_geometry().unwrap().is_some());
+        assert!(matches!(file.next_geometry(), Err(IoError::Wkt(_))));
+        assert_eq!(file.buffer.len(), READ_CHUNK);
+    }
 }
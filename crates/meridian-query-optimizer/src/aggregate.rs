@@ -0,0 +1,369 @@
+//! Aggregate Accumulators
+//!
+//! Per-group aggregate state. State flattens to a fixed number of values, so
+//! partial aggregates from parallel workers can be merged by a final one.
+
+use crate::ast::{AggFunc, AggregateFunction, DataType, Literal, ScalarExpr};
+use crate::eval::{compare_values, value_text, BoundExpr};
+use crate::executor::{ExecutionError, ExecutionResult, Row, Value};
+use crate::spill::{decode_values, encode_key, encode_values};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Aggregate function bound to its input columns
+#[derive(Debug, Clone)]
+pub(crate) struct AggregateSpec {
+    func: AggFunc,
+    /// Argument; `None` for `COUNT(*)`
+    arg: Option<BoundExpr>,
+    distinct: bool,
+    /// Separator of `STRING_AGG`
+    delimiter: String,
+}
+
+/// Running state of one aggregate in one group
+#[derive(Debug, Clone)]
+pub(crate) enum AggState {
+    Count(i64),
+    /// Sum, minimum or maximum so far
+    Value(Value),
+    Avg { sum: Value, count: i64 },
+    /// Welford running moments for variance
+    Moments { count: i64, mean: f64, m2: f64 },
+    List(Vec<Value>),
+    Distinct { seen: HashSet<Vec<u8>>, values: Vec<Value> },
+}
+
+impl AggregateSpec {
+    pub(crate) fn bind(aggregate: &AggregateFunction, columns: &[crate::ast::ColumnRef]) -> ExecutionResult<Self> {
+        let arg = match aggregate.args.first() {
+            None => None,
+            Some(ScalarExpr::Column(c)) if c.name == "*" => None,
+            Some(expr) => Some(BoundExpr::bind(expr, columns)?),
+        };
+        let delimiter = match aggregate.args.get(1) {
+            Some(ScalarExpr::Literal(Literal::String(s))) => s.clone(),
+            _ => ",".to_string(),
+        };
+        Ok(Self {
+            func: aggregate.func,
+            arg,
+            distinct: aggregate.distinct,
+            delimiter,
+        })
+    }
+
+    /// Output type, given the type of the argument
+    pub(crate) fn output_type(&self, arg_type: Option<DataType>) -> DataType {
+        match self.func {
+            AggFunc::Count => DataType::BigInt,
+            AggFunc::Avg | AggFunc::StdDev | AggFunc::Variance => DataType::Double,
+            AggFunc::ArrayAgg | AggFunc::StringAgg => DataType::Text,
+            AggFunc::Sum => match arg_type {
+                Some(DataType::TinyInt | DataType::SmallInt | DataType::Integer | DataType::BigInt) => {
+                    DataType::BigInt
+                }
+                _ => DataType::Double,
+            },
+            AggFunc::Min | AggFunc::Max => arg_type.unwrap_or(DataType::Double),
+        }
+    }
+
+    /// Number of values in the flattened state
+    pub(crate) fn state_width(&self) -> usize {
+        match (self.distinct, self.func) {
+            (true, _) => 1,
+            (_, AggFunc::Avg) => 2,
+            (_, AggFunc::StdDev | AggFunc::Variance) => 3,
+            _ => 1,
+        }
+    }
+
+    pub(crate) fn init(&self) -> AggState {
+        if self.distinct {
+            return AggState::Distinct {
+                seen: HashSet::new(),
+                values: Vec::new(),
+            };
+        }
+        self.init_plain()
+    }
+
+    fn init_plain(&self) -> AggState {
+        match self.func {
+            AggFunc::Count => AggState::Count(0),
+            AggFunc::Sum | AggFunc::Min | AggFunc::Max => AggState::Value(Value::Null),
+            AggFunc::Avg => AggState::Avg {
+                sum: Value::Null,
+                count: 0,
+            },
+            AggFunc::StdDev | AggFunc::Variance => AggState::Moments {
+                count: 0,
+                mean: 0.0,
+                m2: 0.0,
+            },
+            AggFunc::ArrayAgg | AggFunc::StringAgg => AggState::List(Vec::new()),
+        }
+    }
+
+    /// Add an input row; returns the bytes of state retained
+    pub(crate) fn update(&self, state: &mut AggState, row: &Row) -> ExecutionResult<usize> {
+        let value = match &self.arg {
+            Some(arg) => arg.eval(row)?,
+            None => Value::Boolean(true),
+        };
+        if let AggState::Distinct { seen, values } = state {
+            if value.is_null() || !seen.insert(encode_key(std::slice::from_ref(&value))) {
+                return Ok(0);
+            }
+            let size = value_size(&value);
+            values.push(value);
+            return Ok(size);
+        }
+        self.update_value(state, value)
+    }
+
+    fn update_value(&self, state: &mut AggState, value: Value) -> ExecutionResult<usize> {
+        match state {
+            AggState::Count(count) => *count += i64::from(!value.is_null()),
+            AggState::List(values) => {
+                if self.func == AggFunc::StringAgg && value.is_null() {
+                    return Ok(0);
+                }
+                let size = value_size(&value);
+                values.push(value);
+                return Ok(size);
+            }
+            _ if value.is_null() => {}
+            AggState::Value(current) => {
+                let replace = match self.func {
+                    AggFunc::Sum => {
+                        *current = add(current, &value)?;
+                        false
+                    }
+                    AggFunc::Min => current.is_null() || compare_values(&value, current) == Ordering::Less,
+                    _ => current.is_null() || compare_values(&value, current) == Ordering::Greater,
+                };
+                if replace {
+                    *current = value;
+                }
+            }
+            AggState::Avg { sum, count } => {
+                *sum = add(sum, &value)?;
+                *count += 1;
+            }
+            AggState::Moments { count, mean, m2 } => {
+                let x = numeric(&value)?;
+                *count += 1;
+                let delta = x - *mean;
+                *mean += delta / *count as f64;
+                *m2 += delta * (x - *mean);
+            }
+            AggState::Distinct { .. } => unreachable!("distinct values are collected by update"),
+        }
+        Ok(0)
+    }
+
+    /// Merge a flattened partial state; returns the bytes of state retained
+    pub(crate) fn merge(&self, state: &mut AggState, partial: &[Value]) -> ExecutionResult<usize> {
+        let list = || match &partial[0] {
+            Value::Binary(bytes) => decode_values(bytes),
+            other => Err(ExecutionError::Failed(format!("Invalid aggregate state: {:?}", other))),
+        };
+        match state {
+            AggState::Count(count) => *count += int(&partial[0])?,
+            AggState::Value(_) => return self.update_value(state, partial[0].clone()),
+            AggState::Avg { sum, count } => {
+                if !partial[0].is_null() {
+                    *sum = add(sum, &partial[0])?;
+                }
+                *count += int(&partial[1])?;
+            }
+            AggState::Moments { count, mean, m2 } => {
+                let (n, other_mean, other_m2) = (int(&partial[0])?, numeric(&partial[1])?, numeric(&partial[2])?);
+                if n > 0 {
+                    let total = *count + n;
+                    let delta = other_mean - *mean;
+                    *m2 += other_m2 + delta * delta * (*count as f64) * (n as f64) / total as f64;
+                    *mean += delta * n as f64 / total as f64;
+                    *count = total;
+                }
+            }
+            AggState::List(values) => {
+                let more = list()?;
+                let size = more.iter().map(value_size).sum();
+                values.extend(more);
+                return Ok(size);
+            }
+            AggState::Distinct { seen, values } => {
+                let mut size = 0;
+                for value in list()? {
+                    if seen.insert(encode_key(std::slice::from_ref(&value))) {
+                        size += value_size(&value);
+                        values.push(value);
+                    }
+                }
+                return Ok(size);
+            }
+        }
+        Ok(0)
+    }
+
+    /// Flatten the state for a final aggregate to merge
+    pub(crate) fn state_values(&self, state: AggState) -> Vec<Value> {
+        match state {
+            AggState::Count(count) => vec![Value::Integer(count)],
+            AggState::Value(value) => vec![value],
+            AggState::Avg { sum, count } => vec![sum, Value::Integer(count)],
+            AggState::Moments { count, mean, m2 } => {
+                vec![Value::Integer(count), Value::Float(mean), Value::Float(m2)]
+            }
+            AggState::List(values) | AggState::Distinct { values, .. } => {
+                vec![Value::Binary(encode_values(&values))]
+            }
+        }
+    }
+
+    /// Final aggregate value
+    pub(crate) fn finish(&self, state: AggState) -> ExecutionResult<Value> {
+        Ok(match state {
+            AggState::Distinct { values, .. } => {
+                let mut plain = self.init_plain();
+                for value in values {
+                    self.update_value(&mut plain, value)?;
+                }
+                return self.finish(plain);
+            }
+            AggState::Count(count) => Value::Integer(count),
+            AggState::Value(value) => value,
+            AggState::Avg { sum, count } => match numeric(&sum) {
+                Ok(sum) if count > 0 => Value::Float(sum / count as f64),
+                _ => Value::Null,
+            },
+            AggState::Moments { count, m2, .. } if count > 1 => {
+                let variance = m2 / (count - 1) as f64;
+                Value::Float(if self.func == AggFunc::StdDev { variance.sqrt() } else { variance })
+            }
+            AggState::Moments { .. } => Value::Null,
+            AggState::List(values) if values.is_empty() => Value::Null,
+            AggState::List(values) if self.func == AggFunc::StringAgg => {
+                let parts: Vec<String> = values.iter().map(value_text).collect();
+                Value::String(parts.join(&self.delimiter))
+            }
+            AggState::List(values) => {
+                let parts: Vec<String> = values
+                    .iter()
+                    .map(|v| if v.is_null() { "NULL".to_string() } else { value_text(v) })
+                    .collect();
+                Value::String(format!("{{{}}}", parts.join(",")))
+            }
+        })
+    }
+}
+
+fn value_size(value: &Value) -> usize {
+    std::mem::size_of::<Value>()
+        + match value {
+            Value::String(s) => s.len(),
+            Value::Binary(b) => b.len(),
+            _ => 0,
+        }
+}
+
+fn invalid(value: &Value) -> ExecutionError {
+    ExecutionError::Failed(format!("Invalid aggregate input: {:?}", value))
+}
+
+fn numeric(value: &Value) -> ExecutionResult<f64> {
+    match value {
+        Value::Integer(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        other => Err(invalid(other)),
+    }
+}
+
+fn int(value: &Value) -> ExecutionResult<i64> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        other => Err(invalid(other)),
+    }
+}
+
+fn add(sum: &Value, value: &Value) -> ExecutionResult<Value> {
+    Ok(match (sum, value) {
+        (Value::Null, v) => v.clone(),
+        (Value::Integer(a), Value::Integer(b)) => Value::Integer(
+            a.checked_add(*b)
+                .ok_or_else(|| ExecutionError::Failed("Integer overflow".to_string()))?,
+        ),
+        (a, b) => Value::Float(numeric(a)? + numeric(b)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::ColumnRef;
+
+    fn spec(func: AggFunc, distinct: bool) -> AggregateSpec {
+        let aggregate = AggregateFunction {
+            func,
+            args: vec![ScalarExpr::Column(ColumnRef::new("x"))],
+            distinct,
+            alias: None,
+        };
+        AggregateSpec::bind(&aggregate, &[ColumnRef::new("x")]).unwrap()
+    }
+
+    fn run(spec: &AggregateSpec, values: &[Value]) -> AggState {
+        let mut state = spec.init();
+        for value in values {
+            spec.update(&mut state, &Row::new(vec![value.clone()])).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn test_aggregate_functions() {
+        let values = [Value::Integer(2), Value::Null, Value::Integer(4), Value::Integer(4), Value::Integer(9)];
+        let finish = |func, distinct| {
+            let spec = spec(func, distinct);
+            spec.finish(run(&spec, &values)).unwrap()
+        };
+        assert_eq!(finish(AggFunc::Count, false), Value::Integer(4));
+        assert_eq!(finish(AggFunc::Count, true), Value::Integer(3));
+        assert_eq!(finish(AggFunc::Sum, false), Value::Integer(19));
+        assert_eq!(finish(AggFunc::Sum, true), Value::Integer(15));
+        assert_eq!(finish(AggFunc::Min, false), Value::Integer(2));
+        assert_eq!(finish(AggFunc::Max, false), Value::Integer(9));
+        assert_eq!(finish(AggFunc::Avg, false), Value::Float(4.75));
+        match finish(AggFunc::Variance, false) {
+            Value::Float(v) => assert!((v - 26.75 / 3.0).abs() < 1e-9),
+            other => panic!("unexpected variance {:?}", other),
+        }
+        assert_eq!(finish(AggFunc::ArrayAgg, false), Value::String("{2,NULL,4,4,9}".to_string()));
+        assert_eq!(finish(AggFunc::StringAgg, true), Value::String("2,4,9".to_string()));
+    }
+
+    #[test]
+    fn test_merge_partial_states() {
+        let values: Vec<Value> = (1..=10).map(Value::Integer).collect();
+        for func in [AggFunc::Avg, AggFunc::StdDev, AggFunc::Count, AggFunc::Max] {
+            for distinct in [false, true] {
+                let spec = spec(func, distinct);
+                let whole = spec.finish(run(&spec, &values)).unwrap();
+
+                let mut merged = spec.init();
+                for chunk in values.chunks(3) {
+                    let partial = spec.state_values(run(&spec, chunk));
+                    assert_eq!(partial.len(), spec.state_width());
+                    spec.merge(&mut merged, &partial).unwrap();
+                }
+                match (spec.finish(merged).unwrap(), whole) {
+                    (Value::Float(a), Value::Float(b)) => assert!((a - b).abs() < 1e-9),
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+        }
+    }
+}
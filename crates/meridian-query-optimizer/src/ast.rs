@@ -359,6 +359,10 @@ pub enum BinaryOp {
     // Pattern matching
     RegexMatch,
     RegexNotMatch,
+
+    // Spatial
+    /// Bounding boxes intersect, `&&`
+    BboxIntersects,
 }
 
 /// Unary operators
@@ -395,7 +399,7 @@ impl Schema {
 }
 
 /// Column definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ColumnDef {
     pub name: String,
     pub data_type: DataType,
@@ -443,6 +447,8 @@ pub enum DataType {
     Uuid,
     Array(Box<DataType>),
     Struct(Vec<ColumnDef>),
+    /// Geometry as WKT or WKB
+    Geometry,
 }
 
 impl DataType {
@@ -468,6 +474,7 @@ impl DataType {
             DataType::Uuid => 16,
             DataType::Array(inner) => inner.estimated_size() * 10, // estimate 10 elements
             DataType::Struct(fields) => fields.iter().map(|f| f.data_type.estimated_size()).sum(),
+            DataType::Geometry => 128, // average estimate
         }
     }
 }
@@ -381,7 +381,8 @@ struct CachedResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::plan::{Cost, PhysicalNode, PhysicalOp, Schema, Cardinality};
+    use crate::ast::Schema;
+    use crate::plan::{Cost, PhysicalNode, PhysicalOp, Cardinality};
 
     fn create_test_plan() -> PhysicalPlan {
         let node = PhysicalNode::new(
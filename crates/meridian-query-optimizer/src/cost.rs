@@ -8,7 +8,11 @@
 
 use crate::ast::*;
 use crate::plan::*;
-use crate::statistics::{ColumnStatistics, TableStatistics};
+use crate::spatial::{SpatialCondition, SpatialPredicate};
+use crate::statistics::{
+    ColumnStatistics, SpatialHistogram, TableStatistics, DEFAULT_SPATIAL_JOIN_SELECTIVITY,
+    DEFAULT_SPATIAL_SELECTIVITY,
+};
 use std::collections::HashMap;
 
 /// System configuration for cost modeling
@@ -24,6 +28,8 @@ pub struct CostConfig {
     pub cpu_operator_cost: f64,
     /// CPU cost per index entry
     pub cpu_index_tuple_cost: f64,
+    /// CPU cost per exact spatial predicate evaluation
+    pub cpu_spatial_operator_cost: f64,
     /// Network cost per byte transferred
     pub network_byte_cost: f64,
     /// Memory cost per byte
@@ -44,6 +50,7 @@ impl Default for CostConfig {
             cpu_tuple_cost: 0.01,
             cpu_operator_cost: 0.0025,
             cpu_index_tuple_cost: 0.005,
+            cpu_spatial_operator_cost: 0.05,
             network_byte_cost: 0.001,
             memory_byte_cost: 0.0001,
             page_size: 8192, // 8KB pages (PostgreSQL default)
@@ -128,6 +135,21 @@ impl CostEstimator {
                 condition,
             } => self.cost_merge_join(join_type, left_keys, right_keys, condition, children),
 
+            PhysicalOp::SpatialJoin {
+                join_type,
+                left_geometry,
+                right_geometry,
+                distance,
+                strategy,
+                ..
+            } => self.cost_spatial_join(
+                join_type,
+                (left_geometry, right_geometry),
+                distance.unwrap_or(0.0),
+                *strategy,
+                children,
+            ),
+
             PhysicalOp::HashAggregate {
                 group_by,
                 aggregates,
@@ -184,8 +206,7 @@ impl CostEstimator {
         let io_cost = total_pages * self.config.seq_page_cost;
 
         // CPU cost: process all tuples + evaluate predicates
-        let cpu_per_tuple = self.config.cpu_tuple_cost
-            + predicates.len() as f64 * self.config.cpu_operator_cost;
+        let cpu_per_tuple = self.config.cpu_tuple_cost + self.predicate_cpu_cost(predicates);
         let cpu_cost = total_rows * cpu_per_tuple;
 
         // Memory cost: minimal for scan
@@ -226,11 +247,19 @@ impl CostEstimator {
 
         let io_cost = index_io + table_io;
 
-        // CPU cost: index traversal + tuple processing
+        // CPU cost: index traversal + tuple processing. GiST entries only
+        // hold bounding boxes, so spatial key conditions are rechecked
+        // against the fetched geometries.
+        let recheck: Vec<ScalarExpr> = key_conditions
+            .iter()
+            .filter(|c| SpatialCondition::from_expr(c).is_some())
+            .cloned()
+            .collect();
         let cpu_cost = index_rows
             * (self.config.cpu_index_tuple_cost
                 + self.config.cpu_tuple_cost
-                + predicates.len() as f64 * self.config.cpu_operator_cost);
+                + self.predicate_cpu_cost(&recheck)
+                + self.predicate_cpu_cost(predicates));
 
         // Apply additional predicates
         let final_selectivity = index_selectivity * self.estimate_selectivity(predicates, table);
@@ -277,8 +306,7 @@ impl CostEstimator {
         let child_rows = child.cardinality.rows;
 
         // CPU cost: evaluate predicates for each tuple
-        let cpu_per_tuple =
-            self.config.cpu_tuple_cost + predicates.len() as f64 * self.config.cpu_operator_cost;
+        let cpu_per_tuple = self.config.cpu_tuple_cost + self.predicate_cpu_cost(predicates);
         let cpu_cost = child_rows * cpu_per_tuple;
 
         // Estimate output cardinality
@@ -426,6 +454,116 @@ impl CostEstimator {
         (cost, cardinality)
     }
 
+    // Spatial Join Cost
+    fn cost_spatial_join(
+        &self,
+        join_type: &JoinType,
+        geometries: (&ScalarExpr, &ScalarExpr),
+        distance: f64,
+        strategy: SpatialJoinStrategy,
+        children: &[PhysicalNode],
+    ) -> (Cost, Cardinality) {
+        assert_eq!(children.len(), 2);
+        let left = &children[0];
+        let right = &children[1];
+
+        let left_rows = left.cardinality.rows.max(1.0);
+        let right_rows = right.cardinality.rows.max(1.0);
+        let tuple_size = 100.0 + DataType::Geometry.estimated_size() as f64;
+
+        let selectivity = match (
+            self.geometry_histogram(geometries.0, left),
+            self.geometry_histogram(geometries.1, right),
+        ) {
+            (Some(l), Some(r)) => l.estimate_join_selectivity(r, distance),
+            _ => DEFAULT_SPATIAL_JOIN_SELECTIVITY,
+        };
+        let pairs = left_rows * right_rows * selectivity;
+        // Bounding boxes pass more candidates than the exact predicate
+        let candidates = pairs * 1.5;
+        let refine_cpu = candidates * self.config.cpu_spatial_operator_cost;
+
+        let (io_cost, cpu_cost, memory_cost) = match strategy {
+            SpatialJoinStrategy::IndexNestedLoop => {
+                // R-tree bulk load over the right input, one descent per left row
+                let build_cpu = right_rows * right_rows.log2().max(1.0) * self.config.cpu_operator_cost;
+                let height = right_rows.log(16.0).ceil().max(1.0);
+                let probe_cpu = left_rows * height * 16.0 * self.config.cpu_index_tuple_cost;
+                let build_size = right_rows * tuple_size;
+                // An inner side beyond work_mem is partitioned at run time
+                let spill_io = if build_size > self.config.work_mem as f64 {
+                    2.0 * (left_rows + right_rows) * tuple_size / self.config.page_size as f64
+                        * self.config.seq_page_cost
+                } else {
+                    0.0
+                };
+                (
+                    spill_io,
+                    build_cpu + probe_cpu,
+                    build_size.min(self.config.work_mem as f64) * self.config.memory_byte_cost,
+                )
+            }
+            SpatialJoinStrategy::PartitionMerge => {
+                // Geometries overlapping several grid cells are replicated
+                let replication = 1.2;
+                let total_rows = (left_rows + right_rows) * replication;
+                let total_size = total_rows * tuple_size;
+                let partition_io = if total_size > self.config.work_mem as f64 {
+                    2.0 * total_size / self.config.page_size as f64 * self.config.seq_page_cost
+                } else {
+                    0.0
+                };
+                let partition_cpu = total_rows * self.config.cpu_operator_cost;
+                // Sort each cell by min x, then sweep
+                let sort_cpu = total_rows * total_rows.log2().max(1.0) * self.config.cpu_operator_cost;
+                (
+                    partition_io,
+                    partition_cpu + sort_cpu,
+                    total_size.min(self.config.work_mem as f64) * self.config.memory_byte_cost,
+                )
+            }
+        };
+
+        let output_rows = match join_type {
+            JoinType::Semi => left_rows * (right_rows * selectivity).min(1.0),
+            JoinType::AntiSemi => left_rows * (1.0 - (right_rows * selectivity).min(1.0)),
+            JoinType::Left => pairs.max(left_rows),
+            JoinType::Right => pairs.max(right_rows),
+            JoinType::Full => pairs.max(left_rows + right_rows),
+            JoinType::Inner | JoinType::Cross => pairs,
+        };
+
+        let cost = left.cost.add(&right.cost).add(&Cost::new(
+            io_cost,
+            cpu_cost + refine_cpu,
+            0.0,
+            memory_cost,
+        ));
+        let cardinality = Cardinality::with_confidence(output_rows, 0.6);
+
+        (cost, cardinality)
+    }
+
+    /// Cheaper spatial join strategy for two planned inputs
+    pub fn choose_spatial_join_strategy(
+        &self,
+        join_type: &JoinType,
+        geometries: (&ScalarExpr, &ScalarExpr),
+        distance: f64,
+        children: &[PhysicalNode],
+    ) -> SpatialJoinStrategy {
+        let cost = |strategy| {
+            self.cost_spatial_join(join_type, geometries, distance, strategy, children)
+                .0
+                .total_cost
+        };
+        if cost(SpatialJoinStrategy::PartitionMerge) < cost(SpatialJoinStrategy::IndexNestedLoop) {
+            SpatialJoinStrategy::PartitionMerge
+        } else {
+            SpatialJoinStrategy::IndexNestedLoop
+        }
+    }
+
     // Hash Aggregate Cost
     fn cost_hash_aggregate(
         &self,
@@ -708,15 +846,76 @@ impl CostEstimator {
             return 1.0;
         }
 
-        // Simplified: assume each predicate reduces by 10%
-        let per_predicate = 0.1;
-        per_predicate.powi(predicates.len() as i32)
+        // Spatial predicates use the column's 2D histogram; others are
+        // assumed to keep 10% each
+        let stats = self.get_table_stats(table);
+        predicates
+            .iter()
+            .map(|predicate| match SpatialCondition::from_expr(predicate) {
+                Some(condition) => Self::spatial_selectivity(&stats, &condition),
+                None => 0.1,
+            })
+            .product()
     }
 
     // Helper: Estimate index selectivity
-    fn estimate_index_selectivity(&self, _key_conditions: &[ScalarExpr], _table: &str) -> f64 {
-        // Simplified: assume index is selective
-        0.01 // 1% selectivity
+    fn estimate_index_selectivity(&self, key_conditions: &[ScalarExpr], table: &str) -> f64 {
+        let stats = self.get_table_stats(table);
+        let mut spatial = key_conditions.iter().filter_map(SpatialCondition::from_expr).peekable();
+        if spatial.peek().is_none() {
+            // Simplified: assume index is selective
+            return 0.01; // 1% selectivity
+        }
+        let selectivity: f64 = spatial.map(|c| Self::spatial_selectivity(&stats, &c)).product();
+        if key_conditions.iter().any(|c| SpatialCondition::from_expr(c).is_none()) {
+            selectivity * 0.01
+        } else {
+            selectivity
+        }
+    }
+
+    fn spatial_selectivity(stats: &TableStatistics, condition: &SpatialCondition) -> f64 {
+        match condition.window() {
+            Some(window) => stats.estimate_spatial_selectivity(&window.column.name, &window.envelope, window.within),
+            None => DEFAULT_SPATIAL_SELECTIVITY,
+        }
+    }
+
+    // Helper: CPU cost of evaluating predicates on one tuple
+    fn predicate_cpu_cost(&self, predicates: &[ScalarExpr]) -> f64 {
+        predicates
+            .iter()
+            .map(|p| match SpatialCondition::from_expr(p) {
+                // `&&` only compares bounding boxes
+                Some(c) if c.predicate == SpatialPredicate::BboxIntersects => self.config.cpu_operator_cost,
+                Some(_) => self.config.cpu_spatial_operator_cost,
+                None => self.config.cpu_operator_cost,
+            })
+            .sum()
+    }
+
+    // Helper: Histogram of a geometry column scanned in a subtree
+    fn geometry_histogram(&self, expr: &ScalarExpr, node: &PhysicalNode) -> Option<&SpatialHistogram> {
+        let ScalarExpr::Column(column) = expr else {
+            return None;
+        };
+        let mut tables: Vec<&str> = column.table.as_deref().into_iter().collect();
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            match &node.op {
+                PhysicalOp::SeqScan { table, .. }
+                | PhysicalOp::IndexScan { table, .. }
+                | PhysicalOp::BitmapScan { table, .. } => tables.push(table),
+                _ => stack.extend(&node.children),
+            }
+        }
+        tables.into_iter().find_map(|table| {
+            self.statistics
+                .get(table)?
+                .get_column_stats(&column.name)?
+                .spatial_histogram
+                .as_ref()
+        })
     }
 }
 
@@ -742,4 +941,69 @@ mod tests {
         assert!(cost.total_cost > 0.0);
         assert!(card.rows > 0.0);
     }
+
+    fn spatial_stats(table: &str, rows: usize) -> TableStatistics {
+        use crate::spatial::Envelope;
+        use crate::statistics::StatisticsCollector;
+
+        let side = (rows as f64).sqrt() as usize;
+        let envelopes: Vec<_> = (0..side * side)
+            .map(|i| {
+                let (x, y) = ((i % side) as f64, (i / side) as f64);
+                Some(Envelope::new(x, y, x + 0.5, y + 0.5))
+            })
+            .collect();
+        let mut stats = TableStatistics::new(table, rows as u64, rows as u64 / 50);
+        stats.add_column_stats(
+            "geom".to_string(),
+            StatisticsCollector::with_full_scan().collect_geometry_stats("geom", &envelopes),
+        );
+        stats
+    }
+
+    #[test]
+    fn test_spatial_scan_selectivity() {
+        let mut estimator = CostEstimator::with_default_config();
+        estimator.add_table_stats("parcels".to_string(), spatial_stats("parcels", 10_000));
+
+        let window = ScalarExpr::Function {
+            name: "ST_Intersects".to_string(),
+            args: vec![
+                ScalarExpr::Column(ColumnRef::new("geom")),
+                ScalarExpr::Literal(Literal::String("POLYGON((0 0, 3 0, 3 3, 0 3, 0 0))".to_string())),
+            ],
+        };
+        let (scan_cost, scan_card) = estimator.cost_seq_scan("parcels", std::slice::from_ref(&window), &None);
+        assert!(scan_card.rows > 5.0 && scan_card.rows < 40.0, "{}", scan_card.rows);
+
+        let (index_cost, index_card) = estimator.cost_index_scan("parcels", "parcels_geom_gist", &[window], &[]);
+        assert!((index_card.rows - scan_card.rows).abs() < 1e-6);
+        assert!(index_cost.total_cost < scan_cost.total_cost);
+    }
+
+    #[test]
+    fn test_spatial_join_strategy() {
+        let mut estimator = CostEstimator::with_default_config();
+        estimator.add_table_stats("small".to_string(), spatial_stats("small", 100));
+        estimator.add_table_stats("large".to_string(), spatial_stats("large", 1_000_000));
+
+        let scan = |table: &str| {
+            let op = PhysicalOp::SeqScan {
+                table: table.to_string(),
+                alias: None,
+                predicates: vec![],
+                projection: None,
+            };
+            let (cost, cardinality) = estimator.estimate_operator_cost(&op, &[]);
+            PhysicalNode::new(op, vec![], Schema::empty(), cost, cardinality)
+        };
+        let geom = ScalarExpr::Column(ColumnRef::new("geom"));
+        let strategy = |left: &str, right: &str| {
+            estimator.choose_spatial_join_strategy(&JoinType::Inner, (&geom, &geom), 0.0, &[scan(left), scan(right)])
+        };
+
+        // Probing a small R-tree beats partitioning; a huge inner side does not fit in memory
+        assert_eq!(strategy("large", "small"), SpatialJoinStrategy::IndexNestedLoop);
+        assert_eq!(strategy("large", "large"), SpatialJoinStrategy::PartitionMerge);
+    }
 }
@@ -0,0 +1,753 @@
+//! Scalar Expression Evaluation
+//!
+//! Binds scalar expressions to the output columns of an operator's input
+//! once, then evaluates them per row with SQL three-valued logic.
+
+use crate::ast::{AggFunc, AggregateFunction, BinaryOp, ColumnRef, Literal, ScalarExpr, UnaryOp};
+use crate::executor::{ExecutionError, ExecutionResult, Row, Value};
+use crate::spatial::{Geometry, SpatialPredicate};
+use std::cmp::Ordering;
+
+/// Output column name of a computed expression
+///
+/// Operators name computed columns after their expression, so that
+/// operators above can refer to e.g. `count(id)` after an aggregate.
+pub(crate) fn expr_name(expr: &ScalarExpr) -> String {
+    match expr {
+        ScalarExpr::Column(column) => match &column.table {
+            Some(table) => format!("{}.{}", table, column.name),
+            None => column.name.clone(),
+        },
+        ScalarExpr::Literal(literal) => match literal {
+            Literal::Null => "NULL".to_string(),
+            Literal::Boolean(b) => b.to_string(),
+            Literal::Integer(i) => i.to_string(),
+            Literal::Float(f) => f.to_string(),
+            Literal::String(s) | Literal::Date(s) | Literal::Timestamp(s) | Literal::Interval(s) => {
+                format!("'{}'", s)
+            }
+        },
+        ScalarExpr::BinaryOp { left, op, right } => {
+            format!("({} {} {})", expr_name(left), binary_op_symbol(*op), expr_name(right))
+        }
+        ScalarExpr::UnaryOp { op, expr } => match op {
+            UnaryOp::Not => format!("NOT {}", expr_name(expr)),
+            UnaryOp::Negate => format!("-{}", expr_name(expr)),
+            UnaryOp::IsNull => format!("{} IS NULL", expr_name(expr)),
+            UnaryOp::IsNotNull => format!("{} IS NOT NULL", expr_name(expr)),
+        },
+        ScalarExpr::Function { name, args } => {
+            let args: Vec<String> = args.iter().map(expr_name).collect();
+            format!("{}({})", name.to_lowercase(), args.join(", "))
+        }
+        ScalarExpr::Case { .. } => "case".to_string(),
+        ScalarExpr::In { expr, list, negated } => {
+            let list: Vec<String> = list.iter().map(expr_name).collect();
+            let not = if *negated { " NOT" } else { "" };
+            format!("{}{} IN ({})", expr_name(expr), not, list.join(", "))
+        }
+        ScalarExpr::Between { expr, low, high, negated } => {
+            let not = if *negated { " NOT" } else { "" };
+            format!("{}{} BETWEEN {} AND {}", expr_name(expr), not, expr_name(low), expr_name(high))
+        }
+        ScalarExpr::Subquery(_) => "subquery".to_string(),
+    }
+}
+
+/// Output column name of an aggregate, its alias or e.g. `count(*)`
+pub(crate) fn aggregate_name(aggregate: &AggregateFunction) -> String {
+    if let Some(alias) = &aggregate.alias {
+        return alias.clone();
+    }
+    let func = match aggregate.func {
+        AggFunc::Count => "count",
+        AggFunc::Sum => "sum",
+        AggFunc::Avg => "avg",
+        AggFunc::Min => "min",
+        AggFunc::Max => "max",
+        AggFunc::StdDev => "stddev",
+        AggFunc::Variance => "variance",
+        AggFunc::ArrayAgg => "array_agg",
+        AggFunc::StringAgg => "string_agg",
+    };
+    let args: Vec<String> = aggregate.args.iter().map(expr_name).collect();
+    let args = if args.is_empty() { "*".to_string() } else { args.join(", ") };
+    let distinct = if aggregate.distinct { "distinct " } else { "" };
+    format!("{}({}{})", func, distinct, args)
+}
+
+fn binary_op_symbol(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Subtract => "-",
+        BinaryOp::Multiply => "*",
+        BinaryOp::Divide => "/",
+        BinaryOp::Modulo => "%",
+        BinaryOp::Eq => "=",
+        BinaryOp::NotEq => "<>",
+        BinaryOp::Lt => "<",
+        BinaryOp::LtEq => "<=",
+        BinaryOp::Gt => ">",
+        BinaryOp::GtEq => ">=",
+        BinaryOp::And => "AND",
+        BinaryOp::Or => "OR",
+        BinaryOp::Like => "LIKE",
+        BinaryOp::NotLike => "NOT LIKE",
+        BinaryOp::ILike => "ILIKE",
+        BinaryOp::NotILike => "NOT ILIKE",
+        BinaryOp::RegexMatch => "~",
+        BinaryOp::RegexNotMatch => "!~",
+        BinaryOp::BboxIntersects => "&&",
+    }
+}
+
+/// Find a column by reference
+///
+/// A qualified reference matches a column of that table first, then an
+/// unqualified column of the same name; an unqualified reference matches
+/// the first column with that name.
+pub(crate) fn resolve_column(columns: &[ColumnRef], column: &ColumnRef) -> Option<usize> {
+    match &column.table {
+        Some(_) => columns
+            .iter()
+            .position(|c| c == column)
+            .or_else(|| columns.iter().position(|c| c.table.is_none() && c.name == column.name)),
+        None => columns.iter().position(|c| c.name == column.name),
+    }
+}
+
+/// Expression bound to column positions
+#[derive(Debug, Clone)]
+pub(crate) enum BoundExpr {
+    Column(usize),
+    Literal(Value),
+    Binary(Box<BoundExpr>, BinaryOp, Box<BoundExpr>),
+    Unary(UnaryOp, Box<BoundExpr>),
+    Function(String, Vec<BoundExpr>),
+    Case {
+        operand: Option<Box<BoundExpr>>,
+        when_clauses: Vec<(BoundExpr, BoundExpr)>,
+        else_clause: Option<Box<BoundExpr>>,
+    },
+    In {
+        expr: Box<BoundExpr>,
+        list: Vec<BoundExpr>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<BoundExpr>,
+        low: Box<BoundExpr>,
+        high: Box<BoundExpr>,
+        negated: bool,
+    },
+}
+
+impl BoundExpr {
+    /// Bind an expression to the columns of a row layout
+    pub(crate) fn bind(expr: &ScalarExpr, columns: &[ColumnRef]) -> ExecutionResult<Self> {
+        // Computed columns of lower operators, e.g. aggregates, are reused by name
+        if !matches!(expr, ScalarExpr::Column(_) | ScalarExpr::Literal(_)) {
+            let name = ColumnRef::new(expr_name(expr));
+            if let Some(index) = resolve_column(columns, &name) {
+                return Ok(BoundExpr::Column(index));
+            }
+        }
+
+        let bind_box = |e: &ScalarExpr| Self::bind(e, columns).map(Box::new);
+        Ok(match expr {
+            ScalarExpr::Column(column) => BoundExpr::Column(
+                resolve_column(columns, column)
+                    .ok_or_else(|| ExecutionError::Failed(format!("Unknown column: {}", expr_name(expr))))?,
+            ),
+            ScalarExpr::Literal(literal) => BoundExpr::Literal(literal_value(literal)),
+            ScalarExpr::BinaryOp { left, op, right } => BoundExpr::Binary(bind_box(left)?, *op, bind_box(right)?),
+            ScalarExpr::UnaryOp { op, expr } => BoundExpr::Unary(*op, bind_box(expr)?),
+            ScalarExpr::Function { name, args } => BoundExpr::Function(
+                name.to_lowercase(),
+                args.iter().map(|a| Self::bind(a, columns)).collect::<ExecutionResult<_>>()?,
+            ),
+            ScalarExpr::Case {
+                operand,
+                when_clauses,
+                else_clause,
+            } => BoundExpr::Case {
+                operand: operand.as_deref().map(bind_box).transpose()?,
+                when_clauses: when_clauses
+                    .iter()
+                    .map(|(w, t)| Ok((Self::bind(w, columns)?, Self::bind(t, columns)?)))
+                    .collect::<ExecutionResult<_>>()?,
+                else_clause: else_clause.as_deref().map(bind_box).transpose()?,
+            },
+            ScalarExpr::In { expr, list, negated } => BoundExpr::In {
+                expr: bind_box(expr)?,
+                list: list.iter().map(|e| Self::bind(e, columns)).collect::<ExecutionResult<_>>()?,
+                negated: *negated,
+            },
+            ScalarExpr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => BoundExpr::Between {
+                expr: bind_box(expr)?,
+                low: bind_box(low)?,
+                high: bind_box(high)?,
+                negated: *negated,
+            },
+            ScalarExpr::Subquery(_) => {
+                return Err(ExecutionError::NotImplemented("Scalar subqueries".to_string()))
+            }
+        })
+    }
+
+    /// Evaluate the expression on a row
+    pub(crate) fn eval(&self, row: &Row) -> ExecutionResult<Value> {
+        match self {
+            BoundExpr::Column(index) => Ok(row.values.get(*index).cloned().unwrap_or(Value::Null)),
+            BoundExpr::Literal(value) => Ok(value.clone()),
+            BoundExpr::Binary(left, op, right) => {
+                let left = left.eval(row)?;
+                // Short-circuit AND/OR, keeping three-valued semantics
+                match (op, as_bool(&left)) {
+                    (BinaryOp::And, Some(false)) => return Ok(Value::Boolean(false)),
+                    (BinaryOp::Or, Some(true)) => return Ok(Value::Boolean(true)),
+                    _ => {}
+                }
+                binary(&left, *op, &right.eval(row)?)
+            }
+            BoundExpr::Unary(op, expr) => {
+                let value = expr.eval(row)?;
+                Ok(match op {
+                    UnaryOp::IsNull => Value::Boolean(value.is_null()),
+                    UnaryOp::IsNotNull => Value::Boolean(!value.is_null()),
+                    UnaryOp::Not => match value {
+                        Value::Null => Value::Null,
+                        Value::Boolean(b) => Value::Boolean(!b),
+                        other => return Err(type_error("NOT", &other)),
+                    },
+                    UnaryOp::Negate => match value {
+                        Value::Null => Value::Null,
+                        Value::Integer(i) => Value::Integer(
+                            i.checked_neg()
+                                .ok_or_else(|| ExecutionError::Failed("Integer overflow".to_string()))?,
+                        ),
+                        Value::Float(f) => Value::Float(-f),
+                        other => return Err(type_error("-", &other)),
+                    },
+                })
+            }
+            BoundExpr::Function(name, args) => {
+                let args = args.iter().map(|a| a.eval(row)).collect::<ExecutionResult<Vec<_>>>()?;
+                function(name, &args)
+            }
+            BoundExpr::Case {
+                operand,
+                when_clauses,
+                else_clause,
+            } => {
+                let operand = operand.as_ref().map(|o| o.eval(row)).transpose()?;
+                for (when, then) in when_clauses {
+                    let condition = when.eval(row)?;
+                    let hit = match &operand {
+                        Some(operand) => sql_compare(operand, &condition) == Some(Ordering::Equal),
+                        None => as_bool(&condition) == Some(true),
+                    };
+                    if hit {
+                        return then.eval(row);
+                    }
+                }
+                else_clause.as_ref().map_or(Ok(Value::Null), |e| e.eval(row))
+            }
+            BoundExpr::In { expr, list, negated } => {
+                let value = expr.eval(row)?;
+                if value.is_null() {
+                    return Ok(Value::Null);
+                }
+                let mut saw_null = false;
+                for item in list {
+                    let item = item.eval(row)?;
+                    match sql_compare(&value, &item) {
+                        Some(Ordering::Equal) => return Ok(Value::Boolean(!negated)),
+                        None if item.is_null() => saw_null = true,
+                        _ => {}
+                    }
+                }
+                Ok(if saw_null { Value::Null } else { Value::Boolean(*negated) })
+            }
+            BoundExpr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = expr.eval(row)?;
+                let above = binary(&value, BinaryOp::GtEq, &low.eval(row)?)?;
+                let below = binary(&value, BinaryOp::LtEq, &high.eval(row)?)?;
+                let between = binary(&above, BinaryOp::And, &below)?;
+                if *negated {
+                    BoundExpr::Unary(UnaryOp::Not, Box::new(BoundExpr::Literal(between))).eval(row)
+                } else {
+                    Ok(between)
+                }
+            }
+        }
+    }
+
+    /// Evaluate a predicate; NULL counts as false
+    pub(crate) fn matches(&self, row: &Row) -> ExecutionResult<bool> {
+        match self.eval(row)? {
+            Value::Boolean(b) => Ok(b),
+            Value::Null => Ok(false),
+            other => Err(type_error("predicate", &other)),
+        }
+    }
+}
+
+/// Evaluate all predicates of a conjunction
+pub(crate) fn matches_all(predicates: &[BoundExpr], row: &Row) -> ExecutionResult<bool> {
+    for predicate in predicates {
+        if !predicate.matches(row)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+pub(crate) fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Null => Value::Null,
+        Literal::Boolean(b) => Value::Boolean(*b),
+        Literal::Integer(i) => Value::Integer(*i),
+        Literal::Float(f) => Value::Float(*f),
+        Literal::String(s) | Literal::Date(s) | Literal::Timestamp(s) | Literal::Interval(s) => {
+            Value::String(s.clone())
+        }
+    }
+}
+
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Boolean(b) => Some(*b),
+        _ => None,
+    }
+}
+
+fn type_error(op: &str, value: &Value) -> ExecutionError {
+    ExecutionError::Failed(format!("Invalid operand for {}: {:?}", op, value))
+}
+
+fn overflow() -> ExecutionError {
+    ExecutionError::Failed("Integer overflow".to_string())
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+/// Order of two values; integers and floats compare numerically
+///
+/// Values of different types are ordered by type, so that sorts are total.
+pub(crate) fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Integer(_) | Value::Float(_) => 2,
+            Value::String(_) => 3,
+            Value::Binary(_) => 4,
+        }
+    }
+    match (a, b) {
+        (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
+        (Value::Boolean(x), Value::Boolean(y)) => x.cmp(y),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Binary(x), Value::Binary(y)) => x.cmp(y),
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            as_f64(a).unwrap_or_default().total_cmp(&as_f64(b).unwrap_or_default())
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// SQL comparison, `None` if either side is NULL
+pub(crate) fn sql_compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if a.is_null() || b.is_null() {
+        None
+    } else {
+        Some(compare_values(a, b))
+    }
+}
+
+fn binary(left: &Value, op: BinaryOp, right: &Value) -> ExecutionResult<Value> {
+    use BinaryOp::*;
+    match op {
+        And | Or => {
+            let (l, r) = (as_bool(left), as_bool(right));
+            for (value, b) in [(left, l), (right, r)] {
+                if !value.is_null() && b.is_none() {
+                    return Err(type_error(binary_op_symbol(op), value));
+                }
+            }
+            Ok(match (op, l, r) {
+                (And, Some(false), _) | (And, _, Some(false)) => Value::Boolean(false),
+                (And, Some(true), Some(true)) => Value::Boolean(true),
+                (Or, Some(true), _) | (Or, _, Some(true)) => Value::Boolean(true),
+                (Or, Some(false), Some(false)) => Value::Boolean(false),
+                _ => Value::Null,
+            })
+        }
+        _ if left.is_null() || right.is_null() => Ok(Value::Null),
+        Eq | NotEq | Lt | LtEq | Gt | GtEq => {
+            let ordering = compare_values(left, right);
+            Ok(Value::Boolean(match op {
+                Eq => ordering == Ordering::Equal,
+                NotEq => ordering != Ordering::Equal,
+                Lt => ordering == Ordering::Less,
+                LtEq => ordering != Ordering::Greater,
+                Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        Like | NotLike | ILike | NotILike => match (left, right) {
+            (Value::String(text), Value::String(pattern)) => {
+                let insensitive = matches!(op, ILike | NotILike);
+                let matched = if insensitive {
+                    like(&text.to_lowercase(), &pattern.to_lowercase())
+                } else {
+                    like(text, pattern)
+                };
+                Ok(Value::Boolean(matched == matches!(op, Like | ILike)))
+            }
+            _ => Err(type_error(binary_op_symbol(op), left)),
+        },
+        RegexMatch | RegexNotMatch => Err(ExecutionError::NotImplemented(
+            "Regular expression matching".to_string(),
+        )),
+        BboxIntersects => {
+            let (left, right) = (geometry_value(left)?, geometry_value(right)?);
+            Ok(Value::Boolean(left.satisfies(SpatialPredicate::BboxIntersects, &right, 0.0)))
+        }
+        Add | Subtract | Multiply | Divide | Modulo => arithmetic(left, op, right),
+    }
+}
+
+/// Geometry of a WKT string or WKB binary value
+pub(crate) fn geometry_value(value: &Value) -> ExecutionResult<Geometry> {
+    let parsed = match value {
+        Value::String(wkt) => Geometry::from_wkt(wkt),
+        Value::Binary(wkb) => Geometry::from_wkb(wkb),
+        other => return Err(type_error("geometry", other)),
+    };
+    parsed.map_err(|e| ExecutionError::Failed(format!("Invalid geometry: {}", e)))
+}
+
+fn arithmetic(left: &Value, op: BinaryOp, right: &Value) -> ExecutionResult<Value> {
+    use BinaryOp::*;
+    match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            if matches!(op, Divide | Modulo) && *b == 0 {
+                return Err(ExecutionError::Failed("Division by zero".to_string()));
+            }
+            let result = match op {
+                Add => a.checked_add(*b),
+                Subtract => a.checked_sub(*b),
+                Multiply => a.checked_mul(*b),
+                Divide => a.checked_div(*b),
+                _ => a.checked_rem(*b),
+            };
+            result.map(Value::Integer).ok_or_else(overflow)
+        }
+        (Value::String(a), Value::String(b)) if op == Add => Ok(Value::String(format!("{}{}", a, b))),
+        _ => {
+            let (Some(a), Some(b)) = (as_f64(left), as_f64(right)) else {
+                let operand = if as_f64(left).is_none() { left } else { right };
+                return Err(type_error(binary_op_symbol(op), operand));
+            };
+            if matches!(op, Divide | Modulo) && b == 0.0 {
+                return Err(ExecutionError::Failed("Division by zero".to_string()));
+            }
+            Ok(Value::Float(match op {
+                Add => a + b,
+                Subtract => a - b,
+                Multiply => a * b,
+                Divide => a / b,
+                _ => a % b,
+            }))
+        }
+    }
+}
+
+/// SQL LIKE matching with `%`, `_` and `\` escapes
+fn like(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    // Positions in the text reachable after each pattern prefix
+    let mut reachable = vec![false; text.len() + 1];
+    reachable[0] = true;
+    let mut p = 0;
+    while p < pattern.len() {
+        let mut next = vec![false; text.len() + 1];
+        match pattern[p] {
+            '%' => {
+                let mut any = false;
+                for i in 0..=text.len() {
+                    any |= reachable[i];
+                    next[i] = any;
+                }
+            }
+            c => {
+                let (literal, wildcard) = if c == '\\' && p + 1 < pattern.len() {
+                    p += 1;
+                    (pattern[p], false)
+                } else {
+                    (c, c == '_')
+                };
+                for i in 0..text.len() {
+                    next[i + 1] = reachable[i] && (wildcard || text[i] == literal);
+                }
+            }
+        }
+        reachable = next;
+        p += 1;
+    }
+    reachable[text.len()]
+}
+
+fn function(name: &str, args: &[Value]) -> ExecutionResult<Value> {
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(ExecutionError::Failed(format!("{} expects {} arguments", name, n)))
+        }
+    };
+    match name {
+        "coalesce" => Ok(args.iter().find(|v| !v.is_null()).cloned().unwrap_or(Value::Null)),
+        "nullif" => {
+            arity(2)?;
+            Ok(if sql_compare(&args[0], &args[1]) == Some(Ordering::Equal) {
+                Value::Null
+            } else {
+                args[0].clone()
+            })
+        }
+        "greatest" | "least" => Ok(args
+            .iter()
+            .filter(|v| !v.is_null())
+            .cloned()
+            .reduce(|a, b| {
+                let greater = compare_values(&b, &a) == Ordering::Greater;
+                if greater == (name == "greatest") {
+                    b
+                } else {
+                    a
+                }
+            })
+            .unwrap_or(Value::Null)),
+        "concat" => Ok(Value::String(
+            args.iter().filter(|v| !v.is_null()).map(value_text).collect::<String>(),
+        )),
+        _ if args.iter().any(Value::is_null) => Ok(Value::Null),
+        "lower" | "upper" | "length" | "char_length" => {
+            arity(1)?;
+            let Value::String(s) = &args[0] else {
+                return Err(type_error(name, &args[0]));
+            };
+            Ok(match name {
+                "lower" => Value::String(s.to_lowercase()),
+                "upper" => Value::String(s.to_uppercase()),
+                _ => Value::Integer(s.chars().count() as i64),
+            })
+        }
+        "substr" | "substring" => {
+            let (Value::String(s), Value::Integer(start)) = (&args[0], args.get(1).unwrap_or(&Value::Integer(1)))
+            else {
+                return Err(type_error(name, &args[0]));
+            };
+            let skip = (*start - 1).max(0) as usize;
+            let take = match args.get(2) {
+                Some(Value::Integer(n)) => (*n + (*start - 1).min(0)).max(0) as usize,
+                _ => usize::MAX,
+            };
+            Ok(Value::String(s.chars().skip(skip).take(take).collect()))
+        }
+        "abs" => {
+            arity(1)?;
+            match &args[0] {
+                Value::Integer(i) => i.checked_abs().map(Value::Integer).ok_or_else(overflow),
+                Value::Float(f) => Ok(Value::Float(f.abs())),
+                other => Err(type_error(name, other)),
+            }
+        }
+        "round" | "floor" | "ceil" | "ceiling" => {
+            let Some(x) = as_f64(&args[0]) else {
+                return Err(type_error(name, &args[0]));
+            };
+            if let Value::Integer(_) = args[0] {
+                return Ok(args[0].clone());
+            }
+            let digits = match args.get(1) {
+                Some(Value::Integer(d)) => *d as i32,
+                _ => 0,
+            };
+            let scale = 10f64.powi(digits);
+            Ok(Value::Float(match name {
+                "round" => (x * scale).round() / scale,
+                "floor" => x.floor(),
+                _ => x.ceil(),
+            }))
+        }
+        "st_intersects" | "st_contains" | "st_within" | "st_dwithin" => {
+            let predicate = SpatialPredicate::from_function_name(name).expect("spatial function name");
+            arity(predicate.arity())?;
+            let distance = match args.get(2) {
+                Some(d) => as_f64(d).ok_or_else(|| type_error(name, d))?,
+                None => 0.0,
+            };
+            let (a, b) = (geometry_value(&args[0])?, geometry_value(&args[1])?);
+            Ok(Value::Boolean(a.satisfies(predicate, &b, distance)))
+        }
+        "st_makeenvelope" | "st_makepoint" | "st_point" => {
+            let coords = args
+                .iter()
+                .map(|a| as_f64(a).ok_or_else(|| type_error(name, a)))
+                .collect::<ExecutionResult<Vec<_>>>()?;
+            let geometry = match (name, coords.as_slice()) {
+                ("st_makeenvelope", [x1, y1, x2, y2, ..]) => Geometry::Polygon(vec![vec![
+                    (*x1, *y1),
+                    (*x2, *y1),
+                    (*x2, *y2),
+                    (*x1, *y2),
+                    (*x1, *y1),
+                ]]),
+                ("st_makepoint" | "st_point", [x, y, ..]) => Geometry::Point((*x, *y)),
+                _ => return Err(ExecutionError::Failed(format!("{} expects coordinates", name))),
+            };
+            Ok(Value::String(geometry.to_wkt()))
+        }
+        // Geometries are kept as WKT or WKB, so these only validate
+        "st_geomfromtext" | "st_geomfromewkt" | "st_setsrid" => {
+            geometry_value(&args[0])?;
+            Ok(args[0].clone())
+        }
+        _ => Err(ExecutionError::NotImplemented(format!("Function {}", name))),
+    }
+}
+
+/// Text form of a value, as used by string functions
+pub(crate) fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Binary(b) => b.iter().map(|byte| format!("{:02x}", byte)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str) -> ScalarExpr {
+        ScalarExpr::Column(ColumnRef::new(name))
+    }
+
+    fn literal(value: i64) -> ScalarExpr {
+        ScalarExpr::Literal(Literal::Integer(value))
+    }
+
+    fn op(left: ScalarExpr, op: BinaryOp, right: ScalarExpr) -> ScalarExpr {
+        ScalarExpr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }
+    }
+
+    #[test]
+    fn test_bind_and_evaluate() {
+        let columns = vec![ColumnRef::with_table("u", "id"), ColumnRef::with_table("o", "id"), ColumnRef::new("n")];
+        let row = Row::new(vec![Value::Integer(1), Value::Integer(2), Value::Null]);
+
+        let qualified = ScalarExpr::Column(ColumnRef::with_table("o", "id"));
+        assert_eq!(BoundExpr::bind(&qualified, &columns).unwrap().eval(&row).unwrap(), Value::Integer(2));
+
+        let sum = op(column("id"), BinaryOp::Add, ScalarExpr::Literal(Literal::Float(0.5)));
+        assert_eq!(BoundExpr::bind(&sum, &columns).unwrap().eval(&row).unwrap(), Value::Float(1.5));
+
+        // NULL comparisons are unknown, but FALSE AND NULL is false
+        let unknown = op(column("n"), BinaryOp::Eq, literal(1));
+        assert_eq!(BoundExpr::bind(&unknown, &columns).unwrap().eval(&row).unwrap(), Value::Null);
+        let conjunction = op(op(column("id"), BinaryOp::Gt, literal(5)), BinaryOp::And, unknown);
+        assert!(!BoundExpr::bind(&conjunction, &columns).unwrap().matches(&row).unwrap());
+
+        assert!(BoundExpr::bind(&column("missing"), &columns).is_err());
+        let division = op(column("id"), BinaryOp::Divide, literal(0));
+        assert!(BoundExpr::bind(&division, &columns).unwrap().eval(&row).is_err());
+    }
+
+    #[test]
+    fn test_computed_column_names() {
+        let count = ScalarExpr::Function {
+            name: "COUNT".to_string(),
+            args: vec![column("id")],
+        };
+        let aggregate = AggregateFunction {
+            func: AggFunc::Count,
+            args: vec![column("id")],
+            distinct: false,
+            alias: None,
+        };
+        assert_eq!(expr_name(&count), aggregate_name(&aggregate));
+
+        let columns = vec![ColumnRef::new("name"), ColumnRef::new(aggregate_name(&aggregate))];
+        let having = op(count, BinaryOp::Gt, literal(1));
+        let row = Row::new(vec![Value::String("a".to_string()), Value::Integer(3)]);
+        assert!(BoundExpr::bind(&having, &columns).unwrap().matches(&row).unwrap());
+    }
+
+    #[test]
+    fn test_like_and_functions() {
+        assert!(like("meridian", "mer%"));
+        assert!(like("meridian", "%i_n"));
+        assert!(!like("meridian", "m_r"));
+        assert!(like("50%", "50\\%"));
+        assert_eq!(function("upper", &[Value::String("ab".to_string())]).unwrap(), Value::String("AB".to_string()));
+        assert_eq!(
+            function("coalesce", &[Value::Null, Value::Integer(4)]).unwrap(),
+            Value::Integer(4)
+        );
+        assert_eq!(compare_values(&Value::Integer(2), &Value::Float(1.5)), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_spatial_functions() {
+        let columns = vec![ColumnRef::new("geom")];
+        let row = Row::new(vec![Value::String("POINT(3 4)".to_string())]);
+        let envelope = ScalarExpr::Function {
+            name: "ST_MakeEnvelope".to_string(),
+            args: vec![literal(0), literal(0), literal(5), literal(5)],
+        };
+        let intersects = ScalarExpr::Function {
+            name: "ST_Intersects".to_string(),
+            args: vec![column("geom"), envelope.clone()],
+        };
+        assert!(BoundExpr::bind(&intersects, &columns).unwrap().matches(&row).unwrap());
+        let overlap = op(column("geom"), BinaryOp::BboxIntersects, envelope);
+        assert!(BoundExpr::bind(&overlap, &columns).unwrap().matches(&row).unwrap());
+
+        let origin = Value::String("POINT(0 0)".to_string());
+        let point = row.values[0].clone();
+        let dwithin = |d: f64| function("st_dwithin", &[origin.clone(), point.clone(), Value::Float(d)]).unwrap();
+        assert_eq!(dwithin(5.0), Value::Boolean(true));
+        assert_eq!(dwithin(4.9), Value::Boolean(false));
+        assert!(function("st_intersects", &[origin, Value::String("POINT(1".to_string())]).is_err());
+        assert_eq!(function("st_within", &[Value::Null, point]).unwrap(), Value::Null);
+    }
+}
@@ -2,12 +2,31 @@
 //!
 //! Executes physical query plans using the Volcano model where each operator
 //! is an iterator that pulls tuples from its children.
+//!
+//! Hash tables and sorts that exceed the per-operator memory limit spill to
+//! temporary files under [`ExecutionContext::spill_directory`].
 
-use crate::ast::{ProjectionItem, ScalarExpr, Schema};
+use crate::aggregate::{AggState, AggregateSpec};
+use crate::ast::{
+    AggregateFunction, ColumnDef, ColumnRef, DataType, JoinType, Literal, OrderByItem, ProjectionItem,
+    ScalarExpr, Schema, SortDirection,
+};
+use crate::eval::{
+    aggregate_name, compare_values, expr_name, geometry_value, matches_all, resolve_column, BoundExpr,
+};
 use crate::plan::*;
+use crate::spatial::{plane_sweep, Envelope, Geometry, Grid, RTree, SpatialPredicate};
+use crate::spill::{
+    encode_key, partition_of, row_size, ExternalSorter, Partitions, RowStream, SortOrder, SpillFile,
+    SpillWriter,
+};
 use async_trait::async_trait;
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 /// Execution result
 pub type ExecutionResult<T> = Result<T, ExecutionError>;
@@ -100,6 +119,47 @@ impl Value {
     }
 }
 
+/// Source of table rows for sequential scans
+pub trait TableSource: Send + Sync {
+    /// Schema of a table, `None` if it does not exist
+    fn table_schema(&self, table: &str) -> Option<Schema>;
+
+    /// Read up to `limit` rows starting at row `offset`
+    fn read_rows(&self, table: &str, offset: usize, limit: usize) -> ExecutionResult<Vec<Row>>;
+}
+
+/// In-memory table source
+#[derive(Default)]
+pub struct MemoryTableSource {
+    tables: HashMap<String, (Schema, Vec<Row>)>,
+}
+
+impl MemoryTableSource {
+    /// Create an empty source
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a table with its rows
+    pub fn add_table(&mut self, name: impl Into<String>, schema: Schema, rows: Vec<Row>) {
+        self.tables.insert(name.into(), (schema, rows));
+    }
+}
+
+impl TableSource for MemoryTableSource {
+    fn table_schema(&self, table: &str) -> Option<Schema> {
+        self.tables.get(table).map(|(schema, _)| schema.clone())
+    }
+
+    fn read_rows(&self, table: &str, offset: usize, limit: usize) -> ExecutionResult<Vec<Row>> {
+        let (_, rows) = self
+            .tables
+            .get(table)
+            .ok_or_else(|| ExecutionError::Failed(format!("Unknown table: {}", table)))?;
+        Ok(rows.iter().skip(offset).take(limit).cloned().collect())
+    }
+}
+
 /// Execution context
 #[derive(Clone)]
 pub struct ExecutionContext {
@@ -109,6 +169,12 @@ pub struct ExecutionContext {
     pub operator_memory_limit: usize,
     /// Execution timeout (milliseconds)
     pub timeout_ms: Option<u64>,
+    /// Source of table rows; scans return no rows without one
+    pub table_source: Option<Arc<dyn TableSource>>,
+    /// Directory for spill files
+    pub spill_directory: PathBuf,
+    /// Worker slot when running under a Gather
+    pub(crate) worker: Option<WorkerSlot>,
 }
 
 impl Default for ExecutionContext {
@@ -117,10 +183,35 @@ impl Default for ExecutionContext {
             batch_size: 1024,
             operator_memory_limit: 64 * 1024 * 1024, // 64MB
             timeout_ms: None,
+            table_source: None,
+            spill_directory: std::env::temp_dir(),
+            worker: None,
         }
     }
 }
 
+impl ExecutionContext {
+    /// Read base tables from `source`
+    pub fn with_table_source(mut self, source: Arc<dyn TableSource>) -> Self {
+        self.table_source = Some(source);
+        self
+    }
+
+    fn with_worker(&self, worker: Option<WorkerSlot>) -> Self {
+        Self {
+            worker,
+            ..self.clone()
+        }
+    }
+}
+
+/// Worker `index` of `count` parallel workers
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WorkerSlot {
+    index: usize,
+    count: usize,
+}
+
 /// Physical operator executor trait (Volcano model)
 #[async_trait]
 pub trait PhysicalOperator: Send + Sync {
@@ -138,6 +229,15 @@ pub trait PhysicalOperator: Send + Sync {
 
     /// Get estimated cardinality
     fn cardinality(&self) -> &Cardinality;
+
+    /// Get output columns, qualified by table where known
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.schema()
+            .columns
+            .iter()
+            .map(|c| ColumnRef::new(c.name.clone()))
+            .collect()
+    }
 }
 
 /// Query executor
@@ -156,6 +256,7 @@ impl QueryExecutor {
 
     /// Execute a physical plan
     pub async fn execute(&self, plan: PhysicalPlan) -> ExecutionResult<ExecutionStats> {
+        let operators_executed = self.count_operators(&plan.root);
         let mut operator = self.create_operator(plan.root)?;
 
         let start_time = std::time::Instant::now();
@@ -180,13 +281,65 @@ impl QueryExecutor {
             total_rows,
             total_batches,
             execution_time_ms: execution_time.as_millis() as u64,
-            operators_executed: self.count_operators(&plan.root),
+            operators_executed,
         })
     }
 
+    /// Execute a physical plan and collect the result batches
+    pub async fn execute_collect(&self, plan: PhysicalPlan) -> ExecutionResult<Vec<RowBatch>> {
+        let mut operator = self.create_operator(plan.root)?;
+        operator.open().await?;
+
+        let mut batches = Vec::new();
+        while let Some(batch) = operator.next().await? {
+            batches.push(batch);
+        }
+
+        operator.close().await?;
+        Ok(batches)
+    }
+
     /// Create operator from physical node
     fn create_operator(&self, node: PhysicalNode) -> ExecutionResult<Box<dyn PhysicalOperator>> {
-        match node.op {
+        self.build(node, &self.context)
+    }
+
+    fn build(&self, node: PhysicalNode, context: &ExecutionContext) -> ExecutionResult<Box<dyn PhysicalOperator>> {
+        // Operators that need their whole input run once, in the first worker
+        if let Some(worker) = context.worker {
+            if !runs_on_slices(&node) {
+                let cardinality = node.cardinality;
+                let whole = self.build(node, &context.with_worker(None))?;
+                return Ok(Box::new(ExchangeOperator::single(whole, worker, cardinality)));
+            }
+        }
+
+        let split_aggregate = is_split_aggregate(&node);
+        let both_exchanged = node.children.len() == 2
+            && node.children.iter().all(|c| matches!(c.op, PhysicalOp::Exchange { .. }));
+        let PhysicalNode {
+            op,
+            children,
+            schema,
+            cardinality,
+            ..
+        } = node;
+        let mut children = children.into_iter();
+        let mut child = |context: &ExecutionContext| {
+            children
+                .next()
+                .map(|c| self.build(c, context))
+                .transpose()?
+                .ok_or_else(|| ExecutionError::InvalidState("Missing child operator".to_string()))
+        };
+        // Join build sides see all rows unless both inputs are repartitioned
+        let build_context = if both_exchanged {
+            context.clone()
+        } else {
+            context.with_worker(None)
+        };
+
+        match op {
             PhysicalOp::SeqScan {
                 table,
                 alias,
@@ -197,69 +350,333 @@ impl QueryExecutor {
                 alias,
                 predicates,
                 projection,
-                node.schema,
-                node.cardinality,
-                self.context.clone(),
+                schema,
+                cardinality,
+                context.clone(),
             ))),
 
-            PhysicalOp::Filter { predicates } => {
-                let child = if !node.children.is_empty() {
-                    Some(self.create_operator(node.children[0].clone())?)
-                } else {
-                    None
-                };
+            PhysicalOp::Filter { predicates } => Ok(Box::new(FilterOperator::new(
+                predicates,
+                child(context).ok(),
+                schema,
+                cardinality,
+            ))),
 
-                Ok(Box::new(FilterOperator::new(
-                    predicates,
-                    child,
-                    node.schema,
-                    node.cardinality,
-                )))
+            PhysicalOp::Project { projections } => Ok(Box::new(ProjectOperator::new(
+                projections,
+                child(context).ok(),
+                schema,
+                cardinality,
+            ))),
+
+            PhysicalOp::Limit { limit, offset } => Ok(Box::new(LimitOperator::new(
+                limit,
+                offset,
+                child(context).ok(),
+                schema,
+                cardinality,
+            ))),
+
+            PhysicalOp::HashJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                condition,
+            } => {
+                let left = child(context)?;
+                let right = child(&build_context)?;
+                Ok(Box::new(HashJoinOperator::new(
+                    join_type,
+                    left_keys,
+                    right_keys,
+                    condition,
+                    left,
+                    right,
+                    cardinality,
+                    context.clone(),
+                )?))
             }
 
-            PhysicalOp::Project { projections } => {
-                let child = if !node.children.is_empty() {
-                    Some(self.create_operator(node.children[0].clone())?)
-                } else {
-                    None
-                };
+            PhysicalOp::MergeJoin {
+                join_type,
+                left_keys,
+                right_keys,
+                condition,
+            } => {
+                let left = child(context)?;
+                let right = child(&build_context)?;
+                Ok(Box::new(MergeJoinOperator::new(
+                    join_type,
+                    left_keys,
+                    right_keys,
+                    condition,
+                    left,
+                    right,
+                    cardinality,
+                    context.clone(),
+                )?))
+            }
 
-                Ok(Box::new(ProjectOperator::new(
-                    projections,
-                    child,
-                    node.schema,
-                    node.cardinality,
-                )))
+            PhysicalOp::NestedLoopJoin { join_type, condition } => {
+                let left = child(context)?;
+                let right = child(&build_context)?;
+                Ok(Box::new(NestedLoopJoinOperator::new(
+                    join_type,
+                    condition,
+                    left,
+                    right,
+                    cardinality,
+                    context.clone(),
+                )?))
+            }
+
+            PhysicalOp::SpatialJoin {
+                join_type,
+                predicate,
+                left_geometry,
+                right_geometry,
+                distance,
+                condition,
+                strategy,
+            } => {
+                let left = child(context)?;
+                let right = child(&build_context)?;
+                Ok(Box::new(SpatialJoinOperator::new(
+                    join_type,
+                    predicate,
+                    (left_geometry, right_geometry),
+                    distance,
+                    condition,
+                    strategy,
+                    left,
+                    right,
+                    cardinality,
+                    context.clone(),
+                )?))
             }
 
-            PhysicalOp::Limit { limit, offset } => {
-                let child = if !node.children.is_empty() {
-                    Some(self.create_operator(node.children[0].clone())?)
+            PhysicalOp::HashAggregate {
+                group_by,
+                aggregates,
+                having,
+            } => {
+                let (input, mode) = if split_aggregate {
+                    let gather = children
+                        .next()
+                        .ok_or_else(|| ExecutionError::InvalidState("Missing child operator".to_string()))?;
+                    (self.build_gather(gather, context, true)?, AggregateMode::Final)
                 } else {
-                    None
+                    (child(context)?, AggregateMode::Complete)
                 };
+                Ok(Box::new(HashAggregateOperator::new(
+                    group_by,
+                    aggregates,
+                    having,
+                    mode,
+                    input,
+                    cardinality,
+                    context.clone(),
+                )?))
+            }
 
-                Ok(Box::new(LimitOperator::new(
-                    limit,
-                    offset,
-                    child,
-                    node.schema,
-                    node.cardinality,
-                )))
+            PhysicalOp::SortAggregate {
+                group_by,
+                aggregates,
+                having,
+            } => Ok(Box::new(SortAggregateOperator::new(
+                group_by,
+                aggregates,
+                having,
+                child(context)?,
+                cardinality,
+                context.clone(),
+            )?)),
+
+            PhysicalOp::HashDistinct => Ok(Box::new(HashAggregateOperator::distinct(
+                child(context)?,
+                cardinality,
+                context.clone(),
+            ))),
+
+            PhysicalOp::SortDistinct => Ok(Box::new(SortAggregateOperator::distinct(
+                child(context)?,
+                cardinality,
+                context.clone(),
+            ))),
+
+            PhysicalOp::UnionAll | PhysicalOp::HashUnion => {
+                let inputs = children
+                    .map(|c| self.build(c, context))
+                    .collect::<ExecutionResult<Vec<_>>>()?;
+                let union = Box::new(UnionAllOperator::new(inputs, cardinality)?);
+                if matches!(op, PhysicalOp::HashUnion) {
+                    Ok(Box::new(HashAggregateOperator::distinct(union, cardinality, context.clone())))
+                } else {
+                    Ok(union)
+                }
+            }
+
+            PhysicalOp::Sort { order_by } => Ok(Box::new(SortOperator::new(
+                order_by,
+                child(context)?,
+                cardinality,
+                context.clone(),
+            )?)),
+
+            PhysicalOp::TopNSort { order_by, limit } => Ok(Box::new(TopNSortOperator::new(
+                order_by,
+                limit,
+                child(context)?,
+                cardinality,
+                context.clone(),
+            )?)),
+
+            PhysicalOp::Gather { .. } => self.build_gather(
+                PhysicalNode {
+                    op,
+                    children: children.collect(),
+                    schema,
+                    ..PhysicalNode::new(PhysicalOp::Materialize, vec![], Schema::empty(), Cost::zero(), cardinality)
+                },
+                context,
+                false,
+            ),
+
+            PhysicalOp::Exchange { distribution, .. } => {
+                // Rows are partitioned here, so the input is read in full
+                let input = child(&context.with_worker(None))?;
+                Ok(Box::new(ExchangeOperator::new(distribution, input, context, cardinality)?))
             }
 
+            // Results are already materialized by blocking operators below
+            PhysicalOp::Materialize => child(context),
+
             _ => Err(ExecutionError::NotImplemented(format!(
                 "Operator not implemented: {:?}",
-                node.op
+                op
             ))),
         }
     }
 
+    /// Create a Gather, running its input once per worker
+    ///
+    /// With `partial`, the input is a partial aggregate to be finished above
+    /// the Gather.
+    fn build_gather(
+        &self,
+        node: PhysicalNode,
+        context: &ExecutionContext,
+        partial: bool,
+    ) -> ExecutionResult<Box<dyn PhysicalOperator>> {
+        let num_workers = match node.op {
+            PhysicalOp::Gather { num_workers } => num_workers.max(1),
+            _ => return Err(ExecutionError::InvalidState("Expected Gather".to_string())),
+        };
+        let build_input = |input: PhysicalNode, context: &ExecutionContext| {
+            if partial {
+                self.build_partial_aggregate(input, context)
+            } else {
+                self.build(input, context)
+            }
+        };
+
+        let mut inputs = Vec::new();
+        match (node.children.len(), context.worker) {
+            // A single input is split across workers unless already inside one
+            (1, None) => {
+                for index in 0..num_workers {
+                    let worker = WorkerSlot {
+                        index,
+                        count: num_workers,
+                    };
+                    inputs.push(build_input(node.children[0].clone(), &context.with_worker(Some(worker)))?);
+                }
+            }
+            _ => {
+                for input in node.children {
+                    inputs.push(build_input(input, context)?);
+                }
+            }
+        }
+        Ok(Box::new(GatherOperator::new(inputs, node.cardinality)?))
+    }
+
+    fn build_partial_aggregate(
+        &self,
+        node: PhysicalNode,
+        context: &ExecutionContext,
+    ) -> ExecutionResult<Box<dyn PhysicalOperator>> {
+        match node.op {
+            PhysicalOp::HashAggregate {
+                group_by, aggregates, ..
+            } => {
+                let input = node
+                    .children
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| ExecutionError::InvalidState("Missing child operator".to_string()))?;
+                Ok(Box::new(HashAggregateOperator::new(
+                    group_by,
+                    aggregates,
+                    None,
+                    AggregateMode::Partial,
+                    self.build(input, context)?,
+                    node.cardinality,
+                    context.clone(),
+                )?))
+            }
+            _ => Err(ExecutionError::InvalidState("Expected HashAggregate".to_string())),
+        }
+    }
+
     fn count_operators(&self, node: &PhysicalNode) -> usize {
         1 + node.children.iter().map(|c| self.count_operators(c)).sum::<usize>()
     }
 }
 
+/// Whether a node is the final half of an aggregate split by the parallel
+/// planner, i.e. `HashAggregate -> Gather -> HashAggregate` on the same keys
+fn is_split_aggregate(node: &PhysicalNode) -> bool {
+    let PhysicalOp::HashAggregate { group_by, .. } = &node.op else {
+        return false;
+    };
+    let Some(gather) = node.children.first() else {
+        return false;
+    };
+    if !matches!(gather.op, PhysicalOp::Gather { .. }) || gather.children.len() != 1 {
+        return false;
+    }
+    match &gather.children[0].op {
+        PhysicalOp::HashAggregate { group_by: partial, .. } => {
+            partial.iter().map(expr_name).eq(group_by.iter().map(expr_name))
+        }
+        _ => false,
+    }
+}
+
+/// Whether every worker of a Gather can run the node on its slice of the
+/// input and together produce exactly the node's output
+fn runs_on_slices(node: &PhysicalNode) -> bool {
+    match &node.op {
+        PhysicalOp::SeqScan { .. }
+        | PhysicalOp::Filter { .. }
+        | PhysicalOp::Project { .. }
+        | PhysicalOp::Sort { .. }
+        | PhysicalOp::UnionAll
+        | PhysicalOp::Materialize
+        | PhysicalOp::Gather { .. }
+        | PhysicalOp::Exchange { .. } => true,
+        PhysicalOp::HashJoin { join_type, .. }
+        | PhysicalOp::MergeJoin { join_type, .. }
+        | PhysicalOp::NestedLoopJoin { join_type, .. }
+        | PhysicalOp::SpatialJoin { join_type, .. } => {
+            !matches!(join_type, JoinType::Right | JoinType::Full)
+                || node.children.iter().all(|c| matches!(c.op, PhysicalOp::Exchange { .. }))
+        }
+        _ => false,
+    }
+}
+
 /// Execution statistics
 #[derive(Debug, Clone)]
 pub struct ExecutionStats {
@@ -269,6 +686,94 @@ pub struct ExecutionStats {
     pub operators_executed: usize,
 }
 
+/// Take up to one batch of buffered output rows
+fn take_batch(output: &mut VecDeque<Row>, batch_size: usize, schema: &Schema) -> Option<RowBatch> {
+    if output.is_empty() {
+        return None;
+    }
+    let count = output.len().min(batch_size.max(1));
+    Some(RowBatch {
+        rows: output.drain(..count).collect(),
+        schema: schema.clone(),
+    })
+}
+
+/// Take up to `count` rows from a stream, `None` once it is exhausted
+fn take_rows(stream: &mut RowStream, count: usize) -> ExecutionResult<Option<Vec<Row>>> {
+    let rows = stream.by_ref().take(count.max(1)).collect::<ExecutionResult<Vec<_>>>()?;
+    Ok(if rows.is_empty() { None } else { Some(rows) })
+}
+
+fn empty_stream() -> RowStream {
+    Box::new(std::iter::empty())
+}
+
+fn is_integral(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::TinyInt | DataType::SmallInt | DataType::Integer | DataType::BigInt
+    )
+}
+
+/// Infer the type of an expression over the given input
+fn expr_type(expr: &ScalarExpr, columns: &[ColumnRef], schema: &Schema) -> DataType {
+    let column_type = |column: &ColumnRef| {
+        resolve_column(columns, column).and_then(|i| schema.columns.get(i).map(|c| c.data_type.clone()))
+    };
+    if !matches!(expr, ScalarExpr::Column(_)) {
+        if let Some(data_type) = column_type(&ColumnRef::new(expr_name(expr))) {
+            return data_type;
+        }
+    }
+    match expr {
+        ScalarExpr::Column(column) => column_type(column).unwrap_or(DataType::Text),
+        ScalarExpr::Literal(literal) => match literal {
+            Literal::Boolean(_) => DataType::Boolean,
+            Literal::Integer(_) => DataType::BigInt,
+            Literal::Float(_) => DataType::Double,
+            Literal::Date(_) => DataType::Date,
+            Literal::Timestamp(_) => DataType::Timestamp,
+            Literal::Interval(_) => DataType::Interval,
+            Literal::Null | Literal::String(_) => DataType::Text,
+        },
+        ScalarExpr::BinaryOp { left, op, right } => {
+            use crate::ast::BinaryOp::*;
+            match op {
+                Add | Subtract | Multiply | Divide | Modulo => {
+                    let (left, right) = (expr_type(left, columns, schema), expr_type(right, columns, schema));
+                    if is_integral(&left) && is_integral(&right) {
+                        DataType::BigInt
+                    } else if left == DataType::Text && right == DataType::Text {
+                        DataType::Text
+                    } else {
+                        DataType::Double
+                    }
+                }
+                _ => DataType::Boolean,
+            }
+        }
+        ScalarExpr::UnaryOp { op, expr } => match op {
+            crate::ast::UnaryOp::Negate => expr_type(expr, columns, schema),
+            _ => DataType::Boolean,
+        },
+        ScalarExpr::Function { name, args } => match name.to_lowercase().as_str() {
+            "length" | "char_length" => DataType::Integer,
+            "lower" | "upper" | "concat" | "substr" | "substring" => DataType::Text,
+            "st_intersects" | "st_contains" | "st_within" | "st_dwithin" => DataType::Boolean,
+            "st_makeenvelope" | "st_makepoint" | "st_point" => DataType::Geometry,
+            "st_geomfromtext" | "st_geomfromewkt" | "st_setsrid" => DataType::Geometry,
+            _ => args
+                .first()
+                .map_or(DataType::Text, |arg| expr_type(arg, columns, schema)),
+        },
+        ScalarExpr::Case { when_clauses, .. } => when_clauses
+            .first()
+            .map_or(DataType::Text, |(_, then)| expr_type(then, columns, schema)),
+        ScalarExpr::In { .. } | ScalarExpr::Between { .. } => DataType::Boolean,
+        ScalarExpr::Subquery(_) => DataType::Text,
+    }
+}
+
 /// Sequential scan operator
 pub struct SeqScanOperator {
     table: String,
@@ -280,6 +785,9 @@ pub struct SeqScanOperator {
     context: ExecutionContext,
     position: usize,
     is_open: bool,
+    /// Positions of the projected columns in the table
+    column_indices: Vec<usize>,
+    bound_predicates: Vec<BoundExpr>,
 }
 
 impl SeqScanOperator {
@@ -292,6 +800,26 @@ impl SeqScanOperator {
         cardinality: Cardinality,
         context: ExecutionContext,
     ) -> Self {
+        // The source schema wins over the planner's when a source is attached
+        let table_schema = context
+            .table_source
+            .as_ref()
+            .and_then(|source| source.table_schema(&table))
+            .unwrap_or(schema);
+        let column_indices: Vec<usize> = match &projection {
+            Some(names) => names
+                .iter()
+                .filter_map(|name| table_schema.columns.iter().position(|c| &c.name == name))
+                .collect(),
+            None => (0..table_schema.columns.len()).collect(),
+        };
+        let schema = Schema::new(
+            column_indices
+                .iter()
+                .map(|&i| table_schema.columns[i].clone())
+                .collect(),
+        );
+
         Self {
             table,
             alias,
@@ -302,15 +830,40 @@ impl SeqScanOperator {
             context,
             position: 0,
             is_open: false,
+            column_indices,
+            bound_predicates: Vec::new(),
         }
     }
+
+    fn qualifier(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.table)
+    }
 }
 
 #[async_trait]
 impl PhysicalOperator for SeqScanOperator {
     async fn open(&mut self) -> ExecutionResult<()> {
+        if let Some(names) = &self.projection {
+            if names.len() != self.column_indices.len() && self.context.table_source.is_some() {
+                return Err(ExecutionError::Failed(format!(
+                    "Unknown column in projection of {}",
+                    self.table
+                )));
+            }
+        }
+        let columns = self.columns();
+        self.bound_predicates = self
+            .predicates
+            .iter()
+            .map(|p| BoundExpr::bind(p, &columns))
+            .collect::<ExecutionResult<_>>()?;
+
         self.is_open = true;
-        self.position = 0;
+        // Workers of a Gather read interleaved blocks of `batch_size` rows
+        self.position = self
+            .context
+            .worker
+            .map_or(0, |w| w.index * self.context.batch_size);
         Ok(())
     }
 
@@ -321,9 +874,35 @@ impl PhysicalOperator for SeqScanOperator {
             ));
         }
 
-        // Simplified: would read from actual storage
-        // For now, return empty batch to signal EOF
-        Ok(None)
+        let Some(source) = self.context.table_source.clone() else {
+            return Ok(None);
+        };
+        let block = self.context.batch_size.max(1);
+        let stride = block * self.context.worker.map_or(1, |w| w.count);
+
+        loop {
+            let rows = source.read_rows(&self.table, self.position, block)?;
+            if rows.is_empty() {
+                return Ok(None);
+            }
+            self.position += stride;
+
+            let mut batch = RowBatch::with_capacity(self.schema.clone(), rows.len());
+            for row in rows {
+                let row = Row::new(
+                    self.column_indices
+                        .iter()
+                        .map(|&i| row.values.get(i).cloned().unwrap_or(Value::Null))
+                        .collect(),
+                );
+                if matches_all(&self.bound_predicates, &row)? {
+                    batch.add_row(row);
+                }
+            }
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
     }
 
     async fn close(&mut self) -> ExecutionResult<()> {
@@ -338,6 +917,15 @@ impl PhysicalOperator for SeqScanOperator {
     fn cardinality(&self) -> &Cardinality {
         &self.cardinality
     }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        let qualifier = self.qualifier();
+        self.schema
+            .columns
+            .iter()
+            .map(|c| ColumnRef::with_table(qualifier, c.name.clone()))
+            .collect()
+    }
 }
 
 /// Filter operator
@@ -346,6 +934,7 @@ pub struct FilterOperator {
     child: Option<Box<dyn PhysicalOperator>>,
     schema: Schema,
     cardinality: Cardinality,
+    bound_predicates: Vec<BoundExpr>,
 }
 
 impl FilterOperator {
@@ -355,23 +944,27 @@ impl FilterOperator {
         schema: Schema,
         cardinality: Cardinality,
     ) -> Self {
+        let schema = child.as_ref().map_or(schema, |c| c.schema().clone());
         Self {
             predicates,
             child,
             schema,
             cardinality,
+            bound_predicates: Vec::new(),
         }
     }
-
-    fn evaluate_predicates(&self, _row: &Row) -> bool {
-        // Simplified: would evaluate actual predicates
-        true
-    }
 }
 
 #[async_trait]
 impl PhysicalOperator for FilterOperator {
     async fn open(&mut self) -> ExecutionResult<()> {
+        let columns = self.columns();
+        self.bound_predicates = self
+            .predicates
+            .iter()
+            .map(|p| BoundExpr::bind(p, &columns))
+            .collect::<ExecutionResult<_>>()?;
+
         if let Some(ref mut child) = self.child {
             child.open().await?;
         }
@@ -380,12 +973,20 @@ impl PhysicalOperator for FilterOperator {
 
     async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
         if let Some(ref mut child) = self.child {
-            while let Some(mut batch) = child.next().await? {
+            while let Some(batch) = child.next().await? {
                 // Filter rows
-                batch.rows.retain(|row| self.evaluate_predicates(row));
+                let mut rows = Vec::with_capacity(batch.rows.len());
+                for row in batch.rows {
+                    if matches_all(&self.bound_predicates, &row)? {
+                        rows.push(row);
+                    }
+                }
 
-                if !batch.is_empty() {
-                    return Ok(Some(batch));
+                if !rows.is_empty() {
+                    return Ok(Some(RowBatch {
+                        rows,
+                        schema: batch.schema,
+                    }));
                 }
             }
         }
@@ -406,6 +1007,13 @@ impl PhysicalOperator for FilterOperator {
     fn cardinality(&self) -> &Cardinality {
         &self.cardinality
     }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        match &self.child {
+            Some(child) => child.columns(),
+            None => self.schema.columns.iter().map(|c| ColumnRef::new(c.name.clone())).collect(),
+        }
+    }
 }
 
 /// Projection operator
@@ -414,6 +1022,9 @@ pub struct ProjectOperator {
     child: Option<Box<dyn PhysicalOperator>>,
     schema: Schema,
     cardinality: Cardinality,
+    bound_projections: Vec<BoundExpr>,
+    /// Output columns; plain column references keep their table
+    output_columns: Vec<ColumnRef>,
 }
 
 impl ProjectOperator {
@@ -423,17 +1034,50 @@ impl ProjectOperator {
         schema: Schema,
         cardinality: Cardinality,
     ) -> Self {
+        let output_columns: Vec<ColumnRef> = projections
+            .iter()
+            .map(|item| match (&item.alias, &item.expr) {
+                (Some(alias), _) => ColumnRef::new(alias.clone()),
+                (None, ScalarExpr::Column(column)) => column.clone(),
+                (None, expr) => ColumnRef::new(expr_name(expr)),
+            })
+            .collect();
+        let schema = match &child {
+            Some(child) => {
+                let input_columns = child.columns();
+                Schema::new(
+                    projections
+                        .iter()
+                        .zip(&output_columns)
+                        .map(|(item, column)| {
+                            ColumnDef::new(
+                                column.name.clone(),
+                                expr_type(&item.expr, &input_columns, child.schema()),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            None => schema,
+        };
+
         Self {
             projections,
             child,
             schema,
             cardinality,
+            bound_projections: Vec::new(),
+            output_columns,
         }
     }
 
-    fn project_row(&self, row: &Row) -> Row {
-        // Simplified: would evaluate projection expressions
-        row.clone()
+    fn project_row(&self, row: &Row) -> ExecutionResult<Row> {
+        Ok(Row::new(
+            self.bound_projections
+                .iter()
+                .map(|expr| expr.eval(row))
+                .collect::<ExecutionResult<_>>()?,
+        ))
     }
 }
 
@@ -441,6 +1085,12 @@ impl ProjectOperator {
 impl PhysicalOperator for ProjectOperator {
     async fn open(&mut self) -> ExecutionResult<()> {
         if let Some(ref mut child) = self.child {
+            let columns = child.columns();
+            self.bound_projections = self
+                .projections
+                .iter()
+                .map(|item| BoundExpr::bind(&item.expr, &columns))
+                .collect::<ExecutionResult<_>>()?;
             child.open().await?;
         }
         Ok(())
@@ -448,12 +1098,13 @@ impl PhysicalOperator for ProjectOperator {
 
     async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
         if let Some(ref mut child) = self.child {
-            if let Some(mut batch) = child.next().await? {
+            if let Some(batch) = child.next().await? {
                 // Project each row
-                for row in &mut batch.rows {
-                    *row = self.project_row(row);
+                let mut projected = RowBatch::with_capacity(self.schema.clone(), batch.len());
+                for row in &batch.rows {
+                    projected.add_row(self.project_row(row)?);
                 }
-                return Ok(Some(batch));
+                return Ok(Some(projected));
             }
         }
         Ok(None)
@@ -473,6 +1124,10 @@ impl PhysicalOperator for ProjectOperator {
     fn cardinality(&self) -> &Cardinality {
         &self.cardinality
     }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
 }
 
 /// Limit operator
@@ -494,6 +1149,7 @@ impl LimitOperator {
         schema: Schema,
         cardinality: Cardinality,
     ) -> Self {
+        let schema = child.as_ref().map_or(schema, |c| c.schema().clone());
         Self {
             limit,
             offset,
@@ -570,39 +1226,3058 @@ impl PhysicalOperator for LimitOperator {
     fn cardinality(&self) -> &Cardinality {
         &self.cardinality
     }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        match &self.child {
+            Some(child) => child.columns(),
+            None => self.schema.columns.iter().map(|c| ColumnRef::new(c.name.clone())).collect(),
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ast::{ColumnDef, DataType};
+/// Join condition and row assembly shared by the join operators
+struct JoinSpec {
+    join_type: JoinType,
+    /// Non-equi part of the condition, bound to left ++ right columns
+    residual: Option<BoundExpr>,
+    left_width: usize,
+    right_width: usize,
+}
 
-    #[tokio::test]
-    async fn test_executor_creation() {
-        let executor = QueryExecutor::with_default_context();
-        assert_eq!(executor.context.batch_size, 1024);
+impl JoinSpec {
+    fn combine(&self, left: &Row, right: &Row) -> Row {
+        let mut values = Vec::with_capacity(self.left_width + self.right_width);
+        values.extend_from_slice(&left.values);
+        values.extend_from_slice(&right.values);
+        Row::new(values)
     }
 
-    #[tokio::test]
-    async fn test_row_batch() {
-        let schema = Schema::new(vec![
-            ColumnDef::new("id", DataType::Integer),
-            ColumnDef::new("name", DataType::Varchar(Some(255))),
-        ]);
+    /// Combined row if the pair satisfies the residual condition
+    fn pair(&self, left: &Row, right: &Row) -> ExecutionResult<Option<Row>> {
+        let row = self.combine(left, right);
+        match &self.residual {
+            Some(residual) if !residual.matches(&row)? => Ok(None),
+            _ => Ok(Some(row)),
+        }
+    }
 
-        let mut batch = RowBatch::new(schema);
-        assert!(batch.is_empty());
+    /// Whether matching pairs are output, rather than left rows alone
+    fn emits_pairs(&self) -> bool {
+        !matches!(self.join_type, JoinType::Semi | JoinType::AntiSemi)
+    }
 
-        batch.add_row(Row::new(vec![Value::Integer(1), Value::String("Alice".to_string())]));
-        assert_eq!(batch.len(), 1);
+    /// Whether unmatched right rows are output
+    fn tracks_right(&self) -> bool {
+        matches!(self.join_type, JoinType::Right | JoinType::Full)
     }
 
-    #[tokio::test]
-    async fn test_value_types() {
-        assert!(Value::Null.is_null());
-        assert!(!Value::Integer(42).is_null());
+    /// Output for a left row once all its matches are known
+    fn finish_left(&self, left: Row, matched: bool, output: &mut VecDeque<Row>) {
+        match (self.join_type, matched) {
+            (JoinType::Left | JoinType::Full, false) => {
+                let mut values = left.values;
+                values.resize(self.left_width + self.right_width, Value::Null);
+                output.push_back(Row::new(values));
+            }
+            (JoinType::Semi, true) | (JoinType::AntiSemi, false) => output.push_back(left),
+            _ => {}
+        }
+    }
 
-        let val = Value::String("test".to_string());
-        assert_eq!(val, Value::String("test".to_string()));
+    /// Output for a right row that matched no left row
+    fn finish_right(&self, right: Row, output: &mut VecDeque<Row>) {
+        if self.tracks_right() {
+            let mut values = vec![Value::Null; self.left_width];
+            values.extend(right.values);
+            output.push_back(Row::new(values));
+        }
+    }
+}
+
+/// Output schema and columns of a join
+fn join_output(
+    join_type: JoinType,
+    left: &dyn PhysicalOperator,
+    right: &dyn PhysicalOperator,
+) -> (Schema, Vec<ColumnRef>) {
+    let nullable = |def: &ColumnDef, outer: bool| ColumnDef {
+        nullable: def.nullable || outer,
+        ..def.clone()
+    };
+    let left_outer = matches!(join_type, JoinType::Right | JoinType::Full);
+    let right_outer = matches!(join_type, JoinType::Left | JoinType::Full);
+    let mut defs: Vec<ColumnDef> = left.schema().columns.iter().map(|c| nullable(c, left_outer)).collect();
+    let mut columns = left.columns();
+    if !matches!(join_type, JoinType::Semi | JoinType::AntiSemi) {
+        defs.extend(right.schema().columns.iter().map(|c| nullable(c, right_outer)));
+        columns.extend(right.columns());
+    }
+    (Schema::new(defs), columns)
+}
+
+fn conjuncts(expr: ScalarExpr, out: &mut Vec<ScalarExpr>) {
+    match expr {
+        ScalarExpr::BinaryOp {
+            left,
+            op: crate::ast::BinaryOp::And,
+            right,
+        } => {
+            conjuncts(*left, out);
+            conjuncts(*right, out);
+        }
+        other => out.push(other),
+    }
+}
+
+/// Bound join keys and residual condition
+///
+/// Without explicit keys, equality conjuncts of the condition that compare
+/// a left expression with a right one become the keys.
+fn bind_join(
+    left_keys: Vec<ScalarExpr>,
+    right_keys: Vec<ScalarExpr>,
+    condition: Option<ScalarExpr>,
+    left_columns: &[ColumnRef],
+    right_columns: &[ColumnRef],
+) -> ExecutionResult<(Vec<BoundExpr>, Vec<BoundExpr>, Option<BoundExpr>)> {
+    let (left_keys, right_keys, residual) = if left_keys.is_empty() && right_keys.is_empty() {
+        let mut parts = Vec::new();
+        if let Some(condition) = condition {
+            conjuncts(condition, &mut parts);
+        }
+        let binds = |expr: &ScalarExpr, columns: &[ColumnRef]| BoundExpr::bind(expr, columns).is_ok();
+        let (mut lefts, mut rights, mut rest) = (Vec::new(), Vec::new(), Vec::new());
+        for part in parts {
+            match part {
+                ScalarExpr::BinaryOp {
+                    left,
+                    op: crate::ast::BinaryOp::Eq,
+                    right,
+                } if binds(&left, left_columns) && binds(&right, right_columns) => {
+                    lefts.push(*left);
+                    rights.push(*right);
+                }
+                ScalarExpr::BinaryOp {
+                    left,
+                    op: crate::ast::BinaryOp::Eq,
+                    right,
+                } if binds(&right, left_columns) && binds(&left, right_columns) => {
+                    lefts.push(*right);
+                    rights.push(*left);
+                }
+                other => rest.push(other),
+            }
+        }
+        let residual = rest.into_iter().reduce(|a, b| ScalarExpr::BinaryOp {
+            left: Box::new(a),
+            op: crate::ast::BinaryOp::And,
+            right: Box::new(b),
+        });
+        (lefts, rights, residual)
+    } else {
+        (left_keys, right_keys, condition)
+    };
+
+    if left_keys.len() != right_keys.len() {
+        return Err(ExecutionError::Failed(
+            "Join key lists differ in length".to_string(),
+        ));
+    }
+    let combined: Vec<ColumnRef> = left_columns.iter().chain(right_columns).cloned().collect();
+    Ok((
+        left_keys
+            .iter()
+            .map(|k| BoundExpr::bind(k, left_columns))
+            .collect::<ExecutionResult<_>>()?,
+        right_keys
+            .iter()
+            .map(|k| BoundExpr::bind(k, right_columns))
+            .collect::<ExecutionResult<_>>()?,
+        residual.map(|r| BoundExpr::bind(&r, &combined)).transpose()?,
+    ))
+}
+
+/// Evaluate key expressions; `None` if any key is NULL and cannot match
+fn key_values(keys: &[BoundExpr], row: &Row) -> ExecutionResult<Option<Vec<Value>>> {
+    let values = keys.iter().map(|k| k.eval(row)).collect::<ExecutionResult<Vec<_>>>()?;
+    Ok(if values.iter().any(Value::is_null) {
+        None
+    } else {
+        Some(values)
+    })
+}
+
+fn join_key(keys: &[BoundExpr], row: &Row) -> ExecutionResult<Option<Vec<u8>>> {
+    Ok(key_values(keys, row)?.map(|values| encode_key(&values)))
+}
+
+/// In-memory build side of a hash join
+#[derive(Default)]
+struct HashTable {
+    rows: Vec<Row>,
+    index: HashMap<Vec<u8>, Vec<usize>>,
+    matched: Vec<bool>,
+}
+
+impl HashTable {
+    /// Add a row; rows with a NULL key are kept only to be output unmatched
+    fn insert(&mut self, key: Option<Vec<u8>>, row: Row) {
+        if let Some(key) = key {
+            self.index.entry(key).or_default().push(self.rows.len());
+        }
+        self.rows.push(row);
+        self.matched.push(false);
+    }
+
+    fn probe(
+        &mut self,
+        spec: &JoinSpec,
+        key: Option<&[u8]>,
+        left: Row,
+        output: &mut VecDeque<Row>,
+    ) -> ExecutionResult<()> {
+        let mut matched = false;
+        if let Some(candidates) = key.and_then(|key| self.index.get(key)) {
+            for &i in candidates {
+                if let Some(row) = spec.pair(&left, &self.rows[i])? {
+                    matched = true;
+                    self.matched[i] = true;
+                    if spec.emits_pairs() {
+                        output.push_back(row);
+                    } else if !spec.tracks_right() {
+                        break;
+                    }
+                }
+            }
+        }
+        spec.finish_left(left, matched, output);
+        Ok(())
+    }
+
+    fn finish(self, spec: &JoinSpec, output: &mut VecDeque<Row>) {
+        if spec.tracks_right() {
+            for (row, matched) in self.rows.into_iter().zip(self.matched) {
+                if !matched {
+                    spec.finish_right(row, output);
+                }
+            }
+        }
+    }
+}
+
+/// Spilled partition of a hash join that is too skewed to split further
+///
+/// Joins by streaming the build rows from disk once per probe batch, so
+/// memory stays bounded however many rows share a key.
+struct SpilledNestedLoop {
+    right: SpillFile,
+    right_matched: Vec<bool>,
+    left: RowStream,
+}
+
+impl SpilledNestedLoop {
+    fn join_batch(
+        &mut self,
+        spec: &JoinSpec,
+        left_keys: &[BoundExpr],
+        right_keys: &[BoundExpr],
+        lefts: Vec<Row>,
+        output: &mut VecDeque<Row>,
+    ) -> ExecutionResult<()> {
+        let keys = lefts.iter().map(|row| join_key(left_keys, row)).collect::<ExecutionResult<Vec<_>>>()?;
+        let mut left_matched = vec![false; lefts.len()];
+
+        if keys.iter().any(Option::is_some) {
+            for (j, right) in self.right.reader()?.enumerate() {
+                let right = right?;
+                let Some(right_key) = join_key(right_keys, &right)? else {
+                    continue;
+                };
+                for (i, left) in lefts.iter().enumerate() {
+                    // Semi and anti joins only need the first match of a row
+                    if keys[i].as_deref() != Some(&right_key[..])
+                        || (left_matched[i] && !spec.emits_pairs() && !spec.tracks_right())
+                    {
+                        continue;
+                    }
+                    if let Some(row) = spec.pair(left, &right)? {
+                        left_matched[i] = true;
+                        self.right_matched[j] = true;
+                        if spec.emits_pairs() {
+                            output.push_back(row);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (left, matched) in lefts.into_iter().zip(left_matched) {
+            spec.finish_left(left, matched, output);
+        }
+        Ok(())
+    }
+
+    fn finish(self, spec: &JoinSpec, output: &mut VecDeque<Row>) -> ExecutionResult<()> {
+        if spec.tracks_right() {
+            for (row, matched) in self.right.reader()?.zip(self.right_matched) {
+                if !matched {
+                    spec.finish_right(row?, output);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hash join operator
+///
+/// Builds a hash table on the right input and streams the left input
+/// through it. When the build side exceeds the memory limit, both inputs
+/// are hash partitioned to disk and joined one partition pair at a time.
+/// Partitions that still do not fit are repartitioned with a new hash seed
+/// up to [`MAX_SPILL_DEPTH`] times, after which they fall back to a nested
+/// loop over the spilled build rows.
+pub struct HashJoinOperator {
+    left: Box<dyn PhysicalOperator>,
+    right: Box<dyn PhysicalOperator>,
+    left_keys: Vec<BoundExpr>,
+    right_keys: Vec<BoundExpr>,
+    spec: JoinSpec,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    built: bool,
+    table: Option<HashTable>,
+    /// Spilled probe rows of the current partition; the left child otherwise
+    probe: Option<RowStream>,
+    /// Spilled (build, probe) partition pairs and their repartitioning depth
+    partitions: VecDeque<(Option<SpillFile>, Option<SpillFile>, usize)>,
+    fallback: Option<SpilledNestedLoop>,
+    output: VecDeque<Row>,
+}
+
+impl HashJoinOperator {
+    /// Create a hash join building on the right input
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        join_type: JoinType,
+        left_keys: Vec<ScalarExpr>,
+        right_keys: Vec<ScalarExpr>,
+        condition: Option<ScalarExpr>,
+        left: Box<dyn PhysicalOperator>,
+        right: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let (left_keys, right_keys, residual) =
+            bind_join(left_keys, right_keys, condition, &left.columns(), &right.columns())?;
+        let (schema, output_columns) = join_output(join_type, left.as_ref(), right.as_ref());
+        let spec = JoinSpec {
+            join_type,
+            residual,
+            left_width: left.schema().columns.len(),
+            right_width: right.schema().columns.len(),
+        };
+
+        Ok(Self {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            spec,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            built: false,
+            table: None,
+            probe: None,
+            partitions: VecDeque::new(),
+            fallback: None,
+            output: VecDeque::new(),
+        })
+    }
+
+    /// Build the hash table, partitioning both inputs if it does not fit
+    async fn build(&mut self) -> ExecutionResult<()> {
+        let mut table = HashTable::default();
+        let mut bytes = 0;
+        let mut spilled: Option<Partitions> = None;
+
+        while let Some(batch) = self.right.next().await? {
+            for row in batch.rows {
+                let key = join_key(&self.right_keys, &row)?;
+                if let Some(partitions) = &mut spilled {
+                    partitions.write(&key.unwrap_or_default(), &row)?;
+                    continue;
+                }
+                bytes += row_size(&row);
+                table.insert(key, row);
+
+                if bytes > self.context.operator_memory_limit {
+                    let mut partitions = Partitions::new(&self.context.spill_directory, 0);
+                    for row in std::mem::take(&mut table).rows {
+                        let key = join_key(&self.right_keys, &row)?;
+                        partitions.write(&key.unwrap_or_default(), &row)?;
+                    }
+                    spilled = Some(partitions);
+                }
+            }
+        }
+
+        let Some(right_partitions) = spilled else {
+            self.table = Some(table);
+            return Ok(());
+        };
+
+        tracing::debug!("Hash join build side exceeds memory limit, partitioning to disk");
+        let mut left_partitions = Partitions::new(&self.context.spill_directory, 0);
+        while let Some(batch) = self.left.next().await? {
+            for row in batch.rows {
+                let key = join_key(&self.left_keys, &row)?;
+                left_partitions.write(&key.unwrap_or_default(), &row)?;
+            }
+        }
+        self.queue_partitions(right_partitions, left_partitions, 0)
+    }
+
+    fn queue_partitions(&mut self, right: Partitions, left: Partitions, depth: usize) -> ExecutionResult<()> {
+        for (right, left) in right.finish()?.into_iter().zip(left.finish()?) {
+            if right.is_some() || left.is_some() {
+                self.partitions.push_back((right, left, depth));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a spilled partition pair, splitting it again if the build side
+    /// does not fit in memory
+    fn load_partition(&mut self, right: Option<SpillFile>, left: Option<SpillFile>, depth: usize) -> ExecutionResult<()> {
+        let Some(right) = right else {
+            // Nothing to build, so every probe row is unmatched
+            self.table = Some(HashTable::default());
+            self.probe = Some(left.map(SpillFile::into_stream).transpose()?.unwrap_or_else(empty_stream));
+            return Ok(());
+        };
+
+        let mut table = HashTable::default();
+        let mut bytes = 0;
+        let mut fits = true;
+        for row in right.reader()? {
+            let row = row?;
+            bytes += row_size(&row);
+            table.insert(join_key(&self.right_keys, &row)?, row);
+            if bytes > self.context.operator_memory_limit {
+                fits = false;
+                break;
+            }
+        }
+        let left = left.map(SpillFile::into_stream).transpose()?.unwrap_or_else(empty_stream);
+        if fits {
+            self.table = Some(table);
+            self.probe = Some(left);
+            return Ok(());
+        }
+        drop(table);
+
+        if depth < MAX_SPILL_DEPTH {
+            tracing::debug!("Hash join partition exceeds memory limit, repartitioning at depth {}", depth + 1);
+            let mut right_partitions = Partitions::new(&self.context.spill_directory, depth + 1);
+            for row in right.into_stream()? {
+                let row = row?;
+                right_partitions.write(&join_key(&self.right_keys, &row)?.unwrap_or_default(), &row)?;
+            }
+            let mut left_partitions = Partitions::new(&self.context.spill_directory, depth + 1);
+            for row in left {
+                let row = row?;
+                left_partitions.write(&join_key(&self.left_keys, &row)?.unwrap_or_default(), &row)?;
+            }
+            return self.queue_partitions(right_partitions, left_partitions, depth + 1);
+        }
+
+        tracing::debug!("Hash join partition still exceeds memory limit at depth {}, using a nested loop", depth);
+        self.fallback = Some(SpilledNestedLoop {
+            right_matched: vec![false; right.rows()],
+            right,
+            left,
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for HashJoinOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.built = false;
+        self.table = None;
+        self.probe = None;
+        self.partitions.clear();
+        self.fallback = None;
+        self.output.clear();
+        self.left.open().await?;
+        self.right.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if !self.built {
+            self.build().await?;
+            self.built = true;
+        }
+
+        loop {
+            if let Some(batch) = take_batch(&mut self.output, self.context.batch_size, &self.schema) {
+                return Ok(Some(batch));
+            }
+
+            if let Some(fallback) = &mut self.fallback {
+                match take_rows(&mut fallback.left, self.context.batch_size)? {
+                    Some(rows) => {
+                        fallback.join_batch(&self.spec, &self.left_keys, &self.right_keys, rows, &mut self.output)?
+                    }
+                    None => {
+                        if let Some(fallback) = self.fallback.take() {
+                            fallback.finish(&self.spec, &mut self.output)?;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let Some(table) = self.table.as_mut() else {
+                // Load the next spilled partition pair
+                let Some((right, left, depth)) = self.partitions.pop_front() else {
+                    return Ok(None);
+                };
+                self.load_partition(right, left, depth)?;
+                continue;
+            };
+
+            let rows = match &mut self.probe {
+                Some(stream) => take_rows(stream, self.context.batch_size)?,
+                None => self.left.next().await?.map(|batch| batch.rows),
+            };
+            match rows {
+                Some(rows) => {
+                    for row in rows {
+                        let key = join_key(&self.left_keys, &row)?;
+                        table.probe(&self.spec, key.as_deref(), row, &mut self.output)?;
+                    }
+                }
+                None => {
+                    if let Some(table) = self.table.take() {
+                        table.finish(&self.spec, &mut self.output);
+                    }
+                    self.probe = None;
+                    if self.partitions.is_empty() && self.output.is_empty() {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.table = None;
+        self.probe = None;
+        self.partitions.clear();
+        self.fallback = None;
+        self.left.close().await?;
+        self.right.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Rows of a nested loop join's inner input
+enum InnerRows {
+    Memory(Vec<Row>),
+    Spilled(SpillFile),
+}
+
+impl InnerRows {
+    fn for_each(&self, mut f: impl FnMut(usize, &Row) -> ExecutionResult<()>) -> ExecutionResult<()> {
+        match self {
+            InnerRows::Memory(rows) => {
+                for (i, row) in rows.iter().enumerate() {
+                    f(i, row)?;
+                }
+            }
+            InnerRows::Spilled(file) => {
+                for (i, row) in file.reader()?.enumerate() {
+                    f(i, &row?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Nested loop join operator
+///
+/// Materializes the right input, on disk if it exceeds the memory limit,
+/// and evaluates the condition for every pair of rows.
+pub struct NestedLoopJoinOperator {
+    left: Box<dyn PhysicalOperator>,
+    right: Box<dyn PhysicalOperator>,
+    spec: JoinSpec,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    inner: Option<InnerRows>,
+    inner_matched: Vec<bool>,
+    left_done: bool,
+    output: VecDeque<Row>,
+}
+
+impl NestedLoopJoinOperator {
+    /// Create a nested loop join over a buffered right input
+    pub fn new(
+        join_type: JoinType,
+        condition: Option<ScalarExpr>,
+        left: Box<dyn PhysicalOperator>,
+        right: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let combined: Vec<ColumnRef> = left.columns().into_iter().chain(right.columns()).collect();
+        let residual = condition.map(|c| BoundExpr::bind(&c, &combined)).transpose()?;
+        let (schema, output_columns) = join_output(join_type, left.as_ref(), right.as_ref());
+        let spec = JoinSpec {
+            join_type,
+            residual,
+            left_width: left.schema().columns.len(),
+            right_width: right.schema().columns.len(),
+        };
+
+        Ok(Self {
+            left,
+            right,
+            spec,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            inner: None,
+            inner_matched: Vec::new(),
+            left_done: false,
+            output: VecDeque::new(),
+        })
+    }
+
+    async fn materialize_inner(&mut self) -> ExecutionResult<InnerRows> {
+        let mut rows = Vec::new();
+        let mut bytes = 0;
+        let mut writer: Option<SpillWriter> = None;
+
+        while let Some(batch) = self.right.next().await? {
+            for row in batch.rows {
+                if let Some(writer) = &mut writer {
+                    writer.write(&row)?;
+                    continue;
+                }
+                bytes += row_size(&row);
+                rows.push(row);
+                if bytes > self.context.operator_memory_limit {
+                    let mut spill = SpillWriter::create(&self.context.spill_directory)?;
+                    for row in rows.drain(..) {
+                        spill.write(&row)?;
+                    }
+                    writer = Some(spill);
+                }
+            }
+        }
+
+        Ok(match writer {
+            Some(writer) => InnerRows::Spilled(writer.finish()?),
+            None => InnerRows::Memory(rows),
+        })
+    }
+
+    fn join_batch(&mut self, lefts: Vec<Row>) -> ExecutionResult<()> {
+        let Some(inner) = &self.inner else {
+            return Err(ExecutionError::InvalidState("Inner input not materialized".to_string()));
+        };
+        let (spec, output, inner_matched) = (&self.spec, &mut self.output, &mut self.inner_matched);
+        let mut left_matched = vec![false; lefts.len()];
+
+        inner.for_each(|j, right| {
+            for (i, left) in lefts.iter().enumerate() {
+                if let Some(row) = spec.pair(left, right)? {
+                    left_matched[i] = true;
+                    inner_matched[j] = true;
+                    if spec.emits_pairs() {
+                        output.push_back(row);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        for (left, matched) in lefts.into_iter().zip(left_matched) {
+            spec.finish_left(left, matched, output);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for NestedLoopJoinOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.inner = None;
+        self.left_done = false;
+        self.output.clear();
+        self.left.open().await?;
+        self.right.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if self.inner.is_none() {
+            let inner = self.materialize_inner().await?;
+            let count = match &inner {
+                InnerRows::Memory(rows) => rows.len(),
+                InnerRows::Spilled(file) => file.rows(),
+            };
+            self.inner_matched = vec![false; count];
+            self.inner = Some(inner);
+        }
+
+        loop {
+            if let Some(batch) = take_batch(&mut self.output, self.context.batch_size, &self.schema) {
+                return Ok(Some(batch));
+            }
+            if self.left_done {
+                return Ok(None);
+            }
+
+            match self.left.next().await? {
+                Some(batch) => self.join_batch(batch.rows)?,
+                None => {
+                    self.left_done = true;
+                    if self.spec.tracks_right() {
+                        if let Some(inner) = &self.inner {
+                            let (spec, output, matched) = (&self.spec, &mut self.output, &self.inner_matched);
+                            inner.for_each(|j, right| {
+                                if !matched[j] {
+                                    spec.finish_right(right.clone(), output);
+                                }
+                                Ok(())
+                            })?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.inner = None;
+        self.left.close().await?;
+        self.right.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Largest number of grid columns and rows of a partitioned spatial join
+const MAX_SPATIAL_GRID: usize = 16;
+
+/// Geometry and envelope of a row, `None` for NULL or empty geometries
+fn row_geometry(expr: &BoundExpr, row: &Row) -> ExecutionResult<Option<(Geometry, Envelope)>> {
+    let value = expr.eval(row)?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    let geometry = geometry_value(&value)?;
+    Ok(geometry.envelope().map(|envelope| (geometry, envelope)))
+}
+
+/// Spatial predicate, geometry expressions and row assembly of a spatial join
+struct SpatialJoinSpec {
+    predicate: SpatialPredicate,
+    /// `ST_DWithin` distance; right envelopes are expanded by it
+    distance: f64,
+    left_geometry: BoundExpr,
+    right_geometry: BoundExpr,
+    join: JoinSpec,
+}
+
+impl SpatialJoinSpec {
+    /// Combined row if the geometries satisfy the predicate and the pair
+    /// the residual condition
+    fn pair(
+        &self,
+        left: &Row,
+        left_geometry: &Geometry,
+        right: &Row,
+        right_geometry: &Geometry,
+    ) -> ExecutionResult<Option<Row>> {
+        if !left_geometry.satisfies(self.predicate, right_geometry, self.distance) {
+            return Ok(None);
+        }
+        self.join.pair(left, right)
+    }
+
+    /// Search envelope of a right geometry
+    fn right_envelope(&self, envelope: Envelope) -> Envelope {
+        envelope.expand(self.distance)
+    }
+}
+
+/// In-memory right input of a spatial join with an R-tree over its envelopes
+struct SpatialIndex {
+    rows: Vec<Row>,
+    geometries: Vec<Option<Geometry>>,
+    tree: RTree,
+    matched: Vec<bool>,
+}
+
+impl SpatialIndex {
+    fn build(spec: &SpatialJoinSpec, rows: Vec<Row>, geometries: Vec<Option<Geometry>>) -> Self {
+        let entries = geometries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, g)| Some((spec.right_envelope(g.as_ref()?.envelope()?), i)))
+            .collect();
+        Self {
+            tree: RTree::build(entries),
+            matched: vec![false; rows.len()],
+            rows,
+            geometries,
+        }
+    }
+
+    fn probe(&mut self, spec: &SpatialJoinSpec, left: Row, output: &mut VecDeque<Row>) -> ExecutionResult<()> {
+        let mut matched = false;
+        if let Some((geometry, envelope)) = row_geometry(&spec.left_geometry, &left)? {
+            let mut candidates = Vec::new();
+            self.tree.search(&envelope, |i| candidates.push(i));
+            // Emit matches in right input order
+            candidates.sort_unstable();
+            for i in candidates {
+                let Some(right_geometry) = &self.geometries[i] else {
+                    continue;
+                };
+                if let Some(row) = spec.pair(&left, &geometry, &self.rows[i], right_geometry)? {
+                    matched = true;
+                    self.matched[i] = true;
+                    if spec.join.emits_pairs() {
+                        output.push_back(row);
+                    } else if !spec.join.tracks_right() {
+                        break;
+                    }
+                }
+            }
+        }
+        spec.join.finish_left(left, matched, output);
+        Ok(())
+    }
+
+    fn finish(self, spec: &JoinSpec, output: &mut VecDeque<Row>) {
+        if spec.tracks_right() {
+            for (row, matched) in self.rows.into_iter().zip(self.matched) {
+                if !matched {
+                    spec.finish_right(row, output);
+                }
+            }
+        }
+    }
+}
+
+/// Both inputs of a spatial join, spilled and partitioned on a grid
+struct SpatialPartitions {
+    grid: Option<Grid>,
+    /// Left and right rows, tagged with their input position, per grid cell
+    cells: VecDeque<(usize, SpillFile, SpillFile)>,
+    left_rows: SpillFile,
+    right_rows: SpillFile,
+    left_matched: Vec<bool>,
+    right_matched: Vec<bool>,
+}
+
+/// Pass over a spilled input that outputs rows by whether they matched
+struct UnmatchedPass {
+    rows: RowStream,
+    matched: Vec<bool>,
+    position: usize,
+    left: bool,
+}
+
+/// Rows of one side of a grid cell with their input positions, geometries
+/// and search envelopes
+fn load_cell(
+    file: SpillFile,
+    expr: &BoundExpr,
+    expand: impl Fn(Envelope) -> Envelope,
+) -> ExecutionResult<Vec<(usize, Row, Geometry, Envelope)>> {
+    let mut entries = Vec::with_capacity(file.rows());
+    for row in file.into_stream()? {
+        let mut row = row?;
+        let Some(Value::Integer(position)) = row.values.pop() else {
+            return Err(ExecutionError::InvalidState("Partitioned row without position".to_string()));
+        };
+        if let Some((geometry, envelope)) = row_geometry(expr, &row)? {
+            entries.push((position as usize, row, geometry, expand(envelope)));
+        }
+    }
+    Ok(entries)
+}
+
+enum SpatialJoinState {
+    Build,
+    Probe(SpatialIndex),
+    Partitioned(SpatialPartitions),
+    Unmatched(VecDeque<UnmatchedPass>),
+    Done,
+}
+
+/// Spatial join operator
+///
+/// Builds an R-tree over the envelopes of the right input and probes it
+/// with each left row, checking the exact predicate on the candidates.
+/// When the right input exceeds the memory limit, or the planner chose a
+/// partition-based spatial merge, both inputs are spilled, partitioned on
+/// a grid over their common extent and joined cell by cell with a plane
+/// sweep. A pair found in several cells is only reported in the cell
+/// holding the lower corner of its envelopes' overlap.
+pub struct SpatialJoinOperator {
+    left: Box<dyn PhysicalOperator>,
+    right: Box<dyn PhysicalOperator>,
+    spec: SpatialJoinSpec,
+    strategy: SpatialJoinStrategy,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    state: SpatialJoinState,
+    output: VecDeque<Row>,
+}
+
+impl SpatialJoinOperator {
+    /// Create a spatial join with the given strategy
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        join_type: JoinType,
+        predicate: SpatialPredicate,
+        geometries: (ScalarExpr, ScalarExpr),
+        distance: Option<f64>,
+        condition: Option<ScalarExpr>,
+        strategy: SpatialJoinStrategy,
+        left: Box<dyn PhysicalOperator>,
+        right: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let (left_columns, right_columns) = (left.columns(), right.columns());
+        // The predicate's arguments need not be in join input order
+        let (left_geometry, right_geometry, predicate) = match (
+            BoundExpr::bind(&geometries.0, &left_columns),
+            BoundExpr::bind(&geometries.1, &right_columns),
+        ) {
+            (Ok(l), Ok(r)) => (l, r, predicate),
+            _ => (
+                BoundExpr::bind(&geometries.1, &left_columns)?,
+                BoundExpr::bind(&geometries.0, &right_columns)?,
+                predicate.converse(),
+            ),
+        };
+        let distance = match (predicate, distance) {
+            (SpatialPredicate::DWithin, None) => {
+                return Err(ExecutionError::NotImplemented(
+                    "ST_DWithin join with a non-constant distance".to_string(),
+                ))
+            }
+            (_, distance) => distance.unwrap_or(0.0),
+        };
+
+        let combined: Vec<ColumnRef> = left_columns.iter().chain(&right_columns).cloned().collect();
+        let residual = condition.map(|c| BoundExpr::bind(&c, &combined)).transpose()?;
+        let (schema, output_columns) = join_output(join_type, left.as_ref(), right.as_ref());
+        let spec = SpatialJoinSpec {
+            predicate,
+            distance,
+            left_geometry,
+            right_geometry,
+            join: JoinSpec {
+                join_type,
+                residual,
+                left_width: left.schema().columns.len(),
+                right_width: right.schema().columns.len(),
+            },
+        };
+
+        Ok(Self {
+            left,
+            right,
+            spec,
+            strategy,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            state: SpatialJoinState::Build,
+            output: VecDeque::new(),
+        })
+    }
+
+    /// Index the right input, or spill and partition both inputs
+    async fn build(&mut self) -> ExecutionResult<()> {
+        let mut rows = Vec::new();
+        let mut geometries = Vec::new();
+        let mut bytes = 0;
+        let mut spilled: Option<SpillWriter> = None;
+
+        while let Some(batch) = self.right.next().await? {
+            for row in batch.rows {
+                if let Some(writer) = &mut spilled {
+                    writer.write(&row)?;
+                    continue;
+                }
+                let geometry = row_geometry(&self.spec.right_geometry, &row)?.map(|(g, _)| g);
+                bytes += row_size(&row) + geometry.as_ref().map_or(0, Geometry::size);
+                rows.push(row);
+                geometries.push(geometry);
+
+                if self.strategy == SpatialJoinStrategy::PartitionMerge
+                    || bytes > self.context.operator_memory_limit
+                {
+                    let mut writer = SpillWriter::create(&self.context.spill_directory)?;
+                    for row in rows.drain(..) {
+                        writer.write(&row)?;
+                    }
+                    geometries.clear();
+                    spilled = Some(writer);
+                }
+            }
+        }
+
+        let Some(writer) = spilled else {
+            self.state = SpatialJoinState::Probe(SpatialIndex::build(&self.spec, rows, geometries));
+            return Ok(());
+        };
+
+        tracing::debug!("Spatial join partitioning both inputs to disk");
+        let right_rows = writer.finish()?;
+        let mut writer = SpillWriter::create(&self.context.spill_directory)?;
+        while let Some(batch) = self.left.next().await? {
+            for row in batch.rows {
+                writer.write(&row)?;
+            }
+        }
+        let left_rows = writer.finish()?;
+        self.state = SpatialJoinState::Partitioned(self.partition(left_rows, right_rows)?);
+        Ok(())
+    }
+
+    /// Distribute both spilled inputs to the grid cells they overlap
+    fn partition(&self, left_rows: SpillFile, right_rows: SpillFile) -> ExecutionResult<SpatialPartitions> {
+        let spec = &self.spec;
+        let scan = |file: &SpillFile, expr: &BoundExpr, expand: &dyn Fn(Envelope) -> Envelope| {
+            let (mut extent, mut bytes) = (None::<Envelope>, 0);
+            for row in file.reader()? {
+                let row = row?;
+                bytes += row_size(&row);
+                if let Some((_, envelope)) = row_geometry(expr, &row)? {
+                    let envelope = expand(envelope);
+                    extent = Some(extent.map_or(envelope, |e| e.merge(&envelope)));
+                }
+            }
+            Ok::<_, ExecutionError>((extent, bytes))
+        };
+        let expand_right = |e: Envelope| spec.right_envelope(e);
+        let (left_extent, left_bytes) = scan(&left_rows, &spec.left_geometry, &|e| e)?;
+        let (right_extent, right_bytes) = scan(&right_rows, &spec.right_geometry, &expand_right)?;
+
+        // Only the common extent can hold matching pairs
+        let grid = match (left_extent, right_extent) {
+            (Some(l), Some(r)) => l.intersection(&r).map(|extent| {
+                // Aim for cells whose rows fit in memory together
+                let limit = self.context.operator_memory_limit.max(1) as f64;
+                let cells = 2.0 * (left_bytes + right_bytes) as f64 / limit;
+                Grid {
+                    extent,
+                    columns: (cells.sqrt().ceil() as usize).clamp(1, MAX_SPATIAL_GRID),
+                }
+            }),
+            _ => None,
+        };
+
+        let mut cells = VecDeque::new();
+        if let Some(grid) = &grid {
+            let lefts = self.distribute(&left_rows, &spec.left_geometry, &|e| e, grid)?;
+            let rights = self.distribute(&right_rows, &spec.right_geometry, &expand_right, grid)?;
+            for (cell, pair) in lefts.into_iter().zip(rights).enumerate() {
+                match pair {
+                    (Some(left), Some(right)) => cells.push_back((cell, left.finish()?, right.finish()?)),
+                    // A cell with one side cannot produce pairs; finishing drops its file
+                    (left, right) => {
+                        for writer in left.into_iter().chain(right) {
+                            writer.finish()?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(SpatialPartitions {
+            grid,
+            cells,
+            left_matched: vec![false; left_rows.rows()],
+            right_matched: vec![false; right_rows.rows()],
+            left_rows,
+            right_rows,
+        })
+    }
+
+    /// Write each row, tagged with its position, to the cells it overlaps
+    fn distribute(
+        &self,
+        file: &SpillFile,
+        expr: &BoundExpr,
+        expand: &dyn Fn(Envelope) -> Envelope,
+        grid: &Grid,
+    ) -> ExecutionResult<Vec<Option<SpillWriter>>> {
+        let mut writers: Vec<Option<SpillWriter>> = (0..grid.len()).map(|_| None).collect();
+        for (position, row) in file.reader()?.enumerate() {
+            let mut row = row?;
+            let Some((_, envelope)) = row_geometry(expr, &row)? else {
+                continue;
+            };
+            row.values.push(Value::Integer(position as i64));
+            for cell in grid.cells_overlapping(&expand(envelope)) {
+                if writers[cell].is_none() {
+                    writers[cell] = Some(SpillWriter::create(&self.context.spill_directory)?);
+                }
+                if let Some(writer) = &mut writers[cell] {
+                    writer.write(&row)?;
+                }
+            }
+        }
+        Ok(writers)
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for SpatialJoinOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.state = SpatialJoinState::Build;
+        self.output.clear();
+        self.left.open().await?;
+        self.right.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if matches!(self.state, SpatialJoinState::Build) {
+            self.build().await?;
+        }
+
+        loop {
+            if let Some(batch) = take_batch(&mut self.output, self.context.batch_size, &self.schema) {
+                return Ok(Some(batch));
+            }
+
+            let spec = &self.spec;
+            match &mut self.state {
+                SpatialJoinState::Build | SpatialJoinState::Done => return Ok(None),
+
+                SpatialJoinState::Probe(index) => match self.left.next().await? {
+                    Some(batch) => {
+                        for row in batch.rows {
+                            index.probe(spec, row, &mut self.output)?;
+                        }
+                    }
+                    None => {
+                        if let SpatialJoinState::Probe(index) =
+                            std::mem::replace(&mut self.state, SpatialJoinState::Done)
+                        {
+                            index.finish(&spec.join, &mut self.output);
+                        }
+                    }
+                },
+
+                SpatialJoinState::Partitioned(partitions) => {
+                    let Some((cell, left, right)) = partitions.cells.pop_front() else {
+                        let SpatialJoinState::Partitioned(partitions) =
+                            std::mem::replace(&mut self.state, SpatialJoinState::Done)
+                        else {
+                            unreachable!("state checked above");
+                        };
+                        let join_type = spec.join.join_type;
+                        let mut passes = VecDeque::new();
+                        if matches!(
+                            join_type,
+                            JoinType::Left | JoinType::Full | JoinType::Semi | JoinType::AntiSemi
+                        ) {
+                            passes.push_back(UnmatchedPass {
+                                rows: partitions.left_rows.into_stream()?,
+                                matched: partitions.left_matched,
+                                position: 0,
+                                left: true,
+                            });
+                        }
+                        if spec.join.tracks_right() {
+                            passes.push_back(UnmatchedPass {
+                                rows: partitions.right_rows.into_stream()?,
+                                matched: partitions.right_matched,
+                                position: 0,
+                                left: false,
+                            });
+                        }
+                        self.state = SpatialJoinState::Unmatched(passes);
+                        continue;
+                    };
+
+                    let lefts = load_cell(left, &spec.left_geometry, |e| e)?;
+                    let rights = load_cell(right, &spec.right_geometry, |e| spec.right_envelope(e))?;
+                    let mut candidates = Vec::new();
+                    plane_sweep(
+                        &lefts.iter().map(|e| e.3).collect::<Vec<_>>(),
+                        &rights.iter().map(|e| e.3).collect::<Vec<_>>(),
+                        |i, j| candidates.push((i, j)),
+                    );
+                    let grid = partitions.grid.as_ref().expect("cells imply a grid");
+                    for (i, j) in candidates {
+                        let (l, left, left_geometry, a) = &lefts[i];
+                        let (r, right, right_geometry, b) = &rights[j];
+                        if grid.cell_of(a.min_x.max(b.min_x), a.min_y.max(b.min_y)) != cell {
+                            continue;
+                        }
+                        if let Some(row) = spec.pair(left, left_geometry, right, right_geometry)? {
+                            partitions.left_matched[*l] = true;
+                            partitions.right_matched[*r] = true;
+                            if spec.join.emits_pairs() {
+                                self.output.push_back(row);
+                            }
+                        }
+                    }
+                }
+
+                SpatialJoinState::Unmatched(passes) => {
+                    let Some(pass) = passes.front_mut() else {
+                        self.state = SpatialJoinState::Done;
+                        continue;
+                    };
+                    match take_rows(&mut pass.rows, self.context.batch_size)? {
+                        Some(rows) => {
+                            for row in rows {
+                                let matched = pass.matched[pass.position];
+                                pass.position += 1;
+                                if pass.left {
+                                    spec.join.finish_left(row, matched, &mut self.output);
+                                } else if !matched {
+                                    spec.join.finish_right(row, &mut self.output);
+                                }
+                            }
+                        }
+                        None => {
+                            passes.pop_front();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.state = SpatialJoinState::Done;
+        self.left.close().await?;
+        self.right.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Sort all rows of an input on key expressions
+///
+/// The returned rows carry the key values in front of the input values.
+async fn sort_input(
+    input: &mut Box<dyn PhysicalOperator>,
+    keys: &[BoundExpr],
+    orders: Vec<SortOrder>,
+    context: &ExecutionContext,
+) -> ExecutionResult<RowStream> {
+    let mut sorter = ExternalSorter::new(orders, context.operator_memory_limit, &context.spill_directory);
+    while let Some(batch) = input.next().await? {
+        for row in batch.rows {
+            let mut values = keys.iter().map(|k| k.eval(&row)).collect::<ExecutionResult<Vec<_>>>()?;
+            values.extend(row.values);
+            sorter.push(Row::new(values))?;
+        }
+    }
+    if sorter.spilled_runs() > 0 {
+        tracing::debug!("Sort spilled {} runs to disk", sorter.spilled_runs());
+    }
+    sorter.finish()
+}
+
+fn strip_keys(mut row: Row, key_count: usize) -> Row {
+    row.values.drain(..key_count);
+    row
+}
+
+fn compare_keys(a: &[Value], b: &[Value]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| compare_values(x, y))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+fn sort_orders(order_by: &[OrderByItem]) -> Vec<SortOrder> {
+    order_by
+        .iter()
+        .map(|item| SortOrder {
+            descending: item.direction == SortDirection::Descending,
+            nulls_first: item.nulls_first,
+        })
+        .collect()
+}
+
+/// Sort-merge join operator
+///
+/// Sorts both inputs on the join keys, spilling sorted runs as needed, and
+/// joins groups of equal keys.
+pub struct MergeJoinOperator {
+    left: Box<dyn PhysicalOperator>,
+    right: Box<dyn PhysicalOperator>,
+    left_keys: Vec<BoundExpr>,
+    right_keys: Vec<BoundExpr>,
+    spec: JoinSpec,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    streams: Option<(RowStream, RowStream)>,
+    left_head: Option<Row>,
+    right_head: Option<Row>,
+    output: VecDeque<Row>,
+}
+
+impl MergeJoinOperator {
+    /// Create a merge join over inputs sorted on their keys
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        join_type: JoinType,
+        left_keys: Vec<ScalarExpr>,
+        right_keys: Vec<ScalarExpr>,
+        condition: Option<ScalarExpr>,
+        left: Box<dyn PhysicalOperator>,
+        right: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let (left_keys, right_keys, residual) =
+            bind_join(left_keys, right_keys, condition, &left.columns(), &right.columns())?;
+        let (schema, output_columns) = join_output(join_type, left.as_ref(), right.as_ref());
+        let spec = JoinSpec {
+            join_type,
+            residual,
+            left_width: left.schema().columns.len(),
+            right_width: right.schema().columns.len(),
+        };
+
+        Ok(Self {
+            left,
+            right,
+            left_keys,
+            right_keys,
+            spec,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            streams: None,
+            left_head: None,
+            right_head: None,
+            output: VecDeque::new(),
+        })
+    }
+
+    fn key_count(&self) -> usize {
+        self.left_keys.len()
+    }
+
+    fn has_null_key(&self, row: &Row) -> bool {
+        row.values[..self.key_count()].iter().any(Value::is_null)
+    }
+
+    /// Advance the merge by one row or one group of equal keys
+    fn step(&mut self) -> ExecutionResult<bool> {
+        let k = self.key_count();
+        let Some((left_stream, right_stream)) = self.streams.as_mut() else {
+            return Ok(false);
+        };
+        let (spec, output) = (&self.spec, &mut self.output);
+
+        match (self.left_head.take(), self.right_head.take()) {
+            (None, None) => return Ok(false),
+            (Some(left), None) => {
+                spec.finish_left(strip_keys(left, k), false, output);
+                self.left_head = left_stream.next().transpose()?;
+            }
+            (None, Some(right)) => {
+                spec.finish_right(strip_keys(right, k), output);
+                self.right_head = right_stream.next().transpose()?;
+            }
+            (Some(left), Some(right)) => {
+                let left_null = left.values[..k].iter().any(Value::is_null);
+                let right_null = right.values[..k].iter().any(Value::is_null);
+                let ordering = compare_keys(&left.values[..k], &right.values[..k]);
+                if left_null || (!right_null && ordering == Ordering::Less) {
+                    spec.finish_left(strip_keys(left, k), false, output);
+                    self.left_head = left_stream.next().transpose()?;
+                    self.right_head = Some(right);
+                } else if right_null || ordering == Ordering::Greater {
+                    spec.finish_right(strip_keys(right, k), output);
+                    self.right_head = right_stream.next().transpose()?;
+                    self.left_head = Some(left);
+                } else {
+                    // Collect the right group with this key
+                    let key = right.values[..k].to_vec();
+                    let mut group = vec![strip_keys(right, k)];
+                    loop {
+                        match right_stream.next().transpose()? {
+                            Some(row) if compare_keys(&row.values[..k], &key) == Ordering::Equal => {
+                                group.push(strip_keys(row, k))
+                            }
+                            other => {
+                                self.right_head = other;
+                                break;
+                            }
+                        }
+                    }
+
+                    // Join every left row with the same key against it
+                    let mut group_matched = vec![false; group.len()];
+                    let mut next_left = Some(left);
+                    while let Some(row) = next_left.take() {
+                        if compare_keys(&row.values[..k], &key) != Ordering::Equal
+                            || row.values[..k].iter().any(Value::is_null)
+                        {
+                            next_left = Some(row);
+                            break;
+                        }
+                        let left = strip_keys(row, k);
+                        let mut matched = false;
+                        for (j, right) in group.iter().enumerate() {
+                            if let Some(pair) = spec.pair(&left, right)? {
+                                matched = true;
+                                group_matched[j] = true;
+                                if spec.emits_pairs() {
+                                    output.push_back(pair);
+                                }
+                            }
+                        }
+                        spec.finish_left(left, matched, output);
+                        next_left = left_stream.next().transpose()?;
+                    }
+                    self.left_head = next_left;
+
+                    for (right, matched) in group.into_iter().zip(group_matched) {
+                        if !matched {
+                            spec.finish_right(right, output);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for MergeJoinOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.streams = None;
+        self.output.clear();
+        self.left.open().await?;
+        self.right.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if self.streams.is_none() {
+            let orders = vec![SortOrder::ASCENDING; self.key_count()];
+            let mut left = sort_input(&mut self.left, &self.left_keys, orders.clone(), &self.context).await?;
+            let mut right = sort_input(&mut self.right, &self.right_keys, orders, &self.context).await?;
+            self.left_head = left.next().transpose()?;
+            self.right_head = right.next().transpose()?;
+            self.streams = Some((left, right));
+        }
+
+        while self.output.len() < self.context.batch_size && self.step()? {}
+        Ok(take_batch(&mut self.output, self.context.batch_size, &self.schema))
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.streams = None;
+        self.left_head = None;
+        self.right_head = None;
+        self.left.close().await?;
+        self.right.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// How an aggregate operator treats its input and output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateMode {
+    /// Aggregate input rows into final values
+    Complete,
+    /// Aggregate input rows into mergeable state, below a Gather
+    Partial,
+    /// Merge the state of partial aggregates into final values
+    Final,
+}
+
+/// Maximum depth of recursive repartitioning of spilled hash join and
+/// aggregate input
+const MAX_SPILL_DEPTH: usize = 3;
+
+/// Grouping and aggregate evaluation shared by the aggregate operators
+struct GroupAggregator {
+    group_by: Vec<BoundExpr>,
+    aggregates: Vec<AggregateSpec>,
+    having: Option<BoundExpr>,
+    mode: AggregateMode,
+}
+
+impl GroupAggregator {
+    fn group_key(&self, row: &Row) -> ExecutionResult<Vec<Value>> {
+        match self.mode {
+            AggregateMode::Final => Ok(row.values[..self.group_by.len()].to_vec()),
+            _ => self.group_by.iter().map(|g| g.eval(row)).collect(),
+        }
+    }
+
+    fn init(&self) -> Vec<AggState> {
+        self.aggregates.iter().map(AggregateSpec::init).collect()
+    }
+
+    /// Fold a row into group state; returns the bytes of state retained
+    fn accumulate(&self, states: &mut [AggState], row: &Row) -> ExecutionResult<usize> {
+        let mut bytes = 0;
+        let mut offset = self.group_by.len();
+        for (spec, state) in self.aggregates.iter().zip(states) {
+            bytes += match self.mode {
+                AggregateMode::Final => {
+                    let width = spec.state_width();
+                    let partial = row.values.get(offset..offset + width).ok_or_else(|| {
+                        ExecutionError::InvalidState("Partial aggregate row too short".to_string())
+                    })?;
+                    offset += width;
+                    spec.merge(state, partial)?
+                }
+                _ => spec.update(state, row)?,
+            };
+        }
+        Ok(bytes)
+    }
+
+    /// Output row of a group, `None` if HAVING rejects it
+    fn output(&self, key: Vec<Value>, states: Vec<AggState>) -> ExecutionResult<Option<Row>> {
+        let mut values = key;
+        for (spec, state) in self.aggregates.iter().zip(states) {
+            match self.mode {
+                AggregateMode::Partial => values.extend(spec.state_values(state)),
+                _ => values.push(spec.finish(state)?),
+            }
+        }
+        let row = Row::new(values);
+        match &self.having {
+            Some(having) if !having.matches(&row)? => Ok(None),
+            _ => Ok(Some(row)),
+        }
+    }
+
+    /// Row for a global aggregate over no input, e.g. `COUNT(*) = 0`
+    fn empty_output(&self) -> ExecutionResult<Option<Row>> {
+        if self.group_by.is_empty() && self.mode != AggregateMode::Partial {
+            self.output(Vec::new(), self.init())
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Output schema, columns and bound aggregator of an aggregate operator
+fn bind_aggregate(
+    group_by: &[ScalarExpr],
+    aggregates: &[AggregateFunction],
+    having: Option<&ScalarExpr>,
+    mode: AggregateMode,
+    input: &dyn PhysicalOperator,
+) -> ExecutionResult<(Schema, Vec<ColumnRef>, GroupAggregator)> {
+    let input_columns = input.columns();
+    let bound_group_by = group_by
+        .iter()
+        .map(|g| BoundExpr::bind(g, &input_columns))
+        .collect::<ExecutionResult<Vec<_>>>();
+    let bound_aggregates = aggregates
+        .iter()
+        .map(|a| AggregateSpec::bind(a, &input_columns))
+        .collect::<ExecutionResult<Vec<_>>>();
+    // A final aggregate reads partial state, not the original input
+    let (bound_group_by, bound_aggregates) = match mode {
+        AggregateMode::Final => {
+            let bind_columns: Vec<ColumnRef> = group_by.iter().map(|g| ColumnRef::new(expr_name(g))).collect();
+            (
+                group_by
+                    .iter()
+                    .map(|g| BoundExpr::bind(g, &bind_columns).or_else(|_| BoundExpr::bind(g, &input_columns)))
+                    .collect::<ExecutionResult<Vec<_>>>()?,
+                aggregates
+                    .iter()
+                    .map(|a| {
+                        AggregateSpec::bind(a, &input_columns).or_else(|_| {
+                            AggregateSpec::bind(
+                                &AggregateFunction {
+                                    args: Vec::new(),
+                                    ..a.clone()
+                                },
+                                &input_columns,
+                            )
+                        })
+                    })
+                    .collect::<ExecutionResult<Vec<_>>>()?,
+            )
+        }
+        _ => (bound_group_by?, bound_aggregates?),
+    };
+
+    let mut columns: Vec<ColumnRef> = group_by
+        .iter()
+        .map(|g| match g {
+            ScalarExpr::Column(column) => column.clone(),
+            other => ColumnRef::new(expr_name(other)),
+        })
+        .collect();
+    let mut defs: Vec<ColumnDef> = match mode {
+        AggregateMode::Final => input.schema().columns[..group_by.len().min(input.schema().columns.len())].to_vec(),
+        _ => group_by
+            .iter()
+            .zip(&columns)
+            .map(|(g, c)| ColumnDef::new(c.name.clone(), expr_type(g, &input_columns, input.schema())))
+            .collect(),
+    };
+
+    for (aggregate, spec) in aggregates.iter().zip(&bound_aggregates) {
+        let name = aggregate_name(aggregate);
+        if mode == AggregateMode::Partial {
+            for i in 0..spec.state_width() {
+                columns.push(ColumnRef::new(format!("{}#{}", name, i)));
+                defs.push(ColumnDef::new(format!("{}#{}", name, i), DataType::Varbinary(None)));
+            }
+        } else {
+            let arg_type = match mode {
+                AggregateMode::Final => None,
+                _ => aggregate.args.first().map(|a| expr_type(a, &input_columns, input.schema())),
+            };
+            columns.push(ColumnRef::new(name.clone()));
+            defs.push(ColumnDef::new(name, spec.output_type(arg_type)));
+        }
+    }
+
+    let having = having.map(|h| BoundExpr::bind(h, &columns)).transpose()?;
+    let aggregator = GroupAggregator {
+        group_by: bound_group_by,
+        aggregates: bound_aggregates,
+        having,
+        mode,
+    };
+    Ok((Schema::new(defs), columns, aggregator))
+}
+
+/// Groups of one hash aggregation pass
+struct GroupTable {
+    index: HashMap<Vec<u8>, usize>,
+    groups: Vec<(Vec<Value>, Vec<AggState>)>,
+    bytes: usize,
+    /// New groups that did not fit, spilled for a later pass
+    spilled: Option<Partitions>,
+    depth: usize,
+}
+
+impl GroupTable {
+    fn new(depth: usize) -> Self {
+        Self {
+            index: HashMap::new(),
+            groups: Vec::new(),
+            bytes: 0,
+            spilled: None,
+            depth,
+        }
+    }
+
+    fn add(&mut self, aggregator: &GroupAggregator, row: Row, context: &ExecutionContext) -> ExecutionResult<()> {
+        let key_values = aggregator.group_key(&row)?;
+        let key = encode_key(&key_values);
+        if let Some(&group) = self.index.get(&key) {
+            self.bytes += aggregator.accumulate(&mut self.groups[group].1, &row)?;
+            return Ok(());
+        }
+
+        // Groups already in memory keep aggregating; rows of new groups spill
+        if self.bytes > context.operator_memory_limit && self.depth < MAX_SPILL_DEPTH {
+            let depth = self.depth;
+            return self
+                .spilled
+                .get_or_insert_with(|| Partitions::new(&context.spill_directory, depth))
+                .write(&key, &row);
+        }
+
+        let mut states = aggregator.init();
+        self.bytes += key.len() + row_size(&Row::new(key_values.clone())) + 64 * states.len();
+        self.bytes += aggregator.accumulate(&mut states, &row)?;
+        self.index.insert(key, self.groups.len());
+        self.groups.push((key_values, states));
+        Ok(())
+    }
+}
+
+/// Hash aggregate operator
+///
+/// Once the group table exceeds the memory limit, rows of new groups are
+/// hash partitioned to disk and aggregated partition by partition.
+pub struct HashAggregateOperator {
+    child: Box<dyn PhysicalOperator>,
+    aggregator: GroupAggregator,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    consumed: bool,
+    pending: Vec<(SpillFile, usize)>,
+    output: VecDeque<Row>,
+}
+
+impl HashAggregateOperator {
+    /// Create a hash aggregate
+    pub fn new(
+        group_by: Vec<ScalarExpr>,
+        aggregates: Vec<AggregateFunction>,
+        having: Option<ScalarExpr>,
+        mode: AggregateMode,
+        child: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let (schema, output_columns, aggregator) =
+            bind_aggregate(&group_by, &aggregates, having.as_ref(), mode, child.as_ref())?;
+        Ok(Self::with_aggregator(child, aggregator, schema, output_columns, cardinality, context))
+    }
+
+    /// Distinct rows of the input
+    pub fn distinct(child: Box<dyn PhysicalOperator>, cardinality: Cardinality, context: ExecutionContext) -> Self {
+        let aggregator = GroupAggregator {
+            group_by: (0..child.schema().columns.len()).map(BoundExpr::Column).collect(),
+            aggregates: Vec::new(),
+            having: None,
+            mode: AggregateMode::Complete,
+        };
+        let (schema, columns) = (child.schema().clone(), child.columns());
+        Self::with_aggregator(child, aggregator, schema, columns, cardinality, context)
+    }
+
+    fn with_aggregator(
+        child: Box<dyn PhysicalOperator>,
+        aggregator: GroupAggregator,
+        schema: Schema,
+        output_columns: Vec<ColumnRef>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> Self {
+        Self {
+            child,
+            aggregator,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            consumed: false,
+            pending: Vec::new(),
+            output: VecDeque::new(),
+        }
+    }
+
+    /// Emit the groups of a finished pass and queue its spilled partitions
+    fn finish_pass(&mut self, table: GroupTable, global: bool) -> ExecutionResult<()> {
+        if global && table.groups.is_empty() {
+            if let Some(row) = self.aggregator.empty_output()? {
+                self.output.push_back(row);
+            }
+        }
+        for (key, states) in table.groups {
+            if let Some(row) = self.aggregator.output(key, states)? {
+                self.output.push_back(row);
+            }
+        }
+        if let Some(spilled) = table.spilled {
+            tracing::debug!("Hash aggregate spilled at depth {}", table.depth);
+            for file in spilled.finish()?.into_iter().flatten() {
+                self.pending.push((file, table.depth + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for HashAggregateOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.consumed = false;
+        self.pending.clear();
+        self.output.clear();
+        self.child.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if !self.consumed {
+            let mut table = GroupTable::new(0);
+            while let Some(batch) = self.child.next().await? {
+                for row in batch.rows {
+                    table.add(&self.aggregator, row, &self.context)?;
+                }
+            }
+            self.consumed = true;
+            self.finish_pass(table, true)?;
+        }
+
+        loop {
+            if let Some(batch) = take_batch(&mut self.output, self.context.batch_size, &self.schema) {
+                return Ok(Some(batch));
+            }
+            let Some((file, depth)) = self.pending.pop() else {
+                return Ok(None);
+            };
+            let mut table = GroupTable::new(depth);
+            for row in file.reader()? {
+                table.add(&self.aggregator, row?, &self.context)?;
+            }
+            self.finish_pass(table, false)?;
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.pending.clear();
+        self.output.clear();
+        self.child.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Sort aggregate operator
+///
+/// Sorts the input on the group keys and aggregates one group at a time,
+/// producing groups in key order.
+pub struct SortAggregateOperator {
+    child: Box<dyn PhysicalOperator>,
+    aggregator: GroupAggregator,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    sorted: Option<RowStream>,
+    current: Option<(Vec<Value>, Vec<AggState>)>,
+    groups_seen: bool,
+    done: bool,
+    output: VecDeque<Row>,
+}
+
+impl SortAggregateOperator {
+    /// Create an aggregate over input sorted on the grouping keys
+    pub fn new(
+        group_by: Vec<ScalarExpr>,
+        aggregates: Vec<AggregateFunction>,
+        having: Option<ScalarExpr>,
+        child: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let (schema, output_columns, aggregator) = bind_aggregate(
+            &group_by,
+            &aggregates,
+            having.as_ref(),
+            AggregateMode::Complete,
+            child.as_ref(),
+        )?;
+        Ok(Self::with_aggregator(child, aggregator, schema, output_columns, cardinality, context))
+    }
+
+    /// Distinct rows of the input, in sorted order
+    pub fn distinct(child: Box<dyn PhysicalOperator>, cardinality: Cardinality, context: ExecutionContext) -> Self {
+        let aggregator = GroupAggregator {
+            group_by: (0..child.schema().columns.len()).map(BoundExpr::Column).collect(),
+            aggregates: Vec::new(),
+            having: None,
+            mode: AggregateMode::Complete,
+        };
+        let (schema, columns) = (child.schema().clone(), child.columns());
+        Self::with_aggregator(child, aggregator, schema, columns, cardinality, context)
+    }
+
+    fn with_aggregator(
+        child: Box<dyn PhysicalOperator>,
+        aggregator: GroupAggregator,
+        schema: Schema,
+        output_columns: Vec<ColumnRef>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> Self {
+        Self {
+            child,
+            aggregator,
+            schema,
+            output_columns,
+            cardinality,
+            context,
+            sorted: None,
+            current: None,
+            groups_seen: false,
+            done: false,
+            output: VecDeque::new(),
+        }
+    }
+
+    fn flush_group(&mut self) -> ExecutionResult<()> {
+        if let Some((key, states)) = self.current.take() {
+            if let Some(row) = self.aggregator.output(key, states)? {
+                self.output.push_back(row);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for SortAggregateOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.sorted = None;
+        self.current = None;
+        self.groups_seen = false;
+        self.done = false;
+        self.output.clear();
+        self.child.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if self.sorted.is_none() && !self.done {
+            let orders = vec![SortOrder::ASCENDING; self.aggregator.group_by.len()];
+            let group_by = self.aggregator.group_by.clone();
+            self.sorted = Some(sort_input(&mut self.child, &group_by, orders, &self.context).await?);
+        }
+
+        let k = self.aggregator.group_by.len();
+        while !self.done && self.output.len() < self.context.batch_size {
+            let next = match self.sorted.as_mut() {
+                Some(sorted) => sorted.next().transpose()?,
+                None => None,
+            };
+            let Some(row) = next else {
+                self.flush_group()?;
+                if !self.groups_seen {
+                    if let Some(row) = self.aggregator.empty_output()? {
+                        self.output.push_back(row);
+                    }
+                }
+                self.sorted = None;
+                self.done = true;
+                break;
+            };
+
+            let key = row.values[..k].to_vec();
+            let input = strip_keys(row, k);
+            let same_group = self
+                .current
+                .as_ref()
+                .is_some_and(|(current, _)| encode_key(current) == encode_key(&key));
+            if !same_group {
+                self.flush_group()?;
+                self.current = Some((key, self.aggregator.init()));
+                self.groups_seen = true;
+            }
+            if let Some((_, states)) = self.current.as_mut() {
+                self.aggregator.accumulate(states, &input)?;
+            }
+        }
+
+        Ok(take_batch(&mut self.output, self.context.batch_size, &self.schema))
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.sorted = None;
+        self.current = None;
+        self.child.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Sort operator, spilling sorted runs when over the memory limit
+pub struct SortOperator {
+    child: Box<dyn PhysicalOperator>,
+    keys: Vec<BoundExpr>,
+    orders: Vec<SortOrder>,
+    schema: Schema,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    sorted: Option<RowStream>,
+}
+
+impl SortOperator {
+    /// Create a sort
+    pub fn new(
+        order_by: Vec<OrderByItem>,
+        child: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let columns = child.columns();
+        let keys = order_by
+            .iter()
+            .map(|item| BoundExpr::bind(&item.expr, &columns))
+            .collect::<ExecutionResult<_>>()?;
+        Ok(Self {
+            schema: child.schema().clone(),
+            child,
+            keys,
+            orders: sort_orders(&order_by),
+            cardinality,
+            context,
+            sorted: None,
+        })
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for SortOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.sorted = None;
+        self.child.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if self.sorted.is_none() {
+            self.sorted =
+                Some(sort_input(&mut self.child, &self.keys, self.orders.clone(), &self.context).await?);
+        }
+        let Some(sorted) = self.sorted.as_mut() else {
+            return Ok(None);
+        };
+        let k = self.keys.len();
+        Ok(take_rows(sorted, self.context.batch_size)?.map(|rows| RowBatch {
+            rows: rows.into_iter().map(|row| strip_keys(row, k)).collect(),
+            schema: self.schema.clone(),
+        }))
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.sorted = None;
+        self.child.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.child.columns()
+    }
+}
+
+/// Top-N sort operator, keeping only the first `limit` rows in memory
+pub struct TopNSortOperator {
+    child: Box<dyn PhysicalOperator>,
+    keys: Vec<BoundExpr>,
+    orders: Vec<SortOrder>,
+    limit: usize,
+    schema: Schema,
+    cardinality: Cardinality,
+    context: ExecutionContext,
+    result: Option<VecDeque<Row>>,
+}
+
+impl TopNSortOperator {
+    /// Create a sort keeping only the first `limit` rows
+    pub fn new(
+        order_by: Vec<OrderByItem>,
+        limit: u64,
+        child: Box<dyn PhysicalOperator>,
+        cardinality: Cardinality,
+        context: ExecutionContext,
+    ) -> ExecutionResult<Self> {
+        let columns = child.columns();
+        let keys = order_by
+            .iter()
+            .map(|item| BoundExpr::bind(&item.expr, &columns))
+            .collect::<ExecutionResult<_>>()?;
+        Ok(Self {
+            schema: child.schema().clone(),
+            child,
+            keys,
+            orders: sort_orders(&order_by),
+            limit: limit as usize,
+            cardinality,
+            context,
+            result: None,
+        })
+    }
+
+    fn compact(&self, rows: &mut Vec<Row>) {
+        // Stable, so earlier rows win ties
+        rows.sort_by(|a, b| crate::spill::compare_sort_keys(a, b, &self.orders));
+        rows.truncate(self.limit);
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for TopNSortOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.result = None;
+        self.child.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        if self.result.is_none() {
+            let mut rows: Vec<Row> = Vec::new();
+            let threshold = self.limit.saturating_mul(2).max(self.context.batch_size);
+            while let Some(batch) = self.child.next().await? {
+                for row in batch.rows {
+                    let mut values = self.keys.iter().map(|k| k.eval(&row)).collect::<ExecutionResult<Vec<_>>>()?;
+                    values.extend(row.values);
+                    rows.push(Row::new(values));
+                }
+                if rows.len() > threshold {
+                    self.compact(&mut rows);
+                }
+            }
+            self.compact(&mut rows);
+            let k = self.keys.len();
+            self.result = Some(rows.into_iter().map(|row| strip_keys(row, k)).collect());
+        }
+
+        match self.result.as_mut() {
+            Some(result) => Ok(take_batch(result, self.context.batch_size, &self.schema)),
+            None => Ok(None),
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.result = None;
+        self.child.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.child.columns()
+    }
+}
+
+/// Union all operator, concatenating its inputs
+pub struct UnionAllOperator {
+    children: Vec<Box<dyn PhysicalOperator>>,
+    current: usize,
+    schema: Schema,
+    cardinality: Cardinality,
+}
+
+impl UnionAllOperator {
+    /// Concatenate the children in order
+    pub fn new(children: Vec<Box<dyn PhysicalOperator>>, cardinality: Cardinality) -> ExecutionResult<Self> {
+        let schema = children
+            .first()
+            .map(|c| c.schema().clone())
+            .ok_or_else(|| ExecutionError::InvalidState("Union without inputs".to_string()))?;
+        if children.iter().any(|c| c.schema().columns.len() != schema.columns.len()) {
+            return Err(ExecutionError::Failed(
+                "Union inputs differ in column count".to_string(),
+            ));
+        }
+        Ok(Self {
+            children,
+            current: 0,
+            schema,
+            cardinality,
+        })
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for UnionAllOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.current = 0;
+        for child in &mut self.children {
+            child.open().await?;
+        }
+        Ok(())
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        while let Some(child) = self.children.get_mut(self.current) {
+            if let Some(batch) = child.next().await? {
+                return Ok(Some(RowBatch {
+                    rows: batch.rows,
+                    schema: self.schema.clone(),
+                }));
+            }
+            self.current += 1;
+        }
+        Ok(None)
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        for child in &mut self.children {
+            child.close().await?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.children[0].columns()
+    }
+}
+
+/// Gather operator, running each input on its own task
+///
+/// Batches are forwarded as the inputs produce them, in no particular order.
+pub struct GatherOperator {
+    children: Vec<Box<dyn PhysicalOperator>>,
+    schema: Schema,
+    output_columns: Vec<ColumnRef>,
+    cardinality: Cardinality,
+    receiver: Option<mpsc::Receiver<ExecutionResult<RowBatch>>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl GatherOperator {
+    /// Collect the rows of children run in parallel
+    pub fn new(children: Vec<Box<dyn PhysicalOperator>>, cardinality: Cardinality) -> ExecutionResult<Self> {
+        let first = children
+            .first()
+            .ok_or_else(|| ExecutionError::InvalidState("Gather without inputs".to_string()))?;
+        Ok(Self {
+            schema: first.schema().clone(),
+            output_columns: first.columns(),
+            children,
+            cardinality,
+            receiver: None,
+            tasks: Vec::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for GatherOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        if self.children.is_empty() {
+            return Err(ExecutionError::InvalidState(
+                "Gather cannot be reopened".to_string(),
+            ));
+        }
+        let (sender, receiver) = mpsc::channel(self.children.len());
+        for mut child in self.children.drain(..) {
+            let sender = sender.clone();
+            self.tasks.push(tokio::spawn(async move {
+                let result = async {
+                    child.open().await?;
+                    while let Some(batch) = child.next().await? {
+                        if sender.send(Ok(batch)).await.is_err() {
+                            break;
+                        }
+                    }
+                    child.close().await
+                }
+                .await;
+                if let Err(e) = result {
+                    let _ = sender.send(Err(e)).await;
+                }
+            }));
+        }
+        self.receiver = Some(receiver);
+        Ok(())
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return Err(ExecutionError::InvalidState(
+                "Operator not opened".to_string(),
+            ));
+        };
+        match receiver.recv().await {
+            Some(Ok(batch)) => Ok(Some(batch)),
+            Some(Err(e)) => {
+                for task in &self.tasks {
+                    task.abort();
+                }
+                Err(e)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.receiver = None;
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        Ok(())
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.output_columns.clone()
+    }
+}
+
+/// Exchange operator, keeping the rows of one worker's partition
+///
+/// Outside a Gather there is a single consumer and all rows pass. Range
+/// distribution has no split points here and partitions by hash instead.
+pub struct ExchangeOperator {
+    child: Box<dyn PhysicalOperator>,
+    distribution: Distribution,
+    keys: Vec<BoundExpr>,
+    worker: Option<WorkerSlot>,
+    rows_seen: usize,
+    schema: Schema,
+    cardinality: Cardinality,
+}
+
+impl ExchangeOperator {
+    /// Create an exchange keeping one partition of its input
+    pub fn new(
+        distribution: Distribution,
+        child: Box<dyn PhysicalOperator>,
+        context: &ExecutionContext,
+        cardinality: Cardinality,
+    ) -> ExecutionResult<Self> {
+        let columns = child.columns();
+        let keys = match &distribution {
+            Distribution::Hash(keys) | Distribution::Range(keys) => keys
+                .iter()
+                .map(|k| BoundExpr::bind(k, &columns))
+                .collect::<ExecutionResult<_>>()?,
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            schema: child.schema().clone(),
+            child,
+            distribution,
+            keys,
+            worker: context.worker,
+            rows_seen: 0,
+            cardinality,
+        })
+    }
+
+    /// All rows to the first worker, none to the others
+    fn single(child: Box<dyn PhysicalOperator>, worker: WorkerSlot, cardinality: Cardinality) -> Self {
+        Self {
+            schema: child.schema().clone(),
+            child,
+            distribution: Distribution::Single,
+            keys: Vec::new(),
+            worker: Some(worker),
+            rows_seen: 0,
+            cardinality,
+        }
+    }
+
+    fn keeps(&mut self, row: &Row, worker: WorkerSlot) -> ExecutionResult<bool> {
+        Ok(match self.distribution {
+            Distribution::Hash(_) | Distribution::Range(_) => {
+                let key = self.keys.iter().map(|k| k.eval(row)).collect::<ExecutionResult<Vec<_>>>()?;
+                partition_of(&encode_key(&key), 0, worker.count) == worker.index
+            }
+            Distribution::RoundRobin => {
+                self.rows_seen += 1;
+                (self.rows_seen - 1) % worker.count == worker.index
+            }
+            Distribution::Broadcast => true,
+            Distribution::Single => worker.index == 0,
+        })
+    }
+}
+
+#[async_trait]
+impl PhysicalOperator for ExchangeOperator {
+    async fn open(&mut self) -> ExecutionResult<()> {
+        self.rows_seen = 0;
+        self.child.open().await
+    }
+
+    async fn next(&mut self) -> ExecutionResult<Option<RowBatch>> {
+        let Some(worker) = self.worker else {
+            return self.child.next().await;
+        };
+        if matches!(self.distribution, Distribution::Single) && worker.index != 0 {
+            return Ok(None);
+        }
+
+        while let Some(batch) = self.child.next().await? {
+            let mut rows = Vec::with_capacity(batch.rows.len() / worker.count + 1);
+            for row in batch.rows {
+                if self.keeps(&row, worker)? {
+                    rows.push(row);
+                }
+            }
+            if !rows.is_empty() {
+                return Ok(Some(RowBatch {
+                    rows,
+                    schema: batch.schema,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn close(&mut self) -> ExecutionResult<()> {
+        self.child.close().await
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn cardinality(&self) -> &Cardinality {
+        &self.cardinality
+    }
+
+    fn columns(&self) -> Vec<ColumnRef> {
+        self.child.columns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{AggFunc, BinaryOp};
+
+    #[tokio::test]
+    async fn test_executor_creation() {
+        let executor = QueryExecutor::with_default_context();
+        assert_eq!(executor.context.batch_size, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_row_batch() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Integer),
+            ColumnDef::new("name", DataType::Varchar(Some(255))),
+        ]);
+
+        let mut batch = RowBatch::new(schema);
+        assert!(batch.is_empty());
+
+        batch.add_row(Row::new(vec![Value::Integer(1), Value::String("Alice".to_string())]));
+        assert_eq!(batch.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_value_types() {
+        assert!(Value::Null.is_null());
+        assert!(!Value::Integer(42).is_null());
+
+        let val = Value::String("test".to_string());
+        assert_eq!(val, Value::String("test".to_string()));
+    }
+
+    fn col(table: &str, name: &str) -> ScalarExpr {
+        ScalarExpr::Column(ColumnRef::with_table(table, name))
+    }
+
+    fn eq(left: ScalarExpr, right: ScalarExpr) -> ScalarExpr {
+        ScalarExpr::BinaryOp {
+            left: Box::new(left),
+            op: BinaryOp::Eq,
+            right: Box::new(right),
+        }
+    }
+
+    fn node(op: PhysicalOp, children: Vec<PhysicalNode>) -> PhysicalNode {
+        PhysicalNode::new(op, children, Schema::empty(), Cost::zero(), Cardinality::unknown())
+    }
+
+    fn scan(table: &str) -> PhysicalNode {
+        node(
+            PhysicalOp::SeqScan {
+                table: table.to_string(),
+                alias: None,
+                predicates: vec![],
+                projection: None,
+            },
+            vec![],
+        )
+    }
+
+    fn aggregate(func: AggFunc, arg: Option<ScalarExpr>, alias: &str) -> AggregateFunction {
+        AggregateFunction {
+            func,
+            args: arg.into_iter().collect(),
+            distinct: false,
+            alias: Some(alias.to_string()),
+        }
+    }
+
+    /// `users(id, name)` with ids 0..n and `orders(user_id, amount)` with
+    /// two orders per even user plus one order of an unknown user
+    fn source(users: i64) -> Arc<dyn TableSource> {
+        let mut source = MemoryTableSource::new();
+        source.add_table(
+            "users",
+            Schema::new(vec![
+                ColumnDef::new("id", DataType::BigInt),
+                ColumnDef::new("name", DataType::Text),
+            ]),
+            (0..users)
+                .map(|i| Row::new(vec![Value::Integer(i), Value::String(format!("user{}", i))]))
+                .collect(),
+        );
+        let mut orders: Vec<Row> = (0..users)
+            .filter(|i| i % 2 == 0)
+            .flat_map(|i| [1, 2].map(|k| Row::new(vec![Value::Float(i as f64), Value::Integer(k * 10)])))
+            .collect();
+        orders.push(Row::new(vec![Value::Null, Value::Integer(5)]));
+        source.add_table(
+            "orders",
+            Schema::new(vec![
+                ColumnDef::new("user_id", DataType::Double),
+                ColumnDef::new("amount", DataType::BigInt),
+            ]),
+            orders,
+        );
+        Arc::new(source)
+    }
+
+    async fn run(root: PhysicalNode, context: ExecutionContext) -> Vec<Row> {
+        let executor = QueryExecutor::new(context);
+        let batches = executor.execute_collect(PhysicalPlan::new(root, Cost::zero())).await.unwrap();
+        batches.into_iter().flat_map(|b| b.rows).collect()
+    }
+
+    fn context(users: i64, memory_limit: usize) -> ExecutionContext {
+        ExecutionContext {
+            batch_size: 16,
+            operator_memory_limit: memory_limit,
+            ..ExecutionContext::default()
+        }
+        .with_table_source(source(users))
+    }
+
+    fn join(op: fn(JoinType, Option<ScalarExpr>) -> PhysicalOp, join_type: JoinType) -> PhysicalNode {
+        let condition = eq(col("users", "id"), col("orders", "user_id"));
+        node(op(join_type, Some(condition)), vec![scan("users"), scan("orders")])
+    }
+
+    fn hash_join(join_type: JoinType, condition: Option<ScalarExpr>) -> PhysicalOp {
+        PhysicalOp::HashJoin {
+            join_type,
+            left_keys: vec![],
+            right_keys: vec![],
+            condition,
+        }
+    }
+
+    fn merge_join(join_type: JoinType, condition: Option<ScalarExpr>) -> PhysicalOp {
+        PhysicalOp::MergeJoin {
+            join_type,
+            left_keys: vec![],
+            right_keys: vec![],
+            condition,
+        }
+    }
+
+    fn nested_loop_join(join_type: JoinType, condition: Option<ScalarExpr>) -> PhysicalOp {
+        PhysicalOp::NestedLoopJoin { join_type, condition }
+    }
+
+    #[tokio::test]
+    async fn test_joins_agree_across_algorithms_and_spill() {
+        // 50 users: the 25 even ids have two orders each, giving 50 matches.
+        // Left adds the 25 odd users without orders (75), right adds the
+        // order with a NULL user (51) and full adds both (76). Semi keeps
+        // the 25 users with orders and anti-semi the 25 without.
+        let expected = [
+            (JoinType::Inner, 50),
+            (JoinType::Left, 75),
+            (JoinType::Right, 51),
+            (JoinType::Full, 76),
+            (JoinType::Semi, 25),
+            (JoinType::AntiSemi, 25),
+        ];
+        let algorithms: [fn(JoinType, Option<ScalarExpr>) -> PhysicalOp; 3] =
+            [hash_join, merge_join, nested_loop_join];
+
+        for (join_type, rows) in expected {
+            for algorithm in algorithms {
+                for memory_limit in [usize::MAX, 512] {
+                    let result = run(join(algorithm, join_type), context(50, memory_limit)).await;
+                    assert_eq!(result.len(), rows, "{:?} with limit {}", join_type, memory_limit);
+                    if join_type == JoinType::Inner {
+                        // id = user_id holds across integer and float keys
+                        assert!(result.iter().all(|r| r.values.len() == 4
+                            && compare_values(&r.values[0], &r.values[2]) == Ordering::Equal));
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hash_join_residual_condition() {
+        let condition = ScalarExpr::BinaryOp {
+            left: Box::new(eq(col("users", "id"), col("orders", "user_id"))),
+            op: BinaryOp::And,
+            right: Box::new(ScalarExpr::BinaryOp {
+                left: Box::new(col("orders", "amount")),
+                op: BinaryOp::Gt,
+                right: Box::new(ScalarExpr::Literal(Literal::Integer(10))),
+            }),
+        };
+        let plan = node(hash_join(JoinType::Left, Some(condition)), vec![scan("users"), scan("orders")]);
+        let rows = run(plan, context(10, usize::MAX)).await;
+        // One match per even user, odd users padded with NULLs
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows.iter().filter(|r| r.values[3] == Value::Integer(20)).count(), 5);
+    }
+
+    fn grouped_totals(op: fn(Vec<ScalarExpr>, Vec<AggregateFunction>, Option<ScalarExpr>) -> PhysicalOp) -> PhysicalNode {
+        let having = ScalarExpr::BinaryOp {
+            left: Box::new(ScalarExpr::Column(ColumnRef::new("n"))),
+            op: BinaryOp::Gt,
+            right: Box::new(ScalarExpr::Literal(Literal::Integer(1))),
+        };
+        node(
+            op(
+                vec![col("orders", "user_id")],
+                vec![
+                    aggregate(AggFunc::Count, None, "n"),
+                    aggregate(AggFunc::Sum, Some(col("orders", "amount")), "total"),
+                    aggregate(AggFunc::Avg, Some(col("orders", "amount")), "mean"),
+                ],
+                Some(having),
+            ),
+            vec![scan("orders")],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_hash_join_hot_key_over_memory_limit() {
+        // 200 build rows share key 1, so no hash split brings that partition
+        // under the limit and it falls back to a nested loop
+        let mut source = MemoryTableSource::new();
+        let schema = || Schema::new(vec![ColumnDef::new("k", DataType::BigInt), ColumnDef::new("v", DataType::BigInt)]);
+        let row = |k: Value, v: i64| Row::new(vec![k, Value::Integer(v)]);
+        source.add_table(
+            "probe",
+            schema(),
+            vec![row(Value::Integer(1), 0), row(Value::Integer(1), 1), row(Value::Integer(2), 2), row(Value::Null, 3)],
+        );
+        let mut build: Vec<Row> = (0..200).map(|v| row(Value::Integer(1), v)).collect();
+        build.push(row(Value::Integer(3), 200));
+        source.add_table("build", schema(), build);
+        let source: Arc<dyn TableSource> = Arc::new(source);
+
+        // 2 x 200 matches; probe keys 2 and NULL and build key 3 are unmatched
+        let expected = [
+            (JoinType::Inner, 400),
+            (JoinType::Left, 402),
+            (JoinType::Right, 401),
+            (JoinType::Full, 403),
+            (JoinType::Semi, 2),
+            (JoinType::AntiSemi, 2),
+        ];
+        for (join_type, rows) in expected {
+            let condition = eq(col("probe", "k"), col("build", "k"));
+            let plan = node(hash_join(join_type, Some(condition)), vec![scan("probe"), scan("build")]);
+            let context = ExecutionContext {
+                batch_size: 16,
+                operator_memory_limit: 512,
+                ..ExecutionContext::default()
+            }
+            .with_table_source(Arc::clone(&source));
+            assert_eq!(run(plan, context).await.len(), rows, "{:?}", join_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregates_with_spill() {
+        let hash = |group_by, aggregates, having| PhysicalOp::HashAggregate {
+            group_by,
+            aggregates,
+            having,
+        };
+        let sort = |group_by, aggregates, having| PhysicalOp::SortAggregate {
+            group_by,
+            aggregates,
+            having,
+        };
+
+        for op in [hash as fn(_, _, _) -> _, sort] {
+            for memory_limit in [usize::MAX, 256] {
+                let mut rows = run(grouped_totals(op), context(200, memory_limit)).await;
+                // The NULL user group has a single order and fails HAVING
+                assert_eq!(rows.len(), 100);
+                rows.sort_by(|a, b| compare_values(&a.values[0], &b.values[0]));
+                assert_eq!(rows[3].values, vec![
+                    Value::Float(6.0),
+                    Value::Integer(2),
+                    Value::Integer(30),
+                    Value::Float(15.0),
+                ]);
+            }
+        }
+
+        // A global aggregate over no rows still produces one row
+        let empty = node(
+            PhysicalOp::HashAggregate {
+                group_by: vec![],
+                aggregates: vec![aggregate(AggFunc::Count, None, "n")],
+                having: None,
+            },
+            vec![scan("users")],
+        );
+        assert_eq!(run(empty, context(0, usize::MAX)).await[0].values, vec![Value::Integer(0)]);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_aggregate_and_join() {
+        let aggregates = vec![
+            aggregate(AggFunc::Count, None, "n"),
+            aggregate(AggFunc::Avg, Some(col("users", "id")), "mean"),
+        ];
+        let partial = node(
+            PhysicalOp::HashAggregate {
+                group_by: vec![],
+                aggregates: aggregates.clone(),
+                having: None,
+            },
+            vec![scan("users")],
+        );
+        let plan = node(
+            PhysicalOp::HashAggregate {
+                group_by: vec![],
+                aggregates,
+                having: None,
+            },
+            vec![node(PhysicalOp::Gather { num_workers: 4 }, vec![partial])],
+        );
+        let rows = run(plan, context(100, usize::MAX)).await;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, vec![Value::Integer(100), Value::Float(49.5)]);
+
+        // Parallel join as laid out by the parallel planner
+        let exchange = |child, key| {
+            node(
+                PhysicalOp::Exchange {
+                    distribution: Distribution::Hash(vec![key]),
+                    num_partitions: 3,
+                },
+                vec![child],
+            )
+        };
+        let plan = node(
+            PhysicalOp::Gather { num_workers: 3 },
+            vec![node(
+                hash_join(JoinType::Full, None),
+                vec![
+                    exchange(scan("users"), col("users", "id")),
+                    exchange(scan("orders"), col("orders", "user_id")),
+                ],
+            )],
+        );
+        let plan = PhysicalNode {
+            children: vec![PhysicalNode {
+                op: PhysicalOp::HashJoin {
+                    join_type: JoinType::Full,
+                    left_keys: vec![col("users", "id")],
+                    right_keys: vec![col("orders", "user_id")],
+                    condition: None,
+                },
+                ..plan.children[0].clone()
+            }],
+            ..plan
+        };
+        // 50 matches, 25 users without orders and 1 order without a user
+        assert_eq!(run(plan, context(50, usize::MAX)).await.len(), 76);
+    }
+
+    #[tokio::test]
+    async fn test_sort_and_top_n() {
+        let order_by = vec![
+            OrderByItem {
+                expr: col("orders", "amount"),
+                direction: SortDirection::Descending,
+                nulls_first: false,
+            },
+            OrderByItem {
+                expr: col("orders", "user_id"),
+                direction: SortDirection::Ascending,
+                nulls_first: true,
+            },
+        ];
+
+        let sorted = run(
+            node(PhysicalOp::Sort { order_by: order_by.clone() }, vec![scan("orders")]),
+            context(100, 512),
+        )
+        .await;
+        assert_eq!(sorted.len(), 101);
+        assert_eq!(sorted[0].values, vec![Value::Float(0.0), Value::Integer(20)]);
+        assert_eq!(sorted[49].values, vec![Value::Float(98.0), Value::Integer(20)]);
+        assert_eq!(sorted[100].values, vec![Value::Null, Value::Integer(5)]);
+
+        let top = run(
+            node(PhysicalOp::TopNSort { order_by, limit: 3 }, vec![scan("orders")]),
+            context(100, usize::MAX),
+        )
+        .await;
+        let expected: Vec<Row> = sorted.into_iter().take(3).collect();
+        assert_eq!(top.len(), 3);
+        for (a, b) in top.iter().zip(&expected) {
+            assert_eq!(a.values, b.values);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_project_and_distinct() {
+        let filtered = node(
+            PhysicalOp::Filter {
+                predicates: vec![ScalarExpr::BinaryOp {
+                    left: Box::new(col("orders", "amount")),
+                    op: BinaryOp::GtEq,
+                    right: Box::new(ScalarExpr::Literal(Literal::Integer(10))),
+                }],
+            },
+            vec![scan("orders")],
+        );
+        let projected = node(
+            PhysicalOp::Project {
+                projections: vec![ProjectionItem {
+                    expr: ScalarExpr::BinaryOp {
+                        left: Box::new(col("orders", "amount")),
+                        op: BinaryOp::Divide,
+                        right: Box::new(ScalarExpr::Literal(Literal::Integer(10))),
+                    },
+                    alias: Some("tens".to_string()),
+                }],
+            },
+            vec![filtered],
+        );
+        let rows = run(node(PhysicalOp::SortDistinct, vec![projected]), context(20, usize::MAX)).await;
+        let values: Vec<Value> = rows.into_iter().map(|r| r.values[0].clone()).collect();
+        assert_eq!(values, vec![Value::Integer(1), Value::Integer(2)]);
+    }
+
+    /// `places(id, geom)` with a point in the middle of each unit square of
+    /// a 10x10 grid plus a NULL geometry, and `zones(name, geom)` with a
+    /// 2x2 square per zone over the same grid plus one far away zone
+    fn spatial_context(memory_limit: usize) -> ExecutionContext {
+        let mut source = MemoryTableSource::new();
+        let mut places: Vec<Row> = (0..100)
+            .map(|i| {
+                let point = format!("POINT({}.5 {}.5)", i % 10, i / 10);
+                Row::new(vec![Value::Integer(i), Value::String(point)])
+            })
+            .collect();
+        places.push(Row::new(vec![Value::Integer(100), Value::Null]));
+        source.add_table(
+            "places",
+            Schema::new(vec![
+                ColumnDef::new("id", DataType::BigInt),
+                ColumnDef::new("geom", DataType::Geometry),
+            ]),
+            places,
+        );
+        let square = |x: i64, y: i64, size: i64| {
+            let (x1, y1) = (x + size, y + size);
+            format!("POLYGON(({x} {y}, {x1} {y}, {x1} {y1}, {x} {y1}, {x} {y}))")
+        };
+        let mut zones: Vec<Row> = (0..25)
+            .map(|k| {
+                let geom = square(k % 5 * 2, k / 5 * 2, 2);
+                Row::new(vec![Value::String(format!("zone{}", k)), Value::String(geom)])
+            })
+            .collect();
+        zones.push(Row::new(vec![Value::String("remote".to_string()), Value::String(square(100, 100, 1))]));
+        source.add_table(
+            "zones",
+            Schema::new(vec![
+                ColumnDef::new("name", DataType::Text),
+                ColumnDef::new("geom", DataType::Geometry),
+            ]),
+            zones,
+        );
+        ExecutionContext {
+            batch_size: 16,
+            operator_memory_limit: memory_limit,
+            ..ExecutionContext::default()
+        }
+        .with_table_source(Arc::new(source))
+    }
+
+    fn spatial_join(
+        join_type: JoinType,
+        condition: ScalarExpr,
+        strategy: SpatialJoinStrategy,
+    ) -> PhysicalNode {
+        let (spatial, residual) = crate::spatial::split_spatial_join_condition(&condition).unwrap();
+        let op = PhysicalOp::SpatialJoin {
+            join_type,
+            predicate: spatial.predicate,
+            left_geometry: spatial.left,
+            right_geometry: spatial.right,
+            distance: spatial.distance,
+            condition: residual,
+            strategy,
+        };
+        node(op, vec![scan("places"), scan("zones")])
+    }
+
+    #[tokio::test]
+    async fn test_spatial_join_agrees_across_strategies_and_spill() {
+        let function = |name: &str, args: Vec<ScalarExpr>| ScalarExpr::Function {
+            name: name.to_string(),
+            args,
+        };
+        // Arguments in zones, places order exercise the converse predicate
+        let contains = function("ST_Contains", vec![col("zones", "geom"), col("places", "geom")]);
+        let dwithin = function(
+            "ST_DWithin",
+            vec![
+                col("places", "geom"),
+                col("zones", "geom"),
+                ScalarExpr::Literal(Literal::Float(0.75)),
+            ],
+        );
+        let configurations = [
+            (SpatialJoinStrategy::IndexNestedLoop, 512),
+            (SpatialJoinStrategy::PartitionMerge, usize::MAX),
+            (SpatialJoinStrategy::PartitionMerge, 512),
+        ];
+
+        let expected = [
+            (JoinType::Inner, 100),
+            (JoinType::Left, 101),
+            (JoinType::Right, 101),
+            (JoinType::Full, 102),
+            (JoinType::Semi, 100),
+            (JoinType::AntiSemi, 1),
+        ];
+        for (join_type, rows) in expected {
+            let result = run(
+                spatial_join(join_type, contains.clone(), SpatialJoinStrategy::IndexNestedLoop),
+                spatial_context(usize::MAX),
+            )
+            .await;
+            assert_eq!(result.len(), rows, "{:?}", join_type);
+            for (strategy, memory_limit) in configurations {
+                let plan = spatial_join(join_type, contains.clone(), strategy);
+                let result = run(plan, spatial_context(memory_limit)).await;
+                assert_eq!(result.len(), rows, "{:?} {:?} with limit {}", join_type, strategy, memory_limit);
+            }
+        }
+
+        // Points within 0.75 of a zone border also match the neighbouring zone
+        let expected = run(
+            spatial_join(JoinType::Inner, dwithin.clone(), SpatialJoinStrategy::IndexNestedLoop),
+            spatial_context(usize::MAX),
+        )
+        .await
+        .len();
+        assert_eq!(expected, 100 + 2 * 80 + 4 * 16);
+        for (strategy, memory_limit) in configurations {
+            let plan = spatial_join(JoinType::Inner, dwithin.clone(), strategy);
+            let result = run(plan, spatial_context(memory_limit)).await;
+            assert_eq!(result.len(), expected, "{:?} with limit {}", strategy, memory_limit);
+        }
     }
 }
@@ -194,6 +194,22 @@ impl ExplainFormatter {
                 }
             }
 
+            PhysicalOp::SpatialJoin {
+                join_type,
+                predicate,
+                strategy,
+                ..
+            } => {
+                write!(output, "Spatial {:?} Join", join_type).unwrap();
+                if self.options.verbose {
+                    let strategy = match strategy {
+                        SpatialJoinStrategy::IndexNestedLoop => "R-tree index nested loop",
+                        SpatialJoinStrategy::PartitionMerge => "partition-based spatial merge",
+                    };
+                    write!(output, " [{}, {}]", predicate, strategy).unwrap();
+                }
+            }
+
             PhysicalOp::HashAggregate { group_by, aggregates, .. } => {
                 write!(output, "Hash Aggregate").unwrap();
                 if self.options.verbose {
@@ -364,7 +380,8 @@ impl CostBreakdown {
 
             PhysicalOp::NestedLoopJoin { .. }
             | PhysicalOp::HashJoin { .. }
-            | PhysicalOp::MergeJoin { .. } => {
+            | PhysicalOp::MergeJoin { .. }
+            | PhysicalOp::SpatialJoin { .. } => {
                 self.join_cost += node.cost.total_cost;
             }
 
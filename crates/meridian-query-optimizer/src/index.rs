@@ -4,6 +4,7 @@
 
 use crate::ast::*;
 use crate::plan::*;
+use crate::spatial::SpatialCondition;
 use crate::statistics::{ColumnStatistics, TableStatistics};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -100,7 +101,7 @@ impl IndexSelector {
 
         candidates.first().map(|(index, score)| {
             let (key_conditions, filter_conditions) =
-                self.split_predicates_for_index(predicates, &index.columns);
+                self.split_predicates_for_index(predicates, index);
 
             IndexSelection {
                 index: index.clone(),
@@ -122,7 +123,7 @@ impl IndexSelector {
         let mut score = 0.0;
 
         // Score based on predicate coverage
-        let covered_columns = self.count_covered_columns(predicates, &index.columns);
+        let covered_columns = self.count_covered_columns(predicates, index);
         score += covered_columns as f64 * 10.0;
 
         // Bonus for covering all predicates
@@ -139,6 +140,22 @@ impl IndexSelector {
             score += stats.clustering_factor * 15.0;
         }
 
+        // Score spatial predicates by the fraction of the layer they select
+        if index.index_type == IndexType::GiST {
+            for condition in predicates.iter().filter_map(SpatialCondition::from_expr) {
+                let Some(window) = condition.window() else {
+                    continue;
+                };
+                if index.columns.contains(&window.column.name) {
+                    let selectivity = table_stats.map_or(
+                        crate::statistics::DEFAULT_SPATIAL_SELECTIVITY,
+                        |stats| stats.estimate_spatial_selectivity(&window.column.name, &window.envelope, window.within),
+                    );
+                    score += (1.0 - selectivity) * 30.0;
+                }
+            }
+        }
+
         // Score based on ORDER BY coverage
         if let Some(order_by_items) = order_by {
             if self.index_covers_order_by(index, order_by_items) {
@@ -160,13 +177,16 @@ impl IndexSelector {
     }
 
     /// Count how many columns in predicates are covered by index
-    fn count_covered_columns(&self, predicates: &[ScalarExpr], index_columns: &[String]) -> usize {
+    fn count_covered_columns(&self, predicates: &[ScalarExpr], index: &IndexDefinition) -> usize {
         let mut covered = 0;
 
         for pred in predicates {
+            if !self.index_supports_predicate(index, pred) {
+                continue;
+            }
             let pred_columns = self.extract_predicate_columns(pred);
             for pred_col in pred_columns {
-                if index_columns.contains(&pred_col) {
+                if index.columns.contains(&pred_col) {
                     covered += 1;
                     break;
                 }
@@ -186,10 +206,19 @@ impl IndexSelector {
                 cols
             }
             ScalarExpr::UnaryOp { expr, .. } => self.extract_predicate_columns(expr),
+            ScalarExpr::Function { .. } => match SpatialCondition::from_expr(expr) {
+                Some(condition) => condition.columns().into_iter().map(|c| c.name.clone()).collect(),
+                None => vec![],
+            },
             _ => vec![],
         }
     }
 
+    /// Spatial predicates can only be answered by GiST indexes
+    fn index_supports_predicate(&self, index: &IndexDefinition, pred: &ScalarExpr) -> bool {
+        SpatialCondition::from_expr(pred).is_none() || index.index_type == IndexType::GiST
+    }
+
     /// Check if index can satisfy ORDER BY without additional sort
     fn index_covers_order_by(&self, index: &IndexDefinition, order_by: &[OrderByItem]) -> bool {
         if order_by.is_empty() {
@@ -235,7 +264,7 @@ impl IndexSelector {
     fn split_predicates_for_index(
         &self,
         predicates: &[ScalarExpr],
-        index_columns: &[String],
+        index: &IndexDefinition,
     ) -> (Vec<ScalarExpr>, Vec<ScalarExpr>) {
         let mut key_conditions = Vec::new();
         let mut filter_conditions = Vec::new();
@@ -246,9 +275,10 @@ impl IndexSelector {
             // Can use as key condition if:
             // 1. Uses only one column
             // 2. That column is in the index
-            // 3. It's an equality or range condition
-            if pred_columns.len() == 1 && index_columns.contains(&pred_columns[0]) {
-                if self.is_indexable_condition(pred) {
+            // 3. It's an equality or range condition, or a spatial
+            //    predicate on a GiST index
+            if pred_columns.len() == 1 && index.columns.contains(&pred_columns[0]) {
+                if self.is_indexable_condition(pred, index.index_type) {
                     key_conditions.push(pred.clone());
                     continue;
                 }
@@ -261,7 +291,11 @@ impl IndexSelector {
     }
 
     /// Check if condition can use index
-    fn is_indexable_condition(&self, expr: &ScalarExpr) -> bool {
+    fn is_indexable_condition(&self, expr: &ScalarExpr, index_type: IndexType) -> bool {
+        if let Some(condition) = SpatialCondition::from_expr(expr) {
+            // The other side must be known before the scan starts
+            return index_type == IndexType::GiST && condition.window().is_some();
+        }
         matches!(
             expr,
             ScalarExpr::BinaryOp {
@@ -294,9 +328,10 @@ impl IndexSelector {
 
             for index in table_indexes {
                 // Find indexes that cover this predicate
-                if pred_columns
-                    .iter()
-                    .all(|col| index.columns.contains(col))
+                if self.index_supports_predicate(index, pred)
+                    && pred_columns
+                        .iter()
+                        .all(|col| index.columns.contains(col))
                 {
                     if !selected_indexes.iter().any(|idx: &IndexDefinition| idx.name == index.name) {
                         selected_indexes.push(index.clone());
@@ -522,4 +557,41 @@ mod tests {
         assert_eq!(index.index_type, IndexType::BTree);
         assert!(index.unique);
     }
+
+    #[test]
+    fn test_gist_index_for_spatial_predicates() {
+        let mut selector = IndexSelector::new();
+        for (name, index_type) in [("parcels_geom_btree", IndexType::BTree), ("parcels_geom_gist", IndexType::GiST)] {
+            selector.add_index(IndexDefinition {
+                name: name.to_string(),
+                table: "parcels".to_string(),
+                columns: vec!["geom".to_string()],
+                index_type,
+                unique: false,
+                partial: None,
+                statistics: None,
+            });
+        }
+
+        let envelope = ScalarExpr::Function {
+            name: "ST_MakeEnvelope".to_string(),
+            args: [0, 0, 10, 10].iter().map(|&n| ScalarExpr::Literal(Literal::Integer(n))).collect(),
+        };
+        let predicates = vec![
+            ScalarExpr::BinaryOp {
+                left: Box::new(ScalarExpr::Column(ColumnRef::new("geom"))),
+                op: BinaryOp::BboxIntersects,
+                right: Box::new(envelope.clone()),
+            },
+            ScalarExpr::Function {
+                name: "ST_Intersects".to_string(),
+                args: vec![ScalarExpr::Column(ColumnRef::new("geom")), envelope],
+            },
+        ];
+
+        let selection = selector.select_index_for_scan("parcels", &predicates, None).unwrap();
+        assert_eq!(selection.index.index_type, IndexType::GiST);
+        assert_eq!(selection.key_conditions.len(), 2);
+        assert!(selection.filter_conditions.is_empty());
+    }
 }
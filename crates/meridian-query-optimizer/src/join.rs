@@ -5,10 +5,12 @@
 //! - Hash Join (fast for equi-joins)
 //! - Merge Join (efficient for sorted inputs)
 //! - Index Nested Loop Join (uses indexes on inner table)
+//! - Spatial Join (R-tree probe or partition-based spatial merge)
 
 use crate::ast::*;
 use crate::cost::{CostConfig, CostEstimator};
 use crate::plan::*;
+use crate::spatial::split_spatial_join_condition;
 use crate::statistics::TableStatistics;
 use std::collections::HashMap;
 
@@ -91,6 +93,25 @@ impl JoinOptimizer {
             }
         }
 
+        // Spatial Join - for conditions with a spatial predicate
+        if let Some((spatial, residual)) = condition.as_ref().and_then(split_spatial_join_condition) {
+            for strategy in [SpatialJoinStrategy::IndexNestedLoop, SpatialJoinStrategy::PartitionMerge] {
+                let op = PhysicalOp::SpatialJoin {
+                    join_type,
+                    predicate: spatial.predicate,
+                    left_geometry: spatial.left.clone(),
+                    right_geometry: spatial.right.clone(),
+                    distance: spatial.distance,
+                    condition: residual.clone(),
+                    strategy,
+                };
+                let cost = self
+                    .cost_estimator
+                    .estimate_operator_cost(&op, &[left.clone(), right.clone()]);
+                candidates.push((op, cost));
+            }
+        }
+
         // Select algorithm with lowest cost
         candidates
             .into_iter()
@@ -505,6 +526,7 @@ impl JoinStrategySelector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::spatial::SpatialPredicate;
 
     #[test]
     fn test_join_optimizer_creation() {
@@ -555,4 +577,44 @@ mod tests {
 
         // Would test with actual physical nodes
     }
+
+    #[test]
+    fn test_spatial_join_selected() {
+        let optimizer = JoinOptimizer::with_default_config();
+        let scan = |table: &str| {
+            let op = PhysicalOp::SeqScan {
+                table: table.to_string(),
+                alias: None,
+                predicates: vec![],
+                projection: None,
+            };
+            PhysicalNode::new(op, vec![], Schema::empty(), Cost::new(100.0, 100.0, 0.0, 0.0), Cardinality::new(10000.0))
+        };
+        let intersects = ScalarExpr::Function {
+            name: "ST_Intersects".to_string(),
+            args: vec![
+                ScalarExpr::Column(ColumnRef::with_table("p", "geom")),
+                ScalarExpr::Column(ColumnRef::with_table("z", "geom")),
+            ],
+        };
+        let kind = ScalarExpr::BinaryOp {
+            left: Box::new(ScalarExpr::Column(ColumnRef::with_table("z", "kind"))),
+            op: BinaryOp::Eq,
+            right: Box::new(ScalarExpr::Literal(Literal::String("flood".to_string()))),
+        };
+        let condition = ScalarExpr::BinaryOp {
+            left: Box::new(intersects),
+            op: BinaryOp::And,
+            right: Box::new(kind),
+        };
+
+        let op = optimizer.select_join_algorithm(JoinType::Inner, &Some(condition), &scan("parcels"), &scan("zones"));
+        match op {
+            PhysicalOp::SpatialJoin { predicate, condition, .. } => {
+                assert_eq!(predicate, SpatialPredicate::Intersects);
+                assert!(matches!(condition, Some(ScalarExpr::BinaryOp { op: BinaryOp::Eq, .. })));
+            }
+            other => panic!("expected a spatial join, got {:?}", other),
+        }
+    }
 }
@@ -7,8 +7,8 @@
 //! - **Advanced SQL Parsing**: Multi-dialect support (PostgreSQL, MySQL, SQLite)
 //! - **Cost-Based Optimization**: Sophisticated cost models for physical operators
 //! - **Rule-Based Transformations**: Predicate pushdown, join reordering, projection pruning
-//! - **Join Optimization**: Multiple join algorithms (nested loop, hash, merge)
-//! - **Index Selection**: Intelligent index usage and recommendations
+//! - **Join Optimization**: Multiple join algorithms (nested loop, hash, merge, spatial)
+//! - **Index Selection**: Intelligent index usage and recommendations, GiST for spatial predicates
 //! - **Parallel Execution**: Automatic parallelization for large queries
 //! - **Query Plan Caching**: Fast plan reuse for repeated queries
 //! - **EXPLAIN Support**: Detailed execution plan visualization
@@ -65,15 +65,23 @@ pub mod parallel;
 pub mod parser;
 pub mod plan;
 pub mod rules;
+pub mod spatial;
 pub mod statistics;
 
+mod aggregate;
+mod eval;
+mod spill;
+
 // Re-exports for convenience
 pub use ast::{
     BinaryOp, ColumnRef, DataType, JoinType, Literal, QueryExpr, RelExpr, ScalarExpr, Schema,
 };
 pub use cache::{PlanCache, PlanCacheConfig, PreparedStatementCache};
 pub use cost::{CostConfig, CostEstimator};
-pub use executor::{ExecutionContext, ExecutionStats, QueryExecutor, RowBatch, Value};
+pub use executor::{
+    AggregateMode, ExecutionContext, ExecutionStats, MemoryTableSource, QueryExecutor, RowBatch,
+    TableSource, Value,
+};
 pub use explain::{ExplainFormat, ExplainFormatter, ExplainOptions};
 pub use index::{IndexDefinition, IndexRecommender, IndexSelector, IndexType};
 pub use join::{JoinOptimizer, JoinOrderOptimizer};
@@ -81,8 +89,10 @@ pub use parallel::{ParallelConfig, ParallelPlanner};
 pub use parser::{QueryParser, SqlDialect};
 pub use plan::{Cardinality, Cost, LogicalPlan, PhysicalPlan};
 pub use rules::{OptimizationRule, RuleBasedOptimizer};
+pub use spatial::{Envelope, SpatialCondition, SpatialPredicate};
 pub use statistics::{
-    ColumnStatistics, Histogram, StatisticsCollector, StatisticsManager, TableStatistics,
+    ColumnStatistics, Histogram, SpatialHistogram, StatisticsCollector, StatisticsManager,
+    TableStatistics,
 };
 
 use thiserror::Error;
@@ -277,6 +287,14 @@ impl QueryOptimizer {
     fn create_physical_node(&self, node: &plan::LogicalNode) -> PhysicalPlan {
         use plan::{LogicalOp, PhysicalNode, PhysicalOp};
 
+        let children: Vec<PhysicalNode> = node
+            .children
+            .iter()
+            .map(|child| self.create_physical_node(child).root)
+            .collect();
+
+        let mut physical_op = self.convert_logical_op(&node.op);
+
         let (cost, cardinality) = match &node.op {
             LogicalOp::Scan {
                 table,
@@ -292,17 +310,35 @@ impl QueryOptimizer {
                 },
                 &[],
             ),
+            LogicalOp::Join {
+                join_type,
+                condition: Some(condition),
+            } if children.len() == 2 => {
+                match spatial::split_spatial_join_condition(condition) {
+                    Some((spatial, residual)) => {
+                        let strategy = self.cost_estimator.choose_spatial_join_strategy(
+                            join_type,
+                            (&spatial.left, &spatial.right),
+                            spatial.distance.unwrap_or(0.0),
+                            &children,
+                        );
+                        physical_op = PhysicalOp::SpatialJoin {
+                            join_type: *join_type,
+                            predicate: spatial.predicate,
+                            left_geometry: spatial.left,
+                            right_geometry: spatial.right,
+                            distance: spatial.distance,
+                            condition: residual,
+                            strategy,
+                        };
+                        self.cost_estimator.estimate_operator_cost(&physical_op, &children)
+                    }
+                    None => (Cost::zero(), Cardinality::unknown()),
+                }
+            }
             _ => (Cost::zero(), Cardinality::unknown()),
         };
 
-        let physical_op = self.convert_logical_op(&node.op);
-
-        let children = node
-            .children
-            .iter()
-            .map(|child| self.create_physical_node(child).root)
-            .collect();
-
         let physical_node = PhysicalNode::new(
             physical_op,
             children,
@@ -314,7 +350,7 @@ impl QueryOptimizer {
         PhysicalPlan::new(physical_node, cost)
     }
 
-    fn convert_logical_op(&self, op: &plan::LogicalOp) -> PhysicalOp {
+    fn convert_logical_op(&self, op: &plan::LogicalOp) -> plan::PhysicalOp {
         use plan::{LogicalOp, PhysicalOp};
 
         match op {
@@ -2,6 +2,7 @@
 //!
 //! Determines when and how to parallelize query execution across multiple workers.
 
+use crate::ast::{AggregateFunction, JoinType, ScalarExpr};
 use crate::plan::*;
 use serde::{Deserialize, Serialize};
 
@@ -23,7 +24,7 @@ pub struct ParallelConfig {
 impl Default for ParallelConfig {
     fn default() -> Self {
         Self {
-            max_workers: num_cpus::get(),
+            max_workers: num_cpus::get().max(2),
             min_parallel_table_scan_size: 100_000,
             min_parallel_cost: 1000.0,
             parallel_setup_cost: 1000.0,
@@ -75,7 +76,7 @@ impl ParallelPlanner {
 
     /// Parallelize a physical node
     fn parallelize_node(&self, node: PhysicalNode, level: usize) -> PhysicalNode {
-        match node.op {
+        match node.op.clone() {
             // Parallelize table scans
             PhysicalOp::SeqScan { .. } if node.cardinality.rows >= self.config.min_parallel_table_scan_size as f64 => {
                 self.create_parallel_scan(node)
@@ -247,7 +248,7 @@ impl ParallelPlanner {
     fn calculate_optimal_workers(&self, estimated_rows: f64) -> usize {
         // Simple heuristic: more rows = more workers
         let workers = (estimated_rows / 100_000.0).sqrt().ceil() as usize;
-        workers.clamp(2, self.config.max_workers)
+        workers.clamp(2, self.config.max_workers.max(2))
     }
 
     /// Estimate cost of parallel execution
@@ -3,6 +3,7 @@
 //! Converts SQL text into our internal AST representation for optimization.
 
 use crate::ast::*;
+use crate::spatial::SpatialPredicate;
 use sqlparser::ast as sql;
 use sqlparser::dialect::{Dialect, GenericDialect, PostgreSqlDialect, MySqlDialect, SQLiteDialect};
 use sqlparser::parser::Parser;
@@ -95,9 +96,9 @@ impl QueryParser {
         let rel_expr = self.convert_query_body(&query.body)?;
 
         // Apply ORDER BY if present
-        let rel_expr = if !query.order_by.is_empty() {
-            let order_by = query
-                .order_by
+        let order_by_exprs = query.order_by.as_ref().map_or(&[][..], |o| &o.exprs[..]);
+        let rel_expr = if !order_by_exprs.is_empty() {
+            let order_by = order_by_exprs
                 .iter()
                 .map(|o| self.convert_order_by(o))
                 .collect::<Result<Vec<_>>>()?;
@@ -179,9 +180,14 @@ impl QueryParser {
         }
 
         // Apply GROUP BY and aggregates
-        if !select.group_by.is_empty() || self.has_aggregates(&select.projection) {
-            let group_by = select
-                .group_by
+        let group_by_exprs = match &select.group_by {
+            sql::GroupByExpr::Expressions(exprs, modifiers) if modifiers.is_empty() => exprs,
+            other => {
+                return Err(ParseError::UnsupportedFeature(format!("GROUP BY: {}", other)));
+            }
+        };
+        if !group_by_exprs.is_empty() || self.has_aggregates(&select.projection) {
+            let group_by = group_by_exprs
                 .iter()
                 .map(|e| self.convert_expr(e))
                 .collect::<Result<Vec<_>>>()?;
@@ -385,8 +391,16 @@ impl QueryParser {
 
             sql::Expr::Function(func) => {
                 let name = func.name.to_string();
-                let args = func
-                    .args
+                let args = match &func.args {
+                    sql::FunctionArguments::None => &[][..],
+                    sql::FunctionArguments::List(list) => &list.args[..],
+                    sql::FunctionArguments::Subquery(_) => {
+                        return Err(ParseError::UnsupportedFeature(
+                            "Subquery function arguments".to_string(),
+                        ))
+                    }
+                };
+                let args = args
                     .iter()
                     .map(|arg| match arg {
                         sql::FunctionArg::Unnamed(sql::FunctionArgExpr::Expr(e)) => {
@@ -398,6 +412,17 @@ impl QueryParser {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
+                if let Some(predicate) = SpatialPredicate::from_function_name(&name) {
+                    if args.len() != predicate.arity() {
+                        return Err(ParseError::InvalidQuery(format!(
+                            "{} expects {} arguments, got {}",
+                            predicate,
+                            predicate.arity(),
+                            args.len()
+                        )));
+                    }
+                }
+
                 Ok(ScalarExpr::Function { name, args })
             }
 
@@ -472,6 +497,36 @@ impl QueryParser {
                 })
             }
 
+            sql::Expr::Like {
+                negated,
+                expr,
+                pattern,
+                escape_char: None,
+                any: false,
+            } => {
+                let op = if *negated { BinaryOp::NotLike } else { BinaryOp::Like };
+                Ok(ScalarExpr::BinaryOp {
+                    left: Box::new(self.convert_expr(expr)?),
+                    op,
+                    right: Box::new(self.convert_expr(pattern)?),
+                })
+            }
+
+            sql::Expr::ILike {
+                negated,
+                expr,
+                pattern,
+                escape_char: None,
+                any: false,
+            } => {
+                let op = if *negated { BinaryOp::NotILike } else { BinaryOp::ILike };
+                Ok(ScalarExpr::BinaryOp {
+                    left: Box::new(self.convert_expr(expr)?),
+                    op,
+                    right: Box::new(self.convert_expr(pattern)?),
+                })
+            }
+
             sql::Expr::IsNull(expr) => {
                 let inner = self.convert_expr(expr)?;
                 Ok(ScalarExpr::UnaryOp {
@@ -536,7 +591,7 @@ impl QueryParser {
             sql::BinaryOperator::GtEq => Ok(BinaryOp::GtEq),
             sql::BinaryOperator::And => Ok(BinaryOp::And),
             sql::BinaryOperator::Or => Ok(BinaryOp::Or),
-            sql::BinaryOperator::Like => Ok(BinaryOp::Like),
+            sql::BinaryOperator::PGOverlap => Ok(BinaryOp::BboxIntersects),
             _ => Err(ParseError::UnsupportedFeature(format!(
                 "Binary operator: {:?}",
                 op
@@ -680,4 +735,17 @@ mod tests {
         let result = parser.parse("SELECT * FROM users ORDER BY id DESC LIMIT 10 OFFSET 20");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_spatial_predicates() {
+        let parser = QueryParser::with_generic_dialect();
+        let result = parser.parse(
+            "SELECT p.id FROM parcels p JOIN zones z ON ST_Intersects(p.geom, z.geom) \
+             WHERE p.geom && ST_MakeEnvelope(0, 0, 10, 10) AND ST_DWithin(p.geom, 'POINT(1 1)', 5)",
+        );
+        assert!(result.is_ok());
+
+        let result = parser.parse("SELECT id FROM parcels WHERE ST_DWithin(geom, 'POINT(1 1)')");
+        assert!(matches!(result, Err(ParseError::InvalidQuery(_))));
+    }
 }
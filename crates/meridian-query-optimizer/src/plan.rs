@@ -3,6 +3,7 @@
 //! Logical and physical query execution plans with cost estimates.
 
 use crate::ast::*;
+use crate::spatial::SpatialPredicate;
 use crate::statistics::TableStatistics;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -318,6 +319,24 @@ pub enum PhysicalOp {
         condition: Option<ScalarExpr>,
     },
 
+    /// Spatial join on a spatial predicate between two geometries
+    SpatialJoin {
+        /// Join type
+        join_type: JoinType,
+        /// Predicate joined on
+        predicate: SpatialPredicate,
+        /// Geometry of the left input
+        left_geometry: ScalarExpr,
+        /// Geometry of the right input
+        right_geometry: ScalarExpr,
+        /// Search distance of `ST_DWithin`
+        distance: Option<f64>,
+        /// Remaining join conditions
+        condition: Option<ScalarExpr>,
+        /// Execution strategy chosen by the cost model
+        strategy: SpatialJoinStrategy,
+    },
+
     /// Hash aggregation
     HashAggregate {
         group_by: Vec<ScalarExpr>,
@@ -376,6 +395,15 @@ pub enum PhysicalOp {
     Materialize,
 }
 
+/// Execution strategy of a spatial join
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpatialJoinStrategy {
+    /// Probe an R-tree built over the right input
+    IndexNestedLoop,
+    /// Partition both inputs on a grid and plane-sweep each cell
+    PartitionMerge,
+}
+
 /// Data distribution strategy for parallel execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Distribution {
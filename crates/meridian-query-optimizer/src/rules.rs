@@ -278,7 +278,7 @@ impl PredicatePushdownRule {
         let mut join_preds = Vec::new();
 
         for pred in predicates {
-            let columns = extract_columns(pred);
+            let columns = extract_column_names(pred);
             let left_columns = get_output_columns(&join_children[0]);
             let right_columns = get_output_columns(&join_children[1]);
 
@@ -0,0 +1,1151 @@
+//! Spatial Predicates and Geometry Evaluation
+//!
+//! Recognizes the spatial predicates the optimizer can cost and index:
+//! - `ST_Intersects(a, b)`
+//! - `ST_Contains(a, b)` and its converse `ST_Within(a, b)`
+//! - `ST_DWithin(a, b, distance)`
+//! - `a && b` (bounding boxes intersect)
+//!
+//! Geometries are WKT strings or ISO/EWKB binaries. Predicates are evaluated
+//! on planar coordinates, with a bounding box test before the exact test.
+
+use crate::ast::{BinaryOp, ColumnRef, Literal, ScalarExpr};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Smallest x
+    pub min_x: f64,
+    /// Smallest y
+    pub min_y: f64,
+    /// Largest x
+    pub max_x: f64,
+    /// Largest y
+    pub max_y: f64,
+}
+
+impl Envelope {
+    /// Envelope spanning two corners in any order
+    pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Self {
+        Self {
+            min_x: x1.min(x2),
+            min_y: y1.min(y2),
+            max_x: x1.max(x2),
+            max_y: y1.max(y2),
+        }
+    }
+
+    /// Degenerate envelope of a single point
+    pub fn from_point(x: f64, y: f64) -> Self {
+        Self::new(x, y, x, y)
+    }
+
+    /// Extent along x
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    /// Extent along y
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Area of the box
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    /// Center point
+    pub fn center(&self) -> (f64, f64) {
+        ((self.min_x + self.max_x) / 2.0, (self.min_y + self.max_y) / 2.0)
+    }
+
+    /// Whether the boxes share at least one point
+    pub fn intersects(&self, other: &Envelope) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    /// Whether `other` lies entirely inside this box
+    pub fn contains(&self, other: &Envelope) -> bool {
+        self.min_x <= other.min_x
+            && self.min_y <= other.min_y
+            && self.max_x >= other.max_x
+            && self.max_y >= other.max_y
+    }
+
+    /// Whether a point lies inside the box or on its boundary
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        self.min_x <= x && x <= self.max_x && self.min_y <= y && y <= self.max_y
+    }
+
+    /// Common part of two envelopes, `None` if they are disjoint
+    pub fn intersection(&self, other: &Envelope) -> Option<Envelope> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Some(Envelope {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        })
+    }
+
+    /// Smallest envelope covering both
+    pub fn merge(&self, other: &Envelope) -> Envelope {
+        Envelope {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// Grow the envelope by `distance` on every side
+    pub fn expand(&self, distance: f64) -> Envelope {
+        Envelope {
+            min_x: self.min_x - distance,
+            min_y: self.min_y - distance,
+            max_x: self.max_x + distance,
+            max_y: self.max_y + distance,
+        }
+    }
+}
+
+/// Spatial predicate between two geometries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpatialPredicate {
+    /// `ST_Intersects(a, b)`
+    Intersects,
+    /// `ST_Contains(a, b)`: `a` contains `b`
+    Contains,
+    /// `ST_Within(a, b)`: `a` lies within `b`
+    Within,
+    /// `ST_DWithin(a, b, d)`: `a` and `b` are at most `d` apart
+    DWithin,
+    /// `a && b`: the bounding boxes intersect
+    BboxIntersects,
+}
+
+impl SpatialPredicate {
+    /// Predicate of a function name, matched case-insensitively
+    pub fn from_function_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "st_intersects" => Some(SpatialPredicate::Intersects),
+            "st_contains" => Some(SpatialPredicate::Contains),
+            "st_within" => Some(SpatialPredicate::Within),
+            "st_dwithin" => Some(SpatialPredicate::DWithin),
+            _ => None,
+        }
+    }
+
+    /// Number of function arguments
+    pub fn arity(&self) -> usize {
+        match self {
+            SpatialPredicate::DWithin => 3,
+            _ => 2,
+        }
+    }
+
+    /// The predicate with its geometry arguments swapped
+    pub fn converse(&self) -> Self {
+        match self {
+            SpatialPredicate::Contains => SpatialPredicate::Within,
+            SpatialPredicate::Within => SpatialPredicate::Contains,
+            other => *other,
+        }
+    }
+}
+
+impl fmt::Display for SpatialPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SpatialPredicate::Intersects => "ST_Intersects",
+            SpatialPredicate::Contains => "ST_Contains",
+            SpatialPredicate::Within => "ST_Within",
+            SpatialPredicate::DWithin => "ST_DWithin",
+            SpatialPredicate::BboxIntersects => "&&",
+        })
+    }
+}
+
+/// A spatial predicate recognized in a scalar expression
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpatialCondition {
+    /// Predicate being tested
+    pub predicate: SpatialPredicate,
+    /// First geometry argument
+    pub left: ScalarExpr,
+    /// Second geometry argument
+    pub right: ScalarExpr,
+    /// Search distance of `ST_DWithin`, when it is a constant
+    pub distance: Option<f64>,
+}
+
+/// Column side of a spatial condition against a constant geometry
+#[derive(Debug, Clone)]
+pub struct SpatialWindow {
+    /// Geometry column being filtered
+    pub column: ColumnRef,
+    /// Envelope the column's geometry must intersect
+    pub envelope: Envelope,
+    /// Whether the column's geometry must lie inside the envelope
+    pub within: bool,
+}
+
+impl SpatialCondition {
+    /// Recognize a spatial predicate, `None` for any other expression
+    pub fn from_expr(expr: &ScalarExpr) -> Option<Self> {
+        match expr {
+            ScalarExpr::BinaryOp {
+                left,
+                op: BinaryOp::BboxIntersects,
+                right,
+            } => Some(Self {
+                predicate: SpatialPredicate::BboxIntersects,
+                left: (**left).clone(),
+                right: (**right).clone(),
+                distance: None,
+            }),
+            ScalarExpr::Function { name, args } => {
+                let predicate = SpatialPredicate::from_function_name(name)?;
+                if args.len() != predicate.arity() {
+                    return None;
+                }
+                let distance = match predicate {
+                    SpatialPredicate::DWithin => constant_number(&args[2]),
+                    _ => None,
+                };
+                Some(Self {
+                    predicate,
+                    left: args[0].clone(),
+                    right: args[1].clone(),
+                    distance,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The condition with its geometry arguments swapped
+    pub fn swapped(&self) -> Self {
+        Self {
+            predicate: self.predicate.converse(),
+            left: self.right.clone(),
+            right: self.left.clone(),
+            distance: self.distance,
+        }
+    }
+
+    /// Geometry columns referenced directly by the condition
+    pub fn columns(&self) -> Vec<&ColumnRef> {
+        [&self.left, &self.right]
+            .into_iter()
+            .filter_map(|side| match side {
+                ScalarExpr::Column(column) => Some(column),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Search window when one side is a column and the other a constant
+    /// geometry; `None` otherwise, or for a non-constant distance
+    pub fn window(&self) -> Option<SpatialWindow> {
+        let (column, constant, predicate) = match (&self.left, &self.right) {
+            (ScalarExpr::Column(column), other) => (column, other, self.predicate),
+            (other, ScalarExpr::Column(column)) => (column, other, self.predicate.converse()),
+            _ => return None,
+        };
+        let envelope = constant_envelope(constant)?;
+        let envelope = match predicate {
+            SpatialPredicate::DWithin => envelope.expand(self.distance?),
+            _ => envelope,
+        };
+        Some(SpatialWindow {
+            column: column.clone(),
+            envelope,
+            within: predicate == SpatialPredicate::Within,
+        })
+    }
+}
+
+/// Split a join condition into its first spatial conjunct between two
+/// geometry expressions and the remaining conjuncts
+pub fn split_spatial_join_condition(condition: &ScalarExpr) -> Option<(SpatialCondition, Option<ScalarExpr>)> {
+    fn conjuncts<'a>(expr: &'a ScalarExpr, out: &mut Vec<&'a ScalarExpr>) {
+        match expr {
+            ScalarExpr::BinaryOp {
+                left,
+                op: BinaryOp::And,
+                right,
+            } => {
+                conjuncts(left, out);
+                conjuncts(right, out);
+            }
+            other => out.push(other),
+        }
+    }
+    let mut all = Vec::new();
+    conjuncts(condition, &mut all);
+
+    let position = all.iter().position(|c| {
+        SpatialCondition::from_expr(c).is_some_and(|s| {
+            s.columns().len() == 2 && (s.predicate != SpatialPredicate::DWithin || s.distance.is_some())
+        })
+    })?;
+    let spatial = SpatialCondition::from_expr(all.remove(position))?;
+    let residual = all.into_iter().cloned().reduce(|left, right| ScalarExpr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOp::And,
+        right: Box::new(right),
+    });
+    Some((spatial, residual))
+}
+
+/// Numeric value of a constant expression
+fn constant_number(expr: &ScalarExpr) -> Option<f64> {
+    match expr {
+        ScalarExpr::Literal(Literal::Integer(i)) => Some(*i as f64),
+        ScalarExpr::Literal(Literal::Float(f)) => Some(*f),
+        ScalarExpr::UnaryOp {
+            op: crate::ast::UnaryOp::Negate,
+            expr,
+        } => constant_number(expr).map(|n| -n),
+        _ => None,
+    }
+}
+
+/// Envelope of a constant geometry expression
+///
+/// Understands WKT literals and the `ST_MakeEnvelope`, `ST_MakePoint`,
+/// `ST_Point`, `ST_GeomFromText` and `ST_SetSRID` constructors.
+pub fn constant_envelope(expr: &ScalarExpr) -> Option<Envelope> {
+    match expr {
+        ScalarExpr::Literal(Literal::String(wkt)) => Geometry::from_wkt(wkt).ok()?.envelope(),
+        ScalarExpr::Function { name, args } => match name.to_lowercase().as_str() {
+            "st_makeenvelope" if args.len() >= 4 => {
+                let c: Vec<f64> = args[..4].iter().map(constant_number).collect::<Option<_>>()?;
+                Some(Envelope::new(c[0], c[1], c[2], c[3]))
+            }
+            "st_makepoint" | "st_point" if args.len() >= 2 => {
+                Some(Envelope::from_point(constant_number(&args[0])?, constant_number(&args[1])?))
+            }
+            "st_geomfromtext" | "st_geomfromewkt" | "st_setsrid" if !args.is_empty() => {
+                constant_envelope(&args[0])
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Planar coordinate
+pub(crate) type Coord = (f64, f64);
+
+/// Geometry value, reduced to its XY coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Geometry {
+    Point(Coord),
+    LineString(Vec<Coord>),
+    /// Exterior ring followed by any holes
+    Polygon(Vec<Vec<Coord>>),
+    /// Multi-geometries and geometry collections
+    Collection(Vec<Geometry>),
+}
+
+impl Geometry {
+    /// Bounding box, `None` for an empty geometry
+    pub(crate) fn envelope(&self) -> Option<Envelope> {
+        let mut envelope: Option<Envelope> = None;
+        self.for_each_coord(&mut |(x, y)| {
+            let point = Envelope::from_point(x, y);
+            envelope = Some(envelope.map_or(point, |e| e.merge(&point)));
+        });
+        envelope
+    }
+
+    fn for_each_coord(&self, f: &mut impl FnMut(Coord)) {
+        match self {
+            Geometry::Point(c) => f(*c),
+            Geometry::LineString(coords) => coords.iter().copied().for_each(f),
+            Geometry::Polygon(rings) => rings.iter().flatten().copied().for_each(f),
+            Geometry::Collection(parts) => parts.iter().for_each(|p| p.for_each_coord(f)),
+        }
+    }
+
+    /// Approximate in-memory size in bytes
+    pub(crate) fn size(&self) -> usize {
+        let mut coords = 0;
+        self.for_each_coord(&mut |_| coords += 1);
+        std::mem::size_of::<Geometry>() + coords * std::mem::size_of::<Coord>()
+    }
+
+    /// Points, lines and polygons making up the geometry
+    fn parts(&self) -> Vec<&Geometry> {
+        match self {
+            Geometry::Collection(parts) => parts.iter().flat_map(|p| p.parts()).collect(),
+            simple => vec![simple],
+        }
+    }
+
+    /// Evaluate a predicate against another geometry
+    pub(crate) fn satisfies(&self, predicate: SpatialPredicate, other: &Geometry, distance: f64) -> bool {
+        match predicate {
+            SpatialPredicate::Intersects => self.intersects(other),
+            SpatialPredicate::Contains => self.contains(other),
+            SpatialPredicate::Within => other.contains(self),
+            SpatialPredicate::DWithin => self.distance(other) <= distance,
+            SpatialPredicate::BboxIntersects => match (self.envelope(), other.envelope()) {
+                (Some(a), Some(b)) => a.intersects(&b),
+                _ => false,
+            },
+        }
+    }
+
+    pub(crate) fn intersects(&self, other: &Geometry) -> bool {
+        match (self.envelope(), other.envelope()) {
+            (Some(a), Some(b)) if a.intersects(&b) => {}
+            _ => return false,
+        }
+        let theirs = other.parts();
+        self.parts()
+            .into_iter()
+            .any(|a| theirs.iter().any(|b| simple_intersects(a, b)))
+    }
+
+    /// Whether every point of `other` lies in this geometry
+    ///
+    /// Checks that all vertices of `other` are covered and that none of its
+    /// edges cross a polygon boundary of this geometry.
+    pub(crate) fn contains(&self, other: &Geometry) -> bool {
+        match (self.envelope(), other.envelope()) {
+            (Some(a), Some(b)) if a.contains(&b) => {}
+            _ => return false,
+        }
+        let ours = self.parts();
+        let mut covered = true;
+        other.for_each_coord(&mut |c| {
+            covered = covered && ours.iter().any(|part| covers_point(part, c));
+        });
+        if !covered {
+            return false;
+        }
+        let boundary: Vec<(Coord, Coord)> = ours
+            .iter()
+            .filter(|part| matches!(part, Geometry::Polygon(_)))
+            .flat_map(|part| segments(part))
+            .collect();
+        !segments(other)
+            .iter()
+            .any(|(p, q)| boundary.iter().any(|(r, s)| crosses(*p, *q, *r, *s)))
+    }
+
+    /// Minimum planar distance, 0 if the geometries intersect
+    pub(crate) fn distance(&self, other: &Geometry) -> f64 {
+        if self.intersects(other) {
+            return 0.0;
+        }
+        let theirs = segments(other);
+        segments(self)
+            .iter()
+            .flat_map(|(p, q)| theirs.iter().map(move |(r, s)| segment_distance(*p, *q, *r, *s)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Well-known text representation
+    pub(crate) fn to_wkt(&self) -> String {
+        fn coords(cs: &[Coord]) -> String {
+            let cs: Vec<String> = cs.iter().map(|(x, y)| format!("{} {}", x, y)).collect();
+            format!("({})", cs.join(", "))
+        }
+        match self {
+            Geometry::Point((x, y)) => format!("POINT({} {})", x, y),
+            Geometry::LineString(cs) => format!("LINESTRING{}", coords(cs)),
+            Geometry::Polygon(rings) => {
+                let rings: Vec<String> = rings.iter().map(|r| coords(r)).collect();
+                format!("POLYGON({})", rings.join(", "))
+            }
+            Geometry::Collection(parts) => {
+                let parts: Vec<String> = parts.iter().map(Geometry::to_wkt).collect();
+                format!("GEOMETRYCOLLECTION({})", parts.join(", "))
+            }
+        }
+    }
+
+    /// Parse well-known text, with an optional `SRID=n;` prefix
+    pub(crate) fn from_wkt(text: &str) -> Result<Geometry, String> {
+        let text = match text.trim_start().strip_prefix("SRID=") {
+            Some(rest) => rest.split_once(';').map(|(_, wkt)| wkt).ok_or("missing ';' after SRID")?,
+            None => text,
+        };
+        let mut parser = WktParser {
+            tokens: wkt_tokens(text),
+            pos: 0,
+        };
+        let geometry = parser.geometry()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(geometry),
+            Some(token) => Err(format!("unexpected '{}' after geometry", token)),
+        }
+    }
+
+    /// Parse ISO WKB or PostGIS EWKB
+    pub(crate) fn from_wkb(bytes: &[u8]) -> Result<Geometry, String> {
+        let mut reader = WkbReader { bytes, pos: 0 };
+        let geometry = reader.geometry()?;
+        if reader.pos != bytes.len() {
+            return Err("trailing bytes after geometry".to_string());
+        }
+        Ok(geometry)
+    }
+}
+
+/// Edges of a geometry; a point is a zero-length edge
+fn segments(geometry: &Geometry) -> Vec<(Coord, Coord)> {
+    fn path(coords: &[Coord], out: &mut Vec<(Coord, Coord)>) {
+        match coords {
+            [single] => out.push((*single, *single)),
+            _ => out.extend(coords.windows(2).map(|w| (w[0], w[1]))),
+        }
+    }
+    let mut out = Vec::new();
+    for part in geometry.parts() {
+        match part {
+            Geometry::Point(c) => out.push((*c, *c)),
+            Geometry::LineString(coords) => path(coords, &mut out),
+            Geometry::Polygon(rings) => rings.iter().for_each(|ring| path(ring, &mut out)),
+            Geometry::Collection(_) => {}
+        }
+    }
+    out
+}
+
+fn simple_intersects(a: &Geometry, b: &Geometry) -> bool {
+    let (ea, eb) = (segments(a), segments(b));
+    if ea
+        .iter()
+        .any(|(p, q)| eb.iter().any(|(r, s)| segments_intersect(*p, *q, *r, *s)))
+    {
+        return true;
+    }
+    // One geometry inside a polygon without touching its boundary
+    let inside = |part: &Geometry, polygon: &Geometry| match (part_coord(part), polygon) {
+        (Some(c), Geometry::Polygon(rings)) => point_in_polygon(c, rings),
+        _ => false,
+    };
+    inside(a, b) || inside(b, a)
+}
+
+fn part_coord(geometry: &Geometry) -> Option<Coord> {
+    match geometry {
+        Geometry::Point(c) => Some(*c),
+        Geometry::LineString(coords) => coords.first().copied(),
+        Geometry::Polygon(rings) => rings.first().and_then(|r| r.first().copied()),
+        Geometry::Collection(_) => None,
+    }
+}
+
+/// Whether a point lies in or on a simple geometry
+fn covers_point(geometry: &Geometry, c: Coord) -> bool {
+    let on_boundary = segments(geometry)
+        .iter()
+        .any(|(p, q)| orientation(*p, *q, c) == 0 && on_segment(*p, *q, c));
+    on_boundary
+        || match geometry {
+            Geometry::Polygon(rings) => point_in_polygon(c, rings),
+            _ => false,
+        }
+}
+
+/// Even-odd test against the exterior ring and holes
+fn point_in_polygon((x, y): Coord, rings: &[Vec<Coord>]) -> bool {
+    let mut inside = false;
+    for ring in rings {
+        for w in ring.windows(2) {
+            let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+            if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn orientation(p: Coord, q: Coord, r: Coord) -> i8 {
+    let cross = (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0);
+    if cross > 0.0 {
+        1
+    } else if cross < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Whether collinear point `r` lies within the bounds of segment `pq`
+fn on_segment(p: Coord, q: Coord, r: Coord) -> bool {
+    r.0 >= p.0.min(q.0) && r.0 <= p.0.max(q.0) && r.1 >= p.1.min(q.1) && r.1 <= p.1.max(q.1)
+}
+
+fn segments_intersect(p: Coord, q: Coord, r: Coord, s: Coord) -> bool {
+    let (o1, o2) = (orientation(p, q, r), orientation(p, q, s));
+    let (o3, o4) = (orientation(r, s, p), orientation(r, s, q));
+    (o1 != o2 && o3 != o4)
+        || (o1 == 0 && on_segment(p, q, r))
+        || (o2 == 0 && on_segment(p, q, s))
+        || (o3 == 0 && on_segment(r, s, p))
+        || (o4 == 0 && on_segment(r, s, q))
+}
+
+/// Whether two segments cross at a single interior point of both
+fn crosses(p: Coord, q: Coord, r: Coord, s: Coord) -> bool {
+    let (o1, o2) = (orientation(p, q, r), orientation(p, q, s));
+    let (o3, o4) = (orientation(r, s, p), orientation(r, s, q));
+    o1 * o2 < 0 && o3 * o4 < 0
+}
+
+fn point_segment_distance(c: Coord, p: Coord, q: Coord) -> f64 {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((c.0 - p.0) * dx + (c.1 - p.1) * dy) / length).clamp(0.0, 1.0)
+    };
+    let (x, y) = (p.0 + t * dx, p.1 + t * dy);
+    ((c.0 - x).powi(2) + (c.1 - y).powi(2)).sqrt()
+}
+
+fn segment_distance(p: Coord, q: Coord, r: Coord, s: Coord) -> f64 {
+    if segments_intersect(p, q, r, s) {
+        return 0.0;
+    }
+    point_segment_distance(p, r, s)
+        .min(point_segment_distance(q, r, s))
+        .min(point_segment_distance(r, p, q))
+        .min(point_segment_distance(s, p, q))
+}
+
+/// Split WKT into words, numbers and punctuation
+fn wkt_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for ch in text.chars() {
+        if ch == '(' || ch == ')' || ch == ',' || ch.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !ch.is_whitespace() {
+                tokens.push(ch.to_string());
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct WktParser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl WktParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&str, String> {
+        let token = self.tokens.get(self.pos).ok_or("unexpected end of WKT")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected '{}', found '{}'", expected, token)),
+        }
+    }
+
+    /// `true` if the geometry is EMPTY
+    fn empty(&mut self) -> bool {
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case("EMPTY")) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, String> {
+        let kind = self.next()?.to_uppercase();
+        if self
+            .peek()
+            .is_some_and(|t| matches!(t.to_uppercase().as_str(), "Z" | "M" | "ZM"))
+        {
+            self.pos += 1;
+        }
+        if self.empty() {
+            return Ok(Geometry::Collection(vec![]));
+        }
+        match kind.as_str() {
+            "POINT" => {
+                self.expect("(")?;
+                let c = self.coord()?;
+                self.expect(")")?;
+                Ok(Geometry::Point(c))
+            }
+            "LINESTRING" => Ok(Geometry::LineString(self.coords()?)),
+            "POLYGON" => Ok(Geometry::Polygon(self.list(Self::coords)?)),
+            "MULTIPOINT" => {
+                let points = self.list(|p| {
+                    // Both `MULTIPOINT(1 2, 3 4)` and `MULTIPOINT((1 2), (3 4))`
+                    if p.peek() == Some("(") {
+                        p.pos += 1;
+                        let c = p.coord()?;
+                        p.expect(")")?;
+                        Ok(c)
+                    } else {
+                        p.coord()
+                    }
+                })?;
+                Ok(Geometry::Collection(points.into_iter().map(Geometry::Point).collect()))
+            }
+            "MULTILINESTRING" => Ok(Geometry::Collection(
+                self.list(Self::coords)?.into_iter().map(Geometry::LineString).collect(),
+            )),
+            "MULTIPOLYGON" => Ok(Geometry::Collection(
+                self.list(|p| p.list(Self::coords))?
+                    .into_iter()
+                    .map(Geometry::Polygon)
+                    .collect(),
+            )),
+            "GEOMETRYCOLLECTION" => Ok(Geometry::Collection(self.list(Self::geometry)?)),
+            other => Err(format!("unsupported geometry type {}", other)),
+        }
+    }
+
+    /// Parenthesized, comma-separated list
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        self.expect("(")?;
+        let mut items = vec![item(self)?];
+        loop {
+            match self.next()? {
+                "," => items.push(item(self)?),
+                ")" => return Ok(items),
+                token => return Err(format!("expected ',' or ')', found '{}'", token)),
+            }
+        }
+    }
+
+    fn coords(&mut self) -> Result<Vec<Coord>, String> {
+        self.list(Self::coord)
+    }
+
+    /// X and Y of a position, skipping any Z and M ordinates
+    fn coord(&mut self) -> Result<Coord, String> {
+        let number = |p: &mut Self| {
+            let token = p.next()?;
+            token.parse::<f64>().map_err(|_| format!("invalid number '{}'", token))
+        };
+        let c = (number(self)?, number(self)?);
+        while self.peek().is_some_and(|t| t != "," && t != ")") {
+            number(self)?;
+        }
+        Ok(c)
+    }
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let slice = self
+            .bytes
+            .get(self.pos..self.pos + N)
+            .ok_or("unexpected end of WKB")?;
+        self.pos += N;
+        Ok(slice.try_into().expect("slice of length N"))
+    }
+
+    fn u32(&mut self, little: bool) -> Result<u32, String> {
+        let b = self.take::<4>()?;
+        Ok(if little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64(&mut self, little: bool) -> Result<f64, String> {
+        let b = self.take::<8>()?;
+        Ok(if little { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, String> {
+        let little = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            other => return Err(format!("invalid WKB byte order {}", other)),
+        };
+        let raw = self.u32(little)?;
+        // EWKB flags, or ISO type codes offset by 1000 per extra dimension
+        let mut dims = 2 + usize::from(raw & 0x8000_0000 != 0) + usize::from(raw & 0x4000_0000 != 0);
+        if raw & 0x2000_0000 != 0 {
+            self.u32(little)?;
+        }
+        let code = raw & 0x0FFF_FFFF;
+        dims += match code / 1000 {
+            1 | 2 => 1,
+            3 => 2,
+            _ => 0,
+        };
+        let coords = |r: &mut Self| -> Result<Vec<Coord>, String> {
+            let n = r.u32(little)? as usize;
+            (0..n).map(|_| r.coord(little, dims)).collect()
+        };
+
+        match code % 1000 {
+            1 => {
+                let c = self.coord(little, dims)?;
+                // An empty point is encoded with NaN coordinates
+                Ok(if c.0.is_nan() && c.1.is_nan() {
+                    Geometry::Collection(vec![])
+                } else {
+                    Geometry::Point(c)
+                })
+            }
+            2 => Ok(Geometry::LineString(coords(self)?)),
+            3 => {
+                let n = self.u32(little)? as usize;
+                Ok(Geometry::Polygon((0..n).map(|_| coords(self)).collect::<Result<_, _>>()?))
+            }
+            4..=7 => {
+                let n = self.u32(little)? as usize;
+                Ok(Geometry::Collection((0..n).map(|_| self.geometry()).collect::<Result<_, _>>()?))
+            }
+            other => Err(format!("unsupported WKB geometry type {}", other)),
+        }
+    }
+
+    fn coord(&mut self, little: bool, dims: usize) -> Result<Coord, String> {
+        let c = (self.f64(little)?, self.f64(little)?);
+        for _ in 2..dims {
+            self.f64(little)?;
+        }
+        Ok(c)
+    }
+}
+
+/// Bulk-loaded R-tree over envelopes
+///
+/// Built once with Sort-Tile-Recursive packing and queried read-only.
+pub(crate) struct RTree {
+    /// Nodes level by level, leaves first
+    nodes: Vec<RTreeNode>,
+    root: Option<usize>,
+}
+
+struct RTreeNode {
+    envelope: Envelope,
+    /// Entry ids in leaves, node indexes otherwise
+    children: Vec<(Envelope, usize)>,
+    leaf: bool,
+}
+
+impl RTree {
+    const NODE_CAPACITY: usize = 16;
+
+    pub(crate) fn build(entries: Vec<(Envelope, usize)>) -> Self {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            root: None,
+        };
+        if entries.is_empty() {
+            return tree;
+        }
+        let mut level = entries;
+        let mut leaf = true;
+        loop {
+            let first = tree.nodes.len();
+            for group in Self::pack(level) {
+                let envelope = group
+                    .iter()
+                    .map(|(e, _)| *e)
+                    .reduce(|a, b| a.merge(&b))
+                    .expect("packed groups are not empty");
+                tree.nodes.push(RTreeNode {
+                    envelope,
+                    children: group,
+                    leaf,
+                });
+            }
+            let count = tree.nodes.len() - first;
+            if count == 1 {
+                tree.root = Some(first);
+                return tree;
+            }
+            level = (first..first + count).map(|i| (tree.nodes[i].envelope, i)).collect();
+            leaf = false;
+        }
+    }
+
+    /// Sort-Tile-Recursive grouping into nodes of at most `NODE_CAPACITY`
+    fn pack(mut entries: Vec<(Envelope, usize)>) -> Vec<Vec<(Envelope, usize)>> {
+        let center_x = |e: &Envelope| e.min_x + e.max_x;
+        let center_y = |e: &Envelope| e.min_y + e.max_y;
+        let node_count = entries.len().div_ceil(Self::NODE_CAPACITY);
+        let slice_count = (node_count as f64).sqrt().ceil() as usize;
+        let slice_size = slice_count * Self::NODE_CAPACITY;
+
+        entries.sort_by(|a, b| center_x(&a.0).total_cmp(&center_x(&b.0)));
+        let mut groups = Vec::with_capacity(node_count);
+        for slice in entries.chunks_mut(slice_size) {
+            slice.sort_by(|a, b| center_y(&a.0).total_cmp(&center_y(&b.0)));
+            groups.extend(slice.chunks(Self::NODE_CAPACITY).map(|c| c.to_vec()));
+        }
+        groups
+    }
+
+    /// Call `f` with every entry whose envelope intersects `window`
+    pub(crate) fn search(&self, window: &Envelope, mut f: impl FnMut(usize)) {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.envelope.intersects(window) {
+                continue;
+            }
+            for (envelope, child) in &node.children {
+                if envelope.intersects(window) {
+                    if node.leaf {
+                        f(*child);
+                    } else {
+                        stack.push(*child);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of levels, 0 when empty
+    pub(crate) fn height(&self) -> usize {
+        let mut height = 0;
+        let mut node = self.root;
+        while let Some(index) = node {
+            height += 1;
+            let n = &self.nodes[index];
+            node = if n.leaf { None } else { n.children.first().map(|(_, c)| *c) };
+        }
+        height
+    }
+}
+
+/// Uniform grid of `columns` x `columns` cells over an extent
+///
+/// Positions outside the extent fall in the nearest edge cell.
+pub(crate) struct Grid {
+    pub(crate) extent: Envelope,
+    pub(crate) columns: usize,
+}
+
+impl Grid {
+    pub(crate) fn len(&self) -> usize {
+        self.columns * self.columns
+    }
+
+    fn axis(&self, value: f64, min: f64, size: f64) -> usize {
+        if size <= 0.0 || value <= min {
+            return 0;
+        }
+        (((value - min) / size * self.columns as f64) as usize).min(self.columns - 1)
+    }
+
+    pub(crate) fn cell_of(&self, x: f64, y: f64) -> usize {
+        let column = self.axis(x, self.extent.min_x, self.extent.width());
+        let row = self.axis(y, self.extent.min_y, self.extent.height());
+        row * self.columns + column
+    }
+
+    /// Cells an envelope overlaps, none if it misses the extent
+    pub(crate) fn cells_overlapping(&self, envelope: &Envelope) -> Vec<usize> {
+        if !envelope.intersects(&self.extent) {
+            return vec![];
+        }
+        let (first, last) = (
+            self.cell_of(envelope.min_x, envelope.min_y),
+            self.cell_of(envelope.max_x, envelope.max_y),
+        );
+        let (c0, r0, c1, r1) = (
+            first % self.columns,
+            first / self.columns,
+            last % self.columns,
+            last / self.columns,
+        );
+        (r0..=r1)
+            .flat_map(|row| (c0..=c1).map(move |column| row * self.columns + column))
+            .collect()
+    }
+}
+
+/// Report every pair of intersecting envelopes between two sets
+///
+/// Sweeps a vertical line along x over both sets sorted by `min_x`.
+pub(crate) fn plane_sweep(left: &[Envelope], right: &[Envelope], mut f: impl FnMut(usize, usize)) {
+    let sorted = |envelopes: &[Envelope]| {
+        let mut order: Vec<usize> = (0..envelopes.len()).collect();
+        order.sort_by(|&a, &b| envelopes[a].min_x.total_cmp(&envelopes[b].min_x));
+        order
+    };
+    let (lefts, rights) = (sorted(left), sorted(right));
+    let overlaps_y = |a: &Envelope, b: &Envelope| a.min_y <= b.max_y && b.min_y <= a.max_y;
+
+    let (mut i, mut j) = (0, 0);
+    while i < lefts.len() && j < rights.len() {
+        if left[lefts[i]].min_x <= right[rights[j]].min_x {
+            let current = &left[lefts[i]];
+            for &k in rights[j..].iter().take_while(|&&k| right[k].min_x <= current.max_x) {
+                if overlaps_y(current, &right[k]) {
+                    f(lefts[i], k);
+                }
+            }
+            i += 1;
+        } else {
+            let current = &right[rights[j]];
+            for &k in lefts[i..].iter().take_while(|&&k| left[k].min_x <= current.max_x) {
+                if overlaps_y(&left[k], current) {
+                    f(k, rights[j]);
+                }
+            }
+            j += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wkt(text: &str) -> Geometry {
+        Geometry::from_wkt(text).unwrap()
+    }
+
+    #[test]
+    fn test_recognize_spatial_predicates() {
+        let column = ScalarExpr::Column(ColumnRef::new("geom"));
+        let envelope = ScalarExpr::Function {
+            name: "ST_MakeEnvelope".to_string(),
+            args: [0, 0, 10, 5].iter().map(|&n| ScalarExpr::Literal(Literal::Integer(n))).collect(),
+        };
+        let dwithin = ScalarExpr::Function {
+            name: "ST_DWithin".to_string(),
+            args: vec![envelope.clone(), column.clone(), ScalarExpr::Literal(Literal::Float(1.5))],
+        };
+
+        let condition = SpatialCondition::from_expr(&dwithin).unwrap();
+        assert_eq!(condition.predicate, SpatialPredicate::DWithin);
+        let window = condition.window().unwrap();
+        assert_eq!(window.column.name, "geom");
+        assert_eq!(window.envelope, Envelope::new(-1.5, -1.5, 11.5, 6.5));
+
+        let within = SpatialCondition::from_expr(&ScalarExpr::Function {
+            name: "st_contains".to_string(),
+            args: vec![envelope, column],
+        })
+        .unwrap();
+        assert!(within.window().unwrap().within);
+        assert!(SpatialCondition::from_expr(&ScalarExpr::Function {
+            name: "ST_Area".to_string(),
+            args: vec![],
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn test_wkt_and_wkb_parsing() {
+        let polygon = wkt("SRID=4326;POLYGON Z ((0 0 1, 4 0 1, 4 4 1, 0 4 1, 0 0 1), (1 1 0, 2 1 0, 2 2 0, 1 1 0))");
+        assert_eq!(polygon.envelope(), Some(Envelope::new(0.0, 0.0, 4.0, 4.0)));
+        assert_eq!(wkt("MULTIPOINT((1 2), (3 4))"), wkt("MULTIPOINT(1 2, 3 4)"));
+        assert_eq!(wkt("LINESTRING EMPTY").envelope(), None);
+        assert!(Geometry::from_wkt("POINT(1)").is_err());
+
+        // Little-endian EWKB point with SRID 4326
+        let mut wkb = vec![1u8];
+        wkb.extend_from_slice(&0x2000_0001u32.to_le_bytes());
+        wkb.extend_from_slice(&4326u32.to_le_bytes());
+        wkb.extend_from_slice(&3.0f64.to_le_bytes());
+        wkb.extend_from_slice(&7.0f64.to_le_bytes());
+        assert_eq!(Geometry::from_wkb(&wkb).unwrap(), Geometry::Point((3.0, 7.0)));
+        assert!(Geometry::from_wkb(&wkb[..10]).is_err());
+    }
+
+    #[test]
+    fn test_geometry_predicates() {
+        let square = wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))");
+        assert!(square.intersects(&wkt("POINT(1 1)")));
+        assert!(!square.intersects(&wkt("POINT(5 5)")));
+        assert!(square.intersects(&wkt("LINESTRING(-5 5, 1 5)")));
+        assert!(square.contains(&wkt("LINESTRING(1 1, 3 3)")));
+        assert!(!square.contains(&wkt("LINESTRING(1 1, 11 1)")));
+        assert!(!square.contains(&wkt("LINESTRING(3 5, 7 5)")));
+        assert!(wkt("POINT(2 2)").satisfies(SpatialPredicate::Within, &square, 0.0));
+        assert_eq!(wkt("POINT(13 14)").distance(&square), 5.0);
+        assert!(wkt("POINT(13 14)").satisfies(SpatialPredicate::DWithin, &square, 5.0));
+    }
+
+    #[test]
+    fn test_rtree_search() {
+        let entries: Vec<(Envelope, usize)> = (0..1000)
+            .map(|i| {
+                let (x, y) = ((i % 40) as f64, (i / 40) as f64);
+                (Envelope::new(x, y, x + 0.5, y + 0.5), i)
+            })
+            .collect();
+        let tree = RTree::build(entries.clone());
+        assert!(tree.height() >= 2);
+
+        let window = Envelope::new(10.2, 3.2, 12.1, 4.1);
+        let mut found = Vec::new();
+        tree.search(&window, |i| found.push(i));
+        found.sort();
+        let expected: Vec<usize> = entries
+            .iter()
+            .filter(|(e, _)| e.intersects(&window))
+            .map(|(_, i)| *i)
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_plane_sweep_and_grid() {
+        let left: Vec<Envelope> = (0..50).map(|i| Envelope::new(i as f64, 0.0, i as f64 + 1.5, 1.0)).collect();
+        let right: Vec<Envelope> = (0..20).map(|i| Envelope::new(i as f64 * 3.0, 0.5, i as f64 * 3.0 + 0.5, 2.0)).collect();
+        let mut swept = Vec::new();
+        plane_sweep(&left, &right, |i, j| swept.push((i, j)));
+        swept.sort();
+        let mut expected = Vec::new();
+        for (i, l) in left.iter().enumerate() {
+            for (j, r) in right.iter().enumerate() {
+                if l.intersects(r) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert_eq!(swept, expected);
+
+        let grid = Grid {
+            extent: Envelope::new(0.0, 0.0, 10.0, 10.0),
+            columns: 4,
+        };
+        assert_eq!(grid.cell_of(-5.0, 20.0), 12);
+        assert_eq!(grid.cells_overlapping(&Envelope::new(2.0, 2.0, 6.0, 3.0)), vec![0, 1, 2, 4, 5, 6]);
+        assert!(grid.cells_overlapping(&Envelope::new(11.0, 0.0, 12.0, 1.0)).is_empty());
+    }
+}
@@ -0,0 +1,515 @@
+//! Spill-to-Disk Support
+//!
+//! Binary row encoding, temporary spill files, hash partitioning and an
+//! external merge sort for operators that exceed their memory budget.
+
+use crate::eval::compare_values;
+use crate::executor::{ExecutionError, ExecutionResult, Row, Value};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Stream of rows produced by a blocking operator
+pub(crate) type RowStream = Box<dyn Iterator<Item = ExecutionResult<Row>> + Send + Sync>;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_INTEGER: u8 = 3;
+const TAG_FLOAT: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_BINARY: u8 = 6;
+
+fn encode_value(value: &Value, buf: &mut Vec<u8>) {
+    match value {
+        Value::Null => buf.push(TAG_NULL),
+        Value::Boolean(false) => buf.push(TAG_FALSE),
+        Value::Boolean(true) => buf.push(TAG_TRUE),
+        Value::Integer(i) => {
+            buf.push(TAG_INTEGER);
+            buf.extend_from_slice(&i.to_le_bytes());
+        }
+        Value::Float(f) => {
+            buf.push(TAG_FLOAT);
+            buf.extend_from_slice(&f.to_le_bytes());
+        }
+        Value::String(s) => {
+            buf.push(TAG_STRING);
+            buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            buf.extend_from_slice(s.as_bytes());
+        }
+        Value::Binary(b) => {
+            buf.push(TAG_BINARY);
+            buf.extend_from_slice(&(b.len() as u32).to_le_bytes());
+            buf.extend_from_slice(b);
+        }
+    }
+}
+
+/// Encode values as a length-prefixed byte string
+pub(crate) fn encode_values(values: &[Value]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(4 + values.len() * 9);
+    buf.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    for value in values {
+        encode_value(value, &mut buf);
+    }
+    buf
+}
+
+/// Decode values produced by [`encode_values`]
+pub(crate) fn decode_values(bytes: &[u8]) -> ExecutionResult<Vec<Value>> {
+    let mut reader = bytes;
+    read_values(&mut reader)?.ok_or_else(|| corrupt("empty buffer"))
+}
+
+fn corrupt(reason: &str) -> ExecutionError {
+    ExecutionError::Failed(format!("Corrupt spill data: {}", reason))
+}
+
+fn read_exact<R: Read>(reader: &mut R, buf: &mut [u8]) -> ExecutionResult<()> {
+    reader.read_exact(buf).map_err(ExecutionError::from)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> ExecutionResult<u32> {
+    let mut buf = [0u8; 4];
+    read_exact(reader, &mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> ExecutionResult<Vec<u8>> {
+    let mut buf = vec![0u8; read_u32(reader)? as usize];
+    read_exact(reader, &mut buf)?;
+    Ok(buf)
+}
+
+/// Read one encoded row, `None` at a clean end of input
+fn read_values<R: Read>(reader: &mut R) -> ExecutionResult<Option<Vec<Value>>> {
+    let mut len = [0u8; 4];
+    match reader.read(&mut len[..1])? {
+        0 => return Ok(None),
+        _ => read_exact(reader, &mut len[1..])?,
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    let mut values = Vec::with_capacity(len);
+    for _ in 0..len {
+        let mut tag = [0u8; 1];
+        read_exact(reader, &mut tag)?;
+        values.push(match tag[0] {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Boolean(false),
+            TAG_TRUE => Value::Boolean(true),
+            TAG_INTEGER | TAG_FLOAT => {
+                let mut buf = [0u8; 8];
+                read_exact(reader, &mut buf)?;
+                if tag[0] == TAG_INTEGER {
+                    Value::Integer(i64::from_le_bytes(buf))
+                } else {
+                    Value::Float(f64::from_le_bytes(buf))
+                }
+            }
+            TAG_STRING => Value::String(
+                String::from_utf8(read_bytes(reader)?).map_err(|_| corrupt("invalid UTF-8"))?,
+            ),
+            TAG_BINARY => Value::Binary(read_bytes(reader)?),
+            other => return Err(corrupt(&format!("unknown tag {}", other))),
+        });
+    }
+    Ok(Some(values))
+}
+
+/// Hash key of a list of values
+///
+/// Integral floats encode as integers and `-0.0` as `0.0`, so values that
+/// compare equal produce equal keys.
+pub(crate) fn encode_key(values: &[Value]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(values.len() * 9);
+    for value in values {
+        match value {
+            Value::Float(f) if f.fract() == 0.0 && f.abs() < 9.0e15 => {
+                encode_value(&Value::Integer(*f as i64), &mut buf)
+            }
+            Value::Float(f) if f.is_nan() => encode_value(&Value::Float(f64::NAN), &mut buf),
+            other => encode_value(other, &mut buf),
+        }
+    }
+    buf
+}
+
+/// Partition of a key among `partitions`, varied by `seed`
+pub(crate) fn partition_of(key: &[u8], seed: usize, partitions: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    (hasher.finish() % partitions as u64) as usize
+}
+
+/// Approximate in-memory size of a row in bytes
+pub(crate) fn row_size(row: &Row) -> usize {
+    std::mem::size_of::<Row>()
+        + row
+            .values
+            .iter()
+            .map(|value| {
+                std::mem::size_of::<Value>()
+                    + match value {
+                        Value::String(s) => s.len(),
+                        Value::Binary(b) => b.len(),
+                        _ => 0,
+                    }
+            })
+            .sum::<usize>()
+}
+
+/// Writer for a temporary spill file
+pub(crate) struct SpillWriter {
+    writer: BufWriter<File>,
+    file: SpillFile,
+}
+
+impl SpillWriter {
+    /// Create a new spill file in `directory`
+    pub(crate) fn create(directory: &Path) -> ExecutionResult<Self> {
+        std::fs::create_dir_all(directory)?;
+        let path = directory.join(format!("meridian-spill-{}.bin", uuid::Uuid::new_v4()));
+        let writer = BufWriter::new(File::create(&path)?);
+        Ok(Self {
+            writer,
+            file: SpillFile { path, rows: 0 },
+        })
+    }
+
+    pub(crate) fn write(&mut self, row: &Row) -> ExecutionResult<()> {
+        self.writer.write_all(&encode_values(&row.values))?;
+        self.file.rows += 1;
+        Ok(())
+    }
+
+    /// Flush and close the file for reading
+    pub(crate) fn finish(mut self) -> ExecutionResult<SpillFile> {
+        self.writer.flush()?;
+        Ok(self.file)
+    }
+}
+
+/// Temporary spill file, removed when dropped
+pub(crate) struct SpillFile {
+    path: PathBuf,
+    rows: usize,
+}
+
+impl SpillFile {
+    /// Number of rows in the file
+    pub(crate) fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Read the rows back in write order
+    pub(crate) fn reader(&self) -> ExecutionResult<SpillReader> {
+        Ok(SpillReader {
+            reader: BufReader::new(File::open(&self.path)?),
+        })
+    }
+
+    /// Read the rows back, removing the file once the reader is dropped
+    pub(crate) fn into_stream(self) -> ExecutionResult<RowStream> {
+        let reader = self.reader()?;
+        Ok(Box::new(reader.inspect(move |_| {
+            let _keep = &self;
+        })))
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Iterator over the rows of a spill file
+pub(crate) struct SpillReader {
+    reader: BufReader<File>,
+}
+
+impl Iterator for SpillReader {
+    type Item = ExecutionResult<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_values(&mut self.reader).transpose().map(|values| values.map(Row::new))
+    }
+}
+
+/// Spill files for rows partitioned by key hash
+pub(crate) struct Partitions {
+    writers: Vec<Option<SpillWriter>>,
+    directory: PathBuf,
+    seed: usize,
+}
+
+impl Partitions {
+    /// Number of partitions used when an operator spills
+    pub(crate) const COUNT: usize = 16;
+
+    pub(crate) fn new(directory: &Path, seed: usize) -> Self {
+        Self {
+            writers: (0..Self::COUNT).map(|_| None).collect(),
+            directory: directory.to_path_buf(),
+            seed,
+        }
+    }
+
+    /// Append a row to the partition of its key
+    pub(crate) fn write(&mut self, key: &[u8], row: &Row) -> ExecutionResult<()> {
+        let partition = partition_of(key, self.seed, Self::COUNT);
+        let writer = match &mut self.writers[partition] {
+            Some(writer) => writer,
+            slot => slot.insert(SpillWriter::create(&self.directory)?),
+        };
+        writer.write(row)
+    }
+
+    /// Finish all partitions; empty partitions are `None`
+    pub(crate) fn finish(self) -> ExecutionResult<Vec<Option<SpillFile>>> {
+        self.writers.into_iter().map(|writer| writer.map(SpillWriter::finish).transpose()).collect()
+    }
+}
+
+/// Sort direction and null placement of one key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SortOrder {
+    pub descending: bool,
+    pub nulls_first: bool,
+}
+
+impl SortOrder {
+    pub(crate) const ASCENDING: SortOrder = SortOrder {
+        descending: false,
+        nulls_first: false,
+    };
+}
+
+/// Compare rows on their leading key values
+pub(crate) fn compare_sort_keys(a: &Row, b: &Row, orders: &[SortOrder]) -> Ordering {
+    for (i, order) in orders.iter().enumerate() {
+        let (x, y) = (&a.values[i], &b.values[i]);
+        let ordering = match (x.is_null(), y.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if order.nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if order.nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if order.descending => compare_values(y, x),
+            (false, false) => compare_values(x, y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// External merge sort over rows whose leading values are sort keys
+///
+/// Rows are buffered up to the memory budget, then sorted and spilled as a
+/// run. Runs are merged with a heap; ties keep insertion order.
+pub(crate) struct ExternalSorter {
+    orders: Arc<[SortOrder]>,
+    memory_limit: usize,
+    directory: PathBuf,
+    buffer: Vec<Row>,
+    buffered_bytes: usize,
+    runs: Vec<SpillFile>,
+}
+
+impl ExternalSorter {
+    pub(crate) fn new(orders: Vec<SortOrder>, memory_limit: usize, directory: &Path) -> Self {
+        Self {
+            orders: orders.into(),
+            memory_limit,
+            directory: directory.to_path_buf(),
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Number of runs spilled to disk so far
+    pub(crate) fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub(crate) fn push(&mut self, row: Row) -> ExecutionResult<()> {
+        self.buffered_bytes += row_size(&row);
+        self.buffer.push(row);
+        if self.buffered_bytes > self.memory_limit && self.buffer.len() > 1 {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    fn sort_buffer(&mut self) {
+        let orders = self.orders.clone();
+        self.buffer.sort_by(|a, b| compare_sort_keys(a, b, &orders));
+    }
+
+    fn spill(&mut self) -> ExecutionResult<()> {
+        self.sort_buffer();
+        let mut writer = SpillWriter::create(&self.directory)?;
+        for row in self.buffer.drain(..) {
+            writer.write(&row)?;
+        }
+        self.runs.push(writer.finish()?);
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    /// Sorted stream of all pushed rows
+    pub(crate) fn finish(mut self) -> ExecutionResult<RowStream> {
+        if self.runs.is_empty() {
+            self.sort_buffer();
+            return Ok(Box::new(self.buffer.into_iter().map(Ok)));
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        let mut sources = Vec::with_capacity(self.runs.len());
+        let mut heap = BinaryHeap::new();
+        for (run, file) in self.runs.into_iter().enumerate() {
+            let mut stream = file.into_stream()?;
+            if let Some(row) = stream.next().transpose()? {
+                heap.push(MergeEntry {
+                    row,
+                    run,
+                    orders: self.orders.clone(),
+                });
+            }
+            sources.push(stream);
+        }
+        Ok(Box::new(MergeStream { sources, heap }))
+    }
+}
+
+struct MergeEntry {
+    row: Row,
+    run: usize,
+    orders: Arc<[SortOrder]>,
+}
+
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap; reverse to pop the smallest row first
+        compare_sort_keys(&other.row, &self.row, &self.orders).then(other.run.cmp(&self.run))
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
+
+struct MergeStream {
+    sources: Vec<RowStream>,
+    heap: BinaryHeap<MergeEntry>,
+}
+
+impl Iterator for MergeStream {
+    type Item = ExecutionResult<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.heap.pop()?;
+        match self.sources[entry.run].next() {
+            Some(Ok(row)) => self.heap.push(MergeEntry {
+                row,
+                run: entry.run,
+                orders: entry.orders,
+            }),
+            Some(Err(e)) => return Some(Err(e)),
+            None => {}
+        }
+        Some(Ok(entry.row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: Vec<Value>) -> Row {
+        Row::new(values)
+    }
+
+    #[test]
+    fn test_spill_file_round_trip() {
+        let rows = vec![
+            row(vec![Value::Null, Value::Boolean(true), Value::Integer(-7)]),
+            row(vec![Value::Float(2.5), Value::String("héllo".to_string()), Value::Binary(vec![1, 2])]),
+            row(vec![]),
+        ];
+        let mut writer = SpillWriter::create(&std::env::temp_dir()).unwrap();
+        for r in &rows {
+            writer.write(r).unwrap();
+        }
+        let file = writer.finish().unwrap();
+        assert_eq!(file.rows(), 3);
+        let read: Vec<Row> = file.reader().unwrap().collect::<ExecutionResult<_>>().unwrap();
+        assert_eq!(read.len(), 3);
+        for (a, b) in read.iter().zip(&rows) {
+            assert_eq!(a.values, b.values);
+        }
+
+        let path = file.path.clone();
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_key_normalization() {
+        assert_eq!(encode_key(&[Value::Integer(3)]), encode_key(&[Value::Float(3.0)]));
+        assert_eq!(encode_key(&[Value::Float(0.0)]), encode_key(&[Value::Float(-0.0)]));
+        assert_ne!(encode_key(&[Value::Integer(3)]), encode_key(&[Value::String("3".to_string())]));
+        assert_eq!(
+            decode_values(&encode_values(&[Value::Integer(1), Value::Null])).unwrap(),
+            vec![Value::Integer(1), Value::Null]
+        );
+    }
+
+    #[test]
+    fn test_external_sort_spills_and_merges() {
+        let orders = vec![SortOrder {
+            descending: true,
+            nulls_first: true,
+        }];
+        let mut sorter = ExternalSorter::new(orders, 512, &std::env::temp_dir());
+        for i in 0..200 {
+            let key = if i % 50 == 0 { Value::Null } else { Value::Integer(i % 17) };
+            sorter.push(row(vec![key, Value::Integer(i)])).unwrap();
+        }
+        assert!(sorter.spilled_runs() > 1);
+
+        let sorted: Vec<Row> = sorter.finish().unwrap().collect::<ExecutionResult<_>>().unwrap();
+        assert_eq!(sorted.len(), 200);
+        assert!(sorted[..4].iter().all(|r| r.values[0].is_null()));
+        for pair in sorted[4..].windows(2) {
+            let (a, b) = (&pair[0].values, &pair[1].values);
+            assert_ne!(compare_values(&a[0], &b[0]), Ordering::Less);
+            // Stable: equal keys keep insertion order
+            if a[0] == b[0] {
+                assert_eq!(compare_values(&a[1], &b[1]), Ordering::Less);
+            }
+        }
+    }
+}
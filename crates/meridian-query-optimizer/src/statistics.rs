@@ -6,8 +6,10 @@
 //! - Distinct value counts
 //! - NULL counts
 //! - Data skew analysis
+//! - 2D histograms of geometry columns
 
 use crate::ast::{ColumnRef, DataType};
+use crate::spatial::Envelope;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -68,8 +70,34 @@ impl TableStatistics {
             .map(|s| s.estimate_equality_selectivity())
             .unwrap_or(0.1)
     }
+
+    /// Fraction of rows whose geometry intersects `window`, or lies
+    /// within it when `within` is set
+    pub fn estimate_spatial_selectivity(&self, column: &str, window: &Envelope, within: bool) -> f64 {
+        let Some(histogram) = self.column_stats.get(column).and_then(|s| s.spatial_histogram.as_ref()) else {
+            return DEFAULT_SPATIAL_SELECTIVITY;
+        };
+        let selectivity = if within {
+            histogram.estimate_within_selectivity(window)
+        } else {
+            histogram.estimate_selectivity(window)
+        };
+        // Rows without a geometry never match
+        let non_null = if self.row_count == 0 {
+            1.0
+        } else {
+            (histogram.total_count as f64 / self.row_count as f64).min(1.0)
+        };
+        selectivity * non_null
+    }
 }
 
+/// Selectivity of a spatial predicate without a spatial histogram
+pub const DEFAULT_SPATIAL_SELECTIVITY: f64 = 0.001;
+
+/// Selectivity of a spatial join without spatial histograms
+pub const DEFAULT_SPATIAL_JOIN_SELECTIVITY: f64 = 0.0001;
+
 /// Statistics for a column
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnStatistics {
@@ -102,6 +130,9 @@ pub struct ColumnStatistics {
 
     /// Correlation with physical row order (-1.0 to 1.0)
     pub correlation: Option<f64>,
+
+    /// 2D histogram for geometry columns
+    pub spatial_histogram: Option<SpatialHistogram>,
 }
 
 impl ColumnStatistics {
@@ -117,6 +148,7 @@ impl ColumnStatistics {
             histogram: None,
             avg_width: None,
             correlation: None,
+            spatial_histogram: None,
         }
     }
 
@@ -328,6 +360,152 @@ impl Default for StatValue {
     }
 }
 
+/// 2D histogram of geometry envelopes over a layer's extent
+///
+/// Each geometry is counted in the grid cell holding its envelope's
+/// center; average envelope sizes account for geometries that reach
+/// beyond their cell.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpatialHistogram {
+    /// Extent of all envelopes
+    pub extent: Envelope,
+    /// Grid columns along x
+    pub columns: usize,
+    /// Grid rows along y
+    pub rows: usize,
+    /// Geometry counts per cell, row-major from `min_y`
+    pub cells: Vec<u64>,
+    /// Number of non-empty geometries
+    pub total_count: u64,
+    /// Average envelope width
+    pub avg_width: f64,
+    /// Average envelope height
+    pub avg_height: f64,
+}
+
+impl SpatialHistogram {
+    /// Build a `columns` x `rows` histogram, `None` without envelopes
+    pub fn build(envelopes: &[Envelope], columns: usize, rows: usize) -> Option<Self> {
+        let extent = envelopes.iter().copied().reduce(|a, b| a.merge(&b))?;
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let mut histogram = Self {
+            extent,
+            columns,
+            rows,
+            cells: vec![0; columns * rows],
+            total_count: envelopes.len() as u64,
+            avg_width: envelopes.iter().map(Envelope::width).sum::<f64>() / envelopes.len() as f64,
+            avg_height: envelopes.iter().map(Envelope::height).sum::<f64>() / envelopes.len() as f64,
+        };
+        for envelope in envelopes {
+            let (x, y) = envelope.center();
+            let cell = histogram.cell_of(x, y);
+            histogram.cells[cell] += 1;
+        }
+        Some(histogram)
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> usize {
+        let index = |value: f64, min: f64, size: f64, count: usize| {
+            if size <= 0.0 {
+                0
+            } else {
+                (((value - min) / size * count as f64) as usize).min(count - 1)
+            }
+        };
+        let column = index(x, self.extent.min_x, self.extent.width(), self.columns);
+        let row = index(y, self.extent.min_y, self.extent.height(), self.rows);
+        row * self.columns + column
+    }
+
+    /// Share of a cell's area covered by `window`
+    fn cell_overlap(&self, cell: usize, window: &Envelope) -> f64 {
+        let (column, row) = (cell % self.columns, cell / self.columns);
+        let axis = |min: f64, size: f64, count: usize, index: usize, low: f64, high: f64| {
+            let step = size / count as f64;
+            let (start, end) = (min + step * index as f64, min + step * (index + 1) as f64);
+            if step <= 0.0 {
+                return if low <= start && start <= high { 1.0 } else { 0.0 };
+            }
+            ((end.min(high) - start.max(low)) / step).clamp(0.0, 1.0)
+        };
+        axis(self.extent.min_x, self.extent.width(), self.columns, column, window.min_x, window.max_x)
+            * axis(self.extent.min_y, self.extent.height(), self.rows, row, window.min_y, window.max_y)
+    }
+
+    /// Fraction of geometries whose center falls in `window`
+    fn center_fraction(&self, window: &Envelope) -> f64 {
+        if self.total_count == 0 || !window.intersects(&self.extent) {
+            return 0.0;
+        }
+        let matching: f64 = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(cell, &count)| count as f64 * self.cell_overlap(cell, window))
+            .sum();
+        (matching / self.total_count as f64).clamp(0.0, 1.0)
+    }
+
+    /// Fraction of geometries whose envelope intersects `window`
+    pub fn estimate_selectivity(&self, window: &Envelope) -> f64 {
+        // An envelope intersects the window if its center is within half
+        // its size of the window
+        let grown = Envelope {
+            min_x: window.min_x - self.avg_width / 2.0,
+            min_y: window.min_y - self.avg_height / 2.0,
+            max_x: window.max_x + self.avg_width / 2.0,
+            max_y: window.max_y + self.avg_height / 2.0,
+        };
+        self.center_fraction(&grown)
+    }
+
+    /// Fraction of geometries whose envelope lies within `window`
+    pub fn estimate_within_selectivity(&self, window: &Envelope) -> f64 {
+        let (half_width, half_height) = (self.avg_width / 2.0, self.avg_height / 2.0);
+        if window.width() < 2.0 * half_width || window.height() < 2.0 * half_height {
+            return 0.0;
+        }
+        self.center_fraction(&Envelope::new(
+            window.min_x + half_width,
+            window.min_y + half_height,
+            window.max_x - half_width,
+            window.max_y - half_height,
+        ))
+    }
+
+    /// Fraction of geometry pairs within `distance` of each other
+    pub fn estimate_join_selectivity(&self, other: &SpatialHistogram, distance: f64) -> f64 {
+        if self.total_count == 0 || other.total_count == 0 {
+            return 0.0;
+        }
+        let (step_x, step_y) = (
+            self.extent.width() / self.columns as f64,
+            self.extent.height() / self.rows as f64,
+        );
+        // Probe `other` with a typical geometry at the center of each cell
+        let matching: f64 = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(cell, &count)| {
+                let x = self.extent.min_x + step_x * ((cell % self.columns) as f64 + 0.5);
+                let y = self.extent.min_y + step_y * ((cell / self.columns) as f64 + 0.5);
+                let probe = Envelope::new(
+                    x - self.avg_width / 2.0,
+                    y - self.avg_height / 2.0,
+                    x + self.avg_width / 2.0,
+                    y + self.avg_height / 2.0,
+                );
+                count as f64 * other.estimate_selectivity(&probe.expand(distance))
+            })
+            .sum();
+        (matching / self.total_count as f64).clamp(0.0, 1.0)
+    }
+}
+
 /// Statistics collector
 pub struct StatisticsCollector {
     sample_rate: f64,
@@ -419,6 +597,23 @@ impl StatisticsCollector {
         stats
     }
 
+    /// Collect statistics for geometry column from its envelopes, `None`
+    /// for NULL or empty geometries
+    pub fn collect_geometry_stats(
+        &self,
+        column_name: impl Into<String>,
+        envelopes: &[Option<Envelope>],
+    ) -> ColumnStatistics {
+        let mut stats = ColumnStatistics::new(column_name, DataType::Geometry);
+        let present: Vec<Envelope> = envelopes.iter().flatten().copied().collect();
+
+        stats.null_count = (envelopes.len() - present.len()) as u64;
+        stats.distinct_count = present.len() as u64; // Geometries are rarely repeated
+        stats.spatial_histogram = SpatialHistogram::build(&present, 32, 32);
+
+        stats
+    }
+
     /// Find most common integer values
     fn find_most_common_integer(&self, values: &[i64], limit: usize) -> Vec<(StatValue, f64)> {
         let mut frequency_map: HashMap<i64, u64> = HashMap::new();
@@ -505,6 +700,31 @@ impl StatisticsManager {
         // Estimate: geometric mean
         Some((left_rows * right_rows).sqrt())
     }
+
+    /// Estimate rows of a spatial join from the geometry columns' histograms
+    pub fn estimate_spatial_join_cardinality(
+        &self,
+        left: (&str, &str),
+        right: (&str, &str),
+        distance: f64,
+    ) -> Option<f64> {
+        let left_stats = self.get_table(left.0)?;
+        let right_stats = self.get_table(right.0)?;
+        let histogram = |stats: &TableStatistics, column: &str| {
+            stats.get_column_stats(column).and_then(|s| s.spatial_histogram.clone())
+        };
+
+        let selectivity = match (histogram(left_stats, left.1), histogram(right_stats, right.1)) {
+            (Some(l), Some(r)) => {
+                let non_null = |h: &SpatialHistogram, rows: u64| (h.total_count as f64 / rows.max(1) as f64).min(1.0);
+                l.estimate_join_selectivity(&r, distance)
+                    * non_null(&l, left_stats.row_count)
+                    * non_null(&r, right_stats.row_count)
+            }
+            _ => DEFAULT_SPATIAL_JOIN_SELECTIVITY,
+        };
+        Some(left_stats.row_count as f64 * right_stats.row_count as f64 * selectivity)
+    }
 }
 
 impl Default for StatisticsManager {
@@ -565,4 +785,56 @@ mod tests {
         assert!(manager.get_table("users").is_some());
         assert!(manager.get_table("nonexistent").is_none());
     }
+
+    fn grid_envelopes(n: usize, size: f64) -> Vec<Option<Envelope>> {
+        (0..n * n)
+            .map(|i| {
+                let (x, y) = ((i % n) as f64, (i / n) as f64);
+                Some(Envelope::new(x, y, x + size, y + size))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spatial_histogram_selectivity() {
+        let collector = StatisticsCollector::with_full_scan();
+        let mut envelopes = grid_envelopes(100, 0.5);
+        envelopes.push(None);
+        let stats = collector.collect_geometry_stats("geom", &envelopes);
+        assert_eq!(stats.null_count, 1);
+        let histogram = stats.spatial_histogram.as_ref().unwrap();
+        assert_eq!(histogram.total_count, 10_000);
+
+        // A quarter of a uniform layer
+        let quarter = histogram.estimate_selectivity(&Envelope::new(0.0, 0.0, 49.75, 49.75));
+        assert!((quarter - 0.25).abs() < 0.03, "{}", quarter);
+        assert!(histogram.estimate_within_selectivity(&Envelope::new(0.0, 0.0, 49.75, 49.75)) < quarter);
+        assert_eq!(histogram.estimate_selectivity(&Envelope::new(500.0, 500.0, 600.0, 600.0)), 0.0);
+
+        let mut table = TableStatistics::new("parcels", 10_001, 100);
+        table.add_column_stats("geom".to_string(), stats);
+        let small = table.estimate_spatial_selectivity("geom", &Envelope::new(10.0, 10.0, 12.0, 12.0), false);
+        assert!(small > 0.0 && small < 0.002, "{}", small);
+        assert_eq!(
+            table.estimate_spatial_selectivity("other", &Envelope::new(0.0, 0.0, 1.0, 1.0), false),
+            DEFAULT_SPATIAL_SELECTIVITY
+        );
+    }
+
+    #[test]
+    fn test_spatial_join_cardinality() {
+        let collector = StatisticsCollector::with_full_scan();
+        let mut manager = StatisticsManager::new();
+        for (table, size) in [("parcels", 0.5), ("zones", 0.5)] {
+            let mut stats = TableStatistics::new(table, 2500, 25);
+            stats.add_column_stats("geom".to_string(), collector.collect_geometry_stats("geom", &grid_envelopes(50, size)));
+            manager.add_table(stats);
+        }
+
+        // Each geometry only meets itself, plus its neighbours within distance 1
+        let exact = manager.estimate_spatial_join_cardinality(("parcels", "geom"), ("zones", "geom"), 0.0).unwrap();
+        let near = manager.estimate_spatial_join_cardinality(("parcels", "geom"), ("zones", "geom"), 1.0).unwrap();
+        assert!(exact > 1000.0 && exact < 5000.0, "{}", exact);
+        assert!(near > exact);
+    }
 }
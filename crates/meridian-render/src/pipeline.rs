@@ -344,7 +344,12 @@ impl LayerCompositor {
 
     /// Composite all layers into a single image
     pub fn composite(&self, tile_size: u32) -> RenderResult<DynamicImage> {
-        let mut result = DynamicImage::new_rgba8(tile_size, tile_size);
+        self.composite_sized(tile_size, tile_size)
+    }
+
+    /// Composite all layers into an image of arbitrary size
+    pub fn composite_sized(&self, width: u32, height: u32) -> RenderResult<DynamicImage> {
+        let mut result = DynamicImage::new_rgba8(width, height);
 
         for layer in &self.layers {
             if let Some(image) = &layer.image {
@@ -424,14 +429,21 @@ fn overlay_with_opacity(
         if x < base_rgba.width() && y < base_rgba.height() {
             let base_pixel = base_rgba.get_pixel_mut(x, y);
 
-            // Alpha blend with opacity
-            let alpha = (pixel[3] as f32 / 255.0) * opacity;
-            let inv_alpha = 1.0 - alpha;
+            // Source-over blend with straight alpha
+            let src_alpha = (pixel[3] as f32 / 255.0) * opacity;
+            let dst_alpha = base_pixel[3] as f32 / 255.0;
+            let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+            if out_alpha <= 0.0 {
+                continue;
+            }
 
-            base_pixel[0] = ((base_pixel[0] as f32 * inv_alpha) + (pixel[0] as f32 * alpha)) as u8;
-            base_pixel[1] = ((base_pixel[1] as f32 * inv_alpha) + (pixel[1] as f32 * alpha)) as u8;
-            base_pixel[2] = ((base_pixel[2] as f32 * inv_alpha) + (pixel[2] as f32 * alpha)) as u8;
-            base_pixel[3] = ((base_pixel[3] as f32).max(pixel[3] as f32 * opacity)) as u8;
+            for channel in 0..3 {
+                let src = pixel[channel] as f32 * src_alpha;
+                let dst = base_pixel[channel] as f32 * dst_alpha * (1.0 - src_alpha);
+                base_pixel[channel] = ((src + dst) / out_alpha).round().clamp(0.0, 255.0) as u8;
+            }
+            base_pixel[3] = (out_alpha * 255.0).round() as u8;
         }
     }
 
@@ -472,6 +484,21 @@ mod tests {
         assert_eq!(compositor.layers.len(), 1);
     }
 
+    #[test]
+    fn test_composite_sized_blends_over_transparent_base() {
+        let mut compositor = LayerCompositor::new();
+        let mut red = image::RgbaImage::new(4, 2);
+        for pixel in red.pixels_mut() {
+            *pixel = image::Rgba([255, 0, 0, 255]);
+        }
+        compositor.add_layer(CompositeLayer::new(DynamicImage::ImageRgba8(red)).with_opacity(0.5));
+
+        let result = compositor.composite_sized(4, 2).unwrap().to_rgba8();
+        assert_eq!(result.dimensions(), (4, 2));
+        // Color is preserved, only alpha reflects the opacity
+        assert_eq!(result.get_pixel(0, 0).0, [255, 0, 0, 128]);
+    }
+
     #[test]
     fn test_composite_layer_opacity() {
         let image = DynamicImage::new_rgba8(256, 256);
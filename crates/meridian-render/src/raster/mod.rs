@@ -34,6 +34,29 @@ impl TileFormat {
             TileFormat::WebP => "webp",
         }
     }
+
+    /// Parse a MIME type such as `image/png` or `image/png; mode=8bit`
+    pub fn from_mime_type(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "image/png" | "png" => Some(TileFormat::Png),
+            "image/jpeg" | "image/jpg" | "jpeg" | "jpg" => Some(TileFormat::Jpeg),
+            "image/webp" | "webp" => Some(TileFormat::WebP),
+            _ => None,
+        }
+    }
+
+    /// Whether the format can carry an alpha channel
+    pub fn supports_transparency(&self) -> bool {
+        !matches!(self, TileFormat::Jpeg)
+    }
+}
+
+/// Map extent and output size a layer is rendered into
+struct Viewport<'a> {
+    bounds: &'a TileBounds,
+    width: u32,
+    height: u32,
 }
 
 /// Raster tile renderer
@@ -90,7 +113,33 @@ impl RasterRenderer {
         style: &Style,
         data: &TileData,
     ) -> RenderResult<DynamicImage> {
-        let mut pixmap = Pixmap::new(self.tile_size, self.tile_size)
+        let bounds = coord.bounds();
+        self.render_extent(
+            &bounds,
+            self.tile_size,
+            self.tile_size,
+            f64::from(coord.z),
+            style,
+            data,
+        )
+    }
+
+    /// Render an arbitrary map extent at the given pixel size
+    ///
+    /// Unlike [`render_tile`](Self::render_tile) the extent does not have to
+    /// line up with the tile grid, which is what WMS GetMap requests need.
+    /// `bounds` must be in the same CRS as the coordinates in `data`, and
+    /// `zoom` drives the style's min/max zoom filtering.
+    pub fn render_extent(
+        &self,
+        bounds: &TileBounds,
+        width: u32,
+        height: u32,
+        zoom: f64,
+        style: &Style,
+        data: &TileData,
+    ) -> RenderResult<DynamicImage> {
+        let mut pixmap = Pixmap::new(width, height)
             .ok_or_else(|| RenderError::Other("Failed to create pixmap".to_string()))?;
 
         // Fill background
@@ -101,19 +150,32 @@ impl RasterRenderer {
             self.background_color[3],
         ));
 
-        let bounds = coord.bounds();
+        let viewport = Viewport {
+            bounds,
+            width,
+            height,
+        };
 
         // Render layers in order
         for layer in &style.layers {
-            if !layer.is_visible_at_zoom(f64::from(coord.z)) {
+            if !layer.is_visible_at_zoom(zoom) {
                 continue;
             }
 
-            self.render_layer(&mut pixmap, layer, &bounds, data)?;
+            self.render_layer(&mut pixmap, layer, &viewport, data)?;
         }
 
-        // Convert pixmap to image
-        let img_buffer = ImageBuffer::from_raw(self.tile_size, self.tile_size, pixmap.take())
+        // tiny-skia stores premultiplied alpha, images expect straight alpha
+        let pixels: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+
+        let img_buffer = ImageBuffer::from_raw(width, height, pixels)
             .ok_or_else(|| RenderError::Other("Failed to create image buffer".to_string()))?;
 
         Ok(DynamicImage::ImageRgba8(img_buffer))
@@ -124,14 +186,14 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         layer: &Layer,
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         data: &TileData,
     ) -> RenderResult<()> {
         match layer.layer_type {
-            LayerType::Fill => self.render_fill_layer(pixmap, layer, bounds, data),
-            LayerType::Line => self.render_line_layer(pixmap, layer, bounds, data),
-            LayerType::Circle => self.render_circle_layer(pixmap, layer, bounds, data),
-            LayerType::Symbol => self.render_symbol_layer(pixmap, layer, bounds, data),
+            LayerType::Fill => self.render_fill_layer(pixmap, layer, viewport, data),
+            LayerType::Line => self.render_line_layer(pixmap, layer, viewport, data),
+            LayerType::Circle => self.render_circle_layer(pixmap, layer, viewport, data),
+            LayerType::Symbol => self.render_symbol_layer(pixmap, layer, viewport, data),
             LayerType::Background => self.render_background_layer(pixmap, layer),
             _ => Ok(()), // Other layer types not implemented yet
         }
@@ -155,7 +217,7 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         layer: &Layer,
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         data: &TileData,
     ) -> RenderResult<()> {
         let paint_props = layer.paint.as_ref();
@@ -168,8 +230,9 @@ impl RasterRenderer {
         let opacity = self.get_opacity(paint_props.unwrap());
 
         // Render polygons
-        for polygon in &data.polygons {
-            self.render_polygon(pixmap, polygon, bounds, color, opacity)?;
+        for (index, polygon) in data.polygons.iter().enumerate() {
+            let holes = data.holes.get(index).map(Vec::as_slice).unwrap_or(&[]);
+            self.render_polygon(pixmap, polygon, holes, viewport, color, opacity)?;
         }
 
         Ok(())
@@ -180,7 +243,7 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         layer: &Layer,
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         data: &TileData,
     ) -> RenderResult<()> {
         let paint_props = layer.paint.as_ref();
@@ -193,7 +256,7 @@ impl RasterRenderer {
         let opacity = self.get_opacity(paint_props.unwrap());
 
         for line in &data.lines {
-            self.render_line(pixmap, line, bounds, color, width, opacity)?;
+            self.render_line(pixmap, line, viewport, color, width, opacity)?;
         }
 
         Ok(())
@@ -204,7 +267,7 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         layer: &Layer,
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         data: &TileData,
     ) -> RenderResult<()> {
         let paint_props = layer.paint.as_ref();
@@ -217,7 +280,7 @@ impl RasterRenderer {
         let opacity = self.get_opacity(paint_props.unwrap());
 
         for point in &data.points {
-            self.render_circle(pixmap, point, bounds, color, radius, opacity)?;
+            self.render_circle(pixmap, point, viewport, color, radius, opacity)?;
         }
 
         Ok(())
@@ -228,7 +291,7 @@ impl RasterRenderer {
         &self,
         _pixmap: &mut Pixmap,
         _layer: &Layer,
-        _bounds: &TileBounds,
+        _viewport: &Viewport<'_>,
         _data: &TileData,
     ) -> RenderResult<()> {
         // Symbol rendering would require text rendering and icon placement
@@ -236,12 +299,13 @@ impl RasterRenderer {
         Ok(())
     }
 
-    /// Render a polygon
+    /// Render a polygon, cutting out any interior rings
     fn render_polygon(
         &self,
         pixmap: &mut Pixmap,
         polygon: &[(f64, f64)],
-        bounds: &TileBounds,
+        holes: &[Vec<(f64, f64)>],
+        viewport: &Viewport<'_>,
         color: SkiaColor,
         opacity: f32,
     ) -> RenderResult<()> {
@@ -250,29 +314,35 @@ impl RasterRenderer {
         }
 
         let mut path_builder = PathBuilder::new();
-        let first = self.project_point(polygon[0], bounds);
-        path_builder.move_to(first.0, first.1);
+        for ring in std::iter::once(polygon).chain(holes.iter().map(Vec::as_slice)) {
+            if ring.is_empty() {
+                continue;
+            }
 
-        for &point in &polygon[1..] {
-            let (x, y) = self.project_point(point, bounds);
-            path_builder.line_to(x, y);
+            let first = self.project_point(ring[0], viewport);
+            path_builder.move_to(first.0, first.1);
+
+            for &point in &ring[1..] {
+                let (x, y) = self.project_point(point, viewport);
+                path_builder.line_to(x, y);
+            }
+
+            path_builder.close();
         }
 
-        path_builder.close();
+        // Even-odd keeps holes open regardless of ring orientation
+        let fill_rule = if holes.is_empty() {
+            tiny_skia::FillRule::Winding
+        } else {
+            tiny_skia::FillRule::EvenOdd
+        };
 
         if let Some(path) = path_builder.finish() {
             let mut paint = Paint::default();
-            paint.set_color(color);
+            paint.set_color(with_opacity(color, opacity));
             paint.anti_alias = self.antialias;
-            // Opacity handled in blend mode
 
-            pixmap.fill_path(
-                &path,
-                &paint,
-                tiny_skia::FillRule::Winding,
-                Transform::identity(),
-                None,
-            );
+            pixmap.fill_path(&path, &paint, fill_rule, Transform::identity(), None);
         }
 
         Ok(())
@@ -283,7 +353,7 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         line: &[(f64, f64)],
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         color: SkiaColor,
         width: f32,
         opacity: f32,
@@ -293,19 +363,18 @@ impl RasterRenderer {
         }
 
         let mut path_builder = PathBuilder::new();
-        let first = self.project_point(line[0], bounds);
+        let first = self.project_point(line[0], viewport);
         path_builder.move_to(first.0, first.1);
 
         for &point in &line[1..] {
-            let (x, y) = self.project_point(point, bounds);
+            let (x, y) = self.project_point(point, viewport);
             path_builder.line_to(x, y);
         }
 
         if let Some(path) = path_builder.finish() {
             let mut paint = Paint::default();
-            paint.set_color(color);
+            paint.set_color(with_opacity(color, opacity));
             paint.anti_alias = self.antialias;
-            // Opacity handled in blend mode
 
             let mut stroke = Stroke::default();
             stroke.width = width;
@@ -321,21 +390,20 @@ impl RasterRenderer {
         &self,
         pixmap: &mut Pixmap,
         point: &(f64, f64),
-        bounds: &TileBounds,
+        viewport: &Viewport<'_>,
         color: SkiaColor,
         radius: f32,
         opacity: f32,
     ) -> RenderResult<()> {
-        let (x, y) = self.project_point(*point, bounds);
+        let (x, y) = self.project_point(*point, viewport);
 
         let mut path_builder = PathBuilder::new();
         path_builder.push_circle(x, y, radius);
 
         if let Some(path) = path_builder.finish() {
             let mut paint = Paint::default();
-            paint.set_color(color);
+            paint.set_color(with_opacity(color, opacity));
             paint.anti_alias = self.antialias;
-            // Opacity handled in blend mode
 
             pixmap.fill_path(
                 &path,
@@ -350,9 +418,10 @@ impl RasterRenderer {
     }
 
     /// Project a world coordinate to pixel coordinate
-    fn project_point(&self, point: (f64, f64), bounds: &TileBounds) -> (f32, f32) {
-        let x = ((point.0 - bounds.min_x) / bounds.width() * f64::from(self.tile_size)) as f32;
-        let y = ((bounds.max_y - point.1) / bounds.height() * f64::from(self.tile_size)) as f32;
+    fn project_point(&self, point: (f64, f64), viewport: &Viewport<'_>) -> (f32, f32) {
+        let bounds = viewport.bounds;
+        let x = ((point.0 - bounds.min_x) / bounds.width() * f64::from(viewport.width)) as f32;
+        let y = ((bounds.max_y - point.1) / bounds.height() * f64::from(viewport.height)) as f32;
         (x, y)
     }
 
@@ -438,7 +507,8 @@ impl RasterRenderer {
                 image.write_to(&mut buffer, ImageFormat::Png)?;
             }
             TileFormat::Jpeg => {
-                image.write_to(&mut buffer, ImageFormat::Jpeg)?;
+                // JPEG has no alpha channel
+                DynamicImage::ImageRgb8(image.to_rgb8()).write_to(&mut buffer, ImageFormat::Jpeg)?;
             }
             TileFormat::WebP => {
                 image.write_to(&mut buffer, ImageFormat::WebP)?;
@@ -455,6 +525,13 @@ impl Default for RasterRenderer {
     }
 }
 
+/// Scale a color's alpha by a layer opacity
+fn with_opacity(color: SkiaColor, opacity: f32) -> SkiaColor {
+    let mut color = color;
+    color.apply_opacity(opacity.clamp(0.0, 1.0));
+    color
+}
+
 /// Tile data containing geometries to render
 #[derive(Debug, Clone, Default)]
 pub struct TileData {
@@ -462,8 +539,10 @@ pub struct TileData {
     pub points: Vec<(f64, f64)>,
     /// Lines
     pub lines: Vec<Vec<(f64, f64)>>,
-    /// Polygons
+    /// Polygons (exterior rings)
     pub polygons: Vec<Vec<(f64, f64)>>,
+    /// Interior rings, indexed like `polygons`
+    pub holes: Vec<Vec<Vec<(f64, f64)>>>,
 }
 
 impl TileData {
@@ -484,7 +563,20 @@ impl TileData {
 
     /// Add a polygon
     pub fn add_polygon(&mut self, polygon: Vec<(f64, f64)>) {
-        self.polygons.push(polygon);
+        self.add_polygon_with_holes(polygon, Vec::new());
+    }
+
+    /// Add a polygon with interior rings
+    pub fn add_polygon_with_holes(&mut self, exterior: Vec<(f64, f64)>, holes: Vec<Vec<(f64, f64)>>) {
+        // Keep `holes` aligned with `polygons` even if polygons were pushed directly
+        self.holes.resize(self.polygons.len(), Vec::new());
+        self.polygons.push(exterior);
+        self.holes.push(holes);
+    }
+
+    /// Whether there is nothing to draw
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty() && self.lines.is_empty() && self.polygons.is_empty()
     }
 }
 
@@ -520,4 +612,58 @@ mod tests {
         assert_eq!(data.lines.len(), 1);
         assert_eq!(data.polygons.len(), 1);
     }
+
+    #[test]
+    fn test_tile_format_from_mime_type() {
+        assert_eq!(TileFormat::from_mime_type("image/png"), Some(TileFormat::Png));
+        assert_eq!(TileFormat::from_mime_type("image/png; mode=8bit"), Some(TileFormat::Png));
+        assert_eq!(TileFormat::from_mime_type("IMAGE/JPEG"), Some(TileFormat::Jpeg));
+        assert_eq!(TileFormat::from_mime_type("image/gif"), None);
+    }
+
+    #[test]
+    fn test_render_extent_with_hole() {
+        use crate::style::{Color, Layer, PaintProperties, PropertyValue};
+
+        let mut style = Style::new("test".to_string());
+        style.add_layer(Layer {
+            id: "fill".to_string(),
+            layer_type: LayerType::Fill,
+            source: None,
+            source_layer: None,
+            minzoom: None,
+            maxzoom: None,
+            filter: None,
+            paint: Some(PaintProperties {
+                fill_color: Some(PropertyValue::Constant(Color::rgb(255, 0, 0))),
+                ..Default::default()
+            }),
+            layout: None,
+        });
+
+        let mut data = TileData::new();
+        data.add_polygon_with_holes(
+            vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)],
+            vec![vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0), (4.0, 4.0)]],
+        );
+
+        let bounds = TileBounds {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 10.0,
+            max_y: 5.0,
+        };
+        let renderer = RasterRenderer::new()
+            .with_background_color(Rgba([0, 0, 0, 0]))
+            .with_antialias(false);
+        let image = renderer
+            .render_extent(&bounds, 200, 100, 10.0, &style, &data)
+            .unwrap()
+            .to_rgba8();
+
+        assert_eq!(image.dimensions(), (200, 100));
+        assert_eq!(image.get_pixel(20, 50).0, [255, 0, 0, 255]);
+        // Inside the hole
+        assert_eq!(image.get_pixel(100, 10).0[3], 0);
+    }
 }
@@ -182,7 +182,7 @@ pub enum Expression {
 }
 
 /// Paint properties
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PaintProperties {
     // Fill properties
     #[serde(skip_serializing_if = "Option::is_none", rename = "fill-color")]
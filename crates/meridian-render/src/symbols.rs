@@ -389,6 +389,8 @@ impl SpriteSheet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image::ImageFormat;
+    use std::io::Cursor;
 
     #[test]
     fn test_symbol_creation() {
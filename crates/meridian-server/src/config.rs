@@ -39,6 +39,9 @@ pub struct ServerConfig {
     /// Cache configuration
     pub cache: CacheConfig,
 
+    /// WMTS tile cache configuration
+    pub tile_cache: TileCacheConfig,
+
     /// Logging configuration
     pub logging: LoggingConfig,
 }
@@ -163,6 +166,16 @@ pub struct CacheConfig {
     pub max_size_mb: usize,
 }
 
+/// WMTS tile cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileCacheConfig {
+    /// Storage backend (none, directory, mbtiles)
+    pub backend: String,
+
+    /// Root directory of the cached tiles
+    pub path: PathBuf,
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -198,6 +211,7 @@ impl Default for ServerConfig {
             database: DatabaseConfig::default(),
             auth: AuthConfig::default(),
             cache: CacheConfig::default(),
+            tile_cache: TileCacheConfig::default(),
             logging: LoggingConfig::default(),
         }
     }
@@ -277,6 +291,15 @@ impl Default for CacheConfig {
     }
 }
 
+impl Default for TileCacheConfig {
+    fn default() -> Self {
+        Self {
+            backend: "directory".to_string(),
+            path: PathBuf::from("data/tiles"),
+        }
+    }
+}
+
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
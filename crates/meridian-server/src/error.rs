@@ -210,6 +210,16 @@ impl From<validator::ValidationErrors> for ServerError {
     }
 }
 
+impl From<meridian_db::DbError> for ServerError {
+    fn from(err: meridian_db::DbError) -> Self {
+        match err {
+            meridian_db::DbError::NotFound(msg) => ServerError::NotFound(msg),
+            meridian_db::DbError::Locked(msg) => ServerError::Conflict(msg),
+            other => ServerError::Database(other.to_string()),
+        }
+    }
+}
+
 impl From<anyhow::Error> for ServerError {
     fn from(err: anyhow::Error) -> Self {
         ServerError::Internal(err.to_string())
@@ -6,6 +6,7 @@
 pub mod config;
 pub mod error;
 pub mod middleware;
+pub mod ogc;
 pub mod routes;
 pub mod state;
 
@@ -0,0 +1,208 @@
+//! Access to the layers published through the OGC services
+//!
+//! Resolves OGC layer names against the layer repository and loads features
+//! and styles from `meridian-db`.
+
+use meridian_db::{
+    EditSummary, Feature, FeatureEdit, FeatureFilter, FeatureLock, FeatureQuery,
+    FeatureQueryResult, FeatureRepository, Layer, LayerProperty, LayerRepository, LockAction,
+    Pagination, Pool, ReleaseAction,
+};
+use meridian_render::style::{Color, Layer as StyleLayer, LayerType, PaintProperties, PropertyValue, Style};
+use uuid::Uuid;
+
+use super::OgcBBox;
+use crate::{error::ServerResult, ServerError};
+
+/// Maximum number of layers listed in capabilities documents
+const MAX_PUBLISHED_LAYERS: u32 = 1000;
+
+/// Catalog of layers available to the OGC services
+pub struct LayerCatalog {
+    layers: LayerRepository,
+    features: FeatureRepository,
+}
+
+impl LayerCatalog {
+    /// Create a catalog backed by the given pool
+    pub fn new(pool: &Pool) -> Self {
+        Self {
+            layers: LayerRepository::new(pool),
+            features: FeatureRepository::new(pool),
+        }
+    }
+
+    /// Resolve a layer by its OGC name
+    ///
+    /// A namespace prefix (`meridian:roads`) is ignored.
+    pub async fn layer(&self, name: &str) -> ServerResult<Layer> {
+        let name = super::fes::strip_prefix(name);
+        self.layers
+            .find_by_name(name)
+            .await?
+            .ok_or_else(|| ServerError::OgcError(format!("LayerNotDefined: {}", name)))
+    }
+
+    /// List the layers published through the OGC services
+    pub async fn published_layers(&self) -> ServerResult<Vec<Layer>> {
+        let page = self
+            .layers
+            .find_visible(Pagination::new(0, MAX_PUBLISHED_LAYERS))
+            .await?;
+        Ok(page.items)
+    }
+
+    /// Extent of a layer's features in the given SRID
+    pub async fn extent(&self, layer: &Layer, srid: i32) -> ServerResult<Option<meridian_db::BBox>> {
+        Ok(self.features.layer_extent(layer.id, srid).await?)
+    }
+
+    /// Load the features of a layer intersecting a bounding box
+    ///
+    /// Geometries are returned in the CRS of the bounding box.
+    pub async fn features_in_bbox(
+        &self,
+        layer: &Layer,
+        bbox: &OgcBBox,
+        limit: u32,
+    ) -> ServerResult<Vec<Feature>> {
+        Ok(self
+            .features
+            .find_by_layer_in_bbox(layer.id, &bbox.to_db(), bbox.srid, limit)
+            .await?)
+    }
+
+    /// Run a filtered, sorted and paged feature query against a layer
+    pub async fn query(&self, layer: &Layer, query: &FeatureQuery) -> ServerResult<FeatureQueryResult> {
+        Ok(self.features.query(layer.id, query).await?)
+    }
+
+    /// Attribute schema of a layer, inferred from its features
+    pub async fn properties(&self, layer: &Layer) -> ServerResult<Vec<LayerProperty>> {
+        Ok(self.features.layer_properties(layer.id).await?)
+    }
+
+    /// Apply a batch of feature edits in a single database transaction
+    pub async fn apply_edits(
+        &self,
+        edits: &[FeatureEdit],
+        lock_id: Option<&str>,
+        release: ReleaseAction,
+    ) -> ServerResult<EditSummary> {
+        Ok(self.features.apply_edits(edits, lock_id, release).await?)
+    }
+
+    /// Lock the features matching a filter in each of the given layers
+    pub async fn lock_features(
+        &self,
+        targets: &[(Uuid, FeatureFilter)],
+        expiry_secs: u64,
+        action: LockAction,
+    ) -> ServerResult<FeatureLock> {
+        Ok(self.features.lock_features(targets, expiry_secs, action).await?)
+    }
+
+    /// Resolve the style for a layer
+    ///
+    /// An empty or missing style name selects the layer's default style. Layers
+    /// without stored styles get a generic style.
+    pub async fn style(&self, layer: &Layer, name: Option<&str>) -> ServerResult<Style> {
+        let name = name.filter(|n| !n.is_empty() && !n.eq_ignore_ascii_case("default"));
+
+        match self.layers.find_style(layer.id, name).await? {
+            Some(stored) => parse_style(&layer.name, &stored.style),
+            None if name.is_some() => Err(ServerError::OgcError(format!(
+                "StyleNotDefined: {} for layer {}",
+                name.unwrap_or_default(),
+                layer.name
+            ))),
+            None => Ok(default_style(&layer.name)),
+        }
+    }
+}
+
+/// Parse a stored style, accepting either a full style document or a single
+/// style layer definition
+fn parse_style(layer_name: &str, value: &serde_json::Value) -> ServerResult<Style> {
+    if value.get("layers").is_some() {
+        return serde_json::from_value(value.clone())
+            .map_err(|e| ServerError::Internal(format!("Invalid style for {}: {}", layer_name, e)));
+    }
+
+    let style_layer: StyleLayer = serde_json::from_value(value.clone())
+        .map_err(|e| ServerError::Internal(format!("Invalid style for {}: {}", layer_name, e)))?;
+    let mut style = Style::new(layer_name.to_string());
+    style.add_layer(style_layer);
+    Ok(style)
+}
+
+/// Generic style drawing polygons, lines and points of a layer
+pub fn default_style(layer_name: &str) -> Style {
+    let mut style = Style::new(layer_name.to_string());
+
+    let style_layer = |id: &str, layer_type: LayerType, paint: PaintProperties| StyleLayer {
+        id: format!("{}-{}", layer_name, id),
+        layer_type,
+        source: None,
+        source_layer: None,
+        minzoom: None,
+        maxzoom: None,
+        filter: None,
+        paint: Some(paint),
+        layout: None,
+    };
+
+    style.add_layer(style_layer(
+        "fill",
+        LayerType::Fill,
+        PaintProperties {
+            fill_color: Some(PropertyValue::Constant(Color::rgb(51, 136, 255))),
+            fill_opacity: Some(PropertyValue::Constant(0.4)),
+            ..Default::default()
+        },
+    ));
+    style.add_layer(style_layer(
+        "line",
+        LayerType::Line,
+        PaintProperties {
+            line_color: Some(PropertyValue::Constant(Color::rgb(51, 136, 255))),
+            line_width: Some(PropertyValue::Constant(2.0)),
+            ..Default::default()
+        },
+    ));
+    style.add_layer(style_layer(
+        "circle",
+        LayerType::Circle,
+        PaintProperties {
+            circle_color: Some(PropertyValue::Constant(Color::rgb(51, 136, 255))),
+            circle_radius: Some(PropertyValue::Constant(4.0)),
+            ..Default::default()
+        },
+    ));
+
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_style() {
+        let style = default_style("roads");
+        assert_eq!(style.layers.len(), 3);
+        assert_eq!(style.layers[0].id, "roads-fill");
+    }
+
+    #[test]
+    fn test_parse_single_layer_style() {
+        let value = serde_json::json!({
+            "id": "parcels",
+            "type": "fill",
+            "paint": { "fill-color": "#ff0000" }
+        });
+        let style = parse_style("parcels", &value).unwrap();
+        assert_eq!(style.layers.len(), 1);
+        assert_eq!(style.layers[0].layer_type, LayerType::Fill);
+    }
+}
@@ -0,0 +1,917 @@
+//! OGC Common Query Language (CQL2)
+//!
+//! Parses CQL2-text and CQL2-JSON filter expressions into `meridian-db`
+//! feature filters. Supported are the Basic CQL2, advanced comparison
+//! (`LIKE`, `BETWEEN`, `IN`), case-insensitive comparison (`CASEI`) and
+//! spatial function (`S_INTERSECTS`, ...) conformance classes. Temporal
+//! literals (`TIMESTAMP`, `DATE`) are compared as ISO 8601 strings.
+
+use meridian_db::{CompareOp, FeatureFilter, SpatialOp};
+use meridian_io::wkt::WktReader;
+use serde_json::{json, Value};
+
+use super::geojson;
+use crate::{error::ServerResult, ServerError};
+
+/// Coordinate reference system of geometry literals in a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterCrs {
+    /// EPSG code
+    pub srid: i32,
+    /// Whether literals are written latitude first
+    pub lat_lon: bool,
+}
+
+impl Default for FilterCrs {
+    /// CRS84, the default filter CRS of OGC API - Features
+    fn default() -> Self {
+        Self {
+            srid: 4326,
+            lat_lon: false,
+        }
+    }
+}
+
+/// Parse a CQL2-text expression
+pub fn parse_text(text: &str, crs: FilterCrs) -> ServerResult<FeatureFilter> {
+    let tokens = tokenize(text)?;
+    let mut parser = TextParser {
+        source: text,
+        tokens,
+        pos: 0,
+        depth: 0,
+        crs,
+    };
+    let filter = parser.or_expr()?;
+    match parser.peek() {
+        None => Ok(filter),
+        Some(token) => Err(invalid(&format!("unexpected {}", token.kind))),
+    }
+}
+
+/// Parse a CQL2-JSON expression
+pub fn parse_json(value: &Value, crs: FilterCrs) -> ServerResult<FeatureFilter> {
+    JsonParser { crs }.boolean(value)
+}
+
+/// Operand of a predicate
+#[derive(Debug, Clone, PartialEq)]
+enum Scalar {
+    Property { name: String, casei: bool },
+    Literal { value: Value, casei: bool },
+}
+
+impl Scalar {
+    fn casei(self) -> Self {
+        match self {
+            Scalar::Property { name, .. } => Scalar::Property { name, casei: true },
+            Scalar::Literal { value, .. } => Scalar::Literal { value, casei: true },
+        }
+    }
+
+    fn is_casei(&self) -> bool {
+        match self {
+            Scalar::Property { casei, .. } | Scalar::Literal { casei, .. } => *casei,
+        }
+    }
+
+    fn property(self) -> ServerResult<String> {
+        match self {
+            Scalar::Property { name, .. } => Ok(name),
+            Scalar::Literal { value, .. } => Err(invalid(&format!("expected a property, found {}", value))),
+        }
+    }
+
+    fn literal(self) -> ServerResult<Value> {
+        match self {
+            Scalar::Literal { value, .. } => Ok(value),
+            Scalar::Property { name, .. } => Err(invalid(&format!("expected a literal, found {}", name))),
+        }
+    }
+}
+
+/// Operand of a spatial function
+#[derive(Debug, Clone, PartialEq)]
+enum Spatial {
+    Property,
+    Geometry(Value),
+}
+
+fn comparison(left: Scalar, op: CompareOp, right: Scalar) -> ServerResult<FeatureFilter> {
+    let match_case = !(left.is_casei() || right.is_casei());
+    let (property, op, value) = match (left, right) {
+        (Scalar::Property { name, .. }, Scalar::Literal { value, .. }) => (name, op, value),
+        (Scalar::Literal { value, .. }, Scalar::Property { name, .. }) => {
+            let flipped = match op {
+                CompareOp::Lt => CompareOp::Gt,
+                CompareOp::Le => CompareOp::Ge,
+                CompareOp::Gt => CompareOp::Lt,
+                CompareOp::Ge => CompareOp::Le,
+                other => other,
+            };
+            (name, flipped, value)
+        }
+        _ => return Err(invalid("comparisons need one property and one literal")),
+    };
+    Ok(FeatureFilter::Compare {
+        property,
+        op,
+        value,
+        match_case,
+    })
+}
+
+fn like(left: Scalar, pattern: Scalar) -> ServerResult<FeatureFilter> {
+    let match_case = !(left.is_casei() || pattern.is_casei());
+    // CQL2 patterns use the SQL wildcards and backslash escape
+    let pattern = match pattern.literal()? {
+        Value::String(pattern) => pattern,
+        other => return Err(invalid(&format!("LIKE pattern must be a string, found {}", other))),
+    };
+    Ok(FeatureFilter::Like {
+        property: left.property()?,
+        pattern,
+        match_case,
+    })
+}
+
+fn in_list(left: Scalar, items: Vec<Scalar>) -> ServerResult<FeatureFilter> {
+    let options = items
+        .into_iter()
+        .map(|item| comparison(left.clone(), CompareOp::Eq, item))
+        .collect::<ServerResult<Vec<_>>>()?;
+    Ok(match options.len() {
+        0 => FeatureFilter::Exclude,
+        _ => FeatureFilter::Or(options),
+    })
+}
+
+fn spatial(name: &str, left: Spatial, right: Spatial, crs: FilterCrs) -> ServerResult<FeatureFilter> {
+    let op = match name.to_ascii_uppercase().as_str() {
+        "S_INTERSECTS" => SpatialOp::Intersects,
+        "S_DISJOINT" => SpatialOp::Disjoint,
+        "S_CONTAINS" => SpatialOp::Contains,
+        "S_WITHIN" => SpatialOp::Within,
+        "S_TOUCHES" => SpatialOp::Touches,
+        "S_CROSSES" => SpatialOp::Crosses,
+        "S_OVERLAPS" => SpatialOp::Overlaps,
+        "S_EQUALS" => SpatialOp::Equals,
+        _ => return Err(invalid(&format!("unsupported function {}", name))),
+    };
+
+    let (op, mut geometry) = match (left, right) {
+        (Spatial::Property, Spatial::Geometry(geometry)) => (op, geometry),
+        // The predicate reads the other way round with the literal first
+        (Spatial::Geometry(geometry), Spatial::Property) => match op {
+            SpatialOp::Contains => (SpatialOp::Within, geometry),
+            SpatialOp::Within => (SpatialOp::Contains, geometry),
+            other => (other, geometry),
+        },
+        _ => return Err(invalid("spatial functions need one property and one geometry")),
+    };
+
+    if crs.lat_lon {
+        geojson::swap_axes(&mut geometry);
+    }
+    Ok(FeatureFilter::Spatial {
+        op,
+        geometry,
+        srid: crs.srid,
+        distance: None,
+    })
+}
+
+fn bbox_geometry(values: &[f64]) -> ServerResult<Value> {
+    let (min_x, min_y, max_x, max_y) = match *values {
+        [min_x, min_y, max_x, max_y] => (min_x, min_y, max_x, max_y),
+        [min_x, min_y, _, max_x, max_y, _] => (min_x, min_y, max_x, max_y),
+        _ => return Err(invalid("BBOX needs 4 or 6 numbers")),
+    };
+    Ok(json!({
+        "type": "Polygon",
+        "coordinates": [[
+            [min_x, min_y], [max_x, min_y], [max_x, max_y], [min_x, max_y], [min_x, min_y]
+        ]]
+    }))
+}
+
+fn invalid(message: &str) -> ServerError {
+    ServerError::BadRequest(format!("Invalid CQL2 filter: {}", message))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    QuotedName(String),
+    Str(String),
+    Number(f64),
+    Op(CompareOp),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Word(w) => write!(f, "'{}'", w),
+            TokenKind::QuotedName(n) => write!(f, "\"{}\"", n),
+            TokenKind::Str(s) => write!(f, "string '{}'", s),
+            TokenKind::Number(n) => write!(f, "number {}", n),
+            TokenKind::Op(op) => write!(f, "operator {:?}", op),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::Comma => write!(f, "','"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+}
+
+fn tokenize(text: &str) -> ServerResult<Vec<Token>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        let start = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            ',' => {
+                i += 1;
+                TokenKind::Comma
+            }
+            '=' => {
+                i += 1;
+                TokenKind::Op(CompareOp::Eq)
+            }
+            '<' | '>' => {
+                let next = bytes.get(i + 1).map(|b| *b as char);
+                let (op, len) = match (c, next) {
+                    ('<', Some('>')) => (CompareOp::NotEq, 2),
+                    ('<', Some('=')) => (CompareOp::Le, 2),
+                    ('>', Some('=')) => (CompareOp::Ge, 2),
+                    ('<', _) => (CompareOp::Lt, 1),
+                    _ => (CompareOp::Gt, 1),
+                };
+                i += len;
+                TokenKind::Op(op)
+            }
+            '\'' | '"' => {
+                // Quotes are escaped by doubling them
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match text[i..].chars().next() {
+                        None => return Err(invalid("unterminated string")),
+                        Some(q) if q == c && bytes.get(i + 1) == Some(&(c as u8)) => {
+                            value.push(c);
+                            i += 2;
+                        }
+                        Some(q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(other) => {
+                            value.push(other);
+                            i += other.len_utf8();
+                        }
+                    }
+                }
+                if c == '\'' {
+                    TokenKind::Str(value)
+                } else {
+                    TokenKind::QuotedName(value)
+                }
+            }
+            c if c.is_ascii_digit() || c == '.' || ((c == '-' || c == '+') && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit() || *b == b'.')) => {
+                i += 1;
+                while i < bytes.len() {
+                    let d = bytes[i] as char;
+                    let exponent_sign = (d == '-' || d == '+') && matches!(bytes[i - 1], b'e' | b'E');
+                    if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exponent_sign {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let number = text[start..i]
+                    .parse::<f64>()
+                    .map_err(|_| invalid(&format!("invalid number {}", &text[start..i])))?;
+                TokenKind::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < text.len() {
+                    let w = text[i..].chars().next().unwrap_or(' ');
+                    if w.is_alphanumeric() || w == '_' || w == ':' || w == '.' {
+                        i += w.len_utf8();
+                    } else {
+                        break;
+                    }
+                }
+                TokenKind::Word(text[start..i].to_string())
+            }
+            other => return Err(invalid(&format!("unexpected character '{}'", other))),
+        };
+        tokens.push(Token { kind, start });
+    }
+
+    Ok(tokens)
+}
+
+/// Deepest nesting of parentheses, `NOT` and functions a text filter may use
+const MAX_DEPTH: usize = 64;
+
+const WKT_TYPES: [&str; 7] = [
+    "POINT",
+    "LINESTRING",
+    "POLYGON",
+    "MULTIPOINT",
+    "MULTILINESTRING",
+    "MULTIPOLYGON",
+    "GEOMETRYCOLLECTION",
+];
+
+struct TextParser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+    crs: FilterCrs,
+}
+
+impl TextParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> ServerResult<TokenKind> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .ok_or_else(|| invalid("unexpected end of expression"))?;
+        self.pos += 1;
+        Ok(token.kind.clone())
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: TokenKind) -> ServerResult<()> {
+        let found = self.next()?;
+        if found == expected {
+            Ok(())
+        } else {
+            Err(invalid(&format!("expected {}, found {}", expected, found)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ServerResult<()> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(invalid(&format!("expected {}", keyword)))
+        }
+    }
+
+    /// Run `parse` one nesting level deeper, rejecting filters nested past `MAX_DEPTH`
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ServerResult<T>) -> ServerResult<T> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ServerError::OgcError(format!(
+                "InvalidParameterValue: filter is nested more than {} levels deep",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn or_expr(&mut self) -> ServerResult<FeatureFilter> {
+        let mut terms = vec![self.and_expr()?];
+        while self.accept_keyword("OR") {
+            terms.push(self.and_expr()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { FeatureFilter::Or(terms) })
+    }
+
+    fn and_expr(&mut self) -> ServerResult<FeatureFilter> {
+        let mut factors = vec![self.not_expr()?];
+        while self.accept_keyword("AND") {
+            factors.push(self.not_expr()?);
+        }
+        Ok(if factors.len() == 1 { factors.remove(0) } else { FeatureFilter::And(factors) })
+    }
+
+    fn not_expr(&mut self) -> ServerResult<FeatureFilter> {
+        if self.accept_keyword("NOT") {
+            return Ok(FeatureFilter::Not(Box::new(self.nested(Self::not_expr)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> ServerResult<FeatureFilter> {
+        if matches!(self.peek().map(|t| &t.kind), Some(TokenKind::LParen)) {
+            self.pos += 1;
+            let filter = self.nested(Self::or_expr)?;
+            self.expect(TokenKind::RParen)?;
+            return Ok(filter);
+        }
+
+        let is_call = matches!(self.tokens.get(self.pos + 1).map(|t| &t.kind), Some(TokenKind::LParen));
+        if let Some(TokenKind::Word(word)) = self.peek().map(|t| t.kind.clone()) {
+            if is_call && word.to_ascii_uppercase().starts_with("S_") {
+                self.pos += 2;
+                let left = self.spatial_operand()?;
+                self.expect(TokenKind::Comma)?;
+                let right = self.spatial_operand()?;
+                self.expect(TokenKind::RParen)?;
+                return spatial(&word, left, right, self.crs);
+            }
+            if !is_call && (word.eq_ignore_ascii_case("TRUE") || word.eq_ignore_ascii_case("FALSE")) {
+                let next_is_operator = matches!(
+                    self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                    Some(TokenKind::Op(_))
+                );
+                if !next_is_operator {
+                    self.pos += 1;
+                    return Ok(if word.eq_ignore_ascii_case("TRUE") {
+                        FeatureFilter::Include
+                    } else {
+                        FeatureFilter::Exclude
+                    });
+                }
+            }
+        }
+
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> ServerResult<FeatureFilter> {
+        let left = self.scalar()?;
+
+        if let Some(TokenKind::Op(op)) = self.peek().map(|t| t.kind.clone()) {
+            self.pos += 1;
+            let right = self.scalar()?;
+            return comparison(left, op, right);
+        }
+
+        if self.accept_keyword("IS") {
+            let negate = self.accept_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            let filter = FeatureFilter::IsNull(left.property()?);
+            return Ok(negated(filter, negate));
+        }
+
+        let negate = self.accept_keyword("NOT");
+        let filter = if self.accept_keyword("LIKE") {
+            let pattern = self.scalar()?;
+            like(left, pattern)?
+        } else if self.accept_keyword("BETWEEN") {
+            let lower = self.scalar()?.literal()?;
+            self.expect_keyword("AND")?;
+            let upper = self.scalar()?.literal()?;
+            FeatureFilter::Between {
+                property: left.property()?,
+                lower,
+                upper,
+            }
+        } else if self.accept_keyword("IN") {
+            self.expect(TokenKind::LParen)?;
+            let mut items = vec![self.scalar()?];
+            while matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Comma)) {
+                self.pos += 1;
+                items.push(self.scalar()?);
+            }
+            self.expect(TokenKind::RParen)?;
+            in_list(left, items)?
+        } else {
+            return Err(invalid("expected a comparison"));
+        };
+        Ok(negated(filter, negate))
+    }
+
+    fn scalar(&mut self) -> ServerResult<Scalar> {
+        let is_call = matches!(self.tokens.get(self.pos + 1).map(|t| &t.kind), Some(TokenKind::LParen));
+        match self.next()? {
+            TokenKind::Word(word) if is_call => {
+                self.pos += 1;
+                let scalar = match word.to_ascii_uppercase().as_str() {
+                    "CASEI" => self.nested(Self::scalar)?.casei(),
+                    "TIMESTAMP" | "DATE" => match self.next()? {
+                        TokenKind::Str(value) => Scalar::Literal {
+                            value: Value::String(value),
+                            casei: false,
+                        },
+                        other => return Err(invalid(&format!("expected a string, found {}", other))),
+                    },
+                    _ => return Err(invalid(&format!("unsupported function {}", word))),
+                };
+                self.expect(TokenKind::RParen)?;
+                Ok(scalar)
+            }
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("TRUE") || word.eq_ignore_ascii_case("FALSE") => {
+                Ok(Scalar::Literal {
+                    value: Value::Bool(word.eq_ignore_ascii_case("TRUE")),
+                    casei: false,
+                })
+            }
+            TokenKind::Word(name) | TokenKind::QuotedName(name) => Ok(Scalar::Property { name, casei: false }),
+            TokenKind::Str(value) => Ok(Scalar::Literal {
+                value: Value::String(value),
+                casei: false,
+            }),
+            TokenKind::Number(number) => Ok(Scalar::Literal {
+                value: number_value(number),
+                casei: false,
+            }),
+            other => Err(invalid(&format!("expected a property or literal, found {}", other))),
+        }
+    }
+
+    fn spatial_operand(&mut self) -> ServerResult<Spatial> {
+        let token = self.peek().cloned().ok_or_else(|| invalid("unexpected end of expression"))?;
+        let word = match &token.kind {
+            TokenKind::Word(word) => word.to_ascii_uppercase(),
+            TokenKind::QuotedName(_) => {
+                self.pos += 1;
+                return Ok(Spatial::Property);
+            }
+            other => return Err(invalid(&format!("expected a geometry, found {}", other))),
+        };
+
+        if word == "BBOX" {
+            self.pos += 1;
+            self.expect(TokenKind::LParen)?;
+            let mut values = Vec::new();
+            loop {
+                match self.next()? {
+                    TokenKind::Number(n) => values.push(n),
+                    other => return Err(invalid(&format!("expected a number, found {}", other))),
+                }
+                match self.next()? {
+                    TokenKind::Comma => continue,
+                    TokenKind::RParen => break,
+                    other => return Err(invalid(&format!("expected ',' or ')', found {}", other))),
+                }
+            }
+            return Ok(Spatial::Geometry(bbox_geometry(&values)?));
+        }
+
+        if WKT_TYPES.contains(&word.as_str()) {
+            return Ok(Spatial::Geometry(self.wkt(token.start)?));
+        }
+
+        self.pos += 1;
+        Ok(Spatial::Property)
+    }
+
+    /// Read a WKT literal starting at byte `start`, up to its closing parenthesis
+    fn wkt(&mut self, start: usize) -> ServerResult<Value> {
+        let mut depth = 0;
+        let mut end = None;
+        while let Some(token) = self.tokens.get(self.pos) {
+            self.pos += 1;
+            match token.kind {
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(token.start + 1);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let end = end.ok_or_else(|| invalid("unterminated geometry"))?;
+
+        let geometry = WktReader::parse_wkt(&self.source[start..end])
+            .map_err(|e| invalid(&e.to_string()))?;
+        Ok(geojson::from_geometry(&geometry))
+    }
+}
+
+fn negated(filter: FeatureFilter, negate: bool) -> FeatureFilter {
+    if negate {
+        FeatureFilter::Not(Box::new(filter))
+    } else {
+        filter
+    }
+}
+
+/// JSON number for a parsed literal, keeping integers integral
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
+    }
+}
+
+struct JsonParser {
+    crs: FilterCrs,
+}
+
+impl JsonParser {
+    fn boolean(&self, value: &Value) -> ServerResult<FeatureFilter> {
+        if let Value::Bool(b) = value {
+            return Ok(if *b { FeatureFilter::Include } else { FeatureFilter::Exclude });
+        }
+
+        let (op, args) = self.operation(value)?;
+        let arg = |i: usize| args.get(i).ok_or_else(|| invalid(&format!("{} is missing arguments", op)));
+
+        let filter = match op.as_str() {
+            "and" | "or" => {
+                let operands = args.iter().map(|a| self.boolean(a)).collect::<ServerResult<Vec<_>>>()?;
+                if op == "and" {
+                    FeatureFilter::And(operands)
+                } else {
+                    FeatureFilter::Or(operands)
+                }
+            }
+            "not" => FeatureFilter::Not(Box::new(self.boolean(arg(0)?)?)),
+            "=" | "<>" | "<" | "<=" | ">" | ">=" => {
+                let compare_op = match op.as_str() {
+                    "=" => CompareOp::Eq,
+                    "<>" => CompareOp::NotEq,
+                    "<" => CompareOp::Lt,
+                    "<=" => CompareOp::Le,
+                    ">" => CompareOp::Gt,
+                    _ => CompareOp::Ge,
+                };
+                comparison(self.scalar(arg(0)?)?, compare_op, self.scalar(arg(1)?)?)?
+            }
+            "like" => like(self.scalar(arg(0)?)?, self.scalar(arg(1)?)?)?,
+            "between" => FeatureFilter::Between {
+                property: self.scalar(arg(0)?)?.property()?,
+                lower: self.scalar(arg(1)?)?.literal()?,
+                upper: self.scalar(arg(2)?)?.literal()?,
+            },
+            "in" => {
+                let items = arg(1)?
+                    .as_array()
+                    .ok_or_else(|| invalid("in needs a list"))?
+                    .iter()
+                    .map(|item| self.scalar(item))
+                    .collect::<ServerResult<Vec<_>>>()?;
+                in_list(self.scalar(arg(0)?)?, items)?
+            }
+            "isNull" => FeatureFilter::IsNull(self.scalar(arg(0)?)?.property()?),
+            name if name.starts_with("s_") => {
+                spatial(name, self.spatial(arg(0)?)?, self.spatial(arg(1)?)?, self.crs)?
+            }
+            other => return Err(invalid(&format!("unsupported operator {}", other))),
+        };
+        Ok(filter)
+    }
+
+    fn operation<'v>(&self, value: &'v Value) -> ServerResult<(String, &'v Vec<Value>)> {
+        let op = value
+            .get("op")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid(&format!("expected an operation, found {}", value)))?;
+        let args = value
+            .get("args")
+            .and_then(Value::as_array)
+            .ok_or_else(|| invalid(&format!("{} without args", op)))?;
+        Ok((op.to_string(), args))
+    }
+
+    fn scalar(&self, value: &Value) -> ServerResult<Scalar> {
+        match value {
+            Value::Object(object) => {
+                if let Some(name) = object.get("property").and_then(Value::as_str) {
+                    return Ok(Scalar::Property {
+                        name: name.to_string(),
+                        casei: false,
+                    });
+                }
+                if let Some(instant) = object.get("timestamp").or_else(|| object.get("date")) {
+                    return Ok(Scalar::Literal {
+                        value: instant.clone(),
+                        casei: false,
+                    });
+                }
+                match self.operation(value)? {
+                    (op, args) if op == "casei" => {
+                        let inner = args.first().ok_or_else(|| invalid("casei without argument"))?;
+                        Ok(self.scalar(inner)?.casei())
+                    }
+                    (op, _) => Err(invalid(&format!("unsupported function {}", op))),
+                }
+            }
+            Value::Array(_) | Value::Null => Err(invalid(&format!("unexpected {}", value))),
+            literal => Ok(Scalar::Literal {
+                value: literal.clone(),
+                casei: false,
+            }),
+        }
+    }
+
+    fn spatial(&self, value: &Value) -> ServerResult<Spatial> {
+        if value.get("property").is_some() {
+            return Ok(Spatial::Property);
+        }
+        if let Some(bbox) = value.get("bbox").and_then(Value::as_array) {
+            let values = bbox
+                .iter()
+                .map(|v| v.as_f64().ok_or_else(|| invalid("bbox values must be numbers")))
+                .collect::<ServerResult<Vec<_>>>()?;
+            return Ok(Spatial::Geometry(bbox_geometry(&values)?));
+        }
+        // Validate GeoJSON literals before they reach the database
+        geojson::to_geometry(value)?;
+        Ok(Spatial::Geometry(value.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn text(filter: &str) -> FeatureFilter {
+        parse_text(filter, FilterCrs::default()).unwrap()
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(
+            text("lanes >= 2 AND NOT name = 'Main St'"),
+            FeatureFilter::And(vec![
+                FeatureFilter::Compare {
+                    property: "lanes".to_string(),
+                    op: CompareOp::Ge,
+                    value: json!(2),
+                    match_case: true,
+                },
+                FeatureFilter::Not(Box::new(FeatureFilter::Compare {
+                    property: "name".to_string(),
+                    op: CompareOp::Eq,
+                    value: json!("Main St"),
+                    match_case: true,
+                })),
+            ])
+        );
+
+        // Literal first flips the operator
+        assert_eq!(
+            text("10.5 > width"),
+            FeatureFilter::Compare {
+                property: "width".to_string(),
+                op: CompareOp::Lt,
+                value: json!(10.5),
+                match_case: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_advanced_comparisons() {
+        assert_eq!(
+            text("CASEI(name) LIKE 'main%'"),
+            FeatureFilter::Like {
+                property: "name".to_string(),
+                pattern: "main%".to_string(),
+                match_case: false,
+            }
+        );
+        assert_eq!(
+            text("\"road class\" IN ('a', 'b')"),
+            FeatureFilter::Or(vec![
+                FeatureFilter::Compare {
+                    property: "road class".to_string(),
+                    op: CompareOp::Eq,
+                    value: json!("a"),
+                    match_case: true,
+                },
+                FeatureFilter::Compare {
+                    property: "road class".to_string(),
+                    op: CompareOp::Eq,
+                    value: json!("b"),
+                    match_case: true,
+                },
+            ])
+        );
+        assert_eq!(
+            text("(lanes NOT BETWEEN 1 AND 3) OR surface IS NOT NULL"),
+            FeatureFilter::Or(vec![
+                FeatureFilter::Not(Box::new(FeatureFilter::Between {
+                    property: "lanes".to_string(),
+                    lower: json!(1),
+                    upper: json!(3),
+                })),
+                FeatureFilter::Not(Box::new(FeatureFilter::IsNull("surface".to_string()))),
+            ])
+        );
+        assert_eq!(
+            text("opened > TIMESTAMP('2020-01-01T00:00:00Z')"),
+            FeatureFilter::Compare {
+                property: "opened".to_string(),
+                op: CompareOp::Gt,
+                value: json!("2020-01-01T00:00:00Z"),
+                match_case: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_spatial_text() {
+        match text("S_INTERSECTS(geometry, BBOX(-10, 40, 10, 50))") {
+            FeatureFilter::Spatial { op, geometry, srid, .. } => {
+                assert_eq!(op, SpatialOp::Intersects);
+                assert_eq!(srid, 4326);
+                assert_eq!(geometry["coordinates"][0][2], json!([10.0, 50.0]));
+            }
+            other => panic!("unexpected filter {:?}", other),
+        }
+
+        let crs = FilterCrs { srid: 4326, lat_lon: true };
+        match parse_text("S_WITHIN(POINT(50 10), geometry)", crs).unwrap() {
+            FeatureFilter::Spatial { op, geometry, .. } => {
+                assert_eq!(op, SpatialOp::Contains);
+                assert_eq!(geometry, json!({"type": "Point", "coordinates": [10.0, 50.0]}));
+            }
+            other => panic!("unexpected filter {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_text() {
+        for filter in ["name =", "name = 'open", "lanes > 2 lanes", "S_FOO(geometry, POINT(0 0))", "a = b"] {
+            assert!(parse_text(filter, FilterCrs::default()).is_err(), "{}", filter);
+        }
+
+        let nested = |depth: usize| format!("{}lanes > 2{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_text(&nested(MAX_DEPTH), FilterCrs::default()).is_ok());
+        let error = parse_text(&nested(500), FilterCrs::default()).unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert!(error.to_string().contains("InvalidParameterValue"), "{}", error);
+        assert!(parse_text(&format!("{}lanes > 2", "NOT ".repeat(500)), FilterCrs::default()).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let filter = json!({
+            "op": "and",
+            "args": [
+                { "op": "=", "args": [{ "op": "casei", "args": [{ "property": "name" }] }, "main st"] },
+                { "op": "in", "args": [{ "property": "lanes" }, [2, 4]] },
+                { "op": "s_intersects", "args": [
+                    { "property": "geometry" },
+                    { "type": "Point", "coordinates": [1.0, 2.0] }
+                ]},
+                { "op": "isNull", "args": [{ "property": "surface" }] }
+            ]
+        });
+
+        match parse_json(&filter, FilterCrs::default()).unwrap() {
+            FeatureFilter::And(parts) => {
+                assert_eq!(parts.len(), 4);
+                assert_eq!(
+                    parts[0],
+                    FeatureFilter::Compare {
+                        property: "name".to_string(),
+                        op: CompareOp::Eq,
+                        value: json!("main st"),
+                        match_case: false,
+                    }
+                );
+                assert!(matches!(&parts[1], FeatureFilter::Or(options) if options.len() == 2));
+                assert!(matches!(&parts[2], FeatureFilter::Spatial { op: SpatialOp::Intersects, .. }));
+                assert_eq!(parts[3], FeatureFilter::IsNull("surface".to_string()));
+            }
+            other => panic!("unexpected filter {:?}", other),
+        }
+
+        assert!(parse_json(&json!({ "op": "near", "args": [] }), FilterCrs::default()).is_err());
+    }
+}
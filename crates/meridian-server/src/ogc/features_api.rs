@@ -0,0 +1,622 @@
+//! OGC API - Features (Parts 1, 2 and 3)
+//!
+//! Builds the JSON documents served under `/ogc/features`: landing page,
+//! conformance declaration, collections, queryables and GeoJSON feature
+//! collections with paging links. Items requests are turned into
+//! `meridian-db` feature queries; `bbox`, `datetime` and CQL2 `filter`
+//! parameters become feature filters and `crs` selects the output SRID.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use meridian_core::crs::Crs;
+use meridian_db::{BBox, Feature, FeatureFilter, FeatureQuery, FeatureQueryResult, Layer, LayerProperty};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{cql2, geojson, is_lat_lon_order, wfs::GEOMETRY_PROPERTY, LayerCatalog};
+use crate::{error::ServerResult, routes::ogc_api::ItemsParams, ServerError};
+
+/// Number of features per page when `limit` is not given
+pub const DEFAULT_LIMIT: u64 = 10;
+
+/// Largest accepted `limit`; larger values are reduced to it
+pub const MAX_LIMIT: u64 = 10_000;
+
+/// Conformance classes implemented by the service
+pub const CONFORMANCE: [&str; 13] = [
+    "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/core",
+    "http://www.opengis.net/spec/ogcapi-features-1/1.0/conf/geojson",
+    "http://www.opengis.net/spec/ogcapi-features-2/1.0/conf/crs",
+    "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/filter",
+    "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/features-filter",
+    "http://www.opengis.net/spec/ogcapi-features-3/1.0/conf/queryables",
+    "http://www.opengis.net/spec/cql2/1.0/conf/cql2-text",
+    "http://www.opengis.net/spec/cql2/1.0/conf/cql2-json",
+    "http://www.opengis.net/spec/cql2/1.0/conf/basic-cql2",
+    "http://www.opengis.net/spec/cql2/1.0/conf/advanced-comparison-operators",
+    "http://www.opengis.net/spec/cql2/1.0/conf/case-insensitive-comparison",
+    "http://www.opengis.net/spec/cql2/1.0/conf/basic-spatial-functions",
+    "http://www.opengis.net/spec/cql2/1.0/conf/spatial-functions",
+];
+
+/// Layer metadata key naming the property used for `datetime` filtering
+///
+/// Layers without it are filtered on the features' last update time.
+pub const TEMPORAL_PROPERTY_KEY: &str = "temporal_property";
+
+/// Inclusive time interval; `None` leaves an end open
+pub type Interval = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+const JSON: &str = "application/json";
+const GEOJSON: &str = "application/geo+json";
+
+/// A CRS accepted by a collection, with the SRID used for database queries
+#[derive(Debug, Clone)]
+pub struct CollectionCrs {
+    /// Parsed CRS
+    pub crs: Crs,
+    /// EPSG code of the CRS
+    pub srid: i32,
+    /// Whether coordinates are written latitude first
+    pub lat_lon: bool,
+}
+
+impl CollectionCrs {
+    /// OGC URI of the CRS
+    pub fn uri(&self) -> String {
+        self.crs.uri().unwrap_or_default()
+    }
+}
+
+/// URIs of the CRSs a collection can be requested in
+///
+/// CRS84 comes first as the default; the layer's storage CRS is always
+/// included.
+pub fn supported_crs(layer: &Layer) -> Vec<String> {
+    let mut uris = vec![
+        meridian_core::crs::CRS84_URI.to_string(),
+        epsg_uri(4326),
+        epsg_uri(3857),
+    ];
+    let storage = epsg_uri(layer.srid);
+    if !uris.contains(&storage) {
+        uris.push(storage);
+    }
+    uris
+}
+
+/// Resolve a `crs`, `bbox-crs` or `filter-crs` parameter for a collection
+///
+/// A missing value selects CRS84. CRSs the collection does not advertise
+/// are rejected.
+pub fn resolve_crs(layer: &Layer, value: Option<&str>) -> ServerResult<CollectionCrs> {
+    let crs = match value.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(value) => Crs::from_uri(value).map_err(|e| ServerError::BadRequest(e.to_string()))?,
+        None => Crs::crs84(),
+    };
+
+    let uri = crs.uri().unwrap_or_default();
+    if !supported_crs(layer).contains(&uri) {
+        return Err(ServerError::BadRequest(format!(
+            "CRS {} is not supported by collection {}",
+            value.unwrap_or_default(),
+            layer.name
+        )));
+    }
+
+    let srid = crs.epsg.unwrap_or(4326) as i32;
+    let lat_lon = !crs.is_crs84() && is_lat_lon_order(srid);
+    Ok(CollectionCrs { crs, srid, lat_lon })
+}
+
+fn epsg_uri(srid: i32) -> String {
+    format!("http://www.opengis.net/def/crs/EPSG/0/{}", srid)
+}
+
+/// Parse a `datetime` parameter into an inclusive interval
+///
+/// Accepts an RFC 3339 instant, a full date (covering the whole day) or an
+/// interval of two of those separated by `/`, where `..` or an empty value
+/// leaves that end open.
+pub fn parse_datetime(value: &str) -> ServerResult<Interval> {
+    let value = value.trim();
+    let invalid = || ServerError::BadRequest(format!("Invalid datetime: {}", value));
+
+    let bound = |part: &str, end: bool| -> ServerResult<Option<DateTime<Utc>>> {
+        let part = part.trim();
+        if part.is_empty() || part == ".." {
+            return Ok(None);
+        }
+        if let Ok(instant) = DateTime::parse_from_rfc3339(part) {
+            return Ok(Some(instant.with_timezone(&Utc)));
+        }
+        let date = NaiveDate::parse_from_str(part, "%Y-%m-%d").map_err(|_| invalid())?;
+        let time = if end {
+            date.and_hms_milli_opt(23, 59, 59, 999)
+        } else {
+            date.and_hms_opt(0, 0, 0)
+        };
+        Ok(time.map(|t| t.and_utc()))
+    };
+
+    match value.split_once('/') {
+        Some((start, end)) => {
+            let (start, end) = (bound(start, false)?, bound(end, true)?);
+            if let (Some(s), Some(e)) = (start, end) {
+                if s > e {
+                    return Err(invalid());
+                }
+            }
+            Ok((start, end))
+        }
+        None if value.is_empty() || value == ".." => Err(invalid()),
+        None => Ok((bound(value, false)?, bound(value, true)?)),
+    }
+}
+
+/// A validated items request
+#[derive(Debug, Clone)]
+pub struct ItemsRequest {
+    /// Filter from `bbox`, `datetime` and `filter`
+    pub filter: FeatureFilter,
+    /// Index of the first feature to return
+    pub offset: u64,
+    /// Maximum number of features to return
+    pub limit: u64,
+    /// Output CRS
+    pub crs: CollectionCrs,
+}
+
+impl ItemsRequest {
+    /// Build an items request for a collection from query parameters
+    pub fn from_params(layer: &Layer, params: &ItemsParams) -> ServerResult<Self> {
+        check_format(params.f.as_deref())?;
+
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let offset = params.offset.unwrap_or(0);
+        let crs = resolve_crs(layer, params.crs.as_deref())?;
+
+        let mut filters = Vec::new();
+
+        if let Some(bbox) = params.bbox.as_deref().filter(|b| !b.is_empty()) {
+            let bbox_crs = resolve_crs(layer, params.bbox_crs.as_deref())?;
+            filters.push(parse_bbox(bbox, &bbox_crs)?);
+        } else if params.bbox_crs.is_some() {
+            return Err(ServerError::BadRequest("bbox-crs requires bbox".to_string()));
+        }
+
+        if let Some(datetime) = params.datetime.as_deref() {
+            let (start, end) = parse_datetime(datetime)?;
+            let property = layer
+                .metadata
+                .get(TEMPORAL_PROPERTY_KEY)
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            filters.push(FeatureFilter::Temporal { property, start, end });
+        }
+
+        if let Some(filter) = params.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+            let filter_crs = resolve_crs(layer, params.filter_crs.as_deref())?;
+            let filter_crs = cql2::FilterCrs {
+                srid: filter_crs.srid,
+                lat_lon: filter_crs.lat_lon,
+            };
+            let filter = match params.filter_lang.as_deref().unwrap_or("cql2-text") {
+                "cql2-text" => cql2::parse_text(filter, filter_crs)?,
+                "cql2-json" => {
+                    let value: Value = serde_json::from_str(filter)
+                        .map_err(|e| ServerError::BadRequest(format!("Invalid CQL2 filter: {}", e)))?;
+                    cql2::parse_json(&value, filter_crs)?
+                }
+                other => {
+                    return Err(ServerError::BadRequest(format!("Unsupported filter-lang: {}", other)));
+                }
+            };
+            filters.push(filter);
+        }
+
+        Ok(Self {
+            filter: FeatureFilter::and(filters),
+            offset,
+            limit,
+            crs,
+        })
+    }
+
+    /// Database query for the request
+    pub fn query(&self) -> FeatureQuery {
+        FeatureQuery::new(self.filter.clone())
+            .with_paging(self.offset, Some(self.limit))
+            .with_output_srid(self.crs.srid)
+    }
+}
+
+/// Reject output formats other than JSON
+pub fn check_format(f: Option<&str>) -> ServerResult<()> {
+    match f.map(str::to_ascii_lowercase).as_deref() {
+        None | Some("") | Some("json") | Some("geojson") => Ok(()),
+        Some(other) => Err(ServerError::BadRequest(format!("Unsupported format: {}", other))),
+    }
+}
+
+/// Parse a `bbox` parameter of 4 or 6 numbers given in `crs`
+fn parse_bbox(value: &str, crs: &CollectionCrs) -> ServerResult<FeatureFilter> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ServerError::BadRequest(format!("Invalid bbox: {}", value)))?;
+
+    let (a, b, c, d) = match values[..] {
+        [a, b, c, d] | [a, b, _, c, d, _] => (a, b, c, d),
+        _ => return Err(ServerError::BadRequest(format!("Invalid bbox: {}", value))),
+    };
+    let (min_x, min_y, max_x, max_y) = if crs.lat_lon { (b, a, d, c) } else { (a, b, c, d) };
+
+    if min_y > max_y || (min_x > max_x && !crs.crs.is_crs84()) {
+        return Err(ServerError::BadRequest(format!("Invalid bbox extent: {}", value)));
+    }
+
+    // A CRS84 box with min_x > max_x crosses the antimeridian
+    if min_x > max_x {
+        return Ok(FeatureFilter::Or(vec![
+            FeatureFilter::bbox(min_x, min_y, 180.0, max_y, crs.srid),
+            FeatureFilter::bbox(-180.0, min_y, max_x, max_y, crs.srid),
+        ]));
+    }
+    Ok(FeatureFilter::bbox(min_x, min_y, max_x, max_y, crs.srid))
+}
+
+/// Resolve the layer behind a collection id
+pub async fn collection_layer(catalog: &LayerCatalog, id: &str) -> ServerResult<Layer> {
+    catalog.layer(id).await.map_err(|e| match e {
+        ServerError::OgcError(_) => ServerError::NotFound(format!("Collection {}", id)),
+        other => other,
+    })
+}
+
+/// Load a single feature of a collection, with geometry in `crs`
+pub async fn get_feature(
+    catalog: &LayerCatalog,
+    layer: &Layer,
+    fid: &str,
+    crs: &CollectionCrs,
+) -> ServerResult<Feature> {
+    let not_found = || ServerError::NotFound(format!("Feature {} in collection {}", fid, layer.name));
+    let id = Uuid::parse_str(fid).map_err(|_| not_found())?;
+
+    let query = FeatureQuery::new(FeatureFilter::ResourceId(vec![id]))
+        .with_paging(0, Some(1))
+        .with_output_srid(crs.srid);
+    let result = catalog.query(layer, &query).await?;
+    result.features.into_iter().next().ok_or_else(not_found)
+}
+
+fn link(href: String, rel: &str, media_type: &str, title: &str) -> Value {
+    json!({
+        "href": href,
+        "rel": rel,
+        "type": media_type,
+        "title": title,
+    })
+}
+
+/// Landing page document
+pub fn landing_page(base: &str) -> Value {
+    json!({
+        "title": "Meridian OGC API - Features",
+        "description": "Access to the Meridian layers as OGC API - Features collections",
+        "links": [
+            link(base.to_string(), "self", JSON, "This document"),
+            link(format!("{}/conformance", base), "conformance", JSON, "Conformance classes"),
+            link(format!("{}/collections", base), "data", JSON, "Feature collections"),
+        ],
+    })
+}
+
+/// Conformance declaration
+pub fn conformance() -> Value {
+    json!({ "conformsTo": CONFORMANCE })
+}
+
+/// Description of a single collection
+///
+/// `extent` is the layer's bounding box in CRS84.
+pub fn collection(base: &str, layer: &Layer, extent: Option<&BBox>) -> Value {
+    let url = format!("{}/collections/{}", base, layer.name);
+
+    let mut collection = json!({
+        "id": layer.name,
+        "title": layer.name,
+        "itemType": "feature",
+        "crs": supported_crs(layer),
+        "storageCrs": epsg_uri(layer.srid),
+        "links": [
+            link(url.clone(), "self", JSON, "This collection"),
+            link(format!("{}/items", url), "items", GEOJSON, "Features"),
+            link(
+                format!("{}/queryables", url),
+                "http://www.opengis.net/def/rel/ogc/1.0/queryables",
+                "application/schema+json",
+                "Queryable properties",
+            ),
+        ],
+    });
+
+    if let Some(description) = &layer.description {
+        collection["description"] = json!(description);
+    }
+    if let Some(extent) = extent {
+        collection["extent"] = json!({
+            "spatial": {
+                "bbox": [[extent.min_x, extent.min_y, extent.max_x, extent.max_y]],
+                "crs": meridian_core::crs::CRS84_URI,
+            }
+        });
+    }
+    collection
+}
+
+/// Collections document
+pub fn collections(base: &str, layers: &[(Layer, Option<BBox>)]) -> Value {
+    json!({
+        "links": [
+            link(format!("{}/collections", base), "self", JSON, "Feature collections"),
+        ],
+        "collections": layers
+            .iter()
+            .map(|(layer, extent)| collection(base, layer, extent.as_ref()))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// JSON Schema of the properties of a collection that filters may use
+pub fn queryables(base: &str, layer: &Layer, properties: &[LayerProperty]) -> Value {
+    let mut schema = serde_json::Map::new();
+    schema.insert(
+        GEOMETRY_PROPERTY.to_string(),
+        json!({ "format": geometry_format(layer.geometry_type.as_deref()) }),
+    );
+
+    for property in properties {
+        let json_type = match property.json_type() {
+            Some("number") if property.integral => "integer",
+            Some(t @ ("number" | "string" | "boolean")) => t,
+            _ => continue,
+        };
+        schema.insert(property.name.clone(), json!({ "type": json_type }));
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("{}/collections/{}/queryables", base, layer.name),
+        "type": "object",
+        "title": layer.name,
+        "properties": schema,
+        "additionalProperties": false,
+    })
+}
+
+/// JSON Schema `format` of a layer's geometry
+fn geometry_format(geometry_type: Option<&str>) -> &'static str {
+    match geometry_type.map(str::to_ascii_lowercase).as_deref() {
+        Some("point") => "geometry-point",
+        Some("multipoint") => "geometry-multipoint",
+        Some("linestring") => "geometry-linestring",
+        Some("multilinestring") => "geometry-multilinestring",
+        Some("polygon") => "geometry-polygon",
+        Some("multipolygon") => "geometry-multipolygon",
+        _ => "geometry-any",
+    }
+}
+
+/// GeoJSON feature, with geometry axes swapped for latitude-first CRSs
+pub fn feature_json(feature: &Feature, crs: &CollectionCrs) -> Value {
+    let mut geometry = feature.geometry_json.clone().unwrap_or(Value::Null);
+    if crs.lat_lon {
+        geojson::swap_axes(&mut geometry);
+    }
+    json!({
+        "type": "Feature",
+        "id": feature.id.to_string(),
+        "geometry": geometry,
+        "properties": feature.properties,
+    })
+}
+
+/// Single feature document
+pub fn feature_document(base: &str, layer: &Layer, feature: &Feature, crs: &CollectionCrs) -> Value {
+    let url = format!("{}/collections/{}", base, layer.name);
+    let mut document = feature_json(feature, crs);
+    document["links"] = json!([
+        link(format!("{}/items/{}", url, feature.id), "self", GEOJSON, "This feature"),
+        link(url, "collection", JSON, "The collection"),
+    ]);
+    document
+}
+
+/// Feature collection document for an items request
+///
+/// `next` and `prev` links repeat the request parameters with a shifted
+/// `offset`.
+pub fn items_document(
+    base: &str,
+    layer: &Layer,
+    params: &ItemsParams,
+    request: &ItemsRequest,
+    result: &FeatureQueryResult,
+) -> ServerResult<Value> {
+    let url = format!("{}/collections/{}", base, layer.name);
+    let page_url = |offset: u64| -> ServerResult<String> {
+        let mut params = params.clone();
+        params.offset = Some(offset).filter(|o| *o > 0);
+        params.limit = Some(request.limit);
+        let query = serde_urlencoded::to_string(&params)
+            .map_err(|e| ServerError::Internal(format!("Failed to encode link: {}", e)))?;
+        Ok(format!("{}/items?{}", url, query))
+    };
+
+    let returned = result.features.len() as u64;
+    let mut links = vec![
+        link(page_url(request.offset)?, "self", GEOJSON, "This page"),
+        link(url.clone(), "collection", JSON, "The collection"),
+    ];
+    if request.offset + returned < result.number_matched {
+        links.push(link(page_url(request.offset + returned)?, "next", GEOJSON, "Next page"));
+    }
+    if request.offset > 0 {
+        links.push(link(
+            page_url(request.offset.saturating_sub(request.limit))?,
+            "prev",
+            GEOJSON,
+            "Previous page",
+        ));
+    }
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "timeStamp": Utc::now().to_rfc3339(),
+        "numberMatched": result.number_matched,
+        "numberReturned": returned,
+        "links": links,
+        "features": result
+            .features
+            .iter()
+            .map(|feature| feature_json(feature, &request.crs))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn layer() -> Layer {
+        Layer::new("roads".to_string(), "vector".to_string(), 25832)
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let instant = Utc.with_ymd_and_hms(2020, 5, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            parse_datetime("2020-05-01T12:00:00Z").unwrap(),
+            (Some(instant), Some(instant))
+        );
+        assert_eq!(parse_datetime("../2020-05-01T12:00:00Z").unwrap(), (None, Some(instant)));
+
+        let (start, end) = parse_datetime("2020-05-01/..").unwrap();
+        assert_eq!(start, Some(Utc.with_ymd_and_hms(2020, 5, 1, 0, 0, 0).unwrap()));
+        assert_eq!(end, None);
+
+        let (start, end) = parse_datetime("2020-05-01").unwrap();
+        assert!(start.unwrap() < instant && instant < end.unwrap());
+
+        assert!(parse_datetime("2020-05-02/2020-05-01").is_err());
+        assert!(parse_datetime("yesterday").is_err());
+        assert!(parse_datetime("..").is_err());
+    }
+
+    #[test]
+    fn test_resolve_crs() {
+        let layer = layer();
+        assert_eq!(supported_crs(&layer).len(), 4);
+
+        let crs84 = resolve_crs(&layer, None).unwrap();
+        assert_eq!((crs84.srid, crs84.lat_lon), (4326, false));
+
+        let wgs84 = resolve_crs(&layer, Some("http://www.opengis.net/def/crs/EPSG/0/4326")).unwrap();
+        assert_eq!((wgs84.srid, wgs84.lat_lon), (4326, true));
+
+        let storage = resolve_crs(&layer, Some("http://www.opengis.net/def/crs/EPSG/0/25832")).unwrap();
+        assert_eq!(storage.uri(), "http://www.opengis.net/def/crs/EPSG/0/25832");
+
+        assert!(resolve_crs(&layer, Some("http://www.opengis.net/def/crs/EPSG/0/2056")).is_err());
+    }
+
+    #[test]
+    fn test_items_request() {
+        let layer = layer();
+        let params = ItemsParams {
+            bbox: Some("40,-10,50,10".to_string()),
+            bbox_crs: Some("http://www.opengis.net/def/crs/EPSG/0/4326".to_string()),
+            limit: Some(1_000_000),
+            filter: Some("lanes > 2".to_string()),
+            ..Default::default()
+        };
+        let request = ItemsRequest::from_params(&layer, &params).unwrap();
+        assert_eq!(request.limit, MAX_LIMIT);
+        assert_eq!(request.offset, 0);
+
+        match &request.filter {
+            FeatureFilter::And(filters) => {
+                assert_eq!(filters[0], FeatureFilter::bbox(-10.0, 40.0, 10.0, 50.0, 4326));
+                assert!(matches!(filters[1], FeatureFilter::Compare { .. }));
+            }
+            other => panic!("unexpected filter {:?}", other),
+        }
+
+        let params = ItemsParams {
+            filter_lang: Some("sql".to_string()),
+            filter: Some("1 = 1".to_string()),
+            ..Default::default()
+        };
+        assert!(ItemsRequest::from_params(&layer, &params).is_err());
+    }
+
+    #[test]
+    fn test_paging_links() {
+        let layer = layer();
+        let params = ItemsParams {
+            offset: Some(10),
+            limit: Some(10),
+            datetime: Some("2020-01-01/..".to_string()),
+            ..Default::default()
+        };
+        let request = ItemsRequest::from_params(&layer, &params).unwrap();
+        let result = FeatureQueryResult {
+            features: (0..10).map(|_| Feature::new(layer.id, json!({}))).collect(),
+            number_matched: 25,
+        };
+
+        let document = items_document("http://localhost/ogc/features", &layer, &params, &request, &result).unwrap();
+        assert_eq!(document["numberReturned"], json!(10));
+        let links = document["links"].as_array().unwrap();
+        let href = |rel: &str| {
+            links
+                .iter()
+                .find(|l| l["rel"] == rel)
+                .and_then(|l| l["href"].as_str())
+                .map(str::to_string)
+        };
+        assert_eq!(
+            href("next").as_deref(),
+            Some("http://localhost/ogc/features/collections/roads/items?limit=10&offset=20&datetime=2020-01-01%2F..")
+        );
+        assert_eq!(
+            href("prev").as_deref(),
+            Some("http://localhost/ogc/features/collections/roads/items?limit=10&datetime=2020-01-01%2F..")
+        );
+    }
+
+    #[test]
+    fn test_queryables() {
+        let properties = vec![
+            LayerProperty {
+                name: "lanes".to_string(),
+                types: vec!["number".to_string()],
+                integral: true,
+                nullable: false,
+            },
+            LayerProperty {
+                name: "tags".to_string(),
+                types: vec!["object".to_string()],
+                integral: false,
+                nullable: true,
+            },
+        ];
+        let schema = queryables("http://localhost/ogc/features", &layer(), &properties);
+        assert_eq!(schema["properties"]["lanes"]["type"], "integer");
+        assert_eq!(schema["properties"]["geometry"]["format"], "geometry-any");
+        assert!(schema["properties"].get("tags").is_none());
+    }
+}
@@ -0,0 +1,355 @@
+//! OGC Filter Encoding 2.0
+//!
+//! Parses `fes:Filter` documents into `meridian-db` feature filters. Filter
+//! Encoding 1.1 element names (`ogc:PropertyName`, `ogc:FeatureId`) are
+//! accepted as well, since many clients still send them.
+
+use meridian_db::{CompareOp, FeatureFilter, SpatialOp};
+use roxmltree::{Document, Node};
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{geojson, gml};
+use crate::{error::ServerResult, ServerError};
+
+/// Parse a filter document
+///
+/// Geometries without a `srsName` are taken to be in `default_srid`.
+pub fn parse_filter(xml: &str, default_srid: i32) -> ServerResult<FeatureFilter> {
+    let document = Document::parse(xml)
+        .map_err(|e| ServerError::OgcError(format!("Invalid filter XML: {}", e)))?;
+    parse_filter_element(document.root_element(), default_srid)
+}
+
+/// Parse a `fes:Filter` element
+pub fn parse_filter_element(node: Node<'_, '_>, default_srid: i32) -> ServerResult<FeatureFilter> {
+    if node.tag_name().name() != "Filter" {
+        return Err(ServerError::OgcError(format!(
+            "Expected Filter element, found {}",
+            node.tag_name().name()
+        )));
+    }
+
+    let parser = FilterParser { default_srid };
+
+    // Several id elements directly below the filter select features by id
+    let ids: Vec<Node<'_, '_>> = elements(node)
+        .filter(|c| matches!(c.tag_name().name(), "ResourceId" | "FeatureId" | "GmlObjectId"))
+        .collect();
+    if !ids.is_empty() {
+        return parser.resource_ids(&ids);
+    }
+
+    let mut operators = elements(node);
+    match (operators.next(), operators.next()) {
+        (Some(op), None) => parser.operator(op),
+        _ => Err(ServerError::OgcError(
+            "Filter must contain exactly one operator".to_string(),
+        )),
+    }
+}
+
+/// Parse a resource id of the form `layer.uuid`
+pub fn parse_resource_id(rid: &str) -> ServerResult<(String, Uuid)> {
+    let (layer, id) = rid
+        .rsplit_once('.')
+        .ok_or_else(|| ServerError::OgcError(format!("Invalid resource id: {}", rid)))?;
+    let id = Uuid::parse_str(id)
+        .map_err(|_| ServerError::OgcError(format!("Invalid resource id: {}", rid)))?;
+    Ok((strip_prefix(layer).to_string(), id))
+}
+
+/// Strip a namespace prefix from a qualified name
+pub fn strip_prefix(name: &str) -> &str {
+    name.rsplit_once(':').map(|(_, local)| local).unwrap_or(name)
+}
+
+struct FilterParser {
+    default_srid: i32,
+}
+
+impl FilterParser {
+    fn operator(&self, node: Node<'_, '_>) -> ServerResult<FeatureFilter> {
+        let name = node.tag_name().name();
+        let filter = match name {
+            "And" | "Or" => {
+                let operands = elements(node)
+                    .map(|c| self.operator(c))
+                    .collect::<ServerResult<Vec<_>>>()?;
+                if operands.len() < 2 {
+                    return Err(ServerError::OgcError(format!("{} needs two operands", name)));
+                }
+                if name == "And" {
+                    FeatureFilter::And(operands)
+                } else {
+                    FeatureFilter::Or(operands)
+                }
+            }
+            "Not" => {
+                let operand = elements(node)
+                    .next()
+                    .ok_or_else(|| ServerError::OgcError("Not without operand".to_string()))?;
+                FeatureFilter::Not(Box::new(self.operator(operand)?))
+            }
+            "PropertyIsEqualTo" => self.comparison(node, CompareOp::Eq)?,
+            "PropertyIsNotEqualTo" => self.comparison(node, CompareOp::NotEq)?,
+            "PropertyIsLessThan" => self.comparison(node, CompareOp::Lt)?,
+            "PropertyIsLessThanOrEqualTo" => self.comparison(node, CompareOp::Le)?,
+            "PropertyIsGreaterThan" => self.comparison(node, CompareOp::Gt)?,
+            "PropertyIsGreaterThanOrEqualTo" => self.comparison(node, CompareOp::Ge)?,
+            "PropertyIsLike" => self.like(node)?,
+            "PropertyIsNull" | "PropertyIsNil" => FeatureFilter::IsNull(property_name(node)?),
+            "PropertyIsBetween" => {
+                let bound = |name: &str| -> ServerResult<Value> {
+                    let boundary = gml::child(node, name)
+                        .ok_or_else(|| ServerError::OgcError(format!("PropertyIsBetween without {}", name)))?;
+                    literal(&boundary)
+                };
+                FeatureFilter::Between {
+                    property: property_name(node)?,
+                    lower: bound("LowerBoundary")?,
+                    upper: bound("UpperBoundary")?,
+                }
+            }
+            "BBOX" => self.spatial(node, SpatialOp::BBox)?,
+            "Intersects" => self.spatial(node, SpatialOp::Intersects)?,
+            "Disjoint" => self.spatial(node, SpatialOp::Disjoint)?,
+            "Contains" => self.spatial(node, SpatialOp::Contains)?,
+            "Within" => self.spatial(node, SpatialOp::Within)?,
+            "Touches" => self.spatial(node, SpatialOp::Touches)?,
+            "Crosses" => self.spatial(node, SpatialOp::Crosses)?,
+            "Overlaps" => self.spatial(node, SpatialOp::Overlaps)?,
+            "Equals" => self.spatial(node, SpatialOp::Equals)?,
+            "DWithin" => self.spatial(node, SpatialOp::DWithin)?,
+            "Beyond" => self.spatial(node, SpatialOp::Beyond)?,
+            "ResourceId" | "FeatureId" | "GmlObjectId" => self.resource_ids(&[node])?,
+            other => {
+                return Err(ServerError::OgcError(format!(
+                    "OperationNotSupported: filter operator {}",
+                    other
+                )))
+            }
+        };
+        Ok(filter)
+    }
+
+    fn comparison(&self, node: Node<'_, '_>, op: CompareOp) -> ServerResult<FeatureFilter> {
+        let literal_node = gml::child(node, "Literal")
+            .ok_or_else(|| ServerError::OgcError("Comparison without Literal".to_string()))?;
+
+        Ok(FeatureFilter::Compare {
+            property: property_name(node)?,
+            op,
+            value: Value::String(literal_node.text().unwrap_or_default().to_string()),
+            match_case: node.attribute("matchCase") != Some("false"),
+        })
+    }
+
+    fn like(&self, node: Node<'_, '_>) -> ServerResult<FeatureFilter> {
+        let pattern = gml::child(node, "Literal")
+            .and_then(|l| l.text())
+            .ok_or_else(|| ServerError::OgcError("PropertyIsLike without Literal".to_string()))?;
+
+        let attr_char = |names: &[&str], default: char| -> char {
+            names
+                .iter()
+                .find_map(|n| node.attribute(*n))
+                .and_then(|v| v.chars().next())
+                .unwrap_or(default)
+        };
+
+        Ok(FeatureFilter::Like {
+            property: property_name(node)?,
+            pattern: meridian_db::queries::filter::like_pattern(
+                pattern,
+                attr_char(&["wildCard"], '*'),
+                attr_char(&["singleChar"], '.'),
+                attr_char(&["escapeChar", "escape"], '\\'),
+            ),
+            match_case: node.attribute("matchCase") != Some("false"),
+        })
+    }
+
+    fn spatial(&self, node: Node<'_, '_>, op: SpatialOp) -> ServerResult<FeatureFilter> {
+        let geometry_node = elements(node)
+            .find(|c| !matches!(c.tag_name().name(), "ValueReference" | "PropertyName" | "Distance"))
+            .ok_or_else(|| ServerError::OgcError(format!("{:?} without geometry", op)))?;
+        let (geometry, srid) = gml::decode_geometry(geometry_node, self.default_srid)?;
+
+        let distance = match op {
+            SpatialOp::DWithin | SpatialOp::Beyond => {
+                let distance = gml::child(node, "Distance")
+                    .ok_or_else(|| ServerError::OgcError("Distance operator without Distance".to_string()))?;
+                Some(distance_in_metres(&distance)?)
+            }
+            _ => None,
+        };
+
+        Ok(FeatureFilter::Spatial {
+            op,
+            geometry: geojson::from_geometry(&geometry),
+            srid,
+            distance,
+        })
+    }
+
+    fn resource_ids(&self, nodes: &[Node<'_, '_>]) -> ServerResult<FeatureFilter> {
+        let ids = nodes
+            .iter()
+            .map(|n| {
+                let rid = n
+                    .attribute("rid")
+                    .or_else(|| n.attribute("fid"))
+                    .or_else(|| n.attribute((gml::GML_NS, "id")))
+                    .ok_or_else(|| ServerError::OgcError("ResourceId without rid".to_string()))?;
+                parse_resource_id(rid).map(|(_, id)| id)
+            })
+            .collect::<ServerResult<Vec<_>>>()?;
+        Ok(FeatureFilter::ResourceId(ids))
+    }
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(Node::is_element)
+}
+
+/// Name of the property an operator applies to, without namespace prefix
+fn property_name(node: Node<'_, '_>) -> ServerResult<String> {
+    let reference = gml::child(node, "ValueReference")
+        .or_else(|| gml::child(node, "PropertyName"))
+        .and_then(|n| n.text())
+        .ok_or_else(|| ServerError::OgcError("Operator without ValueReference".to_string()))?;
+
+    // Drop a leading feature type step, e.g. `meridian:roads/meridian:name`
+    let step = reference.trim().rsplit('/').next().unwrap_or_default();
+    Ok(strip_prefix(step).to_string())
+}
+
+fn literal(node: &Node<'_, '_>) -> ServerResult<Value> {
+    gml::child(*node, "Literal")
+        .map(|l| Value::String(l.text().unwrap_or_default().to_string()))
+        .ok_or_else(|| ServerError::OgcError("Expected Literal".to_string()))
+}
+
+fn distance_in_metres(node: &Node<'_, '_>) -> ServerResult<f64> {
+    let value: f64 = node
+        .text()
+        .unwrap_or_default()
+        .trim()
+        .parse()
+        .map_err(|_| ServerError::OgcError("Invalid Distance".to_string()))?;
+
+    let uom = node.attribute("uom").or_else(|| node.attribute("units")).unwrap_or("m");
+    let unit = uom.rsplit([':', '#', '/']).next().unwrap_or(uom).to_ascii_lowercase();
+    let factor = match unit.as_str() {
+        "m" | "metre" | "meter" | "metres" | "meters" | "9001" => 1.0,
+        "km" | "kilometre" | "kilometer" | "kilometres" | "kilometers" | "9036" => 1000.0,
+        "ft" | "foot" | "feet" | "9002" => 0.3048,
+        "mi" | "mile" | "miles" | "9093" => 1609.344,
+        other => {
+            return Err(ServerError::OgcError(format!("Unsupported distance unit: {}", other)))
+        }
+    };
+    Ok(value * factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comparison_and_bbox() {
+        let xml = r#"<fes:Filter xmlns:fes="http://www.opengis.net/fes/2.0" xmlns:gml="http://www.opengis.net/gml/3.2">
+          <fes:And>
+            <fes:PropertyIsGreaterThan>
+              <fes:ValueReference>meridian:lanes</fes:ValueReference>
+              <fes:Literal>2</fes:Literal>
+            </fes:PropertyIsGreaterThan>
+            <fes:BBOX>
+              <fes:ValueReference>geometry</fes:ValueReference>
+              <gml:Envelope srsName="urn:ogc:def:crs:EPSG::4326">
+                <gml:lowerCorner>40 -10</gml:lowerCorner>
+                <gml:upperCorner>50 10</gml:upperCorner>
+              </gml:Envelope>
+            </fes:BBOX>
+          </fes:And>
+        </fes:Filter>"#;
+
+        let filter = parse_filter(xml, 4326).unwrap();
+        let FeatureFilter::And(operands) = filter else {
+            panic!("expected And");
+        };
+        assert_eq!(
+            operands[0],
+            FeatureFilter::Compare {
+                property: "lanes".to_string(),
+                op: CompareOp::Gt,
+                value: Value::String("2".to_string()),
+                match_case: true,
+            }
+        );
+        match &operands[1] {
+            FeatureFilter::Spatial { op, geometry, srid, .. } => {
+                assert_eq!(*op, SpatialOp::BBox);
+                assert_eq!(*srid, 4326);
+                let ring = geometry["coordinates"][0].as_array().unwrap();
+                assert!(ring.contains(&serde_json::json!([-10.0, 40.0])));
+                assert!(ring.contains(&serde_json::json!([10.0, 50.0])));
+            }
+            other => panic!("unexpected filter: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_like_and_dwithin() {
+        let xml = r#"<Filter xmlns="http://www.opengis.net/fes/2.0" xmlns:gml="http://www.opengis.net/gml/3.2">
+          <Or>
+            <PropertyIsLike wildCard="*" singleChar="?" escapeChar="\" matchCase="false">
+              <ValueReference>name</ValueReference>
+              <Literal>Main*</Literal>
+            </PropertyIsLike>
+            <DWithin>
+              <ValueReference>geometry</ValueReference>
+              <gml:Point srsName="EPSG:3857"><gml:pos>100 200</gml:pos></gml:Point>
+              <Distance uom="km">2</Distance>
+            </DWithin>
+          </Or>
+        </Filter>"#;
+
+        let FeatureFilter::Or(operands) = parse_filter(xml, 4326).unwrap() else {
+            panic!("expected Or");
+        };
+        assert_eq!(
+            operands[0],
+            FeatureFilter::Like {
+                property: "name".to_string(),
+                pattern: "Main%".to_string(),
+                match_case: false,
+            }
+        );
+        assert!(matches!(
+            operands[1],
+            FeatureFilter::Spatial { op: SpatialOp::DWithin, srid: 3857, distance: Some(d), .. } if d == 2000.0
+        ));
+    }
+
+    #[test]
+    fn test_parse_resource_ids() {
+        let xml = r#"<fes:Filter xmlns:fes="http://www.opengis.net/fes/2.0">
+          <fes:ResourceId rid="roads.6f1d6f3e-4b7a-4c1e-9d55-2b3c4d5e6f70"/>
+          <fes:ResourceId rid="roads.00000000-0000-0000-0000-000000000001"/>
+        </fes:Filter>"#;
+
+        match parse_filter(xml, 4326).unwrap() {
+            FeatureFilter::ResourceId(ids) => assert_eq!(ids.len(), 2),
+            other => panic!("unexpected filter: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_filter() {
+        assert!(parse_filter("<Filter/>", 4326).is_err());
+        assert!(parse_filter("<Filter><PropertyIsFoo/></Filter>", 4326).is_err());
+        assert!(parse_resource_id("roads.not-a-uuid").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! GeoJSON geometry conversion
+//!
+//! Features are stored with GeoJSON geometries; the OGC services convert
+//! them to `geo` types for hit-testing and rendering and back for output.
+
+use meridian_core::geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon,
+};
+use serde_json::{json, Value};
+
+use crate::{error::ServerResult, ServerError};
+
+/// Convert a GeoJSON geometry object to a `geo` geometry
+pub fn to_geometry(value: &Value) -> ServerResult<Geometry<f64>> {
+    let geometry_type = value
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ServerError::GeometryError("Geometry must have a 'type' field".to_string()))?;
+
+    if geometry_type == "GeometryCollection" {
+        let members = value
+            .get("geometries")
+            .and_then(Value::as_array)
+            .ok_or_else(|| ServerError::GeometryError("GeometryCollection without geometries".to_string()))?;
+        let geometries = members.iter().map(to_geometry).collect::<ServerResult<Vec<_>>>()?;
+        return Ok(Geometry::GeometryCollection(GeometryCollection(geometries)));
+    }
+
+    let coordinates = value
+        .get("coordinates")
+        .ok_or_else(|| ServerError::GeometryError("Geometry must have a 'coordinates' field".to_string()))?;
+
+    let geometry = match geometry_type {
+        "Point" => Geometry::Point(Point(coord(coordinates)?)),
+        "MultiPoint" => Geometry::MultiPoint(MultiPoint(
+            array(coordinates)?
+                .iter()
+                .map(|c| coord(c).map(Point))
+                .collect::<ServerResult<_>>()?,
+        )),
+        "LineString" => Geometry::LineString(line_string(coordinates)?),
+        "MultiLineString" => Geometry::MultiLineString(MultiLineString(
+            array(coordinates)?
+                .iter()
+                .map(line_string)
+                .collect::<ServerResult<_>>()?,
+        )),
+        "Polygon" => Geometry::Polygon(polygon(coordinates)?),
+        "MultiPolygon" => Geometry::MultiPolygon(MultiPolygon(
+            array(coordinates)?
+                .iter()
+                .map(polygon)
+                .collect::<ServerResult<_>>()?,
+        )),
+        other => {
+            return Err(ServerError::GeometryError(format!(
+                "Unsupported geometry type: {}",
+                other
+            )))
+        }
+    };
+
+    Ok(geometry)
+}
+
+/// Convert a `geo` geometry to a GeoJSON geometry object
+pub fn from_geometry(geometry: &Geometry<f64>) -> Value {
+    match geometry {
+        Geometry::Point(p) => json!({ "type": "Point", "coordinates": position(p.0) }),
+        Geometry::MultiPoint(mp) => json!({
+            "type": "MultiPoint",
+            "coordinates": mp.0.iter().map(|p| position(p.0)).collect::<Vec<_>>(),
+        }),
+        Geometry::LineString(ls) => json!({ "type": "LineString", "coordinates": positions(ls) }),
+        Geometry::MultiLineString(mls) => json!({
+            "type": "MultiLineString",
+            "coordinates": mls.0.iter().map(positions).collect::<Vec<_>>(),
+        }),
+        Geometry::Polygon(p) => json!({ "type": "Polygon", "coordinates": rings(p) }),
+        Geometry::MultiPolygon(mp) => json!({
+            "type": "MultiPolygon",
+            "coordinates": mp.0.iter().map(rings).collect::<Vec<_>>(),
+        }),
+        Geometry::GeometryCollection(gc) => json!({
+            "type": "GeometryCollection",
+            "geometries": gc.0.iter().map(from_geometry).collect::<Vec<_>>(),
+        }),
+        Geometry::Line(line) => json!({
+            "type": "LineString",
+            "coordinates": [position(line.start), position(line.end)],
+        }),
+        Geometry::Rect(rect) => from_geometry(&Geometry::Polygon(rect.to_polygon())),
+        Geometry::Triangle(triangle) => from_geometry(&Geometry::Polygon(triangle.to_polygon())),
+    }
+}
+
+/// Swap the first two ordinates of every position of a GeoJSON geometry
+///
+/// Used for CRSs with latitude-first axis order such as EPSG:4326.
+pub fn swap_axes(geometry: &mut Value) {
+    fn swap(value: &mut Value) {
+        if let Some(items) = value.as_array_mut() {
+            if items.len() >= 2 && items.iter().all(Value::is_number) {
+                items.swap(0, 1);
+            } else {
+                items.iter_mut().for_each(swap);
+            }
+        }
+    }
+
+    if let Some(coordinates) = geometry.get_mut("coordinates") {
+        swap(coordinates);
+    }
+    if let Some(Value::Array(geometries)) = geometry.get_mut("geometries") {
+        geometries.iter_mut().for_each(swap_axes);
+    }
+}
+
+fn array(value: &Value) -> ServerResult<&Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| ServerError::GeometryError("Coordinates must be an array".to_string()))
+}
+
+fn coord(value: &Value) -> ServerResult<Coord<f64>> {
+    let values = array(value)?;
+    let x = values.first().and_then(Value::as_f64);
+    let y = values.get(1).and_then(Value::as_f64);
+    match (x, y) {
+        (Some(x), Some(y)) => Ok(Coord { x, y }),
+        _ => Err(ServerError::GeometryError("Invalid position".to_string())),
+    }
+}
+
+fn line_string(value: &Value) -> ServerResult<LineString<f64>> {
+    Ok(LineString(array(value)?.iter().map(coord).collect::<ServerResult<_>>()?))
+}
+
+fn polygon(value: &Value) -> ServerResult<Polygon<f64>> {
+    let mut rings = array(value)?.iter().map(line_string);
+    let exterior = rings
+        .next()
+        .transpose()?
+        .ok_or_else(|| ServerError::GeometryError("Polygon without exterior ring".to_string()))?;
+    Ok(Polygon::new(exterior, rings.collect::<ServerResult<_>>()?))
+}
+
+fn position(c: Coord<f64>) -> Value {
+    json!([c.x, c.y])
+}
+
+fn positions(ls: &LineString<f64>) -> Vec<Value> {
+    ls.0.iter().map(|c| position(*c)).collect()
+}
+
+fn rings(p: &Polygon<f64>) -> Vec<Vec<Value>> {
+    std::iter::once(p.exterior())
+        .chain(p.interiors())
+        .map(positions)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_roundtrip() {
+        let value = json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [0.0, 0.0]],
+                [[2.0, 2.0], [4.0, 2.0], [4.0, 4.0], [2.0, 2.0]]
+            ]
+        });
+
+        let geometry = to_geometry(&value).unwrap();
+        match &geometry {
+            Geometry::Polygon(p) => assert_eq!(p.interiors().len(), 1),
+            other => panic!("unexpected geometry: {:?}", other),
+        }
+        assert_eq!(from_geometry(&geometry), value);
+    }
+
+    #[test]
+    fn test_swap_axes() {
+        let mut value = json!({
+            "type": "GeometryCollection",
+            "geometries": [
+                { "type": "Point", "coordinates": [10.0, 50.0] },
+                { "type": "LineString", "coordinates": [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]] }
+            ]
+        });
+        swap_axes(&mut value);
+        assert_eq!(value["geometries"][0]["coordinates"], json!([50.0, 10.0]));
+        assert_eq!(value["geometries"][1]["coordinates"], json!([[2.0, 1.0, 3.0], [5.0, 4.0, 6.0]]));
+    }
+
+    #[test]
+    fn test_invalid_geometry() {
+        assert!(to_geometry(&json!({ "type": "Point" })).is_err());
+        assert!(to_geometry(&json!({ "type": "Circle", "coordinates": [] })).is_err());
+    }
+}
@@ -0,0 +1,627 @@
+//! GML 3.2 encoding
+//!
+//! Writes `geo` geometries as GML 3.2 geometry elements and features as WFS 2.0
+//! feature collections, reads GML geometries from filter and transaction
+//! documents, and escapes text for the XML documents produced by the OGC
+//! services.
+
+use meridian_core::geo_types::{
+    Coord, Geometry, GeometryCollection, LineString, MultiLineString, MultiPoint, MultiPolygon,
+    Point, Polygon, Rect,
+};
+use roxmltree::Node;
+use serde_json::Value;
+use std::fmt::Write;
+
+use super::{is_crs84, is_lat_lon_order, parse_crs};
+use crate::{error::ServerResult, ServerError};
+
+/// GML 3.2 namespace
+pub const GML_NS: &str = "http://www.opengis.net/gml/3.2";
+
+/// WFS 2.0 namespace
+pub const WFS_NS: &str = "http://www.opengis.net/wfs/2.0";
+
+/// Filter Encoding 2.0 namespace
+pub const FES_NS: &str = "http://www.opengis.net/fes/2.0";
+
+/// Namespace of the application schema for published feature types
+pub const FEATURE_NS: &str = "http://meridian-gis.org/features";
+
+/// Prefix bound to [`FEATURE_NS`]
+pub const FEATURE_PREFIX: &str = "meridian";
+
+/// A feature to be written into a GML feature collection
+pub struct GmlFeature<'a> {
+    /// Feature type (layer) name
+    pub type_name: &'a str,
+    /// Feature identifier, used as `gml:id`
+    pub id: String,
+    /// Geometry in the output CRS
+    pub geometry: Option<Geometry<f64>>,
+    /// Feature attributes
+    pub properties: &'a serde_json::Map<String, Value>,
+}
+
+/// Encode features as a WFS 2.0 `wfs:FeatureCollection` with GML 3.2 members
+///
+/// `lock_id` is set for GetFeatureWithLock responses.
+pub fn encode_feature_collection(
+    features: &[GmlFeature<'_>],
+    srid: i32,
+    lat_lon: bool,
+    number_matched: Option<u64>,
+    lock_id: Option<&str>,
+) -> String {
+    let mut out = String::new();
+    let matched = number_matched
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let lock = lock_id
+        .map(|id| format!(r#" lockId="{}""#, escape(id)))
+        .unwrap_or_default();
+
+    let _ = write!(
+        out,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:FeatureCollection xmlns:wfs="{wfs}" xmlns:gml="{gml}" xmlns:{prefix}="{ns}" numberMatched="{matched}" numberReturned="{returned}" timeStamp="{timestamp}"{lock}>"#,
+        wfs = WFS_NS,
+        gml = GML_NS,
+        prefix = FEATURE_PREFIX,
+        ns = FEATURE_NS,
+        matched = matched,
+        returned = features.len(),
+        timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        lock = lock,
+    );
+
+    for feature in features {
+        out.push_str("\n  <wfs:member>");
+        out.push_str(&encode_feature(feature, srid, lat_lon));
+        out.push_str("</wfs:member>");
+    }
+
+    out.push_str("\n</wfs:FeatureCollection>");
+    out
+}
+
+/// Encode a single feature element in the application schema namespace
+pub fn encode_feature(feature: &GmlFeature<'_>, srid: i32, lat_lon: bool) -> String {
+    let type_name = xml_name(feature.type_name);
+    let mut out = format!(
+        r#"<{p}:{t} gml:id="{id}">"#,
+        p = FEATURE_PREFIX,
+        t = type_name,
+        id = escape(&feature.id)
+    );
+
+    if let Some(geometry) = &feature.geometry {
+        let _ = write!(
+            out,
+            "<{p}:geometry>{g}</{p}:geometry>",
+            p = FEATURE_PREFIX,
+            g = encode_geometry(geometry, srid, &feature.id, lat_lon)
+        );
+    }
+
+    for (key, value) in feature.properties {
+        let name = xml_name(key);
+        match value {
+            Value::Null => {
+                let _ = write!(
+                    out,
+                    r#"<{p}:{n} xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true"/>"#,
+                    p = FEATURE_PREFIX,
+                    n = name
+                );
+            }
+            _ => {
+                let _ = write!(
+                    out,
+                    "<{p}:{n}>{v}</{p}:{n}>",
+                    p = FEATURE_PREFIX,
+                    n = name,
+                    v = escape(&value_text(value))
+                );
+            }
+        }
+    }
+
+    let _ = write!(out, "</{}:{}>", FEATURE_PREFIX, type_name);
+    out
+}
+
+/// Text content for a property value; objects and arrays are written as JSON
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Turn an arbitrary layer or attribute name into a valid XML element name
+pub fn xml_name(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    if !out.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// Escape text for use in XML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// URN form of an EPSG code as used in `srsName` attributes
+pub fn srs_name(srid: i32) -> String {
+    format!("urn:ogc:def:crs:EPSG::{}", srid)
+}
+
+/// Encode a geometry as a GML 3.2 element
+///
+/// `id` is used to derive the mandatory `gml:id` attributes. With
+/// `lat_lon` set, positions are written latitude first, which is the axis
+/// order of EPSG:4326 in GML 3.2.
+pub fn encode_geometry(geometry: &Geometry<f64>, srid: i32, id: &str, lat_lon: bool) -> String {
+    let mut out = String::new();
+    let srs = srs_name(srid);
+    let mut writer = GmlWriter { out: &mut out, lat_lon, counter: 0, id };
+    writer.geometry(geometry, Some(&srs));
+    out
+}
+
+/// Whether positions for a `srsName` are written latitude first
+///
+/// URN and URI forms of geographic CRSs use the EPSG axis order, while the
+/// legacy `EPSG:4326` form is conventionally longitude first.
+pub fn srs_is_lat_lon(srs_name: &str) -> bool {
+    let upper = srs_name.trim().to_ascii_uppercase();
+    if upper.starts_with("EPSG:") || is_crs84(srs_name) {
+        return false;
+    }
+    parse_crs(srs_name).map(is_lat_lon_order).unwrap_or(false)
+}
+
+/// Decode a GML geometry element (GML 3.2, also accepting GML 3.1 names)
+///
+/// Returns the geometry in x/y order together with the SRID from the
+/// element's `srsName`, or `default_srid` when it has none.
+pub fn decode_geometry(node: Node<'_, '_>, default_srid: i32) -> ServerResult<(Geometry<f64>, i32)> {
+    let srs_name = node.attribute("srsName");
+    let srid = match srs_name {
+        Some(srs) => parse_crs(srs)?,
+        None => default_srid,
+    };
+    let swap = match srs_name {
+        Some(srs) => srs_is_lat_lon(srs),
+        None => is_lat_lon_order(srid),
+    };
+
+    let reader = GmlReader { swap };
+    Ok((reader.geometry(node)?, srid))
+}
+
+struct GmlReader {
+    swap: bool,
+}
+
+impl GmlReader {
+    fn geometry(&self, node: Node<'_, '_>) -> ServerResult<Geometry<f64>> {
+        let geometry = match node.tag_name().name() {
+            "Point" => Geometry::Point(Point(self.single_position(node)?)),
+            "LineString" | "Curve" | "LinearRing" => Geometry::LineString(self.line_string(node)?),
+            "Polygon" | "Surface" => Geometry::Polygon(self.polygon(node)?),
+            "Envelope" | "Box" => {
+                let corners = |name: &str| -> ServerResult<Coord<f64>> {
+                    let corner = child(node, name).ok_or_else(|| invalid(&format!("Envelope without {}", name)))?;
+                    self.single_position(corner)
+                };
+                let (lower, upper) = match (child(node, "lowerCorner"), child(node, "coordinates")) {
+                    (Some(_), _) => (corners("lowerCorner")?, corners("upperCorner")?),
+                    (None, Some(coords)) => {
+                        let positions = self.positions(coords)?;
+                        match positions.as_slice() {
+                            [lower, upper] => (*lower, *upper),
+                            _ => return Err(invalid("Box must have two positions")),
+                        }
+                    }
+                    (None, None) => return Err(invalid("Envelope without corners")),
+                };
+                Geometry::Polygon(Rect::new(lower, upper).to_polygon())
+            }
+            "MultiPoint" => Geometry::MultiPoint(MultiPoint(
+                self.members(node, &["pointMember", "pointMembers"])?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::Point(p) => Ok(p),
+                        _ => Err(invalid("MultiPoint member must be a Point")),
+                    })
+                    .collect::<ServerResult<_>>()?,
+            )),
+            "MultiCurve" | "MultiLineString" => Geometry::MultiLineString(MultiLineString(
+                self.members(node, &["curveMember", "curveMembers", "lineStringMember"])?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::LineString(ls) => Ok(ls),
+                        _ => Err(invalid("MultiCurve member must be a LineString")),
+                    })
+                    .collect::<ServerResult<_>>()?,
+            )),
+            "MultiSurface" | "MultiPolygon" => Geometry::MultiPolygon(MultiPolygon(
+                self.members(node, &["surfaceMember", "surfaceMembers", "polygonMember"])?
+                    .into_iter()
+                    .map(|g| match g {
+                        Geometry::Polygon(p) => Ok(p),
+                        _ => Err(invalid("MultiSurface member must be a Polygon")),
+                    })
+                    .collect::<ServerResult<_>>()?,
+            )),
+            "MultiGeometry" => Geometry::GeometryCollection(GeometryCollection(
+                self.members(node, &["geometryMember", "geometryMembers"])?,
+            )),
+            other => {
+                return Err(ServerError::GeometryError(format!(
+                    "Unsupported GML geometry: {}",
+                    other
+                )))
+            }
+        };
+        Ok(geometry)
+    }
+
+    fn members(&self, node: Node<'_, '_>, names: &[&str]) -> ServerResult<Vec<Geometry<f64>>> {
+        node.children()
+            .filter(|c| c.is_element() && names.contains(&c.tag_name().name()))
+            .flat_map(|member| member.children().filter(Node::is_element))
+            .map(|g| self.geometry(g))
+            .collect()
+    }
+
+    fn line_string(&self, node: Node<'_, '_>) -> ServerResult<LineString<f64>> {
+        // gml:Curve wraps its coordinates in segments/LineStringSegment
+        let node = node
+            .descendants()
+            .find(|d| d.has_tag_name_local("LineStringSegment"))
+            .unwrap_or(node);
+        Ok(LineString(self.positions(node)?))
+    }
+
+    fn polygon(&self, node: Node<'_, '_>) -> ServerResult<Polygon<f64>> {
+        let node = node
+            .descendants()
+            .find(|d| d.has_tag_name_local("PolygonPatch"))
+            .unwrap_or(node);
+
+        let ring = |boundary: Node<'_, '_>| -> ServerResult<LineString<f64>> {
+            let ring = boundary
+                .children()
+                .find(Node::is_element)
+                .ok_or_else(|| invalid("Polygon boundary without ring"))?;
+            self.line_string(ring)
+        };
+
+        let exterior = node
+            .children()
+            .find(|c| c.has_tag_name_local("exterior") || c.has_tag_name_local("outerBoundaryIs"))
+            .ok_or_else(|| invalid("Polygon without exterior"))?;
+        let interiors = node
+            .children()
+            .filter(|c| c.has_tag_name_local("interior") || c.has_tag_name_local("innerBoundaryIs"))
+            .map(ring)
+            .collect::<ServerResult<Vec<_>>>()?;
+
+        Ok(Polygon::new(ring(exterior)?, interiors))
+    }
+
+    fn single_position(&self, node: Node<'_, '_>) -> ServerResult<Coord<f64>> {
+        let positions = self.positions(node)?;
+        match positions.as_slice() {
+            [c] => Ok(*c),
+            _ => Err(invalid("Expected a single position")),
+        }
+    }
+
+    /// Positions from `pos`, `posList`, `coordinates` or the node's own text
+    fn positions(&self, node: Node<'_, '_>) -> ServerResult<Vec<Coord<f64>>> {
+        let dimension = |n: Node<'_, '_>| -> usize {
+            n.attribute("srsDimension")
+                .or_else(|| n.attribute("dimension"))
+                .and_then(|d| d.parse().ok())
+                .unwrap_or(2)
+        };
+
+        if let Some(list) = child(node, "posList") {
+            return self.parse_ordinates(list.text().unwrap_or_default(), dimension(list));
+        }
+        if let Some(coords) = child(node, "coordinates") {
+            let cs = coords.attribute("cs").unwrap_or(",");
+            let ts = coords.attribute("ts").unwrap_or(" ");
+            return coords
+                .text()
+                .unwrap_or_default()
+                .split(ts)
+                .filter(|t| !t.trim().is_empty())
+                .map(|tuple| {
+                    let values: Vec<&str> = tuple.trim().split(cs).collect();
+                    self.parse_ordinates(&values.join(" "), values.len())
+                        .and_then(|mut c| c.pop().ok_or_else(|| invalid("Empty coordinate tuple")))
+                })
+                .collect();
+        }
+
+        let pos: Vec<Node<'_, '_>> = node.children().filter(|c| c.has_tag_name_local("pos")).collect();
+        if !pos.is_empty() {
+            let mut coords = Vec::with_capacity(pos.len());
+            for p in pos {
+                coords.extend(self.parse_ordinates(p.text().unwrap_or_default(), dimension(p))?);
+            }
+            return Ok(coords);
+        }
+
+        // lowerCorner/upperCorner and bare position elements carry text directly
+        match node.text().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(text) => self.parse_ordinates(text, dimension(node)),
+            None => Err(invalid("Geometry without coordinates")),
+        }
+    }
+
+    fn parse_ordinates(&self, text: &str, dimension: usize) -> ServerResult<Vec<Coord<f64>>> {
+        let values = text
+            .split_whitespace()
+            .map(|v| v.parse::<f64>().map_err(|_| invalid(&format!("Invalid coordinate: {}", v))))
+            .collect::<ServerResult<Vec<f64>>>()?;
+
+        let dimension = dimension.max(2);
+        if values.len() % dimension != 0 {
+            return Err(invalid("Coordinate count does not match dimension"));
+        }
+
+        Ok(values
+            .chunks(dimension)
+            .map(|c| if self.swap { Coord { x: c[1], y: c[0] } } else { Coord { x: c[0], y: c[1] } })
+            .collect())
+    }
+}
+
+trait LocalName {
+    fn has_tag_name_local(&self, name: &str) -> bool;
+}
+
+impl LocalName for Node<'_, '_> {
+    fn has_tag_name_local(&self, name: &str) -> bool {
+        self.is_element() && self.tag_name().name() == name
+    }
+}
+
+/// First child element with the given local name
+pub fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+fn invalid(message: &str) -> ServerError {
+    ServerError::GeometryError(format!("Invalid GML: {}", message))
+}
+
+struct GmlWriter<'a> {
+    out: &'a mut String,
+    lat_lon: bool,
+    counter: usize,
+    id: &'a str,
+}
+
+impl GmlWriter<'_> {
+    fn next_id(&mut self) -> String {
+        self.counter += 1;
+        format!("{}.geom.{}", escape(self.id), self.counter)
+    }
+
+    fn open(&mut self, tag: &str, srs: Option<&str>) {
+        let id = self.next_id();
+        let _ = match srs {
+            Some(srs) => write!(self.out, r#"<gml:{} gml:id="{}" srsName="{}">"#, tag, id, srs),
+            None => write!(self.out, r#"<gml:{} gml:id="{}">"#, tag, id),
+        };
+    }
+
+    fn pos_list(&mut self, coords: &[Coord<f64>]) {
+        let lat_lon = self.lat_lon;
+        let values: Vec<String> = coords
+            .iter()
+            .map(|c| if lat_lon { format!("{} {}", c.y, c.x) } else { format!("{} {}", c.x, c.y) })
+            .collect();
+        let _ = write!(self.out, "<gml:posList>{}</gml:posList>", values.join(" "));
+    }
+
+    fn pos(&mut self, c: Coord<f64>) {
+        let _ = if self.lat_lon {
+            write!(self.out, "<gml:pos>{} {}</gml:pos>", c.y, c.x)
+        } else {
+            write!(self.out, "<gml:pos>{} {}</gml:pos>", c.x, c.y)
+        };
+    }
+
+    fn line_string(&mut self, ls: &LineString<f64>, srs: Option<&str>) {
+        self.open("LineString", srs);
+        self.pos_list(&ls.0);
+        self.out.push_str("</gml:LineString>");
+    }
+
+    fn polygon(&mut self, polygon: &Polygon<f64>, srs: Option<&str>) {
+        self.open("Polygon", srs);
+        self.out.push_str("<gml:exterior><gml:LinearRing>");
+        self.pos_list(&polygon.exterior().0);
+        self.out.push_str("</gml:LinearRing></gml:exterior>");
+        for interior in polygon.interiors() {
+            self.out.push_str("<gml:interior><gml:LinearRing>");
+            self.pos_list(&interior.0);
+            self.out.push_str("</gml:LinearRing></gml:interior>");
+        }
+        self.out.push_str("</gml:Polygon>");
+    }
+
+    fn geometry(&mut self, geometry: &Geometry<f64>, srs: Option<&str>) {
+        match geometry {
+            Geometry::Point(p) => {
+                self.open("Point", srs);
+                self.pos(p.0);
+                self.out.push_str("</gml:Point>");
+            }
+            Geometry::LineString(ls) => self.line_string(ls, srs),
+            Geometry::Polygon(p) => self.polygon(p, srs),
+            Geometry::MultiPoint(mp) => {
+                self.open("MultiPoint", srs);
+                for p in &mp.0 {
+                    self.out.push_str("<gml:pointMember>");
+                    self.open("Point", None);
+                    self.pos(p.0);
+                    self.out.push_str("</gml:Point></gml:pointMember>");
+                }
+                self.out.push_str("</gml:MultiPoint>");
+            }
+            Geometry::MultiLineString(mls) => {
+                self.open("MultiCurve", srs);
+                for ls in &mls.0 {
+                    self.out.push_str("<gml:curveMember>");
+                    self.line_string(ls, None);
+                    self.out.push_str("</gml:curveMember>");
+                }
+                self.out.push_str("</gml:MultiCurve>");
+            }
+            Geometry::MultiPolygon(mp) => {
+                self.open("MultiSurface", srs);
+                for p in &mp.0 {
+                    self.out.push_str("<gml:surfaceMember>");
+                    self.polygon(p, None);
+                    self.out.push_str("</gml:surfaceMember>");
+                }
+                self.out.push_str("</gml:MultiSurface>");
+            }
+            Geometry::GeometryCollection(gc) => {
+                self.open("MultiGeometry", srs);
+                for member in &gc.0 {
+                    self.out.push_str("<gml:geometryMember>");
+                    self.geometry(member, None);
+                    self.out.push_str("</gml:geometryMember>");
+                }
+                self.out.push_str("</gml:MultiGeometry>");
+            }
+            Geometry::Line(line) => {
+                self.line_string(&LineString(vec![line.start, line.end]), srs)
+            }
+            Geometry::Rect(rect) => self.polygon(&rect.to_polygon(), srs),
+            Geometry::Triangle(triangle) => self.polygon(&triangle.to_polygon(), srs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meridian_core::geo_types::{point, polygon};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_xml_name() {
+        assert_eq!(xml_name("land use"), "land_use");
+        assert_eq!(xml_name("2020_census"), "_2020_census");
+    }
+
+    #[test]
+    fn test_encode_feature_collection() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("name".to_string(), Value::String("A & B".to_string()));
+        properties.insert("lanes".to_string(), serde_json::json!(2));
+        let features = vec![GmlFeature {
+            type_name: "roads",
+            id: "roads.1".to_string(),
+            geometry: Some(Geometry::Point(point!(x: 1.0, y: 2.0))),
+            properties: &properties,
+        }];
+
+        let xml = encode_feature_collection(&features, 3857, false, Some(10), None);
+        assert!(xml.contains(r#"numberMatched="10" numberReturned="1""#));
+        assert!(!xml.contains("lockId"));
+        assert!(xml.contains(r#"<meridian:roads gml:id="roads.1">"#));
+        assert!(xml.contains("<meridian:name>A &amp; B</meridian:name>"));
+        assert!(xml.contains("<meridian:lanes>2</meridian:lanes>"));
+    }
+
+    #[test]
+    fn test_encode_point_lat_lon() {
+        let gml = encode_geometry(&Geometry::Point(point!(x: 10.0, y: 50.0)), 4326, "f1", true);
+        assert!(gml.starts_with(r#"<gml:Point gml:id="f1.geom.1" srsName="urn:ogc:def:crs:EPSG::4326">"#));
+        assert!(gml.contains("<gml:pos>50 10</gml:pos>"));
+    }
+
+    #[test]
+    fn test_decode_polygon_lat_lon() {
+        let xml = r#"<gml:Polygon xmlns:gml="http://www.opengis.net/gml/3.2" srsName="urn:ogc:def:crs:EPSG::4326">
+            <gml:exterior><gml:LinearRing><gml:posList>0 0 0 10 10 10 0 0</gml:posList></gml:LinearRing></gml:exterior>
+        </gml:Polygon>"#;
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        let (geometry, srid) = decode_geometry(doc.root_element(), 3857).unwrap();
+
+        assert_eq!(srid, 4326);
+        match geometry {
+            Geometry::Polygon(p) => assert_eq!(p.exterior().0[1], Coord { x: 10.0, y: 0.0 }),
+            other => panic!("unexpected geometry: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_envelope_and_multipoint() {
+        let xml = r#"<gml:Envelope xmlns:gml="http://www.opengis.net/gml/3.2" srsName="EPSG:3857">
+            <gml:lowerCorner>0 0</gml:lowerCorner><gml:upperCorner>5 5</gml:upperCorner>
+        </gml:Envelope>"#;
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        let (geometry, srid) = decode_geometry(doc.root_element(), 4326).unwrap();
+        assert_eq!(srid, 3857);
+        assert!(matches!(geometry, Geometry::Polygon(_)));
+
+        let xml = r#"<gml:MultiPoint xmlns:gml="http://www.opengis.net/gml/3.2">
+            <gml:pointMember><gml:Point><gml:pos>1 2</gml:pos></gml:Point></gml:pointMember>
+            <gml:pointMember><gml:Point><gml:coordinates>3,4</gml:coordinates></gml:Point></gml:pointMember>
+        </gml:MultiPoint>"#;
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        let (geometry, _) = decode_geometry(doc.root_element(), 3857).unwrap();
+        match geometry {
+            Geometry::MultiPoint(mp) => assert_eq!(mp.0[1], point!(x: 3.0, y: 4.0)),
+            other => panic!("unexpected geometry: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_srs_is_lat_lon() {
+        assert!(srs_is_lat_lon("urn:ogc:def:crs:EPSG::4326"));
+        assert!(!srs_is_lat_lon("EPSG:4326"));
+        assert!(!srs_is_lat_lon("urn:ogc:def:crs:EPSG::3857"));
+    }
+
+    #[test]
+    fn test_encode_polygon() {
+        let poly = polygon![(x: 0.0, y: 0.0), (x: 1.0, y: 0.0), (x: 1.0, y: 1.0)];
+        let gml = encode_geometry(&Geometry::Polygon(poly), 3857, "f2", false);
+        assert!(gml.contains("<gml:exterior><gml:LinearRing><gml:posList>0 0 1 0 1 1 0 0</gml:posList>"));
+        assert!(gml.ends_with("</gml:Polygon>"));
+    }
+}
@@ -0,0 +1,247 @@
+//! OGC service support
+//!
+//! Building blocks shared by the OGC endpoints in [`crate::routes::ogc`]:
+//! case-insensitive KVP parsing, CRS and bounding box handling, access to the
+//! published layers, the WMS rendering and feature info encoders, the WMTS
+//! tile matrix sets and tile cache, the WFS query, filter, schema, transaction
+//! and locking support, and the OGC API - Features documents and CQL2 filter
+//! parser.
+
+pub mod catalog;
+pub mod cql2;
+pub mod features_api;
+pub mod fes;
+pub mod geojson;
+pub mod gml;
+pub mod wfs;
+pub mod wfst;
+pub mod wms;
+pub mod wmts;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::request::Parts,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{config::ServerConfig, error::ServerResult, ServerError};
+
+pub use catalog::LayerCatalog;
+
+/// Query string extractor for OGC key-value-pair requests
+///
+/// OGC parameter names are case-insensitive (`LAYERS`, `layers` and `Layers`
+/// are the same parameter), so keys are lowercased before deserializing.
+#[derive(Debug, Clone)]
+pub struct OgcQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for OgcQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ServerError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        parse_kvp(query).map(OgcQuery)
+    }
+}
+
+/// Deserialize an OGC KVP query string with case-insensitive keys
+pub fn parse_kvp<T: DeserializeOwned>(query: &str) -> ServerResult<T> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query)
+        .map_err(|e| ServerError::OgcError(format!("Invalid query string: {}", e)))?;
+
+    let normalized: Vec<(String, String)> = pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_ascii_lowercase(), value))
+        .collect();
+
+    let encoded = serde_urlencoded::to_string(&normalized)
+        .map_err(|e| ServerError::OgcError(format!("Invalid query string: {}", e)))?;
+
+    serde_urlencoded::from_str(&encoded)
+        .map_err(|e| ServerError::OgcError(format!("Invalid request parameters: {}", e)))
+}
+
+/// Deserialize OGC booleans, which are spelled `TRUE`/`FALSE` in KVP requests
+pub fn deserialize_ogc_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(v) if v.eq_ignore_ascii_case("true") || v == "1" => Ok(Some(true)),
+        Some(v) if v.eq_ignore_ascii_case("false") || v == "0" => Ok(Some(false)),
+        Some(v) => Err(serde::de::Error::custom(format!("invalid boolean: {}", v))),
+    }
+}
+
+/// Public URL of an OGC service endpoint, used in capabilities documents
+pub fn service_url(config: &ServerConfig, service: &str) -> String {
+    let scheme = if config.tls.is_some() { "https" } else { "http" };
+    let host = if config.host == "0.0.0.0" { "localhost" } else { config.host.as_str() };
+    format!("{}://{}:{}/ogc/{}", scheme, host, config.port, service)
+}
+
+/// Parse a CRS identifier into an EPSG code
+///
+/// Accepts `EPSG:4326`, `CRS:84`, `urn:ogc:def:crs:EPSG::4326` and
+/// `http://www.opengis.net/def/crs/EPSG/0/4326` style identifiers.
+pub fn parse_crs(crs: &str) -> ServerResult<i32> {
+    let crs = crs.trim();
+    let upper = crs.to_ascii_uppercase();
+
+    if upper == "CRS:84" || upper.ends_with("/OGC/1.3/CRS84") || upper.ends_with("OGC:1.3:CRS84") {
+        return Ok(4326);
+    }
+
+    let code = if let Some(code) = upper.strip_prefix("EPSG:") {
+        code
+    } else if upper.starts_with("URN:OGC:DEF:CRS:EPSG:") {
+        upper.rsplit(':').next().unwrap_or_default()
+    } else if upper.contains("/DEF/CRS/EPSG/") {
+        upper.rsplit('/').next().unwrap_or_default()
+    } else {
+        ""
+    };
+
+    code.parse::<i32>()
+        .map_err(|_| ServerError::OgcError(format!("Unsupported CRS: {}", crs)))
+}
+
+/// Whether a CRS identifier uses CRS84 (longitude first) axis order
+pub fn is_crs84(crs: &str) -> bool {
+    let upper = crs.trim().to_ascii_uppercase();
+    upper == "CRS:84" || upper.ends_with("CRS84")
+}
+
+/// Whether an EPSG code is a geographic CRS with latitude-first axis order
+pub fn is_lat_lon_order(srid: i32) -> bool {
+    matches!(srid, 4326 | 4258 | 4269 | 4283 | 4617 | 4674 | 4019)
+}
+
+/// Whether an EPSG code is a geographic (degree based) CRS
+pub fn is_geographic(srid: i32) -> bool {
+    is_lat_lon_order(srid) || (4000..5000).contains(&srid)
+}
+
+/// Bounding box of an OGC request, always stored in x/y (easting/northing) order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OgcBBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+    pub srid: i32,
+}
+
+impl OgcBBox {
+    /// Parse a `minx,miny,maxx,maxy[,crs]` bounding box
+    ///
+    /// When `swap_axes` is set, the values are read in latitude/longitude
+    /// order as WMS 1.3.0 and WFS 2.0 require for EPSG:4326. A trailing CRS
+    /// (WFS style) overrides `default_srid`.
+    pub fn parse(value: &str, default_srid: i32, swap_axes: bool) -> ServerResult<Self> {
+        let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+        if parts.len() != 4 && parts.len() != 5 {
+            return Err(ServerError::OgcError(format!("Invalid BBOX: {}", value)));
+        }
+
+        let mut coords = [0.0; 4];
+        for (i, part) in parts[..4].iter().enumerate() {
+            coords[i] = part
+                .parse::<f64>()
+                .map_err(|_| ServerError::OgcError(format!("Invalid BBOX value: {}", part)))?;
+        }
+
+        let (srid, swap) = match parts.get(4) {
+            Some(crs) => {
+                let srid = parse_crs(crs)?;
+                (srid, !is_crs84(crs) && is_lat_lon_order(srid) && swap_axes)
+            }
+            None => (default_srid, swap_axes),
+        };
+
+        let [a, b, c, d] = coords;
+        let (min_x, min_y, max_x, max_y) = if swap { (b, a, d, c) } else { (a, b, c, d) };
+
+        if min_x > max_x || min_y > max_y {
+            return Err(ServerError::OgcError(format!("Invalid BBOX extent: {}", value)));
+        }
+
+        Ok(Self {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+            srid,
+        })
+    }
+
+    /// Width of the box in CRS units
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    /// Height of the box in CRS units
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// Convert to the database bounding box type
+    pub fn to_db(&self) -> meridian_db::BBox {
+        meridian_db::BBox::new(self.min_x, self.min_y, self.max_x, self.max_y, self.srid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Params {
+        request: Option<String>,
+        width: Option<u32>,
+        #[serde(default, deserialize_with = "deserialize_ogc_bool")]
+        transparent: Option<bool>,
+    }
+
+    #[test]
+    fn test_parse_kvp_is_case_insensitive() {
+        let params: Params = parse_kvp("REQUEST=GetMap&Width=256&TRANSPARENT=TRUE").unwrap();
+        assert_eq!(params.request.as_deref(), Some("GetMap"));
+        assert_eq!(params.width, Some(256));
+        assert_eq!(params.transparent, Some(true));
+    }
+
+    #[test]
+    fn test_parse_crs() {
+        assert_eq!(parse_crs("EPSG:3857").unwrap(), 3857);
+        assert_eq!(parse_crs("CRS:84").unwrap(), 4326);
+        assert_eq!(parse_crs("urn:ogc:def:crs:EPSG::4326").unwrap(), 4326);
+        assert_eq!(parse_crs("http://www.opengis.net/def/crs/EPSG/0/25832").unwrap(), 25832);
+        assert!(parse_crs("foo").is_err());
+    }
+
+    #[test]
+    fn test_bbox_axis_order() {
+        let bbox = OgcBBox::parse("40,-10,50,10", 4326, true).unwrap();
+        assert_eq!((bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y), (-10.0, 40.0, 10.0, 50.0));
+
+        let bbox = OgcBBox::parse("-10,40,10,50", 4326, false).unwrap();
+        assert_eq!((bbox.min_x, bbox.min_y), (-10.0, 40.0));
+
+        let bbox = OgcBBox::parse("0,0,100,100,EPSG:3857", 4326, true).unwrap();
+        assert_eq!(bbox.srid, 3857);
+        assert_eq!(bbox.max_x, 100.0);
+
+        assert!(OgcBBox::parse("1,2,3", 4326, false).is_err());
+        assert!(OgcBBox::parse("10,0,0,10", 4326, false).is_err());
+    }
+}
@@ -0,0 +1,561 @@
+//! WFS 2.0 GetFeature and DescribeFeatureType
+//!
+//! Turns WFS KVP requests into `meridian-db` feature queries, pages results
+//! across feature types and encodes them as GML 3.2 or GeoJSON. Feature type
+//! schemas are generated from the attributes found in a layer's features.
+
+use meridian_db::{FeatureFilter, FeatureQuery, Layer, LayerProperty, SortKey};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use uuid::Uuid;
+
+use super::{fes, geojson, gml, is_lat_lon_order, parse_crs, LayerCatalog, OgcBBox};
+use crate::{error::ServerResult, routes::ogc::WfsParams, ServerError};
+
+/// Default and maximum number of features returned by GetFeature
+pub const MAX_FEATURES: u64 = 10_000;
+
+/// Name of the geometry property of every feature type
+pub const GEOMETRY_PROPERTY: &str = "geometry";
+
+/// GetFeature output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Gml32,
+    GeoJson,
+}
+
+impl OutputFormat {
+    /// All supported formats, as advertised in capabilities
+    pub const ALL: [OutputFormat; 2] = [OutputFormat::Gml32, OutputFormat::GeoJson];
+
+    /// Parse an OUTPUTFORMAT value
+    pub fn from_mime_type(mime: &str) -> Option<Self> {
+        let mime = mime.trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "gml32" | "gml3" | "application/gml+xml" | "application/gml+xml; version=3.2"
+            | "text/xml; subtype=gml/3.2" | "text/xml; subtype=gml/3.2.1" | "text/xml" => {
+                Some(OutputFormat::Gml32)
+            }
+            "json" | "geojson" | "application/json" | "application/geo+json" => {
+                Some(OutputFormat::GeoJson)
+            }
+            _ => None,
+        }
+    }
+
+    /// MIME type of the response
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Gml32 => "application/gml+xml; version=3.2",
+            OutputFormat::GeoJson => "application/geo+json",
+        }
+    }
+}
+
+/// A validated GetFeature request
+#[derive(Debug, Clone)]
+pub struct GetFeatureRequest {
+    /// Feature types to query, in order
+    pub type_names: Vec<String>,
+    /// Filter from BBOX or FILTER
+    pub filter: FeatureFilter,
+    /// Features selected by RESOURCEID, keyed by feature type
+    pub resource_ids: Option<Vec<(String, Uuid)>>,
+    /// Sort order
+    pub sort: Vec<SortKey>,
+    /// Index of the first feature to return
+    pub start_index: u64,
+    /// Maximum number of features to return
+    pub count: u64,
+    /// Properties to include; `None` for all
+    pub property_names: Option<Vec<String>>,
+    /// Output SRID
+    pub srid: i32,
+    /// Whether GML positions are written latitude first
+    pub lat_lon: bool,
+    /// Output format
+    pub output_format: OutputFormat,
+}
+
+impl GetFeatureRequest {
+    /// Build a GetFeature request from WFS parameters
+    pub fn from_params(params: &WfsParams) -> ServerResult<Self> {
+        let srid = match params.srsname.as_deref().filter(|s| !s.is_empty()) {
+            Some(srs) => parse_crs(srs)?,
+            None => 4326,
+        };
+        let lat_lon = is_lat_lon_order(srid);
+
+        let resource_ids = params
+            .resourceid
+            .as_deref()
+            .or(params.featureid.as_deref())
+            .filter(|r| !r.is_empty())
+            .map(|ids| {
+                ids.split(',')
+                    .map(|rid| fes::parse_resource_id(rid.trim()))
+                    .collect::<ServerResult<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let type_names = match params.typenames.as_deref().or(params.typename.as_deref()) {
+            Some(names) if !names.is_empty() => split_list(names)
+                .map(|n| fes::strip_prefix(n).to_string())
+                .collect(),
+            _ => match &resource_ids {
+                Some(ids) => {
+                    let mut names: Vec<String> = Vec::new();
+                    for (layer, _) in ids {
+                        if !names.contains(layer) {
+                            names.push(layer.clone());
+                        }
+                    }
+                    names
+                }
+                None => return Err(missing("TYPENAMES")),
+            },
+        };
+
+        // BBOX, FILTER and RESOURCEID are mutually exclusive in WFS 2.0
+        let selections = [params.bbox.is_some(), params.filter.is_some(), resource_ids.is_some()];
+        if selections.iter().filter(|s| **s).count() > 1 {
+            return Err(ServerError::OgcError(
+                "BBOX, FILTER and RESOURCEID are mutually exclusive".to_string(),
+            ));
+        }
+        if params.cql_filter.is_some() {
+            return Err(ServerError::OgcError(
+                "OperationNotSupported: CQL_FILTER is not supported, use FILTER".to_string(),
+            ));
+        }
+        if let Some(language) = params.filter_language.as_deref() {
+            if !language.ends_with("fes/2.0/filter") && !language.ends_with("ogc/filter") {
+                return Err(ServerError::OgcError(format!(
+                    "Unsupported FILTER_LANGUAGE: {}",
+                    language
+                )));
+            }
+        }
+
+        let filter = if let Some(bbox) = params.bbox.as_deref() {
+            // Without a CRS suffix the box is in the default CRS, EPSG:4326 in latitude/longitude order
+            let bbox = OgcBBox::parse(bbox, 4326, true)?;
+            FeatureFilter::bbox(bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y, bbox.srid)
+        } else if let Some(filter) = params.filter.as_deref() {
+            fes::parse_filter(filter, 4326)?
+        } else {
+            FeatureFilter::Include
+        };
+
+        let count = params
+            .count
+            .or(params.maxfeatures)
+            .map(u64::from)
+            .unwrap_or(MAX_FEATURES)
+            .min(MAX_FEATURES);
+
+        let output_format = match params.outputformat.as_deref() {
+            None | Some("") => OutputFormat::Gml32,
+            Some(format) => OutputFormat::from_mime_type(format)
+                .ok_or_else(|| ServerError::OgcError(format!("InvalidParameterValue: OUTPUTFORMAT {}", format)))?,
+        };
+
+        Ok(Self {
+            type_names,
+            filter,
+            resource_ids,
+            sort: parse_sort_by(params.sortby.as_deref())?,
+            start_index: params.startindex.map(u64::from).unwrap_or(0),
+            count,
+            property_names: params.propertyname.as_deref().map(|names| {
+                split_list(names)
+                    .map(|n| fes::strip_prefix(n).to_string())
+                    .collect()
+            }),
+            srid,
+            lat_lon,
+            output_format,
+        })
+    }
+
+    /// Filter to apply to a feature type
+    pub fn filter_for(&self, type_name: &str) -> FeatureFilter {
+        match &self.resource_ids {
+            Some(ids) => {
+                let ids: Vec<Uuid> = ids
+                    .iter()
+                    .filter(|(layer, _)| layer == type_name)
+                    .map(|(_, id)| *id)
+                    .collect();
+                if ids.is_empty() {
+                    FeatureFilter::Exclude
+                } else {
+                    FeatureFilter::ResourceId(ids)
+                }
+            }
+            None => self.filter.clone(),
+        }
+    }
+
+    /// Whether the geometry is part of the requested properties
+    pub fn includes_geometry(&self) -> bool {
+        match &self.property_names {
+            Some(names) => names.iter().any(|n| n == GEOMETRY_PROPERTY),
+            None => true,
+        }
+    }
+
+    /// Restrict feature properties to the requested ones
+    pub fn select_properties(&self, properties: &Value) -> serde_json::Map<String, Value> {
+        let all = properties.as_object().cloned().unwrap_or_default();
+        match &self.property_names {
+            Some(names) => all.into_iter().filter(|(k, _)| names.contains(k)).collect(),
+            None => all,
+        }
+    }
+}
+
+/// Features of one feature type in a GetFeature response
+pub struct TypeResults {
+    /// Feature type name
+    pub type_name: String,
+    /// Features of the requested page
+    pub features: Vec<meridian_db::Feature>,
+}
+
+/// GetFeature results across all requested feature types
+pub struct FeatureResults {
+    /// Results per feature type, in request order
+    pub types: Vec<TypeResults>,
+    /// Number of features matching the request
+    pub number_matched: u64,
+    /// Lock held on the returned features (GetFeatureWithLock)
+    pub lock_id: Option<String>,
+}
+
+impl FeatureResults {
+    /// Number of features in the response
+    pub fn number_returned(&self) -> usize {
+        self.types.iter().map(|t| t.features.len()).sum()
+    }
+}
+
+/// Execute a GetFeature request
+///
+/// Paging runs over the concatenated results of all requested feature types.
+pub async fn get_features(
+    catalog: &LayerCatalog,
+    request: &GetFeatureRequest,
+) -> ServerResult<FeatureResults> {
+    let mut skip = request.start_index;
+    let mut remaining = request.count;
+    let mut number_matched = 0;
+    let mut types = Vec::with_capacity(request.type_names.len());
+
+    for type_name in &request.type_names {
+        let layer = catalog.layer(type_name).await?;
+        let query = FeatureQuery::new(request.filter_for(type_name))
+            .with_sort(request.sort.clone())
+            .with_paging(skip, Some(remaining))
+            .with_output_srid(request.srid);
+
+        let result = catalog.query(&layer, &query).await?;
+        number_matched += result.number_matched;
+        skip = skip.saturating_sub(result.number_matched);
+        remaining -= result.features.len() as u64;
+
+        types.push(TypeResults {
+            type_name: layer.name,
+            features: result.features,
+        });
+    }
+
+    Ok(FeatureResults {
+        types,
+        number_matched,
+        lock_id: None,
+    })
+}
+
+/// Encode GetFeature results in the requested format
+pub fn encode_features(request: &GetFeatureRequest, results: &FeatureResults) -> ServerResult<String> {
+    let include_geometry = request.includes_geometry();
+
+    match request.output_format {
+        OutputFormat::GeoJson => {
+            let features: Vec<Value> = results
+                .types
+                .iter()
+                .flat_map(|t| t.features.iter().map(move |f| (t, f)))
+                .map(|(t, feature)| {
+                    let geometry = feature
+                        .geometry_json
+                        .as_ref()
+                        .filter(|_| include_geometry)
+                        .cloned()
+                        .unwrap_or(Value::Null);
+                    json!({
+                        "type": "Feature",
+                        "id": format!("{}.{}", t.type_name, feature.id),
+                        "geometry": geometry,
+                        "properties": request.select_properties(&feature.properties),
+                    })
+                })
+                .collect();
+
+            let mut collection = json!({
+                "type": "FeatureCollection",
+                "numberMatched": results.number_matched,
+                "numberReturned": results.number_returned(),
+                "timeStamp": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                "features": features,
+            });
+            if let Some(lock_id) = &results.lock_id {
+                collection["lockId"] = Value::String(lock_id.clone());
+            }
+            Ok(collection.to_string())
+        }
+        OutputFormat::Gml32 => {
+            let properties: Vec<Vec<serde_json::Map<String, Value>>> = results
+                .types
+                .iter()
+                .map(|t| t.features.iter().map(|f| request.select_properties(&f.properties)).collect())
+                .collect();
+
+            let mut features = Vec::with_capacity(results.number_returned());
+            for (t, props) in results.types.iter().zip(&properties) {
+                for (feature, properties) in t.features.iter().zip(props) {
+                    let geometry = match (&feature.geometry_json, include_geometry) {
+                        (Some(value), true) => Some(geojson::to_geometry(value)?),
+                        _ => None,
+                    };
+                    features.push(gml::GmlFeature {
+                        type_name: &t.type_name,
+                        id: format!("{}.{}", t.type_name, feature.id),
+                        geometry,
+                        properties,
+                    });
+                }
+            }
+
+            Ok(gml::encode_feature_collection(
+                &features,
+                request.srid,
+                request.lat_lon,
+                Some(results.number_matched),
+                results.lock_id.as_deref(),
+            ))
+        }
+    }
+}
+
+/// Generate the XML schema describing feature types
+pub fn describe_feature_types(types: &[(Layer, Vec<LayerProperty>)]) -> String {
+    let mut xsd = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:gml="{gml}" xmlns:{prefix}="{ns}" targetNamespace="{ns}" elementFormDefault="qualified" version="1.0">
+  <xsd:import namespace="{gml}" schemaLocation="http://schemas.opengis.net/gml/3.2.1/gml.xsd"/>
+"#,
+        gml = gml::GML_NS,
+        prefix = gml::FEATURE_PREFIX,
+        ns = gml::FEATURE_NS,
+    );
+
+    for (layer, properties) in types {
+        let name = gml::xml_name(&layer.name);
+        let _ = write!(
+            xsd,
+            r#"  <xsd:complexType name="{name}Type">
+    <xsd:complexContent>
+      <xsd:extension base="gml:AbstractFeatureType">
+        <xsd:sequence>
+          <xsd:element name="{geometry}" type="{geometry_type}" minOccurs="0" maxOccurs="1"/>
+"#,
+            name = name,
+            geometry = GEOMETRY_PROPERTY,
+            geometry_type = gml_property_type(layer.geometry_type.as_deref()),
+        );
+
+        for property in properties {
+            let _ = writeln!(
+                xsd,
+                r#"          <xsd:element name="{}" type="{}" minOccurs="0" maxOccurs="1" nillable="{}"/>"#,
+                gml::xml_name(&property.name),
+                xsd_type(property),
+                property.nullable
+            );
+        }
+
+        let _ = write!(
+            xsd,
+            r#"        </xsd:sequence>
+      </xsd:extension>
+    </xsd:complexContent>
+  </xsd:complexType>
+  <xsd:element name="{name}" type="{prefix}:{name}Type" substitutionGroup="gml:AbstractFeature"/>
+"#,
+            name = name,
+            prefix = gml::FEATURE_PREFIX,
+        );
+    }
+
+    xsd.push_str("</xsd:schema>\n");
+    xsd
+}
+
+/// GML property type for a layer geometry type
+pub fn gml_property_type(geometry_type: Option<&str>) -> &'static str {
+    match geometry_type.map(|t| t.to_ascii_lowercase()).as_deref() {
+        Some("point") => "gml:PointPropertyType",
+        Some("linestring") => "gml:CurvePropertyType",
+        Some("polygon") => "gml:SurfacePropertyType",
+        Some("multipoint") => "gml:MultiPointPropertyType",
+        Some("multilinestring") => "gml:MultiCurvePropertyType",
+        Some("multipolygon") => "gml:MultiSurfacePropertyType",
+        _ => "gml:GeometryPropertyType",
+    }
+}
+
+/// XML schema type for an inferred layer property
+fn xsd_type(property: &LayerProperty) -> &'static str {
+    match property.json_type() {
+        Some("number") if property.integral => "xsd:long",
+        Some("number") => "xsd:double",
+        Some("boolean") => "xsd:boolean",
+        _ => "xsd:string",
+    }
+}
+
+/// Parse a SORTBY value such as `name ASC,height DESC`
+pub fn parse_sort_by(sort_by: Option<&str>) -> ServerResult<Vec<SortKey>> {
+    let Some(sort_by) = sort_by.filter(|s| !s.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+
+    sort_by
+        .split(',')
+        .map(|key| {
+            let mut parts = key.split_whitespace();
+            let property = parts
+                .next()
+                .ok_or_else(|| ServerError::OgcError(format!("Invalid SORTBY: {}", sort_by)))?;
+            let descending = match parts.next().map(|d| d.to_ascii_uppercase()).as_deref() {
+                None | Some("ASC") | Some("A") => false,
+                Some("DESC") | Some("D") => true,
+                Some(other) => {
+                    return Err(ServerError::OgcError(format!("Invalid sort order: {}", other)))
+                }
+            };
+            Ok(SortKey {
+                property: fes::strip_prefix(property).to_string(),
+                descending,
+            })
+        })
+        .collect()
+}
+
+/// Split a comma separated list, ignoring WFS 2.0 query grouping parentheses
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(|v| v.trim().trim_start_matches('(').trim_end_matches(')').trim())
+        .filter(|v| !v.is_empty())
+}
+
+fn missing(name: &str) -> ServerError {
+    ServerError::OgcError(format!("MissingParameterValue: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogc::parse_kvp;
+
+    fn params(query: &str) -> WfsParams {
+        parse_kvp(query).unwrap()
+    }
+
+    #[test]
+    fn test_get_feature_request() {
+        let request = GetFeatureRequest::from_params(&params(
+            "SERVICE=WFS&VERSION=2.0.0&REQUEST=GetFeature&TYPENAMES=meridian:roads\
+             &COUNT=50&STARTINDEX=100&SORTBY=name%20DESC&PROPERTYNAME=name,geometry\
+             &OUTPUTFORMAT=application/json&BBOX=40,-10,50,10",
+        ))
+        .unwrap();
+
+        assert_eq!(request.type_names, vec!["roads"]);
+        assert_eq!(request.count, 50);
+        assert_eq!(request.start_index, 100);
+        assert_eq!(request.sort, vec![SortKey { property: "name".to_string(), descending: true }]);
+        assert_eq!(request.output_format, OutputFormat::GeoJson);
+        assert!(request.includes_geometry());
+        assert_eq!(
+            request.filter,
+            FeatureFilter::bbox(-10.0, 40.0, 10.0, 50.0, 4326)
+        );
+    }
+
+    #[test]
+    fn test_resource_id_request() {
+        let request = GetFeatureRequest::from_params(&params(
+            "REQUEST=GetFeature&RESOURCEID=roads.00000000-0000-0000-0000-000000000001,\
+             parcels.00000000-0000-0000-0000-000000000002",
+        ))
+        .unwrap();
+
+        assert_eq!(request.type_names, vec!["roads", "parcels"]);
+        assert_eq!(
+            request.filter_for("parcels"),
+            FeatureFilter::ResourceId(vec![Uuid::from_u128(2)])
+        );
+    }
+
+    #[test]
+    fn test_exclusive_selections() {
+        assert!(GetFeatureRequest::from_params(&params(
+            "TYPENAMES=roads&BBOX=0,0,1,1&RESOURCEID=roads.00000000-0000-0000-0000-000000000001"
+        ))
+        .is_err());
+        assert!(GetFeatureRequest::from_params(&params("COUNT=1")).is_err());
+    }
+
+    #[test]
+    fn test_select_properties() {
+        let request =
+            GetFeatureRequest::from_params(&params("TYPENAMES=roads&PROPERTYNAME=name")).unwrap();
+        let selected = request.select_properties(&json!({ "name": "Main", "lanes": 2 }));
+        assert_eq!(selected.len(), 1);
+        assert!(!request.includes_geometry());
+    }
+
+    #[test]
+    fn test_describe_feature_types() {
+        let layer = Layer {
+            geometry_type: Some("LineString".to_string()),
+            ..Layer::new("roads".to_string(), "vector".to_string(), 4326)
+        };
+        let properties = vec![
+            LayerProperty {
+                name: "lanes".to_string(),
+                types: vec!["number".to_string()],
+                integral: true,
+                nullable: false,
+            },
+            LayerProperty {
+                name: "name".to_string(),
+                types: vec!["string".to_string(), "null".to_string()],
+                integral: true,
+                nullable: true,
+            },
+        ];
+
+        let xsd = describe_feature_types(&[(layer, properties)]);
+        assert!(xsd.contains(r#"<xsd:complexType name="roadsType">"#));
+        assert!(xsd.contains(r#"name="geometry" type="gml:CurvePropertyType""#));
+        assert!(xsd.contains(r#"name="lanes" type="xsd:long" minOccurs="0" maxOccurs="1" nillable="false""#));
+        assert!(xsd.contains(r#"name="name" type="xsd:string""#));
+        assert!(xsd.contains(r#"<xsd:element name="roads" type="meridian:roadsType" substitutionGroup="gml:AbstractFeature"/>"#));
+    }
+}
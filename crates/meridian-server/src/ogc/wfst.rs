@@ -0,0 +1,841 @@
+//! WFS 2.0 transactions and feature locking
+//!
+//! Parses `wfs:Transaction` and `wfs:LockFeature` documents, applies the
+//! actions of a transaction as a single `meridian-db` transaction, and
+//! encodes `wfs:TransactionResponse` and `wfs:LockFeatureResponse` documents.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use meridian_db::{
+    EditGeometry, FeatureEdit, FeatureFilter, FeatureUpdate, Layer, LockAction, NewFeature,
+    ReleaseAction,
+};
+use roxmltree::{Document, Node};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use super::{
+    fes, geojson,
+    gml::{self, escape},
+    parse_crs,
+    wfs::{self, FeatureResults, GetFeatureRequest, GEOMETRY_PROPERTY},
+    LayerCatalog,
+};
+use crate::{error::ServerResult, routes::ogc::WfsParams, ServerError};
+
+/// Lock expiry used when a request does not give one, in seconds
+pub const DEFAULT_LOCK_EXPIRY_SECS: u64 = 300;
+
+/// Longest lock expiry granted, in seconds
+pub const MAX_LOCK_EXPIRY_SECS: u64 = 86_400;
+
+/// XML Schema instance namespace, for `xsi:nil`
+const XSI_NS: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// A WFS request sent as an XML document with HTTP POST
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlRequest {
+    Transaction(TransactionRequest),
+    LockFeature(LockRequest),
+}
+
+impl XmlRequest {
+    /// Parse a POSTed WFS request document
+    pub fn parse(xml: &str) -> ServerResult<Self> {
+        let document = Document::parse(xml)
+            .map_err(|e| ServerError::OgcError(format!("Invalid request XML: {}", e)))?;
+        let root = document.root_element();
+
+        match root.tag_name().name() {
+            "Transaction" => Ok(XmlRequest::Transaction(TransactionRequest::from_element(root)?)),
+            "LockFeature" => Ok(XmlRequest::LockFeature(LockRequest::from_element(root)?)),
+            other => Err(ServerError::OgcError(format!(
+                "OperationNotSupported: {} is not supported with XML encoding",
+                other
+            ))),
+        }
+    }
+}
+
+/// New value of a property in an Update action
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    /// Literal, converted to the property's type
+    Text(String),
+    /// Geometry
+    Geometry(EditGeometry),
+    /// Set the property to null
+    Null,
+    /// Remove the property
+    Remove,
+}
+
+/// A feature of an Insert action
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertFeature {
+    /// Feature type name
+    pub type_name: String,
+    /// Geometry, if any
+    pub geometry: Option<EditGeometry>,
+    /// Property literals; `None` for nil properties
+    pub properties: Vec<(String, Option<String>)>,
+}
+
+/// An action of a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionAction {
+    Insert {
+        handle: Option<String>,
+        features: Vec<InsertFeature>,
+    },
+    Update {
+        type_name: String,
+        properties: Vec<(String, PropertyValue)>,
+        filter: FeatureFilter,
+    },
+    Delete {
+        type_name: String,
+        filter: FeatureFilter,
+    },
+}
+
+/// A `wfs:Transaction` request
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionRequest {
+    /// Actions, applied in order
+    pub actions: Vec<TransactionAction>,
+    /// Lock held on the features being updated or deleted
+    pub lock_id: Option<String>,
+    /// Locks to release once the transaction commits
+    pub release_action: ReleaseAction,
+}
+
+impl TransactionRequest {
+    /// Parse a `wfs:Transaction` element
+    pub fn from_element(node: Node<'_, '_>) -> ServerResult<Self> {
+        let actions = elements(node)
+            .map(|action| match action.tag_name().name() {
+                "Insert" => parse_insert(action),
+                "Update" => parse_update(action),
+                "Delete" => parse_delete(action),
+                other => Err(ServerError::OgcError(format!(
+                    "OperationNotSupported: transaction action {}",
+                    other
+                ))),
+            })
+            .collect::<ServerResult<Vec<_>>>()?;
+
+        let release_action = match node.attribute("releaseAction") {
+            None => ReleaseAction::All,
+            Some(action) if action.eq_ignore_ascii_case("ALL") => ReleaseAction::All,
+            Some(action) if action.eq_ignore_ascii_case("SOME") => ReleaseAction::Some,
+            Some(other) => return Err(invalid_value("releaseAction", other)),
+        };
+
+        Ok(Self {
+            actions,
+            lock_id: node.attribute("lockId").map(str::to_string),
+            release_action,
+        })
+    }
+}
+
+fn parse_insert(node: Node<'_, '_>) -> ServerResult<TransactionAction> {
+    let srid = default_srid(node)?;
+    let features = elements(node)
+        .map(|feature| parse_insert_feature(feature, srid))
+        .collect::<ServerResult<Vec<_>>>()?;
+
+    Ok(TransactionAction::Insert {
+        handle: node.attribute("handle").map(str::to_string),
+        features,
+    })
+}
+
+fn parse_insert_feature(node: Node<'_, '_>, srid: i32) -> ServerResult<InsertFeature> {
+    let mut feature = InsertFeature {
+        type_name: node.tag_name().name().to_string(),
+        geometry: None,
+        properties: Vec::new(),
+    };
+
+    // gml:name, gml:boundedBy and friends are not feature properties
+    for property in elements(node).filter(|p| p.tag_name().namespace() != Some(gml::GML_NS)) {
+        let name = property.tag_name().name();
+        match elements(property).next() {
+            Some(value) => {
+                if feature.geometry.is_some() {
+                    return Err(ServerError::OgcError(format!(
+                        "InvalidValue: {} has more than one geometry",
+                        feature.type_name
+                    )));
+                }
+                feature.geometry = Some(edit_geometry(value, srid)?);
+            }
+            None if name == GEOMETRY_PROPERTY => {}
+            None if is_nil(property) => feature.properties.push((name.to_string(), None)),
+            None => feature.properties.push((
+                name.to_string(),
+                Some(property.text().unwrap_or_default().to_string()),
+            )),
+        }
+    }
+
+    Ok(feature)
+}
+
+fn parse_update(node: Node<'_, '_>) -> ServerResult<TransactionAction> {
+    let srid = default_srid(node)?;
+    let type_name = type_name(node)?;
+
+    let mut properties = Vec::new();
+    for property in elements(node).filter(|p| p.tag_name().name() == "Property") {
+        let reference = gml::child(property, "ValueReference")
+            .or_else(|| gml::child(property, "Name"))
+            .ok_or_else(|| ServerError::OgcError("Property without ValueReference".to_string()))?;
+        let name = fes::strip_prefix(reference.text().unwrap_or_default().trim()).to_string();
+
+        let value = match (reference.attribute("action"), gml::child(property, "Value")) {
+            (Some("remove"), _) => PropertyValue::Remove,
+            (None | Some("replace"), None) => PropertyValue::Null,
+            (None | Some("replace"), Some(value)) => match elements(value).next() {
+                Some(geometry) => PropertyValue::Geometry(edit_geometry(geometry, srid)?),
+                None if is_nil(value) => PropertyValue::Null,
+                None => PropertyValue::Text(value.text().unwrap_or_default().to_string()),
+            },
+            (Some(other), _) => {
+                return Err(ServerError::OgcError(format!(
+                    "OperationNotSupported: update action {}",
+                    other
+                )))
+            }
+        };
+        properties.push((name, value));
+    }
+
+    // Without a filter an update applies to every feature of the type
+    let filter = match gml::child(node, "Filter") {
+        Some(filter) => fes::parse_filter_element(filter, srid)?,
+        None => FeatureFilter::Include,
+    };
+
+    Ok(TransactionAction::Update {
+        type_name,
+        properties,
+        filter,
+    })
+}
+
+fn parse_delete(node: Node<'_, '_>) -> ServerResult<TransactionAction> {
+    let filter = gml::child(node, "Filter")
+        .ok_or_else(|| ServerError::OgcError("MissingParameterValue: Delete without Filter".to_string()))?;
+
+    Ok(TransactionAction::Delete {
+        type_name: type_name(node)?,
+        filter: fes::parse_filter_element(filter, default_srid(node)?)?,
+    })
+}
+
+/// Outcome of a transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionResult {
+    /// Inserted features as `(handle, resource id)`, in insert order
+    pub inserted: Vec<(Option<String>, String)>,
+    /// Number of updated features
+    pub updated: u64,
+    /// Number of deleted features
+    pub deleted: u64,
+}
+
+/// Apply the actions of a transaction in a single database transaction
+///
+/// Property literals are converted to the types the layer already uses for
+/// them; unknown properties are stored as strings.
+pub async fn execute_transaction(
+    catalog: &LayerCatalog,
+    request: &TransactionRequest,
+) -> ServerResult<TransactionResult> {
+    let mut schemas: HashMap<String, TypeSchema> = HashMap::new();
+    let mut edits = Vec::new();
+    let mut inserts = Vec::new();
+
+    for action in &request.actions {
+        match action {
+            TransactionAction::Insert { handle, features } => {
+                for feature in features {
+                    let schema = TypeSchema::load(catalog, &mut schemas, &feature.type_name).await?;
+                    let properties = feature
+                        .properties
+                        .iter()
+                        .map(|(name, text)| {
+                            let value = match text {
+                                Some(text) => schema.coerce(name, text)?,
+                                None => Value::Null,
+                            };
+                            Ok((name.clone(), value))
+                        })
+                        .collect::<ServerResult<Map<String, Value>>>()?;
+
+                    edits.push(FeatureEdit::Insert(NewFeature {
+                        layer_id: schema.layer.id,
+                        geometry: feature.geometry.clone(),
+                        properties,
+                    }));
+                    inserts.push((handle.clone(), schema.layer.name.clone()));
+                }
+            }
+            TransactionAction::Update {
+                type_name,
+                properties,
+                filter,
+            } => {
+                let schema = TypeSchema::load(catalog, &mut schemas, type_name).await?;
+                let mut update = FeatureUpdate {
+                    layer_id: schema.layer.id,
+                    filter: filter.clone(),
+                    set_properties: Map::new(),
+                    remove_properties: Vec::new(),
+                    geometry: None,
+                };
+
+                for (name, value) in properties {
+                    match value {
+                        PropertyValue::Geometry(geometry) => update.geometry = Some(Some(geometry.clone())),
+                        PropertyValue::Null | PropertyValue::Remove if name == GEOMETRY_PROPERTY => {
+                            update.geometry = Some(None)
+                        }
+                        PropertyValue::Text(text) if name == GEOMETRY_PROPERTY => {
+                            return Err(invalid_value(name, text))
+                        }
+                        PropertyValue::Text(text) => {
+                            update.set_properties.insert(name.clone(), schema.coerce(name, text)?);
+                        }
+                        PropertyValue::Null => {
+                            update.set_properties.insert(name.clone(), Value::Null);
+                        }
+                        PropertyValue::Remove => update.remove_properties.push(name.clone()),
+                    }
+                }
+                edits.push(FeatureEdit::Update(update));
+            }
+            TransactionAction::Delete { type_name, filter } => {
+                let schema = TypeSchema::load(catalog, &mut schemas, type_name).await?;
+                edits.push(FeatureEdit::Delete {
+                    layer_id: schema.layer.id,
+                    filter: filter.clone(),
+                });
+            }
+        }
+    }
+
+    let summary = catalog
+        .apply_edits(&edits, request.lock_id.as_deref(), request.release_action)
+        .await
+        .map_err(|e| match e {
+            ServerError::Conflict(msg) => ServerError::OgcError(format!("OperationProcessingFailed: {}", msg)),
+            other => other,
+        })?;
+
+    Ok(TransactionResult {
+        inserted: inserts
+            .into_iter()
+            .zip(summary.inserted)
+            .map(|((handle, layer), id)| (handle, format!("{}.{}", layer, id)))
+            .collect(),
+        updated: summary.updated,
+        deleted: summary.deleted,
+    })
+}
+
+/// A feature type together with the JSON types of its properties
+struct TypeSchema {
+    layer: Layer,
+    types: HashMap<String, String>,
+}
+
+impl TypeSchema {
+    async fn load<'a>(
+        catalog: &LayerCatalog,
+        cache: &'a mut HashMap<String, TypeSchema>,
+        type_name: &str,
+    ) -> ServerResult<&'a TypeSchema> {
+        let key = fes::strip_prefix(type_name).to_string();
+        if !cache.contains_key(&key) {
+            let layer = catalog.layer(&key).await?;
+            let types = catalog
+                .properties(&layer)
+                .await?
+                .into_iter()
+                .filter_map(|p| {
+                    let json_type = p.json_type()?.to_string();
+                    Some((p.name, json_type))
+                })
+                .collect();
+            cache.insert(key.clone(), TypeSchema { layer, types });
+        }
+        Ok(&cache[&key])
+    }
+
+    /// Convert a literal to the JSON type used for a property
+    fn coerce(&self, name: &str, text: &str) -> ServerResult<Value> {
+        coerce_value(self.types.get(name).map(String::as_str), name, text)
+    }
+}
+
+fn coerce_value(json_type: Option<&str>, name: &str, text: &str) -> ServerResult<Value> {
+    let trimmed = text.trim();
+    match json_type {
+        Some("number") => {
+            if let Ok(integer) = trimmed.parse::<i64>() {
+                return Ok(Value::from(integer));
+            }
+            trimmed
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| invalid_value(name, text))
+        }
+        Some("boolean") => match trimmed {
+            "true" | "1" => Ok(Value::Bool(true)),
+            "false" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid_value(name, text)),
+        },
+        _ => Ok(Value::String(text.to_string())),
+    }
+}
+
+/// Encode a `wfs:TransactionResponse`
+pub fn encode_transaction_response(result: &TransactionResult) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:TransactionResponse xmlns:wfs="{wfs}" xmlns:fes="{fes}" version="2.0.0">
+  <wfs:TransactionSummary>
+    <wfs:totalInserted>{inserted}</wfs:totalInserted>
+    <wfs:totalUpdated>{updated}</wfs:totalUpdated>
+    <wfs:totalReplaced>0</wfs:totalReplaced>
+    <wfs:totalDeleted>{deleted}</wfs:totalDeleted>
+  </wfs:TransactionSummary>"#,
+        wfs = gml::WFS_NS,
+        fes = gml::FES_NS,
+        inserted = result.inserted.len(),
+        updated = result.updated,
+        deleted = result.deleted,
+    );
+
+    if !result.inserted.is_empty() {
+        out.push_str("\n  <wfs:InsertResults>");
+        for (handle, rid) in &result.inserted {
+            let handle = handle
+                .as_deref()
+                .map(|h| format!(r#" handle="{}""#, escape(h)))
+                .unwrap_or_default();
+            let _ = write!(
+                out,
+                r#"
+    <wfs:Feature{}><fes:ResourceId rid="{}"/></wfs:Feature>"#,
+                handle,
+                escape(rid)
+            );
+        }
+        out.push_str("\n  </wfs:InsertResults>");
+    }
+
+    out.push_str("\n</wfs:TransactionResponse>");
+    out
+}
+
+/// A LockFeature request
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockRequest {
+    /// Features to lock as `(type name, filter)`
+    pub queries: Vec<(String, FeatureFilter)>,
+    /// Lock expiry in seconds
+    pub expiry_secs: u64,
+    /// Whether all matching features must be locked
+    pub action: LockAction,
+}
+
+impl LockRequest {
+    /// Build a LockFeature request from WFS parameters
+    ///
+    /// Features are selected with the same parameters as GetFeature.
+    pub fn from_params(params: &WfsParams) -> ServerResult<Self> {
+        let query = GetFeatureRequest::from_params(params)?;
+        Ok(Self {
+            queries: query
+                .type_names
+                .iter()
+                .map(|t| (t.clone(), query.filter_for(t)))
+                .collect(),
+            expiry_secs: lock_expiry(params.expiry)?,
+            action: parse_lock_action(params.lockaction.as_deref())?,
+        })
+    }
+
+    /// Parse a `wfs:LockFeature` element
+    pub fn from_element(node: Node<'_, '_>) -> ServerResult<Self> {
+        let mut queries = Vec::new();
+        for query in elements(node).filter(|q| q.tag_name().name() == "Query") {
+            let type_names = query
+                .attribute("typeNames")
+                .or_else(|| query.attribute("typeName"))
+                .ok_or_else(|| ServerError::OgcError("MissingParameterValue: typeNames".to_string()))?;
+            let filter = match gml::child(query, "Filter") {
+                Some(filter) => fes::parse_filter_element(filter, default_srid(query)?)?,
+                None => FeatureFilter::Include,
+            };
+            for type_name in type_names.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+                queries.push((fes::strip_prefix(type_name).to_string(), filter.clone()));
+            }
+        }
+        if queries.is_empty() {
+            return Err(ServerError::OgcError("MissingParameterValue: Query".to_string()));
+        }
+
+        let expiry = node
+            .attribute("expiry")
+            .map(|e| e.trim().parse::<u64>().map_err(|_| invalid_value("expiry", e)))
+            .transpose()?;
+
+        Ok(Self {
+            queries,
+            expiry_secs: lock_expiry(expiry)?,
+            action: parse_lock_action(node.attribute("lockAction"))?,
+        })
+    }
+}
+
+/// Outcome of a lock request
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockResult {
+    /// Lock identifier
+    pub lock_id: String,
+    /// Resource ids of the locked features
+    pub locked: Vec<String>,
+    /// Resource ids of matching features held by other locks
+    pub not_locked: Vec<String>,
+}
+
+/// Lock the features selected by a LockFeature request
+pub async fn execute_lock(catalog: &LayerCatalog, request: &LockRequest) -> ServerResult<LockResult> {
+    let mut layers: Vec<Layer> = Vec::new();
+    let mut targets = Vec::with_capacity(request.queries.len());
+    for (type_name, filter) in &request.queries {
+        let layer = catalog.layer(type_name).await?;
+        targets.push((layer.id, filter.clone()));
+        layers.push(layer);
+    }
+
+    let lock = catalog
+        .lock_features(&targets, request.expiry_secs, request.action)
+        .await
+        .map_err(cannot_lock)?;
+
+    let rid = |(layer_id, id): &(Uuid, Uuid)| {
+        let layer = layers
+            .iter()
+            .find(|l| l.id == *layer_id)
+            .map(|l| l.name.as_str())
+            .unwrap_or_default();
+        format!("{}.{}", layer, id)
+    };
+
+    Ok(LockResult {
+        locked: lock.locked.iter().map(rid).collect(),
+        not_locked: lock.not_locked.iter().map(rid).collect(),
+        lock_id: lock.lock_id,
+    })
+}
+
+/// Encode a `wfs:LockFeatureResponse`
+pub fn encode_lock_response(result: &LockResult) -> String {
+    let resource_ids = |rids: &[String]| -> String {
+        rids.iter()
+            .map(|rid| format!(r#"<fes:ResourceId rid="{}"/>"#, escape(rid)))
+            .collect()
+    };
+
+    let mut out = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:LockFeatureResponse xmlns:wfs="{}" xmlns:fes="{}" lockId="{}">"#,
+        gml::WFS_NS,
+        gml::FES_NS,
+        escape(&result.lock_id)
+    );
+    if !result.locked.is_empty() {
+        let _ = write!(out, "\n  <wfs:FeaturesLocked>{}</wfs:FeaturesLocked>", resource_ids(&result.locked));
+    }
+    if !result.not_locked.is_empty() {
+        let _ = write!(
+            out,
+            "\n  <wfs:FeaturesNotLocked>{}</wfs:FeaturesNotLocked>",
+            resource_ids(&result.not_locked)
+        );
+    }
+    out.push_str("\n</wfs:LockFeatureResponse>");
+    out
+}
+
+/// Execute a GetFeatureWithLock request
+///
+/// Every returned feature is locked; the request fails if any of them is
+/// held by another lock.
+pub async fn get_features_with_lock(
+    catalog: &LayerCatalog,
+    request: &GetFeatureRequest,
+    expiry_secs: u64,
+) -> ServerResult<FeatureResults> {
+    let mut results = wfs::get_features(catalog, request).await?;
+
+    let targets: Vec<_> = results
+        .types
+        .iter()
+        .filter_map(|t| {
+            let layer_id = t.features.first()?.layer_id;
+            Some((layer_id, FeatureFilter::ResourceId(t.features.iter().map(|f| f.id).collect())))
+        })
+        .collect();
+
+    let lock = catalog
+        .lock_features(&targets, expiry_secs, LockAction::All)
+        .await
+        .map_err(cannot_lock)?;
+    results.lock_id = Some(lock.lock_id);
+    Ok(results)
+}
+
+/// Lock expiry in seconds, defaulted and capped
+///
+/// A zero expiry would grant a lock that has already lapsed and is
+/// rejected.
+pub fn lock_expiry(expiry: Option<u64>) -> ServerResult<u64> {
+    match expiry {
+        Some(0) => Err(invalid_value("expiry", "0")),
+        expiry => Ok(expiry.unwrap_or(DEFAULT_LOCK_EXPIRY_SECS).min(MAX_LOCK_EXPIRY_SECS)),
+    }
+}
+
+fn parse_lock_action(value: Option<&str>) -> ServerResult<LockAction> {
+    match value {
+        None | Some("") => Ok(LockAction::All),
+        Some(action) if action.eq_ignore_ascii_case("ALL") => Ok(LockAction::All),
+        Some(action) if action.eq_ignore_ascii_case("SOME") => Ok(LockAction::Some),
+        Some(other) => Err(invalid_value("lockAction", other)),
+    }
+}
+
+fn cannot_lock(err: ServerError) -> ServerError {
+    match err {
+        ServerError::Conflict(msg) => ServerError::OgcError(format!("CannotLockAllFeatures: {}", msg)),
+        other => other,
+    }
+}
+
+fn edit_geometry(node: Node<'_, '_>, default_srid: i32) -> ServerResult<EditGeometry> {
+    let (geometry, srid) = gml::decode_geometry(node, default_srid)?;
+    Ok(EditGeometry {
+        geojson: geojson::from_geometry(&geometry),
+        srid,
+    })
+}
+
+/// SRID given by an action's `srsName`, EPSG:4326 otherwise
+fn default_srid(node: Node<'_, '_>) -> ServerResult<i32> {
+    match node.attribute("srsName") {
+        Some(srs) => parse_crs(srs),
+        None => Ok(4326),
+    }
+}
+
+fn type_name(node: Node<'_, '_>) -> ServerResult<String> {
+    node.attribute("typeName")
+        .map(|name| fes::strip_prefix(name).to_string())
+        .ok_or_else(|| {
+            ServerError::OgcError(format!(
+                "MissingParameterValue: {} without typeName",
+                node.tag_name().name()
+            ))
+        })
+}
+
+fn is_nil(node: Node<'_, '_>) -> bool {
+    matches!(node.attribute((XSI_NS, "nil")), Some("true") | Some("1"))
+}
+
+fn elements<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(Node::is_element)
+}
+
+fn invalid_value(name: &str, value: &str) -> ServerError {
+    ServerError::OgcError(format!("InvalidValue: {} for {}", value, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSACTION: &str = r#"<wfs:Transaction service="WFS" version="2.0.0" releaseAction="SOME" lockId="abc"
+        xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:fes="http://www.opengis.net/fes/2.0"
+        xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:meridian="http://meridian-gis.org/features"
+        xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <wfs:Insert handle="new-road">
+        <meridian:roads>
+          <meridian:geometry>
+            <gml:LineString srsName="urn:ogc:def:crs:EPSG::4326"><gml:posList>50 10 51 11</gml:posList></gml:LineString>
+          </meridian:geometry>
+          <meridian:name>Main St</meridian:name>
+          <meridian:lanes xsi:nil="true"/>
+        </meridian:roads>
+      </wfs:Insert>
+      <wfs:Update typeName="meridian:roads">
+        <wfs:Property><wfs:ValueReference>lanes</wfs:ValueReference><wfs:Value>4</wfs:Value></wfs:Property>
+        <wfs:Property><wfs:ValueReference action="remove">surface</wfs:ValueReference></wfs:Property>
+        <fes:Filter><fes:ResourceId rid="roads.00000000-0000-0000-0000-000000000001"/></fes:Filter>
+      </wfs:Update>
+      <wfs:Delete typeName="meridian:roads">
+        <fes:Filter><fes:ResourceId rid="roads.00000000-0000-0000-0000-000000000002"/></fes:Filter>
+      </wfs:Delete>
+    </wfs:Transaction>"#;
+
+    #[test]
+    fn test_parse_transaction() {
+        let request = match XmlRequest::parse(TRANSACTION).unwrap() {
+            XmlRequest::Transaction(request) => request,
+            other => panic!("unexpected request {:?}", other),
+        };
+        assert_eq!(request.lock_id.as_deref(), Some("abc"));
+        assert_eq!(request.release_action, ReleaseAction::Some);
+        assert_eq!(request.actions.len(), 3);
+
+        match &request.actions[0] {
+            TransactionAction::Insert { handle, features } => {
+                assert_eq!(handle.as_deref(), Some("new-road"));
+                assert_eq!(features[0].type_name, "roads");
+                assert_eq!(
+                    features[0].properties,
+                    vec![("name".to_string(), Some("Main St".to_string())), ("lanes".to_string(), None)]
+                );
+                let geometry = features[0].geometry.as_ref().unwrap();
+                assert_eq!(geometry.srid, 4326);
+                assert_eq!(geometry.geojson["coordinates"][0], serde_json::json!([10.0, 50.0]));
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+
+        assert_eq!(
+            request.actions[1],
+            TransactionAction::Update {
+                type_name: "roads".to_string(),
+                properties: vec![
+                    ("lanes".to_string(), PropertyValue::Text("4".to_string())),
+                    ("surface".to_string(), PropertyValue::Remove),
+                ],
+                filter: FeatureFilter::ResourceId(vec![Uuid::from_u128(1)]),
+            }
+        );
+        assert_eq!(
+            request.actions[2],
+            TransactionAction::Delete {
+                type_name: "roads".to_string(),
+                filter: FeatureFilter::ResourceId(vec![Uuid::from_u128(2)]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_delete_requires_filter() {
+        let xml = r#"<wfs:Transaction xmlns:wfs="http://www.opengis.net/wfs/2.0">
+            <wfs:Delete typeName="roads"/></wfs:Transaction>"#;
+        assert!(XmlRequest::parse(xml).is_err());
+    }
+
+    #[test]
+    fn test_parse_lock_feature() {
+        let xml = r#"<wfs:LockFeature xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:fes="http://www.opengis.net/fes/2.0"
+            expiry="600" lockAction="SOME">
+          <wfs:Query typeNames="meridian:roads">
+            <fes:Filter><fes:PropertyIsEqualTo><fes:ValueReference>name</fes:ValueReference>
+              <fes:Literal>Main St</fes:Literal></fes:PropertyIsEqualTo></fes:Filter>
+          </wfs:Query>
+          <wfs:Query typeNames="meridian:parcels"/>
+        </wfs:LockFeature>"#;
+        let request = match XmlRequest::parse(xml).unwrap() {
+            XmlRequest::LockFeature(request) => request,
+            other => panic!("unexpected request {:?}", other),
+        };
+        assert_eq!(request.expiry_secs, 600);
+        assert_eq!(request.action, LockAction::Some);
+        assert_eq!(request.queries.len(), 2);
+        assert_eq!(request.queries[1], ("parcels".to_string(), FeatureFilter::Include));
+    }
+
+    #[test]
+    fn test_lock_params() {
+        let params: WfsParams =
+            crate::ogc::parse_kvp("REQUEST=LockFeature&TYPENAMES=roads&EXPIRY=999999").unwrap();
+        let request = LockRequest::from_params(&params).unwrap();
+        assert_eq!(request.expiry_secs, MAX_LOCK_EXPIRY_SECS);
+        assert_eq!(request.action, LockAction::All);
+        assert_eq!(request.queries, vec![("roads".to_string(), FeatureFilter::Include)]);
+
+        let params: WfsParams = crate::ogc::parse_kvp("REQUEST=LockFeature&TYPENAMES=roads").unwrap();
+        let request = LockRequest::from_params(&params).unwrap();
+        assert_eq!(request.expiry_secs, DEFAULT_LOCK_EXPIRY_SECS);
+    }
+
+    #[test]
+    fn test_lock_expiry_validation() {
+        let lock_feature = |expiry: &str| {
+            XmlRequest::parse(&format!(
+                r#"<wfs:LockFeature xmlns:wfs="http://www.opengis.net/wfs/2.0" expiry="{}">
+                  <wfs:Query typeNames="roads"/></wfs:LockFeature>"#,
+                expiry
+            ))
+        };
+        match lock_feature("90000").unwrap() {
+            XmlRequest::LockFeature(request) => assert_eq!(request.expiry_secs, MAX_LOCK_EXPIRY_SECS),
+            other => panic!("unexpected request {:?}", other),
+        }
+        assert!(lock_feature("0").is_err());
+        assert!(lock_feature("-5").is_err());
+        assert!(lock_feature("99999999999999999999999").is_err());
+
+        let params: WfsParams = crate::ogc::parse_kvp("REQUEST=LockFeature&TYPENAMES=roads&EXPIRY=0").unwrap();
+        assert!(LockRequest::from_params(&params).is_err());
+    }
+
+    #[test]
+    fn test_coerce_value() {
+        assert_eq!(coerce_value(Some("number"), "lanes", "4").unwrap(), serde_json::json!(4));
+        assert_eq!(coerce_value(Some("number"), "width", " 7.5").unwrap(), serde_json::json!(7.5));
+        assert!(coerce_value(Some("number"), "lanes", "four").is_err());
+        assert_eq!(coerce_value(Some("boolean"), "oneway", "true").unwrap(), Value::Bool(true));
+        assert_eq!(coerce_value(None, "zip", "007").unwrap(), Value::String("007".to_string()));
+    }
+
+    #[test]
+    fn test_encode_responses() {
+        let xml = encode_transaction_response(&TransactionResult {
+            inserted: vec![(Some("new-road".to_string()), "roads.1".to_string())],
+            updated: 2,
+            deleted: 1,
+        });
+        assert!(xml.contains("<wfs:totalInserted>1</wfs:totalInserted>"));
+        assert!(xml.contains("<wfs:totalUpdated>2</wfs:totalUpdated>"));
+        assert!(xml.contains(r#"<wfs:Feature handle="new-road"><fes:ResourceId rid="roads.1"/></wfs:Feature>"#));
+        assert!(Document::parse(&xml).is_ok());
+
+        let xml = encode_lock_response(&LockResult {
+            lock_id: "abc".to_string(),
+            locked: vec!["roads.1".to_string()],
+            not_locked: Vec::new(),
+        });
+        assert!(xml.contains(r#"lockId="abc""#));
+        assert!(xml.contains(r#"<wfs:FeaturesLocked><fes:ResourceId rid="roads.1"/></wfs:FeaturesLocked>"#));
+        assert!(!xml.contains("FeaturesNotLocked"));
+    }
+}
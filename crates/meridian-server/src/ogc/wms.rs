@@ -0,0 +1,718 @@
+//! WMS GetMap and GetFeatureInfo
+//!
+//! Parses WMS 1.1.1/1.3.0 map requests, renders layers with
+//! `meridian-render` and hit-tests features for GetFeatureInfo.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use meridian_core::geo::{Contains, EuclideanDistance};
+use meridian_core::geo_types::{Coord, Geometry, Point};
+use meridian_db::{Feature, Layer};
+use meridian_render::pipeline::{CompositeLayer, LayerCompositor};
+use meridian_render::raster::{RasterRenderer, TileData, TileFormat};
+use meridian_render::style::Style;
+use meridian_render::TileBounds;
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+use super::{geojson, gml, is_crs84, is_geographic, is_lat_lon_order, parse_crs, LayerCatalog, OgcBBox};
+use crate::{error::ServerResult, routes::ogc::WmsParams, ServerError};
+
+/// Largest image edge accepted for GetMap
+pub const MAX_IMAGE_SIZE: u32 = 4096;
+
+/// Maximum number of features loaded per layer for a map
+pub const MAX_FEATURES_PER_LAYER: u32 = 50_000;
+
+/// Search radius around the queried pixel for GetFeatureInfo
+pub const FEATURE_INFO_TOLERANCE_PX: f64 = 4.0;
+
+/// Half the circumference of the Web Mercator world in metres
+const WEB_MERCATOR_EXTENT: f64 = 20037508.342789244;
+
+/// A validated GetMap request
+#[derive(Debug, Clone)]
+pub struct MapRequest {
+    /// Requested WMS version
+    pub version: String,
+    /// Layer names in drawing order
+    pub layers: Vec<String>,
+    /// Style name per layer, `None` for the default style
+    pub styles: Vec<Option<String>>,
+    /// Map extent in x/y order
+    pub bbox: OgcBBox,
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Output image format
+    pub format: TileFormat,
+    /// Whether the background is left transparent
+    pub transparent: bool,
+    /// Background color
+    pub bgcolor: Rgba<u8>,
+}
+
+impl MapRequest {
+    /// Build a map request from WMS parameters
+    pub fn from_params(params: &WmsParams) -> ServerResult<Self> {
+        let version = params.version.clone().unwrap_or_else(|| "1.3.0".to_string());
+
+        let layers: Vec<String> = required(&params.layers, "LAYERS")?
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        if layers.is_empty() {
+            return Err(ServerError::OgcError("LAYERS must name at least one layer".to_string()));
+        }
+
+        let styles = parse_styles(params.styles.as_deref(), layers.len())?;
+
+        // WMS 1.3.0 uses CRS and the axis order of the CRS; 1.1.1 uses SRS and x/y
+        let (crs, swap_axes) = if version.starts_with("1.3") {
+            let crs = required(&params.crs, "CRS")?;
+            let srid = parse_crs(crs)?;
+            (srid, is_lat_lon_order(srid) && !is_crs84(crs))
+        } else {
+            let srs = required(&params.srs, "SRS").or_else(|_| required(&params.crs, "SRS"))?;
+            (parse_crs(srs)?, false)
+        };
+        let bbox = OgcBBox::parse(required(&params.bbox, "BBOX")?, crs, swap_axes)?;
+        if bbox.width() <= 0.0 || bbox.height() <= 0.0 {
+            return Err(ServerError::OgcError("BBOX must have a non-zero extent".to_string()));
+        }
+
+        let width = params.width.ok_or_else(|| missing("WIDTH"))?;
+        let height = params.height.ok_or_else(|| missing("HEIGHT"))?;
+        if width == 0 || height == 0 || width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+            return Err(ServerError::OgcError(format!(
+                "WIDTH and HEIGHT must be between 1 and {}",
+                MAX_IMAGE_SIZE
+            )));
+        }
+
+        let format = match params.format.as_deref() {
+            None | Some("") => TileFormat::Png,
+            Some(mime) => TileFormat::from_mime_type(mime)
+                .ok_or_else(|| ServerError::OgcError(format!("InvalidFormat: {}", mime)))?,
+        };
+
+        let bgcolor = match params.bgcolor.as_deref() {
+            Some(value) => parse_bgcolor(value)?,
+            None => Rgba([255, 255, 255, 255]),
+        };
+
+        Ok(Self {
+            version,
+            layers,
+            styles,
+            bbox,
+            width,
+            height,
+            format,
+            transparent: params.transparent.unwrap_or(false) && format.supports_transparency(),
+            bgcolor,
+        })
+    }
+
+    /// Map units per pixel along x and y
+    pub fn resolution(&self) -> (f64, f64) {
+        (
+            self.bbox.width() / f64::from(self.width),
+            self.bbox.height() / f64::from(self.height),
+        )
+    }
+
+    /// Approximate web map zoom level of the request, used for style zoom filters
+    pub fn zoom(&self) -> f64 {
+        let world = if is_geographic(self.bbox.srid) {
+            360.0
+        } else {
+            2.0 * WEB_MERCATOR_EXTENT
+        };
+        let (resolution, _) = self.resolution();
+        (world / (resolution * 256.0)).log2().max(0.0)
+    }
+
+    /// World coordinate at the centre of pixel `(i, j)`
+    pub fn pixel_to_world(&self, i: u32, j: u32) -> Coord<f64> {
+        let (res_x, res_y) = self.resolution();
+        Coord {
+            x: self.bbox.min_x + (f64::from(i) + 0.5) * res_x,
+            y: self.bbox.max_y - (f64::from(j) + 0.5) * res_y,
+        }
+    }
+
+    /// Render bounds for `meridian-render`
+    pub fn render_bounds(&self) -> TileBounds {
+        TileBounds {
+            min_x: self.bbox.min_x,
+            min_y: self.bbox.min_y,
+            max_x: self.bbox.max_x,
+            max_y: self.bbox.max_y,
+        }
+    }
+}
+
+/// A layer ready to be drawn into a map
+pub struct MapLayer {
+    /// Style to render with
+    pub style: Style,
+    /// Geometries in the request CRS
+    pub data: TileData,
+    /// Layer opacity
+    pub opacity: f32,
+}
+
+/// Load the features of a layer within a map extent for rendering
+pub async fn load_map_layer(
+    catalog: &LayerCatalog,
+    layer: &Layer,
+    style: Style,
+    bbox: &OgcBBox,
+) -> ServerResult<MapLayer> {
+    let features = catalog
+        .features_in_bbox(layer, bbox, MAX_FEATURES_PER_LAYER)
+        .await?;
+
+    Ok(MapLayer {
+        style,
+        data: features_to_tile_data(&features),
+        opacity: layer.opacity as f32,
+    })
+}
+
+/// Render and encode a map image
+///
+/// Each layer is rendered on a transparent canvas and composited in order
+/// over the background. Rendering is CPU bound; call this from a blocking task.
+pub fn render_map(request: &MapRequest, layers: &[MapLayer]) -> ServerResult<Vec<u8>> {
+    let renderer = RasterRenderer::new()
+        .with_background_color(Rgba([0, 0, 0, 0]))
+        .with_antialias(true);
+    let bounds = request.render_bounds();
+    let zoom = request.zoom();
+
+    let mut compositor = LayerCompositor::new();
+    if !request.transparent {
+        let background = RgbaImage::from_pixel(request.width, request.height, request.bgcolor);
+        compositor.add_layer(CompositeLayer::new(DynamicImage::ImageRgba8(background)));
+    }
+
+    for layer in layers {
+        if layer.data.is_empty() {
+            continue;
+        }
+        let image = renderer
+            .render_extent(&bounds, request.width, request.height, zoom, &layer.style, &layer.data)
+            .map_err(|e| ServerError::Internal(format!("Rendering failed: {}", e)))?;
+        compositor.add_layer(CompositeLayer::new(image).with_opacity(layer.opacity));
+    }
+
+    let image = compositor
+        .composite_sized(request.width, request.height)
+        .map_err(|e| ServerError::Internal(format!("Compositing failed: {}", e)))?;
+
+    renderer
+        .encode(&image, request.format)
+        .map_err(|e| ServerError::Internal(format!("Encoding failed: {}", e)))
+}
+
+/// Collect feature geometries into render data
+pub fn features_to_tile_data(features: &[Feature]) -> TileData {
+    let mut data = TileData::new();
+    for geometry in features.iter().filter_map(feature_geometry) {
+        add_geometry(&mut data, &geometry);
+    }
+    data
+}
+
+/// Decode the GeoJSON geometry of a stored feature
+pub fn feature_geometry(feature: &Feature) -> Option<Geometry<f64>> {
+    let value = feature.geometry_json.as_ref()?;
+    match geojson::to_geometry(value) {
+        Ok(geometry) => Some(geometry),
+        Err(e) => {
+            tracing::warn!("Skipping feature {} with invalid geometry: {}", feature.id, e);
+            None
+        }
+    }
+}
+
+fn add_geometry(data: &mut TileData, geometry: &Geometry<f64>) {
+    let ring = |ls: &meridian_core::geo_types::LineString<f64>| -> Vec<(f64, f64)> {
+        ls.0.iter().map(|c| (c.x, c.y)).collect()
+    };
+
+    match geometry {
+        Geometry::Point(p) => data.add_point(p.x(), p.y()),
+        Geometry::MultiPoint(mp) => mp.0.iter().for_each(|p| data.add_point(p.x(), p.y())),
+        Geometry::LineString(ls) => data.add_line(ring(ls)),
+        Geometry::MultiLineString(mls) => mls.0.iter().for_each(|ls| data.add_line(ring(ls))),
+        Geometry::Polygon(p) => {
+            data.add_polygon_with_holes(ring(p.exterior()), p.interiors().iter().map(ring).collect())
+        }
+        Geometry::MultiPolygon(mp) => mp.0.iter().for_each(|p| {
+            data.add_polygon_with_holes(ring(p.exterior()), p.interiors().iter().map(ring).collect())
+        }),
+        Geometry::GeometryCollection(gc) => gc.0.iter().for_each(|g| add_geometry(data, g)),
+        Geometry::Line(line) => data.add_line(vec![line.start.x_y(), line.end.x_y()]),
+        Geometry::Rect(rect) => data.add_polygon(ring(rect.to_polygon().exterior())),
+        Geometry::Triangle(triangle) => data.add_polygon(ring(triangle.to_polygon().exterior())),
+    }
+}
+
+/// Output formats supported by GetFeatureInfo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoFormat {
+    GeoJson,
+    Html,
+    Gml,
+}
+
+impl InfoFormat {
+    /// All supported formats, as advertised in capabilities
+    pub const ALL: [InfoFormat; 3] = [InfoFormat::GeoJson, InfoFormat::Html, InfoFormat::Gml];
+
+    /// Parse an INFO_FORMAT value
+    pub fn from_mime_type(mime: &str) -> Option<Self> {
+        let mime = mime.trim().to_ascii_lowercase();
+        match mime.split(';').next().unwrap_or_default().trim() {
+            "application/json" | "application/geo+json" | "application/geojson" => {
+                Some(InfoFormat::GeoJson)
+            }
+            "text/html" => Some(InfoFormat::Html),
+            "application/gml+xml" | "application/vnd.ogc.gml" | "text/xml" => Some(InfoFormat::Gml),
+            _ => None,
+        }
+    }
+
+    /// MIME type of the response
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            InfoFormat::GeoJson => "application/json",
+            InfoFormat::Html => "text/html",
+            InfoFormat::Gml => "application/gml+xml; version=3.2",
+        }
+    }
+}
+
+/// A validated GetFeatureInfo request
+#[derive(Debug, Clone)]
+pub struct FeatureInfoRequest {
+    /// The map the client clicked on
+    pub map: MapRequest,
+    /// Layers to query
+    pub query_layers: Vec<String>,
+    /// Pixel column
+    pub i: u32,
+    /// Pixel row
+    pub j: u32,
+    /// Response format
+    pub info_format: InfoFormat,
+    /// Maximum number of features per layer
+    pub feature_count: u32,
+}
+
+impl FeatureInfoRequest {
+    /// Build a feature info request from WMS parameters
+    pub fn from_params(params: &WmsParams) -> ServerResult<Self> {
+        let map = MapRequest::from_params(params)?;
+
+        let query_layers: Vec<String> = required(&params.query_layers, "QUERY_LAYERS")?
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        if let Some(layer) = query_layers.iter().find(|l| !map.layers.contains(l)) {
+            return Err(ServerError::OgcError(format!(
+                "LayerNotQueryable: {} is not part of LAYERS",
+                layer
+            )));
+        }
+
+        // WMS 1.3.0 names the pixel I/J, 1.1.1 X/Y
+        let (i, j) = if map.version.starts_with("1.3") {
+            (params.i.ok_or_else(|| missing("I"))?, params.j.ok_or_else(|| missing("J"))?)
+        } else {
+            (
+                params.x.or(params.i).ok_or_else(|| missing("X"))?,
+                params.y.or(params.j).ok_or_else(|| missing("Y"))?,
+            )
+        };
+        if i >= map.width || j >= map.height {
+            return Err(ServerError::OgcError("InvalidPoint: pixel outside the map".to_string()));
+        }
+
+        let info_format = match params.info_format.as_deref() {
+            None | Some("") => InfoFormat::Html,
+            Some(mime) => InfoFormat::from_mime_type(mime)
+                .ok_or_else(|| ServerError::OgcError(format!("InvalidFormat: {}", mime)))?,
+        };
+
+        Ok(Self {
+            map,
+            query_layers,
+            i,
+            j,
+            info_format,
+            feature_count: params.feature_count.unwrap_or(1).max(1),
+        })
+    }
+
+    /// Queried location in map coordinates
+    pub fn location(&self) -> Point<f64> {
+        Point(self.map.pixel_to_world(self.i, self.j))
+    }
+
+    /// Search distance in map units
+    pub fn tolerance(&self) -> f64 {
+        let (res_x, res_y) = self.map.resolution();
+        res_x.max(res_y) * FEATURE_INFO_TOLERANCE_PX
+    }
+
+    /// Box around the queried location used to prefilter features
+    pub fn search_bbox(&self) -> OgcBBox {
+        let location = self.location();
+        let tolerance = self.tolerance();
+        OgcBBox {
+            min_x: location.x() - tolerance,
+            min_y: location.y() - tolerance,
+            max_x: location.x() + tolerance,
+            max_y: location.y() + tolerance,
+            srid: self.map.bbox.srid,
+        }
+    }
+}
+
+/// Whether a geometry lies at a location, within a tolerance
+pub fn hit_test(geometry: &Geometry<f64>, location: &Point<f64>, tolerance: f64) -> bool {
+    match geometry {
+        Geometry::Point(p) => p.euclidean_distance(location) <= tolerance,
+        Geometry::MultiPoint(mp) => location.euclidean_distance(mp) <= tolerance,
+        Geometry::LineString(ls) => location.euclidean_distance(ls) <= tolerance,
+        Geometry::MultiLineString(mls) => location.euclidean_distance(mls) <= tolerance,
+        Geometry::Polygon(p) => p.contains(location) || location.euclidean_distance(p) <= tolerance,
+        Geometry::MultiPolygon(mp) => {
+            mp.contains(location) || location.euclidean_distance(mp) <= tolerance
+        }
+        Geometry::GeometryCollection(gc) => gc.0.iter().any(|g| hit_test(g, location, tolerance)),
+        Geometry::Line(line) => location.euclidean_distance(line) <= tolerance,
+        Geometry::Rect(rect) => hit_test(&Geometry::Polygon(rect.to_polygon()), location, tolerance),
+        Geometry::Triangle(triangle) => {
+            hit_test(&Geometry::Polygon(triangle.to_polygon()), location, tolerance)
+        }
+    }
+}
+
+/// Features of one layer found by GetFeatureInfo
+pub struct LayerHits {
+    /// Layer name
+    pub layer: String,
+    /// Matching features with their decoded geometries
+    pub features: Vec<(Feature, Geometry<f64>)>,
+}
+
+/// Keep the features hit at the queried location, nearest first
+pub fn select_hits(
+    request: &FeatureInfoRequest,
+    layer: &str,
+    features: Vec<Feature>,
+) -> LayerHits {
+    let location = request.location();
+    let tolerance = request.tolerance();
+
+    let mut hits: Vec<(f64, Feature, Geometry<f64>)> = features
+        .into_iter()
+        .filter_map(|feature| {
+            let geometry = feature_geometry(&feature)?;
+            if !hit_test(&geometry, &location, tolerance) {
+                return None;
+            }
+            let distance = distance_to(&geometry, &location);
+            Some((distance, feature, geometry))
+        })
+        .collect();
+
+    hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    hits.truncate(request.feature_count as usize);
+
+    LayerHits {
+        layer: layer.to_string(),
+        features: hits.into_iter().map(|(_, f, g)| (f, g)).collect(),
+    }
+}
+
+fn distance_to(geometry: &Geometry<f64>, location: &Point<f64>) -> f64 {
+    match geometry {
+        Geometry::Point(p) => p.euclidean_distance(location),
+        Geometry::MultiPoint(mp) => location.euclidean_distance(mp),
+        Geometry::LineString(ls) => location.euclidean_distance(ls),
+        Geometry::MultiLineString(mls) => location.euclidean_distance(mls),
+        Geometry::Polygon(p) if p.contains(location) => 0.0,
+        Geometry::Polygon(p) => location.euclidean_distance(p),
+        Geometry::MultiPolygon(mp) if mp.contains(location) => 0.0,
+        Geometry::MultiPolygon(mp) => location.euclidean_distance(mp),
+        Geometry::GeometryCollection(gc) => gc
+            .0
+            .iter()
+            .map(|g| distance_to(g, location))
+            .fold(f64::INFINITY, f64::min),
+        Geometry::Line(line) => location.euclidean_distance(line),
+        Geometry::Rect(rect) => distance_to(&Geometry::Polygon(rect.to_polygon()), location),
+        Geometry::Triangle(t) => distance_to(&Geometry::Polygon(t.to_polygon()), location),
+    }
+}
+
+/// Feature identifier used in OGC responses (`layer.uuid`)
+pub fn feature_id(layer: &str, feature: &Feature) -> String {
+    format!("{}.{}", layer, feature.id)
+}
+
+/// Encode GetFeatureInfo results in the requested format
+pub fn encode_feature_info(request: &FeatureInfoRequest, hits: &[LayerHits]) -> String {
+    match request.info_format {
+        InfoFormat::GeoJson => encode_geojson(hits),
+        InfoFormat::Html => encode_html(hits),
+        InfoFormat::Gml => {
+            let srid = request.map.bbox.srid;
+            let features: Vec<gml::GmlFeature<'_>> = hits
+                .iter()
+                .flat_map(|layer_hits| {
+                    layer_hits.features.iter().map(move |(feature, geometry)| gml::GmlFeature {
+                        type_name: &layer_hits.layer,
+                        id: feature_id(&layer_hits.layer, feature),
+                        geometry: Some(geometry.clone()),
+                        properties: properties(feature),
+                    })
+                })
+                .collect();
+            gml::encode_feature_collection(&features, srid, is_lat_lon_order(srid), None, None)
+        }
+    }
+}
+
+fn properties(feature: &Feature) -> &serde_json::Map<String, Value> {
+    static EMPTY: std::sync::OnceLock<serde_json::Map<String, Value>> = std::sync::OnceLock::new();
+    feature
+        .properties
+        .as_object()
+        .unwrap_or_else(|| EMPTY.get_or_init(serde_json::Map::new))
+}
+
+fn encode_geojson(hits: &[LayerHits]) -> String {
+    let features: Vec<Value> = hits
+        .iter()
+        .flat_map(|layer_hits| {
+            layer_hits.features.iter().map(move |(feature, geometry)| {
+                json!({
+                    "type": "Feature",
+                    "id": feature_id(&layer_hits.layer, feature),
+                    "layer": layer_hits.layer,
+                    "geometry": geojson::from_geometry(geometry),
+                    "properties": feature.properties,
+                })
+            })
+        })
+        .collect();
+
+    json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
+
+fn encode_html(hits: &[LayerHits]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Feature Info</title></head>\n<body>\n",
+    );
+
+    for layer_hits in hits.iter().filter(|h| !h.features.is_empty()) {
+        let _ = writeln!(html, "<h3>{}</h3>", gml::escape(&layer_hits.layer));
+        html.push_str("<table border=\"1\">\n");
+
+        // Union of attribute names, in first-seen order
+        let mut columns: Vec<&String> = Vec::new();
+        for (feature, _) in &layer_hits.features {
+            for key in properties(feature).keys() {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+
+        html.push_str("<tr><th>id</th>");
+        for column in &columns {
+            let _ = write!(html, "<th>{}</th>", gml::escape(column));
+        }
+        html.push_str("</tr>\n");
+
+        for (feature, _) in &layer_hits.features {
+            let _ = write!(html, "<tr><td>{}</td>", gml::escape(&feature_id(&layer_hits.layer, feature)));
+            for column in &columns {
+                let value = properties(feature)
+                    .get(column.as_str())
+                    .map(gml::value_text)
+                    .unwrap_or_default();
+                let _ = write!(html, "<td>{}</td>", gml::escape(&value));
+            }
+            html.push_str("</tr>\n");
+        }
+
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn parse_styles(styles: Option<&str>, layer_count: usize) -> ServerResult<Vec<Option<String>>> {
+    let styles = match styles.map(str::trim) {
+        None | Some("") => return Ok(vec![None; layer_count]),
+        Some(s) => s,
+    };
+
+    let styles: Vec<Option<String>> = styles
+        .split(',')
+        .map(|s| Some(s.trim().to_string()).filter(|s| !s.is_empty()))
+        .collect();
+
+    if styles.len() != layer_count {
+        return Err(ServerError::OgcError(
+            "STYLES must have one entry per layer in LAYERS".to_string(),
+        ));
+    }
+    Ok(styles)
+}
+
+/// Parse a `0xRRGGBB` background color
+fn parse_bgcolor(value: &str) -> ServerResult<Rgba<u8>> {
+    let hex = value
+        .trim()
+        .strip_prefix("0x")
+        .or_else(|| value.trim().strip_prefix("0X"))
+        .or_else(|| value.trim().strip_prefix('#'))
+        .unwrap_or(value.trim());
+
+    if hex.len() != 6 {
+        return Err(ServerError::OgcError(format!("Invalid BGCOLOR: {}", value)));
+    }
+    let rgb = u32::from_str_radix(hex, 16)
+        .map_err(|_| ServerError::OgcError(format!("Invalid BGCOLOR: {}", value)))?;
+
+    Ok(Rgba([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255]))
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> ServerResult<&'a str> {
+    value.as_deref().filter(|v| !v.is_empty()).ok_or_else(|| missing(name))
+}
+
+fn missing(name: &str) -> ServerError {
+    ServerError::OgcError(format!("MissingParameterValue: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ogc::parse_kvp;
+    use meridian_core::geo_types::polygon;
+
+    fn params(query: &str) -> WmsParams {
+        parse_kvp(query).unwrap()
+    }
+
+    #[test]
+    fn test_map_request_wms_130_axis_order() {
+        let request = MapRequest::from_params(&params(
+            "VERSION=1.3.0&REQUEST=GetMap&LAYERS=roads,parcels&STYLES=&CRS=EPSG:4326\
+             &BBOX=40,-10,50,10&WIDTH=200&HEIGHT=100&FORMAT=image/jpeg&BGCOLOR=0xFF0000",
+        ))
+        .unwrap();
+
+        assert_eq!(request.layers, vec!["roads", "parcels"]);
+        assert_eq!(request.styles, vec![None, None]);
+        assert_eq!(request.bbox.min_x, -10.0);
+        assert_eq!(request.bbox.max_y, 50.0);
+        assert_eq!(request.format, TileFormat::Jpeg);
+        assert!(!request.transparent);
+        assert_eq!(request.bgcolor, Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_map_request_wms_111() {
+        let request = MapRequest::from_params(&params(
+            "VERSION=1.1.1&LAYERS=roads&SRS=EPSG:4326&BBOX=-10,40,10,50\
+             &WIDTH=256&HEIGHT=256&FORMAT=image/png&TRANSPARENT=TRUE",
+        ))
+        .unwrap();
+
+        assert_eq!(request.bbox.min_x, -10.0);
+        assert_eq!(request.bbox.min_y, 40.0);
+        assert!(request.transparent);
+    }
+
+    #[test]
+    fn test_map_request_validation() {
+        assert!(MapRequest::from_params(&params("VERSION=1.3.0&CRS=EPSG:3857&BBOX=0,0,1,1&WIDTH=1&HEIGHT=1")).is_err());
+        assert!(MapRequest::from_params(&params(
+            "VERSION=1.3.0&LAYERS=a&CRS=EPSG:3857&BBOX=0,0,1,1&WIDTH=10000&HEIGHT=1"
+        ))
+        .is_err());
+        assert!(MapRequest::from_params(&params(
+            "VERSION=1.3.0&LAYERS=a,b&STYLES=x&CRS=EPSG:3857&BBOX=0,0,1,1&WIDTH=1&HEIGHT=1"
+        ))
+        .is_err());
+        assert!(MapRequest::from_params(&params(
+            "VERSION=1.3.0&LAYERS=a&CRS=EPSG:3857&BBOX=0,0,1,1&WIDTH=1&HEIGHT=1&FORMAT=image/tiff"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_render_map_background() {
+        let request = MapRequest::from_params(&params(
+            "VERSION=1.3.0&LAYERS=a&CRS=EPSG:3857&BBOX=0,0,100,100&WIDTH=10&HEIGHT=10&BGCOLOR=0x00FF00",
+        ))
+        .unwrap();
+
+        let bytes = render_map(&request, &[]).unwrap();
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(image.get_pixel(5, 5), &Rgba([0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn test_feature_info_request_and_hit_test() {
+        let request = FeatureInfoRequest::from_params(&params(
+            "VERSION=1.3.0&REQUEST=GetFeatureInfo&LAYERS=parcels&QUERY_LAYERS=parcels&CRS=EPSG:3857\
+             &BBOX=0,0,100,100&WIDTH=100&HEIGHT=100&I=10&J=89&INFO_FORMAT=application/json",
+        ))
+        .unwrap();
+
+        let location = request.location();
+        assert_eq!((location.x(), location.y()), (10.5, 10.5));
+        assert_eq!(request.info_format, InfoFormat::GeoJson);
+
+        let square = Geometry::Polygon(polygon![
+            (x: 0.0, y: 0.0), (x: 20.0, y: 0.0), (x: 20.0, y: 20.0), (x: 0.0, y: 20.0)
+        ]);
+        assert!(hit_test(&square, &location, request.tolerance()));
+
+        let far = Geometry::Point(Point::new(50.0, 50.0));
+        assert!(!hit_test(&far, &location, request.tolerance()));
+    }
+
+    #[test]
+    fn test_feature_info_requires_queryable_layer() {
+        assert!(FeatureInfoRequest::from_params(&params(
+            "VERSION=1.3.0&LAYERS=a&QUERY_LAYERS=b&CRS=EPSG:3857&BBOX=0,0,1,1&WIDTH=1&HEIGHT=1&I=0&J=0"
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_info_format_from_mime_type() {
+        assert_eq!(InfoFormat::from_mime_type("text/html"), Some(InfoFormat::Html));
+        assert_eq!(
+            InfoFormat::from_mime_type("application/gml+xml; version=3.2"),
+            Some(InfoFormat::Gml)
+        );
+        assert_eq!(InfoFormat::from_mime_type("text/plain"), None);
+    }
+}
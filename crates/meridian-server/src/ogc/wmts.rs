@@ -0,0 +1,633 @@
+//! WMTS 1.0.0 GetTile and capabilities
+//!
+//! Defines the WebMercatorQuad and WorldCRS84Quad tile matrix sets, renders
+//! tiles through the WMS pipeline and caches the encoded images in a
+//! `meridian-vector-tiles` tile storage backend.
+
+use dashmap::DashMap;
+use image::Rgba;
+use meridian_db::{BBox, Layer};
+use meridian_render::raster::TileFormat;
+use meridian_vector_tiles::storage::{DirectoryStorage, MBTilesStorage, TileStorage};
+use meridian_vector_tiles::TileCoordinate;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::{gml::escape, wms::MapRequest, OgcBBox};
+use crate::{config::TileCacheConfig, error::ServerResult, routes::ogc::WmtsParams, ServerError};
+
+/// Highest tile matrix (zoom level) of every tile matrix set
+pub const MAX_ZOOM: u8 = 20;
+
+/// Tile edge in pixels
+pub const TILE_SIZE: u32 = 256;
+
+/// Tile formats offered for every layer
+pub const FORMATS: [TileFormat; 2] = [TileFormat::Png, TileFormat::Jpeg];
+
+/// Standardized rendering pixel size in metres (0.28 mm)
+const PIXEL_SIZE: f64 = 0.00028;
+
+/// Half the circumference of the Web Mercator world in metres
+const WEB_MERCATOR_EXTENT: f64 = 20037508.342789244;
+
+/// Metres per degree at the equator of the WGS 84 ellipsoid
+const METRES_PER_DEGREE: f64 = 6378137.0 * std::f64::consts::PI / 180.0;
+
+/// Tile matrix sets from the OGC Two Dimensional Tile Matrix Set standard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileMatrixSet {
+    /// Spherical Mercator (EPSG:3857), one tile at zoom 0
+    WebMercatorQuad,
+    /// Plate carrée in CRS84, two tiles at zoom 0
+    WorldCrs84Quad,
+}
+
+impl TileMatrixSet {
+    /// All tile matrix sets, as advertised in capabilities
+    pub const ALL: [TileMatrixSet; 2] = [TileMatrixSet::WebMercatorQuad, TileMatrixSet::WorldCrs84Quad];
+
+    /// Parse a tile matrix set identifier
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|set| set.identifier().eq_ignore_ascii_case(identifier.trim()))
+    }
+
+    /// Identifier used in requests and capabilities
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            TileMatrixSet::WebMercatorQuad => "WebMercatorQuad",
+            TileMatrixSet::WorldCrs84Quad => "WorldCRS84Quad",
+        }
+    }
+
+    /// EPSG code of the tiles
+    pub fn srid(&self) -> i32 {
+        match self {
+            TileMatrixSet::WebMercatorQuad => 3857,
+            TileMatrixSet::WorldCrs84Quad => 4326,
+        }
+    }
+
+    /// CRS URN announced in capabilities
+    pub fn supported_crs(&self) -> &'static str {
+        match self {
+            TileMatrixSet::WebMercatorQuad => "urn:ogc:def:crs:EPSG::3857",
+            TileMatrixSet::WorldCrs84Quad => "urn:ogc:def:crs:OGC:1.3:CRS84",
+        }
+    }
+
+    /// Well-known scale set the matrix set is compatible with
+    pub fn well_known_scale_set(&self) -> &'static str {
+        match self {
+            TileMatrixSet::WebMercatorQuad => "urn:ogc:def:wkss:OGC:1.0:GoogleMapsCompatible",
+            TileMatrixSet::WorldCrs84Quad => "urn:ogc:def:wkss:OGC:1.0:GoogleCRS84Quad",
+        }
+    }
+
+    /// Top left corner of every tile matrix, in x/y order
+    pub fn origin(&self) -> (f64, f64) {
+        match self {
+            TileMatrixSet::WebMercatorQuad => (-WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT),
+            TileMatrixSet::WorldCrs84Quad => (-180.0, 90.0),
+        }
+    }
+
+    /// CRS units per pixel at a zoom level
+    pub fn resolution(&self, zoom: u8) -> f64 {
+        let world_height = match self {
+            TileMatrixSet::WebMercatorQuad => 2.0 * WEB_MERCATOR_EXTENT,
+            TileMatrixSet::WorldCrs84Quad => 180.0,
+        };
+        world_height / f64::from(TILE_SIZE) / f64::from(1u32 << zoom)
+    }
+
+    /// Scale denominator at a zoom level
+    pub fn scale_denominator(&self, zoom: u8) -> f64 {
+        let metres_per_unit = match self {
+            TileMatrixSet::WebMercatorQuad => 1.0,
+            TileMatrixSet::WorldCrs84Quad => METRES_PER_DEGREE,
+        };
+        self.resolution(zoom) * metres_per_unit / PIXEL_SIZE
+    }
+
+    /// Number of tile columns and rows at a zoom level
+    pub fn matrix_size(&self, zoom: u8) -> (u32, u32) {
+        let n = 1u32 << zoom;
+        match self {
+            TileMatrixSet::WebMercatorQuad => (n, n),
+            TileMatrixSet::WorldCrs84Quad => (2 * n, n),
+        }
+    }
+
+    /// Extent of a tile in the CRS of the matrix set
+    pub fn tile_bbox(&self, zoom: u8, row: u32, col: u32) -> OgcBBox {
+        let (origin_x, origin_y) = self.origin();
+        let span = self.resolution(zoom) * f64::from(TILE_SIZE);
+        OgcBBox {
+            min_x: origin_x + f64::from(col) * span,
+            min_y: origin_y - f64::from(row + 1) * span,
+            max_x: origin_x + f64::from(col + 1) * span,
+            max_y: origin_y - f64::from(row) * span,
+            srid: self.srid(),
+        }
+    }
+
+    /// Range of tiles covering an extent given in the CRS of the matrix set
+    pub fn limits(&self, extent: &BBox, zoom: u8) -> TileMatrixLimits {
+        let (origin_x, origin_y) = self.origin();
+        let span = self.resolution(zoom) * f64::from(TILE_SIZE);
+        let (width, height) = self.matrix_size(zoom);
+
+        let index = |value: f64, count: u32| -> u32 {
+            if value.is_nan() || value <= 0.0 {
+                0
+            } else {
+                (value as u32).min(count - 1)
+            }
+        };
+        let min_col = index(((extent.min_x - origin_x) / span).floor(), width);
+        let max_col = index(((extent.max_x - origin_x) / span).ceil() - 1.0, width).max(min_col);
+        let min_row = index(((origin_y - extent.max_y) / span).floor(), height);
+        let max_row = index(((origin_y - extent.min_y) / span).ceil() - 1.0, height).max(min_row);
+
+        TileMatrixLimits {
+            zoom,
+            min_row,
+            max_row,
+            min_col,
+            max_col,
+        }
+    }
+}
+
+/// Tiles of one tile matrix that cover a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileMatrixLimits {
+    pub zoom: u8,
+    pub min_row: u32,
+    pub max_row: u32,
+    pub min_col: u32,
+    pub max_col: u32,
+}
+
+/// A validated GetTile request
+#[derive(Debug, Clone)]
+pub struct TileRequest {
+    /// Layer name
+    pub layer: String,
+    /// Style name, `None` for the default style
+    pub style: Option<String>,
+    /// Tile matrix set
+    pub matrix_set: TileMatrixSet,
+    /// Tile matrix (zoom level)
+    pub zoom: u8,
+    /// Tile row, counted from the top
+    pub row: u32,
+    /// Tile column, counted from the left
+    pub col: u32,
+    /// Image format
+    pub format: TileFormat,
+}
+
+impl TileRequest {
+    /// Build a tile request from WMTS KVP parameters
+    pub fn from_params(params: &WmtsParams) -> ServerResult<Self> {
+        let format = match params.format.as_deref() {
+            None | Some("") => TileFormat::Png,
+            Some(mime) => parse_format(mime)?,
+        };
+
+        Self::new(
+            required(&params.layer, "LAYER")?,
+            params.style.as_deref(),
+            required(&params.tilematrixset, "TILEMATRIXSET")?,
+            required(&params.tilematrix, "TILEMATRIX")?,
+            params.tilerow.ok_or_else(|| missing("TILEROW"))?,
+            params.tilecol.ok_or_else(|| missing("TILECOL"))?,
+            format,
+        )
+    }
+
+    /// Build a tile request from the segments of a RESTful tile URL
+    ///
+    /// The last segment is the tile column with an optional file extension
+    /// selecting the format (`12.png`).
+    pub fn from_path(
+        layer: &str,
+        style: &str,
+        tile_matrix_set: &str,
+        tile_matrix: &str,
+        tile_row: &str,
+        tile_col: &str,
+    ) -> ServerResult<Self> {
+        let (col, format) = match tile_col.split_once('.') {
+            Some((col, extension)) => (col, parse_format(extension)?),
+            None => (tile_col, TileFormat::Png),
+        };
+
+        Self::new(
+            layer,
+            Some(style),
+            tile_matrix_set,
+            tile_matrix,
+            parse_index(tile_row, "TileRow")?,
+            parse_index(col, "TileCol")?,
+            format,
+        )
+    }
+
+    fn new(
+        layer: &str,
+        style: Option<&str>,
+        tile_matrix_set: &str,
+        tile_matrix: &str,
+        row: u32,
+        col: u32,
+        format: TileFormat,
+    ) -> ServerResult<Self> {
+        let matrix_set = TileMatrixSet::from_identifier(tile_matrix_set).ok_or_else(|| {
+            ServerError::OgcError(format!("InvalidParameterValue: TileMatrixSet {}", tile_matrix_set))
+        })?;
+
+        let zoom = tile_matrix
+            .trim()
+            .parse::<u8>()
+            .ok()
+            .filter(|z| *z <= MAX_ZOOM)
+            .ok_or_else(|| ServerError::OgcError(format!("InvalidParameterValue: TileMatrix {}", tile_matrix)))?;
+
+        let (width, height) = matrix_set.matrix_size(zoom);
+        if row >= height || col >= width {
+            return Err(ServerError::OgcError(format!(
+                "TileOutOfRange: row {} / column {} outside tile matrix {} of {}",
+                row,
+                col,
+                zoom,
+                matrix_set.identifier()
+            )));
+        }
+
+        let style = style
+            .map(str::trim)
+            .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("default"))
+            .map(str::to_string);
+
+        Ok(Self {
+            layer: super::fes::strip_prefix(layer.trim()).to_string(),
+            style,
+            matrix_set,
+            zoom,
+            row,
+            col,
+            format,
+        })
+    }
+
+    /// Extent of the tile
+    pub fn bbox(&self) -> OgcBBox {
+        self.matrix_set.tile_bbox(self.zoom, self.row, self.col)
+    }
+
+    /// Tile coordinate in the tile storage
+    pub fn coordinate(&self) -> TileCoordinate {
+        TileCoordinate::new(self.zoom, self.col, self.row)
+    }
+
+    /// Equivalent WMS map request for rendering the tile
+    pub fn map_request(&self) -> MapRequest {
+        MapRequest {
+            version: "1.3.0".to_string(),
+            layers: vec![self.layer.clone()],
+            styles: vec![self.style.clone()],
+            bbox: self.bbox(),
+            width: TILE_SIZE,
+            height: TILE_SIZE,
+            format: self.format,
+            transparent: self.format.supports_transparency(),
+            bgcolor: Rgba([255, 255, 255, 255]),
+        }
+    }
+}
+
+fn parse_format(value: &str) -> ServerResult<TileFormat> {
+    TileFormat::from_mime_type(value)
+        .filter(|format| FORMATS.contains(format))
+        .ok_or_else(|| ServerError::OgcError(format!("InvalidParameterValue: Format {}", value)))
+}
+
+fn parse_index(value: &str, name: &str) -> ServerResult<u32> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ServerError::OgcError(format!("InvalidParameterValue: {} {}", name, value)))
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> ServerResult<&'a str> {
+    value
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| missing(name))
+}
+
+fn missing(name: &str) -> ServerError {
+    ServerError::OgcError(format!("MissingParameterValue: {}", name))
+}
+
+/// Tile storage backend of the cache
+#[derive(Debug, Clone)]
+enum CacheBackend {
+    Disabled,
+    Directory(PathBuf),
+    MBTiles(PathBuf),
+}
+
+/// Cache of rendered WMTS tiles
+///
+/// Each combination of layer, style, tile matrix set and format is kept in
+/// its own tile storage, opened on first use. Storage failures are logged
+/// and treated as cache misses so that tiles are still served.
+pub struct TileCache {
+    backend: CacheBackend,
+    storages: DashMap<String, Arc<dyn TileStorage>>,
+}
+
+impl TileCache {
+    /// Create a tile cache from configuration
+    pub fn new(config: &TileCacheConfig) -> ServerResult<Self> {
+        let backend = match config.backend.to_ascii_lowercase().as_str() {
+            "none" | "disabled" => CacheBackend::Disabled,
+            "directory" => CacheBackend::Directory(config.path.clone()),
+            "mbtiles" => CacheBackend::MBTiles(config.path.clone()),
+            other => {
+                return Err(ServerError::Configuration(format!(
+                    "Unknown tile cache backend: {}",
+                    other
+                )))
+            }
+        };
+
+        Ok(Self {
+            backend,
+            storages: DashMap::new(),
+        })
+    }
+
+    /// Look up a cached tile
+    pub async fn get(&self, request: &TileRequest) -> Option<Vec<u8>> {
+        let storage = self.storage(request).await?;
+        match storage.get_tile(request.coordinate()).await {
+            Ok(tile) => tile,
+            Err(e) => {
+                tracing::warn!("Tile cache read failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Store a rendered tile
+    pub async fn put(&self, request: &TileRequest, data: Vec<u8>) {
+        if let Some(storage) = self.storage(request).await {
+            if let Err(e) = storage.put_tile(request.coordinate(), data).await {
+                tracing::warn!("Tile cache write failed: {}", e);
+            }
+        }
+    }
+
+    async fn storage(&self, request: &TileRequest) -> Option<Arc<dyn TileStorage>> {
+        let key = cache_key(request);
+        if let Some(storage) = self.storages.get(&key) {
+            return Some(storage.clone());
+        }
+
+        let opened: Result<Arc<dyn TileStorage>, String> = match &self.backend {
+            CacheBackend::Disabled => return None,
+            CacheBackend::Directory(root) => DirectoryStorage::new(root.join(&key))
+                .await
+                .map(|s| Arc::new(s.with_extension(request.format.extension())) as Arc<dyn TileStorage>)
+                .map_err(|e| e.to_string()),
+            CacheBackend::MBTiles(root) => match tokio::fs::create_dir_all(root).await {
+                Ok(()) => MBTilesStorage::new(root.join(format!("{}.mbtiles", key.replace('/', "_"))))
+                    .await
+                    .map(|s| Arc::new(s) as Arc<dyn TileStorage>)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        };
+
+        match opened {
+            Ok(storage) => Some(self.storages.entry(key).or_insert(storage).clone()),
+            Err(e) => {
+                tracing::warn!("Failed to open tile cache {}: {}", key, e);
+                None
+            }
+        }
+    }
+}
+
+/// Relative storage location of a tile set
+fn cache_key(request: &TileRequest) -> String {
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    };
+    format!(
+        "{}/{}/{}/{}",
+        sanitize(&request.layer),
+        sanitize(request.style.as_deref().unwrap_or("default")),
+        request.matrix_set.identifier(),
+        request.format.extension()
+    )
+}
+
+/// Layer extents used to build the capabilities `Contents`
+pub struct LayerExtents {
+    /// The layer
+    pub layer: Layer,
+    /// Extent in CRS84
+    pub wgs84: Option<BBox>,
+    /// Extent in Web Mercator
+    pub web_mercator: Option<BBox>,
+}
+
+impl LayerExtents {
+    fn extent(&self, matrix_set: TileMatrixSet) -> Option<BBox> {
+        match matrix_set {
+            TileMatrixSet::WebMercatorQuad => self.web_mercator.clone().map(|mut bbox| {
+                // Clamp extents reaching the poles to the square Mercator world
+                bbox.min_y = bbox.min_y.max(-WEB_MERCATOR_EXTENT);
+                bbox.max_y = bbox.max_y.min(WEB_MERCATOR_EXTENT);
+                bbox
+            }),
+            TileMatrixSet::WorldCrs84Quad => self.wgs84.clone(),
+        }
+    }
+}
+
+/// Encode the `Contents` section of the capabilities document
+///
+/// `url` is the base of the RESTful tile URLs.
+pub fn encode_contents(url: &str, layers: &[LayerExtents]) -> String {
+    let mut xml = String::from("  <Contents>\n");
+
+    for entry in layers {
+        let name = escape(&entry.layer.name);
+        let _ = writeln!(xml, "    <Layer>");
+        let _ = writeln!(xml, "      <ows:Title>{}</ows:Title>", name);
+        if let Some(description) = &entry.layer.description {
+            let _ = writeln!(xml, "      <ows:Abstract>{}</ows:Abstract>", escape(description));
+        }
+        if let Some(bbox) = &entry.wgs84 {
+            let _ = writeln!(
+                xml,
+                "      <ows:WGS84BoundingBox>\n        <ows:LowerCorner>{} {}</ows:LowerCorner>\n        <ows:UpperCorner>{} {}</ows:UpperCorner>\n      </ows:WGS84BoundingBox>",
+                bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y
+            );
+        }
+        let _ = writeln!(xml, "      <ows:Identifier>{}</ows:Identifier>", name);
+        let _ = writeln!(
+            xml,
+            "      <Style isDefault=\"true\">\n        <ows:Identifier>default</ows:Identifier>\n      </Style>"
+        );
+        for format in FORMATS {
+            let _ = writeln!(xml, "      <Format>{}</Format>", format.mime_type());
+        }
+
+        for matrix_set in TileMatrixSet::ALL {
+            let _ = writeln!(xml, "      <TileMatrixSetLink>");
+            let _ = writeln!(xml, "        <TileMatrixSet>{}</TileMatrixSet>", matrix_set.identifier());
+            if let Some(extent) = entry.extent(matrix_set) {
+                let _ = writeln!(xml, "        <TileMatrixSetLimits>");
+                for zoom in 0..=MAX_ZOOM {
+                    let limits = matrix_set.limits(&extent, zoom);
+                    let _ = writeln!(
+                        xml,
+                        "          <TileMatrixLimits>\n            <TileMatrix>{}</TileMatrix>\n            <MinTileRow>{}</MinTileRow>\n            <MaxTileRow>{}</MaxTileRow>\n            <MinTileCol>{}</MinTileCol>\n            <MaxTileCol>{}</MaxTileCol>\n          </TileMatrixLimits>",
+                        limits.zoom, limits.min_row, limits.max_row, limits.min_col, limits.max_col
+                    );
+                }
+                let _ = writeln!(xml, "        </TileMatrixSetLimits>");
+            }
+            let _ = writeln!(xml, "      </TileMatrixSetLink>");
+        }
+
+        for format in FORMATS {
+            let _ = writeln!(
+                xml,
+                "      <ResourceURL format=\"{}\" resourceType=\"tile\" template=\"{}/{}/{{Style}}/{{TileMatrixSet}}/{{TileMatrix}}/{{TileRow}}/{{TileCol}}.{}\"/>",
+                format.mime_type(),
+                escape(url),
+                name,
+                format.extension()
+            );
+        }
+        let _ = writeln!(xml, "    </Layer>");
+    }
+
+    for matrix_set in TileMatrixSet::ALL {
+        xml.push_str(&encode_tile_matrix_set(matrix_set));
+    }
+
+    xml.push_str("  </Contents>\n");
+    xml
+}
+
+/// Encode a `TileMatrixSet` definition
+fn encode_tile_matrix_set(matrix_set: TileMatrixSet) -> String {
+    let mut xml = String::from("    <TileMatrixSet>\n");
+    let _ = writeln!(xml, "      <ows:Identifier>{}</ows:Identifier>", matrix_set.identifier());
+    let _ = writeln!(xml, "      <ows:SupportedCRS>{}</ows:SupportedCRS>", matrix_set.supported_crs());
+    let _ = writeln!(xml, "      <WellKnownScaleSet>{}</WellKnownScaleSet>", matrix_set.well_known_scale_set());
+
+    // CRS84 and EPSG:3857 are both easting first
+    let (origin_x, origin_y) = matrix_set.origin();
+    for zoom in 0..=MAX_ZOOM {
+        let (width, height) = matrix_set.matrix_size(zoom);
+        let _ = writeln!(
+            xml,
+            "      <TileMatrix>\n        <ows:Identifier>{}</ows:Identifier>\n        <ScaleDenominator>{}</ScaleDenominator>\n        <TopLeftCorner>{} {}</TopLeftCorner>\n        <TileWidth>{}</TileWidth>\n        <TileHeight>{}</TileHeight>\n        <MatrixWidth>{}</MatrixWidth>\n        <MatrixHeight>{}</MatrixHeight>\n      </TileMatrix>",
+            zoom,
+            matrix_set.scale_denominator(zoom),
+            origin_x,
+            origin_y,
+            TILE_SIZE,
+            TILE_SIZE,
+            width,
+            height
+        );
+    }
+
+    xml.push_str("    </TileMatrixSet>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_matrix_sets() {
+        let mercator = TileMatrixSet::WebMercatorQuad;
+        assert!((mercator.scale_denominator(0) - 559_082_264.028_717_8).abs() < 1e-3);
+        assert_eq!(mercator.matrix_size(3), (8, 8));
+        let bbox = mercator.tile_bbox(1, 0, 1);
+        assert_eq!((bbox.min_x, bbox.max_y), (0.0, WEB_MERCATOR_EXTENT));
+        assert!((bbox.min_y).abs() < 1e-6);
+
+        let crs84 = TileMatrixSet::WorldCrs84Quad;
+        assert!((crs84.scale_denominator(0) - 279_541_132.014_358_9).abs() < 1e-3);
+        assert_eq!(crs84.matrix_size(0), (2, 1));
+        let bbox = crs84.tile_bbox(0, 0, 1);
+        assert_eq!((bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y), (0.0, -90.0, 180.0, 90.0));
+
+        assert_eq!(TileMatrixSet::from_identifier("worldcrs84quad"), Some(crs84));
+        assert_eq!(TileMatrixSet::from_identifier("GoogleMapsCompatible"), None);
+    }
+
+    #[test]
+    fn test_limits() {
+        let extent = BBox::new(10.0, 40.0, 20.0, 50.0, 4326);
+        let limits = TileMatrixSet::WorldCrs84Quad.limits(&extent, 2);
+        // 45 degree tiles: columns from -180, rows from 90
+        assert_eq!((limits.min_col, limits.max_col), (4, 4));
+        assert_eq!((limits.min_row, limits.max_row), (0, 1));
+
+        let world = BBox::new(-180.0, -90.0, 180.0, 90.0, 4326);
+        let limits = TileMatrixSet::WorldCrs84Quad.limits(&world, 1);
+        assert_eq!((limits.min_col, limits.max_col, limits.min_row, limits.max_row), (0, 3, 0, 1));
+    }
+
+    #[test]
+    fn test_tile_request() {
+        let request = TileRequest::from_path("meridian:roads", "default", "WebMercatorQuad", "3", "2", "5.jpg").unwrap();
+        assert_eq!(request.layer, "roads");
+        assert_eq!(request.style, None);
+        assert_eq!(request.format, TileFormat::Jpeg);
+        assert_eq!(request.coordinate(), TileCoordinate::new(3, 5, 2));
+        assert_eq!(cache_key(&request), "roads/default/WebMercatorQuad/jpg");
+        assert!(!request.map_request().transparent);
+
+        assert!(TileRequest::from_path("roads", "default", "WebMercatorQuad", "3", "8", "0").is_err());
+        assert!(TileRequest::from_path("roads", "default", "WorldCRS84Quad", "0", "0", "1.png").is_ok());
+        assert!(TileRequest::from_path("roads", "default", "WebMercatorQuad", "3", "0", "0.gif").is_err());
+        assert!(TileRequest::from_path("roads", "default", "EPSG:4326", "3", "0", "0").is_err());
+    }
+
+    #[test]
+    fn test_contents() {
+        let layers = vec![LayerExtents {
+            layer: Layer::new("roads".to_string(), "vector".to_string(), 4326),
+            wgs84: Some(BBox::new(10.0, 40.0, 20.0, 50.0, 4326)),
+            web_mercator: None,
+        }];
+        let xml = encode_contents("http://localhost/ogc/wmts", &layers);
+        assert!(xml.contains("<ows:Identifier>roads</ows:Identifier>"));
+        assert!(xml.contains("<ows:LowerCorner>10 40</ows:LowerCorner>"));
+        assert!(xml.contains("template=\"http://localhost/ogc/wmts/roads/{Style}/{TileMatrixSet}/{TileMatrix}/{TileRow}/{TileCol}.png\""));
+        assert_eq!(xml.matches("<TileMatrixSetLimits>").count(), 1);
+        assert_eq!(xml.matches("<MatrixWidth>").count(), 2 * (MAX_ZOOM as usize + 1));
+    }
+}
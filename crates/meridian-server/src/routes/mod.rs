@@ -5,6 +5,7 @@
 pub mod features;
 pub mod layers;
 pub mod ogc;
+pub mod ogc_api;
 pub mod query;
 
 use axum::{
@@ -31,6 +32,7 @@ pub fn ogc_routes() -> Router<AppState> {
         .nest("/wms", ogc::wms_routes())
         .nest("/wfs", ogc::wfs_routes())
         .nest("/wmts", ogc::wmts_routes())
+        .nest("/features", ogc_api::routes())
         .route("/", get(ogc_info))
 }
 
@@ -120,6 +122,10 @@ async fn ogc_info() -> Json<ApiInfo> {
                 path: "/ogc/wmts".to_string(),
                 description: "Web Map Tile Service (WMTS)".to_string(),
             },
+            EndpointInfo {
+                path: "/ogc/features".to_string(),
+                description: "OGC API - Features".to_string(),
+            },
         ],
     })
 }
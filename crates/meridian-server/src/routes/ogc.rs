@@ -4,15 +4,29 @@
 //! and WMTS (Web Map Tile Service)
 
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::{header, StatusCode},
     response::{IntoResponse, Response},
     routing::get,
     Router,
 };
-use serde::{Deserialize, Serialize};
-
-use crate::{error::ServerResult, state::AppState, ServerError};
+use serde::Deserialize;
+use std::fmt::Write;
+
+use crate::{
+    error::ServerResult,
+    ogc::{
+        self,
+        gml::escape,
+        wfs::{self, GetFeatureRequest},
+        wfst::{self, LockRequest, XmlRequest},
+        wms::{self, FeatureInfoRequest, InfoFormat, MapRequest},
+        wmts::{self, LayerExtents, TileRequest},
+        LayerCatalog, OgcQuery,
+    },
+    state::AppState,
+    ServerError,
+};
 
 /// Build WMS routes
 pub fn wms_routes() -> Router<AppState> {
@@ -23,14 +37,14 @@ pub fn wms_routes() -> Router<AppState> {
 /// Build WFS routes
 pub fn wfs_routes() -> Router<AppState> {
     Router::new()
-        .route("/", get(wfs_handler))
+        .route("/", get(wfs_handler).post(wfs_post_handler))
 }
 
 /// Build WMTS routes
 pub fn wmts_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(wmts_handler))
-        .route("/{layer}/{style}/{tilematrixset}/{tilematrix}/{tilerow}/{tilecol}", get(wmts_tile_handler))
+        .route("/:layer/:style/:tilematrixset/:tilematrix/:tilerow/:tilecol", get(wmts_tile_handler))
 }
 
 /// WMS query parameters
@@ -68,10 +82,31 @@ pub struct WmsParams {
     pub format: Option<String>,
 
     /// Transparent background
+    #[serde(default, deserialize_with = "ogc::deserialize_ogc_bool")]
     pub transparent: Option<bool>,
 
     /// Background color
     pub bgcolor: Option<String>,
+
+    /// Layers queried by GetFeatureInfo
+    pub query_layers: Option<String>,
+
+    /// GetFeatureInfo output format
+    pub info_format: Option<String>,
+
+    /// Maximum features per layer returned by GetFeatureInfo
+    pub feature_count: Option<u32>,
+
+    /// Queried pixel column and row
+    pub i: Option<u32>,
+    pub j: Option<u32>,
+
+    /// Queried pixel column and row (WMS 1.1.1)
+    pub x: Option<u32>,
+    pub y: Option<u32>,
+
+    /// Exception format
+    pub exceptions: Option<String>,
 }
 
 /// WFS query parameters
@@ -111,6 +146,25 @@ pub struct WfsParams {
 
     /// Feature ID
     pub featureid: Option<String>,
+    pub resourceid: Option<String>, // For WFS 2.0
+
+    /// Filter Encoding 2.0 filter document
+    pub filter: Option<String>,
+
+    /// Filter language of FILTER
+    pub filter_language: Option<String>,
+
+    /// Sort order (`name ASC,height DESC`)
+    pub sortby: Option<String>,
+
+    /// Output coordinate reference system
+    pub srsname: Option<String>,
+
+    /// Lock expiry in seconds (LockFeature, GetFeatureWithLock)
+    pub expiry: Option<u64>,
+
+    /// Lock action, ALL or SOME (LockFeature)
+    pub lockaction: Option<String>,
 }
 
 /// WMTS query parameters
@@ -149,8 +203,8 @@ pub struct WmtsParams {
 
 /// WMS request handler
 pub async fn wms_handler(
-    State(_state): State<AppState>,
-    Query(params): Query<WmsParams>,
+    State(state): State<AppState>,
+    OgcQuery(params): OgcQuery<WmsParams>,
 ) -> ServerResult<Response> {
     tracing::info!("WMS request: {:?}", params.request);
 
@@ -158,10 +212,10 @@ pub async fn wms_handler(
         .as_deref()
         .unwrap_or("GetCapabilities");
 
-    match request_type {
-        "GetCapabilities" => get_wms_capabilities(params).await,
-        "GetMap" => get_wms_map(params).await,
-        "GetFeatureInfo" => get_wms_feature_info(params).await,
+    match request_type.to_ascii_lowercase().as_str() {
+        "getcapabilities" => get_wms_capabilities(&state, params).await,
+        "getmap" | "map" => get_wms_map(&state, params).await,
+        "getfeatureinfo" => get_wms_feature_info(&state, params).await,
         _ => Err(ServerError::OgcError(format!(
             "Unsupported WMS request: {}",
             request_type
@@ -171,8 +225,8 @@ pub async fn wms_handler(
 
 /// WFS request handler
 pub async fn wfs_handler(
-    State(_state): State<AppState>,
-    Query(params): Query<WfsParams>,
+    State(state): State<AppState>,
+    OgcQuery(params): OgcQuery<WfsParams>,
 ) -> ServerResult<Response> {
     tracing::info!("WFS request: {:?}", params.request);
 
@@ -180,12 +234,14 @@ pub async fn wfs_handler(
         .as_deref()
         .unwrap_or("GetCapabilities");
 
-    match request_type {
-        "GetCapabilities" => get_wfs_capabilities(params).await,
-        "GetFeature" => get_wfs_feature(params).await,
-        "DescribeFeatureType" => describe_wfs_feature_type(params).await,
-        "Transaction" => Err(ServerError::OgcError(
-            "WFS transactions not yet implemented".to_string()
+    match request_type.to_ascii_lowercase().as_str() {
+        "getcapabilities" => get_wfs_capabilities(&state, params).await,
+        "getfeature" => get_wfs_feature(&state, params).await,
+        "describefeaturetype" => describe_wfs_feature_type(&state, params).await,
+        "getfeaturewithlock" => get_wfs_feature_with_lock(&state, params).await,
+        "lockfeature" => lock_wfs_features(&state, LockRequest::from_params(&params)?).await,
+        "transaction" => Err(ServerError::OgcError(
+            "Transaction requests must be sent as XML with HTTP POST".to_string()
         )),
         _ => Err(ServerError::OgcError(format!(
             "Unsupported WFS request: {}",
@@ -194,10 +250,21 @@ pub async fn wfs_handler(
     }
 }
 
+/// WFS handler for XML requests sent with HTTP POST (Transaction, LockFeature)
+pub async fn wfs_post_handler(
+    State(state): State<AppState>,
+    body: String,
+) -> ServerResult<Response> {
+    match XmlRequest::parse(&body)? {
+        XmlRequest::Transaction(request) => wfs_transaction(&state, request).await,
+        XmlRequest::LockFeature(request) => lock_wfs_features(&state, request).await,
+    }
+}
+
 /// WMTS request handler
 pub async fn wmts_handler(
-    State(_state): State<AppState>,
-    Query(params): Query<WmtsParams>,
+    State(state): State<AppState>,
+    OgcQuery(params): OgcQuery<WmtsParams>,
 ) -> ServerResult<Response> {
     tracing::info!("WMTS request: {:?}", params.request);
 
@@ -205,9 +272,9 @@ pub async fn wmts_handler(
         .as_deref()
         .unwrap_or("GetCapabilities");
 
-    match request_type {
-        "GetCapabilities" => get_wmts_capabilities(params).await,
-        "GetTile" => get_wmts_tile(params).await,
+    match request_type.to_ascii_lowercase().as_str() {
+        "getcapabilities" => get_wmts_capabilities(&state, params).await,
+        "gettile" => get_wmts_tile(&state, TileRequest::from_params(&params)?).await,
         _ => Err(ServerError::OgcError(format!(
             "Unsupported WMTS request: {}",
             request_type
@@ -217,9 +284,9 @@ pub async fn wmts_handler(
 
 /// WMTS tile handler (RESTful endpoint)
 pub async fn wmts_tile_handler(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     axum::extract::Path((layer, style, tilematrixset, tilematrix, tilerow, tilecol)):
-        axum::extract::Path<(String, String, String, String, u32, u32)>,
+        axum::extract::Path<(String, String, String, String, String, String)>,
 ) -> ServerResult<Response> {
     tracing::info!(
         "WMTS tile request: layer={}, z={}, row={}, col={}",
@@ -229,26 +296,39 @@ pub async fn wmts_tile_handler(
         tilecol
     );
 
-    // TODO: Implement actual tile generation using meridian-render
-    // For now, return a placeholder response
-    Err(ServerError::OgcError("Tile generation not yet implemented".to_string()))
+    let request = TileRequest::from_path(&layer, &style, &tilematrixset, &tilematrix, &tilerow, &tilecol)?;
+    get_wmts_tile(&state, request).await
 }
 
 /// Get WMS capabilities document
-async fn get_wms_capabilities(params: WmsParams) -> ServerResult<Response> {
+async fn get_wms_capabilities(state: &AppState, params: WmsParams) -> ServerResult<Response> {
     let version = params.version.as_deref().unwrap_or("1.3.0");
+    let url = ogc::service_url(state.config(), "wms");
 
     tracing::info!("Generating WMS {} GetCapabilities", version);
 
-    // TODO: Generate proper WMS capabilities XML
+    let catalog = LayerCatalog::new(state.db.pool());
+    let mut layers = String::new();
+    for layer in catalog.published_layers().await? {
+        let extent = catalog.extent(&layer, 4326).await?;
+        layers.push_str(&wms_layer_element(&layer, extent.as_ref()));
+    }
+
+    let info_formats: String = InfoFormat::ALL
+        .iter()
+        .map(|f| format!("        <Format>{}</Format>\n", escape(f.mime_type())))
+        .collect();
+
     let capabilities = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<WMS_Capabilities version="{version}" xmlns="http://www.opengis.net/wms">
+<WMS_Capabilities version="{version}" xmlns="http://www.opengis.net/wms" xmlns:xlink="http://www.w3.org/1999/xlink">
   <Service>
     <Name>WMS</Name>
     <Title>Meridian GIS Web Map Service</Title>
     <Abstract>OGC WMS implementation for Meridian GIS Platform</Abstract>
-    <OnlineResource xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="http://localhost:8080/ogc/wms"/>
+    <OnlineResource xlink:href="{url}"/>
+    <MaxWidth>{max_size}</MaxWidth>
+    <MaxHeight>{max_size}</MaxHeight>
   </Service>
   <Capability>
     <Request>
@@ -256,26 +336,44 @@ async fn get_wms_capabilities(params: WmsParams) -> ServerResult<Response> {
         <Format>text/xml</Format>
         <DCPType>
           <HTTP>
-            <Get><OnlineResource xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="http://localhost:8080/ogc/wms"/></Get>
+            <Get><OnlineResource xlink:href="{url}"/></Get>
           </HTTP>
         </DCPType>
       </GetCapabilities>
       <GetMap>
         <Format>image/png</Format>
         <Format>image/jpeg</Format>
+        <Format>image/webp</Format>
         <DCPType>
           <HTTP>
-            <Get><OnlineResource xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="http://localhost:8080/ogc/wms"/></Get>
+            <Get><OnlineResource xlink:href="{url}"/></Get>
           </HTTP>
         </DCPType>
       </GetMap>
+      <GetFeatureInfo>
+{info_formats}        <DCPType>
+          <HTTP>
+            <Get><OnlineResource xlink:href="{url}"/></Get>
+          </HTTP>
+        </DCPType>
+      </GetFeatureInfo>
     </Request>
+    <Exception>
+      <Format>XML</Format>
+    </Exception>
     <Layer>
       <Title>Meridian Layers</Title>
-    </Layer>
+      <CRS>EPSG:4326</CRS>
+      <CRS>CRS:84</CRS>
+      <CRS>EPSG:3857</CRS>
+{layers}    </Layer>
   </Capability>
 </WMS_Capabilities>"#,
-        version = version
+        version = version,
+        url = escape(&url),
+        max_size = wms::MAX_IMAGE_SIZE,
+        info_formats = info_formats,
+        layers = layers,
     );
 
     Ok((
@@ -285,57 +383,216 @@ async fn get_wms_capabilities(params: WmsParams) -> ServerResult<Response> {
     ).into_response())
 }
 
-/// Get WMS map image
-async fn get_wms_map(params: WmsParams) -> ServerResult<Response> {
-    // Validate required parameters
-    let _layers = params.layers
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: LAYERS".to_string()))?;
+/// Capabilities `<Layer>` element for a published layer
+fn wms_layer_element(layer: &meridian_db::Layer, extent: Option<&meridian_db::BBox>) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"      <Layer queryable="1" opaque="0">"#);
+    let _ = writeln!(xml, "        <Name>{}</Name>", escape(&layer.name));
+    let _ = writeln!(xml, "        <Title>{}</Title>", escape(&layer.name));
+    if let Some(description) = &layer.description {
+        let _ = writeln!(xml, "        <Abstract>{}</Abstract>", escape(description));
+    }
+
+    if let Some(extent) = extent {
+        let _ = writeln!(
+            xml,
+            "        <EX_GeographicBoundingBox><westBoundLongitude>{}</westBoundLongitude>\
+             <eastBoundLongitude>{}</eastBoundLongitude><southBoundLatitude>{}</southBoundLatitude>\
+             <northBoundLatitude>{}</northBoundLatitude></EX_GeographicBoundingBox>",
+            extent.min_x, extent.max_x, extent.min_y, extent.max_y
+        );
+        // EPSG:4326 bounding boxes are latitude first in WMS 1.3.0
+        let _ = writeln!(
+            xml,
+            r#"        <BoundingBox CRS="EPSG:4326" minx="{}" miny="{}" maxx="{}" maxy="{}"/>"#,
+            extent.min_y, extent.min_x, extent.max_y, extent.max_x
+        );
+    }
+
+    let _ = writeln!(
+        xml,
+        "        <Style><Name>default</Name><Title>Default</Title></Style>"
+    );
+    let _ = writeln!(xml, "      </Layer>");
+    xml
+}
 
-    let _bbox = params.bbox
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: BBOX".to_string()))?;
+/// Get WMS map image
+async fn get_wms_map(state: &AppState, params: WmsParams) -> ServerResult<Response> {
+    let request = MapRequest::from_params(&params)?;
+    let catalog = LayerCatalog::new(state.db.pool());
 
-    let _width = params.width
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: WIDTH".to_string()))?;
+    tracing::info!(
+        "Generating WMS map: layers={:?}, {}x{}",
+        request.layers,
+        request.width,
+        request.height
+    );
 
-    let _height = params.height
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: HEIGHT".to_string()))?;
+    let mut layers = Vec::with_capacity(request.layers.len());
+    for (name, style) in request.layers.iter().zip(&request.styles) {
+        let layer = catalog.layer(name).await?;
+        let style = catalog.style(&layer, style.as_deref()).await?;
+        layers.push(wms::load_map_layer(&catalog, &layer, style, &request.bbox).await?);
+    }
 
-    tracing::info!("Generating WMS map");
+    let mime_type = request.format.mime_type();
+    let image = tokio::task::spawn_blocking(move || wms::render_map(&request, &layers))
+        .await
+        .map_err(|e| ServerError::Internal(format!("Render task failed: {}", e)))??;
 
-    // TODO: Implement actual map rendering using meridian-render
-    Err(ServerError::OgcError("Map rendering not yet implemented".to_string()))
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, mime_type)],
+        image,
+    ).into_response())
 }
 
 /// Get WMS feature info
-async fn get_wms_feature_info(params: WmsParams) -> ServerResult<Response> {
-    tracing::info!("WMS GetFeatureInfo request");
+async fn get_wms_feature_info(state: &AppState, params: WmsParams) -> ServerResult<Response> {
+    let request = FeatureInfoRequest::from_params(&params)?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    tracing::info!(
+        "WMS GetFeatureInfo: layers={:?}, i={}, j={}",
+        request.query_layers,
+        request.i,
+        request.j
+    );
+
+    let search_bbox = request.search_bbox();
+    let mut hits = Vec::with_capacity(request.query_layers.len());
+    for name in &request.query_layers {
+        let layer = catalog.layer(name).await?;
+        let features = catalog
+            .features_in_bbox(&layer, &search_bbox, wms::MAX_FEATURES_PER_LAYER)
+            .await?;
+        hits.push(wms::select_hits(&request, name, features));
+    }
 
-    // TODO: Implement feature info query
-    Err(ServerError::OgcError("GetFeatureInfo not yet implemented".to_string()))
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, request.info_format.mime_type())],
+        wms::encode_feature_info(&request, &hits),
+    ).into_response())
 }
 
 /// Get WFS capabilities document
-async fn get_wfs_capabilities(params: WfsParams) -> ServerResult<Response> {
+async fn get_wfs_capabilities(state: &AppState, params: WfsParams) -> ServerResult<Response> {
     let version = params.version.as_deref().unwrap_or("2.0.0");
+    let url = escape(&ogc::service_url(state.config(), "wfs"));
 
     tracing::info!("Generating WFS {} GetCapabilities", version);
 
-    // TODO: Generate proper WFS capabilities XML
+    let catalog = LayerCatalog::new(state.db.pool());
+    let mut feature_types = String::new();
+    for layer in catalog.published_layers().await? {
+        let extent = catalog.extent(&layer, 4326).await?;
+        feature_types.push_str(&wfs_feature_type_element(&layer, extent.as_ref()));
+    }
+
+    let output_formats: String = wfs::OutputFormat::ALL
+        .iter()
+        .map(|f| format!("<ows:Value>{}</ows:Value>", escape(f.mime_type())))
+        .collect();
+
+    let operation = |name: &str| {
+        format!(
+            r#"    <ows:Operation name="{name}">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{url}"/></ows:HTTP></ows:DCP>
+    </ows:Operation>
+"#,
+            name = name,
+            url = url
+        )
+    };
+
+    let spatial_operators: String = [
+        "BBOX", "Intersects", "Disjoint", "Contains", "Within", "Touches", "Crosses", "Overlaps",
+        "Equals", "DWithin", "Beyond",
+    ]
+    .iter()
+    .map(|op| format!(r#"<fes:SpatialOperator name="{}"/>"#, op))
+    .collect();
+
+    let comparison_operators: String = [
+        "PropertyIsEqualTo", "PropertyIsNotEqualTo", "PropertyIsLessThan",
+        "PropertyIsGreaterThan", "PropertyIsLessThanOrEqualTo",
+        "PropertyIsGreaterThanOrEqualTo", "PropertyIsLike", "PropertyIsNull",
+        "PropertyIsNil", "PropertyIsBetween",
+    ]
+    .iter()
+    .map(|op| format!(r#"<fes:ComparisonOperator name="{}"/>"#, op))
+    .collect();
+
     let capabilities = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<wfs:WFS_Capabilities version="{version}" xmlns:wfs="http://www.opengis.net/wfs/2.0">
-  <ows:ServiceIdentification xmlns:ows="http://www.opengis.net/ows/1.1">
+<wfs:WFS_Capabilities version="{version}" xmlns:wfs="http://www.opengis.net/wfs/2.0" xmlns:ows="http://www.opengis.net/ows/1.1" xmlns:fes="http://www.opengis.net/fes/2.0" xmlns:gml="http://www.opengis.net/gml/3.2" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:{prefix}="{ns}">
+  <ows:ServiceIdentification>
     <ows:Title>Meridian GIS Web Feature Service</ows:Title>
     <ows:ServiceType>WFS</ows:ServiceType>
     <ows:ServiceTypeVersion>{version}</ows:ServiceTypeVersion>
   </ows:ServiceIdentification>
-  <ows:OperationsMetadata xmlns:ows="http://www.opengis.net/ows/1.1">
-    <ows:Operation name="GetCapabilities"/>
-    <ows:Operation name="GetFeature"/>
-    <ows:Operation name="DescribeFeatureType"/>
+  <ows:OperationsMetadata>
+{get_capabilities}{describe_feature_type}    <ows:Operation name="GetFeature">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{url}"/></ows:HTTP></ows:DCP>
+      <ows:Parameter name="outputFormat"><ows:AllowedValues>{output_formats}</ows:AllowedValues></ows:Parameter>
+    </ows:Operation>
+{get_feature_with_lock}    <ows:Operation name="LockFeature">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{url}"/><ows:Post xlink:href="{url}"/></ows:HTTP></ows:DCP>
+    </ows:Operation>
+    <ows:Operation name="Transaction">
+      <ows:DCP><ows:HTTP><ows:Post xlink:href="{url}"/></ows:HTTP></ows:DCP>
+      <ows:Parameter name="releaseAction"><ows:AllowedValues><ows:Value>ALL</ows:Value><ows:Value>SOME</ows:Value></ows:AllowedValues></ows:Parameter>
+    </ows:Operation>
+    <ows:Constraint name="ImplementsBasicWFS"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></ows:Constraint>
+    <ows:Constraint name="ImplementsTransactionalWFS"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></ows:Constraint>
+    <ows:Constraint name="ImplementsLockingWFS"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></ows:Constraint>
+    <ows:Constraint name="KVPEncoding"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></ows:Constraint>
+    <ows:Constraint name="ImplementsResultPaging"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></ows:Constraint>
+    <ows:Constraint name="CountDefault"><ows:NoValues/><ows:DefaultValue>{count_default}</ows:DefaultValue></ows:Constraint>
   </ows:OperationsMetadata>
+  <wfs:FeatureTypeList>
+{feature_types}  </wfs:FeatureTypeList>
+  <fes:Filter_Capabilities>
+    <fes:Conformance>
+      <fes:Constraint name="ImplementsQuery"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsAdHocQuery"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsResourceId"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsMinStandardFilter"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsStandardFilter"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsMinSpatialFilter"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsSpatialFilter"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+      <fes:Constraint name="ImplementsSorting"><ows:NoValues/><ows:DefaultValue>TRUE</ows:DefaultValue></fes:Constraint>
+    </fes:Conformance>
+    <fes:Id_Capabilities><fes:ResourceIdentifier name="fes:ResourceId"/></fes:Id_Capabilities>
+    <fes:Scalar_Capabilities>
+      <fes:LogicalOperators/>
+      <fes:ComparisonOperators>{comparison_operators}</fes:ComparisonOperators>
+    </fes:Scalar_Capabilities>
+    <fes:Spatial_Capabilities>
+      <fes:GeometryOperands>
+        <fes:GeometryOperand name="gml:Envelope"/><fes:GeometryOperand name="gml:Point"/>
+        <fes:GeometryOperand name="gml:LineString"/><fes:GeometryOperand name="gml:Polygon"/>
+        <fes:GeometryOperand name="gml:MultiPoint"/><fes:GeometryOperand name="gml:MultiCurve"/>
+        <fes:GeometryOperand name="gml:MultiSurface"/>
+      </fes:GeometryOperands>
+      <fes:SpatialOperators>{spatial_operators}</fes:SpatialOperators>
+    </fes:Spatial_Capabilities>
+  </fes:Filter_Capabilities>
 </wfs:WFS_Capabilities>"#,
-        version = version
+        version = version,
+        prefix = ogc::gml::FEATURE_PREFIX,
+        ns = ogc::gml::FEATURE_NS,
+        url = url,
+        get_capabilities = operation("GetCapabilities"),
+        describe_feature_type = operation("DescribeFeatureType"),
+        get_feature_with_lock = operation("GetFeatureWithLock"),
+        output_formats = output_formats,
+        count_default = wfs::MAX_FEATURES,
+        feature_types = feature_types,
+        comparison_operators = comparison_operators,
+        spatial_operators = spatial_operators,
     );
 
     Ok((
@@ -345,49 +602,181 @@ async fn get_wfs_capabilities(params: WfsParams) -> ServerResult<Response> {
     ).into_response())
 }
 
+/// Capabilities `<wfs:FeatureType>` element for a published layer
+fn wfs_feature_type_element(layer: &meridian_db::Layer, extent: Option<&meridian_db::BBox>) -> String {
+    let mut xml = String::new();
+    let _ = writeln!(xml, "    <wfs:FeatureType>");
+    let _ = writeln!(
+        xml,
+        "      <wfs:Name>{}:{}</wfs:Name>",
+        ogc::gml::FEATURE_PREFIX,
+        escape(&ogc::gml::xml_name(&layer.name))
+    );
+    let _ = writeln!(xml, "      <wfs:Title>{}</wfs:Title>", escape(&layer.name));
+    if let Some(description) = &layer.description {
+        let _ = writeln!(xml, "      <wfs:Abstract>{}</wfs:Abstract>", escape(description));
+    }
+    let _ = writeln!(xml, "      <wfs:DefaultCRS>urn:ogc:def:crs:EPSG::4326</wfs:DefaultCRS>");
+    let _ = writeln!(xml, "      <wfs:OtherCRS>urn:ogc:def:crs:EPSG::3857</wfs:OtherCRS>");
+    if layer.srid != 4326 && layer.srid != 3857 {
+        let _ = writeln!(xml, "      <wfs:OtherCRS>urn:ogc:def:crs:EPSG::{}</wfs:OtherCRS>", layer.srid);
+    }
+    if let Some(extent) = extent {
+        let _ = writeln!(
+            xml,
+            "      <ows:WGS84BoundingBox><ows:LowerCorner>{} {}</ows:LowerCorner>\
+             <ows:UpperCorner>{} {}</ows:UpperCorner></ows:WGS84BoundingBox>",
+            extent.min_x, extent.min_y, extent.max_x, extent.max_y
+        );
+    }
+    let _ = writeln!(xml, "    </wfs:FeatureType>");
+    xml
+}
+
 /// Get WFS features
-async fn get_wfs_feature(params: WfsParams) -> ServerResult<Response> {
-    let typename = params.typename
-        .or(params.typenames)
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: TYPENAME".to_string()))?;
+async fn get_wfs_feature(state: &AppState, params: WfsParams) -> ServerResult<Response> {
+    let request = GetFeatureRequest::from_params(&params)?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    tracing::info!("WFS GetFeature for types: {:?}", request.type_names);
 
-    tracing::info!("WFS GetFeature for type: {}", typename);
+    let results = wfs::get_features(&catalog, &request).await?;
+    let body = wfs::encode_features(&request, &results)?;
 
-    // TODO: Implement actual feature retrieval
-    Err(ServerError::OgcError("GetFeature not yet implemented".to_string()))
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, request.output_format.mime_type())],
+        body,
+    ).into_response())
+}
+
+/// Get WFS features and lock them
+async fn get_wfs_feature_with_lock(state: &AppState, params: WfsParams) -> ServerResult<Response> {
+    let request = GetFeatureRequest::from_params(&params)?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    tracing::info!("WFS GetFeatureWithLock for types: {:?}", request.type_names);
+
+    let expiry = wfst::lock_expiry(params.expiry)?;
+    let results = wfst::get_features_with_lock(&catalog, &request, expiry).await?;
+    let body = wfs::encode_features(&request, &results)?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, request.output_format.mime_type())],
+        body,
+    ).into_response())
+}
+
+/// Lock WFS features
+async fn lock_wfs_features(state: &AppState, request: LockRequest) -> ServerResult<Response> {
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    tracing::info!("WFS LockFeature for {} queries", request.queries.len());
+
+    let result = wfst::execute_lock(&catalog, &request).await?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/xml; charset=utf-8")],
+        wfst::encode_lock_response(&result),
+    ).into_response())
+}
+
+/// Apply a WFS transaction
+async fn wfs_transaction(state: &AppState, request: wfst::TransactionRequest) -> ServerResult<Response> {
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    tracing::info!("WFS Transaction with {} actions", request.actions.len());
+
+    let result = wfst::execute_transaction(&catalog, &request).await?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/xml; charset=utf-8")],
+        wfst::encode_transaction_response(&result),
+    ).into_response())
 }
 
 /// Describe WFS feature type
-async fn describe_wfs_feature_type(params: WfsParams) -> ServerResult<Response> {
+async fn describe_wfs_feature_type(state: &AppState, params: WfsParams) -> ServerResult<Response> {
+    let catalog = LayerCatalog::new(state.db.pool());
+
     tracing::info!("WFS DescribeFeatureType request");
 
-    // TODO: Implement feature type description
-    Err(ServerError::OgcError("DescribeFeatureType not yet implemented".to_string()))
+    let layers = match params.typenames.as_deref().or(params.typename.as_deref()) {
+        Some(names) if !names.is_empty() => {
+            let mut layers = Vec::new();
+            for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                layers.push(catalog.layer(name).await?);
+            }
+            layers
+        }
+        _ => catalog.published_layers().await?,
+    };
+
+    let mut types = Vec::with_capacity(layers.len());
+    for layer in layers {
+        let properties = catalog.properties(&layer).await?;
+        types.push((layer, properties));
+    }
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/xml; subtype=gml/3.2")],
+        wfs::describe_feature_types(&types),
+    ).into_response())
 }
 
 /// Get WMTS capabilities document
-async fn get_wmts_capabilities(params: WmtsParams) -> ServerResult<Response> {
+async fn get_wmts_capabilities(state: &AppState, params: WmtsParams) -> ServerResult<Response> {
     let version = params.version.as_deref().unwrap_or("1.0.0");
+    let url = escape(&ogc::service_url(state.config(), "wmts"));
 
     tracing::info!("Generating WMTS {} GetCapabilities", version);
 
-    // TODO: Generate proper WMTS capabilities XML
+    let catalog = LayerCatalog::new(state.db.pool());
+    let mut layers = Vec::new();
+    for layer in catalog.published_layers().await? {
+        let wgs84 = catalog.extent(&layer, 4326).await?;
+        let web_mercator = catalog.extent(&layer, 3857).await?;
+        layers.push(LayerExtents {
+            layer,
+            wgs84,
+            web_mercator,
+        });
+    }
+    let contents = wmts::encode_contents(&url, &layers);
+
     let capabilities = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<Capabilities xmlns="http://www.opengis.net/wmts/1.0" version="{version}">
-  <ows:ServiceIdentification xmlns:ows="http://www.opengis.net/ows/1.1">
+<Capabilities xmlns="http://www.opengis.net/wmts/1.0" xmlns:ows="http://www.opengis.net/ows/1.1" xmlns:xlink="http://www.w3.org/1999/xlink" version="{version}">
+  <ows:ServiceIdentification>
     <ows:Title>Meridian GIS Web Map Tile Service</ows:Title>
     <ows:ServiceType>OGC WMTS</ows:ServiceType>
     <ows:ServiceTypeVersion>{version}</ows:ServiceTypeVersion>
   </ows:ServiceIdentification>
-  <ows:OperationsMetadata xmlns:ows="http://www.opengis.net/ows/1.1">
-    <ows:Operation name="GetCapabilities"/>
-    <ows:Operation name="GetTile"/>
+  <ows:OperationsMetadata>
+    <ows:Operation name="GetCapabilities">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{url}?">
+        <ows:Constraint name="GetEncoding"><ows:AllowedValues><ows:Value>KVP</ows:Value></ows:AllowedValues></ows:Constraint>
+      </ows:Get></ows:HTTP></ows:DCP>
+    </ows:Operation>
+    <ows:Operation name="GetTile">
+      <ows:DCP><ows:HTTP>
+        <ows:Get xlink:href="{url}?">
+          <ows:Constraint name="GetEncoding"><ows:AllowedValues><ows:Value>KVP</ows:Value></ows:AllowedValues></ows:Constraint>
+        </ows:Get>
+        <ows:Get xlink:href="{url}/">
+          <ows:Constraint name="GetEncoding"><ows:AllowedValues><ows:Value>RESTful</ows:Value></ows:AllowedValues></ows:Constraint>
+        </ows:Get>
+      </ows:HTTP></ows:DCP>
+    </ows:Operation>
   </ows:OperationsMetadata>
-  <Contents>
-  </Contents>
-</Capabilities>"#,
-        version = version
+{contents}</Capabilities>"#,
+        version = version,
+        url = url,
+        contents = contents
     );
 
     Ok((
@@ -397,18 +786,35 @@ async fn get_wmts_capabilities(params: WmtsParams) -> ServerResult<Response> {
     ).into_response())
 }
 
-/// Get WMTS tile
-async fn get_wmts_tile(params: WmtsParams) -> ServerResult<Response> {
-    let _layer = params.layer
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: LAYER".to_string()))?;
+/// Get a WMTS tile from the tile cache, rendering it on a miss
+async fn get_wmts_tile(state: &AppState, request: TileRequest) -> ServerResult<Response> {
+    let catalog = LayerCatalog::new(state.db.pool());
+    let layer = catalog.layer(&request.layer).await?;
+    let style = catalog.style(&layer, request.style.as_deref()).await?;
+    let mime_type = request.format.mime_type();
+
+    if let Some(tile) = state.tiles.get(&request).await {
+        return Ok((StatusCode::OK, [(header::CONTENT_TYPE, mime_type)], tile).into_response());
+    }
+
+    tracing::info!(
+        "Rendering WMTS tile {}/{}/{}/{} for {}",
+        request.matrix_set.identifier(),
+        request.zoom,
+        request.row,
+        request.col,
+        layer.name
+    );
 
-    let _tilematrix = params.tilematrix
-        .ok_or_else(|| ServerError::OgcError("Missing required parameter: TILEMATRIX".to_string()))?;
+    let map = request.map_request();
+    let layers = vec![wms::load_map_layer(&catalog, &layer, style, &map.bbox).await?];
+    let tile = tokio::task::spawn_blocking(move || wms::render_map(&map, &layers))
+        .await
+        .map_err(|e| ServerError::Internal(format!("Render task failed: {}", e)))??;
 
-    tracing::info!("WMTS GetTile request");
+    state.tiles.put(&request, tile.clone()).await;
 
-    // TODO: Implement actual tile retrieval
-    Err(ServerError::OgcError("GetTile not yet implemented".to_string()))
+    Ok((StatusCode::OK, [(header::CONTENT_TYPE, mime_type)], tile).into_response())
 }
 
 #[cfg(test)]
@@ -432,6 +838,14 @@ mod tests {
             format: None,
             transparent: None,
             bgcolor: None,
+            query_layers: None,
+            info_format: None,
+            feature_count: None,
+            i: None,
+            j: None,
+            x: None,
+            y: None,
+            exceptions: None,
         };
 
         assert_eq!(params.request, Some("GetCapabilities".to_string()));
@@ -0,0 +1,217 @@
+//! OGC API - Features endpoints
+//!
+//! Serves the published layers as feature collections under `/ogc/features`,
+//! alongside the WMS and WFS services in [`super::ogc`].
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::ServerResult,
+    ogc::{
+        self,
+        features_api::{self, ItemsRequest},
+        LayerCatalog,
+    },
+    state::AppState,
+};
+
+/// Build OGC API - Features routes
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(landing_page))
+        .route("/conformance", get(conformance))
+        .route("/collections", get(list_collections))
+        .route("/collections/:collection_id", get(get_collection))
+        .route("/collections/:collection_id/queryables", get(get_queryables))
+        .route("/collections/:collection_id/items", get(get_items))
+        .route("/collections/:collection_id/items/:feature_id", get(get_item))
+}
+
+/// Output format parameter accepted by every endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FormatParams {
+    pub f: Option<String>,
+}
+
+/// Items query parameters
+///
+/// Serialized again, with a new offset, for the paging links.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemsParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bbox: Option<String>,
+    #[serde(rename = "bbox-crs", skip_serializing_if = "Option::is_none")]
+    pub bbox_crs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datetime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(rename = "filter-lang", skip_serializing_if = "Option::is_none")]
+    pub filter_lang: Option<String>,
+    #[serde(rename = "filter-crs", skip_serializing_if = "Option::is_none")]
+    pub filter_crs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub f: Option<String>,
+}
+
+/// Single feature query parameters
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ItemParams {
+    pub crs: Option<String>,
+    pub f: Option<String>,
+}
+
+/// Landing page handler
+async fn landing_page(
+    State(state): State<AppState>,
+    Query(params): Query<FormatParams>,
+) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    Ok(json_response(features_api::landing_page(&base_url(&state))))
+}
+
+/// Conformance declaration handler
+async fn conformance(Query(params): Query<FormatParams>) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    Ok(json_response(features_api::conformance()))
+}
+
+/// Collections handler
+async fn list_collections(
+    State(state): State<AppState>,
+    Query(params): Query<FormatParams>,
+) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    let mut layers = Vec::new();
+    for layer in catalog.published_layers().await? {
+        let extent = catalog.extent(&layer, 4326).await?;
+        layers.push((layer, extent));
+    }
+
+    Ok(json_response(features_api::collections(&base_url(&state), &layers)))
+}
+
+/// Single collection handler
+async fn get_collection(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+    Query(params): Query<FormatParams>,
+) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    let layer = features_api::collection_layer(&catalog, &collection_id).await?;
+    let extent = catalog.extent(&layer, 4326).await?;
+
+    Ok(json_response(features_api::collection(&base_url(&state), &layer, extent.as_ref())))
+}
+
+/// Queryables handler
+async fn get_queryables(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+    Query(params): Query<FormatParams>,
+) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    let layer = features_api::collection_layer(&catalog, &collection_id).await?;
+    let properties = catalog.properties(&layer).await?;
+    let schema = features_api::queryables(&base_url(&state), &layer, &properties);
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/schema+json")],
+        schema.to_string(),
+    ).into_response())
+}
+
+/// Items handler
+async fn get_items(
+    State(state): State<AppState>,
+    Path(collection_id): Path<String>,
+    Query(params): Query<ItemsParams>,
+) -> ServerResult<Response> {
+    let catalog = LayerCatalog::new(state.db.pool());
+    let layer = features_api::collection_layer(&catalog, &collection_id).await?;
+    let request = ItemsRequest::from_params(&layer, &params)?;
+
+    tracing::info!("OGC API items for {}: {:?}", layer.name, request.filter);
+
+    let result = catalog.query(&layer, &request.query()).await?;
+    let document = features_api::items_document(&base_url(&state), &layer, &params, &request, &result)?;
+
+    Ok(geojson_response(document, &request.crs.uri()))
+}
+
+/// Single feature handler
+async fn get_item(
+    State(state): State<AppState>,
+    Path((collection_id, feature_id)): Path<(String, String)>,
+    Query(params): Query<ItemParams>,
+) -> ServerResult<Response> {
+    features_api::check_format(params.f.as_deref())?;
+    let catalog = LayerCatalog::new(state.db.pool());
+
+    let layer = features_api::collection_layer(&catalog, &collection_id).await?;
+    let crs = features_api::resolve_crs(&layer, params.crs.as_deref())?;
+    let feature = features_api::get_feature(&catalog, &layer, &feature_id, &crs).await?;
+    let document = features_api::feature_document(&base_url(&state), &layer, &feature, &crs);
+
+    Ok(geojson_response(document, &crs.uri()))
+}
+
+fn base_url(state: &AppState) -> String {
+    ogc::service_url(state.config(), "features")
+}
+
+fn json_response(body: Value) -> Response {
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        body.to_string(),
+    ).into_response()
+}
+
+/// GeoJSON response announcing the CRS of its coordinates
+fn geojson_response(body: Value, crs_uri: &str) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/geo+json".to_string()),
+            (header::HeaderName::from_static("content-crs"), format!("<{}>", crs_uri)),
+        ],
+        body.to_string(),
+    ).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_params() {
+        let params: ItemsParams =
+            serde_urlencoded::from_str("limit=5&bbox-crs=EPSG%3A3857&filter-lang=cql2-json").unwrap();
+        assert_eq!(params.limit, Some(5));
+        assert_eq!(params.bbox_crs.as_deref(), Some("EPSG:3857"));
+        assert_eq!(params.filter_lang.as_deref(), Some("cql2-json"));
+        assert_eq!(serde_urlencoded::to_string(&params).unwrap(), "limit=5&bbox-crs=EPSG%3A3857&filter-lang=cql2-json");
+    }
+}
@@ -3,7 +3,7 @@
 //! Manages shared state across request handlers including database
 //! connections, caches, and configuration.
 
-use crate::{config::ServerConfig, error::ServerResult, ServerError};
+use crate::{config::ServerConfig, error::ServerResult, ogc::wmts::TileCache, ServerError};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -16,12 +16,15 @@ pub struct AppState {
     /// Server configuration
     pub config: Arc<ServerConfig>,
 
-    /// Database connection pool (placeholder)
+    /// Database connection pool
     pub db: Arc<DatabasePool>,
 
     /// Cache manager (placeholder)
     pub cache: Arc<CacheManager>,
 
+    /// WMTS tile cache
+    pub tiles: Arc<TileCache>,
+
     /// Metrics collector
     pub metrics: Arc<RwLock<MetricsCollector>>,
 }
@@ -35,6 +38,9 @@ impl AppState {
         // Initialize cache
         let cache = CacheManager::new(&config.cache).await?;
 
+        // Initialize the WMTS tile cache
+        let tiles = TileCache::new(&config.tile_cache)?;
+
         // Initialize metrics
         let metrics = MetricsCollector::new();
 
@@ -42,6 +48,7 @@ impl AppState {
             config: Arc::new(config),
             db: Arc::new(db),
             cache: Arc::new(cache),
+            tiles: Arc::new(tiles),
             metrics: Arc::new(RwLock::new(metrics)),
         })
     }
@@ -66,57 +73,47 @@ impl AppState {
 
 /// Database connection pool
 ///
-/// Placeholder for the actual database pool implementation
-/// which would integrate with meridian-db
+/// Wraps the `meridian-db` pool. Connections are opened lazily, so the server
+/// starts even while the database is unreachable.
 pub struct DatabasePool {
-    // This would contain the actual connection pool
-    _phantom: std::marker::PhantomData<()>,
+    pool: meridian_db::Pool,
 }
 
 impl DatabasePool {
     /// Create a new database pool
     pub async fn new(config: &crate::config::DatabaseConfig) -> ServerResult<Self> {
-        // TODO: Initialize actual database pool using meridian-db
         tracing::info!("Initializing database pool with URL: {}",
             config.url.split('@').last().unwrap_or("unknown"));
 
-        // Placeholder validation
         if config.max_connections == 0 {
             return Err(ServerError::Configuration(
                 "Max connections must be greater than 0".to_string()
             ));
         }
 
-        Ok(Self {
-            _phantom: std::marker::PhantomData,
-        })
+        let pool = meridian_db::Pool::from_url_lazy(&config.url, config.max_connections)
+            .map_err(|e| ServerError::Configuration(e.to_string()))?;
+
+        Ok(Self { pool })
     }
 
-    /// Get a connection from the pool
-    pub async fn get_connection(&self) -> ServerResult<DatabaseConnection> {
-        // TODO: Get actual connection from pool
-        Ok(DatabaseConnection {
-            _phantom: std::marker::PhantomData,
-        })
+    /// Get the underlying `meridian-db` pool
+    pub fn pool(&self) -> &meridian_db::Pool {
+        &self.pool
     }
 
     /// Get pool statistics
     pub fn stats(&self) -> PoolStats {
+        let stats = self.pool.stats();
+        let total = stats.size as usize;
         PoolStats {
-            active_connections: 0,
-            idle_connections: 0,
-            total_connections: 0,
+            active_connections: total.saturating_sub(stats.idle),
+            idle_connections: stats.idle,
+            total_connections: total,
         }
     }
 }
 
-/// Database connection
-///
-/// Placeholder for actual database connection
-pub struct DatabaseConnection {
-    _phantom: std::marker::PhantomData<()>,
-}
-
 /// Pool statistics
 #[derive(Debug, Clone)]
 pub struct PoolStats {
@@ -144,6 +144,11 @@ impl Error {
         Error::Compression(msg.into())
     }
 
+    /// Create a new decompression error
+    pub fn decompression<S: Into<String>>(msg: S) -> Self {
+        Error::Decompression(msg.into())
+    }
+
     /// Create a new S3 error
     pub fn s3<S: Into<String>>(msg: S) -> Self {
         Error::S3(msg.into())
@@ -2,15 +2,19 @@
 
 use crate::error::{Error, Result};
 use crate::generation::SourceFeature;
-use crate::source::{TileSource, SourceMetadata};
+use crate::source::{FieldMetadata, FieldType, LayerMetadata, TileSource, SourceMetadata};
+use crate::storage::pmtiles::PMTilesReader;
 use crate::tile::bounds::MercatorBounds;
 use crate::tile::coordinate::TileCoordinate;
 use async_trait::async_trait;
-use std::path::{Path, PathBuf};
+use serde_json::Value;
+use std::path::Path;
 
 /// PMTiles source for reading existing PMTiles archives
+///
+/// Tiles are served straight from the archive with byte-range reads.
 pub struct PMTilesSource {
-    path: PathBuf,
+    reader: PMTilesReader,
 }
 
 impl PMTilesSource {
@@ -25,15 +29,71 @@ impl PMTilesSource {
             )));
         }
 
-        Ok(Self { path })
+        Ok(Self {
+            reader: PMTilesReader::open(&path).await?,
+        })
     }
 
     /// Get the file path
     pub fn path(&self) -> &Path {
-        &self.path
+        self.reader.path()
+    }
+
+    /// Get the archive reader
+    pub fn reader(&self) -> &PMTilesReader {
+        &self.reader
+    }
+
+    /// Get a pre-generated tile, still in the archive's tile compression
+    pub async fn get_tile(&self, tile: TileCoordinate) -> Result<Option<Vec<u8>>> {
+        self.reader.get_tile(tile).await
     }
 }
 
+/// Read the `vector_layers` entry of the archive metadata
+fn vector_layers(metadata: &Value) -> Vec<LayerMetadata> {
+    let Some(layers) = metadata.get("vector_layers").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    layers
+        .iter()
+        .filter_map(|layer| {
+            let fields = layer
+                .get("fields")
+                .and_then(Value::as_object)
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(name, field_type)| FieldMetadata {
+                            name: name.clone(),
+                            field_type: match field_type.as_str() {
+                                Some("String") => FieldType::String,
+                                Some("Number") => FieldType::Float,
+                                Some("Boolean") => FieldType::Boolean,
+                                _ => FieldType::Unknown,
+                            },
+                            description: None,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(LayerMetadata {
+                name: layer.get("id")?.as_str()?.to_string(),
+                description: layer.get("description").and_then(Value::as_str).map(str::to_string),
+                min_zoom: layer.get("minzoom").and_then(Value::as_u64).unwrap_or(0) as u8,
+                max_zoom: layer
+                    .get("maxzoom")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(crate::MAX_ZOOM_LEVEL as u64) as u8,
+                geometry_type: None,
+                fields,
+            })
+        })
+        .collect()
+}
+
 #[async_trait]
 impl TileSource for PMTilesSource {
     async fn get_features(
@@ -41,27 +101,44 @@ impl TileSource for PMTilesSource {
         tile: TileCoordinate,
         _bounds: &MercatorBounds,
     ) -> Result<Vec<SourceFeature>> {
-        // PMTiles sources return pre-generated tiles, not raw features
-        // This would need to decode the MVT tile and extract features
-        // For now, return empty - this is typically used for serving, not generation
+        // PMTiles sources return pre-generated tiles, not raw features;
+        // they are served through `get_tile` rather than used for generation
         Ok(Vec::new())
     }
 
+    fn max_zoom(&self) -> u8 {
+        self.reader.header().max_zoom
+    }
+
+    fn min_zoom(&self) -> u8 {
+        self.reader.header().min_zoom
+    }
+
     async fn layers(&self) -> Result<Vec<String>> {
-        // Would need to read PMTiles metadata
-        Ok(Vec::new())
+        let metadata = self.reader.metadata().await?;
+        Ok(vector_layers(&metadata).into_iter().map(|layer| layer.name).collect())
     }
 
     async fn metadata(&self) -> Result<SourceMetadata> {
-        // Would need to read PMTiles header and metadata
+        let header = self.reader.header();
+        let metadata = self.reader.metadata().await?;
+        let text = |key: &str| metadata.get(key).and_then(Value::as_str).map(str::to_string);
+
         Ok(SourceMetadata {
-            name: self
-                .path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("pmtiles")
-                .to_string(),
-            ..Default::default()
+            name: text("name").unwrap_or_else(|| {
+                self.path()
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("pmtiles")
+                    .to_string()
+            }),
+            description: text("description"),
+            attribution: text("attribution"),
+            bounds: Some(header.bounds()),
+            center: Some(header.center()),
+            min_zoom: header.min_zoom,
+            max_zoom: header.max_zoom,
+            layers: vector_layers(&metadata),
         })
     }
 }
@@ -71,7 +148,16 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_pmtiles_path() {
-        // This test would require a real PMTiles file
+    fn test_vector_layers() {
+        let metadata = serde_json::json!({
+            "vector_layers": [
+                { "id": "roads", "maxzoom": 14, "fields": { "name": "String", "lanes": "Number" } }
+            ]
+        });
+        let layers = vector_layers(&metadata);
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, "roads");
+        assert_eq!(layers[0].max_zoom, 14);
+        assert_eq!(layers[0].fields.len(), 2);
     }
 }
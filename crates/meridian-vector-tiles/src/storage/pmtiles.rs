@@ -1,58 +1,815 @@
 //! PMTiles storage backend
+//!
+//! Reads and writes PMTiles v3 archives. Tiles are addressed by Hilbert tile
+//! ID and located through varint-encoded root and leaf directories, so a
+//! reader only needs byte ranges of the archive file.
 
+use crate::encoding::compression::{compress_brotli, compress_gzip, decompress_brotli, decompress_gzip};
 use crate::error::{Error, Result};
 use crate::storage::{TileStorage, StorageMetadata};
 use crate::tile::coordinate::TileCoordinate;
 use async_trait::async_trait;
+use moka::future::Cache;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::RwLock;
+
+/// Archive magic number
+const MAGIC: &[u8; 7] = b"PMTiles";
+
+/// Supported specification version
+const VERSION: u8 = 3;
+
+/// Fixed header length in bytes
+pub const HEADER_SIZE: usize = 127;
+
+/// The header and root directory must fit in the first 16 KiB
+const ROOT_SIZE_LIMIT: usize = 16_384;
+
+/// Maximum directory nesting followed when looking up a tile
+const MAX_DIRECTORY_DEPTH: usize = 4;
+
+/// Highest zoom level whose tile IDs fit in 64 bits
+const MAX_TILE_ID_ZOOM: u8 = 31;
+
+/// Number of leaf directories kept in the reader cache
+const LEAF_CACHE_CAPACITY: u64 = 1024;
+
+/// Compression applied to directories, metadata or tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Unknown,
+    None,
+    Gzip,
+    Brotli,
+    Zstd,
+}
+
+impl Compression {
+    fn from_byte(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Compression::Unknown),
+            1 => Ok(Compression::None),
+            2 => Ok(Compression::Gzip),
+            3 => Ok(Compression::Brotli),
+            4 => Ok(Compression::Zstd),
+            other => Err(Error::pmtiles(format!("Invalid compression type: {}", other))),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::Unknown => 0,
+            Compression::None => 1,
+            Compression::Gzip => 2,
+            Compression::Brotli => 3,
+            Compression::Zstd => 4,
+        }
+    }
+
+    /// Compress data with this method
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => compress_gzip(data),
+            Compression::Brotli => compress_brotli(data),
+            other => Err(Error::pmtiles(format!("Unsupported compression: {:?}", other))),
+        }
+    }
+
+    /// Decompress data compressed with this method
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => decompress_gzip(data),
+            Compression::Brotli => decompress_brotli(data),
+            other => Err(Error::pmtiles(format!("Unsupported compression: {:?}", other))),
+        }
+    }
+}
+
+/// Tile payload type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileType {
+    Unknown,
+    Mvt,
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl TileType {
+    fn from_byte(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(TileType::Unknown),
+            1 => Ok(TileType::Mvt),
+            2 => Ok(TileType::Png),
+            3 => Ok(TileType::Jpeg),
+            4 => Ok(TileType::Webp),
+            5 => Ok(TileType::Avif),
+            other => Err(Error::pmtiles(format!("Invalid tile type: {}", other))),
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            TileType::Unknown => 0,
+            TileType::Mvt => 1,
+            TileType::Png => 2,
+            TileType::Jpeg => 3,
+            TileType::Webp => 4,
+            TileType::Avif => 5,
+        }
+    }
+}
+
+/// PMTiles v3 header
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub root_dir_offset: u64,
+    pub root_dir_length: u64,
+    pub metadata_offset: u64,
+    pub metadata_length: u64,
+    pub leaf_dirs_offset: u64,
+    pub leaf_dirs_length: u64,
+    pub tile_data_offset: u64,
+    pub tile_data_length: u64,
+    pub addressed_tiles_count: u64,
+    pub tile_entries_count: u64,
+    pub tile_contents_count: u64,
+    pub clustered: bool,
+    pub internal_compression: Compression,
+    pub tile_compression: Compression,
+    pub tile_type: TileType,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    pub min_lon_e7: i32,
+    pub min_lat_e7: i32,
+    pub max_lon_e7: i32,
+    pub max_lat_e7: i32,
+    pub center_zoom: u8,
+    pub center_lon_e7: i32,
+    pub center_lat_e7: i32,
+}
+
+impl Header {
+    /// Parse the header from the start of an archive
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::pmtiles("Archive is shorter than the PMTiles header"));
+        }
+        if &bytes[0..7] != MAGIC {
+            return Err(Error::pmtiles("Missing PMTiles magic number"));
+        }
+        if bytes[7] != VERSION {
+            return Err(Error::pmtiles(format!("Unsupported PMTiles version: {}", bytes[7])));
+        }
+
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let i32_at = |at: usize| i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+        Ok(Self {
+            root_dir_offset: u64_at(8),
+            root_dir_length: u64_at(16),
+            metadata_offset: u64_at(24),
+            metadata_length: u64_at(32),
+            leaf_dirs_offset: u64_at(40),
+            leaf_dirs_length: u64_at(48),
+            tile_data_offset: u64_at(56),
+            tile_data_length: u64_at(64),
+            addressed_tiles_count: u64_at(72),
+            tile_entries_count: u64_at(80),
+            tile_contents_count: u64_at(88),
+            clustered: bytes[96] == 1,
+            internal_compression: Compression::from_byte(bytes[97])?,
+            tile_compression: Compression::from_byte(bytes[98])?,
+            tile_type: TileType::from_byte(bytes[99])?,
+            min_zoom: bytes[100],
+            max_zoom: bytes[101],
+            min_lon_e7: i32_at(102),
+            min_lat_e7: i32_at(106),
+            max_lon_e7: i32_at(110),
+            max_lat_e7: i32_at(114),
+            center_zoom: bytes[118],
+            center_lon_e7: i32_at(119),
+            center_lat_e7: i32_at(123),
+        })
+    }
+
+    /// Serialize the header
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..7].copy_from_slice(MAGIC);
+        bytes[7] = VERSION;
+
+        let fields = [
+            self.root_dir_offset,
+            self.root_dir_length,
+            self.metadata_offset,
+            self.metadata_length,
+            self.leaf_dirs_offset,
+            self.leaf_dirs_length,
+            self.tile_data_offset,
+            self.tile_data_length,
+            self.addressed_tiles_count,
+            self.tile_entries_count,
+            self.tile_contents_count,
+        ];
+        for (i, value) in fields.iter().enumerate() {
+            bytes[8 + i * 8..16 + i * 8].copy_from_slice(&value.to_le_bytes());
+        }
+
+        bytes[96] = self.clustered as u8;
+        bytes[97] = self.internal_compression.to_byte();
+        bytes[98] = self.tile_compression.to_byte();
+        bytes[99] = self.tile_type.to_byte();
+        bytes[100] = self.min_zoom;
+        bytes[101] = self.max_zoom;
+        bytes[102..106].copy_from_slice(&self.min_lon_e7.to_le_bytes());
+        bytes[106..110].copy_from_slice(&self.min_lat_e7.to_le_bytes());
+        bytes[110..114].copy_from_slice(&self.max_lon_e7.to_le_bytes());
+        bytes[114..118].copy_from_slice(&self.max_lat_e7.to_le_bytes());
+        bytes[118] = self.center_zoom;
+        bytes[119..123].copy_from_slice(&self.center_lon_e7.to_le_bytes());
+        bytes[123..127].copy_from_slice(&self.center_lat_e7.to_le_bytes());
+        bytes
+    }
+
+    /// Bounds in lon/lat [west, south, east, north]
+    pub fn bounds(&self) -> [f64; 4] {
+        [
+            from_e7(self.min_lon_e7),
+            from_e7(self.min_lat_e7),
+            from_e7(self.max_lon_e7),
+            from_e7(self.max_lat_e7),
+        ]
+    }
+
+    /// Center point and zoom [lon, lat, zoom]
+    pub fn center(&self) -> [f64; 3] {
+        [
+            from_e7(self.center_lon_e7),
+            from_e7(self.center_lat_e7),
+            self.center_zoom as f64,
+        ]
+    }
+}
+
+fn to_e7(value: f64) -> i32 {
+    (value * 10_000_000.0).round() as i32
+}
+
+fn from_e7(value: i32) -> f64 {
+    value as f64 / 10_000_000.0
+}
+
+/// Convert a tile coordinate to its Hilbert tile ID
+///
+/// IDs count every tile of the lower zoom levels first, then follow the
+/// Hilbert curve across the zoom level.
+pub fn tile_id(tile: TileCoordinate) -> Result<u64> {
+    if tile.z > MAX_TILE_ID_ZOOM {
+        return Err(Error::InvalidZoom { zoom: tile.z, min: 0, max: MAX_TILE_ID_ZOOM });
+    }
+    let n = 1u64 << tile.z;
+    if tile.x as u64 >= n || tile.y as u64 >= n {
+        return Err(Error::InvalidCoordinate(format!("Tile {} is outside its zoom level", tile)));
+    }
+
+    let base = ((1u64 << (2 * tile.z as u64)) - 1) / 3;
+    let (mut x, mut y) = (tile.x as u64, tile.y as u64);
+    let mut d = 0u64;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u64;
+        let ry = (y & s > 0) as u64;
+        d += s * s * ((3 * rx) ^ ry);
+        rotate(n, &mut x, &mut y, rx, ry);
+        s /= 2;
+    }
+
+    Ok(base + d)
+}
+
+/// Convert a Hilbert tile ID back to its tile coordinate
+pub fn tile_coordinate(id: u64) -> Result<TileCoordinate> {
+    let mut base = 0u64;
+    for z in 0..=MAX_TILE_ID_ZOOM {
+        let count = 1u64 << (2 * z as u64);
+        if id - base < count {
+            let n = 1u64 << z;
+            let mut t = id - base;
+            let (mut x, mut y) = (0u64, 0u64);
+            let mut s = 1u64;
+            while s < n {
+                let rx = 1 & (t / 2);
+                let ry = 1 & (t ^ rx);
+                rotate(s, &mut x, &mut y, rx, ry);
+                x += s * rx;
+                y += s * ry;
+                t /= 4;
+                s *= 2;
+            }
+            return Ok(TileCoordinate::new(z, x as u32, y as u32));
+        }
+        base += count;
+    }
+
+    Err(Error::pmtiles(format!("Tile ID {} exceeds the maximum zoom", id)))
+}
+
+fn rotate(n: u64, x: &mut u64, y: &mut u64, rx: u64, ry: u64) {
+    if ry == 0 {
+        if rx == 1 {
+            *x = n - 1 - *x;
+            *y = n - 1 - *y;
+        }
+        std::mem::swap(x, y);
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data
+            .get(*pos)
+            .ok_or_else(|| Error::pmtiles("Truncated varint in directory"))?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::pmtiles("Varint is too long"))
+}
+
+/// Directory entry
+///
+/// A run length of zero marks a pointer to a leaf directory, whose offset is
+/// relative to the leaf directory section. Otherwise the entry covers
+/// `run_length` consecutive tile IDs sharing one tile, offset relative to the
+/// tile data section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub tile_id: u64,
+    pub offset: u64,
+    pub length: u32,
+    pub run_length: u32,
+}
+
+impl Entry {
+    fn is_leaf(&self) -> bool {
+        self.run_length == 0
+    }
+}
+
+/// Encode and compress a directory
+fn encode_directory(entries: &[Entry], compression: Compression) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(entries.len() * 4 + 4);
+    write_varint(&mut buf, entries.len() as u64);
+
+    let mut last_id = 0;
+    for entry in entries {
+        write_varint(&mut buf, entry.tile_id - last_id);
+        last_id = entry.tile_id;
+    }
+    for entry in entries {
+        write_varint(&mut buf, entry.run_length as u64);
+    }
+    for entry in entries {
+        write_varint(&mut buf, entry.length as u64);
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        // Zero means "directly after the previous entry"
+        if i > 0 && entry.offset == entries[i - 1].offset + entries[i - 1].length as u64 {
+            write_varint(&mut buf, 0);
+        } else {
+            write_varint(&mut buf, entry.offset + 1);
+        }
+    }
+
+    compression.compress(&buf)
+}
+
+/// Decompress and decode a directory
+fn decode_directory(data: &[u8], compression: Compression) -> Result<Vec<Entry>> {
+    let buf = compression.decompress(data)?;
+    let mut pos = 0;
+
+    let count = read_varint(&buf, &mut pos)? as usize;
+    if count > buf.len() {
+        return Err(Error::pmtiles("Directory entry count exceeds its size"));
+    }
+    let mut entries = vec![Entry { tile_id: 0, offset: 0, length: 0, run_length: 0 }; count];
+
+    let mut last_id = 0;
+    for entry in entries.iter_mut() {
+        last_id += read_varint(&buf, &mut pos)?;
+        entry.tile_id = last_id;
+    }
+    for entry in entries.iter_mut() {
+        entry.run_length = read_varint(&buf, &mut pos)? as u32;
+    }
+    for entry in entries.iter_mut() {
+        entry.length = read_varint(&buf, &mut pos)? as u32;
+    }
+    for i in 0..count {
+        let value = read_varint(&buf, &mut pos)?;
+        entries[i].offset = if value == 0 && i > 0 {
+            entries[i - 1].offset + entries[i - 1].length as u64
+        } else {
+            value.checked_sub(1).ok_or_else(|| Error::pmtiles("Invalid offset in first directory entry"))?
+        };
+    }
+
+    Ok(entries)
+}
+
+/// Find the entry covering a tile ID, or the leaf directory that may hold it
+fn find_entry(entries: &[Entry], id: u64) -> Option<Entry> {
+    let index = entries.partition_point(|entry| entry.tile_id <= id);
+    let entry = *entries.get(index.checked_sub(1)?)?;
+
+    if entry.is_leaf() || id - entry.tile_id < entry.run_length as u64 {
+        Some(entry)
+    } else {
+        None
+    }
+}
+
+/// Build the root directory and leaf directory section for a set of entries
+///
+/// Entries go into the root directory when it fits next to the header,
+/// otherwise they are split into leaves of growing size until the root of
+/// leaf pointers does.
+fn build_directories(entries: &[Entry], compression: Compression) -> Result<(Vec<u8>, Vec<u8>)> {
+    let root = encode_directory(entries, compression)?;
+    if root.len() + HEADER_SIZE <= ROOT_SIZE_LIMIT {
+        return Ok((root, Vec::new()));
+    }
+
+    let mut leaf_size = (entries.len() / 3500).max(4096);
+    loop {
+        let mut root_entries = Vec::new();
+        let mut leaves = Vec::new();
+        for chunk in entries.chunks(leaf_size) {
+            let leaf = encode_directory(chunk, compression)?;
+            root_entries.push(Entry {
+                tile_id: chunk[0].tile_id,
+                offset: leaves.len() as u64,
+                length: leaf.len() as u32,
+                run_length: 0,
+            });
+            leaves.extend(leaf);
+        }
+
+        let root = encode_directory(&root_entries, compression)?;
+        if root.len() + HEADER_SIZE <= ROOT_SIZE_LIMIT {
+            return Ok((root, leaves));
+        }
+        leaf_size *= 2;
+    }
+}
+
+/// Byte-range reader over a PMTiles archive
+///
+/// Only the header and root directory are held in memory; leaf directories
+/// are cached as they are visited and tiles are read on demand.
+pub struct PMTilesReader {
+    path: PathBuf,
+    file: tokio::sync::Mutex<File>,
+    header: Header,
+    root: Vec<Entry>,
+    leaves: Cache<u64, Arc<Vec<Entry>>>,
+}
+
+impl PMTilesReader {
+    /// Open an archive and read its header and root directory
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path).await?;
+
+        let mut bytes = vec![0u8; HEADER_SIZE];
+        file.read_exact(&mut bytes).await.map_err(|e| {
+            Error::pmtiles(format!("Failed to read header of {}: {}", path.display(), e))
+        })?;
+        let header = Header::from_bytes(&bytes)?;
+
+        let reader = Self {
+            path,
+            file: tokio::sync::Mutex::new(file),
+            root: Vec::new(),
+            leaves: Cache::new(LEAF_CACHE_CAPACITY),
+            header,
+        };
+        let root = reader
+            .read_range(reader.header.root_dir_offset, reader.header.root_dir_length)
+            .await?;
+        let root = decode_directory(&root, reader.header.internal_compression)?;
+
+        Ok(Self { root, ..reader })
+    }
+
+    /// Get the file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the archive header
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Read the metadata JSON
+    pub async fn metadata(&self) -> Result<serde_json::Value> {
+        if self.header.metadata_length == 0 {
+            return Ok(serde_json::Value::Object(Default::default()));
+        }
+        let data = self
+            .read_range(self.header.metadata_offset, self.header.metadata_length)
+            .await?;
+        let json = self.header.internal_compression.decompress(&data)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    /// Get a tile as stored, still in the archive's tile compression
+    pub async fn get_tile(&self, tile: TileCoordinate) -> Result<Option<Vec<u8>>> {
+        match self.find_tile(tile_id(tile)?).await? {
+            Some(entry) => {
+                let offset = self.header.tile_data_offset + entry.offset;
+                Ok(Some(self.read_range(offset, entry.length as u64).await?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Check whether the archive addresses a tile
+    pub async fn has_tile(&self, tile: TileCoordinate) -> Result<bool> {
+        Ok(self.find_tile(tile_id(tile)?).await?.is_some())
+    }
+
+    /// Find the tile entry covering a tile ID, descending into leaves
+    async fn find_tile(&self, id: u64) -> Result<Option<Entry>> {
+        let mut directory: Option<Arc<Vec<Entry>>> = None;
+        for _ in 0..MAX_DIRECTORY_DEPTH {
+            let entries = match &directory {
+                Some(leaf) => leaf.as_slice(),
+                None => self.root.as_slice(),
+            };
+            match find_entry(entries, id) {
+                Some(entry) if entry.is_leaf() => directory = Some(self.leaf(entry).await?),
+                found => return Ok(found),
+            }
+        }
+
+        Err(Error::pmtiles("Directory nesting exceeds the maximum depth"))
+    }
+
+    /// Every tile entry in the archive, in tile ID order
+    pub async fn entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        let mut stack = vec![(Arc::new(self.root.clone()), 0usize)];
+
+        while let Some((directory, index)) = stack.pop() {
+            let Some(entry) = directory.get(index).copied() else {
+                continue;
+            };
+            stack.push((directory, index + 1));
+
+            if entry.is_leaf() {
+                if stack.len() >= MAX_DIRECTORY_DEPTH {
+                    return Err(Error::pmtiles("Directory nesting exceeds the maximum depth"));
+                }
+                stack.push((self.leaf(entry).await?, 0));
+            } else {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Read the tile data section
+    async fn tile_data(&self) -> Result<Vec<u8>> {
+        self.read_range(self.header.tile_data_offset, self.header.tile_data_length)
+            .await
+    }
+
+    async fn leaf(&self, entry: Entry) -> Result<Arc<Vec<Entry>>> {
+        let offset = self.header.leaf_dirs_offset + entry.offset;
+        if let Some(leaf) = self.leaves.get(&offset).await {
+            return Ok(leaf);
+        }
+
+        let data = self.read_range(offset, entry.length as u64).await?;
+        let leaf = Arc::new(decode_directory(&data, self.header.internal_compression)?);
+        self.leaves.insert(offset, leaf.clone()).await;
+        Ok(leaf)
+    }
+
+    async fn read_range(&self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; length as usize];
+        let mut file = self.file.lock().await;
+        file.seek(SeekFrom::Start(offset)).await?;
+        file.read_exact(&mut buf).await.map_err(|e| {
+            Error::pmtiles(format!(
+                "Failed to read {} bytes at {} from {}: {}",
+                length,
+                offset,
+                self.path.display(),
+                e
+            ))
+        })?;
+        Ok(buf)
+    }
+}
 
 /// PMTiles storage implementation
 ///
 /// PMTiles is a single-file archive format for pyramids of tiled data.
 /// https://github.com/protomaps/PMTiles
+///
+/// Archives are immutable, so written and deleted tiles are buffered and
+/// merged into a rewritten archive on [`TileStorage::flush`].
 pub struct PMTilesStorage {
     path: PathBuf,
+    reader: RwLock<Option<Arc<PMTilesReader>>>,
+    pending: Mutex<BTreeMap<u64, Option<Vec<u8>>>>,
 }
 
 impl PMTilesStorage {
-    /// Create a new PMTiles storage
+    /// Open a PMTiles archive, or prepare a new one if the file does not exist
     pub async fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
+        let reader = if path.exists() {
+            Some(Arc::new(PMTilesReader::open(&path).await?))
+        } else {
+            None
+        };
 
-        Ok(Self { path })
+        Ok(Self {
+            path,
+            reader: RwLock::new(reader),
+            pending: Mutex::new(BTreeMap::new()),
+        })
     }
 
     /// Get the file path
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    /// Get the reader for the archive on disk, if it exists
+    pub async fn reader(&self) -> Option<Arc<PMTilesReader>> {
+        self.reader.read().await.clone()
+    }
+
+    fn pending_tile(&self, id: u64) -> Option<Option<Vec<u8>>> {
+        self.pending.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Rewrite the archive with the buffered changes applied
+    async fn rewrite(&self, pending: &BTreeMap<u64, Option<Vec<u8>>>) -> Result<()> {
+        let mut current = self.reader.write().await;
+        let mut writer = PMTilesWriter::new(&self.path);
+        let mut tiles: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+
+        if let Some(reader) = current.as_ref() {
+            let header = reader.header();
+            writer = writer
+                .with_tile_type(header.tile_type)
+                .with_tile_compression(header.tile_compression)
+                .with_metadata(reader.metadata().await?);
+
+            let data = reader.tile_data().await?;
+            for entry in reader.entries().await? {
+                let start = entry.offset as usize;
+                let tile = data
+                    .get(start..start + entry.length as usize)
+                    .ok_or_else(|| Error::pmtiles("Tile entry outside the tile data section"))?;
+                for id in entry.tile_id..entry.tile_id + entry.run_length as u64 {
+                    tiles.insert(id, tile.to_vec());
+                }
+            }
+        }
+
+        for (id, data) in pending {
+            match data {
+                Some(data) => tiles.insert(*id, data.clone()),
+                None => tiles.remove(id),
+            };
+        }
+        for (id, data) in tiles {
+            writer.add_tile(tile_coordinate(id)?, data);
+        }
+
+        writer.write().await?;
+        *current = Some(Arc::new(PMTilesReader::open(&self.path).await?));
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl TileStorage for PMTilesStorage {
     async fn put_tile(&self, tile: TileCoordinate, data: Vec<u8>) -> Result<()> {
-        // PMTiles writing requires buffering and building the complete archive
-        // This is a placeholder for a full implementation
-        Err(Error::storage(
-            "PMTiles writing not yet implemented - use PMTilesWriter",
-        ))
+        let id = tile_id(tile)?;
+        self.pending.lock().unwrap().insert(id, Some(data));
+        Ok(())
     }
 
     async fn get_tile(&self, tile: TileCoordinate) -> Result<Option<Vec<u8>>> {
-        // PMTiles reading would use the pmtiles crate
-        // This is a placeholder for a full implementation
-        Err(Error::storage("PMTiles reading not yet implemented"))
+        if let Some(pending) = self.pending_tile(tile_id(tile)?) {
+            return Ok(pending);
+        }
+        match self.reader().await {
+            Some(reader) => reader.get_tile(tile).await,
+            None => Ok(None),
+        }
     }
 
     async fn has_tile(&self, tile: TileCoordinate) -> Result<bool> {
-        // Check tile existence in PMTiles archive
-        Ok(false)
+        if let Some(pending) = self.pending_tile(tile_id(tile)?) {
+            return Ok(pending.is_some());
+        }
+        match self.reader().await {
+            Some(reader) => reader.has_tile(tile).await,
+            None => Ok(false),
+        }
     }
 
     async fn delete_tile(&self, tile: TileCoordinate) -> Result<()> {
-        Err(Error::storage(
-            "PMTiles does not support individual tile deletion",
-        ))
+        let id = tile_id(tile)?;
+        self.pending.lock().unwrap().insert(id, None);
+        Ok(())
+    }
+
+    async fn list_tiles(&self) -> Result<Vec<TileCoordinate>> {
+        let mut ids = BTreeMap::new();
+        if let Some(reader) = self.reader().await {
+            for entry in reader.entries().await? {
+                for id in entry.tile_id..entry.tile_id + entry.run_length as u64 {
+                    ids.insert(id, true);
+                }
+            }
+        }
+        for (id, data) in self.pending.lock().unwrap().iter() {
+            ids.insert(*id, data.is_some());
+        }
+
+        ids.into_iter()
+            .filter(|(_, present)| *present)
+            .map(|(id, _)| tile_coordinate(id))
+            .collect()
+    }
+
+    async fn metadata(&self) -> Result<StorageMetadata> {
+        let Some(reader) = self.reader().await else {
+            return Ok(StorageMetadata::default());
+        };
+        let header = reader.header();
+
+        Ok(StorageMetadata {
+            tile_count: Some(header.addressed_tiles_count),
+            total_size: Some(tokio::fs::metadata(&self.path).await?.len()),
+            min_zoom: Some(header.min_zoom),
+            max_zoom: Some(header.max_zoom),
+            bounds: Some(header.bounds()),
+        })
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let result = self.rewrite(&pending).await;
+        if result.is_err() {
+            // Keep the changes for the next flush, behind any newer writes
+            let mut current = self.pending.lock().unwrap();
+            for (id, data) in pending {
+                current.entry(id).or_insert(data);
+            }
+        }
+        result
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.flush().await
     }
 }
 
@@ -60,6 +817,12 @@ impl TileStorage for PMTilesStorage {
 pub struct PMTilesWriter {
     path: PathBuf,
     tiles: Vec<(TileCoordinate, Vec<u8>)>,
+    tile_type: TileType,
+    tile_compression: Compression,
+    internal_compression: Compression,
+    metadata: serde_json::Value,
+    bounds: Option<[f64; 4]>,
+    center: Option<[f64; 3]>,
 }
 
 impl PMTilesWriter {
@@ -68,20 +831,174 @@ impl PMTilesWriter {
         Self {
             path: path.as_ref().to_path_buf(),
             tiles: Vec::new(),
+            tile_type: TileType::Mvt,
+            tile_compression: Compression::Gzip,
+            internal_compression: Compression::Gzip,
+            metadata: serde_json::Value::Object(Default::default()),
+            bounds: None,
+            center: None,
         }
     }
 
+    /// Set the tile payload type
+    pub fn with_tile_type(mut self, tile_type: TileType) -> Self {
+        self.tile_type = tile_type;
+        self
+    }
+
+    /// Declare the compression the added tiles are already encoded with
+    pub fn with_tile_compression(mut self, compression: Compression) -> Self {
+        self.tile_compression = compression;
+        self
+    }
+
+    /// Set the compression used for directories and metadata
+    pub fn with_internal_compression(mut self, compression: Compression) -> Self {
+        self.internal_compression = compression;
+        self
+    }
+
+    /// Set the metadata JSON (TileJSON-like fields and `vector_layers`)
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set the bounds [west, south, east, north] instead of deriving them from the tiles
+    pub fn with_bounds(mut self, bounds: [f64; 4]) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Set the center [lon, lat, zoom] instead of deriving it from the bounds
+    pub fn with_center(mut self, center: [f64; 3]) -> Self {
+        self.center = Some(center);
+        self
+    }
+
     /// Add a tile to the archive
+    ///
+    /// A tile added twice keeps the last data.
     pub fn add_tile(&mut self, tile: TileCoordinate, data: Vec<u8>) {
         self.tiles.push((tile, data));
     }
 
     /// Write the PMTiles archive
+    ///
+    /// The archive is written to a temporary file and renamed into place.
     pub async fn write(self) -> Result<()> {
-        // Sort tiles by z/x/y for optimal encoding
-        // Build directory and write to file
-        // This is a placeholder for full implementation
-        Err(Error::storage("PMTiles writing not yet implemented"))
+        let path = self.path.clone();
+        let bytes = self.build()?;
+
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        tokio::fs::write(&temp, &bytes).await?;
+        tokio::fs::rename(&temp, &path).await?;
+        Ok(())
+    }
+
+    /// Build the archive in memory
+    ///
+    /// Layout: header, root directory, metadata, leaf directories, tile data.
+    pub fn build(self) -> Result<Vec<u8>> {
+        let mut tiles = Vec::with_capacity(self.tiles.len());
+        for (index, (tile, data)) in self.tiles.into_iter().enumerate() {
+            tiles.push((tile_id(tile)?, index, tile, data));
+        }
+        // Sort by tile ID, keeping the last insert of any duplicate
+        tiles.sort_by_key(|(id, index, _, _)| (*id, std::cmp::Reverse(*index)));
+        tiles.dedup_by_key(|(id, _, _, _)| *id);
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut tile_data = Vec::new();
+        let mut contents: HashMap<[u8; 32], (u64, u32)> = HashMap::new();
+        let mut zooms = (u8::MAX, 0u8);
+        let mut extent = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+
+        for (id, _, tile, data) in &tiles {
+            let digest: [u8; 32] = Sha256::digest(data).into();
+            let (offset, length) = *contents.entry(digest).or_insert_with(|| {
+                let offset = tile_data.len() as u64;
+                tile_data.extend_from_slice(data);
+                (offset, data.len() as u32)
+            });
+
+            // Identical tiles at consecutive IDs collapse into one run
+            match entries.last_mut() {
+                Some(last)
+                    if last.offset == offset
+                        && last.length == length
+                        && last.tile_id + last.run_length as u64 == *id =>
+                {
+                    last.run_length += 1;
+                }
+                _ => entries.push(Entry { tile_id: *id, offset, length, run_length: 1 }),
+            }
+
+            zooms = (zooms.0.min(tile.z), zooms.1.max(tile.z));
+            let bounds = tile.bounds();
+            extent = [
+                extent[0].min(bounds.west),
+                extent[1].min(bounds.south),
+                extent[2].max(bounds.east),
+                extent[3].max(bounds.north),
+            ];
+        }
+        if tiles.is_empty() {
+            zooms = (0, 0);
+            extent = [-180.0, -85.051_128_779_806_59, 180.0, 85.051_128_779_806_59];
+        }
+
+        let bounds = self.bounds.unwrap_or(extent);
+        let center = self.center.unwrap_or([
+            (bounds[0] + bounds[2]) / 2.0,
+            (bounds[1] + bounds[3]) / 2.0,
+            zooms.0 as f64,
+        ]);
+
+        let compression = self.internal_compression;
+        let (root, leaves) = build_directories(&entries, compression)?;
+        let metadata = compression.compress(&serde_json::to_vec(&self.metadata)?)?;
+
+        let root_dir_offset = HEADER_SIZE as u64;
+        let metadata_offset = root_dir_offset + root.len() as u64;
+        let leaf_dirs_offset = metadata_offset + metadata.len() as u64;
+        let tile_data_offset = leaf_dirs_offset + leaves.len() as u64;
+
+        let header = Header {
+            root_dir_offset,
+            root_dir_length: root.len() as u64,
+            metadata_offset,
+            metadata_length: metadata.len() as u64,
+            leaf_dirs_offset,
+            leaf_dirs_length: leaves.len() as u64,
+            tile_data_offset,
+            tile_data_length: tile_data.len() as u64,
+            addressed_tiles_count: tiles.len() as u64,
+            tile_entries_count: entries.len() as u64,
+            tile_contents_count: contents.len() as u64,
+            clustered: true,
+            internal_compression: compression,
+            tile_compression: self.tile_compression,
+            tile_type: self.tile_type,
+            min_zoom: zooms.0,
+            max_zoom: zooms.1,
+            min_lon_e7: to_e7(bounds[0]),
+            min_lat_e7: to_e7(bounds[1]),
+            max_lon_e7: to_e7(bounds[2]),
+            max_lat_e7: to_e7(bounds[3]),
+            center_zoom: center[2].round() as u8,
+            center_lon_e7: to_e7(center[0]),
+            center_lat_e7: to_e7(center[1]),
+        };
+
+        let mut bytes = Vec::with_capacity(tile_data_offset as usize + tile_data.len());
+        bytes.extend_from_slice(&header.to_bytes());
+        bytes.extend(root);
+        bytes.extend(metadata);
+        bytes.extend(leaves);
+        bytes.extend(tile_data);
+        Ok(bytes)
     }
 }
 
@@ -94,4 +1011,105 @@ mod tests {
         let writer = PMTilesWriter::new("test.pmtiles");
         assert_eq!(writer.tiles.len(), 0);
     }
+
+    #[test]
+    fn test_tile_id() {
+        assert_eq!(tile_id(TileCoordinate::new(0, 0, 0)).unwrap(), 0);
+        assert_eq!(tile_id(TileCoordinate::new(1, 0, 0)).unwrap(), 1);
+        assert_eq!(tile_id(TileCoordinate::new(1, 0, 1)).unwrap(), 2);
+        assert_eq!(tile_id(TileCoordinate::new(1, 1, 1)).unwrap(), 3);
+        assert_eq!(tile_id(TileCoordinate::new(1, 1, 0)).unwrap(), 4);
+        assert_eq!(tile_id(TileCoordinate::new(2, 0, 0)).unwrap(), 5);
+        assert!(tile_id(TileCoordinate::new(1, 2, 0)).is_err());
+
+        for tile in [
+            TileCoordinate::new(3, 5, 2),
+            TileCoordinate::new(12, 3012, 1500),
+            TileCoordinate::new(24, 12_345_678, 8_765_432),
+        ] {
+            assert_eq!(tile_coordinate(tile_id(tile).unwrap()).unwrap(), tile);
+        }
+    }
+
+    #[test]
+    fn test_directory_round_trip() {
+        let entries = vec![
+            Entry { tile_id: 0, offset: 0, length: 10, run_length: 1 },
+            Entry { tile_id: 1, offset: 10, length: 20, run_length: 3 },
+            Entry { tile_id: 9, offset: 0, length: 10, run_length: 1 },
+        ];
+        let encoded = encode_directory(&entries, Compression::Gzip).unwrap();
+        assert_eq!(decode_directory(&encoded, Compression::Gzip).unwrap(), entries);
+
+        assert_eq!(find_entry(&entries, 3), Some(entries[1]));
+        assert_eq!(find_entry(&entries, 5), None);
+        assert_eq!(find_entry(&entries, 9), Some(entries[2]));
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let bytes = PMTilesWriter::new("test.pmtiles").build().unwrap();
+        let header = Header::from_bytes(&bytes).unwrap();
+        assert_eq!(Header::from_bytes(&header.to_bytes()).unwrap(), header);
+        assert_eq!(header.root_dir_offset, HEADER_SIZE as u64);
+        assert_eq!(header.tile_type, TileType::Mvt);
+    }
+
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.pmtiles");
+
+        // Enough tiles to need leaf directories, with a run of identical ocean tiles
+        let mut writer = PMTilesWriter::new(&path)
+            .with_tile_compression(Compression::None)
+            .with_internal_compression(Compression::None)
+            .with_metadata(serde_json::json!({ "name": "test" }));
+        for x in 0..256 {
+            for y in 0..256 {
+                let data = if y < 128 { vec![x as u8, y as u8] } else { b"ocean".to_vec() };
+                writer.add_tile(TileCoordinate::new(8, x, y), data);
+            }
+        }
+        writer.write().await.unwrap();
+
+        let reader = PMTilesReader::open(&path).await.unwrap();
+        let header = reader.header();
+        assert!(header.leaf_dirs_length > 0);
+        assert_eq!(header.addressed_tiles_count, 65_536);
+        assert_eq!(header.tile_contents_count, 256 * 128 + 1);
+        assert!(header.tile_entries_count < 65_536);
+        assert_eq!(reader.metadata().await.unwrap()["name"], "test");
+
+        let tile = reader.get_tile(TileCoordinate::new(8, 17, 42)).await.unwrap();
+        assert_eq!(tile, Some(vec![17, 42]));
+        let tile = reader.get_tile(TileCoordinate::new(8, 200, 250)).await.unwrap();
+        assert_eq!(tile, Some(b"ocean".to_vec()));
+        assert!(!reader.has_tile(TileCoordinate::new(7, 0, 0)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_storage_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiles.pmtiles");
+
+        let storage = PMTilesStorage::new(&path).await.unwrap();
+        storage.put_tile(TileCoordinate::new(0, 0, 0), vec![1]).await.unwrap();
+        storage.put_tile(TileCoordinate::new(1, 1, 0), vec![2]).await.unwrap();
+        assert!(storage.has_tile(TileCoordinate::new(1, 1, 0)).await.unwrap());
+        storage.flush().await.unwrap();
+
+        storage.delete_tile(TileCoordinate::new(0, 0, 0)).await.unwrap();
+        storage.put_tile(TileCoordinate::new(1, 0, 1), vec![3]).await.unwrap();
+        storage.flush().await.unwrap();
+
+        let storage = PMTilesStorage::new(&path).await.unwrap();
+        assert_eq!(storage.get_tile(TileCoordinate::new(0, 0, 0)).await.unwrap(), None);
+        assert_eq!(storage.get_tile(TileCoordinate::new(1, 1, 0)).await.unwrap(), Some(vec![2]));
+        assert_eq!(
+            storage.list_tiles().await.unwrap(),
+            vec![TileCoordinate::new(1, 0, 1), TileCoordinate::new(1, 1, 0)]
+        );
+        assert_eq!(storage.metadata().await.unwrap().tile_count, Some(2));
+    }
 }